@@ -0,0 +1,64 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use argp::FromArgs;
+use objdiff_core::{diff::DiffPolicyPreset, history::History};
+use tracing::info;
+
+use crate::cmd::report::generate_report;
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Record function match percentages in the project history and list regressions
+/// since the previous run.
+#[argp(subcommand, name = "changes")]
+pub struct Args {
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+    #[argp(switch)]
+    /// List every changed function, not only regressions
+    all: bool,
+    #[argp(switch)]
+    /// Compare the last two recorded runs without diffing the project
+    no_record: bool,
+    #[argp(switch)]
+    /// Don't read or write the diff cache in .objdiff/cache
+    no_cache: bool,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let project_dir = args.project.as_deref().unwrap_or_else(|| Path::new("."));
+    let history = History::new(project_dir);
+    if !args.no_record {
+        let report =
+            generate_report(project_dir, false, DiffPolicyPreset::Default, !args.no_cache)?;
+        history.record(&report)?;
+    }
+    let changes = if args.all { history.changes()? } else { history.regressions()? };
+    if changes.is_empty() {
+        info!("No {} since the previous run", if args.all { "changes" } else { "regressions" });
+        return Ok(());
+    }
+
+    let name_width = changes.iter().map(|c| c.function.len()).max().unwrap_or(0).max(8);
+    let mut out = std::io::stdout().lock();
+    let mut unit = None;
+    for change in &changes {
+        if unit != Some(&change.unit) {
+            writeln!(out, "{}", change.unit)?;
+            unit = Some(&change.unit);
+        }
+        let format = |p: Option<f32>| p.map_or_else(|| "-".to_string(), |p| format!("{p:.2}%"));
+        writeln!(
+            out,
+            "  {:<name_width$}  {:>8} -> {:>8}",
+            change.function,
+            format(change.from),
+            format(change.to)
+        )?;
+    }
+    Ok(())
+}
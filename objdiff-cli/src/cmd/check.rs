@@ -0,0 +1,136 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use argp::FromArgs;
+use objdiff_core::{diff::DiffPolicyPreset, report::Report};
+use tracing::info;
+
+use crate::cmd::report::{generate_report, parse_policy, read_report};
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Compare the project against a baseline report and fail if matching regressed.
+#[argp(subcommand, name = "check")]
+pub struct Args {
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+    #[argp(option, short = 'b')]
+    /// Baseline report file (JSON or binary, default: objdiff-baseline.json)
+    baseline: Option<PathBuf>,
+    #[argp(switch)]
+    /// Overwrite the baseline with the current report
+    update_baseline: bool,
+    #[argp(option, from_str_fn(parse_policy))]
+    /// Match scoring policy (default: the baseline's policy)
+    policy: Option<DiffPolicyPreset>,
+    #[argp(switch)]
+    /// Don't read or write the diff cache in .objdiff/cache
+    no_cache: bool,
+}
+
+/// Percentages within this distance are considered unchanged, so float noise between runs
+/// doesn't fail the check.
+const EPSILON: f32 = 0.001;
+
+pub fn run(args: Args) -> Result<()> {
+    let project_dir = args.project.as_deref().unwrap_or_else(|| Path::new("."));
+    let baseline_path =
+        args.baseline.clone().unwrap_or_else(|| project_dir.join("objdiff-baseline.json"));
+    let baseline = if baseline_path.is_file() { Some(read_report(&baseline_path)?) } else { None };
+    let policy = args
+        .policy
+        .or_else(|| baseline.as_ref().and_then(|b| b.policy))
+        .unwrap_or(DiffPolicyPreset::Default);
+    let report = generate_report(project_dir, false, policy, !args.no_cache)?;
+
+    if args.update_baseline {
+        write_baseline(&baseline_path, &report)?;
+        info!("Updated baseline {}", baseline_path.display());
+        return Ok(());
+    }
+    let Some(baseline) = baseline else {
+        bail!(
+            "Baseline {} not found; run with --update-baseline to create it",
+            baseline_path.display()
+        );
+    };
+
+    let regressions = find_regressions(&baseline, &report);
+    let mut out = std::io::stdout().lock();
+    writeln!(
+        out,
+        "Total: {:.2}% -> {:.2}%",
+        baseline.fuzzy_match_percent, report.fuzzy_match_percent
+    )?;
+    let total_dropped = report.fuzzy_match_percent + EPSILON < baseline.fuzzy_match_percent;
+    for regression in &regressions {
+        match regression.current {
+            Some(percent) => writeln!(
+                out,
+                "{}: {} regressed 100.00% -> {percent:.2}%",
+                regression.unit, regression.function
+            )?,
+            None => {
+                writeln!(out, "{}: {} is no longer reported", regression.unit, regression.function)?
+            }
+        }
+    }
+    if total_dropped || !regressions.is_empty() {
+        bail!(
+            "Match regressed ({} function(s) no longer fully matched); \
+             run with --update-baseline to accept",
+            regressions.len()
+        );
+    }
+    writeln!(out, "No regressions")?;
+    Ok(())
+}
+
+struct Regression<'a> {
+    unit: &'a str,
+    function: &'a str,
+    /// Current match percentage, or `None` if the function is missing from the report.
+    current: Option<f32>,
+}
+
+/// Functions that were fully matched in `baseline` but aren't in `current`.
+fn find_regressions<'a>(baseline: &'a Report, current: &Report) -> Vec<Regression<'a>> {
+    let current_units: HashMap<&str, HashMap<&str, f32>> = current
+        .units
+        .iter()
+        .map(|unit| {
+            let functions =
+                unit.functions.iter().map(|f| (f.name.as_str(), f.fuzzy_match_percent)).collect();
+            (unit.name.as_str(), functions)
+        })
+        .collect();
+    let mut regressions = vec![];
+    for unit in &baseline.units {
+        let functions = current_units.get(unit.name.as_str());
+        for function in &unit.functions {
+            if function.fuzzy_match_percent < 100.0 {
+                continue;
+            }
+            let current = functions.and_then(|f| f.get(function.name.as_str()).copied());
+            if current.is_some_and(|p| p >= 100.0) {
+                continue;
+            }
+            regressions.push(Regression { unit: &unit.name, function: &function.name, current });
+        }
+    }
+    regressions
+}
+
+fn write_baseline(path: &Path, report: &Report) -> Result<()> {
+    let mut w = BufWriter::new(
+        File::create(path).with_context(|| format!("Failed to create file {}", path.display()))?,
+    );
+    report.write_json(&mut w)?;
+    w.flush()?;
+    Ok(())
+}
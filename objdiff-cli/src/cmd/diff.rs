@@ -1,4 +1,15 @@
-use std::{fs, io::stdout, path::PathBuf, str::FromStr};
+use std::{
+    fs,
+    fs::File,
+    io::{stdout, BufWriter, IsTerminal, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use anyhow::{bail, Context, Result};
 use argp::FromArgs;
@@ -14,15 +25,30 @@ use crossterm::{
 };
 use event::KeyModifiers;
 use objdiff_core::{
-    config::{ProjectConfig, ProjectObject},
+    bindings::diff::DiffResult,
+    build::{
+        BuildCommand, BuildConfig, BuildErrorSeverity, BuildRunner, BuildStatus, CommandRunner,
+    },
+    config::{build_globset, expand_archive_objects, ProjectConfig, ProjectObject},
     diff,
     diff::{
-        display::{display_diff, DiffText, HighlightKind},
-        DiffObjsResult, ObjDiff, ObjInsDiffKind, ObjSymbolDiff,
+        display::{display_diff, function_pairs, DiffText, HighlightKind, SymbolDiffRef},
+        html, text,
+        text::TextDiffFormat,
+        theme::{byte_kind, encoding_byte_kind, token_kind, Theme},
+        DiffObjsResult, NumberFormat, NumberRadix, ObjDiff, ObjInsDiffKind, ObjInsPrevState,
+        ObjSymbolDiff,
     },
     obj,
-    obj::{ObjInfo, ObjSectionKind, ObjSymbol, SymbolRef},
+    obj::{
+        binary::{FileRange, SymbolMapConfig},
+        demangle::demangle_symbols,
+        AcceptedMismatch, ObjInfo, ObjSectionKind, ObjSymbol, SizeInference, SymbolMappings,
+        SymbolRef,
+    },
+    workflow::{workflow_path, WorkflowState},
 };
+use prost::Message;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
@@ -49,105 +75,277 @@ pub struct Args {
     #[argp(switch, short = 'x')]
     /// Relax relocation diffs
     relax_reloc_diffs: bool,
+    #[argp(switch, short = 'm')]
+    /// Detect moved instruction blocks
+    detect_moved_blocks: bool,
+    #[argp(switch, short = 'a')]
+    /// Compare address literals by section and offset (for linked binaries)
+    symbolic_addresses: bool,
+    #[argp(switch)]
+    /// Exclude alignment padding at the end of functions from the match percentage
+    ignore_padding: bool,
+    #[argp(switch)]
+    /// Resolve relocation addends to the symbol containing the target address
+    normalize_relocs: bool,
+    #[argp(switch, short = 'e')]
+    /// Show the encoding bytes of each instruction
+    show_encoding: bool,
+    #[argp(switch)]
+    /// Count instructions whose encodings differ as mismatched, even if they decode the same
+    strict_bytes: bool,
+    #[argp(switch, short = 'w')]
+    /// Watch project files, rebuilding and reloading on change
+    watch: bool,
+    #[argp(switch)]
+    /// Display immediates in decimal
+    decimal: bool,
+    #[argp(switch)]
+    /// Display negative immediates as unsigned two's complement
+    unsigned: bool,
+    #[argp(option, short = 'f')]
+    /// Output format (tui, html, text, unified, proto; default: tui, or text with -o or if
+    /// stdout isn't a terminal)
+    format: Option<DiffFormat>,
+    #[argp(option, short = 'o')]
+    /// Output file for non-interactive formats (defaults to stdout)
+    output: Option<PathBuf>,
+    #[argp(switch)]
+    /// Export every function in the unit instead of only the given symbol
+    all: bool,
+    #[argp(option)]
+    /// Color theme file (JSON) for the TUI and HTML output
+    theme: Option<PathBuf>,
     #[argp(positional)]
-    /// Function symbol to diff
-    symbol: String,
+    /// [<target> <base>] [symbol]: Object files to diff without a project, and the function
+    /// symbol to diff (opens the symbol list if omitted)
+    positional: Vec<String>,
+}
+
+impl Args {
+    /// Arguments to open the diff of a project unit with default options.
+    pub fn for_unit(project: PathBuf, unit: String, symbol: Option<String>) -> Self {
+        Self {
+            target: None,
+            base: None,
+            project: Some(project),
+            unit: Some(unit),
+            relax_reloc_diffs: false,
+            detect_moved_blocks: false,
+            symbolic_addresses: false,
+            ignore_padding: false,
+            normalize_relocs: false,
+            show_encoding: false,
+            strict_bytes: false,
+            watch: false,
+            decimal: false,
+            unsigned: false,
+            format: None,
+            output: None,
+            all: false,
+            theme: None,
+            positional: symbol.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum DiffFormat {
+    Tui,
+    Html,
+    Text,
+    Unified,
+    Proto,
+}
+
+impl FromStr for DiffFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tui" => Ok(Self::Tui),
+            "html" => Ok(Self::Html),
+            "text" => Ok(Self::Text),
+            "unified" => Ok(Self::Unified),
+            "proto" => Ok(Self::Proto),
+            _ => Err(format!("Invalid diff format: {s}")),
+        }
+    }
+}
+
+/// The objects to diff and the unit settings that apply to them, from the arguments or the
+/// project configuration.
+#[derive(Default)]
+struct ResolvedUnit {
+    target_path: Option<PathBuf>,
+    base_path: Option<PathBuf>,
+    target_map: Option<SymbolMapConfig>,
+    target_range: Option<FileRange>,
+    symbol_mappings: SymbolMappings,
+    accepted_mismatches: Vec<AcceptedMismatch>,
+    size_inference: SizeInference,
+    build_command: Option<BuildCommand>,
+    unit_name: Option<String>,
+    project_config: Option<ProjectConfig>,
+    project_dir: Option<PathBuf>,
 }
 
 pub fn run(args: Args) -> Result<()> {
-    let (target_path, base_path, project_config) =
-        match (&args.target, &args.base, &args.project, &args.unit) {
-            (Some(t), Some(b), None, None) => (Some(t.clone()), Some(b.clone()), None),
-            (None, None, p, u) => {
-                let project = match p {
-                    Some(project) => project.clone(),
-                    _ => std::env::current_dir().context("Failed to get the current directory")?,
-                };
-                let Some((project_config, project_config_info)) =
-                    objdiff_core::config::try_project_config(&project)
-                else {
-                    bail!("Project config not found in {}", &project.display())
+    let (objects, symbol) = match args.positional.as_slice() {
+        [target, base, rest @ ..] if rest.len() <= 1 => {
+            (Some((PathBuf::from(target), PathBuf::from(base))), rest.first().cloned())
+        }
+        [symbol] => (None, Some(symbol.clone())),
+        [] => (None, None),
+        _ => bail!("Expected at most two object files and a symbol"),
+    };
+    let (target_arg, base_arg) = match objects {
+        Some(_) if args.target.is_some() || args.base.is_some() => {
+            bail!("Object files can't be given both as arguments and with -1/-2")
+        }
+        Some((target, base)) => (Some(target), Some(base)),
+        None => (args.target.clone(), args.base.clone()),
+    };
+    let unit = match (&target_arg, &base_arg, &args.project, &args.unit) {
+        (Some(t), Some(b), None, None) => ResolvedUnit {
+            target_path: Some(t.clone()),
+            base_path: Some(b.clone()),
+            ..Default::default()
+        },
+        (None, None, p, u) => {
+            let project = match p {
+                Some(project) => project.clone(),
+                _ => std::env::current_dir().context("Failed to get the current directory")?,
+            };
+            let Some((project_config, project_config_info)) =
+                objdiff_core::config::try_project_config(&project)
+            else {
+                bail!("Project config not found in {}", &project.display())
+            };
+            let mut project_config = project_config.with_context(|| {
+                format!("Reading project config {}", project_config_info.path.display())
+            })?;
+            project_config.objects = expand_archive_objects(
+                std::mem::take(&mut project_config.objects),
+                &project,
+                project_config.target_dir.as_deref(),
+                project_config.base_dir.as_deref(),
+            );
+            let object = {
+                let resolve_paths = |o: &mut ProjectObject| {
+                    o.resolve_paths(
+                        &project,
+                        project_config.target_dir.as_deref(),
+                        project_config.base_dir.as_deref(),
+                    )
                 };
-                let mut project_config = project_config.with_context(|| {
-                    format!("Reading project config {}", project_config_info.path.display())
-                })?;
-                let object = {
-                    let resolve_paths = |o: &mut ProjectObject| {
-                        o.resolve_paths(
-                            &project,
-                            project_config.target_dir.as_deref(),
-                            project_config.base_dir.as_deref(),
-                        )
-                    };
-                    if let Some(u) = u {
-                        let unit_path =
-                            PathBuf::from_str(u).ok().and_then(|p| fs::canonicalize(p).ok());
-
-                        let Some(object) = project_config.objects.iter_mut().find_map(|obj| {
-                            if obj.name.as_deref() == Some(u) {
-                                resolve_paths(obj);
-                                return Some(obj);
-                            }
-
-                            let up = unit_path.as_deref()?;
+                if let Some(u) = u {
+                    let unit_path =
+                        PathBuf::from_str(u).ok().and_then(|p| fs::canonicalize(p).ok());
 
+                    let Some(object) = project_config.objects.iter_mut().find_map(|obj| {
+                        if obj.name.as_deref() == Some(u) {
                             resolve_paths(obj);
+                            return Some(obj);
+                        }
 
-                            if [&obj.base_path, &obj.target_path]
-                                .into_iter()
-                                .filter_map(|p| p.as_ref().and_then(|p| p.canonicalize().ok()))
-                                .any(|p| p == up)
-                            {
-                                return Some(obj);
-                            }
+                        let up = unit_path.as_deref()?;
 
-                            None
-                        }) else {
-                            bail!("Unit not found: {}", u)
-                        };
+                        resolve_paths(obj);
 
-                        object
-                    } else {
-                        let mut idx = None;
-                        let mut count = 0usize;
-                        for (i, obj) in project_config.objects.iter_mut().enumerate() {
-                            resolve_paths(obj);
+                        if [&obj.base_path, &obj.target_path]
+                            .into_iter()
+                            .filter_map(|p| p.as_ref().and_then(|p| p.canonicalize().ok()))
+                            .any(|p| p == up)
+                        {
+                            return Some(obj);
+                        }
 
-                            if obj
-                                .target_path
-                                .as_deref()
-                                .map(|o| obj::read::has_function(o, &args.symbol))
-                                .transpose()?
-                                .unwrap_or(false)
-                            {
-                                idx = Some(i);
-                                count += 1;
-                                if count > 1 {
-                                    break;
-                                }
+                        None
+                    }) else {
+                        bail!("Unit not found: {}", u)
+                    };
+
+                    object
+                } else {
+                    let Some(symbol) = &symbol else {
+                        bail!("Either a unit or a symbol must be specified");
+                    };
+                    let mut idx = None;
+                    let mut count = 0usize;
+                    for (i, obj) in project_config.objects.iter_mut().enumerate() {
+                        resolve_paths(obj);
+
+                        if obj
+                            .target_path
+                            .as_deref()
+                            .map(|o| obj::read::has_function(o, symbol))
+                            .transpose()?
+                            .unwrap_or(false)
+                        {
+                            idx = Some(i);
+                            count += 1;
+                            if count > 1 {
+                                break;
                             }
                         }
-                        match (count, idx) {
-                            (0, None) => bail!("Symbol not found: {}", &args.symbol),
-                            (1, Some(i)) => &mut project_config.objects[i],
-                            (2.., Some(_)) => bail!(
-                                "Multiple instances of {} were found, try specifying a unit",
-                                &args.symbol
-                            ),
-                            _ => unreachable!(),
+                    }
+                    match (count, idx) {
+                        (0, None) => bail!("Symbol not found: {symbol}"),
+                        (1, Some(i)) => &mut project_config.objects[i],
+                        (2.., Some(_)) => {
+                            bail!(
+                                "Multiple instances of {symbol} were found, try specifying a unit"
+                            )
                         }
+                        _ => unreachable!(),
                     }
-                };
-                let target_path = object.target_path.clone();
-                let base_path = object.base_path.clone();
-                (target_path, base_path, Some(project_config))
+                }
+            };
+            ResolvedUnit {
+                target_path: object.target_path.clone(),
+                base_path: object.base_path.clone(),
+                target_map: object.target_map.clone(),
+                target_range: object.target_range.clone(),
+                symbol_mappings: object.symbol_mappings.clone().unwrap_or_default(),
+                accepted_mismatches: object.accepted_mismatches.clone().unwrap_or_default(),
+                size_inference: object.size_inference.unwrap_or_default(),
+                build_command: object.build.clone(),
+                unit_name: Some(object.name().to_string()),
+                project_config: Some(project_config),
+                project_dir: Some(project),
             }
-            _ => bail!("Either target and base or project and unit must be specified"),
-        };
+        }
+        _ => bail!("Either target and base objects or a project unit must be specified"),
+    };
+    let mut number_format =
+        unit.project_config.as_ref().and_then(|c| c.number_format).unwrap_or_default();
+    if args.decimal {
+        number_format.radix = NumberRadix::Decimal;
+    }
+    if args.unsigned {
+        number_format.unsigned = true;
+    }
+    let theme = match &args.theme {
+        Some(path) => {
+            let file = File::open(path)
+                .with_context(|| format!("Failed to open theme {}", path.display()))?;
+            serde_json::from_reader(std::io::BufReader::new(file))
+                .with_context(|| format!("Failed to parse theme {}", path.display()))?
+        }
+        None => Theme::default(),
+    };
     let time_format = time::format_description::parse_borrowed::<2>("[hour]:[minute]:[second]")
         .context("Failed to parse time format")?;
     let mut state = Box::new(FunctionDiffUi {
         relax_reloc_diffs: args.relax_reloc_diffs,
+        detect_moved_blocks: args.detect_moved_blocks,
+        symbolic_addresses: args.symbolic_addresses,
+        ignore_padding: args.ignore_padding,
+        normalize_reloc_addends: args.normalize_relocs,
+        show_encoding: args.show_encoding,
+        strict_bytes: args.strict_bytes,
+        number_format,
+        theme,
         left_highlight: HighlightKind::None,
         right_highlight: HighlightKind::None,
         scroll_x: 0,
@@ -156,10 +354,18 @@ pub fn run(args: Args) -> Result<()> {
         scroll_state_y: ScrollbarState::default(),
         per_page: 0,
         num_rows: 0,
-        symbol_name: args.symbol.clone(),
-        target_path,
-        base_path,
-        project_config,
+        symbol_name: symbol.clone().unwrap_or_default(),
+        target_path: unit.target_path,
+        base_path: unit.base_path,
+        target_map: unit.target_map,
+        target_range: unit.target_range,
+        symbol_mappings: unit.symbol_mappings,
+        accepted_mismatches: unit.accepted_mismatches,
+        size_inference: unit.size_inference,
+        build_command: unit.build_command,
+        unit_name: unit.unit_name,
+        project_config: unit.project_config,
+        project_dir: unit.project_dir,
         left_obj: None,
         right_obj: None,
         prev_obj: None,
@@ -171,8 +377,43 @@ pub fn run(args: Args) -> Result<()> {
         time_format,
         open_options: false,
         three_way: false,
+        build_error: None,
+        symbol_list: symbol.is_none().then(SymbolListState::default),
+    });
+    // Print the diff when the output is redirected or written to a file, every function's if
+    // no symbol is given
+    let format = args.format.unwrap_or(if args.output.is_none() && stdout().is_terminal() {
+        DiffFormat::Tui
+    } else {
+        DiffFormat::Text
     });
+    let all = args.all || (args.format.is_none() && symbol.is_none());
+    if symbol.is_none() && format != DiffFormat::Tui && !all {
+        bail!("A symbol is required for {format:?} output without --all");
+    }
     state.reload()?;
+    if format != DiffFormat::Tui {
+        return state.export(format, args.output.as_deref(), all);
+    }
+
+    let modified = Arc::new(AtomicBool::new(false));
+    let _watcher = if args.watch {
+        let (Some(project_config), Some(project_dir)) = (&state.project_config, &state.project_dir)
+        else {
+            bail!("Watch mode requires a project configuration");
+        };
+        let patterns = match &project_config.watch_patterns {
+            Some(patterns) => patterns.clone(),
+            None => objdiff_core::config::DEFAULT_WATCH_PATTERNS
+                .iter()
+                .map(|s| globset::Glob::new(s).unwrap())
+                .collect(),
+        };
+        let globset = build_globset(&patterns).context("Failed to build watch patterns")?;
+        Some(create_watcher(modified.clone(), project_dir, globset)?)
+    } else {
+        None
+    };
 
     crossterm_panic_handler();
     enable_raw_mode()?;
@@ -180,7 +421,10 @@ pub fn run(args: Args) -> Result<()> {
         stdout(),
         EnterAlternateScreen,
         EnableMouseCapture,
-        SetTitle(format!("{} - objdiff", args.symbol)),
+        SetTitle(match &symbol {
+            Some(symbol) => format!("{symbol} - objdiff"),
+            None => "objdiff".to_string(),
+        }),
     )?;
     let backend = CrosstermBackend::new(stdout());
     let mut terminal = Terminal::new(backend)?;
@@ -203,13 +447,27 @@ pub fn run(args: Args) -> Result<()> {
                     f.buffer_mut().reset();
                 })?;
             }
+            if !event::poll(Duration::from_millis(100))? {
+                if modified.swap(false, Ordering::Relaxed) {
+                    state.rebuild();
+                    break;
+                }
+                result = EventResult::default();
+                continue;
+            }
             match state.handle_event(event::read()?) {
                 EventControlFlow::Break => break 'outer,
                 EventControlFlow::Continue(r) => result = r,
                 EventControlFlow::Reload => break,
             }
         }
-        state.reload()?;
+        if let Err(e) = state.reload() {
+            if !args.watch {
+                return Err(e);
+            }
+            // Keep the previous diff and display the error until the next change
+            state.build_error = Some(format!("{e:#}"));
+        }
     }
 
     // Reset terminal
@@ -229,6 +487,46 @@ fn get_symbol_diff(obj: Option<&ObjDiff>, sym: Option<SymbolRef>) -> Option<&Obj
     Some(obj?.symbol_diff(sym?))
 }
 
+fn symbol_diff_ref<'a>(
+    obj: Option<&'a ObjInfo>,
+    diff: Option<&'a ObjDiff>,
+    sym: Option<SymbolRef>,
+) -> Option<SymbolDiffRef<'a>> {
+    Some((obj?, get_symbol(obj, sym)?, get_symbol_diff(diff, sym)?))
+}
+
+pub(crate) fn create_watcher(
+    modified: Arc<AtomicBool>,
+    project_dir: &Path,
+    patterns: globset::GlobSet,
+) -> Result<notify::RecommendedWatcher> {
+    use notify::Watcher;
+    let base_dir = project_dir.to_owned();
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => {
+                if matches!(
+                    event.kind,
+                    notify::EventKind::Modify(..)
+                        | notify::EventKind::Create(..)
+                        | notify::EventKind::Remove(..)
+                ) {
+                    for path in &event.paths {
+                        let Ok(path) = path.strip_prefix(&base_dir) else {
+                            continue;
+                        };
+                        if patterns.is_match(path) {
+                            modified.store(true, Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
+            Err(e) => tracing::error!("watch error: {e:?}"),
+        })?;
+    watcher.watch(project_dir, notify::RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
 fn find_function(obj: &ObjInfo, name: &str) -> Option<SymbolRef> {
     for (section_idx, section) in obj.sections.iter().enumerate() {
         if section.kind != ObjSectionKind::Code {
@@ -246,6 +544,14 @@ fn find_function(obj: &ObjInfo, name: &str) -> Option<SymbolRef> {
 #[allow(dead_code)]
 struct FunctionDiffUi {
     relax_reloc_diffs: bool,
+    detect_moved_blocks: bool,
+    symbolic_addresses: bool,
+    ignore_padding: bool,
+    normalize_reloc_addends: bool,
+    show_encoding: bool,
+    strict_bytes: bool,
+    number_format: NumberFormat,
+    theme: Theme,
     left_highlight: HighlightKind,
     right_highlight: HighlightKind,
     scroll_x: usize,
@@ -257,7 +563,16 @@ struct FunctionDiffUi {
     symbol_name: String,
     target_path: Option<PathBuf>,
     base_path: Option<PathBuf>,
+    target_map: Option<SymbolMapConfig>,
+    target_range: Option<FileRange>,
+    symbol_mappings: SymbolMappings,
+    accepted_mismatches: Vec<AcceptedMismatch>,
+    size_inference: SizeInference,
+    build_command: Option<BuildCommand>,
+    /// Unit within the project, for its workflow annotations
+    unit_name: Option<String>,
     project_config: Option<ProjectConfig>,
+    project_dir: Option<PathBuf>,
     left_obj: Option<ObjInfo>,
     right_obj: Option<ObjInfo>,
     prev_obj: Option<ObjInfo>,
@@ -269,6 +584,15 @@ struct FunctionDiffUi {
     time_format: Vec<time::format_description::FormatItem<'static>>,
     open_options: bool,
     three_way: bool,
+    build_error: Option<String>,
+    /// Symbol list pane, if open
+    symbol_list: Option<SymbolListState>,
+}
+
+#[derive(Default)]
+struct SymbolListState {
+    selected: usize,
+    scroll: usize,
 }
 
 #[derive(Default)]
@@ -285,7 +609,14 @@ enum EventControlFlow {
 
 impl FunctionDiffUi {
     fn draw(&mut self, f: &mut Frame, result: &mut EventResult) {
-        let chunks = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).split(f.size());
+        let mut area = f.size();
+        if self.symbol_list.is_some() {
+            let chunks =
+                Layout::horizontal([Constraint::Length(40), Constraint::Fill(1)]).split(area);
+            self.draw_symbol_list(f, chunks[0]);
+            area = chunks[1];
+        }
+        let chunks = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).split(area);
         let header_chunks = Layout::horizontal([
             Constraint::Fill(1),
             Constraint::Length(3),
@@ -336,6 +667,29 @@ impl FunctionDiffUi {
                 Style::new().fg(match_percent_color(percent)),
             ));
         }
+        if let Some(symbol_diff) = get_symbol_diff(self.diff_result.left.as_ref(), self.left_sym) {
+            let count =
+                |state| symbol_diff.instructions.iter().filter(|i| i.prev_state == state).count();
+            let regressed = count(ObjInsPrevState::Regressed);
+            let newly_matched = count(ObjInsPrevState::NewlyMatched);
+            if regressed > 0 {
+                line_r
+                    .spans
+                    .push(Span::styled(format!("-{regressed} "), Style::new().fg(Color::LightRed)));
+            }
+            if newly_matched > 0 {
+                line_r.spans.push(Span::styled(
+                    format!("+{newly_matched} "),
+                    Style::new().fg(Color::LightGreen),
+                ));
+            }
+        }
+        if let Some(error) = &self.build_error {
+            line_r.spans.push(Span::styled(
+                format!("{} ", error.lines().next().unwrap_or("Build failed")),
+                Style::new().fg(Color::Red),
+            ));
+        }
         let reload_time = self
             .reload_time
             .as_ref()
@@ -521,6 +875,122 @@ impl FunctionDiffUi {
         }
     }
 
+    /// Functions of the unit with their match percentages, named by the base symbol if
+    /// there is one.
+    fn function_list(&self) -> Vec<(String, Option<f32>)> {
+        let left = self.left_obj.as_ref().zip(self.diff_result.left.as_ref());
+        let right = self.right_obj.as_ref().zip(self.diff_result.right.as_ref());
+        function_pairs(left, right)
+            .into_iter()
+            .filter_map(|(l, r)| {
+                let (_, symbol, diff) = r.or(l)?;
+                Some((symbol.name.clone(), diff.match_percent))
+            })
+            .collect()
+    }
+
+    fn draw_symbol_list(&mut self, f: &mut Frame, rect: Rect) {
+        let functions = self.function_list();
+        let Some(list) = &mut self.symbol_list else {
+            return;
+        };
+        let height = rect.height.saturating_sub(1) as usize;
+        list.selected = list.selected.min(functions.len().saturating_sub(1));
+        if list.selected < list.scroll {
+            list.scroll = list.selected;
+        } else if list.selected >= list.scroll + height {
+            list.scroll = list.selected + 1 - height;
+        }
+        let mut text = Text::default();
+        for (i, (name, percent)) in functions.iter().enumerate().skip(list.scroll).take(height) {
+            let mut line = Line::default();
+            match percent {
+                Some(percent) => line.spans.push(Span::styled(
+                    format!("{percent:>3.0}% "),
+                    Style::new().fg(match_percent_color(*percent)),
+                )),
+                None => line.spans.push(Span::raw("     ")),
+            }
+            let style = if i == list.selected {
+                Style::new().reversed()
+            } else if *name == self.symbol_name {
+                Style::new().fg(Color::White).bold()
+            } else {
+                Style::new()
+            };
+            line.spans.push(Span::styled(name.clone(), style));
+            text.lines.push(line);
+        }
+        f.render_widget(
+            Paragraph::new(text).block(
+                Block::new().borders(Borders::TOP | Borders::RIGHT).gray().title("SYMBOLS".bold()),
+            ),
+            rect,
+        );
+    }
+
+    /// Whether the row differs on either side.
+    fn row_mismatched(&self, row: usize) -> bool {
+        [
+            (self.diff_result.left.as_ref(), self.left_sym),
+            (self.diff_result.right.as_ref(), self.right_sym),
+        ]
+        .into_iter()
+        .filter_map(|(diff, sym)| get_symbol_diff(diff, sym))
+        .any(|d| d.instructions.get(row).is_some_and(|i| i.kind != ObjInsDiffKind::None))
+    }
+
+    /// Scroll to the start of the next (or previous) block of mismatched rows.
+    fn jump_to_mismatch(&mut self, forward: bool) {
+        let is_start =
+            |row: usize| self.row_mismatched(row) && (row == 0 || !self.row_mismatched(row - 1));
+        let found = if forward {
+            (self.scroll_y + 1..self.num_rows).find(|&row| is_start(row))
+        } else {
+            (0..self.scroll_y).rev().find(|&row| is_start(row))
+        };
+        if let Some(row) = found {
+            self.scroll_y = row;
+        }
+    }
+
+    fn handle_symbol_list_key(&mut self, code: KeyCode) -> EventControlFlow {
+        let mut result = EventResult { redraw: true, ..Default::default() };
+        let count = self.function_list().len();
+        let Some(list) = &mut self.symbol_list else {
+            return EventControlFlow::Continue(result);
+        };
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                list.selected = (list.selected + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => list.selected = list.selected.saturating_sub(1),
+            KeyCode::PageDown => list.selected = (list.selected + 20).min(count.saturating_sub(1)),
+            KeyCode::PageUp => list.selected = list.selected.saturating_sub(20),
+            KeyCode::Char('g') => list.selected = 0,
+            KeyCode::Char('G') => list.selected = count.saturating_sub(1),
+            KeyCode::Enter => {
+                let selected = list.selected;
+                if let Some((name, _)) = self.function_list().into_iter().nth(selected) {
+                    self.symbol_name = name;
+                    self.symbol_list = None;
+                    self.scroll_x = 0;
+                    self.scroll_y = 0;
+                    return EventControlFlow::Reload;
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('s') => {
+                // Keep the list open until a symbol is selected
+                if !self.symbol_name.is_empty() {
+                    self.symbol_list = None;
+                }
+            }
+            KeyCode::Char('q') => return EventControlFlow::Break,
+            _ => result.redraw = false,
+        }
+        EventControlFlow::Continue(result)
+    }
+
     fn draw_options(&mut self, f: &mut Frame, _result: &mut EventResult) {
         let percent_x = 50;
         let percent_y = 50;
@@ -548,6 +1018,12 @@ impl FunctionDiffUi {
     fn handle_event(&mut self, event: Event) -> EventControlFlow {
         let mut result = EventResult::default();
         match event {
+            Event::Key(event)
+                if self.symbol_list.is_some()
+                    && matches!(event.kind, KeyEventKind::Press | KeyEventKind::Repeat) =>
+            {
+                return self.handle_symbol_list_key(event.code);
+            }
             Event::Key(event)
                 if matches!(event.kind, KeyEventKind::Press | KeyEventKind::Repeat) =>
             {
@@ -630,11 +1106,67 @@ impl FunctionDiffUi {
                         result.redraw = true;
                         return EventControlFlow::Reload;
                     }
+                    // Toggle symbolic address literal comparison
+                    KeyCode::Char('a') => {
+                        self.symbolic_addresses = !self.symbolic_addresses;
+                        result.redraw = true;
+                        return EventControlFlow::Reload;
+                    }
+                    // Toggle counting alignment padding in the match percentage
+                    KeyCode::Char('p') => {
+                        self.ignore_padding = !self.ignore_padding;
+                        result.redraw = true;
+                        return EventControlFlow::Reload;
+                    }
+                    // Toggle moved block detection
+                    KeyCode::Char('m') => {
+                        self.detect_moved_blocks = !self.detect_moved_blocks;
+                        result.redraw = true;
+                        return EventControlFlow::Reload;
+                    }
+                    // Toggle instruction encoding display
+                    KeyCode::Char('e') => {
+                        self.show_encoding = !self.show_encoding;
+                        result.redraw = true;
+                        return EventControlFlow::Reload;
+                    }
+                    // Toggle byte-exact instruction comparison
+                    KeyCode::Char('E') => {
+                        self.strict_bytes = !self.strict_bytes;
+                        result.redraw = true;
+                        return EventControlFlow::Reload;
+                    }
+                    // Toggle decimal immediates
+                    KeyCode::Char('n') => {
+                        self.number_format.radix = match self.number_format.radix {
+                            NumberRadix::Hex => NumberRadix::Decimal,
+                            NumberRadix::Decimal => NumberRadix::Hex,
+                        };
+                        result.redraw = true;
+                    }
                     // Toggle three-way diff
                     KeyCode::Char('3') => {
                         self.three_way = !self.three_way;
                         result.redraw = true;
                     }
+                    // Open symbol list
+                    KeyCode::Char('s') => {
+                        let functions = self.function_list();
+                        let selected =
+                            functions.iter().position(|(n, _)| *n == self.symbol_name).unwrap_or(0);
+                        self.symbol_list = Some(SymbolListState { selected, scroll: 0 });
+                        result.redraw = true;
+                    }
+                    // Next mismatch
+                    KeyCode::Char(']') => {
+                        self.jump_to_mismatch(true);
+                        result.redraw = true;
+                    }
+                    // Previous mismatch
+                    KeyCode::Char('[') => {
+                        self.jump_to_mismatch(false);
+                        result.redraw = true;
+                    }
                     // Toggle options
                     KeyCode::Char('o') => {
                         self.open_options = !self.open_options;
@@ -711,26 +1243,14 @@ impl FunctionDiffUi {
             let mut line = Line::default();
             display_diff(ins_diff, base_addr, |text| -> Result<()> {
                 let label_text;
-                let mut base_color = match ins_diff.kind {
-                    ObjInsDiffKind::None
-                    | ObjInsDiffKind::OpMismatch
-                    | ObjInsDiffKind::ArgMismatch => Color::Gray,
-                    ObjInsDiffKind::Replace => Color::Cyan,
-                    ObjInsDiffKind::Delete => Color::Red,
-                    ObjInsDiffKind::Insert => Color::Green,
-                };
+                let base_color = tui_color(self.theme.color(token_kind(&text, ins_diff), ins_diff));
                 let mut pad_to = 0;
                 match text {
-                    DiffText::Basic(text) => {
+                    DiffText::Basic(text) | DiffText::Styled(text, _) => {
                         label_text = text.to_string();
                     }
-                    DiffText::BasicColor(s, idx) => {
-                        label_text = s.to_string();
-                        base_color = COLOR_ROTATION[idx % COLOR_ROTATION.len()];
-                    }
                     DiffText::Line(num) => {
                         label_text = format!("{num} ");
-                        base_color = Color::DarkGray;
                         pad_to = 5;
                     }
                     DiffText::Address(addr) => {
@@ -739,24 +1259,56 @@ impl FunctionDiffUi {
                     }
                     DiffText::Opcode(mnemonic, _op) => {
                         label_text = mnemonic.to_string();
-                        if ins_diff.kind == ObjInsDiffKind::OpMismatch {
-                            base_color = Color::Blue;
-                        }
                         pad_to = 8;
                     }
-                    DiffText::Argument(arg, diff) => {
-                        label_text = arg.to_string();
-                        if let Some(diff) = diff {
-                            base_color = COLOR_ROTATION[diff.idx % COLOR_ROTATION.len()]
+                    DiffText::Argument(arg, _) => {
+                        label_text = arg.display(self.number_format).to_string();
+                    }
+                    DiffText::BranchDest(addr, label) => {
+                        label_text = match label {
+                            Some(label) => format!(".L{label}"),
+                            None => format!("{addr:x}"),
+                        };
+                    }
+                    DiffText::Label(label, _) => {
+                        label_text = format!(".L{label}");
+                        pad_to = 4;
+                    }
+                    DiffText::Bytes(bytes, diff) => {
+                        // Color each byte by its own comparison
+                        for (i, b) in bytes.iter().enumerate() {
+                            let color = tui_color(self.theme.color(byte_kind(diff, i), ins_diff));
+                            let text = if i > 0 { format!(" {b:02x}") } else { format!("{b:02x}") };
+                            sx += text.len() as u16;
+                            line.spans.push(Span::styled(text, Style::new().fg(color)));
                         }
+                        return Ok(());
+                    }
+                    DiffText::Encoding(encoding) => {
+                        for (i, b) in encoding.bytes.iter().enumerate() {
+                            let kind = encoding_byte_kind(encoding, i);
+                            let color = tui_color(self.theme.color(kind, ins_diff));
+                            line.spans
+                                .push(Span::styled(format!(" {b:02x}"), Style::new().fg(color)));
+                        }
+                        let len = encoding.bytes.len() * 3;
+                        let pad = 13usize.saturating_sub(len);
+                        line.spans.push(Span::raw(" ".repeat(pad)));
+                        sx += (len + pad) as u16;
+                        return Ok(());
+                    }
+                    DiffText::Moved(_) => {
+                        label_text = " <> ".to_string();
                     }
-                    DiffText::BranchDest(addr) => {
-                        label_text = format!("{addr:x}");
+                    DiffText::Accepted => {
+                        label_text = " ok ".to_string();
                     }
-                    DiffText::Symbol(sym) => {
+                    DiffText::Symbol(sym, _, _) => {
                         let name = sym.demangled_name.as_ref().unwrap_or(&sym.name);
                         label_text = name.clone();
-                        base_color = Color::White;
+                    }
+                    DiffText::Comment(text) => {
+                        label_text = format!(" # {text}");
                     }
                     DiffText::Spacing(n) => {
                         line.spans.push(Span::raw(" ".repeat(n)));
@@ -795,7 +1347,9 @@ impl FunctionDiffUi {
 
     fn print_margin(&self, out: &mut Text, symbol: &ObjSymbolDiff, rect: Rect) {
         for ins_diff in symbol.instructions.iter().skip(self.scroll_y).take(rect.height as usize) {
-            if ins_diff.kind != ObjInsDiffKind::None {
+            if ins_diff.accepted {
+                out.lines.push(Line::raw("="));
+            } else if ins_diff.kind != ObjInsDiffKind::None {
                 out.lines.push(Line::raw(match ins_diff.kind {
                     ObjInsDiffKind::Delete => "<",
                     ObjInsDiffKind::Insert => ">",
@@ -807,30 +1361,163 @@ impl FunctionDiffUi {
         }
     }
 
+    /// Run the project's build command for the target and base objects.
+    fn rebuild(&mut self) {
+        self.build_error = None;
+        let (Some(project_config), Some(project_dir)) = (&self.project_config, &self.project_dir)
+        else {
+            return;
+        };
+        let mut paths = vec![];
+        if project_config.build_target {
+            paths.extend(self.target_path.as_deref());
+        }
+        if project_config.build_base {
+            paths.extend(self.base_path.as_deref());
+        }
+        for path in paths {
+            if let Err(e) = run_make(project_config, project_dir, self.build_command.as_ref(), path)
+            {
+                self.build_error = Some(format!("{e:#}"));
+                return;
+            }
+        }
+    }
+
+    /// Write the diff in a non-interactive format.
+    fn export(&self, format: DiffFormat, output: Option<&Path>, all: bool) -> Result<()> {
+        let mut out: Box<dyn Write> = match output {
+            Some(path) => Box::new(BufWriter::new(
+                File::create(path)
+                    .with_context(|| format!("Failed to create file {}", path.display()))?,
+            )),
+            None => Box::new(stdout().lock()),
+        };
+        let left_obj = self.left_obj.as_ref();
+        let right_obj = self.right_obj.as_ref();
+        let (left_diff, right_diff) =
+            (self.diff_result.left.as_ref(), self.diff_result.right.as_ref());
+        match format {
+            DiffFormat::Tui => unreachable!(),
+            DiffFormat::Html if all => {
+                let title = self
+                    .target_path
+                    .as_deref()
+                    .or(self.base_path.as_deref())
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default();
+                html::write_unit_html(
+                    &mut out,
+                    &title,
+                    &self.theme,
+                    self.number_format,
+                    left_obj.zip(left_diff),
+                    right_obj.zip(right_diff),
+                )?;
+            }
+            DiffFormat::Html => {
+                html::write_function_html(
+                    &mut out,
+                    &self.theme,
+                    self.number_format,
+                    symbol_diff_ref(left_obj, left_diff, self.left_sym),
+                    symbol_diff_ref(right_obj, right_diff, self.right_sym),
+                )?;
+            }
+            DiffFormat::Text | DiffFormat::Unified => {
+                let text_format = if format == DiffFormat::Text {
+                    TextDiffFormat::SideBySide
+                } else {
+                    TextDiffFormat::Unified
+                };
+                if all {
+                    text::write_unit_text(
+                        &mut out,
+                        text_format,
+                        self.number_format,
+                        left_obj.zip(left_diff),
+                        right_obj.zip(right_diff),
+                    )?;
+                } else {
+                    text::write_function_text(
+                        &mut out,
+                        text_format,
+                        self.number_format,
+                        symbol_diff_ref(left_obj, left_diff, self.left_sym),
+                        symbol_diff_ref(right_obj, right_diff, self.right_sym),
+                    )?;
+                }
+            }
+            // Protobuf output always contains the whole unit
+            DiffFormat::Proto => {
+                let result = DiffResult::new(left_obj.zip(left_diff), right_obj.zip(right_diff));
+                out.write_all(&result.encode_to_vec())?;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+
     fn reload(&mut self) -> Result<()> {
         let prev = self.right_obj.take();
+        let demangler = self.project_config.as_ref().and_then(|c| c.demangler).unwrap_or_default();
+        let mips_instr_category =
+            self.project_config.as_ref().and_then(|c| c.mips_instr_category).unwrap_or_default();
+        let idioms =
+            self.project_config.as_ref().and_then(|c| c.idioms.clone()).unwrap_or_default();
         let target = self
             .target_path
             .as_deref()
-            .map(|p| obj::read::read(p).with_context(|| format!("Loading {}", p.display())))
+            .map(|p| {
+                let mut obj = obj::binary::read_target(
+                    p,
+                    self.target_map.as_ref(),
+                    self.target_range.as_ref(),
+                )
+                .with_context(|| format!("Loading {}", p.display()))?;
+                obj::read::infer_symbol_sizes(&mut obj, self.size_inference);
+                obj::read::apply_accepted_mismatches(&mut obj, &self.accepted_mismatches);
+                obj::read::apply_symbol_mappings(&mut obj, &self.symbol_mappings);
+                demangle_symbols(&mut obj, demangler);
+                Ok::<_, anyhow::Error>(obj)
+            })
             .transpose()?;
         let base = self
             .base_path
             .as_deref()
-            .map(|p| obj::read::read(p).with_context(|| format!("Loading {}", p.display())))
+            .map(|p| {
+                let mut obj =
+                    obj::read::read(p).with_context(|| format!("Loading {}", p.display()))?;
+                obj::read::infer_symbol_sizes(&mut obj, self.size_inference);
+                demangle_symbols(&mut obj, demangler);
+                Ok::<_, anyhow::Error>(obj)
+            })
             .transpose()?;
         let config = diff::DiffObjConfig {
             relax_reloc_diffs: self.relax_reloc_diffs,
-            space_between_args: true,                // TODO
-            x86_formatter: Default::default(),       // TODO
-            mips_abi: Default::default(),            // TODO
-            mips_instr_category: Default::default(), // TODO
+            detect_moved_blocks: self.detect_moved_blocks,
+            symbolic_addresses: self.symbolic_addresses,
+            ignore_padding: self.ignore_padding,
+            normalize_reloc_addends: self.normalize_reloc_addends,
+            idioms,
+            prefilter: true,
+            space_between_args: true, // TODO
+            show_encoding: self.show_encoding,
+            policy: diff::DiffPolicy { strict_bytes: self.strict_bytes, ..Default::default() },
+            number_format: self.number_format,
+            x86_formatter: Default::default(), // TODO
+            mips_abi: Default::default(),      // TODO
+            mips_instr_category,
+            arm_arch_version: Default::default(), // TODO
         };
-        let result = diff::diff_objs(&config, target.as_ref(), base.as_ref(), prev.as_ref())?;
+        let mut result = diff::diff_objs(&config, target.as_ref(), base.as_ref(), prev.as_ref())?;
+        self.annotate(target.as_ref(), &mut result);
 
-        let left_sym = target.as_ref().and_then(|o| find_function(o, &self.symbol_name));
-        let right_sym = base.as_ref().and_then(|o| find_function(o, &self.symbol_name));
-        let prev_sym = prev.as_ref().and_then(|o| find_function(o, &self.symbol_name));
+        // Target symbols are renamed to their mapped base names on load
+        let name = self.symbol_mappings.get(&self.symbol_name).unwrap_or(&self.symbol_name);
+        let left_sym = target.as_ref().and_then(|o| find_function(o, name));
+        let right_sym = base.as_ref().and_then(|o| find_function(o, name));
+        let prev_sym = prev.as_ref().and_then(|o| find_function(o, name));
         self.num_rows = match (
             get_symbol_diff(result.left.as_ref(), left_sym),
             get_symbol_diff(result.right.as_ref(), right_sym),
@@ -838,6 +1525,8 @@ impl FunctionDiffUi {
             (Some(l), Some(r)) => l.instructions.len().max(r.instructions.len()),
             (Some(l), None) => l.instructions.len(),
             (None, Some(r)) => r.instructions.len(),
+            // Nothing selected yet in the symbol list
+            (None, None) if self.symbol_name.is_empty() => 0,
             (None, None) => bail!("Symbol not found: {}", self.symbol_name),
         };
         self.left_obj = target;
@@ -850,17 +1539,27 @@ impl FunctionDiffUi {
         self.reload_time = time::OffsetDateTime::now_local().ok();
         Ok(())
     }
+
+    /// Attach the project's instruction notes to the target diff.
+    fn annotate(&self, target: Option<&ObjInfo>, result: &mut DiffObjsResult) {
+        let (Some(project_config), Some(project_dir), Some(unit), Some(target), Some(left)) = (
+            &self.project_config,
+            &self.project_dir,
+            &self.unit_name,
+            target,
+            result.left.as_mut(),
+        ) else {
+            return;
+        };
+        let path = workflow_path(project_dir, project_config.workflow_path.as_deref());
+        match WorkflowState::load(&path) {
+            Ok(state) => state.annotate(unit, target, left),
+            Err(e) => tracing::warn!("Failed to load workflow state: {e:#}"),
+        }
+    }
 }
 
-pub const COLOR_ROTATION: [Color; 7] = [
-    Color::Magenta,
-    Color::Cyan,
-    Color::Green,
-    Color::Red,
-    Color::Yellow,
-    Color::Blue,
-    Color::Green,
-];
+fn tui_color(color: diff::theme::Color) -> Color { Color::Rgb(color.r, color.g, color.b) }
 
 pub fn match_percent_color(match_percent: f32) -> Color {
     if match_percent == 100.0 {
@@ -871,3 +1570,44 @@ pub fn match_percent_color(match_percent: f32) -> Color {
         Color::LightRed
     }
 }
+
+/// Run the project's build command for an object, with the unit's overrides.
+pub(crate) fn build_object(
+    config: &ProjectConfig,
+    project_dir: &Path,
+    unit: Option<&BuildCommand>,
+    path: &Path,
+) -> BuildStatus {
+    // Archive members are built by building the archive itself
+    let path = obj::read::file_path(path);
+    let path = path.strip_prefix(project_dir).unwrap_or(&path);
+    let build_config = BuildConfig {
+        project_dir: Some(project_dir.to_path_buf()),
+        custom_make: config.custom_make.clone(),
+        custom_args: config.custom_args.clone(),
+        selected_wsl_distro: None,
+    };
+    CommandRunner.build(&build_config, unit, path)
+}
+
+pub(crate) fn run_make(
+    config: &ProjectConfig,
+    project_dir: &Path,
+    unit: Option<&BuildCommand>,
+    path: &Path,
+) -> Result<()> {
+    let status = build_object(config, project_dir, unit, path);
+    if status.success {
+        return Ok(());
+    }
+    let errors = status
+        .errors
+        .iter()
+        .filter(|e| e.severity == BuildErrorSeverity::Error)
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        bail!("Build failed for {}: {}", path.display(), status.stderr.trim());
+    }
+    bail!("Build failed for {}:\n{}", path.display(), errors.join("\n"))
+}
@@ -0,0 +1,66 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use argp::FromArgs;
+use objdiff_core::{config::try_project_config, link::link_project};
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Link the base objects in project order and compare the sections against the target binary.
+#[argp(subcommand, name = "link")]
+pub struct Args {
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let project_dir = args.project.as_deref().unwrap_or_else(|| Path::new("."));
+    let Some((config, info)) = try_project_config(project_dir) else {
+        bail!("Project config not found in {}", project_dir.display())
+    };
+    let config =
+        config.with_context(|| format!("Reading project config {}", info.path.display()))?;
+    let report = link_project(project_dir, &config)?;
+
+    let mut out = std::io::stdout().lock();
+    let mut diverged = false;
+    for section in &report.sections {
+        let target_size = match section.target_size {
+            Some(size) if size != section.size => format!(" (target {size:#x})"),
+            _ => String::new(),
+        };
+        write!(
+            out,
+            "{} {:#010x} size {:#x}{target_size}: ",
+            section.name, section.address, section.size
+        )?;
+        let Some(divergence) = &section.divergence else {
+            writeln!(out, "matches")?;
+            continue;
+        };
+        diverged = true;
+        let location = match (&divergence.unit, &divergence.symbol) {
+            (Some(unit), Some(symbol)) => format!(" ({unit}: {symbol})"),
+            (Some(unit), None) => format!(" ({unit})"),
+            _ => String::new(),
+        };
+        let byte = |b: Option<u8>| b.map_or_else(|| "end".to_string(), |b| format!("{b:#04x}"));
+        writeln!(
+            out,
+            "diverges at {:#010x}{location}, expected {} found {}",
+            divergence.address,
+            byte(divergence.expected),
+            byte(divergence.found)
+        )?;
+    }
+    for issue in &report.issues {
+        writeln!(out, "warning: {issue}")?;
+    }
+    if diverged {
+        bail!("Linked output differs from the target");
+    }
+    Ok(())
+}
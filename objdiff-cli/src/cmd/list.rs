@@ -0,0 +1,151 @@
+use std::{io::Write, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use argp::FromArgs;
+use globset::Glob;
+use objdiff_core::{
+    config::{expand_archive_objects, try_project_config},
+    diff::{
+        diff_objs,
+        filter::{NamePattern, SymbolFilter},
+        DiffObjConfig,
+    },
+    obj::read,
+    project::load_unit,
+    workflow::{workflow_path, WorkflowState, WorkflowStatus},
+};
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// List the symbols of a diffed unit.
+#[argp(subcommand, name = "list")]
+pub struct Args {
+    #[argp(option, short = '1')]
+    /// Target object file
+    target: Option<PathBuf>,
+    #[argp(option, short = '2')]
+    /// Base object file
+    base: Option<PathBuf>,
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+    #[argp(option, short = 'u')]
+    /// Unit name within project
+    unit: Option<String>,
+    #[argp(option, short = 'n')]
+    /// Symbol name filter: a substring, a glob, or a /regex/
+    name: Option<String>,
+    #[argp(option, short = 's')]
+    /// Section name glob
+    section: Option<String>,
+    #[argp(option)]
+    /// Minimum symbol size in bytes
+    min_size: Option<u64>,
+    #[argp(option)]
+    /// Maximum symbol size in bytes
+    max_size: Option<u64>,
+    #[argp(switch, short = 'm')]
+    /// Only list symbols that don't fully match
+    mismatched: bool,
+    #[argp(option)]
+    /// Only list symbols with this workflow status (requires a unit)
+    status: Option<WorkflowStatus>,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let filter = SymbolFilter {
+        name: args.name.as_deref().map(NamePattern::parse).transpose()?,
+        section: args
+            .section
+            .as_deref()
+            .map(|s| Glob::new(s).map(|g| g.compile_matcher()))
+            .transpose()
+            .context("Invalid section glob")?,
+        min_size: args.min_size,
+        max_size: args.max_size,
+        mismatched: args.mismatched,
+    };
+
+    let (target, base, mips_instr_category, workflow) =
+        match (&args.target, &args.base, &args.project, &args.unit) {
+            (target, base, None, None) if target.is_some() || base.is_some() => {
+                let load = |path: &PathBuf| {
+                    read::read(path).with_context(|| format!("Failed to open {}", path.display()))
+                };
+                if args.status.is_some() {
+                    bail!("Filtering by workflow status requires a unit");
+                }
+                let target = target.as_ref().map(load).transpose()?;
+                (target, base.as_ref().map(load).transpose()?, Default::default(), None)
+            }
+            (None, None, project, Some(unit)) => {
+                let project_dir = match project {
+                    Some(project) => project.clone(),
+                    None => {
+                        std::env::current_dir().context("Failed to get the current directory")?
+                    }
+                };
+                let Some((config, info)) = try_project_config(&project_dir) else {
+                    bail!("Project config not found in {}", project_dir.display())
+                };
+                let config = config
+                    .with_context(|| format!("Reading project config {}", info.path.display()))?;
+                let objects = expand_archive_objects(
+                    config.objects,
+                    &project_dir,
+                    config.target_dir.as_deref(),
+                    config.base_dir.as_deref(),
+                );
+                let Some(mut object) = objects.into_iter().find(|o| o.name() == unit) else {
+                    bail!("Unit not found: {unit}")
+                };
+                object.resolve_paths(
+                    &project_dir,
+                    config.target_dir.as_deref(),
+                    config.base_dir.as_deref(),
+                );
+                let (target, base) = load_unit(&object, config.demangler.unwrap_or_default())?;
+                let state = WorkflowState::load(&workflow_path(
+                    &project_dir,
+                    config.workflow_path.as_deref(),
+                ))?;
+                let workflow = state.units.get(unit).cloned().unwrap_or_default();
+                (target, base, config.mips_instr_category.unwrap_or_default(), Some(workflow))
+            }
+            _ => bail!("Either target and/or base, or a unit must be specified"),
+        };
+
+    let diff_config =
+        DiffObjConfig { relax_reloc_diffs: true, mips_instr_category, ..Default::default() };
+    let result = diff_objs(&diff_config, target.as_ref(), base.as_ref(), None)?;
+    // List the target's symbols, or the base's if there is no target
+    let (obj, obj_diff) = match (&target, &result.left, &base, &result.right) {
+        (Some(obj), Some(diff), _, _) | (None, _, Some(obj), Some(diff)) => (obj, diff),
+        _ => bail!("Unit has no target or base object"),
+    };
+
+    let mut out = std::io::stdout().lock();
+    for symbol_ref in filter.filter(obj, obj_diff) {
+        let (section, symbol) = obj.section_symbol(symbol_ref);
+        let status =
+            workflow.as_ref().map(|w| w.get(&symbol.name).map(|s| s.status).unwrap_or_default());
+        if args.status.is_some() && status != args.status {
+            continue;
+        }
+        let match_percent = obj_diff
+            .symbol_diff(symbol_ref)
+            .match_percent
+            .map_or_else(|| "-".to_string(), |p| format!("{p:.2}%"));
+        write!(
+            out,
+            "{:<12} {:>8x} {:>8} ",
+            section.map_or(".comm", |s| s.name.as_str()),
+            symbol.size,
+            match_percent,
+        )?;
+        if let Some(status) = status {
+            write!(out, "{:<12} ", status.name())?;
+        }
+        writeln!(out, "{}", symbol.display_name(true))?;
+    }
+    Ok(())
+}
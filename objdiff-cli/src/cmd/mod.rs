@@ -1,2 +1,12 @@
+pub mod changes;
+pub mod check;
 pub mod diff;
+pub mod link;
+pub mod list;
+pub mod objdump;
+pub mod pair;
 pub mod report;
+pub mod scratch;
+pub mod server;
+pub mod session;
+pub mod workflow;
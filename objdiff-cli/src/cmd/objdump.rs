@@ -0,0 +1,117 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context, Result};
+use argp::FromArgs;
+use objdiff_core::{
+    config::{expand_archive_objects, try_project_config},
+    diff::{
+        diff_objs,
+        objdump::{write_objdump, ObjdumpHeader, ObjdumpOptions},
+        DiffObjConfig,
+    },
+    obj::read,
+    project::load_unit,
+};
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Print the disassembly of an object in the layout of `objdump -dr`.
+#[argp(subcommand, name = "objdump")]
+pub struct Args {
+    #[argp(positional)]
+    /// Object file (omit to use a project unit)
+    file: Option<PathBuf>,
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+    #[argp(option, short = 'u')]
+    /// Unit name within project
+    unit: Option<String>,
+    #[argp(switch)]
+    /// Print the unit's base object instead of its target
+    base: bool,
+    #[argp(option, short = 's')]
+    /// Only print this function
+    symbol: Option<String>,
+    #[argp(switch)]
+    /// Omit relocations, like `objdump -d`
+    no_relocs: bool,
+    #[argp(option, short = 'o')]
+    /// Output file (defaults to stdout)
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let (path, obj, mips_instr_category) = match (&args.file, &args.unit) {
+        (Some(path), None) => {
+            let obj =
+                read::read(path).with_context(|| format!("Failed to open {}", path.display()))?;
+            (path.clone(), obj, Default::default())
+        }
+        (None, Some(unit)) => {
+            let project_dir = match &args.project {
+                Some(project) => project.clone(),
+                None => std::env::current_dir().context("Failed to get the current directory")?,
+            };
+            let Some((config, info)) = try_project_config(&project_dir) else {
+                bail!("Project config not found in {}", project_dir.display())
+            };
+            let config = config
+                .with_context(|| format!("Reading project config {}", info.path.display()))?;
+            let objects = expand_archive_objects(
+                config.objects,
+                &project_dir,
+                config.target_dir.as_deref(),
+                config.base_dir.as_deref(),
+            );
+            let Some(mut object) = objects.into_iter().find(|o| o.name() == unit) else {
+                bail!("Unit not found: {unit}")
+            };
+            object.resolve_paths(
+                &project_dir,
+                config.target_dir.as_deref(),
+                config.base_dir.as_deref(),
+            );
+            // Symbol names are printed mangled, as objdump does without -C
+            let (target, base) = load_unit(&object, Default::default())?;
+            let (path, obj) = if args.base {
+                (object.base_path.clone(), base)
+            } else {
+                (object.target_path.clone(), target)
+            };
+            let (Some(path), Some(obj)) = (path, obj) else {
+                bail!("Unit {unit} has no {} object", if args.base { "base" } else { "target" })
+            };
+            (path, obj, config.mips_instr_category.unwrap_or_default())
+        }
+        _ => bail!("Either an object file or a unit must be specified"),
+    };
+
+    let diff_config =
+        DiffObjConfig { space_between_args: false, mips_instr_category, ..Default::default() };
+    let result = diff_objs(&diff_config, Some(&obj), None, None)?;
+    let Some(diff) = result.left else {
+        bail!("Failed to disassemble {}", path.display());
+    };
+    let options = ObjdumpOptions {
+        // Targets read from linked binaries have no object header
+        header: ObjdumpHeader::read(&path).unwrap_or_default(),
+        relocations: !args.no_relocs,
+        symbol: args.symbol,
+        number_format: Default::default(),
+    };
+    match &args.output {
+        Some(output) => {
+            let file = File::create(output)
+                .with_context(|| format!("Failed to create {}", output.display()))?;
+            let mut w = BufWriter::new(file);
+            write_objdump(&mut w, &obj, &diff, &options)?;
+            w.flush()?;
+        }
+        None => write_objdump(&mut std::io::stdout().lock(), &obj, &diff, &options)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::{collections::BTreeMap, io::Write, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use argp::FromArgs;
+use objdiff_core::{
+    config::{expand_archive_objects, try_project_config},
+    diff::{similarity::suggest_pairings, DiffObjConfig},
+    obj::read,
+    project::load_unit,
+};
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Suggest pairings between target and base functions that aren't paired by name.
+#[argp(subcommand, name = "pair")]
+pub struct Args {
+    #[argp(option, short = '1')]
+    /// Target object file
+    target: Option<PathBuf>,
+    #[argp(option, short = '2')]
+    /// Base object file
+    base: Option<PathBuf>,
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+    #[argp(option, short = 'u')]
+    /// Unit name within project
+    unit: Option<String>,
+    #[argp(option, short = 'c', default = "0.5")]
+    /// Minimum confidence of a suggestion, from 0.0 to 1.0 (default: 0.5)
+    min_confidence: f32,
+    #[argp(switch)]
+    /// Print the suggestions as `symbol_mappings` for the unit's configuration
+    json: bool,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let (target, base, mips_instr_category) =
+        match (&args.target, &args.base, &args.project, &args.unit) {
+            (Some(target), Some(base), None, None) => {
+                let load = |path: &PathBuf| {
+                    read::read(path).with_context(|| format!("Failed to open {}", path.display()))
+                };
+                (load(target)?, load(base)?, Default::default())
+            }
+            (None, None, project, Some(unit)) => {
+                let project_dir = match project {
+                    Some(project) => project.clone(),
+                    None => {
+                        std::env::current_dir().context("Failed to get the current directory")?
+                    }
+                };
+                let Some((config, info)) = try_project_config(&project_dir) else {
+                    bail!("Project config not found in {}", project_dir.display())
+                };
+                let config = config
+                    .with_context(|| format!("Reading project config {}", info.path.display()))?;
+                let objects = expand_archive_objects(
+                    config.objects,
+                    &project_dir,
+                    config.target_dir.as_deref(),
+                    config.base_dir.as_deref(),
+                );
+                let Some(mut object) = objects.into_iter().find(|o| o.name() == unit) else {
+                    bail!("Unit not found: {unit}")
+                };
+                object.resolve_paths(
+                    &project_dir,
+                    config.target_dir.as_deref(),
+                    config.base_dir.as_deref(),
+                );
+                let (Some(target), Some(base)) =
+                    load_unit(&object, config.demangler.unwrap_or_default())?
+                else {
+                    bail!("Unit {unit} needs both a target and a base object")
+                };
+                (target, base, config.mips_instr_category.unwrap_or_default())
+            }
+            _ => bail!("Either target and base, or a unit must be specified"),
+        };
+
+    let config = DiffObjConfig { mips_instr_category, ..Default::default() };
+    let pairings = suggest_pairings(&target, &base, &config, args.min_confidence);
+    let mut out = std::io::stdout().lock();
+    if args.json {
+        let mappings = pairings
+            .iter()
+            .map(|p| {
+                let left = &target.section_symbol(p.left).1.name;
+                let right = &base.section_symbol(p.right).1.name;
+                (left.as_str(), right.as_str())
+            })
+            .collect::<BTreeMap<_, _>>();
+        serde_json::to_writer_pretty(&mut out, &mappings)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+    for pairing in &pairings {
+        writeln!(
+            out,
+            "{:>7.2}% {} -> {}",
+            pairing.confidence * 100.0,
+            target.section_symbol(pairing.left).1.display_name(true),
+            base.section_symbol(pairing.right).1.display_name(true),
+        )?;
+    }
+    Ok(())
+}
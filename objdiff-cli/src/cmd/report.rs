@@ -1,20 +1,25 @@
 use std::{
     collections::HashSet,
     fs::File,
-    io::{BufReader, BufWriter, Write},
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
+    str::FromStr,
     time::Instant,
 };
 
 use anyhow::{bail, Context, Result};
 use argp::FromArgs;
 use objdiff_core::{
-    config::ProjectObject,
-    diff, obj,
-    obj::{ObjSectionKind, ObjSymbolFlags},
+    cache::DiffCache,
+    config::{compile_commands::CompilationDatabase, expand_archive_objects},
+    diff::DiffPolicyPreset,
+    frogress,
+    frogress::FrogressEntry,
+    project::{CancelToken, ProjectDiffer},
+    report::{Report, ReportItem, ReportUnit, UnitSummary},
+    workflow::{workflow_path, WorkflowState},
 };
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 #[derive(FromArgs, PartialEq, Debug)]
 /// Commands for processing NVIDIA Shield TV alf files.
@@ -28,7 +33,9 @@ pub struct Args {
 #[argp(subcommand)]
 pub enum SubCommand {
     Generate(GenerateArgs),
+    Summary(SummaryArgs),
     Changes(ChangesArgs),
+    Upload(UploadArgs),
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -39,11 +46,68 @@ pub struct GenerateArgs {
     /// Project directory
     project: Option<PathBuf>,
     #[argp(option, short = 'o')]
-    /// Output JSON file
+    /// Output file
     output: Option<PathBuf>,
+    #[argp(option, short = 'f', default = "ReportFormat::Json")]
+    /// Output format (json, binary)
+    format: ReportFormat,
     #[argp(switch, short = 'd')]
-    /// Deduplicate global and weak symbols (runs single-threaded)
+    /// Deduplicate global and weak symbols
     deduplicate: bool,
+    #[argp(option, default = "DiffPolicyPreset::Default", from_str_fn(parse_policy))]
+    /// Match scoring policy (default, ignore-regalloc, strict)
+    policy: DiffPolicyPreset,
+    #[argp(switch)]
+    /// Don't read or write the diff cache in .objdiff/cache
+    no_cache: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Diff every unit in a project and print a summary table.
+#[argp(subcommand, name = "summary")]
+pub struct SummaryArgs {
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+    #[argp(switch, short = 'd')]
+    /// Deduplicate global and weak symbols
+    deduplicate: bool,
+    #[argp(switch)]
+    /// Only print units that are not fully matched
+    incomplete: bool,
+    #[argp(option, default = "DiffPolicyPreset::Default", from_str_fn(parse_policy))]
+    /// Match scoring policy (default, ignore-regalloc, strict)
+    policy: DiffPolicyPreset,
+    #[argp(switch)]
+    /// Don't read or write the diff cache in .objdiff/cache
+    no_cache: bool,
+}
+
+pub(crate) fn parse_policy(s: &str) -> Result<DiffPolicyPreset, String> {
+    match s {
+        "default" => Ok(DiffPolicyPreset::Default),
+        "ignore-regalloc" => Ok(DiffPolicyPreset::IgnoreRegalloc),
+        "strict" => Ok(DiffPolicyPreset::Strict),
+        _ => Err(format!("Invalid diff policy: {s}")),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ReportFormat {
+    Json,
+    Binary,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "binary" | "bin" => Ok(Self::Binary),
+            _ => Err(format!("Invalid report format: {s}")),
+        }
+    }
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -51,284 +115,249 @@ pub struct GenerateArgs {
 #[argp(subcommand, name = "changes")]
 pub struct ChangesArgs {
     #[argp(positional)]
-    /// Previous report JSON file
+    /// Previous report file (JSON or binary)
     previous: PathBuf,
     #[argp(positional)]
-    /// Current report JSON file
+    /// Current report file (JSON or binary)
     current: PathBuf,
     #[argp(option, short = 'o')]
     /// Output JSON file
     output: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
-struct Report {
-    fuzzy_match_percent: f32,
-    total_code: u64,
-    matched_code: u64,
-    matched_code_percent: f32,
-    total_data: u64,
-    matched_data: u64,
-    matched_data_percent: f32,
-    total_functions: u32,
-    matched_functions: u32,
-    matched_functions_percent: f32,
-    units: Vec<ReportUnit>,
-}
-
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
-struct ReportUnit {
-    name: String,
-    fuzzy_match_percent: f32,
-    total_code: u64,
-    matched_code: u64,
-    total_data: u64,
-    matched_data: u64,
-    total_functions: u32,
-    matched_functions: u32,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    complete: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    module_name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    module_id: Option<u32>,
-    sections: Vec<ReportItem>,
-    functions: Vec<ReportItem>,
-}
-
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
-struct ReportItem {
-    name: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    demangled_name: Option<String>,
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        serialize_with = "serialize_hex",
-        deserialize_with = "deserialize_hex"
-    )]
-    address: Option<u64>,
-    size: u64,
-    fuzzy_match_percent: f32,
+#[derive(FromArgs, PartialEq, Debug)]
+/// Upload a report to a frogress progress website.
+#[argp(subcommand, name = "upload")]
+pub struct UploadArgs {
+    #[argp(positional)]
+    /// Report file (JSON or binary)
+    report: PathBuf,
+    #[argp(option)]
+    /// frogress API base URL
+    url: String,
+    #[argp(option)]
+    /// frogress project slug
+    project: String,
+    #[argp(option)]
+    /// frogress project version
+    version: String,
+    #[argp(option)]
+    /// API key (env: FROGRESS_API_KEY)
+    api_key: Option<String>,
+    #[argp(option)]
+    /// Git commit hash (default: current HEAD)
+    git_hash: Option<String>,
+    #[argp(option)]
+    /// Unix timestamp (default: commit timestamp of HEAD)
+    timestamp: Option<u64>,
+    #[argp(option, default = "frogress::DEFAULT_CATEGORY.to_string()")]
+    /// Category containing every unit (default: default)
+    default_category: String,
+    #[argp(switch)]
+    /// Print the payload without uploading
+    dry_run: bool,
 }
 
 pub fn run(args: Args) -> Result<()> {
     match args.command {
         SubCommand::Generate(args) => generate(args),
+        SubCommand::Summary(args) => summary(args),
         SubCommand::Changes(args) => changes(args),
+        SubCommand::Upload(args) => upload(args),
     }
 }
 
-fn generate(args: GenerateArgs) -> Result<()> {
-    let project_dir = args.project.as_deref().unwrap_or_else(|| Path::new("."));
+pub(crate) fn generate_report(
+    project_dir: &Path,
+    deduplicate: bool,
+    policy: DiffPolicyPreset,
+    use_cache: bool,
+) -> Result<Report> {
     info!("Loading project {}", project_dir.display());
 
     let config = objdiff_core::config::try_project_config(project_dir);
     let Some((Ok(mut project), _)) = config else {
         bail!("No project configuration found");
     };
-    info!(
-        "Generating report for {} units (using {} threads)",
-        project.objects.len(),
-        if args.deduplicate { 1 } else { rayon::current_num_threads() }
+    project.objects = expand_archive_objects(
+        std::mem::take(&mut project.objects),
+        project_dir,
+        project.target_dir.as_deref(),
+        project.base_dir.as_deref(),
     );
+    info!("Generating report for {} units", project.objects.len());
 
+    let demangler = project.demangler.unwrap_or_default();
+    let config = project.report_diff_config(policy);
     let start = Instant::now();
-    let mut report = Report::default();
-    let mut existing_functions: HashSet<String> = HashSet::new();
-    if args.deduplicate {
-        // If deduplicating, we need to run single-threaded
-        for object in &mut project.objects {
-            if let Some(unit) = report_object(
-                object,
-                project_dir,
-                project.target_dir.as_deref(),
-                project.base_dir.as_deref(),
-                Some(&mut existing_functions),
-            )? {
-                report.units.push(unit);
+    let mut objects = Vec::with_capacity(project.objects.len());
+    for mut object in std::mem::take(&mut project.objects) {
+        object.resolve_paths(
+            project_dir,
+            project.target_dir.as_deref(),
+            project.base_dir.as_deref(),
+        );
+        match (&object.target_path, &object.base_path) {
+            (None, Some(_)) if object.complete != Some(true) => {
+                warn!("Skipping object without target: {}", object.name());
             }
+            (None, None) => {
+                warn!("Skipping object without target or base: {}", object.name());
+            }
+            _ => objects.push(object),
         }
-    } else {
-        let units = project
-            .objects
-            .par_iter_mut()
-            .map(|object| {
-                report_object(
-                    object,
-                    project_dir,
-                    project.target_dir.as_deref(),
-                    project.base_dir.as_deref(),
-                    None,
-                )
-            })
-            .collect::<Result<Vec<Option<ReportUnit>>>>()?;
-        report.units = units.into_iter().flatten().collect();
     }
-    for unit in &report.units {
-        report.fuzzy_match_percent += unit.fuzzy_match_percent * unit.total_code as f32;
-        report.total_code += unit.total_code;
-        report.matched_code += unit.matched_code;
-        report.total_data += unit.total_data;
-        report.matched_data += unit.matched_data;
-        report.total_functions += unit.total_functions;
-        report.matched_functions += unit.matched_functions;
-    }
-    if report.total_code == 0 {
-        report.fuzzy_match_percent = 100.0;
-    } else {
-        report.fuzzy_match_percent /= report.total_code as f32;
+
+    let cache = use_cache.then(|| DiffCache::new(project_dir));
+    let keys = objects
+        .iter()
+        .map(|object| {
+            cache.as_ref()?;
+            DiffCache::key(object, &config, demangler)
+                .map_err(|e| warn!("Failed to compute cache key for {}: {e:#}", object.name()))
+                .ok()
+        })
+        .collect::<Vec<_>>();
+    let mut summaries = keys
+        .iter()
+        .map(|key| cache.as_ref().zip(*key).and_then(|(cache, key)| cache.get(key)))
+        .collect::<Vec<_>>();
+    let uncached = (0..objects.len()).filter(|&i| summaries[i].is_none()).collect::<Vec<_>>();
+    info!("{} units cached, diffing {}", objects.len() - uncached.len(), uncached.len());
+
+    let uncached_objects = uncached.iter().map(|&i| objects[i].clone()).collect::<Vec<_>>();
+    let results = ProjectDiffer::new().diff_units(
+        &uncached_objects,
+        &config,
+        demangler,
+        0,
+        |progress| debug!("[{}/{}] {}", progress.completed, progress.total, progress.unit),
+        &CancelToken::default(),
+    )?;
+    for (&i, result) in uncached.iter().zip(results) {
+        let unit_diff = result?;
+        let obj = unit_diff.target.as_ref().or(unit_diff.base.as_ref()).unwrap();
+        let obj_diff = unit_diff.diff.left.as_ref().or(unit_diff.diff.right.as_ref()).unwrap();
+        let mut summary = UnitSummary::from_diff(obj, obj_diff);
+        if let (Some(target), Some(base)) = (&unit_diff.target, &unit_diff.base) {
+            summary = summary.with_layout(target, base);
+        }
+        if let (Some(cache), Some(key)) = (&cache, keys[i]) {
+            if let Err(e) = cache.insert(key, &summary) {
+                warn!("Failed to write cache entry for {}: {e:#}", objects[i].name());
+            }
+        }
+        summaries[i] = Some(summary);
     }
 
-    report.matched_code_percent = if report.total_code == 0 {
-        100.0
-    } else {
-        report.matched_code as f32 / report.total_code as f32 * 100.0
-    };
-    report.matched_data_percent = if report.total_data == 0 {
-        100.0
-    } else {
-        report.matched_data as f32 / report.total_data as f32 * 100.0
-    };
-    report.matched_functions_percent = if report.total_functions == 0 {
-        100.0
-    } else {
-        report.matched_functions as f32 / report.total_functions as f32 * 100.0
-    };
+    let database = CompilationDatabase::for_project(project_dir, &project)
+        .map_err(|e| warn!("Failed to load compilation database: {e:#}"))
+        .ok()
+        .flatten();
+    let mut report = Report { policy: Some(policy), ..Default::default() };
+    let mut existing_functions: HashSet<String> = HashSet::new();
+    for (object, summary) in objects.iter().zip(summaries) {
+        let mut unit = ReportUnit::from_summary(
+            object.name().to_string(),
+            object.complete,
+            &summary.unwrap(),
+            deduplicate.then_some(&mut existing_functions),
+        );
+        unit.categories = object.progress_categories.clone().unwrap_or_default();
+        unit.compile_command = object.compile_command(project_dir, &project, database.as_ref());
+        report.units.push(unit);
+    }
+    match WorkflowState::load(&workflow_path(project_dir, project.workflow_path.as_deref())) {
+        Ok(state) => report.apply_workflow(&state),
+        Err(e) => warn!("Failed to load workflow state: {e:#}"),
+    }
+    report.calculate_progress();
     let duration = start.elapsed();
     info!("Report generated in {}.{:03}s", duration.as_secs(), duration.subsec_millis());
+    Ok(report)
+}
+
+fn generate(args: GenerateArgs) -> Result<()> {
+    let project_dir = args.project.as_deref().unwrap_or_else(|| Path::new("."));
+    let report = generate_report(project_dir, args.deduplicate, args.policy, !args.no_cache)?;
     if let Some(output) = &args.output {
         info!("Writing to {}", output.display());
         let mut output = BufWriter::new(
             File::create(output)
                 .with_context(|| format!("Failed to create file {}", output.display()))?,
         );
-        serde_json::to_writer_pretty(&mut output, &report)?;
+        match args.format {
+            ReportFormat::Json => report.write_json(&mut output)?,
+            ReportFormat::Binary => report.write_binary(&mut output)?,
+        }
         output.flush()?;
     } else {
-        serde_json::to_writer_pretty(std::io::stdout(), &report)?;
+        match args.format {
+            ReportFormat::Json => report.write_json(std::io::stdout())?,
+            ReportFormat::Binary => report.write_binary(&mut std::io::stdout().lock())?,
+        }
     }
     Ok(())
 }
 
-fn report_object(
-    object: &mut ProjectObject,
-    project_dir: &Path,
-    target_dir: Option<&Path>,
-    base_dir: Option<&Path>,
-    mut existing_functions: Option<&mut HashSet<String>>,
-) -> Result<Option<ReportUnit>> {
-    object.resolve_paths(project_dir, target_dir, base_dir);
-    match (&object.target_path, &object.base_path) {
-        (None, Some(_)) if object.complete != Some(true) => {
-            warn!("Skipping object without target: {}", object.name());
-            return Ok(None);
-        }
-        (None, None) => {
-            warn!("Skipping object without target or base: {}", object.name());
-            return Ok(None);
+fn summary(args: SummaryArgs) -> Result<()> {
+    let project_dir = args.project.as_deref().unwrap_or_else(|| Path::new("."));
+    let report = generate_report(project_dir, args.deduplicate, args.policy, !args.no_cache)?;
+
+    let name_width = report.units.iter().map(|u| u.name.len()).max().unwrap_or(0).max(4);
+    let mut out = std::io::stdout().lock();
+    writeln!(
+        out,
+        "{:<name_width$}  {:>8}  {:>8}  {:>8}  {:>11}  {:>6}",
+        "Unit", "Fuzzy", "Code", "Data", "Functions", "Layout"
+    )?;
+    writeln!(out, "{}", "-".repeat(name_width + 51))?;
+    for unit in &report.units {
+        if args.incomplete
+            && unit.matched_code == unit.total_code
+            && unit.matched_data == unit.total_data
+            && unit.layout_ok != Some(false)
+        {
+            continue;
         }
-        _ => {}
+        writeln!(
+            out,
+            "{:<name_width$}  {:>7.2}%  {:>7.2}%  {:>7.2}%  {:>11}  {:>6}",
+            unit.name,
+            unit.fuzzy_match_percent,
+            percent(unit.matched_code, unit.total_code),
+            percent(unit.matched_data, unit.total_data),
+            format!("{}/{}", unit.matched_functions, unit.total_functions),
+            match unit.layout_ok {
+                Some(true) => "OK",
+                Some(false) => "DIFF",
+                None => "-",
+            },
+        )?;
     }
-    let target = object
-        .target_path
-        .as_ref()
-        .map(|p| obj::read::read(p).with_context(|| format!("Failed to open {}", p.display())))
-        .transpose()?;
-    let base = object
-        .base_path
-        .as_ref()
-        .map(|p| obj::read::read(p).with_context(|| format!("Failed to open {}", p.display())))
-        .transpose()?;
-    let config = diff::DiffObjConfig { relax_reloc_diffs: true, ..Default::default() };
-    let result = diff::diff_objs(&config, target.as_ref(), base.as_ref(), None)?;
-    let mut unit = ReportUnit {
-        name: object.name().to_string(),
-        complete: object.complete,
-        module_name: target
-            .as_ref()
-            .and_then(|o| o.split_meta.as_ref())
-            .and_then(|m| m.module_name.clone()),
-        module_id: target.as_ref().and_then(|o| o.split_meta.as_ref()).and_then(|m| m.module_id),
-        ..Default::default()
-    };
-    let obj = target.as_ref().or(base.as_ref()).unwrap();
-
-    let obj_diff = result.left.as_ref().or(result.right.as_ref()).unwrap();
-    for (section, section_diff) in obj.sections.iter().zip(&obj_diff.sections) {
-        let section_match_percent = section_diff.match_percent.unwrap_or_else(|| {
-            // Support cases where we don't have a target object,
-            // assume complete means 100% match
-            if object.complete == Some(true) {
-                100.0
-            } else {
-                0.0
-            }
-        });
-        unit.sections.push(ReportItem {
-            name: section.name.clone(),
-            demangled_name: None,
-            fuzzy_match_percent: section_match_percent,
-            size: section.size,
-            address: section.virtual_address,
-        });
-
-        match section.kind {
-            ObjSectionKind::Data | ObjSectionKind::Bss => {
-                unit.total_data += section.size;
-                if section_match_percent == 100.0 {
-                    unit.matched_data += section.size;
-                }
-                continue;
-            }
-            ObjSectionKind::Code => (),
-        }
+    writeln!(out, "{}", "-".repeat(name_width + 51))?;
+    let layout_units = report.units.iter().filter(|u| u.layout_ok.is_some()).count();
+    let layout_ok_units = report.units.iter().filter(|u| u.layout_ok == Some(true)).count();
+    writeln!(
+        out,
+        "{:<name_width$}  {:>7.2}%  {:>7.2}%  {:>7.2}%  {:>11}  {:>6}",
+        "Total",
+        report.fuzzy_match_percent,
+        report.matched_code_percent,
+        report.matched_data_percent,
+        format!("{}/{}", report.matched_functions, report.total_functions),
+        format!("{layout_ok_units}/{layout_units}"),
+    )?;
+    Ok(())
+}
 
-        for (symbol, symbol_diff) in section.symbols.iter().zip(&section_diff.symbols) {
-            if symbol.size == 0 {
-                continue;
-            }
-            if let Some(existing_functions) = &mut existing_functions {
-                if (symbol.flags.0.contains(ObjSymbolFlags::Global)
-                    || symbol.flags.0.contains(ObjSymbolFlags::Weak))
-                    && !existing_functions.insert(symbol.name.clone())
-                {
-                    continue;
-                }
-            }
-            let match_percent = symbol_diff.match_percent.unwrap_or_else(|| {
-                // Support cases where we don't have a target object,
-                // assume complete means 100% match
-                if object.complete == Some(true) {
-                    100.0
-                } else {
-                    0.0
-                }
-            });
-            unit.fuzzy_match_percent += match_percent * symbol.size as f32;
-            unit.total_code += symbol.size;
-            if match_percent == 100.0 {
-                unit.matched_code += symbol.size;
-            }
-            unit.functions.push(ReportItem {
-                name: symbol.name.clone(),
-                demangled_name: symbol.demangled_name.clone(),
-                size: symbol.size,
-                fuzzy_match_percent: match_percent,
-                address: symbol.virtual_address,
-            });
-            if match_percent == 100.0 {
-                unit.matched_functions += 1;
-            }
-            unit.total_functions += 1;
-        }
-    }
-    if unit.total_code == 0 {
-        unit.fuzzy_match_percent = 100.0;
+#[inline]
+fn percent(matched: u64, total: u64) -> f32 {
+    if total == 0 {
+        100.0
     } else {
-        unit.fuzzy_match_percent /= unit.total_code as f32;
+        matched as f32 / total as f32 * 100.0
     }
-    Ok(Some(unit))
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
@@ -533,31 +562,48 @@ fn process_new_items(items: &[ReportItem]) -> Vec<ChangeItem> {
         .collect()
 }
 
-fn read_report(path: &Path) -> Result<Report> {
-    serde_json::from_reader(BufReader::new(
-        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?,
-    ))
-    .with_context(|| format!("Failed to read report {}", path.display()))
+fn upload(args: UploadArgs) -> Result<()> {
+    let report = read_report(&args.report)?;
+    let (git_hash, timestamp) = match (args.git_hash, args.timestamp) {
+        (Some(hash), Some(timestamp)) => (hash, timestamp),
+        (hash, timestamp) => {
+            let (head_hash, head_timestamp) = git_head()?;
+            (hash.unwrap_or(head_hash), timestamp.unwrap_or(head_timestamp))
+        }
+    };
+    let entry = FrogressEntry::from_report(&report, timestamp, git_hash, &args.default_category);
+    if args.dry_run {
+        serde_json::to_writer_pretty(std::io::stdout(), &entry)?;
+        return Ok(());
+    }
+    let api_key = match args.api_key {
+        Some(key) => key,
+        None => std::env::var("FROGRESS_API_KEY")
+            .context("No API key provided (use --api-key or FROGRESS_API_KEY)")?,
+    };
+    info!("Uploading progress for {} to {}", entry.git_hash, args.url);
+    frogress::upload(&args.url, &args.project, &args.version, &api_key, &[entry])?;
+    info!("Upload complete");
+    Ok(())
 }
 
-fn serialize_hex<S>(x: &Option<u64>, s: S) -> Result<S::Ok, S::Error>
-where S: serde::Serializer {
-    if let Some(x) = x {
-        s.serialize_str(&format!("{:#x}", x))
-    } else {
-        s.serialize_none()
+/// Returns the commit hash and commit timestamp of HEAD.
+fn git_head() -> Result<(String, u64)> {
+    let output = std::process::Command::new("git")
+        .args(["log", "-1", "--format=%H %ct"])
+        .output()
+        .context("Failed to execute git")?;
+    if !output.status.success() {
+        bail!("git log failed: {}", String::from_utf8_lossy(&output.stderr).trim());
     }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some((hash, timestamp)) = stdout.trim().split_once(' ') else {
+        bail!("Unexpected git log output: {}", stdout.trim());
+    };
+    Ok((hash.to_string(), timestamp.parse().context("Invalid commit timestamp")?))
 }
 
-fn deserialize_hex<'de, D>(d: D) -> Result<Option<u64>, D::Error>
-where D: serde::Deserializer<'de> {
-    use serde::Deserialize;
-    let s = String::deserialize(d)?;
-    if s.is_empty() {
-        Ok(None)
-    } else if !s.starts_with("0x") {
-        Err(serde::de::Error::custom("expected hex string"))
-    } else {
-        u64::from_str_radix(&s[2..], 16).map(Some).map_err(serde::de::Error::custom)
-    }
+pub(crate) fn read_report(path: &Path) -> Result<Report> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    Report::parse(&data).with_context(|| format!("Failed to read report {}", path.display()))
 }
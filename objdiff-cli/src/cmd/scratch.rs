@@ -0,0 +1,107 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use argp::FromArgs;
+use objdiff_core::{
+    config::expand_archive_objects,
+    obj,
+    scratch::{create_scratch, CreateScratchConfig},
+};
+use tracing::info;
+
+use crate::cmd::diff::run_make;
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Create a decomp.me scratch for a function.
+#[argp(subcommand, name = "scratch")]
+pub struct Args {
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+    #[argp(option, short = 'u')]
+    /// Unit name within project
+    unit: Option<String>,
+    #[argp(positional)]
+    /// Function symbol to create the scratch for
+    symbol: String,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let project_dir = match &args.project {
+        Some(project) => project.clone(),
+        None => std::env::current_dir().context("Failed to get the current directory")?,
+    };
+    let Some((project_config, project_config_info)) =
+        objdiff_core::config::try_project_config(&project_dir)
+    else {
+        bail!("Project config not found in {}", project_dir.display())
+    };
+    let mut project_config = project_config.with_context(|| {
+        format!("Reading project config {}", project_config_info.path.display())
+    })?;
+    project_config.objects = expand_archive_objects(
+        std::mem::take(&mut project_config.objects),
+        &project_dir,
+        project_config.target_dir.as_deref(),
+        project_config.base_dir.as_deref(),
+    );
+
+    let mut found = None;
+    for (i, object) in project_config.objects.iter_mut().enumerate() {
+        object.resolve_paths(
+            &project_dir,
+            project_config.target_dir.as_deref(),
+            project_config.base_dir.as_deref(),
+        );
+        let matches = match &args.unit {
+            Some(unit) => object.name() == unit,
+            None => object
+                .target_path
+                .as_deref()
+                .map(|p| obj::read::has_function(p, &args.symbol))
+                .transpose()?
+                .unwrap_or(false),
+        };
+        if matches {
+            if found.is_some() {
+                bail!("Multiple instances of {} were found, try specifying a unit", &args.symbol);
+            }
+            found = Some(i);
+        }
+    }
+    let Some(object) = found.map(|i| &project_config.objects[i]) else {
+        match &args.unit {
+            Some(unit) => bail!("Unit not found: {}", unit),
+            None => bail!("Symbol not found: {}", &args.symbol),
+        }
+    };
+    let Some(target_path) = &object.target_path else {
+        bail!("No target path for {}", object.name());
+    };
+    let Some(scratch_config) = &object.scratch else {
+        bail!("No scratch configuration for {}", object.name());
+    };
+
+    let mut context = None;
+    if let Some(ctx_path) = &scratch_config.ctx_path {
+        let ctx_path = project_dir.join(ctx_path);
+        if scratch_config.build_ctx {
+            info!("Building context {}", ctx_path.display());
+            run_make(&project_config, &project_dir, object.build.as_ref(), &ctx_path)?;
+        }
+        context = Some(
+            fs::read_to_string(&ctx_path)
+                .with_context(|| format!("Failed to read {}", ctx_path.display()))?,
+        );
+    }
+
+    info!("Creating scratch for {}", &args.symbol);
+    let result = create_scratch(&CreateScratchConfig::new(
+        scratch_config,
+        target_path.clone(),
+        args.symbol.clone(),
+        context,
+    ))?;
+    println!("{}", result.scratch_url);
+    Ok(())
+}
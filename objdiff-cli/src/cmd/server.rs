@@ -0,0 +1,549 @@
+use std::{
+    io::{stdin, stdout, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    ops::Range,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use argp::FromArgs;
+use objdiff_core::{
+    config::{build_globset, expand_archive_objects, ProjectConfig, ProjectObject},
+    diff::{
+        display::{
+            arg_diff_legend, display_rows, function_pairs, DiffText, DisplayRow, SymbolDiffRef,
+        },
+        frame::{compare_frames, symbol_frame},
+        signature::{compare_signatures, symbol_signature},
+        theme::TokenKind,
+        xref::{diff_references, SymbolReference},
+        DiffObjConfig, NumberFormat, ObjDiff, ObjInsArgDiffKind, ObjInsDiff, ObjInsDiffKind,
+        RelocDiffKind,
+    },
+    obj::{ObjInfo, ObjSectionKind, ObjSymbolFlags},
+    project::{CancelToken, ProjectDiffer, UnitDiff},
+};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::cmd::diff::{build_object, create_watcher};
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Serve project diff results to editor integrations.
+#[argp(subcommand, name = "server")]
+pub struct Args {
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+    #[argp(option, default = "0")]
+    /// TCP port to listen on, bound to localhost (default: any free port)
+    port: u16,
+    #[argp(switch)]
+    /// Serve a single client over stdin/stdout instead of a socket
+    stdio: bool,
+}
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const SERVER_ERROR: i64 = -32000;
+
+struct Server {
+    project_config: ProjectConfig,
+    project_dir: PathBuf,
+    /// Units with resolved paths
+    objects: Vec<ProjectObject>,
+    diff_config: DiffObjConfig,
+    differ: ProjectDiffer,
+    /// Connections subscribed to change notifications, by connection ID
+    subscribers: Mutex<Vec<(usize, mpsc::Sender<String>)>>,
+    next_connection: AtomicUsize,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let project_dir = match args.project {
+        Some(project) => project,
+        None => std::env::current_dir().context("Failed to get the current directory")?,
+    };
+    let Some((project_config, _)) = objdiff_core::config::try_project_config(&project_dir) else {
+        bail!("Project config not found in {}", project_dir.display())
+    };
+    let mut project_config = project_config
+        .with_context(|| format!("Reading project config {}", project_dir.display()))?;
+    let objects = expand_archive_objects(
+        std::mem::take(&mut project_config.objects),
+        &project_dir,
+        project_config.target_dir.as_deref(),
+        project_config.base_dir.as_deref(),
+    )
+    .into_iter()
+    .map(|mut object| {
+        object.resolve_paths(
+            &project_dir,
+            project_config.target_dir.as_deref(),
+            project_config.base_dir.as_deref(),
+        );
+        object
+    })
+    .collect();
+    let diff_config = DiffObjConfig {
+        mips_instr_category: project_config.mips_instr_category.unwrap_or_default(),
+        number_format: project_config.number_format.unwrap_or_default(),
+        idioms: project_config.idioms.clone().unwrap_or_default(),
+        ..Default::default()
+    };
+
+    // Notify subscribers when watched project files change
+    let modified = Arc::new(AtomicBool::new(false));
+    let patterns = match &project_config.watch_patterns {
+        Some(patterns) => patterns.clone(),
+        None => objdiff_core::config::DEFAULT_WATCH_PATTERNS
+            .iter()
+            .map(|s| globset::Glob::new(s).unwrap())
+            .collect(),
+    };
+    let globset = build_globset(&patterns).context("Failed to build watch patterns")?;
+    let _watcher = create_watcher(modified.clone(), &project_dir, globset)?;
+
+    let server = Arc::new(Server {
+        project_config,
+        project_dir,
+        objects,
+        diff_config,
+        differ: ProjectDiffer::new(),
+        subscribers: Mutex::new(Vec::new()),
+        next_connection: AtomicUsize::new(0),
+    });
+    {
+        let server = server.clone();
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_millis(250));
+            if modified.swap(false, Ordering::Relaxed) {
+                server.notify("changed", json!({}));
+            }
+        });
+    }
+
+    if args.stdio {
+        return serve(&server, BufReader::new(stdin()), stdout());
+    }
+    let listener = TcpListener::bind(("127.0.0.1", args.port))
+        .with_context(|| format!("Failed to bind to port {}", args.port))?;
+    // Printed for clients that spawn the server and connect to the chosen port
+    println!("{}", json!({ "listening": listener.local_addr()?.to_string() }));
+    info!("Listening on {}", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to accept connection: {e}");
+                continue;
+            }
+        };
+        let server = server.clone();
+        thread::spawn(move || {
+            if let Err(e) = serve_tcp(&server, stream) {
+                warn!("Connection closed: {e:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn serve_tcp(server: &Server, stream: TcpStream) -> Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    serve(server, reader, stream)
+}
+
+/// Handle newline-delimited JSON-RPC requests until the client disconnects.
+fn serve<R: BufRead, W: Write + Send + 'static>(
+    server: &Server,
+    reader: R,
+    mut writer: W,
+) -> Result<()> {
+    let connection = server.next_connection.fetch_add(1, Ordering::Relaxed);
+    // Responses and notifications are written from a single thread
+    let (tx, rx) = mpsc::channel::<String>();
+    let writer_thread = thread::spawn(move || {
+        for message in rx {
+            if writeln!(writer, "{message}").and_then(|_| writer.flush()).is_err() {
+                break;
+            }
+        }
+    });
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_message(server, connection, &line, &tx) {
+            if tx.send(response.to_string()).is_err() {
+                break;
+            }
+        }
+    }
+    server.subscribers.lock().unwrap().retain(|(id, _)| *id != connection);
+    drop(tx);
+    let _ = writer_thread.join();
+    Ok(())
+}
+
+fn error_response(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message.into() } })
+}
+
+/// Handle a single request, returning the response. Notifications have no response.
+fn handle_message(
+    server: &Server,
+    connection: usize,
+    line: &str,
+    tx: &mpsc::Sender<String>,
+) -> Option<Value> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+    };
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return Some(error_response(id.unwrap_or(Value::Null), INVALID_REQUEST, "Missing method"));
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let result = match method {
+        "units" => Ok(server.units()),
+        "symbols" => server.symbols(&params),
+        "functionDiff" => server.function_diff(&params),
+        "references" => server.references(&params),
+        "build" => server.build(&params),
+        "subscribe" => {
+            server.subscribers.lock().unwrap().push((connection, tx.clone()));
+            Ok(Value::Null)
+        }
+        _ => {
+            return id
+                .map(|id| error_response(id, METHOD_NOT_FOUND, format!("Unknown method {method}")))
+        }
+    };
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => error_response(id, SERVER_ERROR, format!("{e:#}")),
+    })
+}
+
+fn str_param<'a>(params: &'a Value, name: &str) -> Result<&'a str> {
+    params.get(name).and_then(Value::as_str).ok_or_else(|| anyhow!("Missing parameter {name}"))
+}
+
+impl Server {
+    /// Send a notification to all subscribed connections, dropping closed ones.
+    fn notify(&self, method: &str, params: Value) {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params }).to_string();
+        self.subscribers.lock().unwrap().retain(|(_, tx)| tx.send(message.clone()).is_ok());
+    }
+
+    fn object(&self, name: &str) -> Result<&ProjectObject> {
+        self.objects.iter().find(|o| o.name() == name).ok_or_else(|| anyhow!("Unknown unit {name}"))
+    }
+
+    fn diff_unit(&self, name: &str) -> Result<Arc<UnitDiff>> {
+        let object = self.object(name)?;
+        let demangler = self.project_config.demangler.unwrap_or_default();
+        self.differ
+            .diff_units(
+                std::slice::from_ref(object),
+                &self.diff_config,
+                demangler,
+                1,
+                |_| {},
+                &CancelToken::default(),
+            )?
+            .pop()
+            .unwrap()
+    }
+
+    fn units(&self) -> Value {
+        self.objects
+            .iter()
+            .map(|o| {
+                json!({
+                    "name": o.name(),
+                    "target_path": o.target_path,
+                    "base_path": o.base_path,
+                    "complete": o.complete,
+                })
+            })
+            .collect()
+    }
+
+    fn symbols(&self, params: &Value) -> Result<Value> {
+        let unit = self.diff_unit(str_param(params, "unit")?)?;
+        Ok(json!({
+            "match_percent": unit.match_percent(),
+            "target": symbols_json(unit.target.as_ref().zip(unit.diff.left.as_ref())),
+            "base": symbols_json(unit.base.as_ref().zip(unit.diff.right.as_ref())),
+        }))
+    }
+
+    fn function_diff(&self, params: &Value) -> Result<Value> {
+        let unit = self.diff_unit(str_param(params, "unit")?)?;
+        let symbol = str_param(params, "symbol")?;
+        // Optional page of rows, for very large functions
+        let start = params.get("start").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let count = params.get("count").and_then(Value::as_u64).map_or(usize::MAX, |c| c as usize);
+        let rows = start..start.saturating_add(count);
+        let left = unit.target.as_ref().zip(unit.diff.left.as_ref());
+        let right = unit.base.as_ref().zip(unit.diff.right.as_ref());
+        let is_symbol =
+            |side: Option<SymbolDiffRef>| side.is_some_and(|(_, s, _)| s.name == symbol);
+        let Some((left, right)) =
+            function_pairs(left, right).into_iter().find(|&(l, r)| is_symbol(l) || is_symbol(r))
+        else {
+            bail!("Function {symbol} not found");
+        };
+        let match_percent = left.or(right).and_then(|(_, _, d)| d.match_percent);
+        let legend = match (left, right) {
+            (Some((_, _, l)), Some((_, _, r))) => arg_diff_legend(l, r)
+                .into_iter()
+                .map(|e| json!({ "index": e.idx, "target": e.left, "base": e.right }))
+                .collect(),
+            _ => vec![],
+        };
+        let frame: Vec<String> = match (left, right) {
+            (Some((lo, _, l)), Some((ro, _, r))) => symbol_frame(lo, l)
+                .zip(symbol_frame(ro, r))
+                .map(|(l, r)| compare_frames(&l, &r).iter().map(|d| d.to_string()).collect())
+                .unwrap_or_default(),
+            _ => vec![],
+        };
+        let signature: Vec<String> = match (left, right) {
+            (Some((lo, _, l)), Some((ro, _, r))) => symbol_signature(lo, l)
+                .zip(symbol_signature(ro, r))
+                .map(|(l, r)| compare_signatures(&l, &r).iter().map(|d| d.to_string()).collect())
+                .unwrap_or_default(),
+            _ => vec![],
+        };
+        Ok(json!({
+            "match_percent": match_percent,
+            "target": left.map(|l| function_json(l, self.diff_config.number_format, rows.clone())),
+            "base": right.map(|r| function_json(r, self.diff_config.number_format, rows.clone())),
+            "legend": legend,
+            "frame": frame,
+            "signature": signature,
+        }))
+    }
+
+    /// List the references to a symbol in both objects of a unit, matched by the symbol
+    /// containing them.
+    fn references(&self, params: &Value) -> Result<Value> {
+        let unit = self.diff_unit(str_param(params, "unit")?)?;
+        let symbol = str_param(params, "symbol")?;
+        let diff = diff_references(unit.target.as_ref(), unit.base.as_ref(), symbol);
+        let refs = |obj: Option<&ObjInfo>, refs: &[SymbolReference]| {
+            refs.iter().map(|r| reference_json(obj, r)).collect::<Vec<_>>()
+        };
+        let common = diff
+            .common
+            .iter()
+            .map(|(l, r)| {
+                json!({
+                    "target": reference_json(unit.target.as_ref(), l),
+                    "base": reference_json(unit.base.as_ref(), r),
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(json!({
+            "common": common,
+            "target_only": refs(unit.target.as_ref(), &diff.left_only),
+            "base_only": refs(unit.base.as_ref(), &diff.right_only),
+        }))
+    }
+
+    /// Build a unit's objects with the project's build command, returning the parsed
+    /// compiler diagnostics of the first failing build.
+    fn build(&self, params: &Value) -> Result<Value> {
+        let object = self.object(str_param(params, "unit")?)?;
+        let mut paths = vec![];
+        if self.project_config.build_target {
+            paths.extend(object.target_path.as_deref());
+        }
+        if self.project_config.build_base {
+            paths.extend(object.base_path.as_deref());
+        }
+        for path in paths {
+            let status =
+                build_object(&self.project_config, &self.project_dir, object.build.as_ref(), path);
+            if !status.success {
+                return Ok(json!({
+                    "success": false,
+                    "cmdline": status.cmdline,
+                    "stdout": status.stdout,
+                    "stderr": status.stderr,
+                    "errors": status.errors,
+                }));
+            }
+        }
+        self.notify("built", json!({ "unit": object.name() }));
+        Ok(json!({ "success": true, "errors": [] }))
+    }
+}
+
+fn symbols_json(side: Option<(&ObjInfo, &ObjDiff)>) -> Value {
+    let Some((obj, diff)) = side else {
+        return Value::Null;
+    };
+    let mut out = Vec::new();
+    for (section, section_diff) in obj.sections.iter().zip(&diff.sections) {
+        let kind = match section.kind {
+            ObjSectionKind::Code => "code",
+            ObjSectionKind::Data => "data",
+            ObjSectionKind::Bss => "bss",
+        };
+        for (symbol, symbol_diff) in section.symbols.iter().zip(&section_diff.symbols) {
+            out.push(json!({
+                "name": symbol.name,
+                "demangled_name": symbol.demangled_name,
+                "section": section.name,
+                "kind": kind,
+                "address": symbol.address,
+                "size": symbol.size,
+                "global": symbol.flags.0.contains(ObjSymbolFlags::Global),
+                "weak": symbol.flags.0.contains(ObjSymbolFlags::Weak),
+                "common": symbol.flags.0.contains(ObjSymbolFlags::Common),
+                "weak_mismatch": symbol_diff.weak_mismatch,
+                "match_percent": symbol_diff.match_percent,
+            }));
+        }
+    }
+    Value::Array(out)
+}
+
+fn reference_json(obj: Option<&ObjInfo>, reference: &SymbolReference) -> Value {
+    json!({
+        "section": obj.map(|o| o.sections[reference.section_idx].name.as_str()),
+        "symbol": reference.symbol_name,
+        "offset": reference.offset,
+        "address": reference.reloc.address,
+        "kind": match reference.kind {
+            ObjSectionKind::Code => "code",
+            ObjSectionKind::Data => "data",
+            ObjSectionKind::Bss => "bss",
+        },
+        "reloc": obj.map(|o| o.arch.display_reloc(reference.reloc.flags).into_owned()),
+    })
+}
+
+fn function_json(
+    (_, symbol, symbol_diff): SymbolDiffRef,
+    number_format: NumberFormat,
+    rows: Range<usize>,
+) -> Value {
+    let rows = display_rows(&symbol_diff.instructions, symbol.address, rows)
+        .map(|row| {
+            json!({
+                "kind": ins_kind_name(row.ins_diff),
+                "padding": row.ins_diff.padding,
+                "events": ins_events(row, number_format),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "name": symbol.name,
+        "demangled_name": symbol.demangled_name,
+        "total_rows": symbol_diff.instructions.len(),
+        "rows": rows,
+    })
+}
+
+fn ins_kind_name(ins_diff: &ObjInsDiff) -> &'static str {
+    if ins_diff.accepted {
+        return "accepted";
+    }
+    match ins_diff.kind {
+        ObjInsDiffKind::None => "none",
+        ObjInsDiffKind::OpMismatch => "op_mismatch",
+        ObjInsDiffKind::ArgMismatch => "arg_mismatch",
+        ObjInsDiffKind::Replace => "replace",
+        ObjInsDiffKind::Delete => "delete",
+        ObjInsDiffKind::Insert => "insert",
+    }
+}
+
+fn reloc_diff_name(kind: RelocDiffKind) -> &'static str {
+    match kind {
+        RelocDiffKind::SameSymbol => "same_symbol",
+        RelocDiffKind::SameAddress => "same_address",
+        RelocDiffKind::SameValue => "same_value",
+        RelocDiffKind::Mismatch => "mismatch",
+    }
+}
+
+/// The [`DiffText`] events of an instruction row, in display order.
+fn ins_events(row: DisplayRow, number_format: NumberFormat) -> Vec<Value> {
+    let mut events = Vec::new();
+    row.display(|text| {
+        events.push(match text {
+            DiffText::Basic(text) => json!({ "type": "basic", "text": text }),
+            DiffText::Styled(text, TokenKind::Rotation(idx)) => {
+                json!({ "type": "basic_color", "text": text, "color": idx })
+            }
+            DiffText::Styled(text, _) => json!({ "type": "basic", "text": text }),
+            DiffText::Line(line) => json!({ "type": "line", "line": line }),
+            DiffText::Address(address) => json!({ "type": "address", "address": address }),
+            DiffText::Encoding(encoding) => json!({
+                "type": "encoding",
+                "bytes": encoding.bytes,
+                "mismatched": (0..encoding.bytes.len())
+                    .filter(|&i| encoding.byte_mismatch(i))
+                    .collect::<Vec<_>>(),
+            }),
+            DiffText::Opcode(mnemonic, op) => {
+                json!({ "type": "opcode", "mnemonic": mnemonic, "op": op })
+            }
+            DiffText::Argument(arg, diff) => json!({
+                "type": "argument",
+                "text": arg.display(number_format).to_string(),
+                "diff": diff.map(|d| json!({
+                    "index": d.idx,
+                    "kind": match d.kind {
+                        ObjInsArgDiffKind::Mismatch => "mismatch",
+                        ObjInsArgDiffKind::RegisterSwap => "register_swap",
+                    },
+                    "reloc": d.reloc.map(reloc_diff_name),
+                })),
+            }),
+            DiffText::BranchDest(address, label) => {
+                json!({ "type": "branch_dest", "address": address, "label": label })
+            }
+            DiffText::Label(label, idx) => json!({ "type": "label", "label": label, "color": idx }),
+            DiffText::Bytes(bytes, diff) => json!({
+                "type": "bytes",
+                "bytes": bytes,
+                "mismatched": (0..bytes.len())
+                    .filter(|&i| diff.is_some_and(|d| d.byte_mismatch(i)))
+                    .collect::<Vec<_>>(),
+            }),
+            DiffText::Moved(block) => json!({ "type": "moved", "block": block }),
+            DiffText::Accepted => json!({ "type": "accepted" }),
+            DiffText::Symbol(symbol, reloc_diff, callee_match) => json!({
+                "type": "symbol",
+                "name": symbol.name,
+                "demangled_name": symbol.demangled_name,
+                "reloc_diff": reloc_diff.map(reloc_diff_name),
+                "callee_match": callee_match,
+            }),
+            DiffText::Comment(text) => json!({ "type": "comment", "text": text }),
+            DiffText::Spacing(count) => json!({ "type": "spacing", "count": count }),
+            DiffText::Eol => json!({ "type": "eol" }),
+        });
+        Ok::<_, ()>(())
+    })
+    .unwrap();
+    events
+}
@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use argp::FromArgs;
+use objdiff_core::{
+    config::{expand_archive_objects, try_project_config, ProjectConfig, ProjectObject},
+    session::{compare_results, export_session, open_session, unit_results, SESSION_EXTENSION},
+};
+use tracing::{info, warn};
+
+use crate::cmd::diff;
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Export the diff of a unit to a session bundle, or open a bundle read-only.
+#[argp(subcommand, name = "session")]
+pub struct Args {
+    #[argp(subcommand)]
+    command: SubCommand,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argp(subcommand)]
+pub enum SubCommand {
+    Export(ExportArgs),
+    Open(OpenArgs),
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Bundle a unit's objects, diff settings, results and notes into a single file.
+#[argp(subcommand, name = "export")]
+pub struct ExportArgs {
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+    #[argp(option, short = 'o')]
+    /// Output file (defaults to <unit>.objdiff-session)
+    output: Option<PathBuf>,
+    #[argp(positional)]
+    /// Unit name within project
+    unit: String,
+    #[argp(positional)]
+    /// Function to open the session at
+    symbol: Option<String>,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Extract a session bundle and open its diff.
+#[argp(subcommand, name = "open")]
+pub struct OpenArgs {
+    #[argp(positional)]
+    /// Session bundle
+    path: PathBuf,
+    #[argp(option, short = 'o')]
+    /// Directory to extract the session to (defaults to a temporary directory)
+    output: Option<PathBuf>,
+    #[argp(switch)]
+    /// Only extract the session, to open the directory as a project in the GUI
+    extract_only: bool,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    match args.command {
+        SubCommand::Export(args) => export(args),
+        SubCommand::Open(args) => open(args),
+    }
+}
+
+/// Load a project and find a unit in it, with its paths resolved.
+fn find_unit(project_dir: &Path, unit: &str) -> Result<(ProjectConfig, ProjectObject)> {
+    let Some((project_config, project_config_info)) = try_project_config(project_dir) else {
+        bail!("Project config not found in {}", project_dir.display())
+    };
+    let mut project_config = project_config.with_context(|| {
+        format!("Reading project config {}", project_config_info.path.display())
+    })?;
+    project_config.objects = expand_archive_objects(
+        std::mem::take(&mut project_config.objects),
+        project_dir,
+        project_config.target_dir.as_deref(),
+        project_config.base_dir.as_deref(),
+    );
+    let Some(mut object) = project_config.objects.iter().find(|o| o.name() == unit).cloned() else {
+        bail!("Unit not found: {unit}")
+    };
+    object.resolve_paths(
+        project_dir,
+        project_config.target_dir.as_deref(),
+        project_config.base_dir.as_deref(),
+    );
+    Ok((project_config, object))
+}
+
+fn export(args: ExportArgs) -> Result<()> {
+    let project_dir = match &args.project {
+        Some(project) => project.clone(),
+        None => std::env::current_dir().context("Failed to get the current directory")?,
+    };
+    let (project_config, object) = find_unit(&project_dir, &args.unit)?;
+    let output = args.output.unwrap_or_else(|| {
+        let name = args.unit.rsplit(['/', '\\']).next().unwrap_or(&args.unit);
+        PathBuf::from(format!("{name}.{SESSION_EXTENSION}"))
+    });
+    let manifest =
+        export_session(&project_dir, &project_config, &object, args.symbol.as_deref(), &output)?;
+    info!(
+        "Exported {} ({} functions, {} files) to {}",
+        manifest.unit,
+        manifest.results.len(),
+        manifest.files.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn open(args: OpenArgs) -> Result<()> {
+    let dir = match args.output {
+        Some(dir) => dir,
+        None => {
+            let stem = args.path.file_stem().and_then(|s| s.to_str()).unwrap_or("session");
+            std::env::temp_dir().join(format!("objdiff-session-{stem}"))
+        }
+    };
+    let manifest = open_session(&args.path, &dir)?;
+    info!(
+        "Extracted session of {} (exported by objdiff {}) to {}",
+        manifest.unit,
+        manifest.objdiff_version,
+        dir.display()
+    );
+    let (project_config, object) = find_unit(&dir, &manifest.unit)?;
+    let current = unit_results(&project_config, &object)?;
+    for (name, recorded, now) in compare_results(&manifest, &current) {
+        let show = |p: Option<f32>| p.map_or("missing".to_string(), |p| format!("{p:.2}%"));
+        warn!("{name}: {} when exported, {} now", show(recorded), show(now));
+    }
+    if args.extract_only {
+        return Ok(());
+    }
+    diff::run(diff::Args::for_unit(dir, manifest.unit, manifest.symbol))
+}
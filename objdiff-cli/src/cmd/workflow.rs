@@ -0,0 +1,180 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use argp::FromArgs;
+use objdiff_core::{
+    config::try_project_config,
+    workflow::{workflow_path, SymbolWorkflow, WorkflowState, WorkflowStatus},
+};
+use tracing::info;
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Mark functions as matched, in progress or needing review in the shared workflow state.
+#[argp(subcommand, name = "workflow")]
+pub struct Args {
+    #[argp(option, short = 'p')]
+    /// Project directory
+    project: Option<PathBuf>,
+    #[argp(subcommand)]
+    command: SubCommand,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argp(subcommand)]
+pub enum SubCommand {
+    Set(SetArgs),
+    Clear(ClearArgs),
+    Note(NoteArgs),
+    List(ListArgs),
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Set the workflow status of a function.
+#[argp(subcommand, name = "set")]
+pub struct SetArgs {
+    #[argp(positional)]
+    /// Unit name within project
+    unit: String,
+    #[argp(positional)]
+    /// Symbol name
+    symbol: String,
+    #[argp(positional)]
+    /// Status (not-started, in-progress, needs-review, matched)
+    status: WorkflowStatus,
+    #[argp(option, short = 'a')]
+    /// Who is working on the function
+    assignee: Option<String>,
+    #[argp(option)]
+    /// Free-form note
+    note: Option<String>,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Reset a function to not started, removing its assignee and note.
+#[argp(subcommand, name = "clear")]
+pub struct ClearArgs {
+    #[argp(positional)]
+    /// Unit name within project
+    unit: String,
+    #[argp(positional)]
+    /// Symbol name
+    symbol: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Attach a note to an instruction, shown inline in diffs. Omit the text to remove it.
+#[argp(subcommand, name = "note")]
+pub struct NoteArgs {
+    #[argp(positional)]
+    /// Unit name within project
+    unit: String,
+    #[argp(positional)]
+    /// Symbol name
+    symbol: String,
+    #[argp(positional, from_str_fn(parse_offset))]
+    /// Offset of the instruction from the start of the function (hex with 0x)
+    offset: u64,
+    #[argp(positional)]
+    /// Note text
+    text: Option<String>,
+}
+
+fn parse_offset(s: &str) -> Result<u64, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|e| format!("Invalid offset {s}: {e}"))
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// List the marked functions.
+#[argp(subcommand, name = "list")]
+pub struct ListArgs {
+    #[argp(option, short = 'u')]
+    /// Only list functions of this unit
+    unit: Option<String>,
+    #[argp(option, short = 's')]
+    /// Only list functions with this status
+    status: Option<WorkflowStatus>,
+    #[argp(option, short = 'a')]
+    /// Only list functions assigned to this person
+    assignee: Option<String>,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let project_dir = args.project.as_deref().unwrap_or_else(|| Path::new("."));
+    let path = project_workflow_path(project_dir)?;
+    let mut state = WorkflowState::load(&path)?;
+    match args.command {
+        SubCommand::Set(args) => {
+            let mut workflow = state.get(&args.unit, &args.symbol).cloned().unwrap_or_default();
+            workflow.status = args.status;
+            if args.assignee.is_some() {
+                workflow.assignee = args.assignee.filter(|a| !a.is_empty());
+            }
+            if args.note.is_some() {
+                workflow.note = args.note.filter(|n| !n.is_empty());
+            }
+            state.set(&args.unit, &args.symbol, workflow);
+            state.save(&path)?;
+            info!("Marked {} as {}", args.symbol, args.status.name());
+        }
+        SubCommand::Clear(args) => {
+            state.clear(&args.unit, &args.symbol);
+            state.save(&path)?;
+        }
+        SubCommand::Note(args) => {
+            let text = args.text.unwrap_or_default();
+            state.set_annotation(&args.unit, &args.symbol, args.offset, &text);
+            state.save(&path)?;
+        }
+        SubCommand::List(args) => list(&state, &args)?,
+    }
+    Ok(())
+}
+
+/// Resolve the workflow state file from the project config, if there is one.
+pub(crate) fn project_workflow_path(project_dir: &Path) -> Result<PathBuf> {
+    let configured = match try_project_config(project_dir) {
+        Some((config, info)) => {
+            config
+                .with_context(|| format!("Reading project config {}", info.path.display()))?
+                .workflow_path
+        }
+        None => None,
+    };
+    Ok(workflow_path(project_dir, configured.as_deref()))
+}
+
+fn list(state: &WorkflowState, args: &ListArgs) -> Result<()> {
+    let matches = |unit: &str, workflow: &SymbolWorkflow| {
+        args.unit.as_deref().map_or(true, |u| u == unit)
+            && args.status.map_or(true, |s| s == workflow.status)
+            && args.assignee.as_ref().map_or(true, |a| workflow.assignee.as_ref() == Some(a))
+    };
+    let mut out = std::io::stdout().lock();
+    for (unit, symbols) in &state.units {
+        for (symbol, workflow) in symbols.iter().filter(|(_, w)| matches(unit, w)) {
+            write!(
+                out,
+                "{:<12} {:<16} {} {}",
+                workflow.status.name(),
+                workflow.assignee.as_deref().unwrap_or("-"),
+                unit,
+                symbol
+            )?;
+            if let Some(note) = &workflow.note {
+                write!(out, " # {note}")?;
+            }
+            writeln!(out)?;
+            for (offset, note) in &workflow.annotations {
+                writeln!(out, "    +{offset:#x}: {note}")?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -76,8 +76,18 @@ struct TopLevel {
 #[derive(FromArgs, PartialEq, Debug)]
 #[argp(subcommand)]
 enum SubCommand {
+    Changes(cmd::changes::Args),
+    Check(cmd::check::Args),
     Diff(cmd::diff::Args),
+    Link(cmd::link::Args),
+    List(cmd::list::Args),
+    Objdump(cmd::objdump::Args),
+    Pair(cmd::pair::Args),
     Report(cmd::report::Args),
+    Scratch(cmd::scratch::Args),
+    Server(cmd::server::Args),
+    Session(cmd::session::Args),
+    Workflow(cmd::workflow::Args),
 }
 
 // Duplicated from supports-color so we can check early.
@@ -131,8 +141,18 @@ fn main() {
         });
     }
     result = result.and_then(|_| match args.command {
+        SubCommand::Changes(c_args) => cmd::changes::run(c_args),
+        SubCommand::Check(c_args) => cmd::check::run(c_args),
         SubCommand::Diff(c_args) => cmd::diff::run(c_args),
+        SubCommand::Link(c_args) => cmd::link::run(c_args),
+        SubCommand::List(c_args) => cmd::list::run(c_args),
+        SubCommand::Objdump(c_args) => cmd::objdump::run(c_args),
+        SubCommand::Pair(c_args) => cmd::pair::run(c_args),
         SubCommand::Report(c_args) => cmd::report::run(c_args),
+        SubCommand::Scratch(c_args) => cmd::scratch::run(c_args),
+        SubCommand::Server(c_args) => cmd::server::run(c_args),
+        SubCommand::Session(c_args) => cmd::session::run(c_args),
+        SubCommand::Workflow(c_args) => cmd::workflow::run(c_args),
     });
     if let Err(e) = result {
         eprintln!("Failed: {e:?}");
@@ -0,0 +1,91 @@
+//! Diff benchmarks, with and without the identical function prefilter.
+//!
+//! The `samples` group diffs the snapshot test samples in `tests/snapshots`, each object
+//! against itself and against its counterpart, like a mostly matched project. Run with
+//! `cargo bench -p objdiff-core --features all`. Set `OBJDIFF_BENCH_PROJECT=<project dir>` to
+//! also benchmark a whole project whose objects are already built.
+
+use std::{fs, path::PathBuf};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use objdiff_core::{
+    config::try_project_config,
+    diff::{diff_objs, DiffObjConfig},
+    obj::{read, ObjInfo},
+};
+
+/// Sample object pairs of the enabled architectures: each object paired with itself, and
+/// each target with its base.
+fn load_samples() -> Vec<(ObjInfo, ObjInfo)> {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+    let mut cases = fs::read_dir(root)
+        .unwrap()
+        .flat_map(|arch| fs::read_dir(arch.unwrap().path()).into_iter().flatten())
+        .map(|case| case.unwrap().path())
+        .filter(|case| case.is_dir())
+        .collect::<Vec<_>>();
+    cases.sort();
+    let mut units = Vec::new();
+    for case in cases {
+        let load = |name: &str| read::read(&case.join(name));
+        for (left, right) in
+            [("target.o", "target.o"), ("base.o", "base.o"), ("target.o", "base.o")]
+        {
+            // Objects of disabled architectures fail to load
+            if let (Ok(left), Ok(right)) = (load(left), load(right)) {
+                units.push((left, right));
+            }
+        }
+    }
+    units
+}
+
+fn load_project() -> Option<Vec<(ObjInfo, ObjInfo)>> {
+    let project_dir = std::env::var_os("OBJDIFF_BENCH_PROJECT").map(PathBuf::from)?;
+    let (Ok(project), _) = try_project_config(&project_dir)? else {
+        eprintln!("Failed to load the project configuration, skipping");
+        return None;
+    };
+    let mut units = Vec::new();
+    for mut object in project.objects {
+        object.resolve_paths(
+            &project_dir,
+            project.target_dir.as_deref(),
+            project.base_dir.as_deref(),
+        );
+        let (Some(target_path), Some(base_path)) = (&object.target_path, &object.base_path) else {
+            continue;
+        };
+        if let (Ok(target), Ok(base)) = (read::read(target_path), read::read(base_path)) {
+            units.push((target, base));
+        }
+    }
+    Some(units)
+}
+
+fn bench_units(c: &mut Criterion, group_name: &str, units: &[(ObjInfo, ObjInfo)]) {
+    let mut group = c.benchmark_group(group_name);
+    group.sample_size(10);
+    for (name, prefilter) in [("prefilter", true), ("full", false)] {
+        let config = DiffObjConfig { relax_reloc_diffs: true, prefilter, ..Default::default() };
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for (target, base) in units {
+                    diff_objs(&config, Some(target), Some(base), None).unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_samples(c: &mut Criterion) { bench_units(c, "samples", &load_samples()); }
+
+fn bench_project(c: &mut Criterion) {
+    if let Some(units) = load_project() {
+        bench_units(c, "project", &units);
+    }
+}
+
+criterion_group!(benches, bench_samples, bench_project);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+fn main() {
+    #[cfg(feature = "bindings")]
+    compile_protos();
+}
+
+/// Generate the message types of the protobuf bindings. The schemas are parsed with protox,
+/// so that building doesn't need `protoc`.
+#[cfg(feature = "bindings")]
+fn compile_protos() {
+    println!("cargo:rerun-if-changed=protos");
+    let file_descriptors =
+        protox::compile(["diff.proto"], ["protos"]).expect("Failed to parse the protobuf schemas");
+    prost_build::Config::new()
+        .compile_fds(file_descriptors)
+        .expect("Failed to generate the protobuf bindings");
+}
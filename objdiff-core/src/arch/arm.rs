@@ -0,0 +1,389 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use anyhow::{anyhow, bail, Result};
+use object::{
+    elf, Endian, Endianness, File, Object, ObjectSection, ObjectSymbol, Relocation,
+    RelocationFlags, SectionIndex, SectionKind, Symbol,
+};
+use unarm::{
+    args::{Argument, OffsetImm, OffsetReg, Register},
+    parse::{ArmVersion, ParseMode, Parser},
+    ParsedIns,
+};
+
+use crate::{
+    arch::{bytes_ins, ObjArch, ProcessCodeResult, OP_BYTES},
+    diff::{ArmArchVersion, DiffObjConfig},
+    obj::{ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjSection, SymbolRef},
+};
+
+pub struct ObjArchArm {
+    /// Maps section index to a list of disassembly modes (ARM, Thumb or data) sorted by address
+    disasm_modes: HashMap<SectionIndex, Vec<DisasmMode>>,
+    endianness: Endianness,
+}
+
+impl ObjArchArm {
+    pub fn new(file: &File) -> Result<Self> {
+        match file {
+            File::Elf32(_) => {
+                let disasm_modes = elf_mapping_symbols(file);
+                Ok(Self { disasm_modes, endianness: file.endianness() })
+            }
+            _ => bail!("Unsupported ARM file format {:?}", file.format()),
+        }
+    }
+}
+
+/// Collects the mapping symbols of each code section.
+/// `$a` marks the start of ARM code, `$t` Thumb code and `$d` data.
+fn elf_mapping_symbols(file: &File) -> HashMap<SectionIndex, Vec<DisasmMode>> {
+    file.sections()
+        .filter(|s| s.kind() == SectionKind::Text)
+        .map(|section| {
+            let index = section.index();
+            let mut modes: Vec<DisasmMode> = file
+                .symbols()
+                .filter(|s| s.section_index() == Some(index))
+                .filter_map(|s| DisasmMode::from_symbol(&s))
+                .collect();
+            modes.sort_unstable_by_key(|m| m.address);
+            (index, modes)
+        })
+        .collect()
+}
+
+impl ObjArch for ObjArchArm {
+    fn process_code(
+        &self,
+        obj: &ObjInfo,
+        symbol_ref: SymbolRef,
+        config: &DiffObjConfig,
+    ) -> Result<ProcessCodeResult> {
+        let (section, symbol) = obj.section_symbol(symbol_ref);
+        let section = section.ok_or_else(|| anyhow!("Code symbol section not found"))?;
+        let code = &section.data
+            [symbol.section_address as usize..(symbol.section_address + symbol.size) as usize];
+
+        let start_addr = symbol.address as u32;
+        let end_addr = start_addr + symbol.size as u32;
+
+        // Objects without mapping symbols are assumed to contain only ARM code
+        let fallback_modes = [DisasmMode { address: start_addr, mode: ParseMode::Arm }];
+        let modes = self
+            .disasm_modes
+            .get(&SectionIndex(section.orig_index))
+            .map(|m| m.as_slice())
+            .filter(|m| !m.is_empty())
+            .unwrap_or(&fallback_modes);
+        let first_mode_idx = match modes.binary_search_by_key(&start_addr, |m| m.address) {
+            Ok(idx) => idx,
+            Err(idx) => idx.saturating_sub(1),
+        };
+        let first_mode = modes[first_mode_idx].mode;
+        let mut modes_iter =
+            modes.iter().skip(first_mode_idx + 1).take_while(|m| m.address < end_addr);
+        let mut next_mode = modes_iter.next();
+
+        let version = match config.arm_arch_version {
+            ArmArchVersion::Auto | ArmArchVersion::V5Te => ArmVersion::V5Te,
+            ArmArchVersion::V4T => ArmVersion::V4T,
+        };
+
+        let ins_count = code.len() / first_mode.instruction_size();
+        let mut ops = Vec::<u16>::with_capacity(ins_count);
+        let mut insts = Vec::<ObjIns>::with_capacity(ins_count);
+        let mut parser = Parser::new(version, first_mode, start_addr, code);
+        while let Some((address, op, ins)) = parser.next() {
+            if let Some(next) = next_mode {
+                if parser.address >= next.address {
+                    // Switch modes for the next instruction
+                    parser.mode = next.mode;
+                    next_mode = modes_iter.next();
+                }
+            }
+            let line = section.line_info.range(..=address as u64).last().map(|(_, &b)| b);
+            let reloc = section.relocations.iter().find(|r| (r.address as u32 & !1) == address);
+
+            let mut reloc_arg = None;
+            if let Some(reloc) = reloc {
+                match reloc.flags {
+                    // Calls
+                    RelocationFlags::Elf {
+                        r_type:
+                            elf::R_ARM_THM_PC22
+                            | elf::R_ARM_THM_XPC22
+                            | elf::R_ARM_PC24
+                            | elf::R_ARM_XPC25
+                            | elf::R_ARM_CALL,
+                    } => {
+                        reloc_arg =
+                            ins.args.iter().rposition(|a| matches!(a, Argument::BranchDest(_)));
+                    }
+                    // Data
+                    RelocationFlags::Elf { r_type: elf::R_ARM_ABS32 } => {
+                        reloc_arg = ins.args.iter().rposition(|a| matches!(a, Argument::UImm(_)));
+                    }
+                    _ => {}
+                }
+            }
+
+            let size = parser.address - address;
+            if ins.mnemonic == "<illegal>" {
+                let offset = (address - start_addr) as usize;
+                let data = &code[offset..offset + size as usize];
+                ops.push(OP_BYTES);
+                insts.push(bytes_ins(address as u64, data, reloc, line));
+                continue;
+            }
+
+            let (args, branch_dest) = if reloc.is_some() && parser.mode == ParseMode::Data {
+                (vec![ObjInsArg::Reloc], None)
+            } else {
+                push_args(&ins, config, reloc_arg, address)?
+            };
+
+            ops.push(op.id());
+            insts.push(ObjIns {
+                address: address as u64,
+                size: size as u8,
+                op: op.id(),
+                mnemonic: ins.mnemonic.to_string(),
+                args,
+                reloc: reloc.cloned(),
+                branch_dest,
+                line,
+                formatted: ins.to_string(),
+                orig: None,
+            });
+        }
+        Ok(ProcessCodeResult { ops, insts })
+    }
+
+    fn implcit_addend(
+        &self,
+        section: &ObjSection,
+        address: u64,
+        reloc: &Relocation,
+    ) -> Result<i64> {
+        let address = address as usize;
+        Ok(match reloc.flags() {
+            // ARM calls
+            RelocationFlags::Elf {
+                r_type: elf::R_ARM_PC24 | elf::R_ARM_XPC25 | elf::R_ARM_CALL,
+            } => {
+                let data = section.data[address..address + 4].try_into()?;
+                let addend = self.endianness.read_i32_bytes(data);
+                let imm24 = addend & 0xFFFFFF;
+                ((imm24 << 2) << 6 >> 6) as i64
+            }
+            // Thumb calls
+            RelocationFlags::Elf { r_type: elf::R_ARM_THM_PC22 | elf::R_ARM_THM_XPC22 } => {
+                let data = section.data[address..address + 2].try_into()?;
+                let high = self.endianness.read_i16_bytes(data) as i32;
+                let data = section.data[address + 2..address + 4].try_into()?;
+                let low = self.endianness.read_i16_bytes(data) as i32;
+                let imm22 = ((high & 0x7FF) << 11) | (low & 0x7FF);
+                ((imm22 << 1) << 9 >> 9) as i64
+            }
+            // Data
+            RelocationFlags::Elf { r_type: elf::R_ARM_ABS32 } => {
+                let data = section.data[address..address + 4].try_into()?;
+                self.endianness.read_i32_bytes(data) as i64
+            }
+            flags => bail!("Unsupported ARM implicit relocation {flags:?}"),
+        })
+    }
+
+    fn demangle(&self, name: &str) -> Option<String> {
+        cpp_demangle::Symbol::new(name)
+            .ok()
+            .and_then(|s| s.demangle(&cpp_demangle::DemangleOptions::default()).ok())
+    }
+
+    fn display_reloc(&self, flags: RelocationFlags) -> Cow<'static, str> {
+        match flags {
+            RelocationFlags::Elf { r_type } => match r_type {
+                elf::R_ARM_ABS32 => Cow::Borrowed("R_ARM_ABS32"),
+                elf::R_ARM_PC24 => Cow::Borrowed("R_ARM_PC24"),
+                elf::R_ARM_XPC25 => Cow::Borrowed("R_ARM_XPC25"),
+                elf::R_ARM_CALL => Cow::Borrowed("R_ARM_CALL"),
+                elf::R_ARM_THM_PC22 => Cow::Borrowed("R_ARM_THM_CALL"),
+                elf::R_ARM_THM_XPC22 => Cow::Borrowed("R_ARM_THM_XPC22"),
+                _ => Cow::Owned(format!("<{flags:?}>")),
+            },
+            _ => Cow::Owned(format!("<{flags:?}>")),
+        }
+    }
+
+    fn is_return(&self, ins: &ObjIns) -> bool {
+        let has_reg = |name: &str| {
+            ins.args.iter().any(
+                |arg| matches!(arg, ObjInsArg::Arg(ObjInsArgValue::Opaque(reg)) if reg == name),
+            )
+        };
+        match ins.mnemonic.as_str() {
+            "bx" => has_reg("lr"),
+            "pop" | "ldm" | "ldmia" | "ldmfd" => has_reg("pc"),
+            _ => false,
+        }
+    }
+
+    fn is_indirect_jump(&self, ins: &ObjIns) -> bool {
+        let first_is = |name: &str| matches!(ins.args.first(), Some(ObjInsArg::Arg(ObjInsArgValue::Opaque(reg))) if reg == name);
+        match ins.mnemonic.as_str() {
+            "bx" => !self.is_return(ins),
+            "ldr" | "mov" => first_is("pc"),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct DisasmMode {
+    address: u32,
+    mode: ParseMode,
+}
+
+impl DisasmMode {
+    fn from_symbol(symbol: &Symbol<'_, '_>) -> Option<Self> {
+        let name = symbol.name().ok()?;
+        ParseMode::from_mapping_symbol(name)
+            .map(|mode| DisasmMode { address: symbol.address() as u32, mode })
+    }
+}
+
+fn push_args(
+    parsed_ins: &ParsedIns,
+    config: &DiffObjConfig,
+    reloc_arg: Option<usize>,
+    cur_addr: u32,
+) -> Result<(Vec<ObjInsArg>, Option<u64>)> {
+    let mut args = vec![];
+    let mut branch_dest = None;
+    let mut writeback = false;
+    let mut deref = false;
+    for (i, arg) in parsed_ins.args_iter().enumerate() {
+        // Close the dereference before writing a post-indexed offset
+        if deref {
+            match arg {
+                Argument::OffsetImm(OffsetImm { post_indexed: true, .. })
+                | Argument::OffsetReg(OffsetReg { post_indexed: true, .. })
+                | Argument::CoOption(_) => {
+                    deref = false;
+                    args.push(ObjInsArg::PlainText("]".into()));
+                    if writeback {
+                        writeback = false;
+                        args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque("!".into())));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if i > 0 {
+            args.push(ObjInsArg::PlainText(config.separator().into()));
+        }
+
+        if reloc_arg == Some(i) {
+            args.push(ObjInsArg::Reloc);
+            continue;
+        }
+        match arg {
+            Argument::Reg(reg) => {
+                if reg.deref {
+                    deref = true;
+                    args.push(ObjInsArg::PlainText("[".into()));
+                }
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(reg.reg.to_string().into())));
+                if reg.writeback {
+                    if reg.deref {
+                        writeback = true;
+                    } else {
+                        args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque("!".into())));
+                    }
+                }
+            }
+            Argument::RegList(reg_list) => {
+                args.push(ObjInsArg::PlainText("{".into()));
+                let mut first = true;
+                for i in 0..16 {
+                    if (reg_list.regs & (1 << i)) != 0 {
+                        if !first {
+                            args.push(ObjInsArg::PlainText(config.separator().into()));
+                        }
+                        args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(
+                            Register::parse(i).to_string().into(),
+                        )));
+                        first = false;
+                    }
+                }
+                args.push(ObjInsArg::PlainText("}".into()));
+                if reg_list.user_mode {
+                    args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque("^".into())));
+                }
+            }
+            Argument::UImm(value) | Argument::CoOpcode(value) | Argument::SatImm(value) => {
+                args.push(ObjInsArg::PlainText("#".into()));
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(*value as u64)));
+            }
+            Argument::SImm(value) | Argument::OffsetImm(OffsetImm { value, .. }) => {
+                args.push(ObjInsArg::PlainText("#".into()));
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Signed(*value as i64)));
+            }
+            Argument::BranchDest(value) => {
+                let dest = cur_addr.wrapping_add_signed(*value) as u64;
+                args.push(ObjInsArg::BranchDest(dest));
+                branch_dest = Some(dest);
+            }
+            Argument::CoOption(value) => {
+                args.push(ObjInsArg::PlainText("{".into()));
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(*value as u64)));
+                args.push(ObjInsArg::PlainText("}".into()));
+            }
+            Argument::CoprocNum(value) => {
+                args.push(ObjInsArg::PlainText("p".into()));
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(*value as u64)));
+            }
+            Argument::ShiftImm(shift) => {
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(shift.op.to_string().into())));
+                args.push(ObjInsArg::PlainText(" #".into()));
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(shift.imm as u64)));
+            }
+            Argument::ShiftReg(shift) => {
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(shift.op.to_string().into())));
+                args.push(ObjInsArg::PlainText(" ".into()));
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(shift.reg.to_string().into())));
+            }
+            Argument::OffsetReg(offset) => {
+                if !offset.add {
+                    args.push(ObjInsArg::PlainText("-".into()));
+                }
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(offset.reg.to_string().into())));
+            }
+            Argument::CpsrMode(mode) => {
+                args.push(ObjInsArg::PlainText("#".into()));
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(mode.mode as u64)));
+                if mode.writeback {
+                    args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque("!".into())));
+                }
+            }
+            Argument::CoReg(_)
+            | Argument::StatusReg(_)
+            | Argument::StatusMask(_)
+            | Argument::Shift(_)
+            | Argument::CpsrFlags(_)
+            | Argument::Endian(_) => {
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(arg.to_string().into())));
+            }
+            Argument::None => {}
+        }
+    }
+    if deref {
+        args.push(ObjInsArg::PlainText("]".into()));
+        if writeback {
+            args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque("!".into())));
+        }
+    }
+    Ok((args, branch_dest))
+}
@@ -0,0 +1,426 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use anyhow::{anyhow, bail, Result};
+use object::{elf, File, Relocation, RelocationFlags};
+use yaxpeax_arch::{Decoder, U8Reader};
+use yaxpeax_arm::armv8::a64::InstDecoder;
+
+use crate::{
+    arch::{bytes_ins, ObjArch, ProcessCodeResult, RegisterClass, OP_BYTES},
+    diff::DiffObjConfig,
+    obj::{ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjReloc, ObjSection, ObjSymbol, SymbolRef},
+};
+
+pub struct ObjArchArm64 {}
+
+impl ObjArchArm64 {
+    pub fn new(_file: &File) -> Result<Self> { Ok(Self {}) }
+}
+
+impl ObjArch for ObjArchArm64 {
+    fn process_code(
+        &self,
+        obj: &ObjInfo,
+        symbol_ref: SymbolRef,
+        config: &DiffObjConfig,
+    ) -> Result<ProcessCodeResult> {
+        let (section, symbol) = obj.section_symbol(symbol_ref);
+        let section = section.ok_or_else(|| anyhow!("Code symbol section not found"))?;
+        let code = &section.data
+            [symbol.section_address as usize..(symbol.section_address + symbol.size) as usize];
+
+        let decoder = InstDecoder::default();
+        let ins_count = code.len() / 4;
+        let mut ops = Vec::<u16>::with_capacity(ins_count);
+        let mut insts = Vec::<ObjIns>::with_capacity(ins_count);
+        for (idx, word) in code.chunks_exact(4).enumerate() {
+            let address = symbol.address + idx as u64 * 4;
+            let line = section.line_info.range(..=address).last().map(|(_, &b)| b);
+            let reloc = section.relocations.iter().find(|r| r.address == address);
+            // Instructions are always little-endian, even in big-endian objects
+            let Ok(ins) = decoder.decode(&mut U8Reader::new(word)) else {
+                ops.push(OP_BYTES);
+                insts.push(bytes_ins(address, word, reloc, line));
+                continue;
+            };
+
+            let formatted = ins.to_string();
+            let (mnemonic, operands) = formatted.split_once(' ').unwrap_or((&formatted, ""));
+            let (args, branch_dest) = push_args(mnemonic, operands, address, reloc, config);
+            let op = mnemonic_op(mnemonic);
+            ops.push(op);
+            insts.push(ObjIns {
+                address,
+                size: 4,
+                op,
+                mnemonic: mnemonic.to_string(),
+                args,
+                reloc: reloc.cloned(),
+                branch_dest,
+                line,
+                formatted,
+                orig: None,
+            });
+        }
+        resolve_page_pairs(obj, &mut insts, config);
+        Ok(ProcessCodeResult { ops, insts })
+    }
+
+    fn implcit_addend(
+        &self,
+        _section: &ObjSection,
+        address: u64,
+        reloc: &Relocation,
+    ) -> Result<i64> {
+        bail!("Unsupported ARM64 implicit relocation {:#x}:{:?}", address, reloc.flags())
+    }
+
+    fn demangle(&self, name: &str) -> Option<String> {
+        cpp_demangle::Symbol::new(name)
+            .ok()
+            .and_then(|s| s.demangle(&cpp_demangle::DemangleOptions::default()).ok())
+    }
+
+    fn display_reloc(&self, flags: RelocationFlags) -> Cow<'static, str> {
+        match flags {
+            RelocationFlags::Elf { r_type } => match r_type {
+                elf::R_AARCH64_ABS64 => Cow::Borrowed("R_AARCH64_ABS64"),
+                elf::R_AARCH64_ABS32 => Cow::Borrowed("R_AARCH64_ABS32"),
+                elf::R_AARCH64_PREL64 => Cow::Borrowed("R_AARCH64_PREL64"),
+                elf::R_AARCH64_PREL32 => Cow::Borrowed("R_AARCH64_PREL32"),
+                elf::R_AARCH64_ADR_PREL_LO21 => Cow::Borrowed("R_AARCH64_ADR_PREL_LO21"),
+                elf::R_AARCH64_ADR_PREL_PG_HI21 => Cow::Borrowed("R_AARCH64_ADR_PREL_PG_HI21"),
+                elf::R_AARCH64_ADR_PREL_PG_HI21_NC => {
+                    Cow::Borrowed("R_AARCH64_ADR_PREL_PG_HI21_NC")
+                }
+                elf::R_AARCH64_ADD_ABS_LO12_NC => Cow::Borrowed("R_AARCH64_ADD_ABS_LO12_NC"),
+                elf::R_AARCH64_LDST8_ABS_LO12_NC => Cow::Borrowed("R_AARCH64_LDST8_ABS_LO12_NC"),
+                elf::R_AARCH64_LDST16_ABS_LO12_NC => Cow::Borrowed("R_AARCH64_LDST16_ABS_LO12_NC"),
+                elf::R_AARCH64_LDST32_ABS_LO12_NC => Cow::Borrowed("R_AARCH64_LDST32_ABS_LO12_NC"),
+                elf::R_AARCH64_LDST64_ABS_LO12_NC => Cow::Borrowed("R_AARCH64_LDST64_ABS_LO12_NC"),
+                elf::R_AARCH64_LDST128_ABS_LO12_NC => {
+                    Cow::Borrowed("R_AARCH64_LDST128_ABS_LO12_NC")
+                }
+                elf::R_AARCH64_JUMP26 => Cow::Borrowed("R_AARCH64_JUMP26"),
+                elf::R_AARCH64_CALL26 => Cow::Borrowed("R_AARCH64_CALL26"),
+                elf::R_AARCH64_CONDBR19 => Cow::Borrowed("R_AARCH64_CONDBR19"),
+                elf::R_AARCH64_TSTBR14 => Cow::Borrowed("R_AARCH64_TSTBR14"),
+                elf::R_AARCH64_LD_PREL_LO19 => Cow::Borrowed("R_AARCH64_LD_PREL_LO19"),
+                elf::R_AARCH64_ADR_GOT_PAGE => Cow::Borrowed("R_AARCH64_ADR_GOT_PAGE"),
+                elf::R_AARCH64_LD64_GOT_LO12_NC => Cow::Borrowed("R_AARCH64_LD64_GOT_LO12_NC"),
+                _ => Cow::Owned(format!("<{flags:?}>")),
+            },
+            _ => Cow::Owned(format!("<{flags:?}>")),
+        }
+    }
+
+    fn register_class(&self, arg: &ObjInsArgValue) -> Option<RegisterClass> {
+        let ObjInsArgValue::Opaque(name) = arg else {
+            return None;
+        };
+        // Strip SIMD arrangement specifiers, such as v0.16b
+        let name = name.split('.').next().unwrap_or(name);
+        let (prefix, num) = name.split_at(name.chars().next()?.len_utf8());
+        let num = num.parse::<u8>().ok()?;
+        match prefix {
+            // x29 and x30 are the frame pointer and link register
+            "x" | "w" if num <= 28 => Some(RegisterClass::Gpr),
+            "v" | "q" | "d" | "s" | "h" | "b" if num <= 31 => Some(RegisterClass::Fpr),
+            _ => None,
+        }
+    }
+}
+
+/// Op of a mnemonic. The disassembler's opcodes don't cover aliases like `mov`, so the
+/// displayed mnemonic is hashed instead.
+fn mnemonic_op(mnemonic: &str) -> u16 {
+    let mut hash = 0x811c9dc5u32;
+    for b in mnemonic.bytes() {
+        hash ^= b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    (hash % OP_BYTES as u32) as u16
+}
+
+fn is_branch(mnemonic: &str) -> bool {
+    matches!(mnemonic, "b" | "bl" | "cbz" | "cbnz" | "tbz" | "tbnz") || mnemonic.starts_with("b.")
+}
+
+/// Where a relocation is displayed: in place of the PC-relative operand, or of the last
+/// immediate operand. Along with the GNU assembler operator prefix.
+#[derive(Copy, Clone)]
+enum RelocSlot {
+    PcRel(&'static str),
+    Imm(&'static str),
+}
+
+fn reloc_slot(reloc: &ObjReloc) -> Option<RelocSlot> {
+    let RelocationFlags::Elf { r_type } = reloc.flags else {
+        return None;
+    };
+    Some(match r_type {
+        elf::R_AARCH64_CALL26
+        | elf::R_AARCH64_JUMP26
+        | elf::R_AARCH64_CONDBR19
+        | elf::R_AARCH64_TSTBR14
+        | elf::R_AARCH64_ADR_PREL_LO21
+        | elf::R_AARCH64_ADR_PREL_PG_HI21
+        | elf::R_AARCH64_ADR_PREL_PG_HI21_NC
+        | elf::R_AARCH64_LD_PREL_LO19 => RelocSlot::PcRel(""),
+        elf::R_AARCH64_ADR_GOT_PAGE => RelocSlot::PcRel(":got:"),
+        elf::R_AARCH64_ADD_ABS_LO12_NC
+        | elf::R_AARCH64_LDST8_ABS_LO12_NC
+        | elf::R_AARCH64_LDST16_ABS_LO12_NC
+        | elf::R_AARCH64_LDST32_ABS_LO12_NC
+        | elf::R_AARCH64_LDST64_ABS_LO12_NC
+        | elf::R_AARCH64_LDST128_ABS_LO12_NC => RelocSlot::Imm(":lo12:"),
+        elf::R_AARCH64_LD64_GOT_LO12_NC => RelocSlot::Imm(":got_lo12:"),
+        elf::R_AARCH64_MOVW_UABS_G0 | elf::R_AARCH64_MOVW_UABS_G0_NC => RelocSlot::Imm(":abs_g0:"),
+        elf::R_AARCH64_MOVW_UABS_G1 | elf::R_AARCH64_MOVW_UABS_G1_NC => RelocSlot::Imm(":abs_g1:"),
+        elf::R_AARCH64_MOVW_UABS_G2 | elf::R_AARCH64_MOVW_UABS_G2_NC => RelocSlot::Imm(":abs_g2:"),
+        elf::R_AARCH64_MOVW_UABS_G3 => RelocSlot::Imm(":abs_g3:"),
+        _ => return None,
+    })
+}
+
+fn parse_number(s: &str) -> Option<ObjInsArgValue> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let value = match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+        None => s.parse().ok()?,
+    };
+    Some(if negative {
+        ObjInsArgValue::Signed((value as i64).wrapping_neg())
+    } else {
+        ObjInsArgValue::Unsigned(value)
+    })
+}
+
+fn push_reloc(args: &mut Vec<ObjInsArg>, prefix: &'static str) {
+    if !prefix.is_empty() {
+        args.push(ObjInsArg::PlainText(prefix.into()));
+    }
+    args.push(ObjInsArg::Reloc);
+}
+
+/// Splits the disassembler's operand text into arguments, substituting the relocation.
+fn push_args(
+    mnemonic: &str,
+    operands: &str,
+    address: u64,
+    reloc: Option<&ObjReloc>,
+    config: &DiffObjConfig,
+) -> (Vec<ObjInsArg>, Option<u64>) {
+    let mut args = vec![];
+    let mut branch_dest = None;
+    let mut slot = reloc.and_then(reloc_slot);
+    let mut imm_remaining = operands.split([' ', ',']).filter(|w| w.starts_with('#')).count();
+    for (idx, item) in operands.split(", ").filter(|s| !s.is_empty()).enumerate() {
+        if idx > 0 {
+            args.push(ObjInsArg::PlainText(config.separator().into()));
+        }
+        let core = item.trim_start_matches(['[', '{']);
+        let open = &item[..item.len() - core.len()];
+        let close_start = core.trim_end_matches([']', '}', '!']).len();
+        let (core, close) = core.split_at(close_start);
+        if !open.is_empty() {
+            args.push(ObjInsArg::PlainText(open.to_string().into()));
+        }
+        for (word_idx, word) in core.split(' ').enumerate() {
+            if word_idx > 0 {
+                args.push(ObjInsArg::PlainText(" ".into()));
+            }
+            if let Some(imm) = word.strip_prefix('#') {
+                imm_remaining = imm_remaining.saturating_sub(1);
+                if let (Some(RelocSlot::Imm(prefix)), 0) = (slot, imm_remaining) {
+                    push_reloc(&mut args, prefix);
+                    slot = None;
+                    continue;
+                }
+                args.push(ObjInsArg::PlainText("#".into()));
+                args.push(ObjInsArg::Arg(
+                    parse_number(imm)
+                        .unwrap_or_else(|| ObjInsArgValue::Opaque(imm.to_string().into())),
+                ));
+            } else if let Some(offset) = word.strip_prefix('$') {
+                if let Some(RelocSlot::PcRel(prefix)) = slot {
+                    push_reloc(&mut args, prefix);
+                    slot = None;
+                    continue;
+                }
+                let offset = match parse_number(offset.trim_start_matches('+')) {
+                    Some(ObjInsArgValue::Signed(v)) => v,
+                    Some(ObjInsArgValue::Unsigned(v)) => v as i64,
+                    _ => {
+                        args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(word.to_string().into())));
+                        continue;
+                    }
+                };
+                // adrp offsets are relative to the 4 KiB page of the instruction
+                let base = if mnemonic == "adrp" { address & !0xFFF } else { address };
+                let dest = base.wrapping_add_signed(offset);
+                if is_branch(mnemonic) {
+                    args.push(ObjInsArg::BranchDest(dest));
+                    branch_dest = Some(dest);
+                } else {
+                    args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(dest)));
+                }
+            } else if !word.is_empty() {
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(word.to_string().into())));
+            }
+        }
+        // A memory operand without an offset, such as `ldr x0, [x1, :lo12:sym]` with
+        // an unrelocated offset of zero
+        if let (Some(RelocSlot::Imm(prefix)), "[", true) = (slot, open, close.starts_with(']')) {
+            args.push(ObjInsArg::PlainText(config.separator().into()));
+            push_reloc(&mut args, prefix);
+            slot = None;
+        }
+        for c in close.chars() {
+            if c == '!' {
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque("!".into())));
+            } else {
+                args.push(ObjInsArg::PlainText(c.to_string().into()));
+            }
+        }
+    }
+    (args, branch_dest)
+}
+
+/// Finds the symbol containing an address, in any section.
+fn find_symbol(obj: &ObjInfo, address: u64) -> Option<(&ObjSection, &ObjSymbol)> {
+    obj.sections.iter().find_map(|section| {
+        section
+            .symbols
+            .iter()
+            .find(|s| address == s.address || (address > s.address && address < s.address + s.size))
+            .map(|s| (section, s))
+    })
+}
+
+/// Resolves unrelocated `adrp` + `add`/load/store pairs, as found in linked code, to the
+/// symbol they address. Both instructions are given a relocation to the symbol, like a
+/// relocated pair in an object.
+fn resolve_page_pairs(obj: &ObjInfo, insts: &mut [ObjIns], config: &DiffObjConfig) {
+    // Register => (adrp index, page address)
+    let mut pages = BTreeMap::<String, (usize, u64)>::new();
+    for idx in 0..insts.len() {
+        let ins = &insts[idx];
+        let dest_reg = match ins.args.first() {
+            Some(ObjInsArg::Arg(ObjInsArgValue::Opaque(reg))) => Some(reg.to_string()),
+            _ => None,
+        };
+        if ins.mnemonic == "adrp" && ins.reloc.is_none() {
+            if let (Some(reg), Some(ObjInsArg::Arg(ObjInsArgValue::Unsigned(page)))) =
+                (dest_reg, ins.args.last())
+            {
+                pages.insert(reg, (idx, *page));
+            }
+            continue;
+        }
+        if is_branch(&ins.mnemonic) {
+            pages.clear();
+            continue;
+        }
+        if ins.reloc.is_none() {
+            if let Some((base_reg, imm_idx, imm)) = lo12_operand(ins) {
+                if let Some(&(page_idx, page)) = pages.get(&base_reg) {
+                    resolve_pair(obj, insts, config, page_idx, idx, imm_idx, page + imm);
+                }
+            }
+        }
+        if let Some(reg) = insts[idx].args.first().and_then(|a| match a {
+            ObjInsArg::Arg(ObjInsArgValue::Opaque(reg)) => Some(reg.to_string()),
+            _ => None,
+        }) {
+            // Stores don't overwrite their first register
+            if !insts[idx].mnemonic.starts_with("st") {
+                pages.remove(&reg);
+            }
+        }
+    }
+}
+
+/// The base register, argument index and value of the page offset immediate of an
+/// `add xd, xn, #imm` or a `[xn, #imm]` memory operand.
+fn lo12_operand(ins: &ObjIns) -> Option<(String, usize, u64)> {
+    let values = ins.args.iter().enumerate().filter(|(_, a)| !matches!(a, ObjInsArg::PlainText(_)));
+    let values = values.collect::<Vec<_>>();
+    let imm = |i: usize| match values.get(i) {
+        Some(&(idx, ObjInsArg::Arg(ObjInsArgValue::Unsigned(v)))) => Some((idx, *v)),
+        _ => None,
+    };
+    let reg = |i: usize| match values.get(i) {
+        Some((_, ObjInsArg::Arg(ObjInsArgValue::Opaque(r)))) => Some(r.to_string()),
+        _ => None,
+    };
+    if ins.mnemonic == "add" && values.len() == 3 {
+        let (idx, value) = imm(2)?;
+        return Some((reg(1)?, idx, value));
+    }
+    let open = ins.args.iter().position(|a| matches!(a, ObjInsArg::PlainText(s) if s == "["))?;
+    let base = match ins.args.get(open + 1) {
+        Some(ObjInsArg::Arg(ObjInsArgValue::Opaque(r))) => r.to_string(),
+        _ => return None,
+    };
+    match ins.args.get(open + 2..) {
+        Some(
+            [ObjInsArg::PlainText(_), ObjInsArg::PlainText(hash), ObjInsArg::Arg(ObjInsArgValue::Unsigned(v)), ..],
+        ) if hash == "#" => Some((base, open + 4, *v)),
+        // No offset
+        Some([ObjInsArg::PlainText(close), ..]) if close == "]" => Some((base, usize::MAX, 0)),
+        _ => None,
+    }
+}
+
+fn resolve_pair(
+    obj: &ObjInfo,
+    insts: &mut [ObjIns],
+    config: &DiffObjConfig,
+    page_idx: usize,
+    lo_idx: usize,
+    imm_idx: usize,
+    address: u64,
+) {
+    let Some((section, symbol)) = find_symbol(obj, address) else {
+        return;
+    };
+    let target = ObjSymbol { addend: (address - symbol.address) as i64, ..symbol.clone() };
+    let reloc = |r_type: u32, address: u64| ObjReloc {
+        flags: RelocationFlags::Elf { r_type },
+        address,
+        target: target.clone(),
+        target_section: Some(section.name.clone()),
+        via: None,
+    };
+
+    let page = &mut insts[page_idx];
+    if let Some(last) = page.args.last_mut() {
+        *last = ObjInsArg::Reloc;
+    }
+    page.reloc = Some(reloc(elf::R_AARCH64_ADR_PREL_PG_HI21, page.address));
+
+    let lo = &mut insts[lo_idx];
+    let r_type = if lo.mnemonic == "add" {
+        elf::R_AARCH64_ADD_ABS_LO12_NC
+    } else {
+        elf::R_AARCH64_LDST64_ABS_LO12_NC
+    };
+    let lo12 = [ObjInsArg::PlainText(":lo12:".into()), ObjInsArg::Reloc];
+    if imm_idx == usize::MAX {
+        // Insert the offset into a memory operand without one
+        if let Some(close) =
+            lo.args.iter().rposition(|a| matches!(a, ObjInsArg::PlainText(s) if s == "]"))
+        {
+            let sep = ObjInsArg::PlainText(config.separator().into());
+            lo.args.splice(close..close, [sep].into_iter().chain(lo12));
+        }
+    } else {
+        // Replace the "#" and the immediate
+        lo.args.splice(imm_idx - 1..=imm_idx, lo12);
+    }
+    lo.reloc = Some(reloc(r_type, lo.address));
+}
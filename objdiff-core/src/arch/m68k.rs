@@ -0,0 +1,968 @@
+use std::borrow::Cow;
+
+use anyhow::{anyhow, bail, Result};
+use object::{elf, read::elf::FileHeader, File, Relocation, RelocationFlags};
+
+use crate::{
+    arch::{bytes_ins, ObjArch, ProcessCodeResult, OP_BYTES},
+    diff::DiffObjConfig,
+    obj::{ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjReloc, ObjSection, SymbolRef},
+};
+
+pub struct ObjArchM68k {}
+
+impl ObjArchM68k {
+    pub fn new(_file: &File) -> Result<Self> { Ok(Self {}) }
+}
+
+/// Whether the object targets the 68000 family. Checked by ELF machine, as the object
+/// crate doesn't detect the architecture.
+pub fn is_m68k(file: &File) -> bool {
+    match file {
+        File::Elf32(elf) => elf.elf_header().e_machine(elf.endian()) == elf::EM_68K,
+        _ => false,
+    }
+}
+
+impl ObjArch for ObjArchM68k {
+    fn process_code(
+        &self,
+        obj: &ObjInfo,
+        symbol_ref: SymbolRef,
+        config: &DiffObjConfig,
+    ) -> Result<ProcessCodeResult> {
+        let (section, symbol) = obj.section_symbol(symbol_ref);
+        let section = section.ok_or_else(|| anyhow!("Code symbol section not found"))?;
+        let code = &section.data
+            [symbol.section_address as usize..(symbol.section_address + symbol.size) as usize];
+
+        let mut ops = Vec::<u16>::new();
+        let mut insts = Vec::<ObjIns>::new();
+        let mut offset = 0usize;
+        while offset + 2 <= code.len() {
+            let address = symbol.address + offset as u64;
+            let line = section.line_info.range(..=address).last().map(|(_, &b)| b);
+            let mut decoder = Decoder {
+                code,
+                base: symbol.address,
+                offset,
+                address,
+                section,
+                config,
+                args: vec![],
+                reloc: None,
+                branch_dest: None,
+            };
+            let Some((op, mnemonic)) = decoder.decode() else {
+                let reloc = section.relocations.iter().find(|r| r.address & !1 == address);
+                ops.push(OP_BYTES);
+                insts.push(bytes_ins(address, &code[offset..offset + 2], reloc, line));
+                offset += 2;
+                continue;
+            };
+            let Decoder { offset: end, args, reloc, branch_dest, .. } = decoder;
+
+            let mut formatted = mnemonic.clone();
+            for (idx, arg) in args.iter().enumerate() {
+                formatted.push_str(if idx == 0 { " " } else { "" });
+                match arg {
+                    ObjInsArg::PlainText(s) => formatted.push_str(s),
+                    ObjInsArg::Arg(v) => formatted.push_str(&v.to_string()),
+                    ObjInsArg::Reloc => formatted
+                        .push_str(reloc.as_ref().map(|r| r.target.name.as_str()).unwrap_or("")),
+                    ObjInsArg::BranchDest(dest) => formatted.push_str(&format!("{dest:#x}")),
+                    ObjInsArg::Bytes(bytes) => formatted.push_str(&format!("{bytes:02x?}")),
+                }
+            }
+
+            ops.push(op);
+            insts.push(ObjIns {
+                address,
+                size: (end - offset) as u8,
+                op,
+                mnemonic,
+                args,
+                reloc,
+                branch_dest,
+                line,
+                formatted,
+                orig: None,
+            });
+            offset = end;
+        }
+        Ok(ProcessCodeResult { ops, insts })
+    }
+
+    fn implcit_addend(
+        &self,
+        section: &ObjSection,
+        address: u64,
+        reloc: &Relocation,
+    ) -> Result<i64> {
+        let address = address as usize;
+        Ok(match reloc.flags() {
+            RelocationFlags::Elf { r_type: elf::R_68K_32 | elf::R_68K_PC32 } => {
+                i32::from_be_bytes(section.data[address..address + 4].try_into()?) as i64
+            }
+            RelocationFlags::Elf { r_type: elf::R_68K_16 | elf::R_68K_PC16 } => {
+                i16::from_be_bytes(section.data[address..address + 2].try_into()?) as i64
+            }
+            flags => bail!("Unsupported m68k implicit relocation {flags:?}"),
+        })
+    }
+
+    fn demangle(&self, name: &str) -> Option<String> {
+        cpp_demangle::Symbol::new(name)
+            .ok()
+            .and_then(|s| s.demangle(&cpp_demangle::DemangleOptions::default()).ok())
+    }
+
+    fn display_reloc(&self, flags: RelocationFlags) -> Cow<'static, str> {
+        match flags {
+            RelocationFlags::Elf { r_type } => match r_type {
+                elf::R_68K_32 => Cow::Borrowed("R_68K_32"),
+                elf::R_68K_16 => Cow::Borrowed("R_68K_16"),
+                elf::R_68K_8 => Cow::Borrowed("R_68K_8"),
+                elf::R_68K_PC32 => Cow::Borrowed("R_68K_PC32"),
+                elf::R_68K_PC16 => Cow::Borrowed("R_68K_PC16"),
+                elf::R_68K_PC8 => Cow::Borrowed("R_68K_PC8"),
+                _ => Cow::Owned(format!("<{flags:?}>")),
+            },
+            _ => Cow::Owned(format!("<{flags:?}>")),
+        }
+    }
+}
+
+/// Decodes a single instruction and its extension words.
+struct Decoder<'a> {
+    code: &'a [u8],
+    /// Address of the start of the code
+    base: u64,
+    /// Offset of the next word to read
+    offset: usize,
+    /// Address of the instruction
+    address: u64,
+    section: &'a ObjSection,
+    config: &'a DiffObjConfig,
+    args: Vec<ObjInsArg>,
+    reloc: Option<ObjReloc>,
+    branch_dest: Option<u64>,
+}
+
+impl Decoder<'_> {
+    /// Address of the next word to read.
+    fn pc(&self) -> u64 { self.base + self.offset as u64 }
+
+    fn word(&mut self) -> Option<u16> {
+        let bytes = self.code.get(self.offset..self.offset + 2)?;
+        self.offset += 2;
+        Some(u16::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn long(&mut self) -> Option<u32> { Some(((self.word()? as u32) << 16) | self.word()? as u32) }
+
+    /// Pushes the relocation at the address, if any, in place of an extension word's value.
+    /// Only the first relocation of an instruction is kept.
+    fn push_reloc(&mut self, address: u64) -> bool {
+        if self.reloc.is_some() {
+            return false;
+        }
+        let Some(reloc) = self.section.relocations.iter().find(|r| r.address == address) else {
+            return false;
+        };
+        self.reloc = Some(reloc.clone());
+        self.args.push(ObjInsArg::Reloc);
+        true
+    }
+
+    fn text(&mut self, s: &'static str) { self.args.push(ObjInsArg::PlainText(s.into())); }
+
+    fn opaque(&mut self, s: String) {
+        self.args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(s.into())));
+    }
+
+    fn data_reg(&mut self, num: u16) { self.opaque(format!("d{num}")); }
+
+    fn addr_reg(&mut self, num: u16) {
+        self.opaque(if num == 7 { "sp".to_string() } else { format!("a{num}") });
+    }
+
+    fn signed(&mut self, value: i64) {
+        self.args.push(ObjInsArg::Arg(ObjInsArgValue::Signed(value)));
+    }
+
+    fn unsigned(&mut self, value: u64) {
+        self.args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(value)));
+    }
+
+    /// Returns the op and mnemonic.
+    fn decode(&mut self) -> Option<(u16, String)> {
+        let code = self.word()?;
+        if code & 0xF000 == 0xA000 {
+            return Some((code, self.a_line_trap(code)));
+        }
+        let (op, opcode) = OPCODES
+            .iter()
+            .enumerate()
+            .find(|(_, o)| code & o.mask == o.pattern)
+            .map(|(idx, o)| (idx as u16, o))?;
+        let size = match opcode.size {
+            SizeField::None => None,
+            SizeField::Fixed(size) => Some(size),
+            SizeField::Bits6 => Some(match (code >> 6) & 3 {
+                0 => Size::Byte,
+                1 => Size::Word,
+                2 => Size::Long,
+                _ => return None,
+            }),
+            SizeField::Bit6 => Some(if code & 0x40 != 0 { Size::Long } else { Size::Word }),
+        };
+        let mut mnemonic = opcode.mnemonic.to_string();
+        if opcode.cond {
+            let cond = (code >> 8) & 0xF;
+            mnemonic.push_str(if mnemonic == "db" && cond == 1 {
+                "ra"
+            } else {
+                CONDITIONS[cond as usize]
+            });
+        }
+        if let Some(size) = size {
+            mnemonic.push_str(size.suffix());
+        }
+        // The register mask is the first extension word, even if displayed last
+        let reg_list =
+            if opcode.operands.contains(&Operand::RegList) { Some(self.word()?) } else { None };
+        let jump = matches!(opcode.mnemonic, "jmp" | "jsr");
+        for (idx, operand) in opcode.operands.iter().enumerate() {
+            if idx > 0 {
+                self.args.push(ObjInsArg::PlainText(self.config.separator().into()));
+            }
+            let reg_y = code & 7;
+            let reg_x = (code >> 9) & 7;
+            match *operand {
+                Operand::Ea => self.ea((code >> 3) & 7, reg_y, size, jump)?,
+                Operand::MoveDst => self.ea((code >> 6) & 7, reg_x, size, false)?,
+                Operand::Dy => self.data_reg(reg_y),
+                Operand::Dx => self.data_reg(reg_x),
+                Operand::Ay => self.addr_reg(reg_y),
+                Operand::Ax => self.addr_reg(reg_x),
+                Operand::AyInc | Operand::AxInc => {
+                    self.text("(");
+                    self.addr_reg(if *operand == Operand::AyInc { reg_y } else { reg_x });
+                    self.text(")+");
+                }
+                Operand::AyDec | Operand::AxDec => {
+                    self.text("-(");
+                    self.addr_reg(if *operand == Operand::AyDec { reg_y } else { reg_x });
+                    self.text(")");
+                }
+                Operand::AyDisp => {
+                    let pc = self.pc();
+                    let disp = self.word()? as i16;
+                    if !self.push_reloc(pc) {
+                        self.signed(disp as i64);
+                    }
+                    self.text("(");
+                    self.addr_reg(reg_y);
+                    self.text(")");
+                }
+                Operand::Imm => self.imm(size?)?,
+                Operand::BitNum => {
+                    let value = self.word()? & 0xFF;
+                    self.text("#");
+                    self.unsigned(value as u64);
+                }
+                Operand::Imm16 => {
+                    let value = self.word()? as i16;
+                    self.text("#");
+                    self.signed(value as i64);
+                }
+                Operand::Quick3 => {
+                    self.text("#");
+                    self.unsigned(if reg_x == 0 { 8 } else { reg_x as u64 });
+                }
+                Operand::Quick8 => {
+                    self.text("#");
+                    self.signed(code as u8 as i8 as i64);
+                }
+                Operand::Vector => {
+                    self.text("#");
+                    self.unsigned((code & 0xF) as u64);
+                }
+                Operand::ShiftCount => {
+                    if code & 0x20 != 0 {
+                        self.data_reg(reg_x);
+                    } else {
+                        self.text("#");
+                        self.unsigned(if reg_x == 0 { 8 } else { reg_x as u64 });
+                    }
+                }
+                Operand::Branch8 => {
+                    let pc = self.address + 2;
+                    let (disp, suffix) = match code as u8 {
+                        0 => {
+                            let disp = self.word()? as i16 as i64;
+                            if self.push_reloc(pc) {
+                                mnemonic.push_str(".w");
+                                continue;
+                            }
+                            (disp, ".w")
+                        }
+                        // 68020 32-bit displacement
+                        0xFF => {
+                            let disp = self.long()? as i32 as i64;
+                            if self.push_reloc(pc) {
+                                mnemonic.push_str(".l");
+                                continue;
+                            }
+                            (disp, ".l")
+                        }
+                        disp => {
+                            if self.push_reloc(self.address + 1) {
+                                mnemonic.push_str(".s");
+                                continue;
+                            }
+                            (disp as i8 as i64, ".s")
+                        }
+                    };
+                    mnemonic.push_str(suffix);
+                    self.branch(pc.wrapping_add_signed(disp));
+                }
+                Operand::Branch16 => {
+                    let pc = self.pc();
+                    let disp = self.word()? as i16 as i64;
+                    if !self.push_reloc(pc) {
+                        self.branch(pc.wrapping_add_signed(disp));
+                    }
+                }
+                Operand::RegList => {
+                    let predecrement = (code >> 3) & 7 == 4;
+                    self.reg_list(reg_list?, predecrement);
+                }
+                Operand::Reg(name) => self.opaque(name.to_string()),
+            }
+        }
+        Some((op, mnemonic))
+    }
+
+    fn branch(&mut self, dest: u64) {
+        self.args.push(ObjInsArg::BranchDest(dest));
+        self.branch_dest = Some(dest);
+    }
+
+    /// Pushes an effective address operand, reading its extension words.
+    fn ea(&mut self, mode: u16, reg: u16, size: Option<Size>, jump: bool) -> Option<()> {
+        match mode {
+            0 => self.data_reg(reg),
+            1 => self.addr_reg(reg),
+            2 => {
+                self.text("(");
+                self.addr_reg(reg);
+                self.text(")");
+            }
+            3 => {
+                self.text("(");
+                self.addr_reg(reg);
+                self.text(")+");
+            }
+            4 => {
+                self.text("-(");
+                self.addr_reg(reg);
+                self.text(")");
+            }
+            5 => {
+                let pc = self.pc();
+                let disp = self.word()? as i16;
+                if !self.push_reloc(pc) {
+                    self.signed(disp as i64);
+                }
+                self.text("(");
+                self.addr_reg(reg);
+                self.text(")");
+            }
+            6 => self.index(Some(reg))?,
+            _ => match reg {
+                // Absolute short
+                0 => {
+                    let pc = self.pc();
+                    let value = self.word()? as i16;
+                    self.text("(");
+                    if !self.push_reloc(pc) {
+                        self.unsigned(value as i32 as u32 as u64);
+                    }
+                    self.text(").w");
+                }
+                // Absolute long
+                1 => {
+                    let pc = self.pc();
+                    let value = self.long()?;
+                    if !self.push_reloc(pc) {
+                        self.text("(");
+                        self.unsigned(value as u64);
+                        self.text(").l");
+                    }
+                }
+                // PC-relative
+                2 => {
+                    let pc = self.pc();
+                    let disp = self.word()? as i16 as i64;
+                    if !self.push_reloc(pc) {
+                        let dest = pc.wrapping_add_signed(disp);
+                        self.args.push(ObjInsArg::BranchDest(dest));
+                        if jump {
+                            self.branch_dest = Some(dest);
+                        }
+                    }
+                    self.text("(");
+                    self.opaque("pc".to_string());
+                    self.text(")");
+                }
+                3 => self.index(None)?,
+                4 => self.imm(size?)?,
+                _ => return None,
+            },
+        }
+        Some(())
+    }
+
+    /// Pushes an indexed operand, `d8(An,Xn.s)` or `d8(pc,Xn.s)` with `base` of None, from
+    /// its brief extension word.
+    fn index(&mut self, base: Option<u16>) -> Option<()> {
+        let pc = self.pc();
+        let ext = self.word()?;
+        // 68020 full extension words aren't supported
+        if ext & 0x100 != 0 {
+            return None;
+        }
+        let disp = ext as u8 as i8 as i64;
+        match base {
+            Some(_) => self.signed(disp),
+            None => self.args.push(ObjInsArg::BranchDest(pc.wrapping_add_signed(disp))),
+        }
+        self.text("(");
+        match base {
+            Some(reg) => self.addr_reg(reg),
+            None => self.opaque("pc".to_string()),
+        }
+        self.text(",");
+        let index = (ext >> 12) & 7;
+        if ext & 0x8000 != 0 {
+            self.addr_reg(index);
+        } else {
+            self.data_reg(index);
+        }
+        self.text(if ext & 0x800 != 0 { ".l)" } else { ".w)" });
+        Some(())
+    }
+
+    /// Pushes an immediate operand of the given size from its extension words.
+    fn imm(&mut self, size: Size) -> Option<()> {
+        let pc = self.pc();
+        let value = match size {
+            Size::Byte => self.word()? as u8 as u64,
+            Size::Word => self.word()? as u64,
+            Size::Long => self.long()? as u64,
+        };
+        self.text("#");
+        if !self.push_reloc(pc) {
+            self.unsigned(value);
+        }
+        Some(())
+    }
+
+    /// Pushes a movem register list, such as `d0-d7/a0-a6`. In predecrement mode, the
+    /// mask is stored bit-reversed.
+    fn reg_list(&mut self, mask: u16, predecrement: bool) {
+        let mask = if predecrement { mask.reverse_bits() } else { mask };
+        let mut first = true;
+        for (group, shift) in [(false, 0), (true, 8)] {
+            let bits = (mask >> shift) & 0xFF;
+            let mut reg = 0;
+            while reg < 8 {
+                if bits & (1 << reg) == 0 {
+                    reg += 1;
+                    continue;
+                }
+                let start = reg;
+                while reg < 8 && bits & (1 << reg) != 0 {
+                    reg += 1;
+                }
+                if !first {
+                    self.text("/");
+                }
+                first = false;
+                let push = |d: &mut Self, num: u16| {
+                    if group {
+                        d.addr_reg(num)
+                    } else {
+                        d.data_reg(num)
+                    }
+                };
+                push(self, start);
+                if reg - 1 > start {
+                    self.text("-");
+                    push(self, reg - 1);
+                }
+            }
+        }
+        if first {
+            self.text("#");
+            self.unsigned(0);
+        }
+    }
+
+    /// Names a classic Mac OS A-line trap, such as `_NewHandle ,SYS`. Toolbox traps (bit
+    /// 11 set) are numbered by bits 0-9 with an auto-pop flag in bit 10. OS traps are
+    /// numbered by bits 0-7 with flags in bits 9 and 10.
+    fn a_line_trap(&mut self, code: u16) -> String {
+        let (name, flags): (Option<&str>, &[(u16, &str)]) = if code & 0x800 != 0 {
+            let name = TOOLBOX_TRAPS.iter().find(|(c, _)| *c == code & !0x400).map(|(_, n)| *n);
+            (name, &[(0x400, "AUTOPOP")])
+        } else {
+            let num = code & 0xFF;
+            let name = OS_TRAPS.iter().find(|(c, _)| *c == num).map(|(_, n)| *n);
+            let flags: &[(u16, &str)] = match num {
+                // Device Manager
+                0x04..=0x06 => &[(0x400, "ASYNC"), (0x200, "IMMED")],
+                // File Manager
+                0x00..=0x18 | 0x44 => &[(0x400, "ASYNC"), (0x200, "HFS")],
+                // Memory Manager
+                0x19..=0x36 | 0x49 | 0x4A | 0x4C | 0x63 => &[(0x400, "SYS"), (0x200, "CLEAR")],
+                _ => &[],
+            };
+            (name, flags)
+        };
+        let Some(name) = name else {
+            self.unsigned(code as u64);
+            return "dc.w".to_string();
+        };
+        for &(bit, flag) in flags {
+            if code & bit != 0 {
+                if !self.args.is_empty() {
+                    self.args.push(ObjInsArg::PlainText(self.config.separator().into()));
+                }
+                self.opaque(flag.to_string());
+            }
+        }
+        format!("_{name}")
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Size {
+    Byte,
+    Word,
+    Long,
+}
+
+impl Size {
+    fn suffix(self) -> &'static str {
+        match self {
+            Size::Byte => ".b",
+            Size::Word => ".w",
+            Size::Long => ".l",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum SizeField {
+    /// Unsized, such as lea or exg
+    None,
+    Fixed(Size),
+    /// Bits 6-7: 00 = byte, 01 = word, 10 = long
+    Bits6,
+    /// Bit 6: 0 = word, 1 = long
+    Bit6,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Operand {
+    /// Effective address from bits 0-5
+    Ea,
+    /// Destination effective address of move from bits 6-11
+    MoveDst,
+    /// Data register from bits 0-2
+    Dy,
+    /// Data register from bits 9-11
+    Dx,
+    /// Address register from bits 0-2
+    Ay,
+    /// Address register from bits 9-11
+    Ax,
+    AyInc,
+    AxInc,
+    AyDec,
+    AxDec,
+    /// d16(Ay), for movep
+    AyDisp,
+    /// Immediate of the instruction size
+    Imm,
+    /// Bit number from the low byte of an extension word
+    BitNum,
+    /// 16-bit signed immediate extension word
+    Imm16,
+    /// 3-bit immediate from bits 9-11, where 0 means 8
+    Quick3,
+    /// Signed 8-bit immediate from bits 0-7
+    Quick8,
+    /// Trap vector from bits 0-3
+    Vector,
+    /// Shift count: a Quick3 immediate, or Dx if bit 5 is set
+    ShiftCount,
+    /// 8-bit displacement, or a 16-bit extension word if zero
+    Branch8,
+    /// 16-bit displacement extension word
+    Branch16,
+    /// movem register mask
+    RegList,
+    /// Fixed register
+    Reg(&'static str),
+}
+
+struct Opcode {
+    mask: u16,
+    pattern: u16,
+    mnemonic: &'static str,
+    size: SizeField,
+    operands: &'static [Operand],
+    /// Whether the condition code in bits 8-11 is appended to the mnemonic
+    cond: bool,
+}
+
+const fn op(
+    mask: u16,
+    pattern: u16,
+    mnemonic: &'static str,
+    size: SizeField,
+    operands: &'static [Operand],
+) -> Opcode {
+    Opcode { mask, pattern, mnemonic, size, operands, cond: false }
+}
+
+const fn cond(
+    mask: u16,
+    pattern: u16,
+    mnemonic: &'static str,
+    size: SizeField,
+    operands: &'static [Operand],
+) -> Opcode {
+    Opcode { mask, pattern, mnemonic, size, operands, cond: true }
+}
+
+const CONDITIONS: [&str; 16] =
+    ["t", "f", "hi", "ls", "cc", "cs", "ne", "eq", "vc", "vs", "pl", "mi", "ge", "lt", "gt", "le"];
+
+use Operand::*;
+use Size::*;
+use SizeField::{Bit6, Bits6, Fixed};
+
+const B: SizeField = Fixed(Byte);
+const W: SizeField = Fixed(Word);
+const L: SizeField = Fixed(Long);
+const U: SizeField = SizeField::None;
+
+/// 68000 instruction table, with the 68010 additions. More specific masks must come first.
+static OPCODES: &[Opcode] = &[
+    // Group 0: immediates, bit operations and movep
+    op(0xFFFF, 0x003C, "ori", B, &[Imm, Reg("ccr")]),
+    op(0xFFFF, 0x007C, "ori", W, &[Imm, Reg("sr")]),
+    op(0xFFFF, 0x023C, "andi", B, &[Imm, Reg("ccr")]),
+    op(0xFFFF, 0x027C, "andi", W, &[Imm, Reg("sr")]),
+    op(0xFFFF, 0x0A3C, "eori", B, &[Imm, Reg("ccr")]),
+    op(0xFFFF, 0x0A7C, "eori", W, &[Imm, Reg("sr")]),
+    op(0xF1F8, 0x0108, "movep", W, &[AyDisp, Dx]),
+    op(0xF1F8, 0x0148, "movep", L, &[AyDisp, Dx]),
+    op(0xF1F8, 0x0188, "movep", W, &[Dx, AyDisp]),
+    op(0xF1F8, 0x01C8, "movep", L, &[Dx, AyDisp]),
+    op(0xF1C0, 0x0100, "btst", U, &[Dx, Ea]),
+    op(0xF1C0, 0x0140, "bchg", U, &[Dx, Ea]),
+    op(0xF1C0, 0x0180, "bclr", U, &[Dx, Ea]),
+    op(0xF1C0, 0x01C0, "bset", U, &[Dx, Ea]),
+    op(0xFFC0, 0x0800, "btst", U, &[BitNum, Ea]),
+    op(0xFFC0, 0x0840, "bchg", U, &[BitNum, Ea]),
+    op(0xFFC0, 0x0880, "bclr", U, &[BitNum, Ea]),
+    op(0xFFC0, 0x08C0, "bset", U, &[BitNum, Ea]),
+    op(0xFF00, 0x0000, "ori", Bits6, &[Imm, Ea]),
+    op(0xFF00, 0x0200, "andi", Bits6, &[Imm, Ea]),
+    op(0xFF00, 0x0400, "subi", Bits6, &[Imm, Ea]),
+    op(0xFF00, 0x0600, "addi", Bits6, &[Imm, Ea]),
+    op(0xFF00, 0x0A00, "eori", Bits6, &[Imm, Ea]),
+    op(0xFF00, 0x0C00, "cmpi", Bits6, &[Imm, Ea]),
+    // Groups 1-3: moves
+    op(0xF1C0, 0x2040, "movea", L, &[Ea, Ax]),
+    op(0xF1C0, 0x3040, "movea", W, &[Ea, Ax]),
+    op(0xF000, 0x1000, "move", B, &[Ea, MoveDst]),
+    op(0xF000, 0x2000, "move", L, &[Ea, MoveDst]),
+    op(0xF000, 0x3000, "move", W, &[Ea, MoveDst]),
+    // Group 4: miscellaneous
+    op(0xFFFF, 0x4AFC, "illegal", U, &[]),
+    op(0xFFFF, 0x4E70, "reset", U, &[]),
+    op(0xFFFF, 0x4E71, "nop", U, &[]),
+    op(0xFFFF, 0x4E72, "stop", U, &[Imm16]),
+    op(0xFFFF, 0x4E73, "rte", U, &[]),
+    op(0xFFFF, 0x4E74, "rtd", U, &[Imm16]),
+    op(0xFFFF, 0x4E75, "rts", U, &[]),
+    op(0xFFFF, 0x4E76, "trapv", U, &[]),
+    op(0xFFFF, 0x4E77, "rtr", U, &[]),
+    op(0xFFF0, 0x4E40, "trap", U, &[Vector]),
+    op(0xFFF8, 0x4E50, "link", U, &[Ay, Imm16]),
+    op(0xFFF8, 0x4E58, "unlk", U, &[Ay]),
+    op(0xFFF8, 0x4E60, "move", L, &[Ay, Reg("usp")]),
+    op(0xFFF8, 0x4E68, "move", L, &[Reg("usp"), Ay]),
+    op(0xFFF8, 0x4840, "swap", U, &[Dy]),
+    op(0xFFF8, 0x4880, "ext", W, &[Dy]),
+    op(0xFFF8, 0x48C0, "ext", L, &[Dy]),
+    op(0xFFC0, 0x40C0, "move", W, &[Reg("sr"), Ea]),
+    op(0xFFC0, 0x42C0, "move", W, &[Reg("ccr"), Ea]),
+    op(0xFFC0, 0x44C0, "move", W, &[Ea, Reg("ccr")]),
+    op(0xFFC0, 0x46C0, "move", W, &[Ea, Reg("sr")]),
+    op(0xFFC0, 0x4800, "nbcd", B, &[Ea]),
+    op(0xFFC0, 0x4840, "pea", U, &[Ea]),
+    op(0xFFC0, 0x4AC0, "tas", B, &[Ea]),
+    op(0xFFC0, 0x4E80, "jsr", U, &[Ea]),
+    op(0xFFC0, 0x4EC0, "jmp", U, &[Ea]),
+    op(0xFF80, 0x4880, "movem", Bit6, &[RegList, Ea]),
+    op(0xFF80, 0x4C80, "movem", Bit6, &[Ea, RegList]),
+    op(0xF1C0, 0x41C0, "lea", U, &[Ea, Ax]),
+    op(0xF1C0, 0x4180, "chk", W, &[Ea, Dx]),
+    op(0xFF00, 0x4000, "negx", Bits6, &[Ea]),
+    op(0xFF00, 0x4200, "clr", Bits6, &[Ea]),
+    op(0xFF00, 0x4400, "neg", Bits6, &[Ea]),
+    op(0xFF00, 0x4600, "not", Bits6, &[Ea]),
+    op(0xFF00, 0x4A00, "tst", Bits6, &[Ea]),
+    // Group 5: quick arithmetic and conditions
+    cond(0xF0F8, 0x50C8, "db", U, &[Dy, Branch16]),
+    cond(0xF0C0, 0x50C0, "s", U, &[Ea]),
+    op(0xF100, 0x5000, "addq", Bits6, &[Quick3, Ea]),
+    op(0xF100, 0x5100, "subq", Bits6, &[Quick3, Ea]),
+    // Group 6: branches
+    op(0xFF00, 0x6000, "bra", U, &[Branch8]),
+    op(0xFF00, 0x6100, "bsr", U, &[Branch8]),
+    cond(0xF000, 0x6000, "b", U, &[Branch8]),
+    // Group 7
+    op(0xF100, 0x7000, "moveq", U, &[Quick8, Dx]),
+    // Group 8: or, division
+    op(0xF1C0, 0x80C0, "divu", W, &[Ea, Dx]),
+    op(0xF1C0, 0x81C0, "divs", W, &[Ea, Dx]),
+    op(0xF1F8, 0x8100, "sbcd", U, &[Dy, Dx]),
+    op(0xF1F8, 0x8108, "sbcd", U, &[AyDec, AxDec]),
+    op(0xF100, 0x8000, "or", Bits6, &[Ea, Dx]),
+    op(0xF100, 0x8100, "or", Bits6, &[Dx, Ea]),
+    // Group 9: subtraction
+    op(0xF1C0, 0x90C0, "suba", W, &[Ea, Ax]),
+    op(0xF1C0, 0x91C0, "suba", L, &[Ea, Ax]),
+    op(0xF138, 0x9100, "subx", Bits6, &[Dy, Dx]),
+    op(0xF138, 0x9108, "subx", Bits6, &[AyDec, AxDec]),
+    op(0xF100, 0x9000, "sub", Bits6, &[Ea, Dx]),
+    op(0xF100, 0x9100, "sub", Bits6, &[Dx, Ea]),
+    // Group B: comparison, eor
+    op(0xF1C0, 0xB0C0, "cmpa", W, &[Ea, Ax]),
+    op(0xF1C0, 0xB1C0, "cmpa", L, &[Ea, Ax]),
+    op(0xF138, 0xB108, "cmpm", Bits6, &[AyInc, AxInc]),
+    op(0xF100, 0xB000, "cmp", Bits6, &[Ea, Dx]),
+    op(0xF100, 0xB100, "eor", Bits6, &[Dx, Ea]),
+    // Group C: and, multiplication, exchange
+    op(0xF1C0, 0xC0C0, "mulu", W, &[Ea, Dx]),
+    op(0xF1C0, 0xC1C0, "muls", W, &[Ea, Dx]),
+    op(0xF1F8, 0xC100, "abcd", U, &[Dy, Dx]),
+    op(0xF1F8, 0xC108, "abcd", U, &[AyDec, AxDec]),
+    op(0xF1F8, 0xC140, "exg", U, &[Dx, Dy]),
+    op(0xF1F8, 0xC148, "exg", U, &[Ax, Ay]),
+    op(0xF1F8, 0xC188, "exg", U, &[Dx, Ay]),
+    op(0xF100, 0xC000, "and", Bits6, &[Ea, Dx]),
+    op(0xF100, 0xC100, "and", Bits6, &[Dx, Ea]),
+    // Group D: addition
+    op(0xF1C0, 0xD0C0, "adda", W, &[Ea, Ax]),
+    op(0xF1C0, 0xD1C0, "adda", L, &[Ea, Ax]),
+    op(0xF138, 0xD100, "addx", Bits6, &[Dy, Dx]),
+    op(0xF138, 0xD108, "addx", Bits6, &[AyDec, AxDec]),
+    op(0xF100, 0xD000, "add", Bits6, &[Ea, Dx]),
+    op(0xF100, 0xD100, "add", Bits6, &[Dx, Ea]),
+    // Group E: shifts and rotates, of memory then of registers
+    op(0xFFC0, 0xE0C0, "asr", W, &[Ea]),
+    op(0xFFC0, 0xE1C0, "asl", W, &[Ea]),
+    op(0xFFC0, 0xE2C0, "lsr", W, &[Ea]),
+    op(0xFFC0, 0xE3C0, "lsl", W, &[Ea]),
+    op(0xFFC0, 0xE4C0, "roxr", W, &[Ea]),
+    op(0xFFC0, 0xE5C0, "roxl", W, &[Ea]),
+    op(0xFFC0, 0xE6C0, "ror", W, &[Ea]),
+    op(0xFFC0, 0xE7C0, "rol", W, &[Ea]),
+    op(0xF118, 0xE000, "asr", Bits6, &[ShiftCount, Dy]),
+    op(0xF118, 0xE100, "asl", Bits6, &[ShiftCount, Dy]),
+    op(0xF118, 0xE008, "lsr", Bits6, &[ShiftCount, Dy]),
+    op(0xF118, 0xE108, "lsl", Bits6, &[ShiftCount, Dy]),
+    op(0xF118, 0xE010, "roxr", Bits6, &[ShiftCount, Dy]),
+    op(0xF118, 0xE110, "roxl", Bits6, &[ShiftCount, Dy]),
+    op(0xF118, 0xE018, "ror", Bits6, &[ShiftCount, Dy]),
+    op(0xF118, 0xE118, "rol", Bits6, &[ShiftCount, Dy]),
+];
+
+/// Classic Mac OS Toolbox traps, without the auto-pop bit.
+static TOOLBOX_TRAPS: &[(u16, &str)] = &[
+    (0xA850, "InitCursor"),
+    (0xA851, "SetCursor"),
+    (0xA852, "HideCursor"),
+    (0xA853, "ShowCursor"),
+    (0xA860, "WaitNextEvent"),
+    (0xA862, "ForeColor"),
+    (0xA863, "BackColor"),
+    (0xA86E, "InitGraf"),
+    (0xA873, "SetPort"),
+    (0xA874, "GetPort"),
+    (0xA883, "DrawChar"),
+    (0xA884, "DrawString"),
+    (0xA885, "DrawText"),
+    (0xA887, "TextFont"),
+    (0xA888, "TextFace"),
+    (0xA889, "TextMode"),
+    (0xA88A, "TextSize"),
+    (0xA88B, "GetFontInfo"),
+    (0xA88C, "StringWidth"),
+    (0xA891, "LineTo"),
+    (0xA893, "MoveTo"),
+    (0xA89B, "PenSize"),
+    (0xA89E, "PenNormal"),
+    (0xA8A1, "FrameRect"),
+    (0xA8A2, "PaintRect"),
+    (0xA8A3, "EraseRect"),
+    (0xA8A7, "SetRect"),
+    (0xA8A8, "OffsetRect"),
+    (0xA8A9, "InsetRect"),
+    (0xA8AD, "PtInRect"),
+    (0xA8D8, "NewRgn"),
+    (0xA8D9, "DisposeRgn"),
+    (0xA8FE, "InitFonts"),
+    (0xA912, "InitWindows"),
+    (0xA913, "NewWindow"),
+    (0xA914, "DisposeWindow"),
+    (0xA915, "ShowWindow"),
+    (0xA916, "HideWindow"),
+    (0xA91A, "SetWTitle"),
+    (0xA91F, "SelectWindow"),
+    (0xA922, "BeginUpdate"),
+    (0xA923, "EndUpdate"),
+    (0xA925, "DragWindow"),
+    (0xA928, "InvalRect"),
+    (0xA92C, "FindWindow"),
+    (0xA92D, "CloseWindow"),
+    (0xA930, "InitMenus"),
+    (0xA933, "AppendMenu"),
+    (0xA935, "InsertMenu"),
+    (0xA937, "DrawMenuBar"),
+    (0xA938, "HiliteMenu"),
+    (0xA939, "EnableItem"),
+    (0xA93A, "DisableItem"),
+    (0xA93D, "MenuSelect"),
+    (0xA93E, "MenuKey"),
+    (0xA945, "CheckItem"),
+    (0xA946, "GetItem"),
+    (0xA949, "GetMHandle"),
+    (0xA954, "NewControl"),
+    (0xA968, "TrackControl"),
+    (0xA969, "DrawControls"),
+    (0xA96C, "FindControl"),
+    (0xA970, "GetNextEvent"),
+    (0xA972, "GetMouse"),
+    (0xA973, "StillDown"),
+    (0xA974, "Button"),
+    (0xA975, "TickCount"),
+    (0xA97B, "InitDialogs"),
+    (0xA97C, "GetNewDialog"),
+    (0xA983, "DisposeDialog"),
+    (0xA985, "Alert"),
+    (0xA986, "StopAlert"),
+    (0xA98B, "ParamText"),
+    (0xA98D, "GetDItem"),
+    (0xA98F, "SetIText"),
+    (0xA990, "GetIText"),
+    (0xA991, "ModalDialog"),
+    (0xA9A0, "GetResource"),
+    (0xA9A3, "ReleaseResource"),
+    (0xA9B4, "SystemTask"),
+    (0xA9BA, "GetString"),
+    (0xA9BD, "GetNewWindow"),
+    (0xA9BF, "GetMenu"),
+    (0xA9C8, "SysBeep"),
+    (0xA9CC, "TEInit"),
+    (0xA9D2, "TENew"),
+    (0xA9D4, "TEClick"),
+    (0xA9DA, "TEIdle"),
+    (0xA9DC, "TEKey"),
+    (0xA9E1, "HandToHand"),
+    (0xA9E3, "PtrToHand"),
+    (0xA9E7, "Pack0"),
+    (0xA9E8, "Pack1"),
+    (0xA9E9, "Pack2"),
+    (0xA9EA, "Pack3"),
+    (0xA9EB, "Pack4"),
+    (0xA9EC, "Pack5"),
+    (0xA9ED, "Pack6"),
+    (0xA9EE, "Pack7"),
+    (0xA9F0, "LoadSeg"),
+    (0xA9F1, "UnloadSeg"),
+    (0xA9F4, "ExitToShell"),
+    (0xA9FF, "Debugger"),
+    (0xABFF, "DebugStr"),
+];
+
+/// Classic Mac OS Operating System traps, by the trap number in bits 0-7.
+static OS_TRAPS: &[(u16, &str)] = &[
+    (0x00, "Open"),
+    (0x01, "Close"),
+    (0x02, "Read"),
+    (0x03, "Write"),
+    (0x04, "Control"),
+    (0x05, "Status"),
+    (0x06, "KillIO"),
+    (0x07, "GetVolInfo"),
+    (0x08, "Create"),
+    (0x09, "Delete"),
+    (0x0A, "OpenRF"),
+    (0x0B, "Rename"),
+    (0x0C, "GetFileInfo"),
+    (0x0D, "SetFileInfo"),
+    (0x11, "GetEOF"),
+    (0x12, "SetEOF"),
+    (0x13, "FlushVol"),
+    (0x14, "GetVol"),
+    (0x15, "SetVol"),
+    (0x17, "Eject"),
+    (0x18, "GetFPos"),
+    (0x19, "InitZone"),
+    (0x1A, "GetZone"),
+    (0x1B, "SetZone"),
+    (0x1C, "FreeMem"),
+    (0x1D, "MaxMem"),
+    (0x1E, "NewPtr"),
+    (0x1F, "DisposePtr"),
+    (0x20, "SetPtrSize"),
+    (0x21, "GetPtrSize"),
+    (0x22, "NewHandle"),
+    (0x23, "DisposeHandle"),
+    (0x24, "SetHandleSize"),
+    (0x25, "GetHandleSize"),
+    (0x26, "HandleZone"),
+    (0x27, "ReallocHandle"),
+    (0x28, "RecoverHandle"),
+    (0x29, "HLock"),
+    (0x2A, "HUnlock"),
+    (0x2B, "EmptyHandle"),
+    (0x2C, "InitApplZone"),
+    (0x2D, "SetApplLimit"),
+    (0x2E, "BlockMove"),
+    (0x2F, "PostEvent"),
+    (0x30, "OSEventAvail"),
+    (0x31, "GetOSEvent"),
+    (0x32, "FlushEvents"),
+    (0x36, "MoreMasters"),
+    (0x3B, "Delay"),
+    (0x44, "SetFPos"),
+    (0x46, "GetTrapAddress"),
+    (0x47, "SetTrapAddress"),
+    (0x49, "HPurge"),
+    (0x4A, "HNoPurge"),
+    (0x4C, "CompactMem"),
+    (0x55, "StripAddress"),
+    (0x63, "MaxApplZone"),
+];
@@ -1,15 +1,29 @@
-use std::{borrow::Cow, sync::Mutex};
+use std::{borrow::Cow, collections::HashSet, sync::Mutex};
 
 use anyhow::{anyhow, bail, Result};
-use object::{elf, Endian, Endianness, File, FileFlags, Object, Relocation, RelocationFlags};
+use object::{
+    elf, Endian, Endianness, File, FileFlags, Object, ObjectSymbol, Relocation, RelocationFlags,
+    SymbolFlags,
+};
 use rabbitizer::{config, Abi, InstrCategory, Instruction, OperandType};
 
 use crate::{
-    arch::{ObjArch, ProcessCodeResult},
-    diff::{DiffObjConfig, MipsAbi, MipsInstrCategory},
-    obj::{ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjReloc, ObjSection, SymbolRef},
+    arch::{bytes_ins, ObjArch, ProcessCodeResult, RegisterClass, OP_BYTES},
+    diff::{
+        frame::{ins_values, mem_operand, parse_imm, StackFrame},
+        signature::{infer_signature, RegisterUse, Signature},
+        DiffObjConfig, MipsAbi, MipsInstrCategory,
+    },
+    obj::{
+        ObjConstant, ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjReloc, ObjSection, ObjSymbol,
+        SymbolRef,
+    },
 };
 
+mod mips16;
+
+use mips16::Mips16Arg;
+
 static RABBITIZER_MUTEX: Mutex<()> = Mutex::new(());
 
 fn configure_rabbitizer(abi: Abi) {
@@ -22,6 +36,8 @@ pub struct ObjArchMips {
     pub endianness: Endianness,
     pub abi: Abi,
     pub instr_category: InstrCategory,
+    /// Section index and address of functions marked as MIPS16e code
+    pub mips16_symbols: HashSet<(usize, u64)>,
 }
 
 const EF_MIPS_ABI: u32 = 0x0000F000;
@@ -30,6 +46,8 @@ const EF_MIPS_MACH: u32 = 0x00FF0000;
 const E_MIPS_MACH_ALLEGREX: u32 = 0x00840000;
 const E_MIPS_MACH_5900: u32 = 0x00920000;
 
+const STO_MIPS16: u8 = 0xF0;
+
 impl ObjArchMips {
     pub fn new(object: &File) -> Result<Self> {
         let mut abi = Abi::NUMERIC;
@@ -50,7 +68,122 @@ impl ObjArchMips {
             }
             _ => bail!("Unsupported MIPS file flags"),
         }
-        Ok(Self { endianness: object.endianness(), abi, instr_category })
+        let mips16_symbols = object
+            .symbols()
+            .filter(|s| match s.flags() {
+                SymbolFlags::Elf { st_other, .. } => st_other & STO_MIPS16 == STO_MIPS16,
+                _ => false,
+            })
+            .filter_map(|s| Some((s.section_index()?.0, s.address() & !1)))
+            .collect();
+        Ok(Self { endianness: object.endianness(), abi, instr_category, mips16_symbols })
+    }
+
+    /// Big-endian o32 code without an object file header, such as an N64 overlay or,
+    /// if `rsp` is set, RSP microcode.
+    pub fn new_binary(rsp: bool) -> Self {
+        Self {
+            endianness: Endianness::Big,
+            abi: Abi::O32,
+            instr_category: if rsp { InstrCategory::RSP } else { InstrCategory::CPU },
+            mips16_symbols: HashSet::new(),
+        }
+    }
+}
+
+impl ObjArchMips {
+    /// The two halfwords of a 32-bit MIPS16e instruction, in instruction order.
+    fn mips16_halves(&self, data: [u8; 4]) -> (u32, u32) {
+        let first = self.endianness.read_u16_bytes([data[0], data[1]]) as u32;
+        let second = self.endianness.read_u16_bytes([data[2], data[3]]) as u32;
+        (first, second)
+    }
+
+    fn process_mips16(
+        &self,
+        section: &ObjSection,
+        symbol: &ObjSymbol,
+        config: &DiffObjConfig,
+    ) -> Result<ProcessCodeResult> {
+        // MIPS16e symbols may have the ISA bit set
+        let start_address = symbol.address & !1;
+        let start = (symbol.section_address & !1) as usize;
+        let code = section.data[start..start + symbol.size as usize]
+            .chunks_exact(2)
+            .map(|c| self.endianness.read_u16_bytes(c.try_into().unwrap()))
+            .collect::<Vec<_>>();
+
+        let mut ops = Vec::<u16>::with_capacity(code.len());
+        let mut insts = Vec::<ObjIns>::with_capacity(code.len());
+        let mut idx = 0;
+        while idx < code.len() {
+            let cur_addr = start_address + idx as u64 * 2;
+            let ins = mips16::decode(&code[idx..], cur_addr);
+            let ins_size = ins.as_ref().map_or(2, |i| i.size) as u64;
+            let reloc = section
+                .relocations
+                .iter()
+                .find(|r| r.address >= cur_addr && r.address < cur_addr + ins_size);
+            let Some(ins) = ins else {
+                let offset = start + idx * 2;
+                let line = section.line_info.range(..=cur_addr).last().map(|(_, &b)| b);
+                ops.push(OP_BYTES);
+                insts.push(bytes_ins(cur_addr, &section.data[offset..offset + 2], reloc, line));
+                idx += 1;
+                continue;
+            };
+
+            let mut args = Vec::with_capacity(ins.args.len() * 2);
+            let mut branch_dest = None;
+            // The immediate, displacement or jump target is the relocated field
+            let mut reloc_pending = reloc;
+            for (arg_idx, arg) in ins.args.iter().enumerate() {
+                if arg_idx > 0 {
+                    args.push(ObjInsArg::PlainText(config.separator().into()));
+                }
+                match *arg {
+                    Mips16Arg::Reg(name) => {
+                        args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(name.into())));
+                    }
+                    Mips16Arg::Imm(_) | Mips16Arg::JumpTarget(_) if reloc_pending.is_some() => {
+                        push_reloc(&mut args, reloc_pending.take().unwrap())?;
+                    }
+                    Mips16Arg::Imm(imm) => args.push(ObjInsArg::Arg(ObjInsArgValue::Signed(imm))),
+                    Mips16Arg::Offset(disp, base) => {
+                        if let Some(reloc) = reloc_pending.take() {
+                            push_reloc(&mut args, reloc)?;
+                        } else {
+                            args.push(ObjInsArg::Arg(ObjInsArgValue::Signed(disp)));
+                        }
+                        args.push(ObjInsArg::PlainText("(".into()));
+                        args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(base.into())));
+                        args.push(ObjInsArg::PlainText(")".into()));
+                    }
+                    Mips16Arg::BranchDest(dest) => {
+                        args.push(ObjInsArg::BranchDest(dest));
+                        branch_dest = Some(dest);
+                    }
+                    Mips16Arg::JumpTarget(dest) => {
+                        args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(dest)));
+                    }
+                }
+            }
+            ops.push(ins.op);
+            insts.push(ObjIns {
+                address: cur_addr,
+                size: ins.size,
+                op: ins.op,
+                mnemonic: ins.mnemonic.to_string(),
+                args,
+                reloc: reloc.cloned(),
+                branch_dest,
+                line: section.line_info.range(..=cur_addr).last().map(|(_, &b)| b),
+                formatted: ins.to_string(),
+                orig: None,
+            });
+            idx += ins.size as usize / 2;
+        }
+        Ok(ProcessCodeResult { ops, insts })
     }
 }
 
@@ -63,6 +196,16 @@ impl ObjArch for ObjArchMips {
     ) -> Result<ProcessCodeResult> {
         let (section, symbol) = obj.section_symbol(symbol_ref);
         let section = section.ok_or_else(|| anyhow!("Code symbol section not found"))?;
+        let mips16 = match config.mips_instr_category {
+            MipsInstrCategory::Mips16e => true,
+            MipsInstrCategory::Auto => {
+                self.mips16_symbols.contains(&(section.orig_index, symbol.address & !1))
+            }
+            _ => false,
+        };
+        if mips16 {
+            return self.process_mips16(section, symbol, config);
+        }
         let code = &section.data
             [symbol.section_address as usize..(symbol.section_address + symbol.size) as usize];
 
@@ -80,6 +223,7 @@ impl ObjArch for ObjArchMips {
             MipsInstrCategory::R3000Gte => InstrCategory::R3000GTE,
             MipsInstrCategory::R4000Allegrex => InstrCategory::R4000ALLEGREX,
             MipsInstrCategory::R5900 => InstrCategory::R5900,
+            MipsInstrCategory::Mips16e => unreachable!(),
         };
 
         let start_address = symbol.address;
@@ -93,6 +237,14 @@ impl ObjArch for ObjArchMips {
             let code = self.endianness.read_u32_bytes(chunk.try_into()?);
             let instruction = Instruction::new(code, cur_addr, instr_category);
 
+            if !instruction.is_valid() {
+                let line = section.line_info.range(..=cur_addr as u64).last().map(|(_, &b)| b);
+                ops.push(OP_BYTES);
+                insts.push(bytes_ins(cur_addr as u64, chunk, reloc, line));
+                cur_addr += 4;
+                continue;
+            }
+
             let formatted = instruction.disassemble(None, 0);
             let op = instruction.unique_id as u16;
             ops.push(op);
@@ -170,6 +322,13 @@ impl ObjArch for ObjArchMips {
             });
             cur_addr += 4;
         }
+        // Trailing bytes too short for an instruction
+        let remainder = code.chunks_exact(4).remainder();
+        if !remainder.is_empty() {
+            let line = section.line_info.range(..=cur_addr as u64).last().map(|(_, &b)| b);
+            ops.push(OP_BYTES);
+            insts.push(bytes_ins(cur_addr as u64, remainder, None, line));
+        }
         Ok(ProcessCodeResult { ops, insts })
     }
 
@@ -179,7 +338,7 @@ impl ObjArch for ObjArchMips {
         address: u64,
         reloc: &Relocation,
     ) -> Result<i64> {
-        let data = section.data[address as usize..address as usize + 4].try_into()?;
+        let data: [u8; 4] = section.data[address as usize..address as usize + 4].try_into()?;
         let addend = self.endianness.read_u32_bytes(data);
         Ok(match reloc.flags() {
             RelocationFlags::Elf { r_type: elf::R_MIPS_32 } => addend as i64,
@@ -191,6 +350,20 @@ impl ObjArch for ObjArchMips {
                     elf::R_MIPS_LO16 | elf::R_MIPS_GOT16 | elf::R_MIPS_CALL16 | elf::R_MIPS_GPREL16,
             } => (addend & 0x0000FFFF) as i16 as i64,
             RelocationFlags::Elf { r_type: elf::R_MIPS_26 } => ((addend & 0x03FFFFFF) << 2) as i64,
+            RelocationFlags::Elf { r_type: elf::R_MIPS16_26 } => {
+                // jal: target[20:16] and target[25:21] in the first halfword
+                let (first, second) = self.mips16_halves(data);
+                let target = (first & 0x1F) << 21 | ((first >> 5) & 0x1F) << 16 | second;
+                (target << 2) as i64
+            }
+            RelocationFlags::Elf { r_type: elf::R_MIPS16_HI16 } => {
+                let (ext, ins) = self.mips16_halves(data);
+                ((mips16_extended_imm(ext, ins) as u32) << 16) as i32 as i64
+            }
+            RelocationFlags::Elf { r_type: elf::R_MIPS16_LO16 | elf::R_MIPS16_GPREL } => {
+                let (ext, ins) = self.mips16_halves(data);
+                mips16_extended_imm(ext, ins) as i16 as i64
+            }
             flags => bail!("Unsupported MIPS implicit relocation {flags:?}"),
         })
     }
@@ -205,22 +378,187 @@ impl ObjArch for ObjArchMips {
                 elf::R_MIPS_GPREL16 => Cow::Borrowed("R_MIPS_GPREL16"),
                 elf::R_MIPS_32 => Cow::Borrowed("R_MIPS_32"),
                 elf::R_MIPS_26 => Cow::Borrowed("R_MIPS_26"),
+                elf::R_MIPS16_26 => Cow::Borrowed("R_MIPS16_26"),
+                elf::R_MIPS16_GPREL => Cow::Borrowed("R_MIPS16_GPREL"),
+                elf::R_MIPS16_HI16 => Cow::Borrowed("R_MIPS16_HI16"),
+                elf::R_MIPS16_LO16 => Cow::Borrowed("R_MIPS16_LO16"),
                 _ => Cow::Owned(format!("<{flags:?}>")),
             },
             _ => Cow::Owned(format!("<{flags:?}>")),
         }
     }
+
+    fn has_delay_slot(&self, ins: &ObjIns) -> bool { mips16::has_delay_slot(ins.op) }
+
+    fn is_return(&self, ins: &ObjIns) -> bool {
+        ins.mnemonic == "jr"
+            && matches!(
+                ins.args.first(),
+                Some(ObjInsArg::Arg(ObjInsArgValue::Opaque(reg))) if matches!(reg.as_ref(), "$ra" | "ra")
+            )
+    }
+
+    fn is_indirect_jump(&self, ins: &ObjIns) -> bool {
+        ins.mnemonic == "jr" && !self.is_return(ins)
+    }
+
+    fn register_class(&self, arg: &ObjInsArgValue) -> Option<RegisterClass> {
+        let ObjInsArgValue::Opaque(name) = arg else {
+            return None;
+        };
+        let name = name.strip_prefix('$')?;
+        match name {
+            // Registers with fixed roles
+            "zero" | "0" | "at" | "k0" | "k1" | "gp" | "sp" | "fp" | "ra" | "pc" => None,
+            _ if name.starts_with('f') => Some(RegisterClass::Fpr),
+            // EE VU0 floating point and integer registers
+            _ if name.starts_with("vf") || name.starts_with("vi") => Some(RegisterClass::Vector),
+            _ if name.chars().all(|c| c.is_ascii_alphanumeric()) => Some(RegisterClass::Gpr),
+            _ => None,
+        }
+    }
+
+    fn data_constant(&self, ins: &ObjIns, data: &[u8]) -> Option<ObjConstant> {
+        match ins.mnemonic.as_str() {
+            "lwc1" => {
+                let bits = self.endianness.read_u32_bytes(data.get(..4)?.try_into().ok()?);
+                Some(ObjConstant::Float(f32::from_bits(bits)))
+            }
+            "ldc1" => {
+                let bits = self.endianness.read_u64_bytes(data.get(..8)?.try_into().ok()?);
+                Some(ObjConstant::Double(f64::from_bits(bits)))
+            }
+            _ => None,
+        }
+    }
+
+    fn stack_frame(&self, insts: &[&ObjIns]) -> Option<StackFrame> {
+        let mut frame = StackFrame::default();
+        for ins in insts {
+            let mnemonic = ins.mnemonic.as_str();
+            if matches!(mnemonic, "addiu" | "daddiu" | "addi" | "daddi") {
+                let values = ins_values(ins);
+                let (dst, src, imm) = match values[..] {
+                    [dst, src, imm] => (dst, src, imm),
+                    // MIPS16 addiu $sp, imm
+                    [dst, imm] => (dst, dst, imm),
+                    _ => continue,
+                };
+                let Some(imm) = parse_imm(imm) else {
+                    continue;
+                };
+                let sp_value =
+                    |v: &ObjInsArgValue| matches!(v, ObjInsArgValue::Opaque(n) if is_sp(n));
+                if !sp_value(src) {
+                    continue;
+                }
+                if sp_value(dst) {
+                    if frame.size.is_none() && imm < 0 {
+                        frame.size = Some(imm.unsigned_abs());
+                    }
+                } else {
+                    // Address of a stack slot
+                    frame.slots.insert(imm);
+                }
+                continue;
+            }
+            let Some((reg, offset, _)) = mem_operand(ins).filter(|(_, _, b)| is_sp(b)) else {
+                continue;
+            };
+            if matches!(
+                mnemonic,
+                "sb" | "sh" | "sw" | "sd" | "sq" | "swc1" | "sdc1" | "swl" | "swr"
+            ) {
+                if is_callee_saved(reg) && !frame.saved_registers.contains_key(reg) {
+                    frame.saved_registers.insert(reg.to_string(), offset);
+                } else {
+                    frame.slots.insert(offset);
+                }
+            } else if matches!(
+                mnemonic,
+                "lb" | "lbu"
+                    | "lh"
+                    | "lhu"
+                    | "lw"
+                    | "lwu"
+                    | "ld"
+                    | "lq"
+                    | "lwc1"
+                    | "ldc1"
+                    | "lwl"
+                    | "lwr"
+            ) {
+                frame.load(offset);
+            }
+        }
+        Some(frame)
+    }
+
+    fn signature(&self, insts: &[&ObjIns]) -> Option<Signature> {
+        Some(infer_signature(insts, arg_register, register_use))
+    }
+}
+
+/// Argument registers: $a0-$a3 ($a0-$a7 in n32 and n64) and $f12-$f19, which o32 only
+/// uses the even ones of.
+fn arg_register(reg: &str) -> Option<(RegisterClass, usize)> {
+    let name = reg.strip_prefix('$')?;
+    let (class, index) = if let Some(num) = name.strip_prefix("fa") {
+        (RegisterClass::Fpr, num.parse::<usize>().ok()?)
+    } else if let Some(num) = name.strip_prefix('f') {
+        (RegisterClass::Fpr, num.parse::<usize>().ok()?.checked_sub(12)?)
+    } else if let Some(num) = name.strip_prefix('a') {
+        (RegisterClass::Gpr, num.parse::<usize>().ok()?)
+    } else {
+        (RegisterClass::Gpr, name.parse::<usize>().ok().filter(|n| (4..=7).contains(n))? - 4)
+    };
+    (index < 8).then_some((class, index))
+}
+
+fn register_use(ins: &ObjIns) -> RegisterUse {
+    let mnemonic = ins.mnemonic.as_str();
+    match mnemonic {
+        "jal" | "jalr" | "jalx" | "bal" | "bltzal" | "bgezal" | "bltzall" | "bgezall" => {
+            RegisterUse::Call
+        }
+        "mtc1" | "dmtc1" | "ctc1" | "mtc0" | "dmtc0" | "mtc2" | "ctc2" => RegisterUse::WriteSecond,
+        "sb" | "sh" | "sw" | "sd" | "sq" | "swc1" | "sdc1" | "swl" | "swr" | "sdl" | "sdr"
+        | "sc" | "scd" | "swxc1" | "sdxc1" | "jr" | "mult" | "multu" | "dmult" | "dmultu"
+        | "div" | "divu" | "ddiv" | "ddivu" | "mthi" | "mtlo" | "teq" | "tne" | "tge" | "tgeu"
+        | "tlt" | "tltu" | "teqi" | "tnei" | "tgei" | "tgeiu" | "tlti" | "tltiu" => {
+            RegisterUse::ReadAll
+        }
+        // Branches and float compares
+        _ if mnemonic.starts_with('b') || mnemonic.starts_with("c.") => RegisterUse::ReadAll,
+        _ => RegisterUse::Default,
+    }
+}
+
+fn is_sp(name: &str) -> bool { name == "$sp" || name == "$29" }
+
+/// Registers preserved across calls in the o32 and n32 ABIs, including the return address.
+fn is_callee_saved(reg: &str) -> bool {
+    let Some(name) = reg.strip_prefix('$') else {
+        return false;
+    };
+    if name.starts_with('s') || name.starts_with("fs") || matches!(name, "fp" | "ra") {
+        return name != "sp";
+    }
+    match name.strip_prefix('f') {
+        Some(num) => matches!(num.parse::<u8>(), Ok(20..=31)),
+        None => matches!(name.parse::<u8>(), Ok(16..=23 | 30 | 31)),
+    }
 }
 
 fn push_reloc(args: &mut Vec<ObjInsArg>, reloc: &ObjReloc) -> Result<()> {
     match reloc.flags {
         RelocationFlags::Elf { r_type } => match r_type {
-            elf::R_MIPS_HI16 => {
+            elf::R_MIPS_HI16 | elf::R_MIPS16_HI16 => {
                 args.push(ObjInsArg::PlainText("%hi(".into()));
                 args.push(ObjInsArg::Reloc);
                 args.push(ObjInsArg::PlainText(")".into()));
             }
-            elf::R_MIPS_LO16 => {
+            elf::R_MIPS_LO16 | elf::R_MIPS16_LO16 => {
                 args.push(ObjInsArg::PlainText("%lo(".into()));
                 args.push(ObjInsArg::Reloc);
                 args.push(ObjInsArg::PlainText(")".into()));
@@ -235,12 +573,12 @@ fn push_reloc(args: &mut Vec<ObjInsArg>, reloc: &ObjReloc) -> Result<()> {
                 args.push(ObjInsArg::Reloc);
                 args.push(ObjInsArg::PlainText(")".into()));
             }
-            elf::R_MIPS_GPREL16 => {
+            elf::R_MIPS_GPREL16 | elf::R_MIPS16_GPREL => {
                 args.push(ObjInsArg::PlainText("%gp_rel(".into()));
                 args.push(ObjInsArg::Reloc);
                 args.push(ObjInsArg::PlainText(")".into()));
             }
-            elf::R_MIPS_32 | elf::R_MIPS_26 => {
+            elf::R_MIPS_32 | elf::R_MIPS_26 | elf::R_MIPS16_26 => {
                 args.push(ObjInsArg::Reloc);
             }
             _ => bail!("Unsupported ELF MIPS relocation type {r_type}"),
@@ -249,3 +587,9 @@ fn push_reloc(args: &mut Vec<ObjInsArg>, reloc: &ObjReloc) -> Result<()> {
     }
     Ok(())
 }
+
+/// Extract the 16-bit immediate of an `EXTEND`ed MIPS16e instruction. `imm[15:11]` and
+/// `imm[10:5]` are stored in the prefix, `imm[4:0]` in the instruction.
+fn mips16_extended_imm(ext: u32, ins: u32) -> u16 {
+    ((ext & 0x1F) << 11 | ((ext >> 5) & 0x3F) << 5 | (ins & 0x1F)) as u16
+}
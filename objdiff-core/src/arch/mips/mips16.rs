@@ -0,0 +1,324 @@
+//! Decoder for the MIPS16e compressed instruction set, which rabbitizer doesn't support.
+//!
+//! Instructions are 16 bits wide. `EXTEND` prefixes and `jal`/`jalx` form 32-bit
+//! instructions, which are decoded as a single instruction.
+
+use std::fmt;
+
+/// Mnemonics of the decoded instructions. The index is used to build the opcode ID.
+const MNEMONICS: &[&str] = &[
+    "addiu", "b", "jal", "jalx", "beqz", "bnez", "sll", "srl", "sra", "slti", "sltiu", "bteqz",
+    "btnez", "sw", "save", "restore", "move", "li", "cmpi", "lb", "lh", "lw", "lbu", "lhu", "sb",
+    "sh", "addu", "subu", "jr", "jalr", "jrc", "jalrc", "sdbbp", "slt", "sltu", "sllv", "break",
+    "srlv", "srav", "cmp", "neg", "and", "or", "xor", "not", "mfhi", "mflo", "zeb", "zeh", "seb",
+    "seh", "mult", "multu", "div", "divu", "nop",
+];
+
+/// Base of the opcode IDs, chosen to not collide with rabbitizer's instruction IDs.
+const OP_BASE: u16 = 0x8000;
+/// Set in the opcode ID of extended instructions.
+const OP_EXTENDED: u16 = 0x4000;
+
+/// O32 names of the 32 general purpose registers.
+const GPR_NAMES: [&str; 32] = [
+    "$zero", "$at", "$v0", "$v1", "$a0", "$a1", "$a2", "$a3", "$t0", "$t1", "$t2", "$t3", "$t4",
+    "$t5", "$t6", "$t7", "$s0", "$s1", "$s2", "$s3", "$s4", "$s5", "$s6", "$s7", "$t8", "$t9",
+    "$k0", "$k1", "$gp", "$sp", "$fp", "$ra",
+];
+
+/// Registers addressable by the 3-bit register fields.
+const XLAT: [usize; 8] = [16, 17, 2, 3, 4, 5, 6, 7];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Mips16Arg {
+    Reg(&'static str),
+    Imm(i64),
+    /// Displacement from a base register, as in `d($rx)`
+    Offset(i64, &'static str),
+    BranchDest(u64),
+    /// Absolute `jal` target
+    JumpTarget(u64),
+}
+
+pub struct Mips16Ins {
+    pub op: u16,
+    pub mnemonic: &'static str,
+    pub args: Vec<Mips16Arg>,
+    /// 2, or 4 for extended instructions and `jal`/`jalx`
+    pub size: u8,
+}
+
+impl fmt::Display for Mips16Arg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Mips16Arg::Reg(name) => f.write_str(name),
+            Mips16Arg::Imm(imm) => fmt_imm(f, imm),
+            Mips16Arg::Offset(disp, base) => {
+                fmt_imm(f, disp)?;
+                write!(f, "({base})")
+            }
+            Mips16Arg::BranchDest(dest) | Mips16Arg::JumpTarget(dest) => write!(f, "{dest:#x}"),
+        }
+    }
+}
+
+fn fmt_imm(f: &mut fmt::Formatter<'_>, imm: i64) -> fmt::Result {
+    if imm < 0 {
+        write!(f, "-{:#x}", -imm)
+    } else {
+        write!(f, "{imm:#x}")
+    }
+}
+
+impl fmt::Display for Mips16Ins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mnemonic)?;
+        for (i, arg) in self.args.iter().enumerate() {
+            write!(f, "{}{arg}", if i == 0 { " " } else { ", " })?;
+        }
+        Ok(())
+    }
+}
+
+/// Sign-extend the low `bits` bits of `value`.
+fn sext(value: u32, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value as i64) << shift) >> shift
+}
+
+/// Decode the instruction at `address`. `code` holds the remaining halfwords of the
+/// function. Returns `None` for reserved or unsupported (MIPS64) encodings.
+pub fn decode(code: &[u16], address: u64) -> Option<Mips16Ins> {
+    let first = *code.first()? as u32;
+    let major = first >> 11;
+    if major == 0b00011 {
+        // jal/jalx target
+        let second = *code.get(1)? as u32;
+        let target = ((first & 0x1F) << 21 | ((first >> 5) & 0x1F) << 16 | second) << 2;
+        let dest = ((address + 4) & !0x0FFF_FFFF) | target as u64;
+        let name = if first & 0x400 != 0 { "jalx" } else { "jal" };
+        return Some(Mips16Ins::new(name, vec![Mips16Arg::JumpTarget(dest)], 4, false));
+    }
+    if major == 0b11110 {
+        let ins = *code.get(1)? as u32;
+        return decode_extended(first & 0x7FF, ins, address);
+    }
+    decode_basic(first, address)
+}
+
+impl Mips16Ins {
+    fn new(mnemonic: &'static str, args: Vec<Mips16Arg>, size: u8, extended: bool) -> Self {
+        let idx = MNEMONICS.iter().position(|&m| m == mnemonic).unwrap() as u16;
+        let op = if extended { OP_BASE | OP_EXTENDED | idx } else { OP_BASE | idx };
+        Self { op, mnemonic, args, size }
+    }
+}
+
+/// Whether an instruction decoded by this module is followed by a delay slot.
+/// Compact jumps (`jrc`, `jalrc`) and all branches have none.
+pub fn has_delay_slot(op: u16) -> bool {
+    if op & OP_BASE == 0 {
+        return false;
+    }
+    let idx = (op & !(OP_BASE | OP_EXTENDED)) as usize;
+    matches!(MNEMONICS.get(idx), Some(&("jal" | "jalx" | "jr" | "jalr")))
+}
+
+fn decode_basic(ins: u32, address: u64) -> Option<Mips16Ins> {
+    use Mips16Arg::*;
+
+    let rx_name = GPR_NAMES[XLAT[(ins as usize >> 8) & 7]];
+    let (rx, ry) = (Reg(rx_name), Reg(GPR_NAMES[XLAT[(ins as usize >> 5) & 7]]));
+    let rz = Reg(GPR_NAMES[XLAT[(ins as usize >> 2) & 7]]);
+    let imm8 = ins & 0xFF;
+    let imm5 = ins & 0x1F;
+    let branch = |offset: i64| BranchDest(address.wrapping_add(2).wrapping_add_signed(offset << 1));
+    let basic = |name, args| Some(Mips16Ins::new(name, args, 2, false));
+    let sp = GPR_NAMES[29];
+    match ins >> 11 {
+        0b00000 => basic("addiu", vec![rx, Reg(sp), Imm((imm8 << 2) as i64)]),
+        0b00001 => basic("addiu", vec![rx, Reg("$pc"), Imm((imm8 << 2) as i64)]),
+        0b00010 => basic("b", vec![branch(sext(ins & 0x7FF, 11))]),
+        0b00100 => basic("beqz", vec![rx, branch(sext(imm8, 8))]),
+        0b00101 => basic("bnez", vec![rx, branch(sext(imm8, 8))]),
+        0b00110 => {
+            let sa = match (ins >> 2) & 7 {
+                0 => 8,
+                sa => sa,
+            };
+            let name = match ins & 3 {
+                0b00 => "sll",
+                0b10 => "srl",
+                0b11 => "sra",
+                _ => return None,
+            };
+            basic(name, vec![rx, ry, Imm(sa as i64)])
+        }
+        0b01000 if ins & 0x10 == 0 => basic("addiu", vec![ry, rx, Imm(sext(ins & 0xF, 4))]),
+        0b01001 => basic("addiu", vec![rx, Imm(sext(imm8, 8))]),
+        0b01010 => basic("slti", vec![rx, Imm(imm8 as i64)]),
+        0b01011 => basic("sltiu", vec![rx, Imm(imm8 as i64)]),
+        0b01100 => match (ins >> 8) & 7 {
+            0b000 => basic("bteqz", vec![branch(sext(imm8, 8))]),
+            0b001 => basic("btnez", vec![branch(sext(imm8, 8))]),
+            0b010 => basic("sw", vec![Reg(GPR_NAMES[31]), Offset((imm8 << 2) as i64, sp)]),
+            0b011 => basic("addiu", vec![Reg(sp), Imm(sext(imm8, 8) << 3)]),
+            0b100 => {
+                let frame = match ins & 0xF {
+                    0 => 128,
+                    n => n << 3,
+                };
+                let mut args = Vec::new();
+                for (bit, reg) in [(0x40, 31), (0x20, 16), (0x10, 17)] {
+                    if ins & bit != 0 {
+                        args.push(Reg(GPR_NAMES[reg]));
+                    }
+                }
+                args.push(Imm(frame as i64));
+                basic(if ins & 0x80 != 0 { "save" } else { "restore" }, args)
+            }
+            0b101 => {
+                if ins == 0x6500 {
+                    return basic("nop", vec![]);
+                }
+                let r32 = ((ins >> 3) & 3) << 3 | ((ins >> 5) & 7);
+                basic("move", vec![
+                    Reg(GPR_NAMES[r32 as usize]),
+                    Reg(GPR_NAMES[XLAT[ins as usize & 7]]),
+                ])
+            }
+            0b111 => basic("move", vec![ry, Reg(GPR_NAMES[imm5 as usize])]),
+            _ => None,
+        },
+        0b01101 => basic("li", vec![rx, Imm(imm8 as i64)]),
+        0b01110 => basic("cmpi", vec![rx, Imm(imm8 as i64)]),
+        0b10000 => basic("lb", vec![ry, Offset((imm5 << 0) as i64, rx_name)]),
+        0b10001 => basic("lh", vec![ry, Offset((imm5 << 1) as i64, rx_name)]),
+        0b10010 => basic("lw", vec![rx, Offset((imm8 << 2) as i64, sp)]),
+        0b10011 => basic("lw", vec![ry, Offset((imm5 << 2) as i64, rx_name)]),
+        0b10100 => basic("lbu", vec![ry, Offset((imm5 << 0) as i64, rx_name)]),
+        0b10101 => basic("lhu", vec![ry, Offset((imm5 << 1) as i64, rx_name)]),
+        0b10110 => basic("lw", vec![rx, Offset((imm8 << 2) as i64, "$pc")]),
+        0b11000 => basic("sb", vec![ry, Offset((imm5 << 0) as i64, rx_name)]),
+        0b11001 => basic("sh", vec![ry, Offset((imm5 << 1) as i64, rx_name)]),
+        0b11010 => basic("sw", vec![rx, Offset((imm8 << 2) as i64, sp)]),
+        0b11011 => basic("sw", vec![ry, Offset((imm5 << 2) as i64, rx_name)]),
+        0b11100 => match ins & 3 {
+            0b01 => basic("addu", vec![rz, rx, ry]),
+            0b11 => basic("subu", vec![rz, rx, ry]),
+            _ => None,
+        },
+        0b11101 => decode_rr(ins, rx, ry),
+        _ => None,
+    }
+}
+
+fn decode_rr(ins: u32, rx: Mips16Arg, ry: Mips16Arg) -> Option<Mips16Ins> {
+    use Mips16Arg::*;
+
+    let basic = |name, args| Some(Mips16Ins::new(name, args, 2, false));
+    let ra = Reg(GPR_NAMES[31]);
+    match ins & 0x1F {
+        0b00000 => {
+            let (name, args) = match (ins >> 5) & 7 {
+                0b000 => ("jr", vec![rx]),
+                0b001 => ("jr", vec![ra]),
+                0b010 => ("jalr", vec![ra, rx]),
+                0b100 => ("jrc", vec![rx]),
+                0b101 => ("jrc", vec![ra]),
+                0b110 => ("jalrc", vec![ra, rx]),
+                _ => return None,
+            };
+            basic(name, args)
+        }
+        0b00001 => basic("sdbbp", vec![Imm(((ins >> 5) & 0x3F) as i64)]),
+        0b00010 => basic("slt", vec![rx, ry]),
+        0b00011 => basic("sltu", vec![rx, ry]),
+        0b00100 => basic("sllv", vec![ry, rx]),
+        0b00101 => basic("break", vec![Imm(((ins >> 5) & 0x3F) as i64)]),
+        0b00110 => basic("srlv", vec![ry, rx]),
+        0b00111 => basic("srav", vec![ry, rx]),
+        0b01010 => basic("cmp", vec![rx, ry]),
+        0b01011 => basic("neg", vec![rx, ry]),
+        0b01100 => basic("and", vec![rx, ry]),
+        0b01101 => basic("or", vec![rx, ry]),
+        0b01110 => basic("xor", vec![rx, ry]),
+        0b01111 => basic("not", vec![rx, ry]),
+        0b10000 => basic("mfhi", vec![rx]),
+        0b10001 => {
+            let name = match (ins >> 5) & 7 {
+                0b000 => "zeb",
+                0b001 => "zeh",
+                0b100 => "seb",
+                0b101 => "seh",
+                _ => return None,
+            };
+            basic(name, vec![rx])
+        }
+        0b10010 => basic("mflo", vec![rx]),
+        0b11000 => basic("mult", vec![rx, ry]),
+        0b11001 => basic("multu", vec![rx, ry]),
+        0b11010 => basic("div", vec![Reg(GPR_NAMES[0]), rx, ry]),
+        0b11011 => basic("divu", vec![Reg(GPR_NAMES[0]), rx, ry]),
+        _ => None,
+    }
+}
+
+/// Decode an instruction prefixed with `EXTEND`, which widens its immediate.
+fn decode_extended(ext: u32, ins: u32, address: u64) -> Option<Mips16Ins> {
+    use Mips16Arg::*;
+
+    let rx_name = GPR_NAMES[XLAT[(ins as usize >> 8) & 7]];
+    let (rx, ry) = (Reg(rx_name), Reg(GPR_NAMES[XLAT[(ins as usize >> 5) & 7]]));
+    // 16-bit immediate split across the EXTEND prefix and the instruction
+    let imm16 = (ext & 0x1F) << 11 | ((ext >> 5) & 0x3F) << 5 | (ins & 0x1F);
+    let simm = sext(imm16, 16);
+    let uimm = imm16 as i64;
+    let branch = BranchDest(address.wrapping_add(4).wrapping_add_signed(simm << 1));
+    let extended = |name, args| Some(Mips16Ins::new(name, args, 4, true));
+    let sp = GPR_NAMES[29];
+    match ins >> 11 {
+        0b00000 => extended("addiu", vec![rx, Reg(sp), Imm(simm)]),
+        0b00001 => extended("addiu", vec![rx, Reg("$pc"), Imm(simm)]),
+        0b00010 => extended("b", vec![branch]),
+        0b00100 => extended("beqz", vec![rx, branch]),
+        0b00101 => extended("bnez", vec![rx, branch]),
+        0b00110 => {
+            let name = match ins & 3 {
+                0b00 => "sll",
+                0b10 => "srl",
+                0b11 => "sra",
+                _ => return None,
+            };
+            extended(name, vec![rx, ry, Imm(((ext >> 6) & 0x1F) as i64)])
+        }
+        0b01000 if ins & 0x10 == 0 => {
+            let imm15 = (ext & 0xF) << 11 | (ext >> 4) << 4 | (ins & 0xF);
+            extended("addiu", vec![ry, rx, Imm(sext(imm15, 15))])
+        }
+        0b01001 => extended("addiu", vec![rx, Imm(simm)]),
+        0b01010 => extended("slti", vec![rx, Imm(simm)]),
+        0b01011 => extended("sltiu", vec![rx, Imm(simm)]),
+        0b01100 => match (ins >> 8) & 7 {
+            0b000 => extended("bteqz", vec![branch]),
+            0b001 => extended("btnez", vec![branch]),
+            0b010 => extended("sw", vec![Reg(GPR_NAMES[31]), Offset(simm, sp)]),
+            0b011 => extended("addiu", vec![Reg(sp), Imm(simm)]),
+            _ => None,
+        },
+        0b01101 => extended("li", vec![rx, Imm(uimm)]),
+        0b01110 => extended("cmpi", vec![rx, Imm(uimm)]),
+        0b10000 => extended("lb", vec![ry, Offset(simm, rx_name)]),
+        0b10001 => extended("lh", vec![ry, Offset(simm, rx_name)]),
+        0b10010 => extended("lw", vec![rx, Offset(simm, sp)]),
+        0b10011 => extended("lw", vec![ry, Offset(simm, rx_name)]),
+        0b10100 => extended("lbu", vec![ry, Offset(simm, rx_name)]),
+        0b10101 => extended("lhu", vec![ry, Offset(simm, rx_name)]),
+        0b10110 => extended("lw", vec![rx, Offset(simm, "$pc")]),
+        0b11000 => extended("sb", vec![ry, Offset(simm, rx_name)]),
+        0b11001 => extended("sh", vec![ry, Offset(simm, rx_name)]),
+        0b11010 => extended("sw", vec![rx, Offset(simm, sp)]),
+        0b11011 => extended("sw", vec![ry, Offset(simm, rx_name)]),
+        _ => None,
+    }
+}
@@ -4,14 +4,26 @@ use anyhow::{bail, Result};
 use object::{Architecture, Object, Relocation, RelocationFlags};
 
 use crate::{
-    diff::DiffObjConfig,
-    obj::{ObjInfo, ObjIns, ObjSection, SymbolRef},
+    diff::{frame::StackFrame, signature::Signature, DiffObjConfig},
+    obj::{
+        ObjConstant, ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjReloc, ObjSection, SymbolRef,
+    },
 };
 
+#[cfg(feature = "arm")]
+mod arm;
+#[cfg(feature = "arm64")]
+mod arm64;
+#[cfg(feature = "m68k")]
+mod m68k;
 #[cfg(feature = "mips")]
 mod mips;
 #[cfg(feature = "ppc")]
 mod ppc;
+#[cfg(feature = "riscv")]
+mod riscv;
+#[cfg(feature = "sh")]
+mod sh;
 #[cfg(feature = "x86")]
 mod x86;
 
@@ -26,9 +38,59 @@ pub trait ObjArch: Send + Sync {
     fn implcit_addend(&self, section: &ObjSection, address: u64, reloc: &Relocation)
         -> Result<i64>;
 
+    /// Whether a relocation only annotates another relocation at the same address, such as
+    /// a linker relaxation hint. These are dropped when reading the object.
+    fn ignore_relocation(&self, _flags: RelocationFlags) -> bool { false }
+
     fn demangle(&self, _name: &str) -> Option<String> { None }
 
     fn display_reloc(&self, flags: RelocationFlags) -> Cow<'static, str>;
+
+    /// Classify a register operand for register allocation analysis. Only registers
+    /// that the register allocator is free to assign should be classified.
+    fn register_class(&self, _arg: &ObjInsArgValue) -> Option<RegisterClass> { None }
+
+    /// Number of bytes covered by a relocation in a data section.
+    fn get_reloc_byte_size(&self, _flags: RelocationFlags) -> usize { 4 }
+
+    /// Whether the instruction is followed by a delay slot. Used by the diff algorithm
+    /// to keep a branch and its delay slot together.
+    fn has_delay_slot(&self, _ins: &ObjIns) -> bool { false }
+
+    /// Whether the instruction is a filler that compilers and assemblers insert to align
+    /// the start of the next function.
+    fn is_padding(&self, ins: &ObjIns) -> bool { ins.mnemonic == "nop" }
+
+    /// Whether the instruction returns from the function. Used to find the end of
+    /// functions without a size.
+    fn is_return(&self, ins: &ObjIns) -> bool {
+        matches!(ins.mnemonic.as_str(), "blr" | "ret" | "retn" | "rts")
+    }
+
+    /// Whether the instruction jumps to an address held in a register (other than a
+    /// return). Used to detect call stubs.
+    fn is_indirect_jump(&self, ins: &ObjIns) -> bool {
+        matches!(ins.mnemonic.as_str(), "bctr" | "br")
+    }
+
+    /// Decode the constant loaded by an instruction from the data at its relocation
+    /// target, such as a float loaded from a constant pool.
+    fn data_constant(&self, _ins: &ObjIns, _data: &[u8]) -> Option<ObjConstant> { None }
+
+    /// Extract the stack frame layout set up by a function's prologue and epilogue.
+    fn stack_frame(&self, _insts: &[&ObjIns]) -> Option<StackFrame> { None }
+
+    /// Infer a function's arguments from the argument registers it reads before writing
+    /// them.
+    fn signature(&self, _insts: &[&ObjIns]) -> Option<Signature> { None }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum RegisterClass {
+    Gpr,
+    Fpr,
+    /// Vector unit registers, such as the PS2 EE's VU0 registers
+    Vector,
 }
 
 pub struct ProcessCodeResult {
@@ -36,14 +98,66 @@ pub struct ProcessCodeResult {
     pub insts: Vec<ObjIns>,
 }
 
+/// Pseudo-op of bytes that couldn't be decoded, shared by all architectures.
+pub const OP_BYTES: u16 = u16::MAX - 1;
+
+/// Fallback for bytes the disassembler can't decode, such as data in a code section or
+/// instructions from unsupported extensions. The bytes are shown and diffed individually.
+pub fn bytes_ins(address: u64, data: &[u8], reloc: Option<&ObjReloc>, line: Option<u64>) -> ObjIns {
+    let formatted = data.iter().map(|b| format!("{b:#04x}")).collect::<Vec<_>>().join(", ");
+    ObjIns {
+        address,
+        size: data.len() as u8,
+        op: OP_BYTES,
+        mnemonic: ".byte".to_string(),
+        args: vec![ObjInsArg::Bytes(data.to_vec())],
+        reloc: reloc.cloned(),
+        branch_dest: None,
+        line,
+        formatted: format!(".byte {formatted}"),
+        orig: None,
+    }
+}
+
 pub fn new_arch(object: &object::File) -> Result<Box<dyn ObjArch>> {
     Ok(match object.architecture() {
         #[cfg(feature = "ppc")]
         Architecture::PowerPc => Box::new(ppc::ObjArchPpc::new(object)?),
         #[cfg(feature = "mips")]
         Architecture::Mips => Box::new(mips::ObjArchMips::new(object)?),
+        #[cfg(feature = "arm")]
+        Architecture::Arm => Box::new(arm::ObjArchArm::new(object)?),
+        #[cfg(feature = "arm64")]
+        Architecture::Aarch64 => Box::new(arm64::ObjArchArm64::new(object)?),
+        #[cfg(feature = "riscv")]
+        Architecture::Riscv32 | Architecture::Riscv64 => {
+            Box::new(riscv::ObjArchRiscV::new(object)?)
+        }
+        #[cfg(feature = "sh")]
+        Architecture::SuperH => Box::new(sh::ObjArchSh::new(object)?),
         #[cfg(feature = "x86")]
         Architecture::I386 | Architecture::X86_64 => Box::new(x86::ObjArchX86::new(object)?),
+        #[cfg(feature = "m68k")]
+        _ if m68k::is_m68k(object) => Box::new(m68k::ObjArchM68k::new(object)?),
         arch => bail!("Unsupported architecture: {arch:?}"),
     })
 }
+
+/// Creates an architecture for linked binaries without an object file header, such as DOLs.
+pub fn new_binary_arch(architecture: Architecture) -> Result<Box<dyn ObjArch>> {
+    Ok(match architecture {
+        #[cfg(feature = "ppc")]
+        Architecture::PowerPc => Box::new(ppc::ObjArchPpc::new_binary()),
+        #[cfg(feature = "mips")]
+        Architecture::Mips => Box::new(mips::ObjArchMips::new_binary(false)),
+        arch => bail!("Unsupported architecture for binaries: {arch:?}"),
+    })
+}
+
+/// Creates an architecture for raw N64 RSP microcode.
+pub fn new_rsp_arch() -> Result<Box<dyn ObjArch>> {
+    #[cfg(feature = "mips")]
+    return Ok(Box::new(mips::ObjArchMips::new_binary(true)));
+    #[cfg(not(feature = "mips"))]
+    bail!("Unsupported architecture for binaries: RSP")
+}
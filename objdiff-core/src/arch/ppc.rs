@@ -1,15 +1,28 @@
 use std::borrow::Cow;
 
 use anyhow::{anyhow, bail, Result};
-use object::{elf, File, Relocation, RelocationFlags};
-use ppc750cl::{Argument, InsIter, GPR};
+use object::{elf, macho, BinaryFormat, File, Object, Relocation, RelocationFlags};
+use ppc750cl::{Argument, InsIter, Opcode, GPR};
 
 use crate::{
-    arch::{ObjArch, ProcessCodeResult},
-    diff::DiffObjConfig,
-    obj::{ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjReloc, ObjSection, SymbolRef},
+    arch::{
+        bytes_ins,
+        ppc::paired::{PairedArg, PairedIns},
+        ObjArch, ProcessCodeResult, RegisterClass, OP_BYTES,
+    },
+    diff::{
+        frame::{ins_values, mem_operand, parse_imm, StackFrame},
+        signature::{infer_signature, RegisterUse, Signature},
+        DiffObjConfig,
+    },
+    obj::{
+        demangle::Demangler, ObjConstant, ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjReloc,
+        ObjSection, SymbolRef,
+    },
 };
 
+mod paired;
+
 // Relative relocation, can be Simm, Offset or BranchDest
 fn is_relative_arg(arg: &Argument) -> bool {
     matches!(arg, Argument::Simm(_) | Argument::Offset(_) | Argument::BranchDest(_))
@@ -22,10 +35,22 @@ fn is_rel_abs_arg(arg: &Argument) -> bool {
 
 fn is_offset_arg(arg: &Argument) -> bool { matches!(arg, Argument::Offset(_)) }
 
-pub struct ObjArchPpc {}
+pub struct ObjArchPpc {
+    demangler: Demangler,
+}
 
 impl ObjArchPpc {
-    pub fn new(_file: &File) -> Result<Self> { Ok(Self {}) }
+    pub fn new(file: &File) -> Result<Self> {
+        // Mach-O objects are produced by GCC
+        let demangler = if file.format() == BinaryFormat::MachO {
+            Demangler::Itanium
+        } else {
+            Demangler::CodeWarrior
+        };
+        Ok(Self { demangler })
+    }
+
+    pub fn new_binary() -> Self { Self { demangler: Demangler::CodeWarrior } }
 }
 
 impl ObjArch for ObjArchPpc {
@@ -54,10 +79,41 @@ impl ObjArch for ObjArchPpc {
                     RelocationFlags::Elf {
                         r_type: elf::R_PPC_ADDR16_HI | elf::R_PPC_ADDR16_HA | elf::R_PPC_ADDR16_LO,
                     } => ins.code & !0xFFFF,
+                    RelocationFlags::MachO { r_type: macho::PPC_RELOC_BR24, .. } => {
+                        ins.code & !0x3FFFFFC
+                    }
+                    RelocationFlags::MachO {
+                        r_type:
+                            macho::PPC_RELOC_BR14
+                            | macho::PPC_RELOC_LO14
+                            | macho::PPC_RELOC_LO14_SECTDIFF,
+                        ..
+                    } => ins.code & !0xFFFC,
+                    RelocationFlags::MachO { r_type, .. } if is_macho_half16(r_type) => {
+                        ins.code & !0xFFFF
+                    }
                     _ => ins.code,
                 };
             }
 
+            // Paired-single instructions have no relocatable fields
+            if reloc.is_none() {
+                if let Some(paired) = paired::decode(ins.code) {
+                    let line = section.line_info.range(..=cur_addr as u64).last().map(|(_, &b)| b);
+                    ops.push(paired.op);
+                    insts.push(paired_ins(&paired, cur_addr as u64, line, config));
+                    continue;
+                }
+            }
+
+            if ins.op == Opcode::Illegal {
+                let offset = (cur_addr - symbol.address as u32) as usize;
+                let line = section.line_info.range(..=cur_addr as u64).last().map(|(_, &b)| b);
+                ops.push(OP_BYTES);
+                insts.push(bytes_ins(cur_addr as u64, &code[offset..offset + 4], reloc, line));
+                continue;
+            }
+
             let orig = ins.basic().to_string();
             let simplified = ins.simplified();
             let formatted = simplified.to_string();
@@ -76,6 +132,17 @@ impl ObjArch for ObjArchPpc {
                     } => {
                         reloc_arg = simplified.args.iter().rposition(is_rel_abs_arg);
                     }
+                    RelocationFlags::MachO {
+                        r_type: macho::PPC_RELOC_BR24 | macho::PPC_RELOC_BR14,
+                        ..
+                    } => {
+                        reloc_arg = simplified.args.iter().rposition(is_relative_arg);
+                    }
+                    RelocationFlags::MachO { r_type, .. }
+                        if is_macho_half16(r_type) || is_macho_lo14(r_type) =>
+                    {
+                        reloc_arg = simplified.args.iter().rposition(is_rel_abs_arg);
+                    }
                     _ => {}
                 }
             }
@@ -147,6 +214,14 @@ impl ObjArch for ObjArchPpc {
                 orig: Some(orig),
             });
         }
+        // Trailing bytes too short for an instruction
+        let remainder = code.chunks_exact(4).remainder();
+        if !remainder.is_empty() {
+            let address = symbol.address + (code.len() - remainder.len()) as u64;
+            let line = section.line_info.range(..=address).last().map(|(_, &b)| b);
+            ops.push(OP_BYTES);
+            insts.push(bytes_ins(address, remainder, None, line));
+        }
         Ok(ProcessCodeResult { ops, insts })
     }
 
@@ -159,9 +234,7 @@ impl ObjArch for ObjArchPpc {
         bail!("Unsupported PPC implicit relocation {:#x}:{:?}", address, reloc.flags())
     }
 
-    fn demangle(&self, name: &str) -> Option<String> {
-        cwdemangle::demangle(name, &cwdemangle::DemangleOptions::default())
-    }
+    fn demangle(&self, name: &str) -> Option<String> { self.demangler.demangle(name) }
 
     fn display_reloc(&self, flags: RelocationFlags) -> Cow<'static, str> {
         match flags {
@@ -176,9 +249,197 @@ impl ObjArch for ObjArchPpc {
                 elf::R_PPC_REL14 => Cow::Borrowed("R_PPC_REL14"),
                 _ => Cow::Owned(format!("<{flags:?}>")),
             },
+            RelocationFlags::MachO { r_type, .. } => match r_type {
+                macho::PPC_RELOC_VANILLA => Cow::Borrowed("PPC_RELOC_VANILLA"),
+                macho::PPC_RELOC_BR14 => Cow::Borrowed("PPC_RELOC_BR14"),
+                macho::PPC_RELOC_BR24 => Cow::Borrowed("PPC_RELOC_BR24"),
+                macho::PPC_RELOC_HI16 => Cow::Borrowed("PPC_RELOC_HI16"),
+                macho::PPC_RELOC_LO16 => Cow::Borrowed("PPC_RELOC_LO16"),
+                macho::PPC_RELOC_HA16 => Cow::Borrowed("PPC_RELOC_HA16"),
+                macho::PPC_RELOC_LO14 => Cow::Borrowed("PPC_RELOC_LO14"),
+                macho::PPC_RELOC_SECTDIFF => Cow::Borrowed("PPC_RELOC_SECTDIFF"),
+                macho::PPC_RELOC_HI16_SECTDIFF => Cow::Borrowed("PPC_RELOC_HI16_SECTDIFF"),
+                macho::PPC_RELOC_LO16_SECTDIFF => Cow::Borrowed("PPC_RELOC_LO16_SECTDIFF"),
+                macho::PPC_RELOC_HA16_SECTDIFF => Cow::Borrowed("PPC_RELOC_HA16_SECTDIFF"),
+                macho::PPC_RELOC_LO14_SECTDIFF => Cow::Borrowed("PPC_RELOC_LO14_SECTDIFF"),
+                macho::PPC_RELOC_LOCAL_SECTDIFF => Cow::Borrowed("PPC_RELOC_LOCAL_SECTDIFF"),
+                _ => Cow::Owned(format!("<{flags:?}>")),
+            },
             _ => Cow::Owned(format!("<{flags:?}>")),
         }
     }
+
+    fn register_class(&self, arg: &ObjInsArgValue) -> Option<RegisterClass> {
+        let ObjInsArgValue::Opaque(name) = arg else {
+            return None;
+        };
+        let (class, num) = if let Some(num) = name.strip_prefix('r') {
+            (RegisterClass::Gpr, num)
+        } else if let Some(num) = name.strip_prefix('f') {
+            (RegisterClass::Fpr, num)
+        } else {
+            return None;
+        };
+        match num.parse::<u8>().ok()? {
+            // Stack pointer and small data area bases are fixed by the ABI
+            1 | 2 | 13 if class == RegisterClass::Gpr => None,
+            0..=31 => Some(class),
+            _ => None,
+        }
+    }
+
+    fn data_constant(&self, ins: &ObjIns, data: &[u8]) -> Option<ObjConstant> {
+        match ins.mnemonic.as_str() {
+            "lfs" | "lfsu" => {
+                Some(ObjConstant::Float(f32::from_be_bytes(data.get(..4)?.try_into().ok()?)))
+            }
+            "lfd" | "lfdu" => {
+                Some(ObjConstant::Double(f64::from_be_bytes(data.get(..8)?.try_into().ok()?)))
+            }
+            _ => None,
+        }
+    }
+
+    fn stack_frame(&self, insts: &[&ObjIns]) -> Option<StackFrame> {
+        let mut frame = StackFrame::default();
+        // Register holding the link register, copied by mflr for saving
+        let mut lr_reg = None;
+        for ins in insts {
+            let mnemonic = ins.mnemonic.as_str();
+            if mnemonic == "mflr" {
+                lr_reg = match ins_values(ins).first() {
+                    Some(ObjInsArgValue::Opaque(reg)) => Some(reg.to_string()),
+                    _ => None,
+                };
+                continue;
+            }
+            if mnemonic == "addi" {
+                // Address of a stack slot
+                if let [_, ObjInsArgValue::Opaque(base), offset] = ins_values(ins)[..] {
+                    if base == "r1" {
+                        if let Some(offset) = parse_imm(offset) {
+                            frame.slots.insert(offset);
+                        }
+                    }
+                }
+                continue;
+            }
+            let Some((reg, offset, "r1")) = mem_operand(ins) else {
+                continue;
+            };
+            if mnemonic == "stwu" && reg == "r1" {
+                if frame.size.is_none() && offset < 0 {
+                    frame.size = Some(offset.unsigned_abs());
+                }
+            } else if mnemonic == "stmw" {
+                let Some(first) = reg.strip_prefix('r').and_then(|n| n.parse::<i64>().ok()) else {
+                    continue;
+                };
+                for n in first..=31 {
+                    frame.saved_registers.insert(format!("r{n}"), offset + (n - first) * 4);
+                }
+            } else if mnemonic.starts_with("st") || mnemonic.starts_with("psq_st") {
+                if lr_reg.as_deref() == Some(reg) {
+                    frame.saved_registers.insert("lr".to_string(), offset);
+                    lr_reg = None;
+                } else if is_callee_saved(reg) && !frame.saved_registers.contains_key(reg) {
+                    frame.saved_registers.insert(reg.to_string(), offset);
+                } else {
+                    frame.slots.insert(offset);
+                }
+            } else if mnemonic.starts_with('l') || mnemonic.starts_with("psq_l") {
+                frame.load(offset);
+            }
+        }
+        Some(frame)
+    }
+
+    fn signature(&self, insts: &[&ObjIns]) -> Option<Signature> {
+        Some(infer_signature(insts, arg_register, register_use))
+    }
+}
+
+/// Argument registers in the EABI: r3-r10 and f1-f8.
+fn arg_register(reg: &str) -> Option<(RegisterClass, usize)> {
+    let (class, num, first) = if let Some(num) = reg.strip_prefix('r') {
+        (RegisterClass::Gpr, num, 3)
+    } else if let Some(num) = reg.strip_prefix('f') {
+        (RegisterClass::Fpr, num, 1)
+    } else {
+        return None;
+    };
+    let index = num.parse::<usize>().ok()?.checked_sub(first)?;
+    (index < 8).then_some((class, index))
+}
+
+fn register_use(ins: &ObjIns) -> RegisterUse {
+    // Strip branch prediction hints
+    let mnemonic = ins.mnemonic.trim_end_matches(['+', '-']);
+    if mnemonic.starts_with('b') && (mnemonic.ends_with('l') || mnemonic.ends_with("la")) {
+        RegisterUse::Call
+    } else if mnemonic.starts_with('b')
+        || mnemonic.starts_with("st")
+        || mnemonic.starts_with("psq_st")
+        || mnemonic.starts_with("cmp")
+        || mnemonic.starts_with("fcmp")
+        || mnemonic.starts_with("mt")
+        || mnemonic.starts_with("tw")
+        || mnemonic.starts_with("dcb")
+        || mnemonic.starts_with("icb")
+    {
+        RegisterUse::ReadAll
+    } else if matches!(mnemonic.trim_end_matches('.'), "rlwimi" | "inslwi" | "insrwi") {
+        // Inserts keep the other bits of the destination
+        RegisterUse::ReadModify
+    } else {
+        RegisterUse::Default
+    }
+}
+
+/// Non-volatile registers in the EABI, saved by the callee.
+fn is_callee_saved(reg: &str) -> bool {
+    let num = reg.strip_prefix('r').or_else(|| reg.strip_prefix('f'));
+    matches!(num.and_then(|n| n.parse::<u8>().ok()), Some(14..=31))
+}
+
+fn paired_ins(
+    paired: &PairedIns,
+    address: u64,
+    line: Option<u64>,
+    config: &DiffObjConfig,
+) -> ObjIns {
+    let reg = |name: String| ObjInsArg::Arg(ObjInsArgValue::Opaque(name.into()));
+    let mut args = vec![];
+    for (idx, arg) in paired.args.iter().enumerate() {
+        if idx > 0 {
+            args.push(ObjInsArg::PlainText(config.separator().into()));
+        }
+        match *arg {
+            PairedArg::Fpr(_) | PairedArg::Gpr(_) | PairedArg::Crf(_) | PairedArg::Gqr(_) => {
+                args.push(reg(arg.to_string()));
+            }
+            PairedArg::Offset(disp, ra) => {
+                args.push(ObjInsArg::Arg(ObjInsArgValue::Signed(disp as i64)));
+                args.push(ObjInsArg::PlainText("(".into()));
+                args.push(reg(PairedArg::Gpr(ra).to_string()));
+                args.push(ObjInsArg::PlainText(")".into()));
+            }
+            PairedArg::W(w) => args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(w as u64))),
+        }
+    }
+    let formatted = paired.to_string();
+    ObjIns {
+        address,
+        size: 4,
+        mnemonic: paired.mnemonic.clone(),
+        args,
+        reloc: None,
+        op: paired.op,
+        branch_dest: None,
+        line,
+        orig: Some(formatted.clone()),
+        formatted,
+    }
 }
 
 fn push_reloc(args: &mut Vec<ObjInsArg>, reloc: &ObjReloc) -> Result<()> {
@@ -205,7 +466,42 @@ fn push_reloc(args: &mut Vec<ObjInsArg>, reloc: &ObjReloc) -> Result<()> {
             }
             _ => bail!("Unsupported ELF PPC relocation type {r_type}"),
         },
+        RelocationFlags::MachO { r_type, .. } => {
+            let prefix = match r_type {
+                macho::PPC_RELOC_HI16 | macho::PPC_RELOC_HI16_SECTDIFF => Some("hi16("),
+                macho::PPC_RELOC_HA16 | macho::PPC_RELOC_HA16_SECTDIFF => Some("ha16("),
+                macho::PPC_RELOC_LO16
+                | macho::PPC_RELOC_LO16_SECTDIFF
+                | macho::PPC_RELOC_LO14
+                | macho::PPC_RELOC_LO14_SECTDIFF => Some("lo16("),
+                macho::PPC_RELOC_VANILLA | macho::PPC_RELOC_BR24 | macho::PPC_RELOC_BR14 => None,
+                _ => bail!("Unsupported Mach-O PPC relocation type {r_type}"),
+            };
+            if let Some(prefix) = prefix {
+                args.push(ObjInsArg::PlainText(prefix.into()));
+                args.push(ObjInsArg::Reloc);
+                args.push(ObjInsArg::PlainText(")".into()));
+            } else {
+                args.push(ObjInsArg::Reloc);
+            }
+        }
         flags => bail!("Unsupported PPC relocation kind: {flags:?}"),
     };
     Ok(())
 }
+
+fn is_macho_half16(r_type: u8) -> bool {
+    matches!(
+        r_type,
+        macho::PPC_RELOC_HI16
+            | macho::PPC_RELOC_LO16
+            | macho::PPC_RELOC_HA16
+            | macho::PPC_RELOC_HI16_SECTDIFF
+            | macho::PPC_RELOC_LO16_SECTDIFF
+            | macho::PPC_RELOC_HA16_SECTDIFF
+    )
+}
+
+fn is_macho_lo14(r_type: u8) -> bool {
+    matches!(r_type, macho::PPC_RELOC_LO14 | macho::PPC_RELOC_LO14_SECTDIFF)
+}
@@ -0,0 +1,211 @@
+//! Decoder for the Gekko/Broadway paired-single extensions and `dcbz_l`.
+//!
+//! These are decoded here rather than by ppc750cl so that every field, including the
+//! quantization W and GQR fields, is exposed as its own argument and diffed separately.
+
+/// Mnemonics of the decoded instructions. The index is used to build the opcode ID.
+const MNEMONICS: &[&str] = &[
+    "psq_l",
+    "psq_lu",
+    "psq_st",
+    "psq_stu",
+    "psq_lx",
+    "psq_lux",
+    "psq_stx",
+    "psq_stux",
+    "ps_cmpu0",
+    "ps_cmpo0",
+    "ps_cmpu1",
+    "ps_cmpo1",
+    "ps_neg",
+    "ps_mr",
+    "ps_nabs",
+    "ps_abs",
+    "ps_merge00",
+    "ps_merge01",
+    "ps_merge10",
+    "ps_merge11",
+    "ps_div",
+    "ps_sub",
+    "ps_add",
+    "ps_sel",
+    "ps_res",
+    "ps_mul",
+    "ps_rsqrte",
+    "ps_msub",
+    "ps_madd",
+    "ps_nmsub",
+    "ps_nmadd",
+    "ps_sum0",
+    "ps_sum1",
+    "ps_muls0",
+    "ps_muls1",
+    "ps_madds0",
+    "ps_madds1",
+    "dcbz_l",
+];
+
+/// Base of the opcode IDs, chosen to not collide with ppc750cl's opcodes.
+const OP_BASE: u16 = 0x8000;
+/// Set in the opcode ID of instructions with the record bit set.
+const OP_RC: u16 = 0x4000;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PairedArg {
+    Fpr(u8),
+    Gpr(u8),
+    Crf(u8),
+    /// Displacement from a base GPR, as in `d(rA)`
+    Offset(i16, u8),
+    /// Quantization W (single value) field
+    W(u8),
+    /// Graphics quantization register index
+    Gqr(u8),
+}
+
+pub struct PairedIns {
+    pub op: u16,
+    pub mnemonic: String,
+    pub args: Vec<PairedArg>,
+}
+
+impl PairedIns {
+    fn new(name: &str, rc: bool, args: Vec<PairedArg>) -> Self {
+        let idx = MNEMONICS.iter().position(|&m| m == name).unwrap() as u16;
+        let (op, mnemonic) = if rc {
+            (OP_BASE | OP_RC | idx, format!("{name}."))
+        } else {
+            (OP_BASE | idx, name.to_string())
+        };
+        Self { op, mnemonic, args }
+    }
+}
+
+impl std::fmt::Display for PairedArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PairedArg::Fpr(n) => write!(f, "f{n}"),
+            PairedArg::Gpr(n) => write!(f, "r{n}"),
+            PairedArg::Crf(n) => write!(f, "cr{n}"),
+            PairedArg::Offset(d, ra) if *d < 0 => write!(f, "-{:#x}(r{ra})", -(*d as i32)),
+            PairedArg::Offset(d, ra) => write!(f, "{d:#x}(r{ra})"),
+            PairedArg::W(w) => write!(f, "{w}"),
+            PairedArg::Gqr(n) => write!(f, "qr{n}"),
+        }
+    }
+}
+
+impl std::fmt::Display for PairedIns {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.mnemonic)?;
+        for (i, arg) in self.args.iter().enumerate() {
+            write!(f, "{}{arg}", if i == 0 { " " } else { ", " })?;
+        }
+        Ok(())
+    }
+}
+
+/// Decode a paired-single or Gekko cache instruction. Returns `None` for any other
+/// instruction, which should be decoded by ppc750cl instead.
+pub fn decode(code: u32) -> Option<PairedIns> {
+    use PairedArg::*;
+
+    let field = |shift: u32, bits: u32| ((code >> shift) & ((1 << bits) - 1)) as u8;
+    let (d, a, b, c) = (field(21, 5), field(16, 5), field(11, 5), field(6, 5));
+    let rc = code & 1 != 0;
+    match code >> 26 {
+        56 | 57 | 60 | 61 => {
+            let name = match code >> 26 {
+                56 => "psq_l",
+                57 => "psq_lu",
+                60 => "psq_st",
+                _ => "psq_stu",
+            };
+            // 12-bit signed displacement
+            let disp = ((code as i16) << 4) >> 4;
+            Some(PairedIns::new(name, false, vec![
+                Fpr(d),
+                Offset(disp, a),
+                W(field(15, 1)),
+                Gqr(field(12, 3)),
+            ]))
+        }
+        4 => {
+            let xo5 = field(1, 5);
+            let xo10 = (code >> 1) & 0x3FF;
+            match xo5 {
+                // Indexed quantized loads and stores
+                6 | 7 => {
+                    let name = match field(1, 6) {
+                        6 => "psq_lx",
+                        7 => "psq_stx",
+                        38 => "psq_lux",
+                        _ => "psq_stux",
+                    };
+                    Some(PairedIns::new(name, false, vec![
+                        Fpr(d),
+                        Gpr(a),
+                        Gpr(b),
+                        W(field(10, 1)),
+                        Gqr(field(7, 3)),
+                    ]))
+                }
+                0 => {
+                    let name = match xo10 {
+                        0 => "ps_cmpu0",
+                        32 => "ps_cmpo0",
+                        64 => "ps_cmpu1",
+                        96 => "ps_cmpo1",
+                        _ => return None,
+                    };
+                    Some(PairedIns::new(name, false, vec![Crf(field(23, 3)), Fpr(a), Fpr(b)]))
+                }
+                8 => {
+                    let name = match xo10 {
+                        40 => "ps_neg",
+                        72 => "ps_mr",
+                        136 => "ps_nabs",
+                        264 => "ps_abs",
+                        _ => return None,
+                    };
+                    Some(PairedIns::new(name, rc, vec![Fpr(d), Fpr(b)]))
+                }
+                16 => {
+                    let name = match xo10 {
+                        528 => "ps_merge00",
+                        560 => "ps_merge01",
+                        592 => "ps_merge10",
+                        624 => "ps_merge11",
+                        _ => return None,
+                    };
+                    Some(PairedIns::new(name, rc, vec![Fpr(d), Fpr(a), Fpr(b)]))
+                }
+                22 if xo10 == 1014 => Some(PairedIns::new("dcbz_l", false, vec![Gpr(a), Gpr(b)])),
+                18 => Some(PairedIns::new("ps_div", rc, vec![Fpr(d), Fpr(a), Fpr(b)])),
+                20 => Some(PairedIns::new("ps_sub", rc, vec![Fpr(d), Fpr(a), Fpr(b)])),
+                21 => Some(PairedIns::new("ps_add", rc, vec![Fpr(d), Fpr(a), Fpr(b)])),
+                24 => Some(PairedIns::new("ps_res", rc, vec![Fpr(d), Fpr(b)])),
+                26 => Some(PairedIns::new("ps_rsqrte", rc, vec![Fpr(d), Fpr(b)])),
+                25 => Some(PairedIns::new("ps_mul", rc, vec![Fpr(d), Fpr(a), Fpr(c)])),
+                12 => Some(PairedIns::new("ps_muls0", rc, vec![Fpr(d), Fpr(a), Fpr(c)])),
+                13 => Some(PairedIns::new("ps_muls1", rc, vec![Fpr(d), Fpr(a), Fpr(c)])),
+                10 | 11 | 14 | 15 | 23 | 28..=31 => {
+                    let name = match xo5 {
+                        10 => "ps_sum0",
+                        11 => "ps_sum1",
+                        14 => "ps_madds0",
+                        15 => "ps_madds1",
+                        23 => "ps_sel",
+                        28 => "ps_msub",
+                        29 => "ps_madd",
+                        30 => "ps_nmsub",
+                        _ => "ps_nmadd",
+                    };
+                    Some(PairedIns::new(name, rc, vec![Fpr(d), Fpr(a), Fpr(c), Fpr(b)]))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
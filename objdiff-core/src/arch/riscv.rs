@@ -0,0 +1,1088 @@
+use std::borrow::Cow;
+
+use anyhow::{anyhow, bail, Result};
+use object::{elf, File, Object, Relocation, RelocationFlags};
+
+use crate::{
+    arch::{bytes_ins, ObjArch, ProcessCodeResult, RegisterClass, OP_BYTES},
+    diff::DiffObjConfig,
+    obj::{ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjReloc, ObjSection, ObjSymbol, SymbolRef},
+};
+
+pub struct ObjArchRiscV {
+    rv64: bool,
+}
+
+impl ObjArchRiscV {
+    pub fn new(object: &File) -> Result<Self> { Ok(Self { rv64: object.is_64() }) }
+}
+
+/// Compressed instructions are numbered after the 32-bit instructions.
+const COMPRESSED_OP_BASE: u16 = 0x1000;
+
+impl ObjArch for ObjArchRiscV {
+    fn process_code(
+        &self,
+        obj: &ObjInfo,
+        symbol_ref: SymbolRef,
+        config: &DiffObjConfig,
+    ) -> Result<ProcessCodeResult> {
+        let (section, symbol) = obj.section_symbol(symbol_ref);
+        let section = section.ok_or_else(|| anyhow!("Code symbol section not found"))?;
+        let code = &section.data
+            [symbol.section_address as usize..(symbol.section_address + symbol.size) as usize];
+
+        let ins_count = code.len() / 4;
+        let mut ops = Vec::<u16>::with_capacity(ins_count);
+        let mut insts = Vec::<ObjIns>::with_capacity(ins_count);
+        let mut offset = 0usize;
+        while offset + 2 <= code.len() {
+            let cur_addr = symbol.address + offset as u64;
+            let line = section.line_info.range(..=cur_addr).last().map(|(_, &b)| b);
+            let reloc = section.relocations.iter().find(|r| r.address == cur_addr);
+
+            // Instruction parcels are little-endian, and the low bits of the first parcel
+            // give the length of the instruction
+            let low = u16::from_le_bytes(code[offset..offset + 2].try_into()?);
+            let (size, word, decoded) = if low & 3 != 3 {
+                (2, low as u32, decode_compressed(low, self.rv64))
+            } else if offset + 4 <= code.len() {
+                let word = u32::from_le_bytes(code[offset..offset + 4].try_into()?);
+                (4, word, decode(word, self.rv64))
+            } else {
+                (2, low as u32, None)
+            };
+            let Some((op, opcode)) = decoded else {
+                ops.push(OP_BYTES);
+                insts.push(bytes_ins(cur_addr, &code[offset..offset + size], reloc, line));
+                offset += size;
+                continue;
+            };
+
+            let mut ins_reloc = reloc.cloned();
+            if let Some(reloc) = &mut ins_reloc {
+                resolve_pcrel_lo(section, reloc);
+            }
+            let ctx = Context { section, symbol, address: cur_addr, separator: config.separator() };
+            let simplified = if size == 4 { simplify(opcode, word, reloc.is_some()) } else { None };
+            let (mnemonic, operands) = simplified.unwrap_or((opcode.mnemonic, opcode.operands));
+            let mut mnemonic = mnemonic.to_string();
+            if opcode.ordered {
+                mnemonic.push_str(ordering_suffix(word));
+            }
+            let (args, reloc_out, branch_dest) =
+                build_args(&ctx, operands, word, ins_reloc.clone())?;
+            let formatted = format_ins(&mnemonic, &args, reloc_out.as_ref());
+            let orig = if simplified.is_some() {
+                let (orig_args, orig_reloc, _) =
+                    build_args(&ctx, opcode.operands, word, ins_reloc)?;
+                Some(format_ins(opcode.mnemonic, &orig_args, orig_reloc.as_ref()))
+            } else {
+                None
+            };
+
+            ops.push(op);
+            insts.push(ObjIns {
+                address: cur_addr,
+                size: size as u8,
+                op,
+                mnemonic,
+                args,
+                reloc: reloc_out,
+                branch_dest,
+                line,
+                formatted,
+                orig,
+            });
+            offset += size;
+        }
+        Ok(ProcessCodeResult { ops, insts })
+    }
+
+    fn implcit_addend(
+        &self,
+        _section: &ObjSection,
+        address: u64,
+        reloc: &Relocation,
+    ) -> Result<i64> {
+        bail!("Unsupported RISC-V implicit relocation {:#x}:{:?}", address, reloc.flags())
+    }
+
+    fn ignore_relocation(&self, flags: RelocationFlags) -> bool {
+        // Linker relaxation hints, placed at the address of the relocation they annotate
+        matches!(flags, RelocationFlags::Elf { r_type: elf::R_RISCV_RELAX | elf::R_RISCV_ALIGN })
+    }
+
+    fn demangle(&self, name: &str) -> Option<String> {
+        cpp_demangle::Symbol::new(name)
+            .ok()
+            .and_then(|s| s.demangle(&cpp_demangle::DemangleOptions::default()).ok())
+    }
+
+    fn display_reloc(&self, flags: RelocationFlags) -> Cow<'static, str> {
+        match flags {
+            RelocationFlags::Elf { r_type } => match r_type {
+                elf::R_RISCV_32 => Cow::Borrowed("R_RISCV_32"),
+                elf::R_RISCV_64 => Cow::Borrowed("R_RISCV_64"),
+                elf::R_RISCV_BRANCH => Cow::Borrowed("R_RISCV_BRANCH"),
+                elf::R_RISCV_JAL => Cow::Borrowed("R_RISCV_JAL"),
+                elf::R_RISCV_CALL => Cow::Borrowed("R_RISCV_CALL"),
+                elf::R_RISCV_CALL_PLT => Cow::Borrowed("R_RISCV_CALL_PLT"),
+                elf::R_RISCV_GOT_HI20 => Cow::Borrowed("R_RISCV_GOT_HI20"),
+                elf::R_RISCV_TLS_GOT_HI20 => Cow::Borrowed("R_RISCV_TLS_GOT_HI20"),
+                elf::R_RISCV_TLS_GD_HI20 => Cow::Borrowed("R_RISCV_TLS_GD_HI20"),
+                elf::R_RISCV_PCREL_HI20 => Cow::Borrowed("R_RISCV_PCREL_HI20"),
+                elf::R_RISCV_PCREL_LO12_I => Cow::Borrowed("R_RISCV_PCREL_LO12_I"),
+                elf::R_RISCV_PCREL_LO12_S => Cow::Borrowed("R_RISCV_PCREL_LO12_S"),
+                elf::R_RISCV_HI20 => Cow::Borrowed("R_RISCV_HI20"),
+                elf::R_RISCV_LO12_I => Cow::Borrowed("R_RISCV_LO12_I"),
+                elf::R_RISCV_LO12_S => Cow::Borrowed("R_RISCV_LO12_S"),
+                elf::R_RISCV_TPREL_HI20 => Cow::Borrowed("R_RISCV_TPREL_HI20"),
+                elf::R_RISCV_TPREL_LO12_I => Cow::Borrowed("R_RISCV_TPREL_LO12_I"),
+                elf::R_RISCV_TPREL_LO12_S => Cow::Borrowed("R_RISCV_TPREL_LO12_S"),
+                elf::R_RISCV_TPREL_ADD => Cow::Borrowed("R_RISCV_TPREL_ADD"),
+                elf::R_RISCV_RVC_BRANCH => Cow::Borrowed("R_RISCV_RVC_BRANCH"),
+                elf::R_RISCV_RVC_JUMP => Cow::Borrowed("R_RISCV_RVC_JUMP"),
+                elf::R_RISCV_RVC_LUI => Cow::Borrowed("R_RISCV_RVC_LUI"),
+                elf::R_RISCV_32_PCREL => Cow::Borrowed("R_RISCV_32_PCREL"),
+                _ => Cow::Owned(format!("<{flags:?}>")),
+            },
+            _ => Cow::Owned(format!("<{flags:?}>")),
+        }
+    }
+
+    fn register_class(&self, arg: &ObjInsArgValue) -> Option<RegisterClass> {
+        let ObjInsArgValue::Opaque(name) = arg else {
+            return None;
+        };
+        let name: &str = name;
+        // zero, ra, sp, gp and tp have fixed roles
+        if GPR_NAMES[5..].contains(&name) {
+            Some(RegisterClass::Gpr)
+        } else if FPR_NAMES.contains(&name) {
+            Some(RegisterClass::Fpr)
+        } else {
+            None
+        }
+    }
+
+    fn get_reloc_byte_size(&self, flags: RelocationFlags) -> usize {
+        match flags {
+            RelocationFlags::Elf { r_type } => match r_type {
+                elf::R_RISCV_64 | elf::R_RISCV_ADD64 | elf::R_RISCV_SUB64 => 8,
+                // auipc and jalr pair
+                elf::R_RISCV_CALL | elf::R_RISCV_CALL_PLT => 8,
+                elf::R_RISCV_ADD16
+                | elf::R_RISCV_SUB16
+                | elf::R_RISCV_SET16
+                | elf::R_RISCV_RVC_BRANCH
+                | elf::R_RISCV_RVC_JUMP
+                | elf::R_RISCV_RVC_LUI => 2,
+                elf::R_RISCV_ADD8
+                | elf::R_RISCV_SUB8
+                | elf::R_RISCV_SET8
+                | elf::R_RISCV_SET6
+                | elf::R_RISCV_SUB6 => 1,
+                _ => 4,
+            },
+            _ => 4,
+        }
+    }
+
+    fn is_padding(&self, ins: &ObjIns) -> bool { matches!(ins.mnemonic.as_str(), "nop" | "c.nop") }
+
+    fn is_return(&self, ins: &ObjIns) -> bool {
+        ins.mnemonic == "ret" || (ins.mnemonic == "c.jr" && is_ra(ins))
+    }
+
+    fn is_indirect_jump(&self, ins: &ObjIns) -> bool {
+        ins.mnemonic == "jr" || (ins.mnemonic == "c.jr" && !is_ra(ins))
+    }
+}
+
+fn is_ra(ins: &ObjIns) -> bool {
+    matches!(ins.args.first(), Some(ObjInsArg::Arg(ObjInsArgValue::Opaque(name))) if name == "ra")
+}
+
+/// Point a `%pcrel_lo` relocation at the target of its `%pcrel_hi`. The low part refers
+/// to the label of the `auipc` that computed the high part, rather than to the symbol.
+fn resolve_pcrel_lo(section: &ObjSection, reloc: &mut ObjReloc) {
+    if !matches!(reloc.flags, RelocationFlags::Elf {
+        r_type: elf::R_RISCV_PCREL_LO12_I | elf::R_RISCV_PCREL_LO12_S
+    }) || reloc.target_section.as_deref() != Some(section.name.as_str())
+    {
+        return;
+    }
+    let hi_address = (reloc.target.address as i64 + reloc.target.addend) as u64;
+    if let Some(hi) = section.relocations.iter().find(|r| {
+        r.address == hi_address
+            && matches!(r.flags, RelocationFlags::Elf {
+                r_type: elf::R_RISCV_PCREL_HI20
+                    | elf::R_RISCV_GOT_HI20
+                    | elf::R_RISCV_TLS_GOT_HI20
+                    | elf::R_RISCV_TLS_GD_HI20
+            })
+    }) {
+        reloc.target = hi.target.clone();
+        reloc.target_section = hi.target_section.clone();
+    }
+}
+
+fn push_reloc(args: &mut Vec<ObjInsArg>, reloc: &ObjReloc) -> Result<()> {
+    let RelocationFlags::Elf { r_type } = reloc.flags else {
+        bail!("Unsupported RISC-V relocation kind: {:?}", reloc.flags);
+    };
+    let operator = match r_type {
+        elf::R_RISCV_HI20 | elf::R_RISCV_RVC_LUI => Some("%hi("),
+        elf::R_RISCV_LO12_I | elf::R_RISCV_LO12_S => Some("%lo("),
+        elf::R_RISCV_PCREL_HI20 => Some("%pcrel_hi("),
+        elf::R_RISCV_PCREL_LO12_I | elf::R_RISCV_PCREL_LO12_S => Some("%pcrel_lo("),
+        elf::R_RISCV_GOT_HI20 => Some("%got_pcrel_hi("),
+        elf::R_RISCV_TLS_GOT_HI20 => Some("%tls_ie_pcrel_hi("),
+        elf::R_RISCV_TLS_GD_HI20 => Some("%tls_gd_pcrel_hi("),
+        elf::R_RISCV_TPREL_HI20 => Some("%tprel_hi("),
+        elf::R_RISCV_TPREL_LO12_I | elf::R_RISCV_TPREL_LO12_S => Some("%tprel_lo("),
+        elf::R_RISCV_TPREL_ADD => Some("%tprel_add("),
+        elf::R_RISCV_BRANCH
+        | elf::R_RISCV_JAL
+        | elf::R_RISCV_CALL
+        | elf::R_RISCV_CALL_PLT
+        | elf::R_RISCV_RVC_BRANCH
+        | elf::R_RISCV_RVC_JUMP
+        | elf::R_RISCV_32
+        | elf::R_RISCV_64 => None,
+        _ => bail!("Unsupported ELF RISC-V relocation type {r_type}"),
+    };
+    match operator {
+        Some(operator) => {
+            args.push(ObjInsArg::PlainText(operator.into()));
+            args.push(ObjInsArg::Reloc);
+            args.push(ObjInsArg::PlainText(")".into()));
+        }
+        None => args.push(ObjInsArg::Reloc),
+    }
+    Ok(())
+}
+
+fn format_ins(mnemonic: &str, args: &[ObjInsArg], reloc: Option<&ObjReloc>) -> String {
+    let mut formatted = mnemonic.to_string();
+    for (idx, arg) in args.iter().enumerate() {
+        formatted.push_str(if idx == 0 { " " } else { "" });
+        match arg {
+            ObjInsArg::PlainText(s) => formatted.push_str(s),
+            ObjInsArg::Arg(v) => formatted.push_str(&v.to_string()),
+            ObjInsArg::Reloc => {
+                formatted.push_str(reloc.map(|r| r.target.name.as_str()).unwrap_or(""))
+            }
+            ObjInsArg::BranchDest(dest) => formatted.push_str(&format!("{dest:#x}")),
+            ObjInsArg::Bytes(bytes) => formatted.push_str(&format!("{bytes:02x?}")),
+        }
+    }
+    formatted
+}
+
+/// The instruction being decoded.
+struct Context<'a> {
+    section: &'a ObjSection,
+    symbol: &'a ObjSymbol,
+    address: u64,
+    separator: &'static str,
+}
+
+impl Context<'_> {
+    /// The destination of a branch relocation, if it stays within the function. Assemblers
+    /// keep relocations on local branches for linker relaxation; these are shown as plain
+    /// branches, so that differently numbered labels don't cause mismatches.
+    fn local_dest(&self, reloc: &ObjReloc) -> Option<u64> {
+        if reloc.target_section.as_deref() != Some(self.section.name.as_str()) {
+            return None;
+        }
+        let dest = (reloc.target.address as i64 + reloc.target.addend) as u64;
+        (self.symbol.address..self.symbol.address + self.symbol.size)
+            .contains(&dest)
+            .then_some(dest)
+    }
+}
+
+/// Build the arguments of an instruction. The first operand that can carry a relocation
+/// takes the instruction's relocation, if any. Returns the arguments, the relocation that
+/// remains attached to the instruction and the branch destination.
+fn build_args(
+    ctx: &Context,
+    operands: &[Operand],
+    code: u32,
+    mut reloc: Option<ObjReloc>,
+) -> Result<(Vec<ObjInsArg>, Option<ObjReloc>, Option<u64>)> {
+    let mut args = vec![];
+    let mut branch_dest = None;
+    let mut reloc_pending = reloc.is_some();
+    for &operand in operands {
+        if operand == Operand::Rm && bits(code, 14, 12) == 7 {
+            // Dynamic rounding mode is the default
+            continue;
+        }
+        if !args.is_empty() {
+            args.push(ObjInsArg::PlainText(ctx.separator.into()));
+        }
+        let imm = match operand {
+            Operand::Branch | Operand::Jump | Operand::CJump | Operand::CBranch => {
+                let disp = match operand {
+                    Operand::Branch => imm_b(code),
+                    Operand::Jump => imm_j(code),
+                    Operand::CJump => imm_cj(code),
+                    _ => imm_cb(code),
+                };
+                let dest = if reloc_pending {
+                    reloc_pending = false;
+                    let r = reloc.as_ref().unwrap();
+                    match ctx.local_dest(r) {
+                        Some(dest) => {
+                            reloc = None;
+                            Some(dest)
+                        }
+                        None => {
+                            push_reloc(&mut args, r)?;
+                            None
+                        }
+                    }
+                } else {
+                    Some((ctx.address as i64 + disp) as u64)
+                };
+                if let Some(dest) = dest {
+                    args.push(ObjInsArg::BranchDest(dest));
+                    branch_dest = Some(dest);
+                }
+                continue;
+            }
+            Operand::ImmI => ObjInsArgValue::Signed(imm_i(code)),
+            Operand::ImmU => ObjInsArgValue::Unsigned((code >> 12) as u64),
+            Operand::CImm => ObjInsArgValue::Signed(imm_ci(code)),
+            Operand::CLui => ObjInsArgValue::Unsigned((imm_ci(code) as u64) & 0xFFFFF),
+            Operand::CAddi4spn => ObjInsArgValue::Unsigned(imm_addi4spn(code) as u64),
+            Operand::CAddi16sp => ObjInsArgValue::Signed(imm_addi16sp(code)),
+            Operand::MemI | Operand::MemS | Operand::CMem(_) => {
+                let (disp, base) = match operand {
+                    Operand::MemI => (imm_i(code), gpr(bits(code, 19, 15))),
+                    Operand::MemS => (imm_s(code), gpr(bits(code, 19, 15))),
+                    Operand::CMem(kind) => {
+                        let base = match kind {
+                            CMemKind::Word | CMemKind::Double => gpr(8 + bits(code, 9, 7)),
+                            _ => gpr(2),
+                        };
+                        (cmem_offset(kind, code) as i64, base)
+                    }
+                    _ => unreachable!(),
+                };
+                if reloc_pending {
+                    reloc_pending = false;
+                    push_reloc(&mut args, reloc.as_ref().unwrap())?;
+                } else {
+                    args.push(ObjInsArg::Arg(ObjInsArgValue::Signed(disp)));
+                }
+                args.push(ObjInsArg::PlainText("(".into()));
+                args.push(base);
+                args.push(ObjInsArg::PlainText(")".into()));
+                continue;
+            }
+            Operand::TpRelAdd => {
+                // Only shown when present, as the operand doesn't change the encoding
+                if reloc_pending {
+                    reloc_pending = false;
+                    push_reloc(&mut args, reloc.as_ref().unwrap())?;
+                } else {
+                    args.pop();
+                }
+                continue;
+            }
+            _ => {
+                push_operand(&mut args, operand, code);
+                continue;
+            }
+        };
+        if reloc_pending {
+            reloc_pending = false;
+            push_reloc(&mut args, reloc.as_ref().unwrap())?;
+        } else {
+            args.push(ObjInsArg::Arg(imm));
+        }
+    }
+    Ok((args, reloc, branch_dest))
+}
+
+static GPR_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+static FPR_NAMES: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1", "fa2",
+    "fa3", "fa4", "fa5", "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9",
+    "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+static ROUNDING_MODES: [&str; 8] = ["rne", "rtz", "rdn", "rup", "rmm", "5", "6", "dyn"];
+
+fn csr_name(csr: u32) -> Option<&'static str> {
+    Some(match csr {
+        0x001 => "fflags",
+        0x002 => "frm",
+        0x003 => "fcsr",
+        0x100 => "sstatus",
+        0x104 => "sie",
+        0x105 => "stvec",
+        0x140 => "sscratch",
+        0x141 => "sepc",
+        0x142 => "scause",
+        0x143 => "stval",
+        0x144 => "sip",
+        0x180 => "satp",
+        0x300 => "mstatus",
+        0x301 => "misa",
+        0x304 => "mie",
+        0x305 => "mtvec",
+        0x340 => "mscratch",
+        0x341 => "mepc",
+        0x342 => "mcause",
+        0x343 => "mtval",
+        0x344 => "mip",
+        0xB00 => "mcycle",
+        0xB02 => "minstret",
+        0xC00 => "cycle",
+        0xC01 => "time",
+        0xC02 => "instret",
+        0xC80 => "cycleh",
+        0xC81 => "timeh",
+        0xC82 => "instreth",
+        0xF14 => "mhartid",
+        _ => return None,
+    })
+}
+
+#[inline]
+fn bits(code: u32, hi: u32, lo: u32) -> u32 { (code >> lo) & ((1 << (hi - lo + 1)) - 1) }
+
+#[inline]
+fn sext(value: u32, width: u32) -> i64 { ((value << (32 - width)) as i32 >> (32 - width)) as i64 }
+
+fn imm_i(code: u32) -> i64 { sext(code >> 20, 12) }
+
+fn imm_s(code: u32) -> i64 { sext(bits(code, 31, 25) << 5 | bits(code, 11, 7), 12) }
+
+fn imm_b(code: u32) -> i64 {
+    sext(
+        bits(code, 31, 31) << 12
+            | bits(code, 7, 7) << 11
+            | bits(code, 30, 25) << 5
+            | bits(code, 11, 8) << 1,
+        13,
+    )
+}
+
+fn imm_j(code: u32) -> i64 {
+    sext(
+        bits(code, 31, 31) << 20
+            | bits(code, 19, 12) << 12
+            | bits(code, 20, 20) << 11
+            | bits(code, 30, 21) << 1,
+        21,
+    )
+}
+
+fn imm_ci(code: u32) -> i64 { sext(bits(code, 12, 12) << 5 | bits(code, 6, 2), 6) }
+
+fn imm_addi4spn(code: u32) -> u32 {
+    bits(code, 12, 11) << 4 | bits(code, 10, 7) << 6 | bits(code, 6, 6) << 2 | bits(code, 5, 5) << 3
+}
+
+fn imm_addi16sp(code: u32) -> i64 {
+    sext(
+        bits(code, 12, 12) << 9
+            | bits(code, 6, 6) << 4
+            | bits(code, 5, 5) << 6
+            | bits(code, 4, 3) << 7
+            | bits(code, 2, 2) << 5,
+        10,
+    )
+}
+
+fn imm_cj(code: u32) -> i64 {
+    sext(
+        bits(code, 12, 12) << 11
+            | bits(code, 11, 11) << 4
+            | bits(code, 10, 9) << 8
+            | bits(code, 8, 8) << 10
+            | bits(code, 7, 7) << 6
+            | bits(code, 6, 6) << 7
+            | bits(code, 5, 3) << 1
+            | bits(code, 2, 2) << 5,
+        12,
+    )
+}
+
+fn imm_cb(code: u32) -> i64 {
+    sext(
+        bits(code, 12, 12) << 8
+            | bits(code, 11, 10) << 3
+            | bits(code, 6, 5) << 6
+            | bits(code, 4, 3) << 1
+            | bits(code, 2, 2) << 5,
+        9,
+    )
+}
+
+fn cmem_offset(kind: CMemKind, code: u32) -> u32 {
+    match kind {
+        CMemKind::Word => bits(code, 12, 10) << 3 | bits(code, 6, 6) << 2 | bits(code, 5, 5) << 6,
+        CMemKind::Double => bits(code, 12, 10) << 3 | bits(code, 6, 5) << 6,
+        CMemKind::LoadSpWord => {
+            bits(code, 12, 12) << 5 | bits(code, 6, 4) << 2 | bits(code, 3, 2) << 6
+        }
+        CMemKind::LoadSpDouble => {
+            bits(code, 12, 12) << 5 | bits(code, 6, 5) << 3 | bits(code, 4, 2) << 6
+        }
+        CMemKind::StoreSpWord => bits(code, 12, 9) << 2 | bits(code, 8, 7) << 6,
+        CMemKind::StoreSpDouble => bits(code, 12, 10) << 3 | bits(code, 9, 7) << 6,
+    }
+}
+
+fn gpr(num: u32) -> ObjInsArg {
+    ObjInsArg::Arg(ObjInsArgValue::Opaque(GPR_NAMES[num as usize & 31].into()))
+}
+
+fn fpr(num: u32) -> ObjInsArg {
+    ObjInsArg::Arg(ObjInsArgValue::Opaque(FPR_NAMES[num as usize & 31].into()))
+}
+
+fn fence_set(set: u32) -> String {
+    let mut out = String::new();
+    for (bit, c) in [(8, 'i'), (4, 'o'), (2, 'r'), (1, 'w')] {
+        if set & bit != 0 {
+            out.push(c);
+        }
+    }
+    if out.is_empty() {
+        out.push('0');
+    }
+    out
+}
+
+/// `.aq`, `.rl` or `.aqrl` suffix of atomic instructions.
+fn ordering_suffix(code: u32) -> &'static str {
+    match bits(code, 26, 25) {
+        0 => "",
+        1 => ".rl",
+        2 => ".aq",
+        _ => ".aqrl",
+    }
+}
+
+fn push_operand(args: &mut Vec<ObjInsArg>, operand: Operand, code: u32) {
+    match operand {
+        Operand::Rd | Operand::CRd => args.push(gpr(bits(code, 11, 7))),
+        Operand::Rs1 => args.push(gpr(bits(code, 19, 15))),
+        Operand::Rs2 => args.push(gpr(bits(code, 24, 20))),
+        Operand::Fd | Operand::CFRd => args.push(fpr(bits(code, 11, 7))),
+        Operand::Fs1 => args.push(fpr(bits(code, 19, 15))),
+        Operand::Fs2 => args.push(fpr(bits(code, 24, 20))),
+        Operand::Fs3 => args.push(fpr(bits(code, 31, 27))),
+        Operand::CRs2 => args.push(gpr(bits(code, 6, 2))),
+        Operand::CFRs2 => args.push(fpr(bits(code, 6, 2))),
+        Operand::CRegHi => args.push(gpr(8 + bits(code, 9, 7))),
+        Operand::CRegLo => args.push(gpr(8 + bits(code, 4, 2))),
+        Operand::CFRegLo => args.push(fpr(8 + bits(code, 4, 2))),
+        Operand::Shamt => {
+            args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(bits(code, 25, 20) as u64)))
+        }
+        Operand::CShamt => args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(
+            (bits(code, 12, 12) << 5 | bits(code, 6, 2)) as u64,
+        ))),
+        Operand::Zimm => {
+            args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(bits(code, 19, 15) as u64)))
+        }
+        Operand::Csr => {
+            let csr = code >> 20;
+            args.push(ObjInsArg::Arg(match csr_name(csr) {
+                Some(name) => ObjInsArgValue::Opaque(name.into()),
+                None => ObjInsArgValue::Unsigned(csr as u64),
+            }))
+        }
+        Operand::MemA => {
+            args.push(ObjInsArg::PlainText("(".into()));
+            args.push(gpr(bits(code, 19, 15)));
+            args.push(ObjInsArg::PlainText(")".into()));
+        }
+        Operand::Pred => {
+            args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(fence_set(bits(code, 27, 24)).into())))
+        }
+        Operand::Succ => {
+            args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(fence_set(bits(code, 23, 20)).into())))
+        }
+        Operand::Rm => args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(
+            ROUNDING_MODES[bits(code, 14, 12) as usize].into(),
+        ))),
+        Operand::Reg(name) => args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(name.into()))),
+        Operand::ImmI
+        | Operand::ImmU
+        | Operand::MemI
+        | Operand::MemS
+        | Operand::Branch
+        | Operand::Jump
+        | Operand::TpRelAdd
+        | Operand::CImm
+        | Operand::CLui
+        | Operand::CAddi4spn
+        | Operand::CAddi16sp
+        | Operand::CMem(_)
+        | Operand::CJump
+        | Operand::CBranch => unreachable!(),
+    }
+}
+
+/// Layout of the offset of a compressed load or store.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum CMemKind {
+    /// c.lw, c.sw, c.flw and c.fsw, relative to a register from bits 7-9
+    Word,
+    /// c.ld, c.sd, c.fld and c.fsd, relative to a register from bits 7-9
+    Double,
+    /// c.lwsp and c.flwsp
+    LoadSpWord,
+    /// c.ldsp and c.fldsp
+    LoadSpDouble,
+    /// c.swsp and c.fswsp
+    StoreSpWord,
+    /// c.sdsp and c.fsdsp
+    StoreSpDouble,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Operand {
+    /// Integer register from bits 7-11
+    Rd,
+    /// Integer register from bits 15-19
+    Rs1,
+    /// Integer register from bits 20-24
+    Rs2,
+    Fd,
+    Fs1,
+    Fs2,
+    /// Float register from bits 27-31
+    Fs3,
+    /// Sign-extended 12-bit immediate
+    ImmI,
+    /// Upper 20-bit immediate
+    ImmU,
+    /// Shift amount
+    Shamt,
+    Csr,
+    /// 5-bit immediate of the csr*i instructions
+    Zimm,
+    /// imm(rs1) of loads and jalr
+    MemI,
+    /// imm(rs1) of stores
+    MemS,
+    /// (rs1) of atomics
+    MemA,
+    /// PC-relative conditional branch displacement
+    Branch,
+    /// PC-relative jal displacement
+    Jump,
+    /// Fence predecessor set
+    Pred,
+    /// Fence successor set
+    Succ,
+    /// Rounding mode, shown unless dynamic
+    Rm,
+    /// `%tprel_add` annotation of thread pointer additions
+    TpRelAdd,
+    /// Integer register from bits 7-11 of a compressed instruction
+    CRd,
+    /// Integer register from bits 2-6 of a compressed instruction
+    CRs2,
+    CFRd,
+    CFRs2,
+    /// One of x8-x15 from bits 7-9
+    CRegHi,
+    /// One of x8-x15 from bits 2-4
+    CRegLo,
+    /// One of f8-f15 from bits 2-4
+    CFRegLo,
+    /// Sign-extended 6-bit immediate
+    CImm,
+    /// 6-bit shift amount
+    CShamt,
+    /// Upper immediate of c.lui
+    CLui,
+    /// Stack offset of c.addi4spn
+    CAddi4spn,
+    /// Stack adjustment of c.addi16sp
+    CAddi16sp,
+    CMem(CMemKind),
+    CJump,
+    CBranch,
+    /// Fixed register
+    Reg(&'static str),
+}
+
+/// Base ISAs an instruction is valid in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Xlen {
+    Any,
+    Rv32,
+    Rv64,
+}
+
+struct Opcode {
+    mask: u32,
+    pattern: u32,
+    mnemonic: &'static str,
+    operands: &'static [Operand],
+    xlen: Xlen,
+    /// Whether the instruction takes `.aq` and `.rl` ordering suffixes
+    ordered: bool,
+}
+
+const fn op(
+    mask: u32,
+    pattern: u32,
+    mnemonic: &'static str,
+    operands: &'static [Operand],
+) -> Opcode {
+    Opcode { mask, pattern, mnemonic, operands, xlen: Xlen::Any, ordered: false }
+}
+
+const fn rv32(
+    mask: u32,
+    pattern: u32,
+    mnemonic: &'static str,
+    operands: &'static [Operand],
+) -> Opcode {
+    Opcode { mask, pattern, mnemonic, operands, xlen: Xlen::Rv32, ordered: false }
+}
+
+const fn rv64(
+    mask: u32,
+    pattern: u32,
+    mnemonic: &'static str,
+    operands: &'static [Operand],
+) -> Opcode {
+    Opcode { mask, pattern, mnemonic, operands, xlen: Xlen::Rv64, ordered: false }
+}
+
+/// Atomic memory operation, with the ordering bits excluded from the mask.
+const fn amo(
+    pattern: u32,
+    mnemonic: &'static str,
+    operands: &'static [Operand],
+    xlen: Xlen,
+) -> Opcode {
+    let mask = if operands.len() == 2 { 0xF9F0707F } else { 0xF800707F };
+    Opcode { mask, pattern, mnemonic, operands, xlen, ordered: true }
+}
+
+fn find(table: &'static [Opcode], code: u32, rv64: bool) -> Option<(usize, &'static Opcode)> {
+    table.iter().enumerate().find(|(_, o)| {
+        code & o.mask == o.pattern
+            && match o.xlen {
+                Xlen::Any => true,
+                Xlen::Rv32 => !rv64,
+                Xlen::Rv64 => rv64,
+            }
+    })
+}
+
+/// Returns the index of the matching opcode, which is used as the instruction's op.
+fn decode(code: u32, rv64: bool) -> Option<(u16, &'static Opcode)> {
+    find(OPCODES, code, rv64).map(|(idx, o)| (idx as u16, o))
+}
+
+fn decode_compressed(code: u16, rv64: bool) -> Option<(u16, &'static Opcode)> {
+    find(COMPRESSED_OPCODES, code as u32, rv64).map(|(idx, o)| (COMPRESSED_OP_BASE + idx as u16, o))
+}
+
+/// Pseudo-instructions as printed by objdump, for the instructions that match one.
+fn simplify(
+    opcode: &Opcode,
+    code: u32,
+    has_reloc: bool,
+) -> Option<(&'static str, &'static [Operand])> {
+    let rd = bits(code, 11, 7);
+    let rs1 = bits(code, 19, 15);
+    let rs2 = bits(code, 24, 20);
+    let imm = imm_i(code);
+    let simplified: (&'static str, &'static [Operand]) = match opcode.mnemonic {
+        "addi" if rd == 0 && rs1 == 0 && imm == 0 => ("nop", &[]),
+        "addi" if rs1 == 0 && !has_reloc => ("li", &[Rd, ImmI]),
+        "addi" if imm == 0 && !has_reloc => ("mv", &[Rd, Rs1]),
+        "addiw" if imm == 0 && !has_reloc => ("sext.w", &[Rd, Rs1]),
+        "xori" if imm == -1 => ("not", &[Rd, Rs1]),
+        "sltiu" if imm == 1 => ("seqz", &[Rd, Rs1]),
+        "sltu" if rs1 == 0 => ("snez", &[Rd, Rs2]),
+        "sub" if rs1 == 0 => ("neg", &[Rd, Rs2]),
+        "subw" if rs1 == 0 => ("negw", &[Rd, Rs2]),
+        "jal" if rd == 0 => ("j", &[Jump]),
+        "jal" if rd == 1 => ("jal", &[Jump]),
+        "jalr" if rd == 0 && rs1 == 1 && imm == 0 && !has_reloc => ("ret", &[]),
+        "jalr" if rd == 0 && imm == 0 && !has_reloc => ("jr", &[Rs1]),
+        "jalr" if rd == 1 && imm == 0 && !has_reloc => ("jalr", &[Rs1]),
+        "beq" if rs2 == 0 => ("beqz", &[Rs1, Branch]),
+        "bne" if rs2 == 0 => ("bnez", &[Rs1, Branch]),
+        "csrrs" if rs1 == 0 => ("csrr", &[Rd, Csr]),
+        "csrrw" if rd == 0 => ("csrw", &[Csr, Rs1]),
+        "fence" if bits(code, 27, 20) == 0xFF => ("fence", &[]),
+        "fsgnj.s" if rs1 == rs2 => ("fmv.s", &[Fd, Fs1]),
+        "fsgnj.d" if rs1 == rs2 => ("fmv.d", &[Fd, Fs1]),
+        "fsgnjn.s" if rs1 == rs2 => ("fneg.s", &[Fd, Fs1]),
+        "fsgnjn.d" if rs1 == rs2 => ("fneg.d", &[Fd, Fs1]),
+        "fsgnjx.s" if rs1 == rs2 => ("fabs.s", &[Fd, Fs1]),
+        "fsgnjx.d" if rs1 == rs2 => ("fabs.d", &[Fd, Fs1]),
+        _ => return None,
+    };
+    Some(simplified)
+}
+
+use Operand::*;
+
+const R: u32 = 0xFE00707F;
+const I: u32 = 0x0000707F;
+const U: u32 = 0x0000007F;
+/// R-type with a fixed rs2 field
+const R1: u32 = 0xFFF0707F;
+/// Floating-point R-type, excluding the rounding mode
+const FR: u32 = 0xFE00007F;
+/// Floating-point R-type with a fixed rs2 field, excluding the rounding mode
+const FR1: u32 = 0xFFF0007F;
+/// Fused multiply-add, excluding the rounding mode
+const R4: u32 = 0x0600007F;
+
+/// RV32/RV64 IMAFD and Zicsr/Zifencei instruction table. More specific masks must come
+/// first.
+static OPCODES: &[Opcode] = &[
+    // Fixed
+    op(0xFFFFFFFF, 0x00000073, "ecall", &[]),
+    op(0xFFFFFFFF, 0x00100073, "ebreak", &[]),
+    op(0xFFFFFFFF, 0x10200073, "sret", &[]),
+    op(0xFFFFFFFF, 0x30200073, "mret", &[]),
+    op(0xFFFFFFFF, 0x10500073, "wfi", &[]),
+    op(0xFFFFFFFF, 0x8330000F, "fence.tso", &[]),
+    op(0xFE007FFF, 0x12000073, "sfence.vma", &[Rs1, Rs2]),
+    op(I, 0x0000100F, "fence.i", &[]),
+    op(I, 0x0000000F, "fence", &[Pred, Succ]),
+    // Upper immediates and jumps
+    op(U, 0x00000037, "lui", &[Rd, ImmU]),
+    op(U, 0x00000017, "auipc", &[Rd, ImmU]),
+    op(U, 0x0000006F, "jal", &[Rd, Jump]),
+    op(I, 0x00000067, "jalr", &[Rd, MemI]),
+    // Branches
+    op(I, 0x00000063, "beq", &[Rs1, Rs2, Branch]),
+    op(I, 0x00001063, "bne", &[Rs1, Rs2, Branch]),
+    op(I, 0x00004063, "blt", &[Rs1, Rs2, Branch]),
+    op(I, 0x00005063, "bge", &[Rs1, Rs2, Branch]),
+    op(I, 0x00006063, "bltu", &[Rs1, Rs2, Branch]),
+    op(I, 0x00007063, "bgeu", &[Rs1, Rs2, Branch]),
+    // Loads and stores
+    op(I, 0x00000003, "lb", &[Rd, MemI]),
+    op(I, 0x00001003, "lh", &[Rd, MemI]),
+    op(I, 0x00002003, "lw", &[Rd, MemI]),
+    rv64(I, 0x00003003, "ld", &[Rd, MemI]),
+    op(I, 0x00004003, "lbu", &[Rd, MemI]),
+    op(I, 0x00005003, "lhu", &[Rd, MemI]),
+    rv64(I, 0x00006003, "lwu", &[Rd, MemI]),
+    op(I, 0x00000023, "sb", &[Rs2, MemS]),
+    op(I, 0x00001023, "sh", &[Rs2, MemS]),
+    op(I, 0x00002023, "sw", &[Rs2, MemS]),
+    rv64(I, 0x00003023, "sd", &[Rs2, MemS]),
+    // Immediate arithmetic
+    op(0xFC00707F, 0x00001013, "slli", &[Rd, Rs1, Shamt]),
+    op(0xFC00707F, 0x00005013, "srli", &[Rd, Rs1, Shamt]),
+    op(0xFC00707F, 0x40005013, "srai", &[Rd, Rs1, Shamt]),
+    op(I, 0x00000013, "addi", &[Rd, Rs1, ImmI]),
+    op(I, 0x00002013, "slti", &[Rd, Rs1, ImmI]),
+    op(I, 0x00003013, "sltiu", &[Rd, Rs1, ImmI]),
+    op(I, 0x00004013, "xori", &[Rd, Rs1, ImmI]),
+    op(I, 0x00006013, "ori", &[Rd, Rs1, ImmI]),
+    op(I, 0x00007013, "andi", &[Rd, Rs1, ImmI]),
+    rv64(R, 0x0000101B, "slliw", &[Rd, Rs1, Shamt]),
+    rv64(R, 0x0000501B, "srliw", &[Rd, Rs1, Shamt]),
+    rv64(R, 0x4000501B, "sraiw", &[Rd, Rs1, Shamt]),
+    rv64(I, 0x0000001B, "addiw", &[Rd, Rs1, ImmI]),
+    // Register arithmetic
+    op(R, 0x00000033, "add", &[Rd, Rs1, Rs2, TpRelAdd]),
+    op(R, 0x40000033, "sub", &[Rd, Rs1, Rs2]),
+    op(R, 0x00001033, "sll", &[Rd, Rs1, Rs2]),
+    op(R, 0x00002033, "slt", &[Rd, Rs1, Rs2]),
+    op(R, 0x00003033, "sltu", &[Rd, Rs1, Rs2]),
+    op(R, 0x00004033, "xor", &[Rd, Rs1, Rs2]),
+    op(R, 0x00005033, "srl", &[Rd, Rs1, Rs2]),
+    op(R, 0x40005033, "sra", &[Rd, Rs1, Rs2]),
+    op(R, 0x00006033, "or", &[Rd, Rs1, Rs2]),
+    op(R, 0x00007033, "and", &[Rd, Rs1, Rs2]),
+    rv64(R, 0x0000003B, "addw", &[Rd, Rs1, Rs2]),
+    rv64(R, 0x4000003B, "subw", &[Rd, Rs1, Rs2]),
+    rv64(R, 0x0000103B, "sllw", &[Rd, Rs1, Rs2]),
+    rv64(R, 0x0000503B, "srlw", &[Rd, Rs1, Rs2]),
+    rv64(R, 0x4000503B, "sraw", &[Rd, Rs1, Rs2]),
+    // M
+    op(R, 0x02000033, "mul", &[Rd, Rs1, Rs2]),
+    op(R, 0x02001033, "mulh", &[Rd, Rs1, Rs2]),
+    op(R, 0x02002033, "mulhsu", &[Rd, Rs1, Rs2]),
+    op(R, 0x02003033, "mulhu", &[Rd, Rs1, Rs2]),
+    op(R, 0x02004033, "div", &[Rd, Rs1, Rs2]),
+    op(R, 0x02005033, "divu", &[Rd, Rs1, Rs2]),
+    op(R, 0x02006033, "rem", &[Rd, Rs1, Rs2]),
+    op(R, 0x02007033, "remu", &[Rd, Rs1, Rs2]),
+    rv64(R, 0x0200003B, "mulw", &[Rd, Rs1, Rs2]),
+    rv64(R, 0x0200403B, "divw", &[Rd, Rs1, Rs2]),
+    rv64(R, 0x0200503B, "divuw", &[Rd, Rs1, Rs2]),
+    rv64(R, 0x0200603B, "remw", &[Rd, Rs1, Rs2]),
+    rv64(R, 0x0200703B, "remuw", &[Rd, Rs1, Rs2]),
+    // A
+    amo(0x1000202F, "lr.w", &[Rd, MemA], Xlen::Any),
+    amo(0x1800202F, "sc.w", &[Rd, Rs2, MemA], Xlen::Any),
+    amo(0x0800202F, "amoswap.w", &[Rd, Rs2, MemA], Xlen::Any),
+    amo(0x0000202F, "amoadd.w", &[Rd, Rs2, MemA], Xlen::Any),
+    amo(0x2000202F, "amoxor.w", &[Rd, Rs2, MemA], Xlen::Any),
+    amo(0x6000202F, "amoand.w", &[Rd, Rs2, MemA], Xlen::Any),
+    amo(0x4000202F, "amoor.w", &[Rd, Rs2, MemA], Xlen::Any),
+    amo(0x8000202F, "amomin.w", &[Rd, Rs2, MemA], Xlen::Any),
+    amo(0xA000202F, "amomax.w", &[Rd, Rs2, MemA], Xlen::Any),
+    amo(0xC000202F, "amominu.w", &[Rd, Rs2, MemA], Xlen::Any),
+    amo(0xE000202F, "amomaxu.w", &[Rd, Rs2, MemA], Xlen::Any),
+    amo(0x1000302F, "lr.d", &[Rd, MemA], Xlen::Rv64),
+    amo(0x1800302F, "sc.d", &[Rd, Rs2, MemA], Xlen::Rv64),
+    amo(0x0800302F, "amoswap.d", &[Rd, Rs2, MemA], Xlen::Rv64),
+    amo(0x0000302F, "amoadd.d", &[Rd, Rs2, MemA], Xlen::Rv64),
+    amo(0x2000302F, "amoxor.d", &[Rd, Rs2, MemA], Xlen::Rv64),
+    amo(0x6000302F, "amoand.d", &[Rd, Rs2, MemA], Xlen::Rv64),
+    amo(0x4000302F, "amoor.d", &[Rd, Rs2, MemA], Xlen::Rv64),
+    amo(0x8000302F, "amomin.d", &[Rd, Rs2, MemA], Xlen::Rv64),
+    amo(0xA000302F, "amomax.d", &[Rd, Rs2, MemA], Xlen::Rv64),
+    amo(0xC000302F, "amominu.d", &[Rd, Rs2, MemA], Xlen::Rv64),
+    amo(0xE000302F, "amomaxu.d", &[Rd, Rs2, MemA], Xlen::Rv64),
+    // Zicsr
+    op(I, 0x00001073, "csrrw", &[Rd, Csr, Rs1]),
+    op(I, 0x00002073, "csrrs", &[Rd, Csr, Rs1]),
+    op(I, 0x00003073, "csrrc", &[Rd, Csr, Rs1]),
+    op(I, 0x00005073, "csrrwi", &[Rd, Csr, Zimm]),
+    op(I, 0x00006073, "csrrsi", &[Rd, Csr, Zimm]),
+    op(I, 0x00007073, "csrrci", &[Rd, Csr, Zimm]),
+    // F and D loads and stores
+    op(I, 0x00002007, "flw", &[Fd, MemI]),
+    op(I, 0x00003007, "fld", &[Fd, MemI]),
+    op(I, 0x00002027, "fsw", &[Fs2, MemS]),
+    op(I, 0x00003027, "fsd", &[Fs2, MemS]),
+    // Fused multiply-add
+    op(R4, 0x00000043, "fmadd.s", &[Fd, Fs1, Fs2, Fs3, Rm]),
+    op(R4, 0x00000047, "fmsub.s", &[Fd, Fs1, Fs2, Fs3, Rm]),
+    op(R4, 0x0000004B, "fnmsub.s", &[Fd, Fs1, Fs2, Fs3, Rm]),
+    op(R4, 0x0000004F, "fnmadd.s", &[Fd, Fs1, Fs2, Fs3, Rm]),
+    op(R4, 0x02000043, "fmadd.d", &[Fd, Fs1, Fs2, Fs3, Rm]),
+    op(R4, 0x02000047, "fmsub.d", &[Fd, Fs1, Fs2, Fs3, Rm]),
+    op(R4, 0x0200004B, "fnmsub.d", &[Fd, Fs1, Fs2, Fs3, Rm]),
+    op(R4, 0x0200004F, "fnmadd.d", &[Fd, Fs1, Fs2, Fs3, Rm]),
+    // F arithmetic
+    op(FR, 0x00000053, "fadd.s", &[Fd, Fs1, Fs2, Rm]),
+    op(FR, 0x08000053, "fsub.s", &[Fd, Fs1, Fs2, Rm]),
+    op(FR, 0x10000053, "fmul.s", &[Fd, Fs1, Fs2, Rm]),
+    op(FR, 0x18000053, "fdiv.s", &[Fd, Fs1, Fs2, Rm]),
+    op(FR1, 0x58000053, "fsqrt.s", &[Fd, Fs1, Rm]),
+    op(R, 0x20000053, "fsgnj.s", &[Fd, Fs1, Fs2]),
+    op(R, 0x20001053, "fsgnjn.s", &[Fd, Fs1, Fs2]),
+    op(R, 0x20002053, "fsgnjx.s", &[Fd, Fs1, Fs2]),
+    op(R, 0x28000053, "fmin.s", &[Fd, Fs1, Fs2]),
+    op(R, 0x28001053, "fmax.s", &[Fd, Fs1, Fs2]),
+    op(FR1, 0xC0000053, "fcvt.w.s", &[Rd, Fs1, Rm]),
+    op(FR1, 0xC0100053, "fcvt.wu.s", &[Rd, Fs1, Rm]),
+    rv64(FR1, 0xC0200053, "fcvt.l.s", &[Rd, Fs1, Rm]),
+    rv64(FR1, 0xC0300053, "fcvt.lu.s", &[Rd, Fs1, Rm]),
+    op(FR1, 0xD0000053, "fcvt.s.w", &[Fd, Rs1, Rm]),
+    op(FR1, 0xD0100053, "fcvt.s.wu", &[Fd, Rs1, Rm]),
+    rv64(FR1, 0xD0200053, "fcvt.s.l", &[Fd, Rs1, Rm]),
+    rv64(FR1, 0xD0300053, "fcvt.s.lu", &[Fd, Rs1, Rm]),
+    op(R1, 0xE0000053, "fmv.x.w", &[Rd, Fs1]),
+    op(R1, 0xE0001053, "fclass.s", &[Rd, Fs1]),
+    op(R1, 0xF0000053, "fmv.w.x", &[Fd, Rs1]),
+    op(R, 0xA0002053, "feq.s", &[Rd, Fs1, Fs2]),
+    op(R, 0xA0001053, "flt.s", &[Rd, Fs1, Fs2]),
+    op(R, 0xA0000053, "fle.s", &[Rd, Fs1, Fs2]),
+    // D arithmetic
+    op(FR, 0x02000053, "fadd.d", &[Fd, Fs1, Fs2, Rm]),
+    op(FR, 0x0A000053, "fsub.d", &[Fd, Fs1, Fs2, Rm]),
+    op(FR, 0x12000053, "fmul.d", &[Fd, Fs1, Fs2, Rm]),
+    op(FR, 0x1A000053, "fdiv.d", &[Fd, Fs1, Fs2, Rm]),
+    op(FR1, 0x5A000053, "fsqrt.d", &[Fd, Fs1, Rm]),
+    op(R, 0x22000053, "fsgnj.d", &[Fd, Fs1, Fs2]),
+    op(R, 0x22001053, "fsgnjn.d", &[Fd, Fs1, Fs2]),
+    op(R, 0x22002053, "fsgnjx.d", &[Fd, Fs1, Fs2]),
+    op(R, 0x2A000053, "fmin.d", &[Fd, Fs1, Fs2]),
+    op(R, 0x2A001053, "fmax.d", &[Fd, Fs1, Fs2]),
+    op(FR1, 0x40100053, "fcvt.s.d", &[Fd, Fs1, Rm]),
+    op(FR1, 0x42000053, "fcvt.d.s", &[Fd, Fs1, Rm]),
+    op(FR1, 0xC2000053, "fcvt.w.d", &[Rd, Fs1, Rm]),
+    op(FR1, 0xC2100053, "fcvt.wu.d", &[Rd, Fs1, Rm]),
+    rv64(FR1, 0xC2200053, "fcvt.l.d", &[Rd, Fs1, Rm]),
+    rv64(FR1, 0xC2300053, "fcvt.lu.d", &[Rd, Fs1, Rm]),
+    op(FR1, 0xD2000053, "fcvt.d.w", &[Fd, Rs1, Rm]),
+    op(FR1, 0xD2100053, "fcvt.d.wu", &[Fd, Rs1, Rm]),
+    rv64(FR1, 0xD2200053, "fcvt.d.l", &[Fd, Rs1, Rm]),
+    rv64(FR1, 0xD2300053, "fcvt.d.lu", &[Fd, Rs1, Rm]),
+    rv64(R1, 0xE2000053, "fmv.x.d", &[Rd, Fs1]),
+    op(R1, 0xE2001053, "fclass.d", &[Rd, Fs1]),
+    rv64(R1, 0xF2000053, "fmv.d.x", &[Fd, Rs1]),
+    op(R, 0xA2002053, "feq.d", &[Rd, Fs1, Fs2]),
+    op(R, 0xA2001053, "flt.d", &[Rd, Fs1, Fs2]),
+    op(R, 0xA2000053, "fle.d", &[Rd, Fs1, Fs2]),
+];
+
+const CI: u32 = 0xE003;
+
+/// RV32C/RV64C instruction table, including the compressed F and D instructions. More
+/// specific masks must come first.
+static COMPRESSED_OPCODES: &[Opcode] = &[
+    // Quadrant 0
+    op(CI, 0x0000, "c.addi4spn", &[CRegLo, Reg("sp"), CAddi4spn]),
+    op(CI, 0x2000, "c.fld", &[CFRegLo, CMem(CMemKind::Double)]),
+    op(CI, 0x4000, "c.lw", &[CRegLo, CMem(CMemKind::Word)]),
+    rv32(CI, 0x6000, "c.flw", &[CFRegLo, CMem(CMemKind::Word)]),
+    rv64(CI, 0x6000, "c.ld", &[CRegLo, CMem(CMemKind::Double)]),
+    op(CI, 0xA000, "c.fsd", &[CFRegLo, CMem(CMemKind::Double)]),
+    op(CI, 0xC000, "c.sw", &[CRegLo, CMem(CMemKind::Word)]),
+    rv32(CI, 0xE000, "c.fsw", &[CFRegLo, CMem(CMemKind::Word)]),
+    rv64(CI, 0xE000, "c.sd", &[CRegLo, CMem(CMemKind::Double)]),
+    // Quadrant 1
+    op(0xFFFF, 0x0001, "c.nop", &[]),
+    op(CI, 0x0001, "c.addi", &[CRd, CImm]),
+    rv32(CI, 0x2001, "c.jal", &[CJump]),
+    rv64(CI, 0x2001, "c.addiw", &[CRd, CImm]),
+    op(CI, 0x4001, "c.li", &[CRd, CImm]),
+    op(0xEF83, 0x6101, "c.addi16sp", &[Reg("sp"), CAddi16sp]),
+    op(CI, 0x6001, "c.lui", &[CRd, CLui]),
+    op(0xEC03, 0x8001, "c.srli", &[CRegHi, CShamt]),
+    op(0xEC03, 0x8401, "c.srai", &[CRegHi, CShamt]),
+    op(0xEC03, 0x8801, "c.andi", &[CRegHi, CImm]),
+    op(0xFC63, 0x8C01, "c.sub", &[CRegHi, CRegLo]),
+    op(0xFC63, 0x8C21, "c.xor", &[CRegHi, CRegLo]),
+    op(0xFC63, 0x8C41, "c.or", &[CRegHi, CRegLo]),
+    op(0xFC63, 0x8C61, "c.and", &[CRegHi, CRegLo]),
+    rv64(0xFC63, 0x9C01, "c.subw", &[CRegHi, CRegLo]),
+    rv64(0xFC63, 0x9C21, "c.addw", &[CRegHi, CRegLo]),
+    op(CI, 0xA001, "c.j", &[CJump]),
+    op(CI, 0xC001, "c.beqz", &[CRegHi, CBranch]),
+    op(CI, 0xE001, "c.bnez", &[CRegHi, CBranch]),
+    // Quadrant 2
+    op(CI, 0x0002, "c.slli", &[CRd, CShamt]),
+    op(CI, 0x2002, "c.fldsp", &[CFRd, CMem(CMemKind::LoadSpDouble)]),
+    op(CI, 0x4002, "c.lwsp", &[CRd, CMem(CMemKind::LoadSpWord)]),
+    rv32(CI, 0x6002, "c.flwsp", &[CFRd, CMem(CMemKind::LoadSpWord)]),
+    rv64(CI, 0x6002, "c.ldsp", &[CRd, CMem(CMemKind::LoadSpDouble)]),
+    op(0xF07F, 0x8002, "c.jr", &[CRd]),
+    op(0xF003, 0x8002, "c.mv", &[CRd, CRs2]),
+    op(0xFFFF, 0x9002, "c.ebreak", &[]),
+    op(0xF07F, 0x9002, "c.jalr", &[CRd]),
+    op(0xF003, 0x9002, "c.add", &[CRd, CRs2]),
+    op(CI, 0xA002, "c.fsdsp", &[CFRs2, CMem(CMemKind::StoreSpDouble)]),
+    op(CI, 0xC002, "c.swsp", &[CRs2, CMem(CMemKind::StoreSpWord)]),
+    rv32(CI, 0xE002, "c.fswsp", &[CFRs2, CMem(CMemKind::StoreSpWord)]),
+    rv64(CI, 0xE002, "c.sdsp", &[CRs2, CMem(CMemKind::StoreSpDouble)]),
+];
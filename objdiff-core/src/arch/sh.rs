@@ -0,0 +1,551 @@
+use std::borrow::Cow;
+
+use anyhow::{anyhow, bail, Result};
+use object::{elf, Endian, Endianness, File, Object, Relocation, RelocationFlags};
+
+use crate::{
+    arch::{bytes_ins, ObjArch, ProcessCodeResult, OP_BYTES},
+    diff::DiffObjConfig,
+    obj::{ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjSection, SymbolRef},
+};
+
+pub struct ObjArchSh {
+    endianness: Endianness,
+}
+
+impl ObjArchSh {
+    pub fn new(object: &File) -> Result<Self> { Ok(Self { endianness: object.endianness() }) }
+}
+
+/// Pseudo-op used for 32-bit literal pool entries carrying a relocation.
+const OP_LONG: u16 = u16::MAX;
+
+impl ObjArch for ObjArchSh {
+    fn process_code(
+        &self,
+        obj: &ObjInfo,
+        symbol_ref: SymbolRef,
+        config: &DiffObjConfig,
+    ) -> Result<ProcessCodeResult> {
+        let (section, symbol) = obj.section_symbol(symbol_ref);
+        let section = section.ok_or_else(|| anyhow!("Code symbol section not found"))?;
+        let code = &section.data
+            [symbol.section_address as usize..(symbol.section_address + symbol.size) as usize];
+
+        let ins_count = code.len() / 2;
+        let mut ops = Vec::<u16>::with_capacity(ins_count);
+        let mut insts = Vec::<ObjIns>::with_capacity(ins_count);
+        let mut offset = 0usize;
+        while offset + 2 <= code.len() {
+            let cur_addr = symbol.address + offset as u64;
+            let line = section.line_info.range(..=cur_addr).last().map(|(_, &b)| b);
+
+            // Literal pool entries are emitted as a relocated .long
+            if let Some(reloc) = section.relocations.iter().find(|r| {
+                r.address == cur_addr
+                    && matches!(r.flags, RelocationFlags::Elf { r_type: elf::R_SH_DIR32 })
+            }) {
+                if offset + 4 <= code.len() {
+                    ops.push(OP_LONG);
+                    insts.push(ObjIns {
+                        address: cur_addr,
+                        size: 4,
+                        op: OP_LONG,
+                        mnemonic: ".long".to_string(),
+                        args: vec![ObjInsArg::Reloc],
+                        reloc: Some(reloc.clone()),
+                        branch_dest: None,
+                        line,
+                        formatted: format!(".long {}", reloc.target.name),
+                        orig: None,
+                    });
+                    offset += 4;
+                    continue;
+                }
+            }
+
+            let code_bytes: [u8; 2] = code[offset..offset + 2].try_into()?;
+            let code = self.endianness.read_u16_bytes(code_bytes);
+            let reloc = section.relocations.iter().find(|r| r.address & !1 == cur_addr);
+            let Some((op, opcode)) = decode(code) else {
+                ops.push(OP_BYTES);
+                insts.push(bytes_ins(cur_addr, &code_bytes, reloc, line));
+                offset += 2;
+                continue;
+            };
+
+            let mut args = vec![];
+            let mut branch_dest = None;
+            let mut ins_reloc = reloc.cloned();
+            for (idx, operand) in opcode.operands.iter().enumerate() {
+                if idx > 0 {
+                    args.push(ObjInsArg::PlainText(config.separator().into()));
+                }
+                match *operand {
+                    Operand::Label8 | Operand::Label12 => {
+                        let disp = if *operand == Operand::Label8 {
+                            (code & 0xFF) as i8 as i64
+                        } else {
+                            (((code & 0xFFF) << 4) as i16 >> 4) as i64
+                        };
+                        let dest = (cur_addr as i64 + 4 + disp * 2) as u64;
+                        // SH relocations have no operand syntax, so any relocation type,
+                        // including unknown ones, is shown as its target
+                        if reloc.is_some() {
+                            args.push(ObjInsArg::Reloc);
+                        } else {
+                            args.push(ObjInsArg::BranchDest(dest));
+                            branch_dest = Some(dest);
+                        }
+                    }
+                    Operand::AtDispPc(scale) => {
+                        let disp = (code & 0xFF) as u64 * scale as u64;
+                        let base = if scale == 4 { cur_addr & !3 } else { cur_addr };
+                        let dest = base + 4 + disp;
+                        // Display the literal pool entry's relocation in place of the load
+                        if let Some(pool_reloc) = section.relocations.iter().find(|r| {
+                            r.address == dest
+                                && matches!(r.flags, RelocationFlags::Elf {
+                                    r_type: elf::R_SH_DIR32
+                                })
+                        }) {
+                            args.push(ObjInsArg::PlainText("@(".into()));
+                            args.push(ObjInsArg::Reloc);
+                            args.push(ObjInsArg::PlainText(",pc)".into()));
+                            ins_reloc = Some(pool_reloc.clone());
+                        } else {
+                            args.push(ObjInsArg::PlainText("@(".into()));
+                            args.push(ObjInsArg::BranchDest(dest));
+                            args.push(ObjInsArg::PlainText(",pc)".into()));
+                        }
+                    }
+                    _ => push_operand(&mut args, *operand, code),
+                }
+            }
+
+            let mut formatted = opcode.mnemonic.to_string();
+            for (idx, arg) in args.iter().enumerate() {
+                formatted.push_str(if idx == 0 { " " } else { "" });
+                match arg {
+                    ObjInsArg::PlainText(s) => formatted.push_str(s),
+                    ObjInsArg::Arg(v) => formatted.push_str(&v.to_string()),
+                    ObjInsArg::Reloc => formatted
+                        .push_str(ins_reloc.as_ref().map(|r| r.target.name.as_str()).unwrap_or("")),
+                    ObjInsArg::BranchDest(dest) => formatted.push_str(&format!("{dest:#x}")),
+                    ObjInsArg::Bytes(bytes) => formatted.push_str(&format!("{bytes:02x?}")),
+                }
+            }
+
+            ops.push(op);
+            insts.push(ObjIns {
+                address: cur_addr,
+                size: 2,
+                op,
+                mnemonic: opcode.mnemonic.to_string(),
+                args,
+                reloc: ins_reloc,
+                branch_dest,
+                line,
+                formatted,
+                orig: None,
+            });
+            offset += 2;
+        }
+        Ok(ProcessCodeResult { ops, insts })
+    }
+
+    fn implcit_addend(
+        &self,
+        section: &ObjSection,
+        address: u64,
+        reloc: &Relocation,
+    ) -> Result<i64> {
+        match reloc.flags() {
+            RelocationFlags::Elf { r_type: elf::R_SH_DIR32 | elf::R_SH_REL32 } => {
+                let data = section.data[address as usize..address as usize + 4].try_into()?;
+                Ok(self.endianness.read_i32_bytes(data) as i64)
+            }
+            flags => bail!("Unsupported SH implicit relocation {flags:?}"),
+        }
+    }
+
+    fn demangle(&self, name: &str) -> Option<String> {
+        cpp_demangle::Symbol::new(name)
+            .ok()
+            .and_then(|s| s.demangle(&cpp_demangle::DemangleOptions::default()).ok())
+    }
+
+    fn display_reloc(&self, flags: RelocationFlags) -> Cow<'static, str> {
+        match flags {
+            RelocationFlags::Elf { r_type } => match r_type {
+                elf::R_SH_DIR32 => Cow::Borrowed("R_SH_DIR32"),
+                elf::R_SH_REL32 => Cow::Borrowed("R_SH_REL32"),
+                elf::R_SH_DIR8WPN => Cow::Borrowed("R_SH_DIR8WPN"),
+                elf::R_SH_IND12W => Cow::Borrowed("R_SH_IND12W"),
+                elf::R_SH_DIR8WPL => Cow::Borrowed("R_SH_DIR8WPL"),
+                elf::R_SH_DIR8WPZ => Cow::Borrowed("R_SH_DIR8WPZ"),
+                _ => Cow::Owned(format!("<{flags:?}>")),
+            },
+            _ => Cow::Owned(format!("<{flags:?}>")),
+        }
+    }
+
+    fn has_delay_slot(&self, ins: &ObjIns) -> bool {
+        OPCODES.get(ins.op as usize).is_some_and(|o| o.delayed)
+    }
+}
+
+#[inline]
+fn reg_n(code: u16) -> u16 { (code >> 8) & 0xF }
+
+#[inline]
+fn reg_m(code: u16) -> u16 { (code >> 4) & 0xF }
+
+fn gpr(num: u16) -> ObjInsArg { ObjInsArg::Arg(ObjInsArgValue::Opaque(format!("r{num}").into())) }
+
+fn fpr(num: u16) -> ObjInsArg { ObjInsArg::Arg(ObjInsArgValue::Opaque(format!("fr{num}").into())) }
+
+fn push_operand(args: &mut Vec<ObjInsArg>, operand: Operand, code: u16) {
+    match operand {
+        Operand::Rn => args.push(gpr(reg_n(code))),
+        Operand::Rm => args.push(gpr(reg_m(code))),
+        Operand::FRn => args.push(fpr(reg_n(code))),
+        Operand::FRm => args.push(fpr(reg_m(code))),
+        Operand::DRn => args
+            .push(ObjInsArg::Arg(ObjInsArgValue::Opaque(format!("dr{}", reg_n(code) & !1).into()))),
+        Operand::DRm => args
+            .push(ObjInsArg::Arg(ObjInsArgValue::Opaque(format!("dr{}", reg_n(code) & !1).into()))),
+        Operand::FVn => args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(
+            format!("fv{}", (code >> 10 & 3) * 4).into(),
+        ))),
+        Operand::FVm => args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(
+            format!("fv{}", (code >> 8 & 3) * 4).into(),
+        ))),
+        Operand::AtRn | Operand::AtRm => {
+            let reg = if operand == Operand::AtRn { reg_n(code) } else { reg_m(code) };
+            args.push(ObjInsArg::PlainText("@".into()));
+            args.push(gpr(reg));
+        }
+        Operand::AtRnDec => {
+            args.push(ObjInsArg::PlainText("@-".into()));
+            args.push(gpr(reg_n(code)));
+        }
+        Operand::AtRnInc | Operand::AtRmInc => {
+            let reg = if operand == Operand::AtRnInc { reg_n(code) } else { reg_m(code) };
+            args.push(ObjInsArg::PlainText("@".into()));
+            args.push(gpr(reg));
+            args.push(ObjInsArg::PlainText("+".into()));
+        }
+        Operand::AtR0Rn | Operand::AtR0Rm => {
+            let reg = if operand == Operand::AtR0Rn { reg_n(code) } else { reg_m(code) };
+            args.push(ObjInsArg::PlainText("@(".into()));
+            args.push(gpr(0));
+            args.push(ObjInsArg::PlainText(",".into()));
+            args.push(gpr(reg));
+            args.push(ObjInsArg::PlainText(")".into()));
+        }
+        Operand::AtDisp4Rn(scale) | Operand::AtDisp4Rm(scale) => {
+            let reg =
+                if matches!(operand, Operand::AtDisp4Rn(_)) { reg_n(code) } else { reg_m(code) };
+            args.push(ObjInsArg::PlainText("@(".into()));
+            args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned((code & 0xF) as u64 * scale as u64)));
+            args.push(ObjInsArg::PlainText(",".into()));
+            args.push(gpr(reg));
+            args.push(ObjInsArg::PlainText(")".into()));
+        }
+        Operand::AtDispGbr(scale) => {
+            args.push(ObjInsArg::PlainText("@(".into()));
+            args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned(
+                (code & 0xFF) as u64 * scale as u64,
+            )));
+            args.push(ObjInsArg::PlainText(",gbr)".into()));
+        }
+        Operand::AtR0Gbr => args.push(ObjInsArg::PlainText("@(r0,gbr)".into())),
+        Operand::Imm8 => {
+            args.push(ObjInsArg::PlainText("#".into()));
+            args.push(ObjInsArg::Arg(ObjInsArgValue::Unsigned((code & 0xFF) as u64)));
+        }
+        Operand::Simm8 => {
+            args.push(ObjInsArg::PlainText("#".into()));
+            args.push(ObjInsArg::Arg(ObjInsArgValue::Signed((code & 0xFF) as i8 as i64)));
+        }
+        Operand::Reg(name) => args.push(ObjInsArg::Arg(ObjInsArgValue::Opaque(name.into()))),
+        Operand::Label8 | Operand::Label12 | Operand::AtDispPc(_) => unreachable!(),
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Operand {
+    /// General register from bits 8-11
+    Rn,
+    /// General register from bits 4-7
+    Rm,
+    FRn,
+    FRm,
+    DRn,
+    DRm,
+    FVn,
+    FVm,
+    AtRn,
+    AtRm,
+    AtRnDec,
+    AtRnInc,
+    AtRmInc,
+    AtR0Rn,
+    AtR0Rm,
+    /// @(disp,Rn) with a 4-bit displacement and the given scale
+    AtDisp4Rn(u8),
+    /// @(disp,Rm) with a 4-bit displacement and the given scale
+    AtDisp4Rm(u8),
+    /// @(disp,GBR) with an 8-bit displacement and the given scale
+    AtDispGbr(u8),
+    AtR0Gbr,
+    /// @(disp,PC) with an 8-bit displacement and the given scale
+    AtDispPc(u8),
+    Imm8,
+    Simm8,
+    /// PC-relative 8-bit branch displacement
+    Label8,
+    /// PC-relative 12-bit branch displacement
+    Label12,
+    /// Fixed register
+    Reg(&'static str),
+}
+
+struct Opcode {
+    mask: u16,
+    pattern: u16,
+    mnemonic: &'static str,
+    operands: &'static [Operand],
+    /// Whether the instruction is followed by a delay slot
+    delayed: bool,
+}
+
+const fn op(
+    mask: u16,
+    pattern: u16,
+    mnemonic: &'static str,
+    operands: &'static [Operand],
+) -> Opcode {
+    Opcode { mask, pattern, mnemonic, operands, delayed: false }
+}
+
+const fn delayed(
+    mask: u16,
+    pattern: u16,
+    mnemonic: &'static str,
+    operands: &'static [Operand],
+) -> Opcode {
+    Opcode { mask, pattern, mnemonic, operands, delayed: true }
+}
+
+/// Returns the index of the matching opcode, which is used as the instruction's op.
+fn decode(code: u16) -> Option<(u16, &'static Opcode)> {
+    OPCODES
+        .iter()
+        .enumerate()
+        .find(|(_, o)| code & o.mask == o.pattern)
+        .map(|(idx, o)| (idx as u16, o))
+}
+
+use Operand::*;
+
+/// SH-2 and SH-4 instruction table. More specific masks must come first.
+static OPCODES: &[Opcode] = &[
+    // Fixed
+    op(0xFFFF, 0x0008, "clrt", &[]),
+    op(0xFFFF, 0x0009, "nop", &[]),
+    delayed(0xFFFF, 0x000B, "rts", &[]),
+    op(0xFFFF, 0x0018, "sett", &[]),
+    op(0xFFFF, 0x0019, "div0u", &[]),
+    op(0xFFFF, 0x001B, "sleep", &[]),
+    op(0xFFFF, 0x0028, "clrmac", &[]),
+    delayed(0xFFFF, 0x002B, "rte", &[]),
+    op(0xFFFF, 0x0038, "ldtlb", &[]),
+    op(0xFFFF, 0x0048, "clrs", &[]),
+    op(0xFFFF, 0x0058, "sets", &[]),
+    op(0xFFFF, 0xF3FD, "fschg", &[]),
+    op(0xFFFF, 0xFBFD, "frchg", &[]),
+    // Rn
+    op(0xF0FF, 0x0002, "stc", &[Reg("sr"), Rn]),
+    op(0xF0FF, 0x0012, "stc", &[Reg("gbr"), Rn]),
+    op(0xF0FF, 0x0022, "stc", &[Reg("vbr"), Rn]),
+    op(0xF0FF, 0x0032, "stc", &[Reg("ssr"), Rn]),
+    op(0xF0FF, 0x0042, "stc", &[Reg("spc"), Rn]),
+    op(0xF0FF, 0x00F2, "stc", &[Reg("dbr"), Rn]),
+    delayed(0xF0FF, 0x0003, "bsrf", &[Rn]),
+    delayed(0xF0FF, 0x0023, "braf", &[Rn]),
+    op(0xF0FF, 0x0029, "movt", &[Rn]),
+    op(0xF0FF, 0x000A, "sts", &[Reg("mach"), Rn]),
+    op(0xF0FF, 0x001A, "sts", &[Reg("macl"), Rn]),
+    op(0xF0FF, 0x002A, "sts", &[Reg("pr"), Rn]),
+    op(0xF0FF, 0x005A, "sts", &[Reg("fpul"), Rn]),
+    op(0xF0FF, 0x006A, "sts", &[Reg("fpscr"), Rn]),
+    op(0xF0FF, 0x0083, "pref", &[AtRn]),
+    op(0xF0FF, 0x0093, "ocbi", &[AtRn]),
+    op(0xF0FF, 0x00A3, "ocbp", &[AtRn]),
+    op(0xF0FF, 0x00B3, "ocbwb", &[AtRn]),
+    op(0xF0FF, 0x00C3, "movca.l", &[Reg("r0"), AtRn]),
+    op(0xF0FF, 0x4000, "shll", &[Rn]),
+    op(0xF0FF, 0x4001, "shlr", &[Rn]),
+    op(0xF0FF, 0x4004, "rotl", &[Rn]),
+    op(0xF0FF, 0x4005, "rotr", &[Rn]),
+    op(0xF0FF, 0x4008, "shll2", &[Rn]),
+    op(0xF0FF, 0x4009, "shlr2", &[Rn]),
+    op(0xF0FF, 0x4018, "shll8", &[Rn]),
+    op(0xF0FF, 0x4019, "shlr8", &[Rn]),
+    op(0xF0FF, 0x4028, "shll16", &[Rn]),
+    op(0xF0FF, 0x4029, "shlr16", &[Rn]),
+    op(0xF0FF, 0x4010, "dt", &[Rn]),
+    op(0xF0FF, 0x4011, "cmp/pz", &[Rn]),
+    op(0xF0FF, 0x4015, "cmp/pl", &[Rn]),
+    op(0xF0FF, 0x4020, "shal", &[Rn]),
+    op(0xF0FF, 0x4021, "shar", &[Rn]),
+    op(0xF0FF, 0x4024, "rotcl", &[Rn]),
+    op(0xF0FF, 0x4025, "rotcr", &[Rn]),
+    op(0xF0FF, 0x401B, "tas.b", &[AtRn]),
+    delayed(0xF0FF, 0x400B, "jsr", &[AtRn]),
+    delayed(0xF0FF, 0x402B, "jmp", &[AtRn]),
+    op(0xF0FF, 0x4002, "sts.l", &[Reg("mach"), AtRnDec]),
+    op(0xF0FF, 0x4012, "sts.l", &[Reg("macl"), AtRnDec]),
+    op(0xF0FF, 0x4022, "sts.l", &[Reg("pr"), AtRnDec]),
+    op(0xF0FF, 0x4052, "sts.l", &[Reg("fpul"), AtRnDec]),
+    op(0xF0FF, 0x4062, "sts.l", &[Reg("fpscr"), AtRnDec]),
+    op(0xF0FF, 0x4003, "stc.l", &[Reg("sr"), AtRnDec]),
+    op(0xF0FF, 0x4013, "stc.l", &[Reg("gbr"), AtRnDec]),
+    op(0xF0FF, 0x4023, "stc.l", &[Reg("vbr"), AtRnDec]),
+    op(0xF0FF, 0x4006, "lds.l", &[AtRnInc, Reg("mach")]),
+    op(0xF0FF, 0x4016, "lds.l", &[AtRnInc, Reg("macl")]),
+    op(0xF0FF, 0x4026, "lds.l", &[AtRnInc, Reg("pr")]),
+    op(0xF0FF, 0x4056, "lds.l", &[AtRnInc, Reg("fpul")]),
+    op(0xF0FF, 0x4066, "lds.l", &[AtRnInc, Reg("fpscr")]),
+    op(0xF0FF, 0x4007, "ldc.l", &[AtRnInc, Reg("sr")]),
+    op(0xF0FF, 0x4017, "ldc.l", &[AtRnInc, Reg("gbr")]),
+    op(0xF0FF, 0x4027, "ldc.l", &[AtRnInc, Reg("vbr")]),
+    op(0xF0FF, 0x400A, "lds", &[Rn, Reg("mach")]),
+    op(0xF0FF, 0x401A, "lds", &[Rn, Reg("macl")]),
+    op(0xF0FF, 0x402A, "lds", &[Rn, Reg("pr")]),
+    op(0xF0FF, 0x405A, "lds", &[Rn, Reg("fpul")]),
+    op(0xF0FF, 0x406A, "lds", &[Rn, Reg("fpscr")]),
+    op(0xF0FF, 0x400E, "ldc", &[Rn, Reg("sr")]),
+    op(0xF0FF, 0x401E, "ldc", &[Rn, Reg("gbr")]),
+    op(0xF0FF, 0x402E, "ldc", &[Rn, Reg("vbr")]),
+    op(0xF0FF, 0xF00D, "fsts", &[Reg("fpul"), FRn]),
+    op(0xF0FF, 0xF01D, "flds", &[FRn, Reg("fpul")]),
+    op(0xF0FF, 0xF02D, "float", &[Reg("fpul"), FRn]),
+    op(0xF0FF, 0xF03D, "ftrc", &[FRn, Reg("fpul")]),
+    op(0xF0FF, 0xF04D, "fneg", &[FRn]),
+    op(0xF0FF, 0xF05D, "fabs", &[FRn]),
+    op(0xF0FF, 0xF06D, "fsqrt", &[FRn]),
+    op(0xF0FF, 0xF08D, "fldi0", &[FRn]),
+    op(0xF0FF, 0xF09D, "fldi1", &[FRn]),
+    op(0xF1FF, 0xF0AD, "fcnvsd", &[Reg("fpul"), DRn]),
+    op(0xF1FF, 0xF0BD, "fcnvds", &[DRm, Reg("fpul")]),
+    op(0xF0FF, 0xF0ED, "fipr", &[FVm, FVn]),
+    op(0xF3FF, 0xF1FD, "ftrv", &[Reg("xmtrx"), FVn]),
+    // Rm, Rn
+    op(0xF00F, 0x0004, "mov.b", &[Rm, AtR0Rn]),
+    op(0xF00F, 0x0005, "mov.w", &[Rm, AtR0Rn]),
+    op(0xF00F, 0x0006, "mov.l", &[Rm, AtR0Rn]),
+    op(0xF00F, 0x0007, "mul.l", &[Rm, Rn]),
+    op(0xF00F, 0x000C, "mov.b", &[AtR0Rm, Rn]),
+    op(0xF00F, 0x000D, "mov.w", &[AtR0Rm, Rn]),
+    op(0xF00F, 0x000E, "mov.l", &[AtR0Rm, Rn]),
+    op(0xF00F, 0x000F, "mac.l", &[AtRmInc, AtRnInc]),
+    op(0xF00F, 0x2000, "mov.b", &[Rm, AtRn]),
+    op(0xF00F, 0x2001, "mov.w", &[Rm, AtRn]),
+    op(0xF00F, 0x2002, "mov.l", &[Rm, AtRn]),
+    op(0xF00F, 0x2004, "mov.b", &[Rm, AtRnDec]),
+    op(0xF00F, 0x2005, "mov.w", &[Rm, AtRnDec]),
+    op(0xF00F, 0x2006, "mov.l", &[Rm, AtRnDec]),
+    op(0xF00F, 0x2007, "div0s", &[Rm, Rn]),
+    op(0xF00F, 0x2008, "tst", &[Rm, Rn]),
+    op(0xF00F, 0x2009, "and", &[Rm, Rn]),
+    op(0xF00F, 0x200A, "xor", &[Rm, Rn]),
+    op(0xF00F, 0x200B, "or", &[Rm, Rn]),
+    op(0xF00F, 0x200C, "cmp/str", &[Rm, Rn]),
+    op(0xF00F, 0x200D, "xtrct", &[Rm, Rn]),
+    op(0xF00F, 0x200E, "mulu.w", &[Rm, Rn]),
+    op(0xF00F, 0x200F, "muls.w", &[Rm, Rn]),
+    op(0xF00F, 0x3000, "cmp/eq", &[Rm, Rn]),
+    op(0xF00F, 0x3002, "cmp/hs", &[Rm, Rn]),
+    op(0xF00F, 0x3003, "cmp/ge", &[Rm, Rn]),
+    op(0xF00F, 0x3004, "div1", &[Rm, Rn]),
+    op(0xF00F, 0x3005, "dmulu.l", &[Rm, Rn]),
+    op(0xF00F, 0x3006, "cmp/hi", &[Rm, Rn]),
+    op(0xF00F, 0x3007, "cmp/gt", &[Rm, Rn]),
+    op(0xF00F, 0x3008, "sub", &[Rm, Rn]),
+    op(0xF00F, 0x300A, "subc", &[Rm, Rn]),
+    op(0xF00F, 0x300B, "subv", &[Rm, Rn]),
+    op(0xF00F, 0x300C, "add", &[Rm, Rn]),
+    op(0xF00F, 0x300D, "dmuls.l", &[Rm, Rn]),
+    op(0xF00F, 0x300E, "addc", &[Rm, Rn]),
+    op(0xF00F, 0x300F, "addv", &[Rm, Rn]),
+    op(0xF00F, 0x400C, "shad", &[Rm, Rn]),
+    op(0xF00F, 0x400D, "shld", &[Rm, Rn]),
+    op(0xF00F, 0x400F, "mac.w", &[AtRmInc, AtRnInc]),
+    op(0xF00F, 0x6000, "mov.b", &[AtRm, Rn]),
+    op(0xF00F, 0x6001, "mov.w", &[AtRm, Rn]),
+    op(0xF00F, 0x6002, "mov.l", &[AtRm, Rn]),
+    op(0xF00F, 0x6003, "mov", &[Rm, Rn]),
+    op(0xF00F, 0x6004, "mov.b", &[AtRmInc, Rn]),
+    op(0xF00F, 0x6005, "mov.w", &[AtRmInc, Rn]),
+    op(0xF00F, 0x6006, "mov.l", &[AtRmInc, Rn]),
+    op(0xF00F, 0x6007, "not", &[Rm, Rn]),
+    op(0xF00F, 0x6008, "swap.b", &[Rm, Rn]),
+    op(0xF00F, 0x6009, "swap.w", &[Rm, Rn]),
+    op(0xF00F, 0x600A, "negc", &[Rm, Rn]),
+    op(0xF00F, 0x600B, "neg", &[Rm, Rn]),
+    op(0xF00F, 0x600C, "extu.b", &[Rm, Rn]),
+    op(0xF00F, 0x600D, "extu.w", &[Rm, Rn]),
+    op(0xF00F, 0x600E, "exts.b", &[Rm, Rn]),
+    op(0xF00F, 0x600F, "exts.w", &[Rm, Rn]),
+    op(0xF00F, 0xF000, "fadd", &[FRm, FRn]),
+    op(0xF00F, 0xF001, "fsub", &[FRm, FRn]),
+    op(0xF00F, 0xF002, "fmul", &[FRm, FRn]),
+    op(0xF00F, 0xF003, "fdiv", &[FRm, FRn]),
+    op(0xF00F, 0xF004, "fcmp/eq", &[FRm, FRn]),
+    op(0xF00F, 0xF005, "fcmp/gt", &[FRm, FRn]),
+    op(0xF00F, 0xF006, "fmov.s", &[AtR0Rm, FRn]),
+    op(0xF00F, 0xF007, "fmov.s", &[FRm, AtR0Rn]),
+    op(0xF00F, 0xF008, "fmov.s", &[AtRm, FRn]),
+    op(0xF00F, 0xF009, "fmov.s", &[AtRmInc, FRn]),
+    op(0xF00F, 0xF00A, "fmov.s", &[FRm, AtRn]),
+    op(0xF00F, 0xF00B, "fmov.s", &[FRm, AtRnDec]),
+    op(0xF00F, 0xF00C, "fmov", &[FRm, FRn]),
+    op(0xF00F, 0xF00E, "fmac", &[Reg("fr0"), FRm, FRn]),
+    // Displacements
+    op(0xF000, 0x1000, "mov.l", &[Rm, AtDisp4Rn(4)]),
+    op(0xF000, 0x5000, "mov.l", &[AtDisp4Rm(4), Rn]),
+    op(0xFF00, 0x8000, "mov.b", &[Reg("r0"), AtDisp4Rm(1)]),
+    op(0xFF00, 0x8100, "mov.w", &[Reg("r0"), AtDisp4Rm(2)]),
+    op(0xFF00, 0x8400, "mov.b", &[AtDisp4Rm(1), Reg("r0")]),
+    op(0xFF00, 0x8500, "mov.w", &[AtDisp4Rm(2), Reg("r0")]),
+    op(0xFF00, 0x8800, "cmp/eq", &[Simm8, Reg("r0")]),
+    op(0xFF00, 0x8900, "bt", &[Label8]),
+    op(0xFF00, 0x8B00, "bf", &[Label8]),
+    delayed(0xFF00, 0x8D00, "bt/s", &[Label8]),
+    delayed(0xFF00, 0x8F00, "bf/s", &[Label8]),
+    op(0xF000, 0x9000, "mov.w", &[AtDispPc(2), Rn]),
+    delayed(0xF000, 0xA000, "bra", &[Label12]),
+    delayed(0xF000, 0xB000, "bsr", &[Label12]),
+    op(0xFF00, 0xC000, "mov.b", &[Reg("r0"), AtDispGbr(1)]),
+    op(0xFF00, 0xC100, "mov.w", &[Reg("r0"), AtDispGbr(2)]),
+    op(0xFF00, 0xC200, "mov.l", &[Reg("r0"), AtDispGbr(4)]),
+    op(0xFF00, 0xC300, "trapa", &[Imm8]),
+    op(0xFF00, 0xC400, "mov.b", &[AtDispGbr(1), Reg("r0")]),
+    op(0xFF00, 0xC500, "mov.w", &[AtDispGbr(2), Reg("r0")]),
+    op(0xFF00, 0xC600, "mov.l", &[AtDispGbr(4), Reg("r0")]),
+    op(0xFF00, 0xC700, "mova", &[AtDispPc(4), Reg("r0")]),
+    op(0xFF00, 0xC800, "tst", &[Imm8, Reg("r0")]),
+    op(0xFF00, 0xC900, "and", &[Imm8, Reg("r0")]),
+    op(0xFF00, 0xCA00, "xor", &[Imm8, Reg("r0")]),
+    op(0xFF00, 0xCB00, "or", &[Imm8, Reg("r0")]),
+    op(0xFF00, 0xCC00, "tst.b", &[Imm8, AtR0Gbr]),
+    op(0xFF00, 0xCD00, "and.b", &[Imm8, AtR0Gbr]),
+    op(0xFF00, 0xCE00, "xor.b", &[Imm8, AtR0Gbr]),
+    op(0xFF00, 0xCF00, "or.b", &[Imm8, AtR0Gbr]),
+    op(0xF000, 0xD000, "mov.l", &[AtDispPc(4), Rn]),
+    op(0xF000, 0xE000, "mov", &[Simm8, Rn]),
+    op(0xF000, 0x7000, "add", &[Simm8, Rn]),
+];
@@ -6,10 +6,10 @@ use iced_x86::{
     GasFormatter, Instruction, IntelFormatter, MasmFormatter, NasmFormatter, NumberKind, OpKind,
     PrefixKind, Register,
 };
-use object::{pe, Endian, Endianness, File, Object, Relocation, RelocationFlags};
+use object::{elf, pe, Endian, Endianness, File, Object, Relocation, RelocationFlags};
 
 use crate::{
-    arch::{ObjArch, ProcessCodeResult},
+    arch::{bytes_ins, ObjArch, ProcessCodeResult, OP_BYTES},
     diff::{DiffObjConfig, X86Formatter},
     obj::{ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjSection, SymbolRef},
 };
@@ -48,6 +48,7 @@ impl ObjArch for ObjArchX86 {
         formatter.options_mut().set_space_after_operand_separator(config.space_between_args);
 
         let mut output = InstructionFormatterOutput {
+            bits: self.bits,
             formatted: String::new(),
             ins: ObjIns {
                 address: 0,
@@ -75,6 +76,13 @@ impl ObjArch for ObjArchX86 {
                 .iter()
                 .find(|r| r.address >= address && r.address < address + instruction.len() as u64);
             let line = section.line_info.range(..=address).last().map(|(_, &b)| b);
+            if instruction.is_invalid() {
+                let offset = (address - symbol.address) as usize;
+                let data = &code[offset..offset + instruction.len()];
+                result.ops.push(OP_BYTES);
+                result.insts.push(bytes_ins(address, data, reloc, line));
+                continue;
+            }
             output.ins = ObjIns {
                 address,
                 size: instruction.len() as u8,
@@ -136,10 +144,52 @@ impl ObjArch for ObjArchX86 {
         reloc: &Relocation,
     ) -> Result<i64> {
         match reloc.flags() {
-            RelocationFlags::Coff { typ: pe::IMAGE_REL_I386_DIR32 | pe::IMAGE_REL_I386_REL32 } => {
+            RelocationFlags::Coff {
+                typ:
+                    pe::IMAGE_REL_I386_DIR32
+                    | pe::IMAGE_REL_I386_DIR32NB
+                    | pe::IMAGE_REL_I386_REL32
+                    | pe::IMAGE_REL_I386_SECREL,
+            } if self.bits == 32 => {
+                let data = section.data[address as usize..address as usize + 4].try_into()?;
+                Ok(self.endianness.read_i32_bytes(data) as i64)
+            }
+            RelocationFlags::Coff {
+                typ: pe::IMAGE_REL_I386_DIR16 | pe::IMAGE_REL_I386_SECTION,
+            } if self.bits == 32 => {
+                let data = section.data[address as usize..address as usize + 2].try_into()?;
+                Ok(self.endianness.read_i16_bytes(data) as i64)
+            }
+            RelocationFlags::Coff {
+                typ:
+                    pe::IMAGE_REL_AMD64_ADDR32
+                    | pe::IMAGE_REL_AMD64_ADDR32NB
+                    | pe::IMAGE_REL_AMD64_REL32
+                    | pe::IMAGE_REL_AMD64_REL32_1
+                    | pe::IMAGE_REL_AMD64_REL32_2
+                    | pe::IMAGE_REL_AMD64_REL32_3
+                    | pe::IMAGE_REL_AMD64_REL32_4
+                    | pe::IMAGE_REL_AMD64_REL32_5
+                    | pe::IMAGE_REL_AMD64_SECREL,
+            } if self.bits == 64 => {
+                let data = section.data[address as usize..address as usize + 4].try_into()?;
+                Ok(self.endianness.read_i32_bytes(data) as i64)
+            }
+            RelocationFlags::Coff { typ: pe::IMAGE_REL_AMD64_ADDR64 } if self.bits == 64 => {
+                let data = section.data[address as usize..address as usize + 8].try_into()?;
+                Ok(self.endianness.read_i64_bytes(data))
+            }
+            // i386 ELF objects use REL relocations; x86-64 uses RELA
+            RelocationFlags::Elf { r_type: elf::R_386_32 | elf::R_386_PC32 | elf::R_386_PLT32 }
+                if self.bits == 32 =>
+            {
                 let data = section.data[address as usize..address as usize + 4].try_into()?;
                 Ok(self.endianness.read_i32_bytes(data) as i64)
             }
+            RelocationFlags::Elf { r_type: elf::R_386_16 | elf::R_386_PC16 } if self.bits == 32 => {
+                let data = section.data[address as usize..address as usize + 2].try_into()?;
+                Ok(self.endianness.read_i16_bytes(data) as i64)
+            }
             flags => bail!("Unsupported x86 implicit relocation {flags:?}"),
         }
     }
@@ -156,14 +206,125 @@ impl ObjArch for ObjArchX86 {
 
     fn display_reloc(&self, flags: RelocationFlags) -> Cow<'static, str> {
         match flags {
-            RelocationFlags::Coff { typ } => match typ {
+            RelocationFlags::Coff { typ } if self.bits == 32 => match typ {
+                pe::IMAGE_REL_I386_DIR16 => Cow::Borrowed("IMAGE_REL_I386_DIR16"),
+                pe::IMAGE_REL_I386_REL16 => Cow::Borrowed("IMAGE_REL_I386_REL16"),
                 pe::IMAGE_REL_I386_DIR32 => Cow::Borrowed("IMAGE_REL_I386_DIR32"),
+                pe::IMAGE_REL_I386_DIR32NB => Cow::Borrowed("IMAGE_REL_I386_DIR32NB"),
+                pe::IMAGE_REL_I386_SECTION => Cow::Borrowed("IMAGE_REL_I386_SECTION"),
+                pe::IMAGE_REL_I386_SECREL => Cow::Borrowed("IMAGE_REL_I386_SECREL"),
                 pe::IMAGE_REL_I386_REL32 => Cow::Borrowed("IMAGE_REL_I386_REL32"),
                 _ => Cow::Owned(format!("<{flags:?}>")),
             },
+            RelocationFlags::Coff { typ } => match typ {
+                pe::IMAGE_REL_AMD64_ADDR64 => Cow::Borrowed("IMAGE_REL_AMD64_ADDR64"),
+                pe::IMAGE_REL_AMD64_ADDR32 => Cow::Borrowed("IMAGE_REL_AMD64_ADDR32"),
+                pe::IMAGE_REL_AMD64_ADDR32NB => Cow::Borrowed("IMAGE_REL_AMD64_ADDR32NB"),
+                pe::IMAGE_REL_AMD64_REL32 => Cow::Borrowed("IMAGE_REL_AMD64_REL32"),
+                pe::IMAGE_REL_AMD64_REL32_1 => Cow::Borrowed("IMAGE_REL_AMD64_REL32_1"),
+                pe::IMAGE_REL_AMD64_REL32_2 => Cow::Borrowed("IMAGE_REL_AMD64_REL32_2"),
+                pe::IMAGE_REL_AMD64_REL32_3 => Cow::Borrowed("IMAGE_REL_AMD64_REL32_3"),
+                pe::IMAGE_REL_AMD64_REL32_4 => Cow::Borrowed("IMAGE_REL_AMD64_REL32_4"),
+                pe::IMAGE_REL_AMD64_REL32_5 => Cow::Borrowed("IMAGE_REL_AMD64_REL32_5"),
+                pe::IMAGE_REL_AMD64_SECREL => Cow::Borrowed("IMAGE_REL_AMD64_SECREL"),
+                _ => Cow::Owned(format!("<{flags:?}>")),
+            },
+            RelocationFlags::Elf { r_type } if self.bits == 32 => match r_type {
+                elf::R_386_32 => Cow::Borrowed("R_386_32"),
+                elf::R_386_PC32 => Cow::Borrowed("R_386_PC32"),
+                elf::R_386_PLT32 => Cow::Borrowed("R_386_PLT32"),
+                elf::R_386_GOT32 => Cow::Borrowed("R_386_GOT32"),
+                elf::R_386_GOTOFF => Cow::Borrowed("R_386_GOTOFF"),
+                elf::R_386_GOTPC => Cow::Borrowed("R_386_GOTPC"),
+                elf::R_386_16 => Cow::Borrowed("R_386_16"),
+                elf::R_386_PC16 => Cow::Borrowed("R_386_PC16"),
+                _ => Cow::Owned(format!("<{flags:?}>")),
+            },
+            RelocationFlags::Elf { r_type } => match r_type {
+                elf::R_X86_64_64 => Cow::Borrowed("R_X86_64_64"),
+                elf::R_X86_64_PC32 => Cow::Borrowed("R_X86_64_PC32"),
+                elf::R_X86_64_PLT32 => Cow::Borrowed("R_X86_64_PLT32"),
+                elf::R_X86_64_GOTPCREL => Cow::Borrowed("R_X86_64_GOTPCREL"),
+                elf::R_X86_64_GOTPCRELX => Cow::Borrowed("R_X86_64_GOTPCRELX"),
+                elf::R_X86_64_REX_GOTPCRELX => Cow::Borrowed("R_X86_64_REX_GOTPCRELX"),
+                elf::R_X86_64_32 => Cow::Borrowed("R_X86_64_32"),
+                elf::R_X86_64_32S => Cow::Borrowed("R_X86_64_32S"),
+                _ => Cow::Owned(format!("<{flags:?}>")),
+            },
             _ => Cow::Owned(format!("<{flags:?}>")),
         }
     }
+
+    fn get_reloc_byte_size(&self, flags: RelocationFlags) -> usize {
+        match flags {
+            RelocationFlags::Coff { typ } if self.bits == 32 => match typ {
+                pe::IMAGE_REL_I386_DIR16
+                | pe::IMAGE_REL_I386_REL16
+                | pe::IMAGE_REL_I386_SECTION => 2,
+                _ => 4,
+            },
+            RelocationFlags::Coff { typ: pe::IMAGE_REL_AMD64_ADDR64 } => 8,
+            RelocationFlags::Elf { r_type: elf::R_386_16 | elf::R_386_PC16 } if self.bits == 32 => {
+                2
+            }
+            RelocationFlags::Elf { r_type: elf::R_X86_64_64 } if self.bits == 64 => 8,
+            _ => 4,
+        }
+    }
+
+    // MSVC pads functions with int3
+    fn is_padding(&self, ins: &ObjIns) -> bool { matches!(ins.mnemonic.as_str(), "nop" | "int3") }
+}
+
+/// Relocations that can be displayed in place of a label address operand.
+fn is_label_reloc(bits: u32, flags: RelocationFlags) -> bool {
+    match (bits, flags) {
+        (32, RelocationFlags::Coff { typ }) => matches!(
+            typ,
+            pe::IMAGE_REL_I386_DIR32 | pe::IMAGE_REL_I386_DIR32NB | pe::IMAGE_REL_I386_REL32
+        ),
+        // REL32_1..5 are RIP-relative operands followed by an immediate of that many bytes
+        (64, RelocationFlags::Coff { typ }) => matches!(
+            typ,
+            pe::IMAGE_REL_AMD64_ADDR32
+                | pe::IMAGE_REL_AMD64_ADDR32NB
+                | pe::IMAGE_REL_AMD64_REL32
+                | pe::IMAGE_REL_AMD64_REL32_1
+                | pe::IMAGE_REL_AMD64_REL32_2
+                | pe::IMAGE_REL_AMD64_REL32_3
+                | pe::IMAGE_REL_AMD64_REL32_4
+                | pe::IMAGE_REL_AMD64_REL32_5
+        ),
+        (32, RelocationFlags::Elf { r_type }) => {
+            matches!(r_type, elf::R_386_32 | elf::R_386_PC32 | elf::R_386_GOT32 | elf::R_386_GOTOFF)
+        }
+        (64, RelocationFlags::Elf { r_type }) => matches!(
+            r_type,
+            elf::R_X86_64_PC32
+                | elf::R_X86_64_PLT32
+                | elf::R_X86_64_GOTPCREL
+                | elf::R_X86_64_GOTPCRELX
+                | elf::R_X86_64_REX_GOTPCRELX
+                | elf::R_X86_64_32
+                | elf::R_X86_64_32S
+        ),
+        _ => false,
+    }
+}
+
+/// Relocations that can be displayed in place of a call target operand.
+fn is_call_reloc(bits: u32, flags: RelocationFlags) -> bool {
+    match (bits, flags) {
+        (32, RelocationFlags::Coff { typ }) => typ == pe::IMAGE_REL_I386_REL32,
+        (64, RelocationFlags::Coff { typ }) => typ == pe::IMAGE_REL_AMD64_REL32,
+        (32, RelocationFlags::Elf { r_type }) => {
+            matches!(r_type, elf::R_386_PC32 | elf::R_386_PLT32)
+        }
+        (64, RelocationFlags::Elf { r_type }) => {
+            matches!(r_type, elf::R_X86_64_PC32 | elf::R_X86_64_PLT32)
+        }
+        _ => false,
+    }
 }
 
 fn replace_arg(
@@ -193,6 +354,7 @@ fn replace_arg(
 }
 
 struct InstructionFormatterOutput {
+    bits: u32,
     formatted: String,
     ins: ObjIns,
     error: Option<anyhow::Error>,
@@ -264,9 +426,7 @@ impl FormatterOutput for InstructionFormatterOutput {
         match kind {
             FormatterTextKind::LabelAddress => {
                 if let Some(reloc) = self.ins.reloc.as_ref() {
-                    if matches!(reloc.flags, RelocationFlags::Coff {
-                        typ: pe::IMAGE_REL_I386_DIR32 | pe::IMAGE_REL_I386_REL32
-                    }) {
+                    if is_label_reloc(self.bits, reloc.flags) {
                         self.ins.args.push(ObjInsArg::Reloc);
                         return;
                     } else if self.error.is_none() {
@@ -282,9 +442,7 @@ impl FormatterOutput for InstructionFormatterOutput {
             }
             FormatterTextKind::FunctionAddress => {
                 if let Some(reloc) = self.ins.reloc.as_ref() {
-                    if matches!(reloc.flags, RelocationFlags::Coff {
-                        typ: pe::IMAGE_REL_I386_REL32
-                    }) {
+                    if is_call_reloc(self.bits, reloc.flags) {
                         self.ins.args.push(ObjInsArg::Reloc);
                         return;
                     } else if self.error.is_none() {
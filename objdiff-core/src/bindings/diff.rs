@@ -0,0 +1,238 @@
+//! Message types for `protos/diff.proto`, generated by the build script, and their
+//! conversions from the diff results.
+
+use object::RelocationFlags;
+
+use crate::{
+    diff::{
+        ObjDataDiff, ObjDataDiffKind, ObjDiff, ObjInsArgDiff, ObjInsArgDiffKind, ObjInsDiff,
+        ObjInsDiffKind, ObjSectionDiff, ObjSymbolDiff, RelocDiffKind,
+    },
+    obj::{
+        ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjReloc, ObjSection, ObjSectionKind,
+        ObjSymbol, ObjSymbolFlags,
+    },
+};
+
+include!(concat!(env!("OUT_DIR"), "/objdiff.diff.rs"));
+
+impl DiffResult {
+    pub fn new(left: Option<(&ObjInfo, &ObjDiff)>, right: Option<(&ObjInfo, &ObjDiff)>) -> Self {
+        Self {
+            left: left.map(|(obj, diff)| ObjectDiff::new(obj, diff)),
+            right: right.map(|(obj, diff)| ObjectDiff::new(obj, diff)),
+        }
+    }
+}
+
+impl ObjectDiff {
+    pub fn new(obj: &ObjInfo, diff: &ObjDiff) -> Self {
+        Self {
+            sections: obj
+                .sections
+                .iter()
+                .zip(&diff.sections)
+                .map(|(section, section_diff)| SectionDiff::new(obj, section, section_diff))
+                .collect(),
+        }
+    }
+}
+
+impl SectionDiff {
+    fn new(obj: &ObjInfo, section: &ObjSection, section_diff: &ObjSectionDiff) -> Self {
+        let kind = match section.kind {
+            ObjSectionKind::Code => SectionKind::SectionText,
+            ObjSectionKind::Data => SectionKind::SectionData,
+            ObjSectionKind::Bss => SectionKind::SectionBss,
+        };
+        // The data diff of code sections is unused
+        let data = match section.kind {
+            ObjSectionKind::Code => vec![],
+            _ => section_diff.data_diff.iter().map(DataDiff::from).collect(),
+        };
+        Self {
+            name: section.name.clone(),
+            kind: kind as i32,
+            size: section.size,
+            address: section.address,
+            symbols: section
+                .symbols
+                .iter()
+                .zip(&section_diff.symbols)
+                .map(|(symbol, symbol_diff)| SymbolDiff::new(obj, symbol, symbol_diff))
+                .collect(),
+            data,
+            match_percent: section_diff.match_percent,
+        }
+    }
+}
+
+impl From<&ObjSymbol> for Symbol {
+    fn from(symbol: &ObjSymbol) -> Self {
+        let mut flags = 0u32;
+        for (flag, bit) in [
+            (ObjSymbolFlags::Global, SymbolFlag::SymbolGlobal),
+            (ObjSymbolFlags::Local, SymbolFlag::SymbolLocal),
+            (ObjSymbolFlags::Weak, SymbolFlag::SymbolWeak),
+            (ObjSymbolFlags::Common, SymbolFlag::SymbolCommon),
+            (ObjSymbolFlags::Hidden, SymbolFlag::SymbolHidden),
+        ] {
+            if symbol.flags.0.contains(flag) {
+                flags |= bit as u32;
+            }
+        }
+        Self {
+            name: symbol.name.clone(),
+            demangled_name: symbol.demangled_name.clone(),
+            address: symbol.address,
+            size: symbol.size,
+            flags,
+        }
+    }
+}
+
+impl SymbolDiff {
+    fn new(obj: &ObjInfo, symbol: &ObjSymbol, symbol_diff: &ObjSymbolDiff) -> Self {
+        Self {
+            symbol: Some(Symbol::from(symbol)),
+            instructions: symbol_diff
+                .instructions
+                .iter()
+                .map(|ins_diff| InstructionDiff::new(obj, ins_diff))
+                .collect(),
+            match_percent: symbol_diff.match_percent,
+            target: symbol_diff.diff_symbol.map(|r| SymbolRef {
+                section_index: r.section_idx as u32,
+                symbol_index: r.symbol_idx as u32,
+            }),
+        }
+    }
+}
+
+impl From<&ObjDataDiff> for DataDiff {
+    fn from(diff: &ObjDataDiff) -> Self {
+        let kind = match diff.kind {
+            ObjDataDiffKind::None => DiffKind::DiffNone,
+            ObjDataDiffKind::Replace => DiffKind::DiffReplace,
+            ObjDataDiffKind::Delete => DiffKind::DiffDelete,
+            ObjDataDiffKind::Insert => DiffKind::DiffInsert,
+        };
+        Self { kind: kind as i32, data: diff.data.clone(), size: diff.len as u64 }
+    }
+}
+
+impl Instruction {
+    fn new(obj: &ObjInfo, ins: &ObjIns) -> Self {
+        Self {
+            address: ins.address,
+            size: ins.size as u32,
+            opcode: ins.op as u32,
+            mnemonic: ins.mnemonic.clone(),
+            formatted: ins.formatted.clone(),
+            arguments: ins.args.iter().map(Argument::from).collect(),
+            relocation: ins.reloc.as_ref().map(|reloc| Relocation::new(obj, reloc)),
+            branch_dest: ins.branch_dest,
+            line_number: ins.line,
+            original: ins.orig.clone(),
+        }
+    }
+}
+
+impl From<&ObjInsArg> for Argument {
+    fn from(arg: &ObjInsArg) -> Self {
+        let value = match arg {
+            ObjInsArg::PlainText(text) => argument::Value::PlainText(text.to_string()),
+            ObjInsArg::Arg(value) => argument::Value::Argument(ArgumentValue {
+                value: Some(match value {
+                    ObjInsArgValue::Signed(v) => argument_value::Value::Signed(*v),
+                    ObjInsArgValue::Unsigned(v) => argument_value::Value::Unsigned(*v),
+                    ObjInsArgValue::Opaque(v) => argument_value::Value::Opaque(v.to_string()),
+                }),
+            }),
+            ObjInsArg::Reloc => argument::Value::Relocation(ArgumentRelocation {}),
+            ObjInsArg::BranchDest(dest) => argument::Value::BranchDest(*dest),
+            ObjInsArg::Bytes(bytes) => argument::Value::RawBytes(bytes.clone()),
+        };
+        Self { value: Some(value) }
+    }
+}
+
+impl Relocation {
+    fn new(obj: &ObjInfo, reloc: &ObjReloc) -> Self {
+        let r#type = match reloc.flags {
+            RelocationFlags::Elf { r_type } => r_type,
+            RelocationFlags::MachO { r_type, .. } => r_type as u32,
+            RelocationFlags::Coff { typ } => typ as u32,
+            _ => 0,
+        };
+        Self {
+            r#type,
+            type_name: obj.arch.display_reloc(reloc.flags).into_owned(),
+            target: Some(Symbol::from(&reloc.target)),
+            addend: reloc.target.addend,
+            target_section: reloc.target_section.clone(),
+        }
+    }
+}
+
+impl From<RelocDiffKind> for RelocationDiffKind {
+    fn from(kind: RelocDiffKind) -> Self {
+        match kind {
+            RelocDiffKind::SameSymbol => RelocationDiffKind::RelocationSameSymbol,
+            RelocDiffKind::SameAddress => RelocationDiffKind::RelocationSameAddress,
+            RelocDiffKind::SameValue => RelocationDiffKind::RelocationSameValue,
+            RelocDiffKind::Mismatch => RelocationDiffKind::RelocationMismatch,
+        }
+    }
+}
+
+impl From<&ObjInsArgDiff> for ArgumentDiff {
+    fn from(diff: &ObjInsArgDiff) -> Self {
+        let kind = match diff.kind {
+            ObjInsArgDiffKind::Mismatch => ArgumentDiffKind::ArgumentMismatch,
+            ObjInsArgDiffKind::RegisterSwap => ArgumentDiffKind::ArgumentRegisterSwap,
+        };
+        Self {
+            diff_index: diff.idx as u32,
+            kind: kind as i32,
+            relocation: diff.reloc.map(|k| RelocationDiffKind::from(k) as i32),
+            byte_mismatches: diff.bytes,
+        }
+    }
+}
+
+impl InstructionDiff {
+    fn new(obj: &ObjInfo, ins_diff: &ObjInsDiff) -> Self {
+        let diff_kind = match ins_diff.kind {
+            ObjInsDiffKind::None => DiffKind::DiffNone,
+            ObjInsDiffKind::OpMismatch => DiffKind::DiffOpMismatch,
+            ObjInsDiffKind::ArgMismatch => DiffKind::DiffArgMismatch,
+            ObjInsDiffKind::Replace => DiffKind::DiffReplace,
+            ObjInsDiffKind::Delete => DiffKind::DiffDelete,
+            ObjInsDiffKind::Insert => DiffKind::DiffInsert,
+        };
+        Self {
+            diff_kind: diff_kind as i32,
+            instruction: ins_diff.ins.as_ref().map(|ins| Instruction::new(obj, ins)),
+            branch_from: ins_diff.branch_from.as_ref().map(|b| InstructionBranchFrom {
+                instruction_index: b.ins_idx.iter().map(|&i| i as u32).collect(),
+                branch_index: b.branch_idx as u32,
+                label: b.label as u32,
+            }),
+            branch_to: ins_diff.branch_to.as_ref().map(|b| InstructionBranchTo {
+                instruction_index: b.ins_idx as u32,
+                branch_index: b.branch_idx as u32,
+                label: b.label as u32,
+            }),
+            arg_diff: ins_diff
+                .arg_diff
+                .iter()
+                .map(|d| OptionalArgumentDiff { diff: d.as_ref().map(ArgumentDiff::from) })
+                .collect(),
+            accepted: ins_diff.accepted,
+            relocation_diff: ins_diff.reloc_diff.map(|k| RelocationDiffKind::from(k) as i32),
+            callee_match_percent: ins_diff.callee_match,
+            padding: ins_diff.padding,
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Protobuf bindings for the schemas in `objdiff-core/protos`, providing a stable wire
+//! format for external tools.
+
+pub mod diff;
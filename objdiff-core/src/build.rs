@@ -0,0 +1,293 @@
+//! Running build commands for units and collecting their output and errors.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::OnceLock,
+};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// Per-unit overrides of the project build command.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct BuildCommand {
+    /// Program to run in place of the project's `custom_make`
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Arguments in place of the project's `custom_args`
+    #[serde(default)]
+    pub args: Option<Vec<String>>,
+    /// Working directory, relative to the project directory
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// Additional environment variables (e.g. the compiler version)
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+/// Project-wide build settings.
+#[derive(Debug, Default, Clone)]
+pub struct BuildConfig {
+    pub project_dir: Option<PathBuf>,
+    pub custom_make: Option<String>,
+    pub custom_args: Option<Vec<String>>,
+    /// WSL distribution to run the build in (Windows only)
+    pub selected_wsl_distro: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildErrorSeverity {
+    Error,
+    Warning,
+    Note,
+}
+
+/// A compiler diagnostic parsed from the build output.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize)]
+pub struct BuildError {
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub severity: BuildErrorSeverity,
+    pub message: String,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{file}:")?;
+            if let Some(line) = self.line {
+                write!(f, "{line}:")?;
+                if let Some(column) = self.column {
+                    write!(f, "{column}:")?;
+                }
+            }
+            write!(f, " ")?;
+        }
+        let severity = match self.severity {
+            BuildErrorSeverity::Error => "error",
+            BuildErrorSeverity::Warning => "warning",
+            BuildErrorSeverity::Note => "note",
+        };
+        write!(f, "{severity}: {}", self.message)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BuildStatus {
+    pub success: bool,
+    pub cmdline: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// Diagnostics parsed from stdout and stderr
+    pub errors: Vec<BuildError>,
+}
+
+impl Default for BuildStatus {
+    fn default() -> Self {
+        BuildStatus {
+            success: true,
+            cmdline: String::new(),
+            stdout: String::new(),
+            stderr: String::new(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl BuildStatus {
+    fn failed(message: String) -> Self {
+        BuildStatus { success: false, stderr: message, ..Default::default() }
+    }
+}
+
+/// Runs the build for a unit's object.
+pub trait BuildRunner: Send + Sync {
+    /// Builds `target`, a path relative to the project directory. `unit` overrides the
+    /// project's build command for this unit.
+    fn build(
+        &self,
+        config: &BuildConfig,
+        unit: Option<&BuildCommand>,
+        target: &Path,
+    ) -> BuildStatus;
+}
+
+/// Runs the build command as a child process, optionally inside WSL on Windows.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct CommandRunner;
+
+impl BuildRunner for CommandRunner {
+    fn build(
+        &self,
+        config: &BuildConfig,
+        unit: Option<&BuildCommand>,
+        target: &Path,
+    ) -> BuildStatus {
+        let Some(project_dir) = &config.project_dir else {
+            return BuildStatus::failed("Missing project dir".to_string());
+        };
+        match run_command(config, project_dir, unit, target) {
+            Ok(status) => status,
+            Err(e) => BuildStatus::failed(format!("{e:#}")),
+        }
+    }
+}
+
+fn run_command(
+    config: &BuildConfig,
+    project_dir: &Path,
+    unit: Option<&BuildCommand>,
+    target: &Path,
+) -> Result<BuildStatus> {
+    let make =
+        unit.and_then(|u| u.command.as_deref()).or(config.custom_make.as_deref()).unwrap_or("make");
+    let make_args =
+        unit.and_then(|u| u.args.as_deref()).or(config.custom_args.as_deref()).unwrap_or(&[]);
+    let cwd = match unit.and_then(|u| u.cwd.as_deref()) {
+        Some(cwd) => project_dir.join(cwd),
+        None => project_dir.to_path_buf(),
+    };
+    let env = unit.map(|u| &u.env);
+    #[cfg(not(windows))]
+    let mut command = {
+        let mut command = Command::new(make);
+        command.current_dir(&cwd).args(make_args).arg(target);
+        command
+    };
+    #[cfg(windows)]
+    let mut command = {
+        use std::os::windows::process::CommandExt;
+
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        let target = target.to_string_lossy().replace('\\', "/");
+        let mut command;
+        if let Some(distro) = &config.selected_wsl_distro {
+            // Strip distro root prefix \\wsl.localhost\{distro}
+            let wsl_path_prefix = format!("\\\\wsl.localhost\\{}", distro);
+            let cwd = match cwd.strip_prefix(wsl_path_prefix) {
+                Ok(new_cwd) => format!("/{}", new_cwd.to_string_lossy().replace('\\', "/")),
+                Err(_) => cwd.to_string_lossy().to_string(),
+            };
+            command = Command::new("wsl");
+            command
+                .arg("--cd")
+                .arg(cwd)
+                .arg("-d")
+                .arg(distro)
+                .arg("--")
+                .arg(make)
+                .args(make_args)
+                .arg(target);
+            // Environment variables are only forwarded into WSL if listed in WSLENV
+            if let Some(env) = env.filter(|e| !e.is_empty()) {
+                let mut names = env.keys().cloned().collect::<Vec<_>>();
+                if let Ok(existing) = std::env::var("WSLENV") {
+                    names.push(existing);
+                }
+                command.env("WSLENV", names.join(":"));
+            }
+        } else {
+            command = Command::new(make);
+            command.current_dir(&cwd).args(make_args).arg(target);
+        }
+        command.creation_flags(CREATE_NO_WINDOW);
+        command
+    };
+    if let Some(env) = env {
+        command.envs(env);
+    }
+    let mut cmdline = String::new();
+    for (key, value) in env.into_iter().flatten() {
+        cmdline.push_str(&format!("{key}={} ", shell_escape::escape(value.into())));
+    }
+    cmdline.push_str(&shell_escape::escape(command.get_program().to_string_lossy()));
+    for arg in command.get_args() {
+        cmdline.push(' ');
+        cmdline.push_str(shell_escape::escape(arg.to_string_lossy()).as_ref());
+    }
+    let output = command.output().with_context(|| format!("Failed to execute {make}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    let mut errors = parse_errors(&stdout);
+    errors.extend(parse_errors(&stderr));
+    Ok(BuildStatus { success: output.status.success(), cmdline, stdout, stderr, errors })
+}
+
+fn parse_severity(s: &str) -> BuildErrorSeverity {
+    match s.to_ascii_lowercase().as_str() {
+        "warning" => BuildErrorSeverity::Warning,
+        "note" | "info" => BuildErrorSeverity::Note,
+        _ => BuildErrorSeverity::Error,
+    }
+}
+
+/// Parses compiler diagnostics from build output. Recognizes GCC/Clang
+/// (`file:line:col: error: ...`), MSVC (`file(line): error C1234: ...`) and
+/// Metrowerks CodeWarrior (`#   File: ...` blocks) formats.
+pub fn parse_errors(output: &str) -> Vec<BuildError> {
+    static GCC: OnceLock<Regex> = OnceLock::new();
+    static MSVC: OnceLock<Regex> = OnceLock::new();
+    static MW_LINE: OnceLock<Regex> = OnceLock::new();
+    let gcc = GCC.get_or_init(|| {
+        Regex::new(r"^(.+?):(\d+):(?:(\d+):)?\s*(?:fatal )?(error|warning|note):\s*(.*)$").unwrap()
+    });
+    let msvc = MSVC.get_or_init(|| {
+        Regex::new(r"^(.+?)\((\d+)(?:,(\d+))?\)\s*:\s*(?:fatal )?(error|warning)\s*\w*:\s*(.*)$")
+            .unwrap()
+    });
+    let mw_line = MW_LINE.get_or_init(|| Regex::new(r"^#\s+(\d+):").unwrap());
+
+    let mut errors = Vec::new();
+    // Metrowerks diagnostics span several lines starting with #
+    let mut mw_file = None;
+    let mut mw_line_no = None;
+    let mut mw_pending: Option<BuildErrorSeverity> = None;
+    for line in output.lines() {
+        let line = line.trim_end();
+        if let Some(severity) = mw_pending.take() {
+            if let Some(message) = line.strip_prefix('#').map(str::trim) {
+                errors.push(BuildError {
+                    file: mw_file.clone(),
+                    line: mw_line_no,
+                    column: None,
+                    severity,
+                    message: message.to_string(),
+                });
+                continue;
+            }
+        }
+        if let Some(file) = line.strip_prefix('#').and_then(|l| l.trim().strip_prefix("File:")) {
+            mw_file = Some(file.trim().to_string());
+            mw_line_no = None;
+            continue;
+        }
+        if let Some(caps) = mw_line.captures(line) {
+            mw_line_no = caps[1].parse().ok();
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix('#').map(str::trim_start) {
+            if let Some((kind, _)) = rest.split_once(':') {
+                if matches!(kind, "Error" | "Warning") {
+                    mw_pending = Some(parse_severity(kind));
+                    continue;
+                }
+            }
+        }
+        let caps = gcc.captures(line).or_else(|| msvc.captures(line));
+        if let Some(caps) = caps {
+            errors.push(BuildError {
+                file: Some(caps[1].to_string()),
+                line: caps[2].parse().ok(),
+                column: caps.get(3).and_then(|m| m.as_str().parse().ok()),
+                severity: parse_severity(&caps[4]),
+                message: caps[5].to_string(),
+            });
+        }
+    }
+    errors
+}
@@ -0,0 +1,112 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::Hasher,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    config::ProjectObject,
+    diff::DiffObjConfig,
+    obj::{demangle::Demangler, read},
+    report::UnitSummary,
+};
+
+/// Current cache format version. Increment when [`UnitSummary`] or the diff results change.
+const CACHE_VERSION: u32 = 2;
+
+/// Directory containing objdiff's per-project state, relative to the project root.
+pub const STATE_DIR: &str = ".objdiff";
+
+/// On-disk cache of per-unit diff results, stored under `.objdiff/cache` in the project
+/// directory. Entries are keyed by a hash of the unit's input files and configuration, so
+/// a unit is only re-diffed when one of its inputs changes.
+pub struct DiffCache {
+    dir: PathBuf,
+}
+
+impl DiffCache {
+    pub fn new(project_dir: &Path) -> Self {
+        Self { dir: project_dir.join(STATE_DIR).join("cache") }
+    }
+
+    /// Compute the cache key for a unit. Unit paths must already be resolved.
+    pub fn key(
+        object: &ProjectObject,
+        config: &DiffObjConfig,
+        demangler: Demangler,
+    ) -> Result<u64> {
+        // DefaultHasher uses fixed keys, but its algorithm may change between Rust
+        // releases. That only results in cache misses.
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u32(CACHE_VERSION);
+        hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+        let settings = serde_json::to_vec(&(
+            &object.target_path,
+            &object.base_path,
+            &object.symbol_mappings,
+            &object.accepted_mismatches,
+            &object.size_inference,
+            &object.target_map,
+            &object.target_range,
+            config,
+            demangler,
+        ))?;
+        hasher.write(&settings);
+        let inputs = [&object.target_path, &object.base_path]
+            .into_iter()
+            .flatten()
+            .chain(object.target_map.as_ref().map(|m| &m.path));
+        for path in inputs {
+            let path = read::archive_file_path(path);
+            let (data, _) = read::map_file(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            hasher.write_usize(data.len());
+            hasher.write(&data);
+        }
+        Ok(hasher.finish())
+    }
+
+    fn entry_path(&self, key: u64) -> PathBuf { self.dir.join(format!("{key:016x}.json")) }
+
+    /// Look up a cached result. Unreadable entries are treated as missing.
+    pub fn get(&self, key: u64) -> Option<UnitSummary> {
+        let data = fs::read(self.entry_path(key)).ok()?;
+        match serde_json::from_slice(&data) {
+            Ok(summary) => Some(summary),
+            Err(e) => {
+                log::warn!("Ignoring invalid cache entry {key:016x}: {e}");
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, key: u64, summary: &UnitSummary) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.entry_path(key);
+        fs::write(&path, serde_json::to_vec(summary)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Remove a single entry.
+    pub fn invalidate(&self, key: u64) -> Result<()> {
+        match fs::remove_file(self.entry_path(key)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove all entries.
+    pub fn clear(&self) -> Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove {}", self.dir.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Resolving the compiler invocation of a unit, for embedding in reports.
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    config::{ProjectConfig, ProjectObject},
+    report::CompileCommand,
+};
+
+#[derive(serde::Deserialize)]
+struct DatabaseEntry {
+    directory: PathBuf,
+    file: PathBuf,
+    #[serde(default)]
+    command: Option<String>,
+    #[serde(default)]
+    arguments: Option<Vec<String>>,
+    #[serde(default)]
+    output: Option<PathBuf>,
+}
+
+/// A clang-style `compile_commands.json` compilation database.
+#[derive(Default)]
+pub struct CompilationDatabase {
+    entries: Vec<DatabaseEntry>,
+}
+
+impl CompilationDatabase {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let entries = serde_json::from_slice(&data)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Self { entries })
+    }
+
+    /// Loads the project's compilation database: the configured `compile_commands` path,
+    /// or `compile_commands.json` in the project directory if it exists.
+    pub fn for_project(project_dir: &Path, config: &ProjectConfig) -> Result<Option<Self>> {
+        match &config.compile_commands {
+            Some(path) => Self::load(&project_dir.join(path)).map(Some),
+            None => {
+                let path = project_dir.join("compile_commands.json");
+                if path.is_file() {
+                    Self::load(&path).map(Some)
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    /// Finds the entry producing `object`, falling back to the entry compiling `source`.
+    fn find(&self, object: Option<&Path>, source: Option<&Path>) -> Option<&DatabaseEntry> {
+        let object = object.map(absolute);
+        let source = source.map(absolute);
+        let by_output = self.entries.iter().find(|e| {
+            e.output.as_ref().is_some_and(|o| Some(absolute(&e.directory.join(o))) == object)
+        });
+        by_output.or_else(|| {
+            self.entries.iter().find(|e| Some(absolute(&e.directory.join(&e.file))) == source)
+        })
+    }
+}
+
+/// Lexically normalizes a path, resolving `.` and `..` components.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Normalized absolute path, relative to the current directory.
+fn absolute(path: &Path) -> PathBuf {
+    match std::env::current_dir() {
+        Ok(cwd) if path.is_relative() => normalize(&cwd.join(path)),
+        _ => normalize(path),
+    }
+}
+
+fn relative_to(path: &Path, project_dir: &Path) -> String {
+    let path = absolute(path);
+    let relative = path.strip_prefix(absolute(project_dir)).unwrap_or(&path);
+    let s = relative.to_string_lossy().replace('\\', "/");
+    if s.is_empty() {
+        ".".to_string()
+    } else {
+        s
+    }
+}
+
+/// Quotes an argument for a POSIX shell if it contains special characters.
+fn quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Expands the `{source}`, `{object}` and `{unit}` placeholders of a command pattern.
+fn expand_pattern(pattern: &str, object: &ProjectObject, project_dir: &Path) -> String {
+    let source = object
+        .source_path
+        .as_deref()
+        .map(|p| relative_to(&project_dir.join(p), project_dir))
+        .unwrap_or_default();
+    let base = object.base_path.as_deref().map(|p| relative_to(p, project_dir)).unwrap_or_default();
+    pattern.replace("{source}", &source).replace("{object}", &base).replace("{unit}", object.name())
+}
+
+impl ProjectObject {
+    /// The compiler invocation of the unit's base object. Uses the unit's `compile_command`,
+    /// then a matching entry in the compilation database, then the project's
+    /// `compile_command` pattern. Paths must already be resolved.
+    pub fn compile_command(
+        &self,
+        project_dir: &Path,
+        config: &ProjectConfig,
+        database: Option<&CompilationDatabase>,
+    ) -> Option<CompileCommand> {
+        let file =
+            self.source_path.as_deref().map(|p| relative_to(&project_dir.join(p), project_dir));
+        if let Some(pattern) = &self.compile_command {
+            return Some(CompileCommand {
+                directory: ".".to_string(),
+                file,
+                command: expand_pattern(pattern, self, project_dir),
+            });
+        }
+        let source = self.source_path.as_ref().map(|p| project_dir.join(p));
+        if let Some(entry) =
+            database.and_then(|db| db.find(self.base_path.as_deref(), source.as_deref()))
+        {
+            let command = match (&entry.command, &entry.arguments) {
+                (Some(command), _) => command.clone(),
+                (None, Some(arguments)) => {
+                    arguments.iter().map(|a| quote(a)).collect::<Vec<_>>().join(" ")
+                }
+                (None, None) => return None,
+            };
+            return Some(CompileCommand {
+                directory: relative_to(&entry.directory, project_dir),
+                file: Some(entry.file.to_string_lossy().replace('\\', "/")),
+                command,
+            });
+        }
+        config.compile_command.as_ref().map(|pattern| CompileCommand {
+            directory: ".".to_string(),
+            file,
+            command: expand_pattern(pattern, self, project_dir),
+        })
+    }
+}
@@ -1,4 +1,7 @@
+pub mod compile_commands;
+
 use std::{
+    collections::BTreeSet,
     fs::File,
     io::Read,
     path::{Path, PathBuf},
@@ -8,6 +11,19 @@ use anyhow::{anyhow, Context, Result};
 use filetime::FileTime;
 use globset::{Glob, GlobSet, GlobSetBuilder};
 
+use crate::{
+    build::BuildCommand,
+    diff::{
+        idiom::Idiom, DiffObjConfig, DiffPolicy, DiffPolicyPreset, MipsInstrCategory, NumberFormat,
+    },
+    link::LinkConfig,
+    obj::{
+        binary::{FileRange, SymbolMapConfig},
+        demangle::Demangler,
+        read, AcceptedMismatch, SizeInference, SymbolMappings,
+    },
+};
+
 #[inline]
 fn bool_true() -> bool { true }
 
@@ -23,17 +39,74 @@ pub struct ProjectConfig {
     pub target_dir: Option<PathBuf>,
     #[serde(default)]
     pub base_dir: Option<PathBuf>,
+    /// Additional target object directories, one per game version
+    #[serde(default)]
+    pub target_versions: Option<Vec<TargetVersion>>,
     #[serde(default = "bool_true")]
     pub build_base: bool,
     #[serde(default)]
     pub build_target: bool,
     #[serde(default)]
     pub watch_patterns: Option<Vec<Glob>>,
+    /// Symbol demangling scheme, defaults to the architecture's preferred scheme
+    #[serde(default)]
+    pub demangler: Option<Demangler>,
+    /// MIPS instruction set variant, defaults to detecting it from the object
+    #[serde(default)]
+    pub mips_instr_category: Option<MipsInstrCategory>,
+    /// Display format of immediate values
+    #[serde(default)]
+    pub number_format: Option<NumberFormat>,
+    /// Exclude alignment padding at the end of functions from the match percentage
+    #[serde(default)]
+    pub ignore_padding: Option<bool>,
+    /// Instruction idioms whose alternatives compare as equal
+    #[serde(default)]
+    pub idioms: Option<BTreeSet<Idiom>>,
+    /// Sizing of symbols without a size in the object, for units that don't set their own
+    #[serde(default)]
+    pub size_inference: Option<SizeInference>,
+    /// Path to a `compile_commands.json` compilation database
+    #[serde(default)]
+    pub compile_commands: Option<PathBuf>,
+    /// Compiler invocation pattern for units not in the compilation database, with
+    /// `{source}`, `{object}` and `{unit}` placeholders
+    #[serde(default)]
+    pub compile_command: Option<String>,
+    /// Path to the shared workflow state file, defaults to `objdiff-workflow.json`
+    #[serde(default)]
+    pub workflow_path: Option<PathBuf>,
+    /// Original linked binary to compare the linked base objects against
+    #[serde(default)]
+    pub link: Option<LinkConfig>,
     #[serde(default, alias = "units")]
     pub objects: Vec<ProjectObject>,
 }
 
-#[derive(Default, Clone, serde::Deserialize)]
+impl ProjectConfig {
+    /// Diff settings used for the project's reports, with the given scoring policy. Anything
+    /// that records match percentages for comparison with a report uses these.
+    pub fn report_diff_config(&self, policy: DiffPolicyPreset) -> DiffObjConfig {
+        DiffObjConfig {
+            relax_reloc_diffs: true,
+            policy: DiffPolicy::from_preset(policy),
+            mips_instr_category: self.mips_instr_category.unwrap_or_default(),
+            ignore_padding: self.ignore_padding.unwrap_or_default(),
+            idioms: self.idioms.clone().unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A target object directory for one version of the game (e.g. US, EU or JP).
+/// Units are found at the same relative `path` as in `target_dir`.
+#[derive(Default, Clone, PartialEq, serde::Deserialize)]
+pub struct TargetVersion {
+    pub name: String,
+    pub target_dir: PathBuf,
+}
+
+#[derive(Default, Clone, PartialEq, serde::Deserialize)]
 pub struct ProjectObject {
     #[serde(default)]
     pub name: Option<String>,
@@ -49,6 +122,35 @@ pub struct ProjectObject {
     pub complete: Option<bool>,
     #[serde(default)]
     pub scratch: Option<ScratchConfig>,
+    /// Target symbol names mapped to base symbol names
+    #[serde(default)]
+    pub symbol_mappings: Option<SymbolMappings>,
+    /// Progress categories the unit belongs to
+    #[serde(default)]
+    pub progress_categories: Option<Vec<String>>,
+    /// Symbol map for a linked target binary (DOL, REL or raw binary), used in place
+    /// of a target object
+    #[serde(default)]
+    pub target_map: Option<SymbolMapConfig>,
+    /// Like `target_map`, but the target is a byte range of the `target_path` file, such as
+    /// an overlay in a ROM. The symbol map is optional.
+    #[serde(default)]
+    pub target_range: Option<FileRange>,
+    /// Target instructions whose mismatches are accepted as known issues
+    #[serde(default)]
+    pub accepted_mismatches: Option<Vec<AcceptedMismatch>>,
+    /// Sizing of symbols without a size in the object, overriding the project setting
+    #[serde(default)]
+    pub size_inference: Option<SizeInference>,
+    /// Overrides of the project build command for this unit
+    #[serde(default)]
+    pub build: Option<BuildCommand>,
+    /// Source file of the unit, relative to the project directory
+    #[serde(default)]
+    pub source_path: Option<PathBuf>,
+    /// Exact compiler invocation of the unit, overriding the compilation database
+    #[serde(default)]
+    pub compile_command: Option<String>,
 }
 
 impl ProjectObject {
@@ -81,7 +183,98 @@ impl ProjectObject {
         } else if let Some(path) = &self.base_path {
             self.base_path = Some(project_dir.join(path));
         }
+        if let Some(map) = &mut self.target_map {
+            map.path = project_dir.join(&map.path);
+        }
     }
+
+    /// Target object path of the unit for each target version. Units without a `path`
+    /// have no versioned targets.
+    pub fn version_target_paths(
+        &self,
+        project_dir: &Path,
+        versions: &[TargetVersion],
+    ) -> Vec<(String, PathBuf)> {
+        let Some(path) = &self.path else {
+            return vec![];
+        };
+        versions
+            .iter()
+            .map(|v| (v.name.clone(), project_dir.join(&v.target_dir).join(path)))
+            .collect()
+    }
+}
+
+fn with_member(path: &Option<PathBuf>, member: &str) -> Option<PathBuf> {
+    path.as_ref().map(|p| {
+        let mut s = p.clone().into_os_string();
+        s.push(format!("({member})"));
+        PathBuf::from(s)
+    })
+}
+
+fn member_list(path: Option<&Path>) -> Vec<String> {
+    match path {
+        // Paths already referring to an archive member are left as-is
+        Some(path) if read::split_archive_path(path).is_none() && read::is_archive(path) => {
+            read::archive_members(path).unwrap_or_else(|e| {
+                log::warn!("Failed to read archive {}: {e:#}", path.display());
+                vec![]
+            })
+        }
+        _ => vec![],
+    }
+}
+
+/// Expands objects whose target or base is an `ar` archive into one object per
+/// archive member, using the `libfoo.a(bar.o)` path form.
+pub fn expand_archive_objects(
+    objects: Vec<ProjectObject>,
+    project_dir: &Path,
+    target_obj_dir: Option<&Path>,
+    base_obj_dir: Option<&Path>,
+) -> Vec<ProjectObject> {
+    let mut out = Vec::with_capacity(objects.len());
+    for object in objects {
+        let mut resolved = object.clone();
+        resolved.resolve_paths(project_dir, target_obj_dir, base_obj_dir);
+        let target_members = member_list(resolved.target_path.as_deref());
+        let base_members = member_list(resolved.base_path.as_deref());
+        if target_members.is_empty() && base_members.is_empty() {
+            out.push(object);
+            continue;
+        }
+        let mut members = target_members.clone();
+        for member in &base_members {
+            if !members.contains(member) {
+                members.push(member.clone());
+            }
+        }
+        let name = object.name().to_string();
+        for member in members {
+            let in_target = target_members.contains(&member);
+            let in_base = base_members.contains(&member);
+            let member_name = member.rsplit(['/', '\\']).next().unwrap_or(&member);
+            out.push(ProjectObject {
+                name: Some(format!("{name}/{member_name}")),
+                path: if in_target && in_base { with_member(&object.path, &member) } else { None },
+                target_path: if in_target {
+                    with_member(&object.target_path, &member)
+                        .or_else(|| with_member(&resolved.target_path, &member))
+                } else {
+                    None
+                },
+                base_path: if in_base {
+                    with_member(&object.base_path, &member)
+                        .or_else(|| with_member(&resolved.base_path, &member))
+                } else {
+                    None
+                },
+                ..object.clone()
+            });
+        }
+    }
+    out
 }
 
 #[derive(Default, Clone, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -133,6 +326,14 @@ pub fn try_project_config(dir: &Path) -> Option<(Result<ProjectConfig>, ProjectC
                     result = Err(e);
                 }
             }
+            if let Ok(config) = &mut result {
+                // Resolve the project's loader options into each unit
+                if let Some(size_inference) = config.size_inference {
+                    for object in &mut config.objects {
+                        object.size_inference.get_or_insert(size_inference);
+                    }
+                }
+            }
             return Some((result, ProjectConfigInfo { path: config_path, timestamp: ts }));
         }
     }
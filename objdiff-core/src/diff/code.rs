@@ -1,19 +1,26 @@
 use std::{
     cmp::max,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ops::Range,
     time::{Duration, Instant},
 };
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use similar::{capture_diff_slices_deadline, Algorithm};
 
 use crate::{
-    arch::ProcessCodeResult,
+    arch::{ObjArch, ProcessCodeResult},
     diff::{
-        DiffObjConfig, ObjInsArgDiff, ObjInsBranchFrom, ObjInsBranchTo, ObjInsDiff, ObjInsDiffKind,
-        ObjSymbolDiff,
+        idiom::{self, Idiom},
+        prefilter::identical_code,
+        DiffObjConfig, DiffPolicy, DiffProgress, ObjInsArgDiff, ObjInsArgDiffKind,
+        ObjInsBranchFrom, ObjInsBranchTo, ObjInsDiff, ObjInsDiffKind, ObjInsEncoding,
+        ObjInsPrevState, ObjSymbolDiff, RelocDiffKind, SplitBoundary, SplitDiffResult,
+    },
+    obj::{
+        ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjReloc, ObjSection, ObjSectionKind,
+        ObjSymbol, ObjSymbolFlags, SymbolRef,
     },
-    obj::{ObjInfo, ObjInsArg, ObjReloc, ObjSymbol, ObjSymbolFlags, SymbolRef},
 };
 
 pub fn no_diff_code(
@@ -23,12 +30,27 @@ pub fn no_diff_code(
 ) -> Result<ObjSymbolDiff> {
     let out = obj.arch.process_code(obj, symbol_ref, config)?;
 
+    let encodings = config.show_encoding.then(|| symbol_encodings(obj, symbol_ref, &out.insts));
     let mut diff = Vec::<ObjInsDiff>::new();
     for i in out.insts {
         diff.push(ObjInsDiff { ins: Some(i), kind: ObjInsDiffKind::None, ..Default::default() });
     }
+    if let Some(encodings) = encodings {
+        attach_encodings(&mut diff, encodings);
+    }
+    if config.normalize_reloc_addends {
+        normalize_reloc_addends(obj, &mut diff);
+    }
     resolve_branches(&mut diff);
-    Ok(ObjSymbolDiff { symbol_ref, diff_symbol: None, instructions: diff, match_percent: None })
+    resolve_constants(obj, &mut diff);
+    detect_padding(obj.arch.as_ref(), &mut diff);
+    Ok(ObjSymbolDiff {
+        symbol_ref,
+        diff_symbol: None,
+        instructions: diff,
+        match_percent: None,
+        weak_mismatch: false,
+    })
 }
 
 pub fn diff_code(
@@ -38,31 +60,60 @@ pub fn diff_code(
     right_symbol_ref: SymbolRef,
     config: &DiffObjConfig,
 ) -> Result<(ObjSymbolDiff, ObjSymbolDiff)> {
-    let left_out = left_obj.arch.process_code(left_obj, left_symbol_ref, config)?;
-    let right_out = right_obj.arch.process_code(right_obj, right_symbol_ref, config)?;
-
-    let mut left_diff = Vec::<ObjInsDiff>::new();
-    let mut right_diff = Vec::<ObjInsDiff>::new();
-    diff_instructions(&mut left_diff, &mut right_diff, &left_out, &right_out)?;
-
-    resolve_branches(&mut left_diff);
-    resolve_branches(&mut right_diff);
+    diff_code_with_progress(
+        left_obj,
+        right_obj,
+        left_symbol_ref,
+        right_symbol_ref,
+        config,
+        &mut |_| {},
+    )
+}
 
-    let mut diff_state = InsDiffState::default();
-    for (left, right) in left_diff.iter_mut().zip(right_diff.iter_mut()) {
-        let result = compare_ins(config, left, right, &mut diff_state)?;
-        left.kind = result.kind;
-        right.kind = result.kind;
-        left.arg_diff = result.left_args_diff;
-        right.arg_diff = result.right_args_diff;
-    }
+/// Rows compared between [`DiffProgress::Comparing`] reports.
+const PROGRESS_ROWS: usize = 4096;
 
-    let total = left_out.insts.len();
-    let percent = if diff_state.diff_count >= total {
-        0.0
+/// [`diff_code`], reporting the progress of the diff to `on_progress`.
+pub fn diff_code_with_progress(
+    left_obj: &ObjInfo,
+    right_obj: &ObjInfo,
+    left_symbol_ref: SymbolRef,
+    right_symbol_ref: SymbolRef,
+    config: &DiffObjConfig,
+    on_progress: &mut dyn FnMut(DiffProgress),
+) -> Result<(ObjSymbolDiff, ObjSymbolDiff)> {
+    let left_out = left_obj.arch.process_code(left_obj, left_symbol_ref, config)?;
+    let right_out = right_obj.arch.process_code(right_obj, right_symbol_ref, config)?;
+    let (mut left_diff, mut right_diff, percent) = if config.prefilter
+        && left_out.insts.len() == right_out.insts.len()
+        && identical_code(left_obj, left_symbol_ref, right_obj, right_symbol_ref)
+    {
+        let (left_diff, right_diff) =
+            diff_identical(left_obj, right_obj, &left_out, &right_out, config);
+        (left_diff, right_diff, 100.0)
     } else {
-        ((total - diff_state.diff_count) as f32 / total as f32) * 100.0
+        diff_processed(
+            left_obj,
+            right_obj,
+            left_symbol_ref,
+            Some(right_symbol_ref),
+            &left_out,
+            &right_out,
+            config,
+            on_progress,
+        )?
     };
+    if config.show_encoding {
+        attach_encodings(
+            &mut left_diff,
+            symbol_encodings(left_obj, left_symbol_ref, &left_out.insts),
+        );
+        attach_encodings(
+            &mut right_diff,
+            symbol_encodings(right_obj, right_symbol_ref, &right_out.insts),
+        );
+        compare_encodings(&mut left_diff, &mut right_diff);
+    }
 
     Ok((
         ObjSymbolDiff {
@@ -70,29 +121,318 @@ pub fn diff_code(
             diff_symbol: Some(right_symbol_ref),
             instructions: left_diff,
             match_percent: Some(percent),
+            weak_mismatch: false,
         },
         ObjSymbolDiff {
             symbol_ref: right_symbol_ref,
             diff_symbol: Some(left_symbol_ref),
             instructions: right_diff,
             match_percent: Some(percent),
+            weak_mismatch: false,
         },
     ))
 }
 
+/// Diff a target symbol against several base symbols, concatenated in the given order.
+pub fn diff_split_code(
+    left_obj: &ObjInfo,
+    right_obj: &ObjInfo,
+    left_symbol_ref: SymbolRef,
+    right_symbol_refs: &[SymbolRef],
+    config: &DiffObjConfig,
+) -> Result<SplitDiffResult> {
+    let Some(&first_ref) = right_symbol_refs.first() else {
+        bail!("No base symbols to diff against");
+    };
+    let left_out = left_obj.arch.process_code(left_obj, left_symbol_ref, config)?;
+    let mut right_out = ProcessCodeResult { ops: vec![], insts: vec![] };
+    // Index of the first instruction of each base symbol
+    let mut starts = Vec::with_capacity(right_symbol_refs.len());
+    let mut right_encodings = Vec::new();
+    for &symbol_ref in right_symbol_refs {
+        let out = right_obj.arch.process_code(right_obj, symbol_ref, config)?;
+        if config.show_encoding {
+            right_encodings.extend(symbol_encodings(right_obj, symbol_ref, &out.insts));
+        }
+        starts.push(right_out.insts.len());
+        right_out.ops.extend(out.ops);
+        right_out.insts.extend(out.insts);
+    }
+    // Byte comparisons need a single base section
+    let right_section = right_symbol_refs
+        .iter()
+        .all(|r| r.section_idx == first_ref.section_idx)
+        .then_some(first_ref);
+    let (mut left_diff, mut right_diff, percent) = diff_processed(
+        left_obj,
+        right_obj,
+        left_symbol_ref,
+        right_section,
+        &left_out,
+        &right_out,
+        config,
+        &mut |_| {},
+    )?;
+    if config.show_encoding {
+        attach_encodings(
+            &mut left_diff,
+            symbol_encodings(left_obj, left_symbol_ref, &left_out.insts),
+        );
+        attach_encodings(&mut right_diff, right_encodings);
+        compare_encodings(&mut left_diff, &mut right_diff);
+    }
+
+    // Map instruction indices to rows
+    let ins_rows = right_diff
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| d.ins.is_some())
+        .map(|(row, _)| row)
+        .collect::<Vec<_>>();
+    let start_rows = starts
+        .iter()
+        .map(|&idx| ins_rows.get(idx).copied().unwrap_or(right_diff.len()))
+        .collect::<Vec<_>>();
+    let boundaries = right_symbol_refs
+        .iter()
+        .enumerate()
+        .map(|(i, &symbol_ref)| {
+            // Rows before the first base symbol belong to it
+            let start = if i == 0 { 0 } else { start_rows[i] };
+            let end = start_rows.get(i + 1).copied().unwrap_or(right_diff.len());
+            let rows = end.saturating_sub(start);
+            let penalty = (start..end)
+                .map(|row| instruction_penalty(&config.policy, &left_diff[row], &right_diff[row]))
+                .sum::<f32>();
+            let match_percent = if rows == 0 || penalty >= rows as f32 {
+                0.0
+            } else {
+                (rows as f32 - penalty) / rows as f32 * 100.0
+            };
+            SplitBoundary { symbol_ref, rows: start..end, match_percent }
+        })
+        .collect();
+
+    Ok(SplitDiffResult {
+        left: ObjSymbolDiff {
+            symbol_ref: left_symbol_ref,
+            diff_symbol: Some(first_ref),
+            instructions: left_diff,
+            match_percent: Some(percent),
+            weak_mismatch: false,
+        },
+        right: ObjSymbolDiff {
+            symbol_ref: first_ref,
+            diff_symbol: Some(left_symbol_ref),
+            instructions: right_diff,
+            match_percent: Some(percent),
+            weak_mismatch: false,
+        },
+        boundaries,
+    })
+}
+
+/// Diff processed instructions, returning the diff rows of both sides and the match
+/// percentage. `right_symbol_ref` is used for byte comparisons, which are skipped when the
+/// base instructions don't come from a single section.
+#[allow(clippy::too_many_arguments)]
+fn diff_processed(
+    left_obj: &ObjInfo,
+    right_obj: &ObjInfo,
+    left_symbol_ref: SymbolRef,
+    right_symbol_ref: Option<SymbolRef>,
+    left_out: &ProcessCodeResult,
+    right_out: &ProcessCodeResult,
+    config: &DiffObjConfig,
+    on_progress: &mut dyn FnMut(DiffProgress),
+) -> Result<(Vec<ObjInsDiff>, Vec<ObjInsDiff>, f32)> {
+    on_progress(DiffProgress::Aligning {
+        instructions: left_out.insts.len().max(right_out.insts.len()),
+    });
+    let mut left_diff = Vec::<ObjInsDiff>::new();
+    let mut right_diff = Vec::<ObjInsDiff>::new();
+    diff_instructions(
+        &mut left_diff,
+        &mut right_diff,
+        left_obj.arch.as_ref(),
+        left_out,
+        right_obj.arch.as_ref(),
+        right_out,
+        &config.idioms,
+    )?;
+
+    if config.normalize_reloc_addends {
+        normalize_reloc_addends(left_obj, &mut left_diff);
+        normalize_reloc_addends(right_obj, &mut right_diff);
+    }
+    resolve_branches(&mut left_diff);
+    resolve_branches(&mut right_diff);
+    resolve_constants(left_obj, &mut left_diff);
+    resolve_constants(right_obj, &mut right_diff);
+
+    let mut diff_state = InsDiffState::default();
+    if config.symbolic_addresses {
+        diff_state.left_sections = section_ranges(left_obj);
+        diff_state.right_sections = section_ranges(right_obj);
+    }
+    let total_rows = left_diff.len();
+    for start in (0..total_rows).step_by(PROGRESS_ROWS) {
+        let rows = start..(start + PROGRESS_ROWS).min(total_rows);
+        for (left, right) in left_diff[rows.clone()].iter_mut().zip(&mut right_diff[rows.clone()]) {
+            let mut result = compare_ins(config, left, right, &diff_state)?;
+            if result.kind != ObjInsDiffKind::None {
+                if let Some(idiom) = idiom::equivalent(&config.idioms, left, right) {
+                    result = InsDiffResult::default();
+                    left.idiom = Some(idiom);
+                    right.idiom = Some(idiom);
+                }
+            }
+            left.kind = result.kind;
+            right.kind = result.kind;
+            left.arg_diff = result.left_args_diff;
+            right.arg_diff = result.right_args_diff;
+            left.reloc_diff = result.reloc_diff;
+            right.reloc_diff = result.reloc_diff;
+        }
+        on_progress(DiffProgress::Comparing { compared: rows.end, total: total_rows });
+    }
+
+    detect_register_swaps(left_obj.arch.as_ref(), &mut left_diff, &mut right_diff);
+    idiom::mark_reordered_divisions(&config.idioms, &mut left_diff, &mut right_diff);
+    if config.detect_moved_blocks {
+        detect_moved_blocks(&mut left_diff, &mut right_diff);
+    }
+    if let (true, Some(right_symbol_ref)) = (config.policy.strict_bytes, right_symbol_ref) {
+        let left_section = left_obj.section_symbol(left_symbol_ref).0;
+        let right_section = right_obj.section_symbol(right_symbol_ref).0;
+        if let (Some(left_section), Some(right_section)) = (left_section, right_section) {
+            detect_byte_mismatches(left_section, right_section, &mut left_diff, &mut right_diff);
+        }
+    }
+    mark_accepted_mismatches(left_obj, left_symbol_ref, &mut left_diff, &mut right_diff);
+    detect_padding(left_obj.arch.as_ref(), &mut left_diff);
+    detect_padding(right_obj.arch.as_ref(), &mut right_diff);
+
+    let ignored = |left: &ObjInsDiff, right: &ObjInsDiff| {
+        config.ignore_padding && [left, right].iter().all(|d| d.ins.is_none() || d.padding)
+    };
+    let penalty = left_diff
+        .iter()
+        .zip(&right_diff)
+        .filter(|(left, right)| !ignored(left, right))
+        .map(|(left, right)| instruction_penalty(&config.policy, left, right))
+        .sum::<f32>();
+    let total = left_diff
+        .iter()
+        .zip(&right_diff)
+        .filter(|(left, right)| left.ins.is_some() && !ignored(left, right))
+        .count();
+    let percent =
+        if penalty >= total as f32 { 0.0 } else { (total as f32 - penalty) / total as f32 * 100.0 };
+    Ok((left_diff, right_diff, percent))
+}
+
+/// Diff rows of two functions with identical code, which pair up one to one.
+fn diff_identical(
+    left_obj: &ObjInfo,
+    right_obj: &ObjInfo,
+    left_out: &ProcessCodeResult,
+    right_out: &ProcessCodeResult,
+    config: &DiffObjConfig,
+) -> (Vec<ObjInsDiff>, Vec<ObjInsDiff>) {
+    let rows = |out: &ProcessCodeResult| {
+        out.insts
+            .iter()
+            .map(|ins| ObjInsDiff {
+                reloc_diff: ins.reloc.as_ref().map(|_| RelocDiffKind::SameSymbol),
+                ins: Some(ins.clone()),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut left_diff = rows(left_out);
+    let mut right_diff = rows(right_out);
+    if config.normalize_reloc_addends {
+        normalize_reloc_addends(left_obj, &mut left_diff);
+        normalize_reloc_addends(right_obj, &mut right_diff);
+    }
+    resolve_branches(&mut left_diff);
+    resolve_branches(&mut right_diff);
+    resolve_constants(left_obj, &mut left_diff);
+    resolve_constants(right_obj, &mut right_diff);
+    detect_padding(left_obj.arch.as_ref(), &mut left_diff);
+    detect_padding(right_obj.arch.as_ref(), &mut right_diff);
+    (left_diff, right_diff)
+}
+
+/// Groups instructions into diffable units. An instruction with a delay slot is grouped with
+/// the instruction following it, so that the diff never splits a branch from its delay slot.
+/// Instructions matched by an enabled idiom are keyed by their canonical form.
+fn diff_units(
+    arch: &dyn ObjArch,
+    code: &ProcessCodeResult,
+    idioms: &BTreeSet<Idiom>,
+) -> (Vec<u64>, Vec<Range<usize>>) {
+    let mut keys = Vec::with_capacity(code.ops.len());
+    let mut ranges = Vec::with_capacity(code.ops.len());
+    let op = |idx: usize| idiom::align_op(idioms, &code.insts[idx], code.ops[idx]) as u64;
+    let mut idx = 0;
+    while idx < code.insts.len() {
+        if idx + 1 < code.insts.len() && arch.has_delay_slot(&code.insts[idx]) {
+            keys.push(1 << 32 | op(idx) << 16 | op(idx + 1));
+            ranges.push(idx..idx + 2);
+            idx += 2;
+        } else {
+            keys.push(op(idx));
+            ranges.push(idx..idx + 1);
+            idx += 1;
+        }
+    }
+    (keys, ranges)
+}
+
+/// Compare the current diff against a diff of the target and the previous build,
+/// marking instructions that regressed or became matched since the previous build.
+pub fn diff_prev_state(
+    left: &mut ObjSymbolDiff,
+    right: &mut ObjSymbolDiff,
+    snapshot: &ObjSymbolDiff,
+) {
+    // Target instruction address -> matched in previous build
+    let prev_matched = snapshot
+        .instructions
+        .iter()
+        .filter_map(|d| d.ins.as_ref().map(|i| (i.address, d.kind == ObjInsDiffKind::None)))
+        .collect::<BTreeMap<u64, bool>>();
+    for (left, right) in left.instructions.iter_mut().zip(right.instructions.iter_mut()) {
+        let Some(ins) = &left.ins else {
+            continue;
+        };
+        let matched = left.kind == ObjInsDiffKind::None;
+        let state = match prev_matched.get(&ins.address) {
+            Some(true) if !matched => ObjInsPrevState::Regressed,
+            Some(false) if matched => ObjInsPrevState::NewlyMatched,
+            _ => ObjInsPrevState::Unchanged,
+        };
+        left.prev_state = state;
+        right.prev_state = state;
+    }
+}
+
 fn diff_instructions(
     left_diff: &mut Vec<ObjInsDiff>,
     right_diff: &mut Vec<ObjInsDiff>,
+    left_arch: &dyn ObjArch,
     left_code: &ProcessCodeResult,
+    right_arch: &dyn ObjArch,
     right_code: &ProcessCodeResult,
+    idioms: &BTreeSet<Idiom>,
 ) -> Result<()> {
+    let (left_keys, left_units) = diff_units(left_arch, left_code, idioms);
+    let (right_keys, right_units) = diff_units(right_arch, right_code, idioms);
     let deadline = Instant::now() + Duration::from_secs(5);
-    let ops = capture_diff_slices_deadline(
-        Algorithm::Patience,
-        &left_code.ops,
-        &right_code.ops,
-        Some(deadline),
-    );
+    let ops =
+        capture_diff_slices_deadline(Algorithm::Patience, &left_keys, &right_keys, Some(deadline));
     if ops.is_empty() {
         left_diff.extend(
             left_code
@@ -111,6 +451,9 @@ fn diff_instructions(
 
     for op in ops {
         let (_tag, left_range, right_range) = op.as_tag_tuple();
+        // Map unit ranges back to instruction ranges
+        let left_range = unit_range(&left_units, left_range);
+        let right_range = unit_range(&right_units, right_range);
         let len = max(left_range.len(), right_range.len());
         left_diff.extend(
             left_code.insts[left_range.clone()]
@@ -133,6 +476,295 @@ fn diff_instructions(
     Ok(())
 }
 
+fn unit_range(units: &[Range<usize>], range: Range<usize>) -> Range<usize> {
+    if range.is_empty() {
+        let start =
+            units.get(range.start).map_or_else(|| units.last().map_or(0, |u| u.end), |u| u.start);
+        return start..start;
+    }
+    units[range.start].start..units[range.end - 1].end
+}
+
+/// Detect argument mismatches caused only by register allocation differences.
+///
+/// A pair of registers is considered swapped if, across the whole function, the left
+/// register always corresponds to the same right register and vice versa. Mismatched
+/// arguments forming such a pair are marked as [`ObjInsArgDiffKind::RegisterSwap`].
+fn detect_register_swaps(
+    arch: &dyn ObjArch,
+    left_diff: &mut [ObjInsDiff],
+    right_diff: &mut [ObjInsDiff],
+) {
+    let register_pairs = |left: &ObjInsDiff, right: &ObjInsDiff| {
+        let mut pairs = vec![];
+        if let (Some(left_ins), Some(right_ins)) = (&left.ins, &right.ins) {
+            if left_ins.op != right_ins.op || left_ins.args.len() != right_ins.args.len() {
+                return pairs;
+            }
+            for (idx, (a, b)) in left_ins.args.iter().zip(&right_ins.args).enumerate() {
+                if let (ObjInsArg::Arg(a), ObjInsArg::Arg(b)) = (a, b) {
+                    match (arch.register_class(a), arch.register_class(b)) {
+                        (Some(ca), Some(cb)) if ca == cb => {
+                            pairs.push((idx, a.to_string(), b.to_string()))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        pairs
+    };
+
+    // Build the register correspondence in both directions
+    let mut left_map = HashMap::<String, BTreeSet<String>>::new();
+    let mut right_map = HashMap::<String, BTreeSet<String>>::new();
+    for (left, right) in left_diff.iter().zip(right_diff.iter()) {
+        for (_, a, b) in register_pairs(left, right) {
+            left_map.entry(a.clone()).or_default().insert(b.clone());
+            right_map.entry(b).or_default().insert(a);
+        }
+    }
+
+    for (left, right) in left_diff.iter_mut().zip(right_diff.iter_mut()) {
+        if left.kind != ObjInsDiffKind::ArgMismatch {
+            continue;
+        }
+        let pairs = register_pairs(left, right);
+        let is_swap = |idx: usize| {
+            pairs.iter().any(|(i, a, b)| {
+                *i == idx
+                    && left_map.get(a).is_some_and(|s| s.len() == 1)
+                    && right_map.get(b).is_some_and(|s| s.len() == 1)
+            })
+        };
+        let mismatched = left
+            .arg_diff
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| d.is_some())
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        if mismatched.is_empty() || !mismatched.iter().all(|&i| is_swap(i)) {
+            continue;
+        }
+        for idx in mismatched {
+            for diff in [&mut left.arg_diff[idx], &mut right.arg_diff[idx]].into_iter().flatten() {
+                diff.kind = ObjInsArgDiffKind::RegisterSwap;
+            }
+        }
+    }
+}
+
+fn ins_bytes<'a>(section: &'a ObjSection, ins: &ObjIns) -> Option<&'a [u8]> {
+    let offset = ins.address.checked_sub(section.address)? as usize;
+    section.data.get(offset..offset + ins.size as usize)
+}
+
+/// Encoding bytes of each of a symbol's instructions.
+fn symbol_encodings(obj: &ObjInfo, symbol_ref: SymbolRef, insts: &[ObjIns]) -> Vec<Vec<u8>> {
+    let section = obj.section_symbol(symbol_ref).0;
+    insts
+        .iter()
+        .map(|ins| section.and_then(|s| ins_bytes(s, ins)).unwrap_or_default().to_vec())
+        .collect()
+}
+
+/// Attach encodings to the instruction rows of a diff, in instruction order.
+fn attach_encodings(diff: &mut [ObjInsDiff], encodings: Vec<Vec<u8>>) {
+    for (row, bytes) in diff.iter_mut().filter(|d| d.ins.is_some()).zip(encodings) {
+        row.encoding = Some(ObjInsEncoding { bytes, mismatch: 0 });
+    }
+}
+
+/// Marks the differing encoding bytes of rows present on both sides.
+fn compare_encodings(left_diff: &mut [ObjInsDiff], right_diff: &mut [ObjInsDiff]) {
+    for (left, right) in left_diff.iter_mut().zip(right_diff.iter_mut()) {
+        if let (Some(left), Some(right)) = (&mut left.encoding, &mut right.encoding) {
+            let mismatch = byte_mismatches(&left.bytes, &right.bytes);
+            left.mismatch = mismatch;
+            right.mismatch = mismatch;
+        }
+    }
+}
+
+/// Marks unrelocated instructions whose bytes differ as mismatched, for
+/// [`DiffPolicy::strict_bytes`](crate::diff::DiffPolicy::strict_bytes).
+fn detect_byte_mismatches(
+    left_section: &ObjSection,
+    right_section: &ObjSection,
+    left_diff: &mut [ObjInsDiff],
+    right_diff: &mut [ObjInsDiff],
+) {
+    for (left, right) in left_diff.iter_mut().zip(right_diff.iter_mut()) {
+        if left.kind != ObjInsDiffKind::None {
+            continue;
+        }
+        let (Some(left_ins), Some(right_ins)) = (&left.ins, &right.ins) else {
+            continue;
+        };
+        // Relocated bytes are expected to differ
+        if left_ins.reloc.is_some() || right_ins.reloc.is_some() {
+            continue;
+        }
+        if ins_bytes(left_section, left_ins) != ins_bytes(right_section, right_ins) {
+            left.kind = ObjInsDiffKind::ArgMismatch;
+            right.kind = ObjInsDiffKind::ArgMismatch;
+        }
+    }
+}
+
+/// The penalty for a diff row according to the policy, from 0.0 (matching) upwards.
+fn instruction_penalty(policy: &DiffPolicy, left: &ObjInsDiff, right: &ObjInsDiff) -> f32 {
+    if left.accepted {
+        return 0.0;
+    }
+    match left.kind {
+        ObjInsDiffKind::None => 0.0,
+        ObjInsDiffKind::Replace => policy.replace,
+        ObjInsDiffKind::OpMismatch => policy.op_mismatch,
+        ObjInsDiffKind::Insert => policy.insert,
+        ObjInsDiffKind::Delete => policy.delete,
+        ObjInsDiffKind::ArgMismatch => {
+            let mismatched = left
+                .arg_diff
+                .iter()
+                .enumerate()
+                .filter_map(|(i, d)| d.as_ref().map(|d| (i, d.kind)))
+                .collect::<Vec<_>>();
+            let is_reloc = |i: usize| {
+                [&left.ins, &right.ins]
+                    .into_iter()
+                    .flatten()
+                    .any(|ins| matches!(ins.args.get(i), Some(ObjInsArg::Reloc)))
+            };
+            if mismatched.is_empty() {
+                policy.arg_mismatch
+            } else if mismatched.iter().all(|(_, k)| *k == ObjInsArgDiffKind::RegisterSwap) {
+                policy.register_swap
+            } else if mismatched.iter().all(|&(i, _)| is_reloc(i)) {
+                policy.reloc_mismatch
+            } else {
+                policy.arg_mismatch
+            }
+        }
+    }
+}
+
+/// Mark mismatched rows whose target instruction lies within one of the target
+/// object's accepted ranges.
+fn mark_accepted_mismatches(
+    obj: &ObjInfo,
+    symbol_ref: SymbolRef,
+    left_diff: &mut [ObjInsDiff],
+    right_diff: &mut [ObjInsDiff],
+) {
+    let ranges = obj
+        .accepted_ranges
+        .iter()
+        .filter(|(section_idx, _)| *section_idx == symbol_ref.section_idx)
+        .map(|(_, range)| range)
+        .collect::<Vec<_>>();
+    if ranges.is_empty() {
+        return;
+    }
+    for (left, right) in left_diff.iter_mut().zip(right_diff.iter_mut()) {
+        let Some(ins) = &left.ins else {
+            continue;
+        };
+        if left.kind != ObjInsDiffKind::None && ranges.iter().any(|r| r.contains(&ins.address)) {
+            left.accepted = true;
+            right.accepted = true;
+        }
+    }
+}
+
+/// Mark the run of padding instructions at the end of a function. A function consisting
+/// only of padding instructions has none.
+fn detect_padding(arch: &dyn ObjArch, diff: &mut [ObjInsDiff]) {
+    let rows =
+        diff.iter().enumerate().filter_map(|(i, d)| Some((i, d.ins.as_ref()?))).collect::<Vec<_>>();
+    let Some(last_code) = rows.iter().rposition(|(_, ins)| !arch.is_padding(ins)) else {
+        return;
+    };
+    // A nop following the last instruction may be its delay slot
+    let first = if arch.has_delay_slot(rows[last_code].1) { last_code + 2 } else { last_code + 1 };
+    let padding = rows.get(first..).unwrap_or_default().iter().map(|&(i, _)| i).collect::<Vec<_>>();
+    for i in padding {
+        diff[i].padding = true;
+    }
+}
+
+/// Minimum number of instructions for a block to be considered moved.
+const MIN_MOVED_BLOCK_LEN: usize = 2;
+
+/// Match runs of unmatched instructions on one side against identical runs
+/// elsewhere on the other side, marking them as moved blocks.
+fn detect_moved_blocks(left_diff: &mut [ObjInsDiff], right_diff: &mut [ObjInsDiff]) {
+    let left_runs = unmatched_runs(left_diff);
+    let right_runs = unmatched_runs(right_diff);
+    let mut right_used = vec![false; right_runs.len()];
+    let mut block_idx = 0;
+    for left_run in &left_runs {
+        let Some(right_idx) = right_runs.iter().enumerate().position(|(i, right_run)| {
+            !right_used[i]
+                && left_run.len() == right_run.len()
+                && left_run.iter().zip(right_run).all(|(&l, &r)| {
+                    match (&left_diff[l].ins, &right_diff[r].ins) {
+                        (Some(l), Some(r)) => {
+                            l.op == r.op
+                                && l.args.len() == r.args.len()
+                                && l.args.iter().zip(&r.args).all(|(a, b)| moved_arg_eq(a, b))
+                        }
+                        _ => false,
+                    }
+                })
+        }) else {
+            continue;
+        };
+        right_used[right_idx] = true;
+        for &i in left_run {
+            left_diff[i].moved_block = Some(block_idx);
+        }
+        for &i in &right_runs[right_idx] {
+            right_diff[i].moved_block = Some(block_idx);
+        }
+        block_idx += 1;
+    }
+}
+
+fn moved_arg_eq(left: &ObjInsArg, right: &ObjInsArg) -> bool {
+    match (left, right) {
+        (ObjInsArg::PlainText(l), ObjInsArg::PlainText(r)) => l == r,
+        // Branch destinations shift along with the block
+        (ObjInsArg::BranchDest(_), ObjInsArg::BranchDest(_)) => true,
+        _ => left.loose_eq(right),
+    }
+}
+
+/// Collect runs of consecutive unmatched instructions. Padding rows don't end a run.
+fn unmatched_runs(diff: &[ObjInsDiff]) -> Vec<Vec<usize>> {
+    let mut runs = Vec::new();
+    let mut current = Vec::new();
+    for (i, ins_diff) in diff.iter().enumerate() {
+        if ins_diff.ins.is_none() {
+            continue;
+        }
+        if ins_diff.kind == ObjInsDiffKind::None {
+            if current.len() >= MIN_MOVED_BLOCK_LEN {
+                runs.push(std::mem::take(&mut current));
+            } else {
+                current.clear();
+            }
+            continue;
+        }
+        current.push(i);
+    }
+    if current.len() >= MIN_MOVED_BLOCK_LEN {
+        runs.push(current);
+    }
+    runs
+}
+
 fn resolve_branches(vec: &mut [ObjInsDiff]) {
     let mut branch_idx = 0usize;
     // Map addresses to indices
@@ -142,18 +774,34 @@ fn resolve_branches(vec: &mut [ObjInsDiff]) {
             addr_map.insert(ins.address, i);
         }
     }
+    // Number local labels in address order
+    let mut labels = BTreeMap::<usize, usize>::new();
+    for ins_idx in vec
+        .iter()
+        .filter_map(|d| d.ins.as_ref()?.branch_dest.and_then(|a| addr_map.get(&a)))
+        .copied()
+        .collect::<BTreeSet<_>>()
+    {
+        let label = labels.len() + 1;
+        labels.insert(ins_idx, label);
+    }
     // Generate branches
     let mut branches = BTreeMap::<usize, ObjInsBranchFrom>::new();
     for (i, ins_diff) in vec.iter_mut().enumerate() {
         if let Some(ins) = &ins_diff.ins {
-            if let Some(ins_idx) = ins.branch_dest.and_then(|a| addr_map.get(&a)) {
-                if let Some(branch) = branches.get_mut(ins_idx) {
+            if let Some(&ins_idx) = ins.branch_dest.and_then(|a| addr_map.get(&a)) {
+                let label = labels[&ins_idx];
+                if let Some(branch) = branches.get_mut(&ins_idx) {
                     ins_diff.branch_to =
-                        Some(ObjInsBranchTo { ins_idx: *ins_idx, branch_idx: branch.branch_idx });
+                        Some(ObjInsBranchTo { ins_idx, branch_idx: branch.branch_idx, label });
                     branch.ins_idx.push(i);
                 } else {
-                    ins_diff.branch_to = Some(ObjInsBranchTo { ins_idx: *ins_idx, branch_idx });
-                    branches.insert(*ins_idx, ObjInsBranchFrom { ins_idx: vec![i], branch_idx });
+                    ins_diff.branch_to = Some(ObjInsBranchTo { ins_idx, branch_idx, label });
+                    branches.insert(ins_idx, ObjInsBranchFrom {
+                        ins_idx: vec![i],
+                        branch_idx,
+                        label,
+                    });
                     branch_idx += 1;
                 }
             }
@@ -165,10 +813,166 @@ fn resolve_branches(vec: &mut [ObjInsDiff]) {
     }
 }
 
+/// Resolve the constants that instructions load from data sections.
+fn resolve_constants(obj: &ObjInfo, vec: &mut [ObjInsDiff]) {
+    for ins_diff in vec {
+        let Some(ins) = &ins_diff.ins else {
+            continue;
+        };
+        let Some(reloc) = &ins.reloc else {
+            continue;
+        };
+        let Some(section) = obj.sections.iter().find(|s| {
+            s.kind == ObjSectionKind::Data && Some(&s.name) == reloc.target_section.as_ref()
+        }) else {
+            continue;
+        };
+        let offset = reloc.target.section_address as i64 + reloc.target.addend;
+        let Some(data) = usize::try_from(offset).ok().and_then(|o| section.data.get(o..)) else {
+            continue;
+        };
+        ins_diff.constant = obj.arch.data_constant(ins, data);
+    }
+}
+
+/// Retarget relocations with an addend to the innermost symbol containing the target
+/// address, keeping the address itself. A literal referenced as `.rodata+0x8` in one
+/// object and by its own symbol in the other then compares as the same symbol.
+fn normalize_reloc_addends(obj: &ObjInfo, vec: &mut [ObjInsDiff]) {
+    for ins_diff in vec {
+        let Some(reloc) = ins_diff.ins.as_mut().and_then(|i| i.reloc.as_mut()) else {
+            continue;
+        };
+        if reloc.target.addend == 0 {
+            continue;
+        }
+        let Some(section) =
+            obj.sections.iter().find(|s| Some(&s.name) == reloc.target_section.as_ref())
+        else {
+            continue;
+        };
+        let Ok(offset) = u64::try_from(reloc.target.section_address as i64 + reloc.target.addend)
+        else {
+            continue;
+        };
+        // Sizeless symbols only contain their own address
+        let Some(symbol) = section
+            .symbols
+            .iter()
+            .filter(|s| s.section_address <= offset && offset < s.section_address + s.size.max(1))
+            .max_by_key(|s| s.section_address)
+        else {
+            continue;
+        };
+        let addend = (offset - symbol.section_address) as i64;
+        reloc.target = ObjSymbol { addend, ..symbol.clone() };
+    }
+}
+
+/// Whether two relocated instructions load the same constant, regardless of where it's
+/// stored.
+fn constant_eq(left: &ObjInsDiff, right: &ObjInsDiff) -> bool {
+    let reloc_flags =
+        |d: &ObjInsDiff| d.ins.as_ref().and_then(|i| i.reloc.as_ref()).map(|r| r.flags);
+    left.constant.is_some()
+        && left.constant == right.constant
+        && reloc_flags(left) == reloc_flags(right)
+}
+
+/// Address ranges of the sections of an object, for sections with a load address.
+/// Relocatable objects have none, as all of their sections start at zero.
+fn section_ranges(obj: &ObjInfo) -> Vec<(Range<u64>, String)> {
+    obj.sections
+        .iter()
+        .filter_map(|s| {
+            let start = s.virtual_address.or((s.address != 0).then_some(s.address))?;
+            Some((start..start + s.size, s.name.clone()))
+        })
+        .collect()
+}
+
+/// Resolves an address-like immediate to the section containing it and its offset.
+fn section_offset<'a>(
+    sections: &'a [(Range<u64>, String)],
+    value: &ObjInsArgValue,
+) -> Option<(&'a str, u64)> {
+    let address = match *value {
+        ObjInsArgValue::Unsigned(v) => v,
+        ObjInsArgValue::Signed(v) => u64::try_from(v).ok()?,
+        _ => return None,
+    };
+    sections
+        .iter()
+        .find(|(range, _)| range.contains(&address))
+        .map(|(range, name)| (name.as_str(), address - range.start))
+}
+
+/// Key identifying the value of a mismatched argument, used for coloring.
+pub(crate) fn arg_key(ins_diff: &ObjInsDiff, arg: &ObjInsArg) -> String {
+    match arg {
+        ObjInsArg::PlainText(arg) => arg.to_string(),
+        ObjInsArg::Arg(arg) => arg.to_string(),
+        ObjInsArg::Reloc => match ins_diff.ins.as_ref().and_then(|i| i.reloc.as_ref()) {
+            Some(reloc) => match reloc.target.addend {
+                0 => reloc.target.name.clone(),
+                addend if addend < 0 => format!("{}-{:#x}", reloc.target.name, -addend),
+                addend => format!("{}+{:#x}", reloc.target.name, addend),
+            },
+            None => String::new(),
+        },
+        ObjInsArg::BranchDest(arg) => branch_key(ins_diff, *arg),
+        ObjInsArg::Bytes(bytes) => format!("{bytes:02x?}"),
+    }
+}
+
+/// Color index of a mismatched argument pair. Derived from the values with a stable hash
+/// (FNV-1a), so a mismatch keeps its color across rebuilds regardless of the mismatches
+/// before it.
+fn arg_diff_index(left: &str, right: &str) -> usize {
+    let mut hash = 0xcbf29ce484222325u64;
+    for b in left.bytes().chain([0]).chain(right.bytes()) {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    (hash ^ (hash >> 32)) as u32 as usize
+}
+
+/// Key for coloring a mismatched branch destination. Local labels are used so that
+/// destinations at shifted addresses share a color.
+fn branch_key(ins_diff: &ObjInsDiff, dest: u64) -> String {
+    match &ins_diff.branch_to {
+        Some(branch) => format!(".L{}", branch.label),
+        None => format!("{dest}"),
+    }
+}
+
 fn address_eq(left: &ObjSymbol, right: &ObjSymbol) -> bool {
     left.address as i64 + left.addend == right.address as i64 + right.addend
 }
 
+/// Classify the targets of a pair of relocations, ignoring their kinds.
+fn classify_reloc(left: &ObjReloc, right: &ObjReloc) -> RelocDiffKind {
+    let name_matches = left.target.name == right.target.name;
+    let same_symbol = name_matches && left.target.addend == right.target.addend;
+    match (&left.target_section, &right.target_section) {
+        (Some(sl), Some(sr)) if sl == sr => {
+            if same_symbol {
+                RelocDiffKind::SameSymbol
+            } else if address_eq(&left.target, &right.target) {
+                RelocDiffKind::SameAddress
+            } else {
+                RelocDiffKind::Mismatch
+            }
+        }
+        // Possibly a stripped weak symbol
+        (None, Some(_)) if same_symbol && right.target.flags.0.contains(ObjSymbolFlags::Weak) => {
+            RelocDiffKind::SameSymbol
+        }
+        (None, None) if same_symbol => RelocDiffKind::SameSymbol,
+        _ => RelocDiffKind::Mismatch,
+    }
+}
+
 fn reloc_eq(
     config: &DiffObjConfig,
     left_reloc: Option<&ObjReloc>,
@@ -180,27 +984,12 @@ fn reloc_eq(
     if left.flags != right.flags {
         return false;
     }
-    if config.relax_reloc_diffs {
-        return true;
-    }
-
-    let name_matches = left.target.name == right.target.name;
-    match (&left.target_section, &right.target_section) {
-        (Some(sl), Some(sr)) => {
-            // Match if section and name or address match
-            sl == sr && (name_matches || address_eq(&left.target, &right.target))
-        }
-        (Some(_), None) => false,
-        (None, Some(_)) => {
-            // Match if possibly stripped weak symbol
-            name_matches && right.target.flags.0.contains(ObjSymbolFlags::Weak)
-        }
-        (None, None) => name_matches,
-    }
+    config.relax_reloc_diffs || classify_reloc(left, right) != RelocDiffKind::Mismatch
 }
 
 fn arg_eq(
     config: &DiffObjConfig,
+    state: &InsDiffState,
     left: &ObjInsArg,
     right: &ObjInsArg,
     left_diff: &ObjInsDiff,
@@ -212,7 +1001,12 @@ fn arg_eq(
             _ => false,
         },
         ObjInsArg::Arg(l) => match right {
-            ObjInsArg::Arg(r) => l == r,
+            // Compare values regardless of signedness, which only affects display
+            ObjInsArg::Arg(r) => {
+                l.loose_eq(r)
+                    || section_offset(&state.left_sections, l)
+                        .is_some_and(|l| section_offset(&state.right_sections, r) == Some(l))
+            }
             // If relocations are relaxed, match if left is a constant and right is a reloc
             // Useful for instances where the target object is created without relocations
             ObjInsArg::Reloc => config.relax_reloc_diffs,
@@ -220,27 +1014,37 @@ fn arg_eq(
         },
         ObjInsArg::Reloc => {
             matches!(right, ObjInsArg::Reloc)
-                && reloc_eq(
+                && (reloc_eq(
                     config,
                     left_diff.ins.as_ref().and_then(|i| i.reloc.as_ref()),
                     right_diff.ins.as_ref().and_then(|i| i.reloc.as_ref()),
-                )
+                ) || constant_eq(left_diff, right_diff))
         }
         ObjInsArg::BranchDest(_) => {
             // Compare dest instruction idx after diffing
             left_diff.branch_to.as_ref().map(|b| b.ins_idx)
                 == right_diff.branch_to.as_ref().map(|b| b.ins_idx)
         }
+        ObjInsArg::Bytes(l) => matches!(right, ObjInsArg::Bytes(r) if l == r),
     };
 }
 
+/// Mask of the bytes that differ between two raw bytes arguments.
+fn byte_mismatches(left: &[u8], right: &[u8]) -> u64 {
+    let mut mask = 0u64;
+    for i in 0..left.len().max(right.len()) {
+        if left.get(i) != right.get(i) {
+            mask |= 1 << i.min(63);
+        }
+    }
+    mask
+}
+
 #[derive(Default)]
 struct InsDiffState {
-    diff_count: usize,
-    left_arg_idx: usize,
-    right_arg_idx: usize,
-    left_args_idx: BTreeMap<String, usize>,
-    right_args_idx: BTreeMap<String, usize>,
+    /// Section address ranges, if comparing address literals symbolically
+    left_sections: Vec<(Range<u64>, String)>,
+    right_sections: Vec<(Range<u64>, String)>,
 }
 
 #[derive(Default)]
@@ -248,13 +1052,14 @@ struct InsDiffResult {
     kind: ObjInsDiffKind,
     left_args_diff: Vec<Option<ObjInsArgDiff>>,
     right_args_diff: Vec<Option<ObjInsArgDiff>>,
+    reloc_diff: Option<RelocDiffKind>,
 }
 
 fn compare_ins(
     config: &DiffObjConfig,
     left: &ObjInsDiff,
     right: &ObjInsDiff,
-    state: &mut InsDiffState,
+    state: &InsDiffState,
 ) -> Result<InsDiffResult> {
     let mut result = InsDiffResult::default();
     if let (Some(left_ins), Some(right_ins)) = (&left.ins, &right.ins) {
@@ -269,61 +1074,46 @@ fn compare_ins(
         {
             // Totally different op
             result.kind = ObjInsDiffKind::Replace;
-            state.diff_count += 1;
             return Ok(result);
         }
         if left_ins.mnemonic != right_ins.mnemonic {
             // Same op but different mnemonic, still cmp args
             result.kind = ObjInsDiffKind::OpMismatch;
-            state.diff_count += 1;
         }
+        result.reloc_diff =
+            left_ins.reloc.as_ref().zip(right_ins.reloc.as_ref()).map(
+                |(l, r)| match classify_reloc(l, r) {
+                    RelocDiffKind::Mismatch if constant_eq(left, right) => RelocDiffKind::SameValue,
+                    kind => kind,
+                },
+            );
         for (a, b) in left_ins.args.iter().zip(&right_ins.args) {
-            if arg_eq(config, a, b, left, right) {
+            if arg_eq(config, state, a, b, left, right) {
                 result.left_args_diff.push(None);
                 result.right_args_diff.push(None);
             } else {
                 if result.kind == ObjInsDiffKind::None {
                     result.kind = ObjInsDiffKind::ArgMismatch;
-                    state.diff_count += 1;
                 }
-                let a_str = match a {
-                    ObjInsArg::PlainText(arg) => arg.to_string(),
-                    ObjInsArg::Arg(arg) => arg.to_string(),
-                    ObjInsArg::Reloc => String::new(),
-                    ObjInsArg::BranchDest(arg) => format!("{arg}"),
-                };
-                let a_diff = if let Some(idx) = state.left_args_idx.get(&a_str) {
-                    ObjInsArgDiff { idx: *idx }
-                } else {
-                    let idx = state.left_arg_idx;
-                    state.left_args_idx.insert(a_str, idx);
-                    state.left_arg_idx += 1;
-                    ObjInsArgDiff { idx }
-                };
-                let b_str = match b {
-                    ObjInsArg::PlainText(arg) => arg.to_string(),
-                    ObjInsArg::Arg(arg) => arg.to_string(),
-                    ObjInsArg::Reloc => String::new(),
-                    ObjInsArg::BranchDest(arg) => format!("{arg}"),
+                let reloc = match (a, b) {
+                    (ObjInsArg::Reloc, ObjInsArg::Reloc) => result.reloc_diff,
+                    _ => None,
                 };
-                let b_diff = if let Some(idx) = state.right_args_idx.get(&b_str) {
-                    ObjInsArgDiff { idx: *idx }
-                } else {
-                    let idx = state.right_arg_idx;
-                    state.right_args_idx.insert(b_str, idx);
-                    state.right_arg_idx += 1;
-                    ObjInsArgDiff { idx }
+                let bytes = match (a, b) {
+                    (ObjInsArg::Bytes(l), ObjInsArg::Bytes(r)) => byte_mismatches(l, r),
+                    _ => 0,
                 };
+                let idx = arg_diff_index(&arg_key(left, a), &arg_key(right, b));
+                let a_diff = ObjInsArgDiff { idx, kind: ObjInsArgDiffKind::Mismatch, reloc, bytes };
+                let b_diff = a_diff;
                 result.left_args_diff.push(Some(a_diff));
                 result.right_args_diff.push(Some(b_diff));
             }
         }
     } else if left.ins.is_some() {
         result.kind = ObjInsDiffKind::Delete;
-        state.diff_count += 1;
     } else {
         result.kind = ObjInsDiffKind::Insert;
-        state.diff_count += 1;
     }
     Ok(result)
 }
@@ -1,16 +1,73 @@
 use std::{
     cmp::{max, min, Ordering},
+    ops::Range,
     time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
-use similar::{capture_diff_slices_deadline, get_diff_ratio, Algorithm};
+use similar::{capture_diff_slices_deadline, get_diff_ratio, Algorithm, DiffOp};
 
 use crate::{
-    diff::{ObjDataDiff, ObjDataDiffKind, ObjSectionDiff, ObjSymbolDiff},
+    diff::{
+        jump_table::{detect_jump_table, diff_jump_tables, jump_table_match_percent},
+        DiffObjConfig, ObjDataDiff, ObjDataDiffKind, ObjSectionDiff, ObjSymbolDiff,
+    },
     obj::{ObjInfo, ObjSection, SymbolRef},
 };
 
+/// Comparison key for a single byte of data. Bytes covered by a relocation are compared by
+/// relocation target instead of by value, so that pointers don't cause spurious mismatches.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+enum DataKey<'a> {
+    Byte(u8),
+    Reloc { target: &'a str, addend: i64, offset: usize },
+}
+
+fn data_keys<'a>(
+    obj: &ObjInfo,
+    section: &'a ObjSection,
+    range: Range<usize>,
+    config: &DiffObjConfig,
+) -> Vec<DataKey<'a>> {
+    let mut keys =
+        section.data[range.clone()].iter().map(|&b| DataKey::Byte(b)).collect::<Vec<_>>();
+    for reloc in &section.relocations {
+        let size = obj.arch.get_reloc_byte_size(reloc.flags);
+        let start = reloc.address as usize;
+        for offset in 0..size {
+            let Some(idx) = (start + offset).checked_sub(range.start) else {
+                continue;
+            };
+            let Some(key) = keys.get_mut(idx) else {
+                break;
+            };
+            *key = if config.relax_reloc_diffs {
+                DataKey::Reloc { target: "", addend: 0, offset }
+            } else {
+                DataKey::Reloc { target: &reloc.target.name, addend: reloc.target.addend, offset }
+            };
+        }
+    }
+    keys
+}
+
+/// Diff two ranges of data, taking relocations into account.
+fn diff_data_range(
+    left_obj: &ObjInfo,
+    right_obj: &ObjInfo,
+    left: (&ObjSection, Range<usize>),
+    right: (&ObjSection, Range<usize>),
+    config: &DiffObjConfig,
+) -> (Vec<DiffOp>, f32) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let left_keys = data_keys(left_obj, left.0, left.1, config);
+    let right_keys = data_keys(right_obj, right.0, right.1, config);
+    let ops =
+        capture_diff_slices_deadline(Algorithm::Patience, &left_keys, &right_keys, Some(deadline));
+    let match_percent = get_diff_ratio(&ops, left_keys.len(), right_keys.len()) * 100.0;
+    (ops, match_percent)
+}
+
 /// Compare the addresses and sizes of each symbol in the BSS sections.
 pub fn diff_bss_section(
     left: &ObjSection,
@@ -47,33 +104,45 @@ pub fn diff_bss_symbol(
             diff_symbol: Some(right_symbol_ref),
             instructions: vec![],
             match_percent: Some(percent),
+            weak_mismatch: false,
         },
         ObjSymbolDiff {
             symbol_ref: right_symbol_ref,
             diff_symbol: Some(left_symbol_ref),
             instructions: vec![],
             match_percent: Some(percent),
+            weak_mismatch: false,
         },
     ))
 }
 
 pub fn no_diff_symbol(_obj: &ObjInfo, symbol_ref: SymbolRef) -> ObjSymbolDiff {
-    ObjSymbolDiff { symbol_ref, diff_symbol: None, instructions: vec![], match_percent: None }
+    ObjSymbolDiff {
+        symbol_ref,
+        diff_symbol: None,
+        instructions: vec![],
+        match_percent: None,
+        weak_mismatch: false,
+    }
 }
 
 /// Compare the data sections of two object files.
 pub fn diff_data_section(
+    left_obj: &ObjInfo,
+    right_obj: &ObjInfo,
     left: &ObjSection,
     right: &ObjSection,
+    config: &DiffObjConfig,
 ) -> Result<(ObjSectionDiff, ObjSectionDiff)> {
-    let deadline = Instant::now() + Duration::from_secs(5);
     let left_max = left.symbols.iter().map(|s| s.section_address + s.size).max().unwrap_or(0);
     let right_max = right.symbols.iter().map(|s| s.section_address + s.size).max().unwrap_or(0);
-    let left_data = &left.data[..left_max as usize];
-    let right_data = &right.data[..right_max as usize];
-    let ops =
-        capture_diff_slices_deadline(Algorithm::Patience, left_data, right_data, Some(deadline));
-    let match_percent = get_diff_ratio(&ops, left_data.len(), right_data.len()) * 100.0;
+    let (ops, match_percent) = diff_data_range(
+        left_obj,
+        right_obj,
+        (left, 0..left_max as usize),
+        (right, 0..right_max as usize),
+        config,
+    );
 
     let mut left_diff = Vec::<ObjDataDiff>::new();
     let mut right_diff = Vec::<ObjDataDiff>::new();
@@ -162,6 +231,7 @@ pub fn diff_data_symbol(
     right_obj: &ObjInfo,
     left_symbol_ref: SymbolRef,
     right_symbol_ref: SymbolRef,
+    config: &DiffObjConfig,
 ) -> Result<(ObjSymbolDiff, ObjSymbolDiff)> {
     let (left_section, left_symbol) = left_obj.section_symbol(left_symbol_ref);
     let (right_section, right_symbol) = right_obj.section_symbol(right_symbol_ref);
@@ -169,15 +239,30 @@ pub fn diff_data_symbol(
     let left_section = left_section.ok_or_else(|| anyhow!("Data symbol section not found"))?;
     let right_section = right_section.ok_or_else(|| anyhow!("Data symbol section not found"))?;
 
-    let left_data = &left_section.data[left_symbol.section_address as usize
-        ..(left_symbol.section_address + left_symbol.size) as usize];
-    let right_data = &right_section.data[right_symbol.section_address as usize
-        ..(right_symbol.section_address + right_symbol.size) as usize];
-
-    let deadline = Instant::now() + Duration::from_secs(5);
-    let ops =
-        capture_diff_slices_deadline(Algorithm::Patience, left_data, right_data, Some(deadline));
-    let match_percent = get_diff_ratio(&ops, left_data.len(), right_data.len()) * 100.0;
+    let left_range = left_symbol.section_address as usize
+        ..(left_symbol.section_address + left_symbol.size) as usize;
+    let right_range = right_symbol.section_address as usize
+        ..(right_symbol.section_address + right_symbol.size) as usize;
+    let match_percent = match (
+        detect_jump_table(left_obj, left_symbol_ref),
+        detect_jump_table(right_obj, right_symbol_ref),
+    ) {
+        (Some(left_table), Some(right_table)) => jump_table_match_percent(&diff_jump_tables(
+            &left_table,
+            &right_table,
+            config.relax_reloc_diffs,
+        )),
+        _ => {
+            diff_data_range(
+                left_obj,
+                right_obj,
+                (left_section, left_range),
+                (right_section, right_range),
+                config,
+            )
+            .1
+        }
+    };
 
     Ok((
         ObjSymbolDiff {
@@ -185,12 +270,14 @@ pub fn diff_data_symbol(
             diff_symbol: Some(right_symbol_ref),
             instructions: vec![],
             match_percent: Some(match_percent),
+            weak_mismatch: false,
         },
         ObjSymbolDiff {
             symbol_ref: right_symbol_ref,
             diff_symbol: Some(left_symbol_ref),
             instructions: vec![],
             match_percent: Some(match_percent),
+            weak_mismatch: false,
         },
     ))
 }
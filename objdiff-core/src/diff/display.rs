@@ -1,28 +1,47 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, ops::Range};
 
 use crate::{
-    diff::{ObjInsArgDiff, ObjInsDiff},
-    obj::{ObjInsArg, ObjInsArgValue, ObjReloc, ObjSymbol},
+    diff::{
+        code::arg_key, theme::TokenKind, ObjDataDiff, ObjDataDiffKind, ObjDiff, ObjInsArgDiff,
+        ObjInsDiff, ObjInsEncoding, ObjSymbolDiff, RelocDiffKind,
+    },
+    obj::{
+        source::SourceLocation, ObjInfo, ObjInsArg, ObjInsArgValue, ObjReloc, ObjSection,
+        ObjSectionKind, ObjSymbol,
+    },
 };
 
 #[derive(Debug, Copy, Clone)]
 pub enum DiffText<'a> {
     /// Basic text
     Basic(&'a str),
-    /// Colored text
-    BasicColor(&'a str, usize),
+    /// Text with a specific role
+    Styled(&'a str, TokenKind),
     /// Line number
     Line(usize),
     /// Instruction address
     Address(u64),
+    /// Encoding bytes of the instruction, with the bytes that differ from the other side
+    Encoding(&'a ObjInsEncoding),
     /// Instruction mnemonic
     Opcode(&'a str, u16),
     /// Instruction argument
     Argument(&'a ObjInsArgValue, Option<&'a ObjInsArgDiff>),
-    /// Branch destination
-    BranchDest(u64),
-    /// Symbol name
-    Symbol(&'a ObjSymbol),
+    /// Branch destination, with its local label number if it's within the function
+    BranchDest(u64, Option<usize>),
+    /// Local label of a branch target, with an incrementing index for coloring
+    Label(usize, usize),
+    /// Marker for an instruction in a moved block, with an incrementing index for coloring
+    Moved(usize),
+    /// Marker for an accepted mismatch
+    Accepted,
+    /// Raw bytes that couldn't be decoded
+    Bytes(&'a [u8], Option<&'a ObjInsArgDiff>),
+    /// Relocation target symbol name, with the comparison against the other side's target
+    /// and the target function's match percentage
+    Symbol(&'a ObjSymbol, Option<RelocDiffKind>, Option<f32>),
+    /// User note attached to the instruction
+    Comment(&'a str),
     /// Number of spaces
     Spacing(usize),
     /// End of line
@@ -52,8 +71,15 @@ pub fn display_diff<E>(
         cb(DiffText::Line(line as usize))?;
     }
     cb(DiffText::Address(ins.address - base_addr))?;
+    if let Some(encoding) = &ins_diff.encoding {
+        cb(DiffText::Encoding(encoding))?;
+    }
     if let Some(branch) = &ins_diff.branch_from {
-        cb(DiffText::BasicColor(" ~> ", branch.branch_idx))?;
+        cb(DiffText::Label(branch.label, branch.branch_idx))?;
+    } else if ins_diff.accepted {
+        cb(DiffText::Accepted)?;
+    } else if let Some(block_idx) = ins_diff.moved_block {
+        cb(DiffText::Moved(block_idx))?;
     } else {
         cb(DiffText::Spacing(4))?;
     }
@@ -71,34 +97,267 @@ pub fn display_diff<E>(
                 cb(DiffText::Argument(v, diff))?;
             }
             ObjInsArg::Reloc => {
-                display_reloc_name(ins.reloc.as_ref().unwrap(), &mut cb)?;
+                display_reloc_name(
+                    ins.reloc.as_ref().unwrap(),
+                    ins_diff.reloc_diff,
+                    ins_diff.callee_match,
+                    &mut cb,
+                )?;
             }
             ObjInsArg::BranchDest(dest) => {
                 if let Some(dest) = dest.checked_sub(base_addr) {
-                    cb(DiffText::BranchDest(dest))?;
+                    let label = ins_diff.branch_to.as_ref().map(|b| b.label);
+                    cb(DiffText::BranchDest(dest, label))?;
                 } else {
                     cb(DiffText::Basic("<unknown>"))?;
                 }
             }
+            ObjInsArg::Bytes(bytes) => {
+                let diff = ins_diff.arg_diff.get(i).and_then(|o| o.as_ref());
+                cb(DiffText::Bytes(bytes, diff))?;
+            }
         }
     }
+    if let Some(constant) = &ins_diff.constant {
+        cb(DiffText::Basic(&format!(" # {constant}")))?;
+    }
     if let Some(branch) = &ins_diff.branch_to {
-        cb(DiffText::BasicColor(" ~>", branch.branch_idx))?;
+        cb(DiffText::Styled(" ~>", TokenKind::Rotation(branch.branch_idx)))?;
+    }
+    if let Some(comment) = &ins_diff.comment {
+        cb(DiffText::Comment(comment))?;
     }
     cb(DiffText::Eol)?;
     Ok(())
 }
 
+/// A row of a function diff, rendered on demand.
+#[derive(Debug, Copy, Clone)]
+pub struct DisplayRow<'a> {
+    pub index: usize,
+    pub ins_diff: &'a ObjInsDiff,
+    base_addr: u64,
+}
+
+impl DisplayRow<'_> {
+    pub fn display<E>(&self, cb: impl FnMut(DiffText) -> Result<(), E>) -> Result<(), E> {
+        display_diff(self.ins_diff, self.base_addr, cb)
+    }
+}
+
+/// The rows of a function diff in `rows`, rendered as the iterator is consumed, so that
+/// frontends only render what they show of very large functions.
+pub fn display_rows(
+    instructions: &[ObjInsDiff],
+    base_addr: u64,
+    rows: Range<usize>,
+) -> impl Iterator<Item = DisplayRow<'_>> {
+    let end = rows.end.min(instructions.len());
+    let rows = rows.start.min(end)..end;
+    instructions[rows.clone()].iter().zip(rows).map(move |(ins_diff, index)| DisplayRow {
+        index,
+        ins_diff,
+        base_addr,
+    })
+}
+
 fn display_reloc_name<E>(
     reloc: &ObjReloc,
+    reloc_diff: Option<RelocDiffKind>,
+    callee_match: Option<f32>,
     mut cb: impl FnMut(DiffText) -> Result<(), E>,
 ) -> Result<(), E> {
-    cb(DiffText::Symbol(&reloc.target))?;
+    cb(DiffText::Symbol(&reloc.target, reloc_diff, callee_match))?;
     match reloc.target.addend.cmp(&0i64) {
-        Ordering::Greater => cb(DiffText::Basic(&format!("+{:#x}", reloc.target.addend))),
-        Ordering::Less => cb(DiffText::Basic(&format!("-{:#x}", -reloc.target.addend))),
-        _ => Ok(()),
+        Ordering::Greater => cb(DiffText::Basic(&format!("+{:#x}", reloc.target.addend)))?,
+        Ordering::Less => cb(DiffText::Basic(&format!("-{:#x}", -reloc.target.addend)))?,
+        _ => {}
+    }
+    if let Some(via) = &reloc.via {
+        cb(DiffText::Styled(&format!(" (via {via})"), TokenKind::Comment))?;
     }
+    Ok(())
+}
+
+/// A function symbol and its diff, within an object.
+pub type SymbolDiffRef<'a> = (&'a ObjInfo, &'a ObjSymbol, &'a ObjSymbolDiff);
+
+/// Pair up the functions of a unit for display: each target function with its matching
+/// base function, followed by the base functions without a match.
+pub fn function_pairs<'a>(
+    left: Option<(&'a ObjInfo, &'a ObjDiff)>,
+    right: Option<(&'a ObjInfo, &'a ObjDiff)>,
+) -> Vec<(Option<SymbolDiffRef<'a>>, Option<SymbolDiffRef<'a>>)> {
+    let mut out = Vec::new();
+    if let Some((left_obj, left_diff)) = left {
+        for (section, section_diff) in left_obj.sections.iter().zip(&left_diff.sections) {
+            if section.kind != ObjSectionKind::Code {
+                continue;
+            }
+            for (symbol, symbol_diff) in section.symbols.iter().zip(&section_diff.symbols) {
+                let right = right.zip(symbol_diff.diff_symbol).map(|((obj, diff), symbol_ref)| {
+                    (obj, obj.section_symbol(symbol_ref).1, diff.symbol_diff(symbol_ref))
+                });
+                out.push((Some((left_obj, symbol, symbol_diff)), right));
+            }
+        }
+    }
+    if let Some((right_obj, right_diff)) = right {
+        for (section, section_diff) in right_obj.sections.iter().zip(&right_diff.sections) {
+            if section.kind != ObjSectionKind::Code {
+                continue;
+            }
+            for (symbol, symbol_diff) in section.symbols.iter().zip(&section_diff.symbols) {
+                if left.is_none() || symbol_diff.diff_symbol.is_none() {
+                    out.push((None, Some((right_obj, symbol, symbol_diff))));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// An entry of a function's mismatch legend: the color index of a mismatched argument
+/// pair and the values on each side.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ArgDiffLegendEntry {
+    pub idx: usize,
+    /// Target value
+    pub left: String,
+    /// Base value
+    pub right: String,
+}
+
+/// Lists the distinct mismatched argument pairs of a pair of diffed functions, in order of
+/// first appearance, so that UIs can show which color stands for which values.
+pub fn arg_diff_legend(left: &ObjSymbolDiff, right: &ObjSymbolDiff) -> Vec<ArgDiffLegendEntry> {
+    let mut out = Vec::<ArgDiffLegendEntry>::new();
+    for (left_ins, right_ins) in left.instructions.iter().zip(&right.instructions) {
+        let (Some(l), Some(r)) = (&left_ins.ins, &right_ins.ins) else {
+            continue;
+        };
+        for ((la, ra), diff) in l.args.iter().zip(&r.args).zip(&left_ins.arg_diff) {
+            let Some(diff) = diff else {
+                continue;
+            };
+            if out.iter().any(|e| e.idx == diff.idx) {
+                continue;
+            }
+            out.push(ArgDiffLegendEntry {
+                idx: diff.idx,
+                left: arg_key(left_ins, la),
+                right: arg_key(right_ins, ra),
+            });
+        }
+    }
+    out
+}
+
+/// A row of disassembly interleaved with source lines.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SourceRow {
+    /// Source line preceding the instructions generated from it
+    Source(SourceLocation),
+    /// Index into the symbol's instruction diffs
+    Instruction(usize),
+}
+
+/// Interleaves source locations with instructions, emitting a source row each
+/// time the source location changes. Rows for the other side's padding are
+/// passed through as-is.
+pub fn interleave_source(
+    obj: &ObjInfo,
+    section: &ObjSection,
+    ins_diffs: &[ObjInsDiff],
+) -> Vec<SourceRow> {
+    let mut rows = Vec::with_capacity(ins_diffs.len());
+    let mut last = None;
+    for (idx, ins_diff) in ins_diffs.iter().enumerate() {
+        if let Some(ins) = &ins_diff.ins {
+            if let Some(location) = obj.source_location(section, ins.address) {
+                if last != Some(location) {
+                    rows.push(SourceRow::Source(location));
+                    last = Some(location);
+                }
+            }
+        }
+        rows.push(SourceRow::Instruction(idx));
+    }
+    rows
+}
+
+/// A single byte in a data diff row.
+#[derive(Debug, Copy, Clone)]
+pub struct ObjDataByte {
+    /// None if the byte is padding for an insertion or deletion on the other side
+    pub value: Option<u8>,
+    pub kind: ObjDataDiffKind,
+    /// Whether the byte is covered by a relocation
+    pub reloc: bool,
+}
+
+impl ObjDataByte {
+    /// Character to display in the ASCII column.
+    pub fn ascii(&self) -> char {
+        match self.value.map(char::from) {
+            Some(c) if c.is_ascii() && !c.is_ascii_control() => c,
+            Some(_) => '.',
+            None => ' ',
+        }
+    }
+}
+
+/// A row of a data diff, suitable for side-by-side hex + ASCII display.
+#[derive(Debug, Clone)]
+pub struct ObjDataRow {
+    /// Offset of the row within the diff
+    pub address: usize,
+    pub bytes: Vec<ObjDataByte>,
+}
+
+impl ObjDataRow {
+    pub fn has_diff(&self) -> bool { self.bytes.iter().any(|b| b.kind != ObjDataDiffKind::None) }
+}
+
+/// Split data diff hunks into fixed-size rows with per-byte diff state.
+pub fn display_data_rows(
+    obj: &ObjInfo,
+    section: &ObjSection,
+    diffs: &[ObjDataDiff],
+    bytes_per_row: usize,
+) -> Vec<ObjDataRow> {
+    let reloc_ranges = section
+        .relocations
+        .iter()
+        .map(|r| {
+            let start = r.address as usize;
+            start..start + obj.arch.get_reloc_byte_size(r.flags)
+        })
+        .collect::<Vec<_>>();
+    let mut rows = Vec::<ObjDataRow>::new();
+    let mut row = ObjDataRow { address: 0, bytes: Vec::with_capacity(bytes_per_row) };
+    let mut data_offset = 0usize;
+    for diff in diffs {
+        for i in 0..diff.len {
+            let value = diff.data.get(i).copied();
+            let reloc = value.is_some() && reloc_ranges.iter().any(|r| r.contains(&data_offset));
+            if value.is_some() {
+                data_offset += 1;
+            }
+            row.bytes.push(ObjDataByte { value, kind: diff.kind, reloc });
+            if row.bytes.len() == bytes_per_row {
+                let address = row.address + bytes_per_row;
+                rows.push(std::mem::replace(&mut row, ObjDataRow {
+                    address,
+                    bytes: Vec::with_capacity(bytes_per_row),
+                }));
+            }
+        }
+    }
+    if !row.bytes.is_empty() {
+        rows.push(row);
+    }
+    rows
 }
 
 impl PartialEq<DiffText<'_>> for HighlightKind {
@@ -106,8 +365,10 @@ impl PartialEq<DiffText<'_>> for HighlightKind {
         match (self, other) {
             (HighlightKind::Opcode(a), DiffText::Opcode(_, b)) => a == b,
             (HighlightKind::Arg(a), DiffText::Argument(b, _)) => a.loose_eq(b),
-            (HighlightKind::Symbol(a), DiffText::Symbol(b)) => a == &b.name,
-            (HighlightKind::Address(a), DiffText::Address(b) | DiffText::BranchDest(b)) => a == b,
+            (HighlightKind::Symbol(a), DiffText::Symbol(b, _, _)) => a == &b.name,
+            (HighlightKind::Address(a), DiffText::Address(b) | DiffText::BranchDest(b, _)) => {
+                a == b
+            }
             _ => false,
         }
     }
@@ -122,8 +383,8 @@ impl From<DiffText<'_>> for HighlightKind {
         match value {
             DiffText::Opcode(_, op) => HighlightKind::Opcode(op),
             DiffText::Argument(arg, _) => HighlightKind::Arg(arg.clone()),
-            DiffText::Symbol(sym) => HighlightKind::Symbol(sym.name.to_string()),
-            DiffText::Address(addr) | DiffText::BranchDest(addr) => HighlightKind::Address(addr),
+            DiffText::Symbol(sym, _, _) => HighlightKind::Symbol(sym.name.to_string()),
+            DiffText::Address(addr) | DiffText::BranchDest(addr, _) => HighlightKind::Address(addr),
             _ => HighlightKind::None,
         }
     }
@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+
+use crate::{
+    diff::{ObjDiff, ObjSymbolDiff},
+    obj::{ObjInfo, ObjSection, ObjSymbol, SymbolRef},
+};
+
+/// Pattern matched against a symbol's name and demangled name.
+#[derive(Debug, Clone)]
+pub enum NamePattern {
+    /// Case-insensitive substring
+    Substring(String),
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+impl NamePattern {
+    /// Parse a pattern: `/.../` is a regular expression, a pattern containing `*`, `?` or
+    /// `[` is a glob, and anything else is a substring.
+    pub fn parse(s: &str) -> Result<Self> {
+        if let Some(re) = s.strip_prefix('/').and_then(|s| s.strip_suffix('/')) {
+            let re = Regex::new(re).with_context(|| format!("Invalid regex: {re}"))?;
+            Ok(Self::Regex(re))
+        } else if s.contains(['*', '?', '[']) {
+            let glob = Glob::new(s).with_context(|| format!("Invalid glob: {s}"))?;
+            Ok(Self::Glob(glob.compile_matcher()))
+        } else {
+            Ok(Self::Substring(s.to_ascii_lowercase()))
+        }
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        match self {
+            Self::Substring(s) => name.to_ascii_lowercase().contains(s),
+            Self::Glob(glob) => glob.is_match(name),
+            Self::Regex(re) => re.is_match(name),
+        }
+    }
+}
+
+/// Criteria for selecting symbols of a diffed object. Unset criteria match everything.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolFilter {
+    pub name: Option<NamePattern>,
+    /// Section name glob
+    pub section: Option<GlobMatcher>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Only symbols that don't fully match
+    pub mismatched: bool,
+}
+
+impl SymbolFilter {
+    /// Filter for a free-form search string, as typed into a search box. Invalid patterns
+    /// fall back to a substring search.
+    pub fn search(s: &str) -> Self {
+        let name = if s.is_empty() {
+            None
+        } else {
+            Some(
+                NamePattern::parse(s)
+                    .unwrap_or_else(|_| NamePattern::Substring(s.to_ascii_lowercase())),
+            )
+        };
+        Self { name, ..Default::default() }
+    }
+
+    /// Whether the filter has no criteria, matching every symbol.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.section.is_none()
+            && self.min_size.is_none()
+            && self.max_size.is_none()
+            && !self.mismatched
+    }
+
+    /// Whether a symbol matches.
+    pub fn matches(
+        &self,
+        section: Option<&ObjSection>,
+        symbol: &ObjSymbol,
+        symbol_diff: &ObjSymbolDiff,
+    ) -> bool {
+        if let Some(name) = &self.name {
+            if !name.is_match(&symbol.name)
+                && !symbol.demangled_name.as_deref().is_some_and(|n| name.is_match(n))
+            {
+                return false;
+            }
+        }
+        if let Some(glob) = &self.section {
+            if !section.is_some_and(|s| glob.is_match(&s.name)) {
+                return false;
+            }
+        }
+        if self.min_size.is_some_and(|min| symbol.size < min)
+            || self.max_size.is_some_and(|max| symbol.size > max)
+        {
+            return false;
+        }
+        if self.mismatched && symbol_diff.match_percent.is_some_and(|p| p >= 100.0) {
+            return false;
+        }
+        true
+    }
+
+    /// Every matching symbol of an object, in section order.
+    pub fn filter(&self, obj: &ObjInfo, obj_diff: &ObjDiff) -> Vec<SymbolRef> {
+        let mut out = Vec::new();
+        for (section_idx, (section, section_diff)) in
+            obj.sections.iter().zip(&obj_diff.sections).enumerate()
+        {
+            for (symbol_idx, (symbol, symbol_diff)) in
+                section.symbols.iter().zip(&section_diff.symbols).enumerate()
+            {
+                if self.matches(Some(section), symbol, symbol_diff) {
+                    out.push(SymbolRef { section_idx, symbol_idx });
+                }
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,176 @@
+//! Stack frame analysis: compares the frame size, saved registers and stack slots that
+//! two functions' prologues and epilogues set up.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::{
+    diff::ObjSymbolDiff,
+    obj::{ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue},
+    util::ReallySigned,
+};
+
+/// Stack frame layout of a function. Offsets are relative to the stack pointer, as encoded
+/// in the instructions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StackFrame {
+    /// Size of the allocated frame, if the function allocates one
+    pub size: Option<u64>,
+    /// Callee-saved registers (including the return address) and their save slot offsets
+    pub saved_registers: BTreeMap<String, i64>,
+    /// Other accessed stack slots, such as spills and locals
+    pub slots: BTreeSet<i64>,
+}
+
+impl StackFrame {
+    /// Records a load from the stack. Loads from save slots are restores and ignored.
+    pub fn load(&mut self, offset: i64) {
+        if !self.saved_registers.values().any(|&o| o == offset) {
+            self.slots.insert(offset);
+        }
+    }
+}
+
+/// A difference between the target (left) and base (right) stack frames.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameDifference {
+    Size {
+        left: Option<u64>,
+        right: Option<u64>,
+    },
+    /// Register saved by only one side; `left` is true if only the target saves it
+    SavedRegister {
+        register: String,
+        left: bool,
+    },
+    SaveSlot {
+        register: String,
+        left: i64,
+        right: i64,
+    },
+    /// Stack slot accessed by only one side; `left` is true if only the target accesses it
+    Slot {
+        offset: i64,
+        left: bool,
+    },
+}
+
+impl fmt::Display for FrameDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = |size: &Option<u64>| match size {
+            Some(size) => format!("{size:#x}"),
+            None => "not allocated".to_string(),
+        };
+        match self {
+            FrameDifference::Size { left, right } => {
+                write!(f, "frame is {} vs {}", size(left), size(right))
+            }
+            FrameDifference::SavedRegister { register, left: false } => {
+                write!(f, "{register} not saved")
+            }
+            FrameDifference::SavedRegister { register, left: true } => {
+                write!(f, "{register} saved, but not in base")
+            }
+            FrameDifference::SaveSlot { register, left, right } => write!(
+                f,
+                "{register} saved at {:#x} vs {:#x}",
+                ReallySigned(*left),
+                ReallySigned(*right)
+            ),
+            FrameDifference::Slot { offset, left: false } => {
+                write!(f, "stack slot {:#x} not used", ReallySigned(*offset))
+            }
+            FrameDifference::Slot { offset, left: true } => {
+                write!(f, "stack slot {:#x} used, but not in base", ReallySigned(*offset))
+            }
+        }
+    }
+}
+
+/// Extracts the stack frame of a diffed function, if the architecture supports it.
+pub fn symbol_frame(obj: &ObjInfo, diff: &ObjSymbolDiff) -> Option<StackFrame> {
+    let insts = diff.instructions.iter().filter_map(|d| d.ins.as_ref()).collect::<Vec<_>>();
+    obj.arch.stack_frame(&insts)
+}
+
+/// Lists the differences between the target (left) and base (right) frames.
+pub fn compare_frames(left: &StackFrame, right: &StackFrame) -> Vec<FrameDifference> {
+    let mut out = vec![];
+    if left.size != right.size {
+        out.push(FrameDifference::Size { left: left.size, right: right.size });
+    }
+    for (register, &right_offset) in &right.saved_registers {
+        match left.saved_registers.get(register) {
+            Some(&left_offset) if left_offset != right_offset => {
+                out.push(FrameDifference::SaveSlot {
+                    register: register.clone(),
+                    left: left_offset,
+                    right: right_offset,
+                });
+            }
+            Some(_) => {}
+            None => {
+                out.push(FrameDifference::SavedRegister { register: register.clone(), left: false })
+            }
+        }
+    }
+    for register in left.saved_registers.keys() {
+        if !right.saved_registers.contains_key(register) {
+            out.push(FrameDifference::SavedRegister { register: register.clone(), left: true });
+        }
+    }
+    // Slot offsets only line up if the frames have the same size
+    if left.size == right.size {
+        for &offset in right.slots.difference(&left.slots) {
+            out.push(FrameDifference::Slot { offset, left: false });
+        }
+        for &offset in left.slots.difference(&right.slots) {
+            out.push(FrameDifference::Slot { offset, left: true });
+        }
+    }
+    out
+}
+
+/// Parses an immediate operand, which disassemblers may only provide as text.
+pub fn parse_imm(value: &ObjInsArgValue) -> Option<i64> {
+    match value {
+        ObjInsArgValue::Signed(v) => Some(*v),
+        ObjInsArgValue::Unsigned(v) => i64::try_from(*v).ok(),
+        ObjInsArgValue::Opaque(s) => {
+            let (negative, s) = match s.strip_prefix('-') {
+                Some(s) => (true, s),
+                None => (false, s.as_ref()),
+            };
+            let value = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+                None => s.parse().ok()?,
+            };
+            Some(if negative { -value } else { value })
+        }
+    }
+}
+
+/// The non-text operands of an instruction, stopping at relocations.
+pub fn ins_values(ins: &ObjIns) -> Vec<&ObjInsArgValue> {
+    ins.args
+        .iter()
+        .map_while(|arg| match arg {
+            ObjInsArg::PlainText(_) => Some(None),
+            ObjInsArg::Arg(value) => Some(Some(value)),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Splits a `reg, offset(base)` memory operand into its register, offset and base register.
+pub fn mem_operand(ins: &ObjIns) -> Option<(&str, i64, &str)> {
+    match ins_values(ins)[..] {
+        [&ObjInsArgValue::Opaque(ref reg), offset, &ObjInsArgValue::Opaque(ref base), ..] => {
+            Some((reg, parse_imm(offset)?, base))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,216 @@
+use std::{fmt::Write as _, io::Write};
+
+use anyhow::Result;
+
+use crate::{
+    diff::{
+        display::{display_diff, function_pairs, DiffText, SymbolDiffRef},
+        theme::{byte_kind, encoding_byte_kind, token_kind, Theme, TokenKind},
+        NumberFormat, ObjDiff, ObjInsDiff, ObjInsDiffKind,
+    },
+    obj::ObjInfo,
+};
+
+/// Page style. Text colors are set per token from the theme.
+fn style(theme: &Theme) -> String {
+    format!(
+        r#"body {{ background: {}; color: {}; font-family: monospace; }}
+h1, h2 {{ color: {}; font-size: 1.1em; }}
+table {{ border-collapse: collapse; width: 100%; margin-bottom: 2em; }}
+td {{ white-space: pre; vertical-align: top; width: 50%; padding: 0 1em 0 0; }}
+td.mismatch, td.replace, td.delete, td.insert {{ background: rgba(128, 128, 128, 0.12); }}
+.comment {{ font-style: italic; }}
+"#,
+        theme.background, theme.text, theme.emphasized
+    )
+}
+
+/// Write a standalone HTML page with the side-by-side diff of a single function.
+pub fn write_function_html<W: Write>(
+    w: &mut W,
+    theme: &Theme,
+    number_format: NumberFormat,
+    left: Option<SymbolDiffRef>,
+    right: Option<SymbolDiffRef>,
+) -> Result<()> {
+    let title = left.or(right).map(|(_, s, _)| s.display_name(true)).unwrap_or_default();
+    write_header(w, title, theme)?;
+    write_function(w, theme, number_format, left, right)?;
+    write_footer(w)
+}
+
+/// Write a standalone HTML page with the side-by-side diff of every function in a unit.
+pub fn write_unit_html<W: Write>(
+    w: &mut W,
+    title: &str,
+    theme: &Theme,
+    number_format: NumberFormat,
+    left: Option<(&ObjInfo, &ObjDiff)>,
+    right: Option<(&ObjInfo, &ObjDiff)>,
+) -> Result<()> {
+    write_header(w, title, theme)?;
+    for (left, right) in function_pairs(left, right) {
+        write_function(w, theme, number_format, left, right)?;
+    }
+    write_footer(w)
+}
+
+fn write_header<W: Write>(w: &mut W, title: &str, theme: &Theme) -> Result<()> {
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html><head><meta charset=\"utf-8\"><title>{}</title>", escape(title))?;
+    writeln!(w, "<style>{}</style></head><body>", style(theme))?;
+    writeln!(w, "<h1>{}</h1>", escape(title))?;
+    Ok(())
+}
+
+fn write_footer<W: Write>(w: &mut W) -> Result<()> {
+    writeln!(w, "</body></html>")?;
+    Ok(())
+}
+
+fn write_function<W: Write>(
+    w: &mut W,
+    theme: &Theme,
+    number_format: NumberFormat,
+    left: Option<SymbolDiffRef>,
+    right: Option<SymbolDiffRef>,
+) -> Result<()> {
+    let Some((_, symbol, symbol_diff)) = left.or(right) else {
+        return Ok(());
+    };
+    write!(w, "<h2>{}", escape(symbol.display_name(true)))?;
+    if let Some(percent) = symbol_diff.match_percent {
+        write!(w, " ({percent:.2}%)")?;
+    }
+    writeln!(w, "</h2>")?;
+    writeln!(w, "<table>")?;
+    let rows = [left, right]
+        .iter()
+        .flatten()
+        .map(|(_, _, diff)| diff.instructions.len())
+        .max()
+        .unwrap_or(0);
+    for row in 0..rows {
+        write!(w, "<tr>")?;
+        for side in [left, right] {
+            match side.and_then(|(_, s, d)| d.instructions.get(row).map(|i| (s, i))) {
+                Some((symbol, ins_diff)) => write!(
+                    w,
+                    "<td class=\"{}\">{}</td>",
+                    row_class(ins_diff),
+                    ins_html(ins_diff, symbol.address, theme, number_format)
+                )?,
+                None => write!(w, "<td></td>")?,
+            }
+        }
+        writeln!(w, "</tr>")?;
+    }
+    writeln!(w, "</table>")?;
+    Ok(())
+}
+
+fn row_class(ins_diff: &ObjInsDiff) -> &'static str {
+    if ins_diff.accepted {
+        return "accepted";
+    }
+    if ins_diff.padding {
+        return "padding";
+    }
+    match ins_diff.kind {
+        ObjInsDiffKind::None => "",
+        ObjInsDiffKind::OpMismatch | ObjInsDiffKind::ArgMismatch => "mismatch",
+        ObjInsDiffKind::Replace => "replace",
+        ObjInsDiffKind::Delete => "delete",
+        ObjInsDiffKind::Insert => "insert",
+    }
+}
+
+/// Render an instruction, using the same coloring as the function diff view.
+fn ins_html(
+    ins_diff: &ObjInsDiff,
+    base_addr: u64,
+    theme: &Theme,
+    number_format: NumberFormat,
+) -> String {
+    let mut out = String::new();
+    display_diff(ins_diff, base_addr, |text| {
+        let kind = token_kind(&text, ins_diff);
+        let mut pad_to = 0;
+        let label = match text {
+            DiffText::Basic(text) | DiffText::Styled(text, _) => text.to_string(),
+            DiffText::Line(num) => {
+                pad_to = 5;
+                num.to_string()
+            }
+            DiffText::Address(addr) => {
+                pad_to = 5;
+                format!("{addr:x}:")
+            }
+            DiffText::Opcode(mnemonic, _op) => {
+                pad_to = 8;
+                mnemonic.to_string()
+            }
+            DiffText::Argument(arg, _) => arg.display(number_format).to_string(),
+            DiffText::BranchDest(_, Some(label)) => format!(".L{label}"),
+            DiffText::BranchDest(addr, None) => format!("{addr:x}"),
+            DiffText::Label(label, _) => {
+                pad_to = 4;
+                format!(".L{label}")
+            }
+            DiffText::Bytes(bytes, diff) => {
+                // Colored per byte
+                for (i, b) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        out.push(' ');
+                    }
+                    let color = theme.color(byte_kind(diff, i), ins_diff);
+                    write!(out, "<span style=\"color: {color}\">{b:02x}</span>")?;
+                }
+                return Ok(());
+            }
+            DiffText::Encoding(encoding) => {
+                for (i, b) in encoding.bytes.iter().enumerate() {
+                    let color = theme.color(encoding_byte_kind(encoding, i), ins_diff);
+                    write!(out, " <span style=\"color: {color}\">{b:02x}</span>")?;
+                }
+                let len = encoding.bytes.len() * 3;
+                if len < 13 {
+                    out.push_str(&" ".repeat(13 - len));
+                }
+                return Ok(());
+            }
+            DiffText::Moved(_) => " <> ".to_string(),
+            DiffText::Accepted => " ok ".to_string(),
+            DiffText::Symbol(sym, _, _) => sym.display_name(true).to_string(),
+            DiffText::Comment(text) => format!(" # {text}"),
+            DiffText::Spacing(n) => {
+                out.push_str(&" ".repeat(n));
+                return Ok(());
+            }
+            DiffText::Eol => return Ok(()),
+        };
+        let class = if kind == TokenKind::Comment { " class=\"comment\"" } else { "" };
+        let color = theme.color(kind, ins_diff);
+        write!(out, "<span{class} style=\"color: {color}\">{}</span>", escape(&label))?;
+        if label.len() < pad_to {
+            out.push_str(&" ".repeat(pad_to - label.len()));
+        }
+        Ok::<_, std::fmt::Error>(())
+    })
+    .unwrap();
+    out
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
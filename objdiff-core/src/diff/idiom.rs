@@ -0,0 +1,231 @@
+//! Instruction idioms that compilers emit interchangeably for the same operation. Enabled
+//! idiom classes make the diff treat the alternatives as equal, for comparing the output of
+//! slightly different compiler versions.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    diff::{ObjInsDiff, ObjInsDiffKind},
+    obj::{ObjIns, ObjInsArg, ObjInsArgValue},
+};
+
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::VariantArray,
+    strum::EnumMessage,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Idiom {
+    /// MIPS `move` vs `or`/`addu`/`daddu` with `$zero`, PowerPC `mr` vs `or rA, rS, rS`,
+    /// and adding or or-ing zero
+    #[strum(message = "Register moves", detailed_message = "move vs or/addu with $zero, mr vs or")]
+    Move,
+    /// `li r, 0` vs `xor r, r, r`, `sub r, r, r` and `mov r, 0`
+    #[strum(message = "Register zeroing", detailed_message = "li r, 0 vs xor r, r, r")]
+    Zero,
+    /// MIPS `li` vs `addiu`/`ori` with `$zero`
+    #[strum(message = "Immediate loads", detailed_message = "li vs addiu/ori with $zero")]
+    LoadImmediate,
+    /// Division by a constant through a multiply-high and shifts, with the instructions
+    /// scheduled in a different order
+    #[strum(
+        message = "Division by constant",
+        detailed_message = "Reordered multiply-high and shift sequences"
+    )]
+    DivByConstant,
+}
+
+impl Idiom {
+    pub fn name(self) -> &'static str {
+        match self {
+            Idiom::Move => "move",
+            Idiom::Zero => "zero",
+            Idiom::LoadImmediate => "load_immediate",
+            Idiom::DivByConstant => "div_by_constant",
+        }
+    }
+}
+
+impl std::str::FromStr for Idiom {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.replace('-', "_").as_str() {
+            "move" => Ok(Idiom::Move),
+            "zero" => Ok(Idiom::Zero),
+            "load_immediate" | "li" => Ok(Idiom::LoadImmediate),
+            "div_by_constant" | "div" => Ok(Idiom::DivByConstant),
+            _ => Err(format!("Invalid idiom: {s}")),
+        }
+    }
+}
+
+/// Canonical form of an instruction matched by an idiom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Canonical {
+    mnemonic: &'static str,
+    operands: Vec<String>,
+}
+
+impl Canonical {
+    /// Key for aligning instructions, in place of the architecture's opcode.
+    fn op(&self) -> u16 {
+        match self.mnemonic {
+            "move" => u16::MAX - 2,
+            _ => u16::MAX - 3,
+        }
+    }
+}
+
+fn is_zero_reg(v: &ObjInsArgValue) -> bool {
+    matches!(v, ObjInsArgValue::Opaque(name) if matches!(name.as_ref(), "$zero" | "zero" | "$0"))
+}
+
+fn is_zero_imm(v: &ObjInsArgValue) -> bool {
+    matches!(v, ObjInsArgValue::Signed(0) | ObjInsArgValue::Unsigned(0))
+}
+
+fn is_reg(v: &ObjInsArgValue) -> bool { matches!(v, ObjInsArgValue::Opaque(_)) }
+
+/// Operands of an instruction without relocations or branches, which idioms don't apply to.
+fn operands(ins: &ObjIns) -> Option<Vec<&ObjInsArgValue>> {
+    ins.args
+        .iter()
+        .filter_map(|arg| match arg {
+            ObjInsArg::PlainText(_) => None,
+            ObjInsArg::Arg(v) => Some(Some(v)),
+            _ => Some(None),
+        })
+        .collect()
+}
+
+fn canonical(idioms: &BTreeSet<Idiom>, ins: &ObjIns) -> Option<(Idiom, Canonical)> {
+    let ops = operands(ins)?;
+    let li = |idiom: Idiom, d: &ObjInsArgValue, imm: &str| {
+        Some((idiom, Canonical { mnemonic: "li", operands: vec![d.to_string(), imm.to_string()] }))
+    };
+    let mov = |d: &ObjInsArgValue, s: &ObjInsArgValue| {
+        Some((Idiom::Move, Canonical {
+            mnemonic: "move",
+            operands: vec![d.to_string(), s.to_string()],
+        }))
+    };
+    let mnemonic = ins.mnemonic.as_str();
+    if idioms.contains(&Idiom::Zero) {
+        let zero = match (mnemonic, ops.as_slice()) {
+            ("xor" | "sub" | "subu" | "dsubu" | "subf", [_, a, b]) => a == b && is_reg(a),
+            ("xor" | "sub", [d, a]) => d == a && is_reg(a),
+            ("li" | "mov", [d, imm]) => is_reg(d) && is_zero_imm(imm),
+            ("move" | "mr", [_, s]) => is_zero_reg(s),
+            ("or" | "addu" | "daddu", [_, a, b]) => is_zero_reg(a) && is_zero_reg(b),
+            ("addiu" | "daddiu" | "ori", [_, a, imm]) => is_zero_reg(a) && is_zero_imm(imm),
+            _ => false,
+        };
+        if zero {
+            return li(Idiom::Zero, ops[0], "0");
+        }
+    }
+    if idioms.contains(&Idiom::Move) {
+        match (mnemonic, ops.as_slice()) {
+            ("move" | "mr", [d, s]) => return mov(d, s),
+            ("or" | "addu" | "daddu" | "add", [d, a, b]) if is_zero_reg(b) => return mov(d, a),
+            ("or" | "addu" | "daddu" | "add", [d, a, b]) if is_zero_reg(a) => return mov(d, b),
+            ("or", [d, a, b]) if a == b => return mov(d, a),
+            ("ori" | "addi" | "addiu" | "daddiu", [d, a, imm])
+                if is_zero_imm(imm) && is_reg(a) && !is_zero_reg(a) =>
+            {
+                return mov(d, a)
+            }
+            _ => {}
+        }
+    }
+    if idioms.contains(&Idiom::LoadImmediate) {
+        match (mnemonic, ops.as_slice()) {
+            ("li", [d, imm]) if is_reg(d) && !is_reg(imm) => {
+                return li(Idiom::LoadImmediate, d, &imm.to_string())
+            }
+            ("addiu" | "daddiu" | "ori", [d, a, imm]) if is_zero_reg(a) && !is_reg(imm) => {
+                return li(Idiom::LoadImmediate, d, &imm.to_string())
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The opcode to align an instruction by, so that the alternatives of an idiom line up.
+pub(crate) fn align_op(idioms: &BTreeSet<Idiom>, ins: &ObjIns, op: u16) -> u16 {
+    if idioms.is_empty() {
+        return op;
+    }
+    canonical(idioms, ins).map_or(op, |(_, c)| c.op())
+}
+
+/// The idiom under which two different instructions are equivalent, if any.
+pub(crate) fn equivalent(
+    idioms: &BTreeSet<Idiom>,
+    left: &ObjInsDiff,
+    right: &ObjInsDiff,
+) -> Option<Idiom> {
+    if idioms.is_empty() {
+        return None;
+    }
+    let (left, right) = (left.ins.as_ref()?, right.ins.as_ref()?);
+    let (idiom, left) = canonical(idioms, left)?;
+    let (_, right) = canonical(idioms, right)?;
+    (left == right).then_some(idiom)
+}
+
+/// Multiply-high instructions that start a division by a constant.
+const MULTIPLY_HIGH: &[&str] =
+    &["mulhw", "mulhwu", "mult", "multu", "dmult", "dmultu", "imul", "smull", "umull", "smmul"];
+
+/// Mark runs of mismatched rows containing a multiply-high as matching, if both sides of
+/// the run consist of the same instructions in a different order.
+pub(crate) fn mark_reordered_divisions(
+    idioms: &BTreeSet<Idiom>,
+    left_diff: &mut [ObjInsDiff],
+    right_diff: &mut [ObjInsDiff],
+) {
+    if !idioms.contains(&Idiom::DivByConstant) {
+        return;
+    }
+    let mut start = 0;
+    while start < left_diff.len() {
+        if left_diff[start].kind == ObjInsDiffKind::None {
+            start += 1;
+            continue;
+        }
+        let end = (start..left_diff.len())
+            .find(|&i| left_diff[i].kind == ObjInsDiffKind::None)
+            .unwrap_or(left_diff.len());
+        let counts = |diff: &[ObjInsDiff]| {
+            let mut counts = HashMap::<&str, usize>::new();
+            for ins in diff.iter().filter_map(|d| d.ins.as_ref()) {
+                *counts.entry(ins.formatted.as_str()).or_default() += 1;
+            }
+            counts
+        };
+        let has_multiply = left_diff[start..end]
+            .iter()
+            .filter_map(|d| d.ins.as_ref())
+            .any(|ins| MULTIPLY_HIGH.contains(&ins.mnemonic.as_str()));
+        if has_multiply && counts(&left_diff[start..end]) == counts(&right_diff[start..end]) {
+            for diff in left_diff[start..end].iter_mut().chain(&mut right_diff[start..end]) {
+                diff.kind = ObjInsDiffKind::None;
+                diff.arg_diff.clear();
+                diff.idiom = Some(Idiom::DivByConstant);
+            }
+        }
+        start = end;
+    }
+}
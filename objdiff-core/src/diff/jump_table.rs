@@ -0,0 +1,178 @@
+//! Jump tables generated for switch statements. Their entries are code addresses, which
+//! never match numerically between builds, so tables are compared by the structure of
+//! their targets instead: which entries branch to the same case.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    diff::{display::DiffText, ObjDataDiffKind},
+    obj::{ObjInfo, ObjReloc, ObjSection, ObjSectionKind, SymbolRef},
+};
+
+/// Fewer entries than this are more likely to be a pointer pair than a switch.
+const MIN_ENTRIES: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct JumpTableEntry {
+    /// Offset of the entry within the table
+    pub offset: u64,
+    /// Offset of the target within the function
+    pub target: u64,
+    /// Local label of the target, numbered from 1 in address order among the table's
+    /// distinct targets
+    pub label: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct JumpTable {
+    /// Function containing the targets
+    pub function: SymbolRef,
+    pub function_name: String,
+    pub entry_size: usize,
+    pub entries: Vec<JumpTableEntry>,
+}
+
+/// Detect whether a data symbol is a jump table. In relocatable objects, every entry must
+/// be relocated to a code address within a single function. Linked binaries have no
+/// relocations, so every entry must instead hold an address within a single function.
+pub fn detect_jump_table(obj: &ObjInfo, symbol_ref: SymbolRef) -> Option<JumpTable> {
+    let (section, symbol) = obj.section_symbol(symbol_ref);
+    let section = section.filter(|s| s.kind == ObjSectionKind::Data)?;
+    let range = symbol.section_address..symbol.section_address + symbol.size;
+    if section.relocations.is_empty() {
+        return detect_unrelocated(obj, section, range);
+    }
+    let relocs =
+        section.relocations.iter().filter(|r| range.contains(&r.address)).collect::<Vec<_>>();
+    let first = relocs.first()?;
+    let entry_size = obj.arch.get_reloc_byte_size(first.flags);
+    if relocs.len() < MIN_ENTRIES || symbol.size != (relocs.len() * entry_size) as u64 {
+        return None;
+    }
+    let mut targets = Vec::with_capacity(relocs.len());
+    for (i, reloc) in relocs.iter().enumerate() {
+        if reloc.address != range.start + (i * entry_size) as u64 || reloc.flags != first.flags {
+            return None;
+        }
+        targets.push(reloc_target(obj, reloc)?);
+    }
+    build_table(obj, entry_size, targets)
+}
+
+/// The function and offset within it that a relocation targets.
+fn reloc_target(obj: &ObjInfo, reloc: &ObjReloc) -> Option<(SymbolRef, u64)> {
+    let section_idx = obj.sections.iter().position(|s| {
+        s.kind == ObjSectionKind::Code && Some(&s.name) == reloc.target_section.as_ref()
+    })?;
+    let offset = u64::try_from(reloc.target.section_address as i64 + reloc.target.addend).ok()?;
+    code_target(obj, section_idx, offset)
+}
+
+fn code_target(obj: &ObjInfo, section_idx: usize, offset: u64) -> Option<(SymbolRef, u64)> {
+    let (symbol_idx, symbol) = obj.sections[section_idx]
+        .symbols
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.section_address <= offset && offset < s.section_address + s.size)
+        .max_by_key(|(_, s)| s.section_address)?;
+    Some((SymbolRef { section_idx, symbol_idx }, offset - symbol.section_address))
+}
+
+fn detect_unrelocated(
+    obj: &ObjInfo,
+    section: &ObjSection,
+    range: std::ops::Range<u64>,
+) -> Option<JumpTable> {
+    let data = section.data.get(range.start as usize..range.end as usize)?;
+    if data.len() < MIN_ENTRIES * 4 || data.len() % 4 != 0 {
+        return None;
+    }
+    // The byte order isn't known here, only one of them can yield code addresses
+    let read_words = |from_bytes: fn([u8; 4]) -> u32| {
+        data.chunks_exact(4).map(|c| from_bytes(c.try_into().unwrap()) as u64).collect::<Vec<_>>()
+    };
+    let targets = [read_words(u32::from_be_bytes), read_words(u32::from_le_bytes)]
+        .into_iter()
+        .find_map(|words| {
+            words
+                .into_iter()
+                .map(|address| {
+                    let section_idx = obj.sections.iter().position(|s| {
+                        s.kind == ObjSectionKind::Code
+                            && (s.address..s.address + s.size).contains(&address)
+                    })?;
+                    code_target(obj, section_idx, address - obj.sections[section_idx].address)
+                })
+                .collect::<Option<Vec<_>>>()
+        })?;
+    build_table(obj, 4, targets)
+}
+
+fn build_table(
+    obj: &ObjInfo,
+    entry_size: usize,
+    targets: Vec<(SymbolRef, u64)>,
+) -> Option<JumpTable> {
+    let function = targets.first()?.0;
+    if targets.iter().any(|(f, _)| *f != function) {
+        return None;
+    }
+    let distinct = targets.iter().map(|(_, t)| *t).collect::<BTreeSet<_>>();
+    let entries = targets
+        .iter()
+        .enumerate()
+        .map(|(i, (_, target))| JumpTableEntry {
+            offset: (i * entry_size) as u64,
+            target: *target,
+            label: distinct.range(..target).count() + 1,
+        })
+        .collect();
+    Some(JumpTable {
+        function,
+        function_name: obj.section_symbol(function).1.name.clone(),
+        entry_size,
+        entries,
+    })
+}
+
+/// Compare two jump tables entry by entry, as entries are indexed by the case value.
+/// Entries match if they target the same case label of the same function.
+pub fn diff_jump_tables(
+    left: &JumpTable,
+    right: &JumpTable,
+    relax_reloc_diffs: bool,
+) -> Vec<ObjDataDiffKind> {
+    let same_function = relax_reloc_diffs || left.function_name == right.function_name;
+    (0..left.entries.len().max(right.entries.len()))
+        .map(|i| match (left.entries.get(i), right.entries.get(i)) {
+            (Some(l), Some(r)) if same_function && l.label == r.label => ObjDataDiffKind::None,
+            (Some(_), Some(_)) => ObjDataDiffKind::Replace,
+            (Some(_), None) => ObjDataDiffKind::Delete,
+            (None, _) => ObjDataDiffKind::Insert,
+        })
+        .collect()
+}
+
+/// Match percentage of two jump tables.
+pub fn jump_table_match_percent(kinds: &[ObjDataDiffKind]) -> f32 {
+    if kinds.is_empty() {
+        return 100.0;
+    }
+    let matching = kinds.iter().filter(|&&k| k == ObjDataDiffKind::None).count();
+    matching as f32 / kinds.len() as f32 * 100.0
+}
+
+/// Display an entry of a jump table, with its target as a local label of the function.
+pub fn display_jump_table_entry<E>(
+    table: &JumpTable,
+    entry: &JumpTableEntry,
+    mut cb: impl FnMut(DiffText) -> Result<(), E>,
+) -> Result<(), E> {
+    cb(DiffText::Address(entry.offset))?;
+    cb(DiffText::Spacing(4))?;
+    cb(DiffText::Opcode(if table.entry_size == 8 { ".8byte" } else { ".4byte" }, 0))?;
+    cb(DiffText::Spacing(1))?;
+    cb(DiffText::BranchDest(entry.target, Some(entry.label)))?;
+    cb(DiffText::Eol)?;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+//! Section and symbol layout comparison. A unit only links identically to the target once
+//! its section sizes, alignment and symbol order match, even if every function matches.
+
+use std::{collections::HashMap, fmt};
+
+use crate::obj::{ObjInfo, ObjSection, ObjSymbol};
+
+/// A layout difference between the target (left) and base (right) objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutIssue {
+    /// Section present on only one side; `left` is true if only the target has it
+    MissingSection {
+        section: String,
+        left: bool,
+    },
+    SectionSize {
+        section: String,
+        left: u64,
+        right: u64,
+    },
+    SectionAlignment {
+        section: String,
+        left: u64,
+        right: u64,
+    },
+    /// Symbol present on only one side; `left` is true if only the target has it
+    MissingSymbol {
+        section: String,
+        symbol: String,
+        left: bool,
+    },
+    SymbolSize {
+        symbol: String,
+        left: u64,
+        right: u64,
+    },
+    /// Symbol at a different offset within its section, such as from padding differences
+    SymbolOffset {
+        symbol: String,
+        left: u64,
+        right: u64,
+    },
+    /// The first out-of-order base symbol of a section, and the target symbol in its place
+    SymbolOrder {
+        section: String,
+        symbol: String,
+        expected: String,
+    },
+}
+
+impl fmt::Display for LayoutIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutIssue::MissingSection { section, left: true } => {
+                write!(f, "section {section} missing")
+            }
+            LayoutIssue::MissingSection { section, left: false } => {
+                write!(f, "section {section} not in target")
+            }
+            LayoutIssue::SectionSize { section, left, right } => {
+                write!(f, "section {section} size is {right:#x}, expected {left:#x}")
+            }
+            LayoutIssue::SectionAlignment { section, left, right } => {
+                write!(f, "section {section} alignment is {right:#x}, expected {left:#x}")
+            }
+            LayoutIssue::MissingSymbol { section, symbol, left: true } => {
+                write!(f, "{symbol} missing from {section}")
+            }
+            LayoutIssue::MissingSymbol { section, symbol, left: false } => {
+                write!(f, "{symbol} in {section} not in target")
+            }
+            LayoutIssue::SymbolSize { symbol, left, right } => {
+                write!(f, "{symbol} size is {right:#x}, expected {left:#x}")
+            }
+            LayoutIssue::SymbolOffset { symbol, left, right } => {
+                write!(f, "{symbol} is at {right:#x}, expected {left:#x}")
+            }
+            LayoutIssue::SymbolOrder { section, symbol, expected } => {
+                write!(f, "{section} symbols out of order: {symbol} where {expected} is expected")
+            }
+        }
+    }
+}
+
+/// Symbols of a section with a size, in address order.
+fn layout_symbols(section: &ObjSection) -> Vec<&ObjSymbol> {
+    let mut symbols = section.symbols.iter().filter(|s| s.size > 0).collect::<Vec<_>>();
+    symbols.sort_by_key(|s| s.section_address);
+    symbols
+}
+
+/// Compares the layout of the base object against the target. Sections and symbols are
+/// matched by name. An empty result means the layout matches.
+pub fn diff_layout(left: &ObjInfo, right: &ObjInfo) -> Vec<LayoutIssue> {
+    let mut out = vec![];
+    let right_sections =
+        right.sections.iter().map(|s| (s.name.as_str(), s)).collect::<HashMap<_, _>>();
+    for left_section in &left.sections {
+        let name = &left_section.name;
+        let Some(right_section) = right_sections.get(name.as_str()) else {
+            out.push(LayoutIssue::MissingSection { section: name.clone(), left: true });
+            continue;
+        };
+        if left_section.size != right_section.size {
+            out.push(LayoutIssue::SectionSize {
+                section: name.clone(),
+                left: left_section.size,
+                right: right_section.size,
+            });
+        }
+        if let (Some(l), Some(r)) = (left_section.align, right_section.align) {
+            if l != r {
+                out.push(LayoutIssue::SectionAlignment {
+                    section: name.clone(),
+                    left: l,
+                    right: r,
+                });
+            }
+        }
+        diff_section_symbols(name, left_section, right_section, &mut out);
+    }
+    for right_section in &right.sections {
+        if !left.sections.iter().any(|s| s.name == right_section.name) {
+            out.push(LayoutIssue::MissingSection {
+                section: right_section.name.clone(),
+                left: false,
+            });
+        }
+    }
+    out
+}
+
+fn diff_section_symbols(
+    section: &str,
+    left: &ObjSection,
+    right: &ObjSection,
+    out: &mut Vec<LayoutIssue>,
+) {
+    let left_symbols = layout_symbols(left);
+    let right_symbols = layout_symbols(right);
+    let left_by_name =
+        left_symbols.iter().map(|s| (s.name.as_str(), *s)).collect::<HashMap<_, _>>();
+    let right_by_name =
+        right_symbols.iter().map(|s| (s.name.as_str(), *s)).collect::<HashMap<_, _>>();
+    for symbol in &left_symbols {
+        let Some(right_symbol) = right_by_name.get(symbol.name.as_str()) else {
+            out.push(LayoutIssue::MissingSymbol {
+                section: section.to_string(),
+                symbol: symbol.name.clone(),
+                left: true,
+            });
+            continue;
+        };
+        if symbol.size != right_symbol.size {
+            out.push(LayoutIssue::SymbolSize {
+                symbol: symbol.name.clone(),
+                left: symbol.size,
+                right: right_symbol.size,
+            });
+        }
+        if symbol.section_address != right_symbol.section_address {
+            out.push(LayoutIssue::SymbolOffset {
+                symbol: symbol.name.clone(),
+                left: symbol.section_address,
+                right: right_symbol.section_address,
+            });
+        }
+    }
+    for symbol in &right_symbols {
+        if !left_by_name.contains_key(symbol.name.as_str()) {
+            out.push(LayoutIssue::MissingSymbol {
+                section: section.to_string(),
+                symbol: symbol.name.clone(),
+                left: false,
+            });
+        }
+    }
+    // Relative order of the symbols present on both sides
+    let left_order = left_symbols.iter().filter(|s| right_by_name.contains_key(s.name.as_str()));
+    let right_order = right_symbols.iter().filter(|s| left_by_name.contains_key(s.name.as_str()));
+    if let Some((expected, symbol)) = left_order.zip(right_order).find(|(l, r)| l.name != r.name) {
+        out.push(LayoutIssue::SymbolOrder {
+            section: section.to_string(),
+            symbol: symbol.name.clone(),
+            expected: expected.name.clone(),
+        });
+    }
+}
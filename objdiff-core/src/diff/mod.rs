@@ -1,21 +1,45 @@
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::Range,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use crate::{
     diff::{
-        code::{diff_code, no_diff_code},
+        code::{
+            diff_code, diff_code_with_progress, diff_prev_state, diff_split_code, no_diff_code,
+        },
         data::{
             diff_bss_section, diff_bss_symbol, diff_data_section, diff_data_symbol,
             diff_text_section, no_diff_symbol,
         },
+        idiom::Idiom,
+    },
+    obj::{
+        ObjConstant, ObjInfo, ObjIns, ObjSection, ObjSectionKind, ObjSymbol, ObjSymbolFlags,
+        SymbolRef,
     },
-    obj::{ObjInfo, ObjIns, ObjSection, ObjSectionKind, ObjSymbol, SymbolRef},
 };
 
 mod code;
 mod data;
 pub mod display;
+#[cfg(feature = "config")]
+pub mod filter;
+pub mod frame;
+pub mod html;
+pub mod idiom;
+pub mod jump_table;
+pub mod layout;
+pub mod objdump;
+pub mod prefilter;
+pub mod signature;
+pub mod similarity;
+pub mod snapshot;
+pub mod text;
+pub mod theme;
+pub mod xref;
 
 #[derive(
     Debug,
@@ -91,32 +115,196 @@ pub enum MipsInstrCategory {
     R4000Allegrex,
     #[strum(message = "R5900 EE (PS2)")]
     R5900,
+    #[strum(message = "MIPS16e")]
+    Mips16e,
+}
+
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    Eq,
+    PartialEq,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::VariantArray,
+    strum::EnumMessage,
+)]
+pub enum ArmArchVersion {
+    #[default]
+    #[strum(message = "Auto (default)")]
+    Auto,
+    #[strum(message = "ARMv4T (GBA)")]
+    V4T,
+    #[strum(message = "ARMv5TE (DS)")]
+    V5Te,
+}
+
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    Eq,
+    PartialEq,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::VariantArray,
+    strum::EnumMessage,
+)]
+pub enum DiffPolicyPreset {
+    #[default]
+    #[strum(message = "Default")]
+    Default,
+    /// Register allocation differences don't count as mismatches
+    #[strum(message = "Ignore register allocation")]
+    IgnoreRegalloc,
+    /// Any instruction byte difference counts as a full mismatch
+    #[strum(message = "Strict (byte-equal)")]
+    Strict,
+}
+
+/// Weights used to score a function diff. Each weight is the penalty for one
+/// instruction of that kind, where 1.0 counts as a full mismatch.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DiffPolicy {
+    /// Instructions with a different opcode
+    pub replace: f32,
+    /// Instructions with the same opcode but a different mnemonic
+    pub op_mismatch: f32,
+    pub arg_mismatch: f32,
+    /// Argument mismatches where only relocation targets differ
+    pub reloc_mismatch: f32,
+    pub insert: f32,
+    pub delete: f32,
+    /// Argument mismatches caused only by a consistent register swap
+    pub register_swap: f32,
+    /// Compare the bytes of unrelocated instructions, marking any difference as a mismatch
+    pub strict_bytes: bool,
+}
+
+impl Default for DiffPolicy {
+    fn default() -> Self { Self::from_preset(DiffPolicyPreset::Default) }
+}
+
+impl DiffPolicy {
+    pub fn from_preset(preset: DiffPolicyPreset) -> Self {
+        let base = Self {
+            replace: 1.0,
+            op_mismatch: 1.0,
+            arg_mismatch: 1.0,
+            reloc_mismatch: 1.0,
+            insert: 1.0,
+            delete: 1.0,
+            register_swap: 0.5,
+            strict_bytes: false,
+        };
+        match preset {
+            DiffPolicyPreset::Default => base,
+            DiffPolicyPreset::IgnoreRegalloc => Self { register_swap: 0.0, ..base },
+            DiffPolicyPreset::Strict => Self { register_swap: 1.0, strict_bytes: true, ..base },
+        }
+    }
+
+    /// The preset matching these weights, if any.
+    pub fn preset(&self) -> Option<DiffPolicyPreset> {
+        use strum::VariantArray;
+        DiffPolicyPreset::VARIANTS.iter().copied().find(|&p| Self::from_preset(p) == *self)
+    }
+}
+
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    Eq,
+    PartialEq,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::VariantArray,
+    strum::EnumMessage,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberRadix {
+    #[default]
+    #[strum(message = "Hexadecimal (default)")]
+    Hex,
+    #[strum(message = "Decimal")]
+    Decimal,
+}
+
+/// How immediate values are displayed. Only affects display: diffing compares the values
+/// themselves.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct NumberFormat {
+    pub radix: NumberRadix,
+    /// Show negative values as unsigned two's complement (`0xfffffffc` rather than `-0x4`)
+    pub unsigned: bool,
+    /// Minimum number of digits, padded with zeros
+    pub min_digits: u8,
 }
 
 #[inline]
 const fn default_true() -> bool { true }
 
-#[derive(Debug, Clone, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct DiffObjConfig {
     pub relax_reloc_diffs: bool,
+    /// Detect blocks of instructions that were reordered, rather than
+    /// treating them as unrelated insertions and deletions
+    pub detect_moved_blocks: bool,
+    /// Compare immediates that fall inside a section's address range by section and
+    /// offset, for diffing linked binaries where absolute addresses shift
+    pub symbolic_addresses: bool,
+    /// Exclude alignment padding at the end of functions from the match percentage
+    pub ignore_padding: bool,
+    /// Retarget relocations with an addend to the symbol containing the target address,
+    /// so that `sym+0x8` compares equal to a symbol defined at that offset
+    pub normalize_reloc_addends: bool,
+    /// Instruction idioms whose alternatives compare as equal
+    pub idioms: BTreeSet<Idiom>,
+    /// Report functions with identical code as matching without comparing instructions
+    #[serde(default = "default_true")]
+    pub prefilter: bool,
     #[serde(default = "default_true")]
     pub space_between_args: bool,
+    /// Show the encoding bytes of each instruction after its address
+    pub show_encoding: bool,
+    /// Scoring weights for the match percentage
+    pub policy: DiffPolicy,
+    pub number_format: NumberFormat,
     // x86
     pub x86_formatter: X86Formatter,
     // MIPS
     pub mips_abi: MipsAbi,
     pub mips_instr_category: MipsInstrCategory,
+    // ARM
+    pub arm_arch_version: ArmArchVersion,
 }
 
 impl Default for DiffObjConfig {
     fn default() -> Self {
         Self {
             relax_reloc_diffs: false,
+            detect_moved_blocks: false,
+            symbolic_addresses: false,
+            ignore_padding: false,
+            normalize_reloc_addends: false,
+            idioms: BTreeSet::new(),
+            prefilter: true,
             space_between_args: true,
+            show_encoding: false,
+            policy: Default::default(),
+            number_format: Default::default(),
             x86_formatter: Default::default(),
             mips_abi: Default::default(),
             mips_instr_category: Default::default(),
+            arm_arch_version: Default::default(),
         }
     }
 }
@@ -152,6 +340,9 @@ pub struct ObjSymbolDiff {
     pub diff_symbol: Option<SymbolRef>,
     pub instructions: Vec<ObjInsDiff>,
     pub match_percent: Option<f32>,
+    /// The paired symbol is weak on only one side, so the linker may pick a different
+    /// definition than the code being compared
+    pub weak_mismatch: bool,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -165,6 +356,52 @@ pub struct ObjInsDiff {
     pub branch_to: Option<ObjInsBranchTo>,
     /// Arg diffs
     pub arg_diff: Vec<Option<ObjInsArgDiff>>,
+    /// Match state change relative to the previous build
+    pub prev_state: ObjInsPrevState,
+    /// Incrementing index of the moved block containing this instruction, for coloring
+    pub moved_block: Option<usize>,
+    /// Mismatch accepted as a known issue, excluded from the match percentage
+    pub accepted: bool,
+    /// Comparison of the relocation targets, if both instructions are relocated
+    pub reloc_diff: Option<RelocDiffKind>,
+    /// Alignment padding after the end of the function's code
+    pub padding: bool,
+    /// Match percentage of the function targeted by the relocation, if it's defined in
+    /// the same object
+    pub callee_match: Option<f32>,
+    /// Constant loaded from the relocation target
+    pub constant: Option<ObjConstant>,
+    /// Note attached to the instruction by the user
+    pub comment: Option<String>,
+    /// Idiom under which the instruction matches a different one on the other side
+    pub idiom: Option<Idiom>,
+    /// Encoding bytes of the instruction, if
+    /// [`DiffObjConfig::show_encoding`] is set
+    pub encoding: Option<ObjInsEncoding>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ObjInsEncoding {
+    pub bytes: Vec<u8>,
+    /// Bytes that differ from the other side's encoding, one bit per byte. Bytes past the
+    /// 64th share the last bit.
+    pub mismatch: u64,
+}
+
+impl ObjInsEncoding {
+    /// Whether byte `idx` differs from the other side.
+    pub fn byte_mismatch(&self, idx: usize) -> bool { self.mismatch & (1 << idx.min(63)) != 0 }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum ObjInsPrevState {
+    /// Unchanged, or no previous build available
+    #[default]
+    Unchanged,
+    /// Matched in the previous build, but no longer matches
+    Regressed,
+    /// Didn't match in the previous build, but now matches
+    NewlyMatched,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
@@ -199,6 +436,38 @@ pub enum ObjDataDiffKind {
 pub struct ObjInsArgDiff {
     /// Incrementing index for coloring
     pub idx: usize,
+    pub kind: ObjInsArgDiffKind,
+    /// Comparison of the relocation targets, for relocation arguments
+    pub reloc: Option<RelocDiffKind>,
+    /// Mismatched bytes of a raw bytes argument, one bit per byte. Bytes past the 64th
+    /// share the last bit.
+    pub bytes: u64,
+}
+
+impl ObjInsArgDiff {
+    /// Whether byte `idx` of a raw bytes argument differs from the other side.
+    pub fn byte_mismatch(&self, idx: usize) -> bool { self.bytes & (1 << idx.min(63)) != 0 }
+}
+
+/// How the targets of a pair of relocations compare.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RelocDiffKind {
+    /// Same symbol name and addend
+    SameSymbol,
+    /// Different symbols resolving to the same section and offset
+    SameAddress,
+    /// Different constant pool entries holding the same value
+    SameValue,
+    /// Different targets
+    Mismatch,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum ObjInsArgDiffKind {
+    #[default]
+    Mismatch,
+    /// The argument differs only by register allocation
+    RegisterSwap,
 }
 
 #[derive(Debug, Clone)]
@@ -207,6 +476,8 @@ pub struct ObjInsBranchFrom {
     pub ins_idx: Vec<usize>,
     /// Incrementing index for coloring
     pub branch_idx: usize,
+    /// Local label number (`.L1`, `.L2`, ...), in address order within the function
+    pub label: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -215,20 +486,18 @@ pub struct ObjInsBranchTo {
     pub ins_idx: usize,
     /// Incrementing index for coloring
     pub branch_idx: usize,
+    /// Local label number of the target instruction
+    pub label: usize,
 }
 
 #[derive(Default)]
 pub struct ObjDiff {
     pub sections: Vec<ObjSectionDiff>,
-    pub common: Vec<ObjSymbolDiff>,
 }
 
 impl ObjDiff {
     pub fn new_from_obj(obj: &ObjInfo) -> Self {
-        let mut result = Self {
-            sections: Vec::with_capacity(obj.sections.len()),
-            common: Vec::with_capacity(obj.common.len()),
-        };
+        let mut result = Self { sections: Vec::with_capacity(obj.sections.len()) };
         for (section_idx, section) in obj.sections.iter().enumerate() {
             let mut symbols = Vec::with_capacity(section.symbols.len());
             for (symbol_idx, _) in section.symbols.iter().enumerate() {
@@ -237,6 +506,7 @@ impl ObjDiff {
                     diff_symbol: None,
                     instructions: vec![],
                     match_percent: None,
+                    weak_mismatch: false,
                 });
             }
             result.sections.push(ObjSectionDiff {
@@ -250,14 +520,6 @@ impl ObjDiff {
                 match_percent: None,
             });
         }
-        for (symbol_idx, _) in obj.common.iter().enumerate() {
-            result.common.push(ObjSymbolDiff {
-                symbol_ref: SymbolRef { section_idx: obj.sections.len(), symbol_idx },
-                diff_symbol: None,
-                instructions: vec![],
-                match_percent: None,
-            });
-        }
         result
     }
 
@@ -273,21 +535,112 @@ impl ObjDiff {
 
     #[inline]
     pub fn symbol_diff(&self, symbol_ref: SymbolRef) -> &ObjSymbolDiff {
-        if symbol_ref.section_idx == self.sections.len() {
-            &self.common[symbol_ref.symbol_idx]
-        } else {
-            &self.section_diff(symbol_ref.section_idx).symbols[symbol_ref.symbol_idx]
-        }
+        &self.section_diff(symbol_ref.section_idx).symbols[symbol_ref.symbol_idx]
     }
 
     #[inline]
     pub fn symbol_diff_mut(&mut self, symbol_ref: SymbolRef) -> &mut ObjSymbolDiff {
-        if symbol_ref.section_idx == self.sections.len() {
-            &mut self.common[symbol_ref.symbol_idx]
-        } else {
-            &mut self.section_diff_mut(symbol_ref.section_idx).symbols[symbol_ref.symbol_idx]
+        &mut self.section_diff_mut(symbol_ref.section_idx).symbols[symbol_ref.symbol_idx]
+    }
+}
+
+/// A base symbol within a [`SplitDiffResult`].
+#[derive(Debug, Clone)]
+pub struct SplitBoundary {
+    pub symbol_ref: SymbolRef,
+    /// Diff rows covered by the symbol
+    pub rows: Range<usize>,
+    pub match_percent: f32,
+}
+
+/// The result of diffing a target symbol against several concatenated base symbols.
+#[derive(Debug, Clone)]
+pub struct SplitDiffResult {
+    pub left: ObjSymbolDiff,
+    /// Rows of the concatenated base symbols. `symbol_ref` is the first base symbol.
+    pub right: ObjSymbolDiff,
+    /// Base symbols in diff order
+    pub boundaries: Vec<SplitBoundary>,
+}
+
+/// Diff a target function against base functions concatenated in the given order, to
+/// validate how a function was split or merged.
+pub fn diff_split(
+    config: &DiffObjConfig,
+    left: &ObjInfo,
+    left_symbol_ref: SymbolRef,
+    right: &ObjInfo,
+    right_symbol_refs: &[SymbolRef],
+) -> Result<SplitDiffResult> {
+    for (obj, symbol_ref) in std::iter::once((left, left_symbol_ref))
+        .chain(right_symbol_refs.iter().map(|&r| (right, r)))
+    {
+        let (section, symbol) = obj.section_symbol(symbol_ref);
+        if !matches!(section, Some(section) if section.kind == ObjSectionKind::Code) {
+            bail!("Symbol {} is not a function", symbol.name);
         }
     }
+    diff_split_code(left, right, left_symbol_ref, right_symbol_refs, config)
+}
+
+/// Progress of a function diff, for frontends to report while a very large function is
+/// being diffed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DiffProgress {
+    /// Instructions of both sides are being aligned, which takes most of the time of a large
+    /// diff. `instructions` is the instruction count of the larger side.
+    Aligning { instructions: usize },
+    /// `compared` of the `total` aligned rows have been compared.
+    Comparing { compared: usize, total: usize },
+}
+
+/// The result of diffing a base function against one target version.
+#[derive(Debug, Clone)]
+pub struct VersionDiff {
+    pub version: String,
+    /// Matching target symbol, if the version has one
+    pub target_symbol: Option<SymbolRef>,
+    pub left: Option<ObjSymbolDiff>,
+    pub right: ObjSymbolDiff,
+}
+
+impl VersionDiff {
+    pub fn match_percent(&self) -> Option<f32> { self.right.match_percent }
+}
+
+/// Diff a base function against the matching function of each target version, in the
+/// order given.
+pub fn diff_versions(
+    config: &DiffObjConfig,
+    right: &ObjInfo,
+    right_symbol_ref: SymbolRef,
+    targets: &[(&str, &ObjInfo)],
+) -> Result<Vec<VersionDiff>> {
+    let (section, symbol) = right.section_symbol(right_symbol_ref);
+    let Some(section) = section.filter(|s| s.kind == ObjSectionKind::Code) else {
+        bail!("Symbol {} is not a function", symbol.name);
+    };
+    let mut out = Vec::with_capacity(targets.len());
+    for &(version, left) in targets {
+        let Some(left_symbol_ref) = find_symbol(Some(left), symbol, section) else {
+            out.push(VersionDiff {
+                version: version.to_string(),
+                target_symbol: None,
+                left: None,
+                right: no_diff_code(right, right_symbol_ref, config)?,
+            });
+            continue;
+        };
+        let (left_diff, right_diff) =
+            diff_code(left, right, left_symbol_ref, right_symbol_ref, config)?;
+        out.push(VersionDiff {
+            version: version.to_string(),
+            target_symbol: Some(left_symbol_ref),
+            left: Some(left_diff),
+            right: right_diff,
+        });
+    }
+    Ok(out)
 }
 
 #[derive(Default)]
@@ -302,6 +655,18 @@ pub fn diff_objs(
     left: Option<&ObjInfo>,
     right: Option<&ObjInfo>,
     prev: Option<&ObjInfo>,
+) -> Result<DiffObjsResult> {
+    diff_objs_with_progress(config, left, right, prev, |_, _| {})
+}
+
+/// [`diff_objs`], reporting the progress of each paired function to `on_progress` along
+/// with its target symbol.
+pub fn diff_objs_with_progress(
+    config: &DiffObjConfig,
+    left: Option<&ObjInfo>,
+    right: Option<&ObjInfo>,
+    prev: Option<&ObjInfo>,
+    mut on_progress: impl FnMut(SymbolRef, DiffProgress),
 ) -> Result<DiffObjsResult> {
     let symbol_matches = matching_symbols(left, right, prev)?;
     let section_matches = matching_sections(left, right)?;
@@ -321,12 +686,13 @@ pub fn diff_objs(
                 let (right_obj, right_out) = right.as_mut().unwrap();
                 match section_kind {
                     ObjSectionKind::Code => {
-                        let (left_diff, right_diff) = diff_code(
+                        let (left_diff, right_diff) = diff_code_with_progress(
                             left_obj,
                             right_obj,
                             left_symbol_ref,
                             right_symbol_ref,
                             config,
+                            &mut |progress| on_progress(left_symbol_ref, progress),
                         )?;
                         *left_out.symbol_diff_mut(left_symbol_ref) = left_diff;
                         *right_out.symbol_diff_mut(right_symbol_ref) = right_diff;
@@ -341,6 +707,21 @@ pub fn diff_objs(
                                 config,
                             )?;
                             *prev_out.symbol_diff_mut(prev_symbol_ref) = prev_diff;
+
+                            // Diff the target against the previous build to find
+                            // instructions that regressed or became matched
+                            let (snapshot_diff, _) = diff_code(
+                                left_obj,
+                                prev_obj,
+                                left_symbol_ref,
+                                prev_symbol_ref,
+                                config,
+                            )?;
+                            diff_prev_state(
+                                left_out.symbol_diff_mut(left_symbol_ref),
+                                right_out.symbol_diff_mut(right_symbol_ref),
+                                &snapshot_diff,
+                            );
                         }
                     }
                     ObjSectionKind::Data => {
@@ -349,6 +730,7 @@ pub fn diff_objs(
                             right_obj,
                             left_symbol_ref,
                             right_symbol_ref,
+                            config,
                         )?;
                         *left_out.symbol_diff_mut(left_symbol_ref) = left_diff;
                         *right_out.symbol_diff_mut(right_symbol_ref) = right_diff;
@@ -364,6 +746,13 @@ pub fn diff_objs(
                         *right_out.symbol_diff_mut(right_symbol_ref) = right_diff;
                     }
                 }
+                let is_weak = |obj: &ObjInfo, symbol_ref: SymbolRef| {
+                    obj.section_symbol(symbol_ref).1.flags.0.contains(ObjSymbolFlags::Weak)
+                };
+                if is_weak(left_obj, left_symbol_ref) != is_weak(right_obj, right_symbol_ref) {
+                    left_out.symbol_diff_mut(left_symbol_ref).weak_mismatch = true;
+                    right_out.symbol_diff_mut(right_symbol_ref).weak_mismatch = true;
+                }
             }
             SymbolMatch { left: Some(left_symbol_ref), right: None, prev: _, section_kind } => {
                 let (left_obj, left_out) = left.as_mut().unwrap();
@@ -422,7 +811,13 @@ pub fn diff_objs(
                     right_out.section_diff_mut(right_section_idx).merge(right_diff);
                 }
                 ObjSectionKind::Data => {
-                    let (left_diff, right_diff) = diff_data_section(left_section, right_section)?;
+                    let (left_diff, right_diff) = diff_data_section(
+                        left_obj,
+                        right_obj,
+                        left_section,
+                        right_section,
+                        config,
+                    )?;
                     left_out.section_diff_mut(left_section_idx).merge(left_diff);
                     right_out.section_diff_mut(right_section_idx).merge(right_diff);
                 }
@@ -435,6 +830,13 @@ pub fn diff_objs(
         }
     }
 
+    if let Some((left_obj, left_out)) = &mut left {
+        annotate_callees(left_obj, left_out);
+    }
+    if let Some((right_obj, right_out)) = &mut right {
+        annotate_callees(right_obj, right_out);
+    }
+
     Ok(DiffObjsResult {
         left: left.map(|(_, o)| o),
         right: right.map(|(_, o)| o),
@@ -442,6 +844,31 @@ pub fn diff_objs(
     })
 }
 
+/// Record the match percentage of each relocation's target function on the instruction.
+/// Must run after every symbol has been diffed.
+fn annotate_callees(obj: &ObjInfo, obj_diff: &mut ObjDiff) {
+    let mut functions = HashMap::new();
+    for (section, section_diff) in obj.sections.iter().zip(&obj_diff.sections) {
+        if section.kind != ObjSectionKind::Code {
+            continue;
+        }
+        for (symbol, symbol_diff) in section.symbols.iter().zip(&section_diff.symbols) {
+            if let Some(match_percent) = symbol_diff.match_percent {
+                functions.insert(symbol.name.clone(), match_percent);
+            }
+        }
+    }
+    for section_diff in &mut obj_diff.sections {
+        for symbol_diff in &mut section_diff.symbols {
+            for ins_diff in &mut symbol_diff.instructions {
+                if let Some(reloc) = ins_diff.ins.as_ref().and_then(|i| i.reloc.as_ref()) {
+                    ins_diff.callee_match = functions.get(&reloc.target.name).copied();
+                }
+            }
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 struct SymbolMatch {
     left: Option<SymbolRef>,
@@ -480,18 +907,6 @@ fn matching_symbols(
                 }
             }
         }
-        for (symbol_idx, symbol) in left.common.iter().enumerate() {
-            let symbol_match = SymbolMatch {
-                left: Some(SymbolRef { section_idx: left.sections.len(), symbol_idx }),
-                right: find_common_symbol(right, symbol),
-                prev: find_common_symbol(prev, symbol),
-                section_kind: ObjSectionKind::Bss,
-            };
-            matches.push(symbol_match);
-            if let Some(right) = symbol_match.right {
-                right_used.insert(right);
-            }
-        }
     }
     if let Some(right) = right {
         for (section_idx, section) in right.sections.iter().enumerate() {
@@ -508,18 +923,6 @@ fn matching_symbols(
                 });
             }
         }
-        for (symbol_idx, symbol) in right.common.iter().enumerate() {
-            let symbol_ref = SymbolRef { section_idx: right.sections.len(), symbol_idx };
-            if right_used.contains(&symbol_ref) {
-                continue;
-            }
-            matches.push(SymbolMatch {
-                left: None,
-                right: Some(symbol_ref),
-                prev: find_common_symbol(prev, symbol),
-                section_kind: ObjSectionKind::Bss,
-            });
-        }
     }
     Ok(matches)
 }
@@ -530,17 +933,26 @@ fn find_symbol(
     in_section: &ObjSection,
 ) -> Option<SymbolRef> {
     let obj = obj?;
-    // Try to find an exact name match
+    // Try to find an exact name match, preferring a strong definition over weak ones
+    let mut weak_match = None;
     for (section_idx, section) in obj.sections.iter().enumerate() {
         if section.kind != in_section.kind {
             continue;
         }
-        if let Some(symbol_idx) =
-            section.symbols.iter().position(|symbol| symbol.name == in_symbol.name)
-        {
-            return Some(SymbolRef { section_idx, symbol_idx });
+        for (symbol_idx, symbol) in section.symbols.iter().enumerate() {
+            if symbol.name != in_symbol.name {
+                continue;
+            }
+            let symbol_ref = SymbolRef { section_idx, symbol_idx };
+            if !symbol.flags.0.contains(ObjSymbolFlags::Weak) {
+                return Some(symbol_ref);
+            }
+            weak_match = weak_match.or(Some(symbol_ref));
         }
     }
+    if weak_match.is_some() {
+        return weak_match;
+    }
     // Match compiler-generated symbols against each other (e.g. @251 -> @60)
     // If they are at the same address in the same section
     if in_symbol.name.starts_with('@')
@@ -559,16 +971,6 @@ fn find_symbol(
     None
 }
 
-fn find_common_symbol(obj: Option<&ObjInfo>, in_symbol: &ObjSymbol) -> Option<SymbolRef> {
-    let obj = obj?;
-    for (symbol_idx, symbol) in obj.common.iter().enumerate() {
-        if symbol.name == in_symbol.name {
-            return Some(SymbolRef { section_idx: obj.sections.len(), symbol_idx });
-        }
-    }
-    None
-}
-
 /// Find matching sections between each object.
 fn matching_sections(left: Option<&ObjInfo>, right: Option<&ObjInfo>) -> Result<Vec<SectionMatch>> {
     let mut matches = Vec::new();
@@ -0,0 +1,217 @@
+//! Disassembly in the layout of GNU `objdump -dr`, for tooling that parses its output.
+//! Instructions come from the same decoding as the diff, so operands use objdiff's syntax
+//! for each architecture, with relocated operands shown as their target symbol.
+
+use std::{cmp::Ordering, io::Write, path::Path};
+
+use anyhow::Result;
+use object::{Architecture, BinaryFormat, Object};
+
+use crate::{
+    arch::ObjArch,
+    diff::{NumberFormat, ObjDiff},
+    obj::{read, ObjInfo, ObjIns, ObjInsArg, ObjReloc, ObjSection, ObjSectionKind, ObjSymbol},
+};
+
+/// Object file properties shown by objdump that aren't kept in [`ObjInfo`].
+#[derive(Debug, Clone)]
+pub struct ObjdumpHeader {
+    /// BFD target name, such as `elf32-powerpc`
+    pub file_format: String,
+    /// Digits of the symbol addresses
+    pub address_width: usize,
+}
+
+impl Default for ObjdumpHeader {
+    fn default() -> Self { Self { file_format: "unknown".to_string(), address_width: 8 } }
+}
+
+impl ObjdumpHeader {
+    /// Read the header properties of an object file, or of an archive member.
+    pub fn read(obj_path: &Path) -> Result<Self> {
+        let (data, _) = read::map_file(&read::archive_file_path(obj_path))?;
+        let file = object::File::parse(read::object_data(&data, obj_path)?)?;
+        Ok(Self::from_object(&file))
+    }
+
+    pub fn from_object(file: &object::File) -> Self {
+        let little = file.is_little_endian();
+        let bits = if file.is_64() { 64 } else { 32 };
+        let file_format = match file.format() {
+            BinaryFormat::Elf => {
+                let arch = match file.architecture() {
+                    Architecture::PowerPc if little => "powerpcle",
+                    Architecture::PowerPc => "powerpc",
+                    Architecture::PowerPc64 if little => "powerpcle",
+                    Architecture::PowerPc64 => "powerpc",
+                    Architecture::Mips | Architecture::Mips64 if little => "littlemips",
+                    Architecture::Mips | Architecture::Mips64 => "bigmips",
+                    Architecture::Arm if little => "littlearm",
+                    Architecture::Arm => "bigarm",
+                    Architecture::Aarch64 if little => "littleaarch64",
+                    Architecture::Aarch64 => "bigaarch64",
+                    Architecture::SuperH if little => "shl",
+                    Architecture::SuperH => "sh",
+                    Architecture::I386 => "i386",
+                    Architecture::X86_64 => "x86-64",
+                    _ => "unknown",
+                };
+                format!("elf{bits}-{arch}")
+            }
+            BinaryFormat::Coff | BinaryFormat::Pe => match file.architecture() {
+                Architecture::X86_64 => "pe-x86-64".to_string(),
+                Architecture::Aarch64 => "pe-aarch64-little".to_string(),
+                _ => "pe-i386".to_string(),
+            },
+            BinaryFormat::MachO => match file.architecture() {
+                Architecture::X86_64 => "mach-o-x86-64".to_string(),
+                Architecture::Aarch64 => "mach-o-arm64".to_string(),
+                Architecture::PowerPc => "mach-o-be".to_string(),
+                _ => "mach-o-le".to_string(),
+            },
+            _ => "unknown".to_string(),
+        };
+        Self { file_format, address_width: if bits == 64 { 16 } else { 8 } }
+    }
+}
+
+/// Options of [`write_objdump`].
+#[derive(Debug, Clone, Default)]
+pub struct ObjdumpOptions {
+    pub header: ObjdumpHeader,
+    /// Print the relocations of each instruction, like `objdump -r`
+    pub relocations: bool,
+    /// Only print this function, like `objdump --disassemble=<symbol>`
+    pub symbol: Option<String>,
+    pub number_format: NumberFormat,
+}
+
+/// Write the disassembly of the code sections of one side of a diff.
+pub fn write_objdump<W: Write>(
+    w: &mut W,
+    obj: &ObjInfo,
+    diff: &ObjDiff,
+    options: &ObjdumpOptions,
+) -> Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{}:     file format {}", obj.path.display(), options.header.file_format)?;
+    writeln!(w)?;
+    for (section, section_diff) in obj.sections.iter().zip(&diff.sections) {
+        if section.kind != ObjSectionKind::Code {
+            continue;
+        }
+        let mut symbols = section
+            .symbols
+            .iter()
+            .zip(&section_diff.symbols)
+            .filter(|(s, _)| options.symbol.as_ref().map_or(true, |name| &s.name == name))
+            .filter(|(_, d)| d.instructions.iter().any(|i| i.ins.is_some()))
+            .collect::<Vec<_>>();
+        if symbols.is_empty() {
+            continue;
+        }
+        symbols.sort_by_key(|(s, _)| s.address);
+        writeln!(w)?;
+        writeln!(w, "Disassembly of section {}:", section.name)?;
+        for (symbol, symbol_diff) in symbols {
+            writeln!(w)?;
+            writeln!(
+                w,
+                "{:0width$x} <{}>:",
+                symbol.address,
+                symbol.name,
+                width = options.header.address_width
+            )?;
+            for ins in symbol_diff.instructions.iter().filter_map(|i| i.ins.as_ref()) {
+                write_ins(w, obj.arch.as_ref(), section, symbol, ins, options)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_ins<W: Write>(
+    w: &mut W,
+    arch: &dyn ObjArch,
+    section: &ObjSection,
+    symbol: &ObjSymbol,
+    ins: &ObjIns,
+    options: &ObjdumpOptions,
+) -> Result<()> {
+    let start = (ins.address - section.address) as usize;
+    let bytes = section
+        .data
+        .get(start..start + ins.size as usize)
+        .unwrap_or_default()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let args = ins_args(section, symbol, ins, options.number_format);
+    if args.is_empty() {
+        writeln!(w, "{:>4x}:\t{bytes} \t{}", ins.address, ins.mnemonic)?;
+    } else {
+        writeln!(w, "{:>4x}:\t{bytes} \t{:<7} {args}", ins.address, ins.mnemonic)?;
+    }
+    if options.relocations {
+        let end = ins.address + ins.size as u64;
+        for reloc in section.relocations.iter().filter(|r| (ins.address..end).contains(&r.address))
+        {
+            writeln!(
+                w,
+                "\t\t\t{:x}: {}\t{}",
+                reloc.address,
+                arch.display_reloc(reloc.flags),
+                reloc_target(reloc)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn reloc_target(reloc: &ObjReloc) -> String {
+    let target = &reloc.target;
+    match target.addend.cmp(&0) {
+        Ordering::Greater => format!("{}+{:#x}", target.name, target.addend),
+        Ordering::Less => format!("{}-{:#x}", target.name, -target.addend),
+        Ordering::Equal => target.name.clone(),
+    }
+}
+
+fn ins_args(
+    section: &ObjSection,
+    symbol: &ObjSymbol,
+    ins: &ObjIns,
+    number_format: NumberFormat,
+) -> String {
+    let mut out = String::new();
+    for arg in &ins.args {
+        match arg {
+            ObjInsArg::PlainText(s) => out.push_str(s),
+            ObjInsArg::Arg(v) => out.push_str(&v.display(number_format).to_string()),
+            ObjInsArg::Reloc => {
+                if let Some(reloc) = &ins.reloc {
+                    out.push_str(&reloc_target(reloc));
+                }
+            }
+            ObjInsArg::BranchDest(dest) => {
+                // objdump names branch targets after the symbol containing them
+                let target = section
+                    .symbols
+                    .iter()
+                    .find(|s| (s.address..s.address + s.size.max(1)).contains(dest))
+                    .unwrap_or(symbol);
+                match dest.checked_sub(target.address) {
+                    Some(0) => out.push_str(&format!("{dest:x} <{}>", target.name)),
+                    Some(offset) => {
+                        out.push_str(&format!("{dest:x} <{}+{offset:#x}>", target.name))
+                    }
+                    None => out.push_str(&format!("{dest:x}")),
+                }
+            }
+            ObjInsArg::Bytes(bytes) => out
+                .push_str(&bytes.iter().map(|b| format!("{b:#04x}")).collect::<Vec<_>>().join(",")),
+        }
+    }
+    out
+}
@@ -0,0 +1,124 @@
+//! Fast check for functions with identical code, which make up most of a mostly matched
+//! project. Identical functions are paired instruction by instruction and reported as
+//! matching, without running the alignment and the per-instruction comparison.
+
+use std::hash::{Hash, Hasher};
+
+use object::RelocationFlags;
+
+use crate::obj::{ObjInfo, ObjSectionKind, SymbolRef};
+
+/// Word-at-a-time multiply-rotate hash, as used by rustc (FxHash). Much faster than
+/// SipHash on code, and collisions are ruled out by comparing the code itself.
+#[derive(Default)]
+struct FoldHasher(u64);
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+impl FoldHasher {
+    #[inline]
+    fn add(&mut self, word: u64) { self.0 = (self.0.rotate_left(5) ^ word).wrapping_mul(SEED); }
+}
+
+impl Hasher for FoldHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let mut word = [0u8; 8];
+            word[..rest.len()].copy_from_slice(rest);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u64(&mut self, i: u64) { self.add(i) }
+
+    fn write_usize(&mut self, i: usize) { self.add(i as u64) }
+
+    fn finish(&self) -> u64 { self.0 }
+}
+
+/// A relocation, relative to the start of its function.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct RelocKey<'a> {
+    offset: u64,
+    flags: RelocationFlags,
+    target: &'a str,
+    addend: i64,
+    target_section: Option<&'a str>,
+}
+
+/// The code of a function with relocated bytes zeroed, and its relocations by target.
+/// Two functions with equal keys disassemble and compare identically.
+#[derive(Debug, Clone)]
+pub struct CodeKey<'a> {
+    hash: u64,
+    bytes: Vec<u8>,
+    relocs: Vec<RelocKey<'a>>,
+}
+
+impl PartialEq for CodeKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        // Differing functions almost always differ in hash
+        self.hash == other.hash && self.bytes == other.bytes && self.relocs == other.relocs
+    }
+}
+
+impl Eq for CodeKey<'_> {}
+
+impl Hash for CodeKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) { state.write_u64(self.hash) }
+}
+
+impl CodeKey<'_> {
+    /// Hash of the masked code and relocations, for grouping functions by code.
+    pub fn code_hash(&self) -> u64 { self.hash }
+}
+
+/// Build the comparison key of a function, or `None` if it isn't sized code.
+pub fn code_key(obj: &ObjInfo, symbol_ref: SymbolRef) -> Option<CodeKey> {
+    let (section, symbol) = obj.section_symbol(symbol_ref);
+    let section = section.filter(|s| s.kind == ObjSectionKind::Code)?;
+    let start = symbol.section_address;
+    let end = start + symbol.size;
+    let mut bytes = section.data.get(start as usize..end as usize)?.to_vec();
+    let mut relocs = Vec::new();
+    for reloc in section.relocations.iter().filter(|r| (start..end).contains(&r.address)) {
+        let offset = reloc.address - start;
+        let size = obj.arch.get_reloc_byte_size(reloc.flags);
+        let mask_end = (offset as usize + size).min(bytes.len());
+        bytes[offset as usize..mask_end].fill(0);
+        relocs.push(RelocKey {
+            offset,
+            flags: reloc.flags,
+            target: &reloc.target.name,
+            addend: reloc.target.addend,
+            target_section: reloc.target_section.as_deref(),
+        });
+    }
+    let mut hasher = FoldHasher::default();
+    hasher.write(&bytes);
+    relocs.hash(&mut hasher);
+    Some(CodeKey { hash: hasher.finish(), bytes, relocs })
+}
+
+/// Whether two functions have identical code, comparing relocations by target.
+pub fn identical_code(
+    left_obj: &ObjInfo,
+    left_symbol_ref: SymbolRef,
+    right_obj: &ObjInfo,
+    right_symbol_ref: SymbolRef,
+) -> bool {
+    let left_size = left_obj.section_symbol(left_symbol_ref).1.size;
+    let right_size = right_obj.section_symbol(right_symbol_ref).1.size;
+    if left_size != right_size {
+        return false;
+    }
+    match (code_key(left_obj, left_symbol_ref), code_key(right_obj, right_symbol_ref)) {
+        (Some(left), Some(right)) => left == right,
+        _ => false,
+    }
+}
@@ -0,0 +1,161 @@
+//! Function signature inference: compares the argument registers that two functions read
+//! before writing them, which points at mismatched parameter lists and float/int mixups.
+
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    arch::RegisterClass,
+    diff::{frame::ins_values, ObjSymbolDiff},
+    obj::{ObjInfo, ObjIns, ObjInsArgValue},
+};
+
+/// An argument register of a calling convention.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgRegister {
+    pub name: String,
+    pub class: RegisterClass,
+    /// Position among the argument registers of its class
+    pub index: usize,
+}
+
+/// Arguments of a function, inferred from the argument registers it reads before writing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Signature {
+    /// Argument registers read before being written, in order of first use
+    pub args: Vec<ArgRegister>,
+}
+
+impl Signature {
+    /// Number of arguments of a register class. Arguments are assigned in order, so this
+    /// counts unused arguments before the last one that's read.
+    pub fn arg_count(&self, class: RegisterClass) -> usize {
+        self.args.iter().filter(|a| a.class == class).map(|a| a.index + 1).max().unwrap_or(0)
+    }
+}
+
+/// How an instruction uses its register operands.
+pub enum RegisterUse {
+    /// The first register operand is written and the others are read
+    Default,
+    /// All register operands are read, as by stores, compares and branches
+    ReadAll,
+    /// The first register operand is both read and written, and the others are read
+    ReadModify,
+    /// The first register operand is read and the second is written, as by moves to
+    /// coprocessor registers
+    WriteSecond,
+    /// A call, which clobbers the argument registers
+    Call,
+}
+
+/// Infers a function's arguments from its instructions, looking up argument registers
+/// with `arg`. The instructions are scanned in order without following branches, so reads
+/// on one path of a branch can be masked by writes on the other. The scan ends at the first
+/// call, which clobbers the argument registers.
+pub fn infer_signature<'a>(
+    insts: &[&'a ObjIns],
+    arg: impl Fn(&str) -> Option<(RegisterClass, usize)>,
+    register_use: impl Fn(&ObjIns) -> RegisterUse,
+) -> Signature {
+    let mut signature = Signature::default();
+    // Registers holding values set by the function itself
+    let mut written = HashSet::<&str>::new();
+    for &ins in insts {
+        let registers = ins_values(ins)
+            .into_iter()
+            .filter_map(|value| match value {
+                ObjInsArgValue::Opaque(name) => Some(name.as_ref()),
+                _ => None,
+            })
+            .collect::<Vec<&'a str>>();
+        let first = registers.len().min(1);
+        let (reads, writes) = match register_use(ins) {
+            RegisterUse::Default => (&registers[first..], &registers[..first]),
+            RegisterUse::ReadAll => (&registers[..], &[][..]),
+            RegisterUse::ReadModify => (&registers[..], &registers[..first]),
+            RegisterUse::WriteSecond => {
+                (&registers[..first], registers.get(1..2).unwrap_or_default())
+            }
+            RegisterUse::Call => break,
+        };
+        for &name in reads {
+            if written.contains(name) || signature.args.iter().any(|a| a.name == name) {
+                continue;
+            }
+            if let Some((class, index)) = arg(name) {
+                signature.args.push(ArgRegister { name: name.to_string(), class, index });
+            }
+        }
+        written.extend(writes);
+    }
+    signature
+}
+
+/// A difference between the target (left) and base (right) signatures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureDifference {
+    Count {
+        class: RegisterClass,
+        left: usize,
+        right: usize,
+    },
+    /// Argument register read by only one side; `left` is true if only the target reads it
+    Argument {
+        register: String,
+        left: bool,
+    },
+}
+
+fn class_name(class: RegisterClass) -> &'static str {
+    match class {
+        RegisterClass::Gpr => "integer",
+        RegisterClass::Fpr => "float",
+        RegisterClass::Vector => "vector",
+    }
+}
+
+impl fmt::Display for SignatureDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureDifference::Count { class, left, right } => write!(
+                f,
+                "target takes {left} {} arguments, base takes {right}",
+                class_name(*class)
+            ),
+            SignatureDifference::Argument { register, left: true } => {
+                write!(f, "target reads {register} as an argument, base does not")
+            }
+            SignatureDifference::Argument { register, left: false } => {
+                write!(f, "base reads {register} as an argument, target does not")
+            }
+        }
+    }
+}
+
+/// Infers the signature of a diffed function, if the architecture supports it.
+pub fn symbol_signature(obj: &ObjInfo, diff: &ObjSymbolDiff) -> Option<Signature> {
+    let insts = diff.instructions.iter().filter_map(|d| d.ins.as_ref()).collect::<Vec<_>>();
+    obj.arch.signature(&insts)
+}
+
+/// Lists the differences between the target (left) and base (right) signatures.
+pub fn compare_signatures(left: &Signature, right: &Signature) -> Vec<SignatureDifference> {
+    let mut out = vec![];
+    for class in [RegisterClass::Gpr, RegisterClass::Fpr, RegisterClass::Vector] {
+        let (left_count, right_count) = (left.arg_count(class), right.arg_count(class));
+        if left_count != right_count {
+            out.push(SignatureDifference::Count { class, left: left_count, right: right_count });
+        }
+    }
+    for arg in &left.args {
+        if !right.args.iter().any(|a| a.name == arg.name) {
+            out.push(SignatureDifference::Argument { register: arg.name.clone(), left: true });
+        }
+    }
+    for arg in &right.args {
+        if !left.args.iter().any(|a| a.name == arg.name) {
+            out.push(SignatureDifference::Argument { register: arg.name.clone(), left: false });
+        }
+    }
+    out
+}
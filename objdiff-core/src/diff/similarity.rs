@@ -0,0 +1,202 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    time::{Duration, Instant},
+};
+
+use similar::{capture_diff_slices_deadline, Algorithm, DiffTag};
+
+use crate::{
+    diff::DiffObjConfig,
+    obj::{ObjInfo, ObjSectionKind, SymbolRef},
+};
+
+/// A summary of a function's code used to find similar functions across objects.
+#[derive(Debug, Clone)]
+pub struct FunctionFingerprint {
+    pub symbol_ref: SymbolRef,
+    /// Opcode sequence, as produced by [`ObjArch::process_code`](crate::arch::ObjArch::process_code)
+    pub ops: Vec<u16>,
+    /// Hash of the function's control flow: branch targets relative to the
+    /// instruction index, and the positions of relocated instructions.
+    pub structure_hash: u64,
+}
+
+/// A suggested pairing between a left and right function.
+#[derive(Debug, Copy, Clone)]
+pub struct SymbolPairing {
+    pub left: SymbolRef,
+    pub right: SymbolRef,
+    /// Similarity score, from 0.0 to 1.0
+    pub confidence: f32,
+}
+
+/// Number of candidates of closest length compared against each function, in addition to
+/// those with the same structure.
+const MAX_CANDIDATES: usize = 16;
+
+/// Fingerprint every sized function in the object. Functions that fail to decode are
+/// skipped.
+pub fn fingerprint_functions(obj: &ObjInfo, config: &DiffObjConfig) -> Vec<FunctionFingerprint> {
+    let mut out = Vec::new();
+    for (section_idx, section) in obj.sections.iter().enumerate() {
+        if section.kind != ObjSectionKind::Code {
+            continue;
+        }
+        for (symbol_idx, symbol) in section.symbols.iter().enumerate() {
+            if symbol.size == 0 {
+                continue;
+            }
+            let symbol_ref = SymbolRef { section_idx, symbol_idx };
+            let code = match obj.arch.process_code(obj, symbol_ref, config) {
+                Ok(code) => code,
+                Err(e) => {
+                    log::warn!("Skipping {} in similarity search: {e:#}", symbol.name);
+                    continue;
+                }
+            };
+            let indices = code
+                .insts
+                .iter()
+                .enumerate()
+                .map(|(idx, ins)| (ins.address, idx))
+                .collect::<HashMap<_, _>>();
+            let mut hasher = DefaultHasher::new();
+            for (idx, ins) in code.insts.iter().enumerate() {
+                if let Some(dest) = ins.branch_dest {
+                    let dest_idx = indices.get(&dest);
+                    (idx, dest_idx.map(|&d| d as isize - idx as isize)).hash(&mut hasher);
+                }
+                if ins.reloc.is_some() {
+                    idx.hash(&mut hasher);
+                }
+            }
+            code.insts.len().hash(&mut hasher);
+            out.push(FunctionFingerprint {
+                symbol_ref,
+                ops: code.ops,
+                structure_hash: hasher.finish(),
+            });
+        }
+    }
+    out
+}
+
+/// Score the similarity of two fingerprints, from 0.0 to 1.0.
+pub fn fingerprint_similarity(left: &FunctionFingerprint, right: &FunctionFingerprint) -> f32 {
+    let total = left.ops.len() + right.ops.len();
+    if total == 0 {
+        return 0.0;
+    }
+    let deadline = Instant::now() + Duration::from_millis(50);
+    let ops =
+        capture_diff_slices_deadline(Algorithm::Patience, &left.ops, &right.ops, Some(deadline));
+    let matched: usize =
+        ops.iter().filter(|op| op.tag() == DiffTag::Equal).map(|op| op.old_range().len()).sum();
+    let sequence_score = (matched * 2) as f32 / total as f32;
+    let structure_score = if left.structure_hash == right.structure_hash { 1.0 } else { 0.0 };
+    sequence_score * 0.8 + structure_score * 0.2
+}
+
+/// Suggest pairings between functions that weren't paired by name, for objects
+/// with stripped or autogenerated symbol names. Each function is paired at most
+/// once, preferring the highest scoring candidates. Suggestions can be applied
+/// by adding them to the unit's [`SymbolMappings`](crate::obj::SymbolMappings).
+///
+/// Each function is only compared against the functions of the other object with the same
+/// structure, and the [`MAX_CANDIDATES`] closest in length that can reach `min_confidence`.
+pub fn suggest_pairings(
+    left: &ObjInfo,
+    right: &ObjInfo,
+    config: &DiffObjConfig,
+    min_confidence: f32,
+) -> Vec<SymbolPairing> {
+    let left_names = function_names(left);
+    let right_names = function_names(right);
+    let left_prints: Vec<_> = fingerprint_functions(left, config)
+        .into_iter()
+        .filter(|f| !right_names.contains(left.section_symbol(f.symbol_ref).1.name.as_str()))
+        .collect();
+    let mut right_prints: Vec<_> = fingerprint_functions(right, config)
+        .into_iter()
+        .filter(|f| !left_names.contains(right.section_symbol(f.symbol_ref).1.name.as_str()))
+        .collect();
+    right_prints.sort_by_key(|f| f.ops.len());
+    let mut by_structure = HashMap::<u64, Vec<usize>>::new();
+    for (idx, f) in right_prints.iter().enumerate() {
+        by_structure.entry(f.structure_hash).or_default().push(idx);
+    }
+
+    let mut candidates = Vec::new();
+    for l in &left_prints {
+        let mut compared = HashSet::new();
+        let same_structure = by_structure.get(&l.structure_hash).into_iter().flatten().copied();
+        for idx in same_structure.chain(closest_lengths(&right_prints, l.ops.len())) {
+            let r = &right_prints[idx];
+            if !compared.insert(idx) || max_similarity(l, r) < min_confidence {
+                continue;
+            }
+            let confidence = fingerprint_similarity(l, r);
+            if confidence >= min_confidence {
+                candidates.push(SymbolPairing {
+                    left: l.symbol_ref,
+                    right: r.symbol_ref,
+                    confidence,
+                });
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+    let mut left_used = HashSet::new();
+    let mut right_used = HashSet::new();
+    let mut pairings = Vec::new();
+    for pairing in candidates {
+        if left_used.contains(&pairing.left) || right_used.contains(&pairing.right) {
+            continue;
+        }
+        left_used.insert(pairing.left);
+        right_used.insert(pairing.right);
+        pairings.push(pairing);
+    }
+    pairings
+}
+
+/// The highest score two fingerprints can reach, based on their lengths and structure.
+fn max_similarity(left: &FunctionFingerprint, right: &FunctionFingerprint) -> f32 {
+    let (min, max) = if left.ops.len() < right.ops.len() {
+        (left.ops.len(), right.ops.len())
+    } else {
+        (right.ops.len(), left.ops.len())
+    };
+    let structure_score = if left.structure_hash == right.structure_hash { 1.0 } else { 0.0 };
+    (min * 2) as f32 / (min + max).max(1) as f32 * 0.8 + structure_score * 0.2
+}
+
+/// Indices of up to [`MAX_CANDIDATES`] fingerprints closest to `len` in length, from
+/// fingerprints sorted by length.
+fn closest_lengths(prints: &[FunctionFingerprint], len: usize) -> Vec<usize> {
+    let split = prints.partition_point(|f| f.ops.len() < len);
+    let (mut lo, mut hi) = (split, split);
+    while hi - lo < MAX_CANDIDATES && (lo > 0 || hi < prints.len()) {
+        let take_lo = match (lo.checked_sub(1), prints.get(hi)) {
+            (Some(below), Some(above)) => len - prints[below].ops.len() <= above.ops.len() - len,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if take_lo {
+            lo -= 1;
+        } else {
+            hi += 1;
+        }
+    }
+    (lo..hi).collect()
+}
+
+fn function_names(obj: &ObjInfo) -> HashSet<&str> {
+    obj.sections
+        .iter()
+        .filter(|s| s.kind == ObjSectionKind::Code)
+        .flat_map(|s| s.symbols.iter().map(|s| s.name.as_str()))
+        .collect()
+}
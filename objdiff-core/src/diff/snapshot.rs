@@ -0,0 +1,118 @@
+//! Stable text serialization of the display events of a unit diff, for golden snapshot
+//! tests of the diff engine and display code. Unlike the text export, every [`DiffText`]
+//! event is written with all of its data, so that changes to diff kinds, argument diffs or
+//! relocation comparisons show up in the snapshot even when the rendered text stays the same.
+
+use std::fmt::Write;
+
+use crate::{
+    diff::{
+        display::{display_diff, function_pairs, DiffText, SymbolDiffRef},
+        NumberFormat, ObjDiff, ObjInsDiff,
+    },
+    obj::ObjInfo,
+};
+
+/// Serialize the diff of every function pair of a unit, target (left) and base (right).
+pub fn unit_snapshot(
+    left: Option<(&ObjInfo, &ObjDiff)>,
+    right: Option<(&ObjInfo, &ObjDiff)>,
+) -> String {
+    let mut out = String::new();
+    for (i, (left, right)) in function_pairs(left, right).into_iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&function_snapshot(left, right));
+    }
+    out
+}
+
+/// Serialize the diff of a function pair. Each row is written as a line per side, `<` for
+/// the target and `>` for the base, starting with the row's diff kind.
+pub fn function_snapshot(left: Option<SymbolDiffRef>, right: Option<SymbolDiffRef>) -> String {
+    let mut out = String::new();
+    let percent = left.or(right).and_then(|(_, _, d)| d.match_percent);
+    let percent = percent.map(|p| format!(" {p:.2}%")).unwrap_or_default();
+    writeln!(out, "## {} {}{percent}", side_name(left), side_name(right)).unwrap();
+    let rows = |side: Option<SymbolDiffRef>| side.map_or(0, |(_, _, d)| d.instructions.len());
+    for row in 0..rows(left).max(rows(right)) {
+        for (marker, side) in [('<', left), ('>', right)] {
+            let Some((_, symbol, diff)) = side else {
+                continue;
+            };
+            if let Some(ins_diff) = diff.instructions.get(row) {
+                writeln!(out, "{marker} {}", row_events(ins_diff, symbol.address)).unwrap();
+            }
+        }
+    }
+    out
+}
+
+fn side_name<'a>(side: Option<SymbolDiffRef<'a>>) -> &'a str {
+    side.map_or("-", |(_, symbol, _)| symbol.name.as_str())
+}
+
+/// Serialize the display events of an instruction row.
+pub fn row_events(ins_diff: &ObjInsDiff, base_addr: u64) -> String {
+    let mut events = vec![format!("{:?}", ins_diff.kind)];
+    if ins_diff.padding {
+        events.push("padding".to_string());
+    }
+    display_diff(ins_diff, base_addr, |text| {
+        if !matches!(text, DiffText::Eol) {
+            events.push(event_text(text));
+        }
+        Ok::<_, ()>(())
+    })
+    .unwrap();
+    events.join(" ")
+}
+
+fn hex(bytes: &[u8]) -> String { bytes.iter().map(|b| format!("{b:02x}")).collect() }
+
+/// Serialize a single display event.
+pub fn event_text(text: DiffText) -> String {
+    match text {
+        DiffText::Basic(text) => format!("{text:?}"),
+        DiffText::Styled(text, kind) => format!("styled({text:?}, {kind:?})"),
+        DiffText::Line(line) => format!("line({line})"),
+        DiffText::Address(addr) => format!("addr({addr:#x})"),
+        DiffText::Encoding(encoding) => {
+            format!("encoding({}, {:#x})", hex(&encoding.bytes), encoding.mismatch)
+        }
+        DiffText::Opcode(mnemonic, op) => format!("opcode({mnemonic}, {op})"),
+        DiffText::Argument(arg, diff) => {
+            let arg = arg.display(NumberFormat::default());
+            match diff {
+                Some(diff) => match diff.reloc {
+                    Some(reloc) => format!("arg({arg}, {:?} {}, {reloc:?})", diff.kind, diff.idx),
+                    None => format!("arg({arg}, {:?} {})", diff.kind, diff.idx),
+                },
+                None => format!("arg({arg})"),
+            }
+        }
+        DiffText::BranchDest(addr, Some(label)) => format!("dest({addr:#x}, .L{label})"),
+        DiffText::BranchDest(addr, None) => format!("dest({addr:#x})"),
+        DiffText::Label(label, idx) => format!("label(.L{label}, {idx})"),
+        DiffText::Moved(block) => format!("moved({block})"),
+        DiffText::Accepted => "accepted".to_string(),
+        DiffText::Bytes(bytes, diff) => {
+            format!("bytes({}, {:#x})", hex(bytes), diff.map_or(0, |d| d.bytes))
+        }
+        DiffText::Symbol(symbol, reloc_diff, callee_match) => {
+            let mut out = format!("symbol({}", symbol.name);
+            if let Some(reloc_diff) = reloc_diff {
+                write!(out, ", {reloc_diff:?}").unwrap();
+            }
+            if let Some(callee_match) = callee_match {
+                write!(out, ", {callee_match:.2}%").unwrap();
+            }
+            out.push(')');
+            out
+        }
+        DiffText::Comment(text) => format!("comment({text:?})"),
+        DiffText::Spacing(count) => format!("sp({count})"),
+        DiffText::Eol => "eol".to_string(),
+    }
+}
@@ -0,0 +1,242 @@
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::{
+    diff::{
+        display::{display_diff, function_pairs, DiffText, SymbolDiffRef},
+        NumberFormat, ObjDiff, ObjInsDiff, ObjInsDiffKind,
+    },
+    obj::ObjInfo,
+};
+
+/// Plain text diff layout.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TextDiffFormat {
+    /// Target and base disassembly in two columns
+    SideBySide,
+    /// Unified diff from the target to the base, with context lines
+    Unified,
+}
+
+/// Number of unchanged lines shown around each hunk of a unified diff.
+const UNIFIED_CONTEXT: usize = 3;
+
+/// Minimum column width of a side-by-side diff.
+const MIN_COLUMN_WIDTH: usize = 40;
+
+/// Write a plain text diff of a single function.
+pub fn write_function_text<W: Write>(
+    w: &mut W,
+    format: TextDiffFormat,
+    number_format: NumberFormat,
+    left: Option<SymbolDiffRef>,
+    right: Option<SymbolDiffRef>,
+) -> Result<()> {
+    match format {
+        TextDiffFormat::SideBySide => write_side_by_side(w, number_format, left, right),
+        TextDiffFormat::Unified => write_unified(w, number_format, left, right),
+    }
+}
+
+/// Write a plain text diff of every function in a unit.
+pub fn write_unit_text<W: Write>(
+    w: &mut W,
+    format: TextDiffFormat,
+    number_format: NumberFormat,
+    left: Option<(&ObjInfo, &ObjDiff)>,
+    right: Option<(&ObjInfo, &ObjDiff)>,
+) -> Result<()> {
+    for (i, (left, right)) in function_pairs(left, right).into_iter().enumerate() {
+        if i > 0 && format == TextDiffFormat::SideBySide {
+            writeln!(w)?;
+        }
+        write_function_text(w, format, number_format, left, right)?;
+    }
+    Ok(())
+}
+
+/// Render an instruction as plain text, padded the same way as the function diff view.
+pub fn ins_text(ins_diff: &ObjInsDiff, base_addr: u64, number_format: NumberFormat) -> String {
+    let mut out = String::new();
+    display_diff(ins_diff, base_addr, |text| {
+        let mut pad_to = 0;
+        let label = match text {
+            DiffText::Basic(text) | DiffText::Styled(text, _) => text.to_string(),
+            DiffText::Line(num) => {
+                pad_to = 5;
+                format!("{num} ")
+            }
+            DiffText::Address(addr) => {
+                pad_to = 5;
+                format!("{addr:x}:")
+            }
+            DiffText::Encoding(encoding) => {
+                pad_to = 13;
+                format!(" {}", hex_bytes(&encoding.bytes))
+            }
+            DiffText::Opcode(mnemonic, _op) => {
+                pad_to = 8;
+                mnemonic.to_string()
+            }
+            DiffText::Argument(arg, _) => arg.display(number_format).to_string(),
+            DiffText::BranchDest(_, Some(label)) => format!(".L{label}"),
+            DiffText::BranchDest(addr, None) => format!("{addr:x}"),
+            DiffText::Label(label, _) => {
+                pad_to = 4;
+                format!(".L{label}")
+            }
+            DiffText::Bytes(bytes, _) => hex_bytes(bytes),
+            DiffText::Moved(_) => " <> ".to_string(),
+            DiffText::Accepted => " ok ".to_string(),
+            DiffText::Symbol(sym, _, _) => sym.display_name(true).to_string(),
+            DiffText::Comment(text) => format!(" # {text}"),
+            DiffText::Spacing(n) => " ".repeat(n),
+            DiffText::Eol => return Ok(()),
+        };
+        out.push_str(&label);
+        if label.len() < pad_to {
+            out.push_str(&" ".repeat(pad_to - label.len()));
+        }
+        Ok::<_, ()>(())
+    })
+    .unwrap();
+    out
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect::<Vec<_>>().join(" ")
+}
+
+fn function_title(left: Option<SymbolDiffRef>, right: Option<SymbolDiffRef>) -> String {
+    let Some((_, symbol, symbol_diff)) = left.or(right) else {
+        return String::new();
+    };
+    match symbol_diff.match_percent {
+        Some(percent) => format!("{} ({percent:.2}%)", symbol.display_name(true)),
+        None => symbol.display_name(true).to_string(),
+    }
+}
+
+/// Rendered rows of one side of a function diff. Rows without an instruction are `None`.
+fn side_rows(side: Option<SymbolDiffRef>, number_format: NumberFormat) -> Vec<Option<String>> {
+    let Some((_, symbol, symbol_diff)) = side else {
+        return Vec::new();
+    };
+    symbol_diff
+        .instructions
+        .iter()
+        .map(|ins_diff| {
+            ins_diff.ins.as_ref().map(|_| ins_text(ins_diff, symbol.address, number_format))
+        })
+        .collect()
+}
+
+fn row_diff(side: Option<SymbolDiffRef>, row: usize) -> Option<&ObjInsDiff> {
+    side.and_then(|(_, _, d)| d.instructions.get(row))
+}
+
+fn write_side_by_side<W: Write>(
+    w: &mut W,
+    number_format: NumberFormat,
+    left: Option<SymbolDiffRef>,
+    right: Option<SymbolDiffRef>,
+) -> Result<()> {
+    let left_rows = side_rows(left, number_format);
+    let right_rows = side_rows(right, number_format);
+    let width =
+        left_rows.iter().flatten().map(|s| s.len()).max().unwrap_or(0).max(MIN_COLUMN_WIDTH);
+    writeln!(w, "{}", function_title(left, right))?;
+    for row in 0..left_rows.len().max(right_rows.len()) {
+        let marker = match row_diff(left, row).or(row_diff(right, row)) {
+            Some(ins_diff) if ins_diff.accepted => '=',
+            Some(ins_diff) => match ins_diff.kind {
+                ObjInsDiffKind::None => ' ',
+                ObjInsDiffKind::Delete => '<',
+                ObjInsDiffKind::Insert => '>',
+                _ => '|',
+            },
+            None => ' ',
+        };
+        let left_text = left_rows.get(row).cloned().flatten().unwrap_or_default();
+        let right_text = right_rows.get(row).cloned().flatten().unwrap_or_default();
+        let line = format!("{left_text:<width$} {marker} {right_text}");
+        writeln!(w, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+fn write_unified<W: Write>(
+    w: &mut W,
+    number_format: NumberFormat,
+    left: Option<SymbolDiffRef>,
+    right: Option<SymbolDiffRef>,
+) -> Result<()> {
+    let left_rows = side_rows(left, number_format);
+    let right_rows = side_rows(right, number_format);
+    let rows = left_rows.len().max(right_rows.len());
+    // Functions only present on one side are entirely changed
+    let one_sided = left.is_none() || right.is_none();
+    let changed = (0..rows)
+        .map(|row| {
+            one_sided
+                || row_diff(left, row)
+                    .or(row_diff(right, row))
+                    .map_or(true, |d| d.kind != ObjInsDiffKind::None)
+        })
+        .collect::<Vec<_>>();
+    let left_name = left.map(|(_, s, _)| s.display_name(true)).unwrap_or("/dev/null");
+    let right_name = right.map(|(_, s, _)| s.display_name(true)).unwrap_or("/dev/null");
+    writeln!(w, "--- a/{left_name}")?;
+    writeln!(w, "+++ b/{right_name}")?;
+
+    // Group changed rows into hunks, merging hunks whose context overlaps
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for row in (0..rows).filter(|&row| changed[row]) {
+        let start = row.saturating_sub(UNIFIED_CONTEXT);
+        let end = (row + 1 + UNIFIED_CONTEXT).min(rows);
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    // Line numbers of the first row of each side
+    let line_before = |rows: &[Option<String>], row: usize| {
+        rows.iter().take(row).filter(|r| r.is_some()).count() + 1
+    };
+    for (start, end) in hunks {
+        let mut lines = Vec::new();
+        let (mut left_count, mut right_count) = (0, 0);
+        for (row, &row_changed) in changed.iter().enumerate().take(end).skip(start) {
+            let left_text = left_rows.get(row).cloned().flatten();
+            let right_text = right_rows.get(row).cloned().flatten();
+            if !row_changed {
+                if let Some(text) = left_text.or(right_text) {
+                    lines.push(format!(" {text}"));
+                    left_count += 1;
+                    right_count += 1;
+                }
+                continue;
+            }
+            if let Some(text) = left_text {
+                lines.push(format!("-{text}"));
+                left_count += 1;
+            }
+            if let Some(text) = right_text {
+                lines.push(format!("+{text}"));
+                right_count += 1;
+            }
+        }
+        writeln!(
+            w,
+            "@@ -{},{left_count} +{},{right_count} @@",
+            line_before(&left_rows, start),
+            line_before(&right_rows, start)
+        )?;
+        for line in lines {
+            writeln!(w, "{}", line.trim_end())?;
+        }
+    }
+    Ok(())
+}
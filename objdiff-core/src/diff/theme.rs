@@ -0,0 +1,293 @@
+//! Semantic token kinds of the function diff display, and the color themes that map them
+//! to colors. Frontends color [`DiffText`] through a [`Theme`] so that the GUI, the TUI
+//! and the HTML export look the same, and users can customize them in one place.
+
+use std::fmt;
+
+use crate::{
+    diff::{
+        display::DiffText, ObjInsArgDiff, ObjInsArgDiffKind, ObjInsDiff, ObjInsDiffKind,
+        ObjInsEncoding, ObjInsPrevState, RelocDiffKind,
+    },
+    obj::ObjInsArgValue,
+};
+
+/// The role of a piece of diff text, which determines its color.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TokenKind {
+    /// Text without a particular role, colored by the kind of its row
+    Text,
+    /// Source line number
+    Line,
+    /// Instruction address
+    Address,
+    /// Address of an instruction that matched in the previous build but no longer does
+    Regressed,
+    /// Address of an instruction that didn't match in the previous build but now does
+    NewlyMatched,
+    /// Instruction mnemonic
+    Opcode,
+    /// Mnemonic of a branch or call
+    BranchOpcode,
+    /// Mnemonic that differs from the other side
+    OpcodeMismatch,
+    /// Register operand
+    Register,
+    /// Immediate operand
+    Immediate,
+    /// Branch destination
+    BranchDest,
+    /// Relocation target symbol
+    Symbol,
+    /// Relocation target with a different name but the same address as the other side
+    SymbolSameAddress,
+    /// Relocation target that differs from the other side
+    SymbolMismatch,
+    /// Call target that fully matches
+    SymbolMatched,
+    /// Register consistently swapped with another register on the other side
+    Swap,
+    /// Byte that differs from the other side
+    ByteMismatch,
+    /// Instruction encoding byte that matches the other side
+    Encoding,
+    /// Entry of the color rotation, used to tell apart mismatched arguments, branch labels
+    /// and moved blocks
+    Rotation(usize),
+    /// User note
+    Comment,
+}
+
+/// The token kind of a piece of diff text in an instruction row. The bytes of
+/// [`DiffText::Bytes`] and [`DiffText::Encoding`] are classified individually by
+/// [`byte_kind`] and [`encoding_byte_kind`].
+pub fn token_kind(text: &DiffText, ins_diff: &ObjInsDiff) -> TokenKind {
+    match *text {
+        DiffText::Basic(_)
+        | DiffText::Bytes(_, _)
+        | DiffText::Encoding(_)
+        | DiffText::Accepted
+        | DiffText::Spacing(_)
+        | DiffText::Eol => TokenKind::Text,
+        DiffText::Styled(_, kind) => kind,
+        DiffText::Line(_) => TokenKind::Line,
+        DiffText::Address(_) => match ins_diff.prev_state {
+            ObjInsPrevState::Unchanged => TokenKind::Address,
+            ObjInsPrevState::Regressed => TokenKind::Regressed,
+            ObjInsPrevState::NewlyMatched => TokenKind::NewlyMatched,
+        },
+        DiffText::Opcode(_, _) => {
+            if ins_diff.kind == ObjInsDiffKind::OpMismatch {
+                TokenKind::OpcodeMismatch
+            } else if ins_diff.ins.as_ref().is_some_and(|ins| ins.branch_dest.is_some()) {
+                TokenKind::BranchOpcode
+            } else {
+                TokenKind::Opcode
+            }
+        }
+        DiffText::Argument(arg, diff) => match diff {
+            Some(diff) if diff.kind == ObjInsArgDiffKind::RegisterSwap => TokenKind::Swap,
+            Some(diff) => TokenKind::Rotation(diff.idx),
+            None if matches!(arg, ObjInsArgValue::Opaque(_)) => TokenKind::Register,
+            None => TokenKind::Immediate,
+        },
+        DiffText::BranchDest(_, _) => TokenKind::BranchDest,
+        DiffText::Label(_, idx) | DiffText::Moved(idx) => TokenKind::Rotation(idx),
+        DiffText::Symbol(_, reloc_diff, callee_match) => match (reloc_diff, callee_match) {
+            (Some(RelocDiffKind::SameAddress), _) => TokenKind::SymbolSameAddress,
+            (Some(RelocDiffKind::Mismatch), _) => TokenKind::SymbolMismatch,
+            (_, Some(p)) if p >= 100.0 => TokenKind::SymbolMatched,
+            _ => TokenKind::Symbol,
+        },
+        DiffText::Comment(_) => TokenKind::Comment,
+    }
+}
+
+/// The token kind of byte `i` of [`DiffText::Bytes`].
+pub fn byte_kind(diff: Option<&ObjInsArgDiff>, i: usize) -> TokenKind {
+    if diff.is_some_and(|d| d.byte_mismatch(i)) {
+        TokenKind::ByteMismatch
+    } else {
+        TokenKind::Text
+    }
+}
+
+/// The token kind of byte `i` of [`DiffText::Encoding`].
+pub fn encoding_byte_kind(encoding: &ObjInsEncoding, i: usize) -> TokenKind {
+    if encoding.byte_mismatch(i) {
+        TokenKind::ByteMismatch
+    } else {
+        TokenKind::Encoding
+    }
+}
+
+/// An sRGB color, written as `#rrggbb`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self { Self { r, g, b } }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl std::str::FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        let value = u32::from_str_radix(hex, 16).ok().filter(|_| hex.len() == 6);
+        let Some(value) = value else {
+            return Err(format!("Invalid color: {s}"));
+        };
+        Ok(Self::rgb((value >> 16) as u8, (value >> 8) as u8, value as u8))
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> { value.parse() }
+}
+
+impl From<Color> for String {
+    fn from(value: Color) -> Self { value.to_string() }
+}
+
+/// Maps token kinds to colors. Syntax colors that are unset inherit the color of the row.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Theme {
+    pub background: Color,
+    pub text: Color,
+    pub emphasized: Color,
+    pub deemphasized: Color,
+    pub replace: Color,
+    pub insert: Color,
+    pub delete: Color,
+    pub swap: Color,
+    pub regressed: Color,
+    pub newly_matched: Color,
+    pub comment: Color,
+    pub opcode: Option<Color>,
+    pub branch_opcode: Option<Color>,
+    pub register: Option<Color>,
+    pub immediate: Option<Color>,
+    /// Colors for mismatched arguments, branch labels and moved blocks
+    pub rotation: Vec<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self { Self::dark() }
+}
+
+const DEFAULT_ROTATION: [Color; 9] = [
+    Color::rgb(255, 0, 255),
+    Color::rgb(0, 255, 255),
+    Color::rgb(0, 128, 0),
+    Color::rgb(255, 0, 0),
+    Color::rgb(255, 255, 0),
+    Color::rgb(255, 192, 203),
+    Color::rgb(0, 0, 255),
+    Color::rgb(0, 255, 0),
+    Color::rgb(213, 138, 138),
+];
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            background: Color::rgb(27, 27, 27),
+            text: Color::rgb(160, 160, 160),
+            emphasized: Color::rgb(220, 220, 220),
+            deemphasized: Color::rgb(96, 96, 96),
+            replace: Color::rgb(140, 180, 255),
+            insert: Color::rgb(0, 255, 0),
+            delete: Color::rgb(200, 40, 41),
+            swap: Color::rgb(255, 255, 0),
+            regressed: Color::rgb(255, 96, 96),
+            newly_matched: Color::rgb(96, 255, 96),
+            comment: Color::rgb(127, 159, 127),
+            opcode: None,
+            branch_opcode: None,
+            register: None,
+            immediate: None,
+            rotation: DEFAULT_ROTATION.to_vec(),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            background: Color::rgb(248, 248, 248),
+            text: Color::rgb(160, 160, 160),
+            emphasized: Color::rgb(96, 96, 96),
+            deemphasized: Color::rgb(220, 220, 220),
+            replace: Color::rgb(0, 0, 139),
+            insert: Color::rgb(0, 100, 0),
+            delete: Color::rgb(200, 40, 41),
+            swap: Color::rgb(160, 120, 0),
+            regressed: Color::rgb(200, 40, 41),
+            newly_matched: Color::rgb(0, 100, 0),
+            comment: Color::rgb(80, 120, 80),
+            opcode: None,
+            branch_opcode: None,
+            register: None,
+            immediate: None,
+            rotation: DEFAULT_ROTATION.to_vec(),
+        }
+    }
+
+    /// Entry `idx` of the color rotation, wrapping around.
+    pub fn rotation(&self, idx: usize) -> Color {
+        if self.rotation.is_empty() {
+            return self.text;
+        }
+        self.rotation[idx % self.rotation.len()]
+    }
+
+    /// The color of a token in an instruction row. Accepted mismatches and padding are
+    /// deemphasized entirely, and the syntax colors only apply to rows present on both sides.
+    pub fn color(&self, kind: TokenKind, ins_diff: &ObjInsDiff) -> Color {
+        if ins_diff.accepted || ins_diff.padding {
+            return self.deemphasized;
+        }
+        let row = match ins_diff.kind {
+            ObjInsDiffKind::None | ObjInsDiffKind::OpMismatch | ObjInsDiffKind::ArgMismatch => {
+                self.text
+            }
+            ObjInsDiffKind::Replace => self.replace,
+            ObjInsDiffKind::Delete => self.delete,
+            ObjInsDiffKind::Insert => self.insert,
+        };
+        let syntax = |color: Option<Color>| match ins_diff.kind {
+            ObjInsDiffKind::Replace | ObjInsDiffKind::Delete | ObjInsDiffKind::Insert => row,
+            _ => color.unwrap_or(row),
+        };
+        match kind {
+            TokenKind::Text | TokenKind::Address | TokenKind::BranchDest => row,
+            TokenKind::Line | TokenKind::Encoding => self.deemphasized,
+            TokenKind::Regressed => self.regressed,
+            TokenKind::NewlyMatched => self.newly_matched,
+            TokenKind::Opcode => syntax(self.opcode),
+            TokenKind::BranchOpcode => syntax(self.branch_opcode.or(self.opcode)),
+            TokenKind::OpcodeMismatch => self.replace,
+            TokenKind::Register => syntax(self.register),
+            TokenKind::Immediate => syntax(self.immediate),
+            TokenKind::Symbol => self.emphasized,
+            TokenKind::SymbolSameAddress => self.replace,
+            TokenKind::SymbolMismatch | TokenKind::ByteMismatch => self.delete,
+            TokenKind::SymbolMatched => self.insert,
+            TokenKind::Swap => self.swap,
+            TokenKind::Rotation(idx) => self.rotation(idx),
+            TokenKind::Comment => self.comment,
+        }
+    }
+}
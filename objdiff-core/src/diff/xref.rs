@@ -0,0 +1,86 @@
+//! Cross-references: the relocations referring to a symbol, matched between objects.
+
+use std::collections::BTreeMap;
+
+use crate::obj::{ObjInfo, ObjReloc, ObjSectionKind};
+
+/// A relocation referring to a symbol.
+#[derive(Debug, Clone)]
+pub struct SymbolReference<'a> {
+    pub section_idx: usize,
+    /// Containing symbol, if the relocation falls within one
+    pub symbol_name: Option<&'a str>,
+    /// Offset of the relocation from the start of the containing symbol or section
+    pub offset: u64,
+    pub kind: ObjSectionKind,
+    pub reloc: &'a ObjReloc,
+}
+
+/// Lists the relocations targeting the symbol, in section and address order.
+pub fn symbol_references<'a>(obj: &'a ObjInfo, name: &str) -> Vec<SymbolReference<'a>> {
+    let mut out = vec![];
+    for (section_idx, section) in obj.sections.iter().enumerate() {
+        for reloc in section.relocations.iter().filter(|r| r.target.name == name) {
+            let symbol = section
+                .symbols
+                .iter()
+                .filter(|s| s.size > 0)
+                .find(|s| (s.address..s.address + s.size).contains(&reloc.address));
+            out.push(SymbolReference {
+                section_idx,
+                symbol_name: symbol.map(|s| s.name.as_str()),
+                offset: reloc.address - symbol.map_or(section.address, |s| s.address),
+                kind: section.kind,
+                reloc,
+            });
+        }
+    }
+    out.sort_by_key(|r| (r.section_idx, r.reloc.address));
+    out
+}
+
+/// References to a symbol in the target (left) and base (right) objects.
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceDiff<'a> {
+    /// References from the same containing symbol on both sides
+    pub common: Vec<(SymbolReference<'a>, SymbolReference<'a>)>,
+    /// References only in the target, such as an extra call site
+    pub left_only: Vec<SymbolReference<'a>>,
+    /// References only in the base, such as a missing call site
+    pub right_only: Vec<SymbolReference<'a>>,
+}
+
+/// Matches the references to the symbol between both objects. References are paired
+/// by containing symbol, in address order, since offsets shift as code changes.
+pub fn diff_references<'a>(
+    left: Option<&'a ObjInfo>,
+    right: Option<&'a ObjInfo>,
+    name: &str,
+) -> ReferenceDiff<'a> {
+    let group = |obj: Option<&'a ObjInfo>| {
+        let mut groups = BTreeMap::<(Option<&str>, Option<&str>), Vec<SymbolReference>>::new();
+        for reference in obj.map(|o| symbol_references(o, name)).unwrap_or_default() {
+            // Unnamed ranges are matched by section name
+            let section = match reference.symbol_name {
+                Some(_) => None,
+                None => obj.map(|o| o.sections[reference.section_idx].name.as_str()),
+            };
+            groups.entry((reference.symbol_name, section)).or_default().push(reference);
+        }
+        groups
+    };
+    let mut left_groups = group(left);
+    let right_groups = group(right);
+    let mut out = ReferenceDiff::default();
+    for (key, right_refs) in right_groups {
+        let left_refs = left_groups.remove(&key).unwrap_or_default();
+        let common = left_refs.len().min(right_refs.len());
+        let mut left_iter = left_refs.into_iter();
+        let mut right_iter = right_refs.into_iter();
+        out.common.extend(left_iter.by_ref().zip(right_iter.by_ref()).take(common));
+        out.left_only.extend(left_iter);
+        out.right_only.extend(right_iter);
+    }
+    out.left_only.extend(left_groups.into_values().flatten());
+    out
+}
@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::report::{Report, ReportUnit};
+
+/// Category containing every unit in the report.
+pub const DEFAULT_CATEGORY: &str = "default";
+
+/// A single progress entry, as accepted by the frogress API.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct FrogressEntry {
+    pub timestamp: u64,
+    pub git_hash: String,
+    /// Measures per category, e.g. `code` and `code/total`
+    pub categories: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+#[derive(serde::Serialize)]
+struct FrogressRequest<'a> {
+    api_key: &'a str,
+    entries: &'a [FrogressEntry],
+}
+
+impl FrogressEntry {
+    /// Convert a report into a progress entry. Every unit is counted in the
+    /// default category, as well as in each of the unit's own categories.
+    pub fn from_report(
+        report: &Report,
+        timestamp: u64,
+        git_hash: String,
+        default_category: &str,
+    ) -> Self {
+        let mut categories = BTreeMap::<String, BTreeMap<String, u64>>::new();
+        for unit in &report.units {
+            add_measures(categories.entry(default_category.to_string()).or_default(), unit);
+            for category in &unit.categories {
+                if category != default_category {
+                    add_measures(categories.entry(category.clone()).or_default(), unit);
+                }
+            }
+        }
+        Self { timestamp, git_hash, categories }
+    }
+}
+
+fn add_measures(measures: &mut BTreeMap<String, u64>, unit: &ReportUnit) {
+    let mut add = |key: &str, value: u64| *measures.entry(key.to_string()).or_default() += value;
+    add("code", unit.matched_code);
+    add("code/total", unit.total_code);
+    add("data", unit.matched_data);
+    add("data/total", unit.total_data);
+    add("functions", unit.matched_functions as u64);
+    add("functions/total", unit.total_functions as u64);
+    add("instructions", unit.matched_instructions);
+    add("instructions/total", unit.total_instructions);
+}
+
+/// Upload progress entries to a frogress instance.
+///
+/// `api_base` is the API root, e.g. `https://progress.decomp.club`.
+pub fn upload(
+    api_base: &str,
+    project: &str,
+    version: &str,
+    api_key: &str,
+    entries: &[FrogressEntry],
+) -> Result<()> {
+    let url = format!("{}/data/{project}/{version}/", api_base.trim_end_matches('/'));
+    let client = reqwest::blocking::Client::new();
+    let response = client
+        .post(&url)
+        .json(&FrogressRequest { api_key, entries })
+        .send()
+        .map_err(|e| anyhow!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Failed to upload progress: {}", response.text()?));
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    fs::OpenOptions,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{cache::STATE_DIR, report::Report};
+
+/// Match percentages of a unit's functions at the time of a run.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct HistoryRecord {
+    /// Time of the run, in milliseconds since the Unix epoch. Shared by all records of a run.
+    pub run: u64,
+    pub unit: String,
+    /// Function name to fuzzy match percentage
+    pub functions: BTreeMap<String, f32>,
+}
+
+/// A function whose match percentage changed between two runs.
+#[derive(Debug, Clone)]
+pub struct FunctionChange {
+    pub unit: String,
+    pub function: String,
+    /// `None` if the function didn't exist in the earlier run
+    pub from: Option<f32>,
+    /// `None` if the function no longer exists
+    pub to: Option<f32>,
+}
+
+/// Append-only log of per-function match percentages, stored as JSON lines under
+/// `.objdiff/history` in the project directory.
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new(project_dir: &Path) -> Self {
+        Self { path: project_dir.join(STATE_DIR).join("history").join("functions.jsonl") }
+    }
+
+    /// Append the function match percentages of every unit in a report as a new run.
+    pub fn record(&self, report: &Report) -> Result<u64> {
+        let run = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let mut out = Vec::new();
+        for unit in &report.units {
+            let record = HistoryRecord {
+                run,
+                unit: unit.name.clone(),
+                functions: unit
+                    .functions
+                    .iter()
+                    .map(|f| (f.name.clone(), f.fuzzy_match_percent))
+                    .collect(),
+            };
+            serde_json::to_writer(&mut out, &record)?;
+            out.push(b'\n');
+        }
+        // Written in a single call so that concurrent runs don't interleave records
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(&out))
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(run)
+    }
+
+    /// Read every record, in the order they were written. Invalid lines are skipped.
+    pub fn records(&self) -> Result<Vec<HistoryRecord>> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to open {}", self.path.display()))
+            }
+        };
+        let mut records = Vec::new();
+        for (idx, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(e) => log::warn!("Ignoring invalid history line {}: {e}", idx + 1),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Match percentage of a function in each run that recorded its unit, oldest first.
+    pub fn trend(&self, unit: &str, function: &str) -> Result<Vec<(u64, Option<f32>)>> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .filter(|r| r.unit == unit)
+            .map(|r| (r.run, r.functions.get(function).copied()))
+            .collect())
+    }
+
+    /// Functions whose match percentage changed between the last two runs.
+    pub fn changes(&self) -> Result<Vec<FunctionChange>> {
+        let runs = group_runs(self.records()?);
+        let mut iter = runs.into_values().rev();
+        let (Some(to), Some(from)) = (iter.next(), iter.next()) else {
+            return Ok(Vec::new());
+        };
+        Ok(diff_runs(&from, &to))
+    }
+
+    /// Functions whose match percentage decreased between the last two runs.
+    pub fn regressions(&self) -> Result<Vec<FunctionChange>> {
+        Ok(self
+            .changes()?
+            .into_iter()
+            .filter(|c| match (c.from, c.to) {
+                (Some(from), Some(to)) => to < from,
+                (Some(from), None) => from > 0.0,
+                _ => false,
+            })
+            .collect())
+    }
+}
+
+/// Run -> unit name -> record
+fn group_runs(records: Vec<HistoryRecord>) -> BTreeMap<u64, BTreeMap<String, HistoryRecord>> {
+    let mut runs = BTreeMap::<u64, BTreeMap<String, HistoryRecord>>::new();
+    for record in records {
+        runs.entry(record.run).or_default().insert(record.unit.clone(), record);
+    }
+    runs
+}
+
+fn diff_runs(
+    from: &BTreeMap<String, HistoryRecord>,
+    to: &BTreeMap<String, HistoryRecord>,
+) -> Vec<FunctionChange> {
+    let mut changes = Vec::new();
+    for (unit, to_record) in to {
+        let from_functions = from.get(unit).map(|r| &r.functions);
+        for (function, &to_percent) in &to_record.functions {
+            let from_percent = from_functions.and_then(|f| f.get(function).copied());
+            if from_percent != Some(to_percent) {
+                changes.push(FunctionChange {
+                    unit: unit.clone(),
+                    function: function.clone(),
+                    from: from_percent,
+                    to: Some(to_percent),
+                });
+            }
+        }
+        for (function, &from_percent) in from_functions.into_iter().flatten() {
+            if !to_record.functions.contains_key(function) {
+                changes.push(FunctionChange {
+                    unit: unit.clone(),
+                    function: function.clone(),
+                    from: Some(from_percent),
+                    to: None,
+                });
+            }
+        }
+    }
+    changes
+}
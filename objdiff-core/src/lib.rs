@@ -1,9 +1,34 @@
 pub mod arch;
+#[cfg(feature = "bindings")]
+pub mod bindings;
+#[cfg(feature = "build")]
+pub mod build;
+#[cfg(feature = "cache")]
+pub mod cache;
 #[cfg(feature = "config")]
 pub mod config;
 pub mod diff;
+#[cfg(feature = "frogress")]
+pub mod frogress;
+#[cfg(feature = "history")]
+pub mod history;
+#[cfg(feature = "config")]
+pub mod link;
 pub mod obj;
+#[cfg(feature = "config")]
+pub mod project;
+#[cfg(feature = "report")]
+pub mod report;
+#[cfg(feature = "scratch")]
+pub mod scratch;
+#[cfg(feature = "session")]
+pub mod session;
 pub mod util;
+pub mod vfs;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "report")]
+pub mod workflow;
 
 #[cfg(not(feature = "any-arch"))]
 compile_error!("At least one architecture feature must be enabled.");
@@ -0,0 +1,570 @@
+//! Link preview: concatenates the base objects of a project in link order, applies their
+//! relocations and compares the resulting sections against the original linked binary.
+//! This catches problems that diffing each object on its own can't see, such as wrong
+//! section alignment or units missing from the link.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use object::{elf, Architecture, Object, ObjectSection, RelocationFlags, SectionKind};
+
+use crate::{
+    config::{expand_archive_objects, ProjectConfig},
+    obj::{
+        binary::{parse_dol, BinaryKind},
+        read, ObjInfo, ObjSectionKind, ObjSymbolFlags,
+    },
+    vfs,
+};
+
+/// Linked binary to compare the linked base objects against.
+#[derive(Default, Clone, PartialEq, serde::Deserialize)]
+pub struct LinkConfig {
+    /// Original linked ELF or DOL, relative to the project directory
+    pub target: PathBuf,
+    /// Output sections in link order. Defaults to the allocated sections of an ELF target,
+    /// in address order. Required for DOL targets, which have no section names.
+    #[serde(default)]
+    pub sections: Vec<LinkSection>,
+}
+
+#[derive(Default, Clone, PartialEq, serde::Deserialize)]
+pub struct LinkSection {
+    pub name: String,
+    /// Start address, defaults to the address of the target's section with the same name,
+    /// or to directly after the previous section
+    #[serde(default)]
+    pub address: Option<u64>,
+}
+
+/// A section of the original linked binary.
+#[derive(Debug, Clone)]
+struct TargetSection {
+    name: String,
+    address: u64,
+    size: u64,
+    /// Contents, or `None` for uninitialized sections
+    data: Option<Vec<u8>>,
+}
+
+/// The original linked binary, addressed by virtual address.
+#[derive(Debug, Clone)]
+pub struct TargetImage {
+    pub architecture: Architecture,
+    pub big_endian: bool,
+    sections: Vec<TargetSection>,
+}
+
+impl TargetImage {
+    pub fn read(path: &Path) -> Result<Self> {
+        let (data, _) =
+            vfs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        if BinaryKind::from_path(path) == BinaryKind::Dol {
+            let sections = parse_dol(&data)?
+                .into_iter()
+                .map(|s| TargetSection {
+                    name: s.name,
+                    address: s.address,
+                    size: s.data.len() as u64,
+                    data: (s.kind != ObjSectionKind::Bss).then_some(s.data),
+                })
+                .collect();
+            return Ok(Self { architecture: Architecture::PowerPc, big_endian: true, sections });
+        }
+        let file = object::File::parse(&*data)?;
+        let mut sections = Vec::new();
+        for section in file.sections() {
+            if section.address() == 0 {
+                continue;
+            }
+            let data = match section.kind() {
+                SectionKind::Text
+                | SectionKind::Data
+                | SectionKind::ReadOnlyData
+                | SectionKind::ReadOnlyDataWithRel
+                | SectionKind::ReadOnlyString => Some(section.data()?.to_vec()),
+                SectionKind::UninitializedData => None,
+                _ => continue,
+            };
+            sections.push(TargetSection {
+                name: section.name()?.to_string(),
+                address: section.address(),
+                size: section.size(),
+                data,
+            });
+        }
+        sections.sort_by_key(|s| s.address);
+        Ok(Self {
+            architecture: file.architecture(),
+            big_endian: !file.is_little_endian(),
+            sections,
+        })
+    }
+
+    /// The allocated sections in address order.
+    fn default_layout(&self) -> Vec<LinkSection> {
+        self.sections
+            .iter()
+            .map(|s| LinkSection { name: s.name.clone(), address: Some(s.address) })
+            .collect()
+    }
+
+    fn section_by_name(&self, name: &str) -> Option<&TargetSection> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    /// Initialized bytes starting at an address, up to the end of the containing section.
+    fn bytes_at(&self, address: u64) -> Option<&[u8]> {
+        let section =
+            self.sections.iter().find(|s| (s.address..s.address + s.size).contains(&address))?;
+        section.data.as_deref()?.get((address - section.address) as usize..)
+    }
+}
+
+/// A base object to link.
+pub struct LinkUnit {
+    pub name: String,
+    pub obj: ObjInfo,
+}
+
+/// An input section placed in an output section.
+#[derive(Debug, Clone)]
+struct Placement {
+    unit: usize,
+    section: usize,
+    address: u64,
+    size: u64,
+}
+
+#[derive(Debug, Clone)]
+struct LinkedSection {
+    name: String,
+    address: u64,
+    size: u64,
+    bss: bool,
+    data: Vec<u8>,
+    placements: Vec<Placement>,
+}
+
+/// First byte at which a linked section differs from the target.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub address: u64,
+    /// Unit whose section contains the address
+    pub unit: Option<String>,
+    /// Symbol containing the address, with the offset into it
+    pub symbol: Option<String>,
+    /// Byte in the target, `None` past its end
+    pub expected: Option<u8>,
+    /// Byte in the linked output, `None` past its end
+    pub found: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SectionComparison {
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+    /// Size of the target's section with the same name
+    pub target_size: Option<u64>,
+    pub divergence: Option<Divergence>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LinkReport {
+    pub sections: Vec<SectionComparison>,
+    /// Problems found while linking, such as missing objects and unresolved symbols
+    pub issues: Vec<String>,
+}
+
+/// Link the base objects of a project and compare the result against its link target.
+pub fn link_project(project_dir: &Path, config: &ProjectConfig) -> Result<LinkReport> {
+    let Some(link_config) = &config.link else {
+        bail!("Project config has no link target");
+    };
+    let target = TargetImage::read(&project_dir.join(&link_config.target))?;
+    let layout = if link_config.sections.is_empty() {
+        if target.sections.iter().all(|s| !s.name.starts_with('.')) {
+            bail!("Link sections must be configured for targets without section names");
+        }
+        target.default_layout()
+    } else {
+        link_config.sections.clone()
+    };
+
+    let mut issues = Vec::new();
+    let mut units = Vec::new();
+    let objects = expand_archive_objects(
+        config.objects.clone(),
+        project_dir,
+        config.target_dir.as_deref(),
+        config.base_dir.as_deref(),
+    );
+    for mut object in objects {
+        object.resolve_paths(project_dir, config.target_dir.as_deref(), config.base_dir.as_deref());
+        let name = object.name().to_string();
+        let Some(path) = &object.base_path else {
+            issues.push(format!("{name}: no base object"));
+            continue;
+        };
+        match read::read(path) {
+            Ok(mut obj) => {
+                read::infer_symbol_sizes(&mut obj, object.size_inference.unwrap_or_default());
+                units.push(LinkUnit { name, obj })
+            }
+            Err(e) => issues.push(format!("{name}: failed to read {}: {e:#}", path.display())),
+        }
+    }
+
+    let sections = link(&units, &layout, &target, &mut issues);
+    Ok(LinkReport { sections: compare(&units, &sections, &target), issues })
+}
+
+/// Input section names placed into an output section, in order. Common symbols are
+/// allocated at the end of `.bss`.
+fn input_names(output: &str) -> Vec<&str> {
+    if output == ".bss" {
+        vec![".bss", ".comm"]
+    } else {
+        vec![output]
+    }
+}
+
+fn link(
+    units: &[LinkUnit],
+    layout: &[LinkSection],
+    target_image: &TargetImage,
+    issues: &mut Vec<String>,
+) -> Vec<LinkedSection> {
+    // Place the input sections
+    let mut sections = Vec::<LinkedSection>::new();
+    let mut placed = HashMap::<(usize, usize), u64>::new();
+    for output in layout {
+        let address = output
+            .address
+            .or_else(|| target_image.section_by_name(&output.name).map(|s| s.address))
+            .or_else(|| sections.last().map(|s| s.address + s.size))
+            .unwrap_or(0);
+        let mut linked = LinkedSection {
+            name: output.name.clone(),
+            address,
+            size: 0,
+            bss: true,
+            data: Vec::new(),
+            placements: Vec::new(),
+        };
+        for input in input_names(&output.name) {
+            for (unit_idx, unit) in units.iter().enumerate() {
+                for (section_idx, section) in unit.obj.sections.iter().enumerate() {
+                    if section.name != input {
+                        continue;
+                    }
+                    let align = section.align.unwrap_or(1).max(1);
+                    let offset = (linked.size + align - 1) / align * align;
+                    let bss = section.kind == ObjSectionKind::Bss;
+                    if !bss {
+                        linked.bss = false;
+                        linked.data.resize(offset as usize, 0);
+                        linked.data.extend_from_slice(&section.data);
+                    }
+                    linked.size = offset + section.size;
+                    placed.insert((unit_idx, section_idx), address + offset);
+                    linked.placements.push(Placement {
+                        unit: unit_idx,
+                        section: section_idx,
+                        address: address + offset,
+                        size: section.size,
+                    });
+                }
+            }
+        }
+        sections.push(linked);
+    }
+    for (unit_idx, unit) in units.iter().enumerate() {
+        for (section_idx, section) in unit.obj.sections.iter().enumerate() {
+            if section.size > 0 && !placed.contains_key(&(unit_idx, section_idx)) {
+                issues.push(format!(
+                    "{}: section {} is not in the link layout",
+                    unit.name, section.name
+                ));
+            }
+        }
+    }
+
+    let globals = global_symbols(units, &sections, &placed, issues);
+    let ranges = sections
+        .iter()
+        .map(|s| (s.name.clone(), s.address..s.address + s.size))
+        .collect::<Vec<_>>();
+    // Small data is addressed relative to r13 or r2, per the section of the target
+    let sda_base = |value: u64| -> (u32, u64) {
+        let section = ranges.iter().find(|(_, r)| r.contains(&value)).map(|(n, _)| n.as_str());
+        let (reg, base) = match section {
+            Some(".sdata" | ".sbss") => (13, "_SDA_BASE_"),
+            Some(".sdata2" | ".sbss2") => (2, "_SDA2_BASE_"),
+            _ => return (0, 0),
+        };
+        (reg, globals.get(base).copied().unwrap_or(0))
+    };
+
+    // Apply relocations
+    let mut unresolved = BTreeMap::<&str, Vec<&str>>::new();
+    let mut unsupported = BTreeMap::<String, usize>::new();
+    for linked in &mut sections {
+        for placement in &linked.placements {
+            let unit = &units[placement.unit];
+            let section = &unit.obj.sections[placement.section];
+            if section.kind == ObjSectionKind::Bss {
+                continue;
+            }
+            for (i, reloc) in section.relocations.iter().enumerate() {
+                let target = &reloc.target;
+                let local = || {
+                    let idx = unit
+                        .obj
+                        .sections
+                        .iter()
+                        .position(|s| Some(&s.name) == reloc.target_section.as_ref())?;
+                    let base = placed.get(&(placement.unit, idx))?;
+                    base.checked_add_signed(
+                        target.address as i64 - unit.obj.sections[idx].address as i64,
+                    )
+                };
+                let flags = target.flags.0;
+                let symbol = if flags.contains(ObjSymbolFlags::Global)
+                    || flags.contains(ObjSymbolFlags::Weak)
+                {
+                    globals.get(&target.name).copied().or_else(local)
+                } else {
+                    local()
+                };
+                let Some(symbol) = symbol else {
+                    unresolved.entry(target.name.as_str()).or_default().push(unit.name.as_str());
+                    continue;
+                };
+                let mut addend = target.addend;
+                if reloc.flags == (RelocationFlags::Elf { r_type: elf::R_MIPS_HI16 }) {
+                    // The low half of the addend is in the paired LO16 relocation
+                    if let Some(lo) = section.relocations[i + 1..]
+                        .iter()
+                        .find(|r| r.flags == (RelocationFlags::Elf { r_type: elf::R_MIPS_LO16 }))
+                    {
+                        addend += lo.target.addend;
+                    }
+                }
+                let place = placement.address + reloc.address.saturating_sub(section.address);
+                let value = symbol.wrapping_add_signed(addend);
+                let at = (place - linked.address) as usize;
+                if !relocate(
+                    target_image,
+                    reloc.flags,
+                    &mut linked.data,
+                    at,
+                    place,
+                    value,
+                    &sda_base,
+                ) {
+                    let kind = unit.obj.arch.display_reloc(reloc.flags).into_owned();
+                    *unsupported.entry(kind).or_default() += 1;
+                }
+            }
+        }
+    }
+    for (name, units) in unresolved {
+        issues.push(format!("Unresolved symbol {name} referenced by {}", units.join(", ")));
+    }
+    for (kind, count) in unsupported {
+        issues.push(format!("{count} unsupported {kind} relocation(s) were not applied"));
+    }
+    sections
+}
+
+/// Addresses of the global symbols, with strong definitions overriding weak and common
+/// ones. The small data base symbols are defined as the linker would if no object does.
+fn global_symbols(
+    units: &[LinkUnit],
+    sections: &[LinkedSection],
+    placed: &HashMap<(usize, usize), u64>,
+    issues: &mut Vec<String>,
+) -> HashMap<String, u64> {
+    let mut globals = HashMap::<String, (u64, bool)>::new();
+    for (unit_idx, unit) in units.iter().enumerate() {
+        for (section_idx, section) in unit.obj.sections.iter().enumerate() {
+            let Some(&base) = placed.get(&(unit_idx, section_idx)) else {
+                continue;
+            };
+            for symbol in &section.symbols {
+                let flags = symbol.flags.0;
+                if !flags.contains(ObjSymbolFlags::Global) && !flags.contains(ObjSymbolFlags::Weak)
+                {
+                    continue;
+                }
+                let strong = !flags.contains(ObjSymbolFlags::Weak)
+                    && !flags.contains(ObjSymbolFlags::Common);
+                let address = base + symbol.section_address;
+                match globals.get(&symbol.name) {
+                    Some((_, true)) if strong => {
+                        issues.push(format!("{}: duplicate symbol {}", unit.name, symbol.name));
+                    }
+                    Some((_, existing)) if *existing || !strong => {}
+                    _ => {
+                        globals.insert(symbol.name.clone(), (address, strong));
+                    }
+                }
+            }
+        }
+    }
+    for (symbol, section) in [("_SDA_BASE_", ".sdata"), ("_SDA2_BASE_", ".sdata2")] {
+        if let Some(section) = sections.iter().find(|s| s.name == section) {
+            globals.entry(symbol.to_string()).or_insert((section.address + 0x8000, true));
+        }
+    }
+    globals.into_iter().map(|(name, (address, _))| (name, address)).collect()
+}
+
+#[derive(Copy, Clone)]
+enum Patch {
+    Word(u32),
+    Half(u16),
+}
+
+/// Apply a relocation to the linked data at `at`, returning `false` if the relocation type
+/// isn't supported.
+fn relocate(
+    target: &TargetImage,
+    flags: RelocationFlags,
+    data: &mut [u8],
+    at: usize,
+    place: u64,
+    value: u64,
+    sda_base: &dyn Fn(u64) -> (u32, u64),
+) -> bool {
+    let RelocationFlags::Elf { r_type } = flags else {
+        return false;
+    };
+    let big_endian = target.big_endian;
+    let ins = data.get(at..at + 4).map_or(0, |bytes| {
+        let bytes = bytes.try_into().unwrap();
+        if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    });
+    let relative = value.wrapping_sub(place) as u32;
+    let value = value as u32;
+    // Halfword relocations point directly at the immediate field of an instruction
+    let patch = match (target.architecture, r_type) {
+        (Architecture::PowerPc, elf::R_PPC_ADDR32 | elf::R_PPC_UADDR32) => Patch::Word(value),
+        (Architecture::PowerPc, elf::R_PPC_REL32) => Patch::Word(relative),
+        (Architecture::PowerPc, elf::R_PPC_ADDR16_LO) => Patch::Half(value as u16),
+        (Architecture::PowerPc, elf::R_PPC_ADDR16_HI) => Patch::Half((value >> 16) as u16),
+        (Architecture::PowerPc, elf::R_PPC_ADDR16_HA) => {
+            Patch::Half((value.wrapping_add(0x8000) >> 16) as u16)
+        }
+        (Architecture::PowerPc, elf::R_PPC_REL24) => {
+            Patch::Word((ins & !0x3FFFFFC) | (relative & 0x3FFFFFC))
+        }
+        (Architecture::PowerPc, elf::R_PPC_REL14) => {
+            Patch::Word((ins & !0xFFFC) | (relative & 0xFFFC))
+        }
+        (Architecture::PowerPc, elf::R_PPC_EMB_SDA21) => {
+            let (reg, base) = sda_base(value as u64);
+            let offset = value.wrapping_sub(base as u32) & 0xFFFF;
+            Patch::Word((ins & !0x1FFFFF) | (reg << 16) | offset)
+        }
+        (Architecture::Mips | Architecture::Mips64, elf::R_MIPS_32) => Patch::Word(value),
+        (Architecture::Mips | Architecture::Mips64, elf::R_MIPS_26) => {
+            Patch::Word((ins & 0xFC000000) | ((value >> 2) & 0x3FFFFFF))
+        }
+        (Architecture::Mips | Architecture::Mips64, elf::R_MIPS_HI16) => {
+            Patch::Word((ins & 0xFFFF0000) | (value.wrapping_add(0x8000) >> 16))
+        }
+        (Architecture::Mips | Architecture::Mips64, elf::R_MIPS_LO16) => {
+            Patch::Word((ins & 0xFFFF0000) | (value & 0xFFFF))
+        }
+        _ => return false,
+    };
+    let bytes = match (patch, big_endian) {
+        (Patch::Word(v), true) => v.to_be_bytes().to_vec(),
+        (Patch::Word(v), false) => v.to_le_bytes().to_vec(),
+        (Patch::Half(v), true) => v.to_be_bytes().to_vec(),
+        (Patch::Half(v), false) => v.to_le_bytes().to_vec(),
+    };
+    let Some(out) = data.get_mut(at..at + bytes.len()) else {
+        return false;
+    };
+    out.copy_from_slice(&bytes);
+    true
+}
+
+/// Compare each linked section against the target, finding the first differing byte.
+fn compare(
+    units: &[LinkUnit],
+    sections: &[LinkedSection],
+    target: &TargetImage,
+) -> Vec<SectionComparison> {
+    sections
+        .iter()
+        .map(|linked| {
+            let target_size = target.section_by_name(&linked.name).map(|s| s.size);
+            let divergence = if linked.bss {
+                None
+            } else {
+                let expected = target.bytes_at(linked.address).unwrap_or_default();
+                let len = match target_size {
+                    Some(size) => linked.data.len().max(size as usize),
+                    None => linked.data.len(),
+                };
+                (0..len).find(|&i| expected.get(i) != linked.data.get(i)).map(|i| {
+                    let address = linked.address + i as u64;
+                    let (unit, symbol) = locate(units, linked, address);
+                    Divergence {
+                        address,
+                        unit,
+                        symbol,
+                        expected: expected.get(i).copied(),
+                        found: linked.data.get(i).copied(),
+                    }
+                })
+            };
+            SectionComparison {
+                name: linked.name.clone(),
+                address: linked.address,
+                size: linked.size,
+                target_size,
+                divergence,
+            }
+        })
+        .collect()
+}
+
+/// The unit and symbol that an address in a linked section belongs to.
+fn locate(
+    units: &[LinkUnit],
+    linked: &LinkedSection,
+    address: u64,
+) -> (Option<String>, Option<String>) {
+    let Some(placement) = linked.placements.iter().find(|p| {
+        let range: Range<u64> = p.address..p.address + p.size;
+        range.contains(&address)
+    }) else {
+        return (None, None);
+    };
+    let unit = &units[placement.unit];
+    let offset = address - placement.address;
+    let symbol = unit.obj.sections[placement.section]
+        .symbols
+        .iter()
+        .filter(|s| s.section_address <= offset && offset < s.section_address + s.size.max(1))
+        .max_by_key(|s| s.section_address)
+        .map(|s| match offset - s.section_address {
+            0 => s.name.clone(),
+            delta => format!("{}+{:#x}", s.name, delta),
+        });
+    (Some(unit.name.clone()), symbol)
+}
@@ -0,0 +1,775 @@
+//! Loading of linked binaries (DOL, REL, raw binaries or byte ranges of them) using a
+//! symbol map, for projects without a target object to diff against.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use filetime::FileTime;
+use object::{elf, Architecture, RelocationFlags};
+
+use crate::{
+    arch::{new_binary_arch, new_rsp_arch, ObjArch},
+    obj::{
+        split_meta::SplitMeta, ObjInfo, ObjReloc, ObjSection, ObjSectionKind, ObjSymbol,
+        ObjSymbolFlagSet, ObjSymbolFlags,
+    },
+    vfs,
+};
+
+/// A symbol map used to slice symbols out of a linked binary.
+#[derive(Debug, Default, Clone, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SymbolMapConfig {
+    /// Path to the symbol map. Either a CodeWarrior linker map, or a list of
+    /// `address [size] name` or `name = [section:]address; // size:0x..` lines.
+    pub path: PathBuf,
+    /// Only load symbols belonging to this unit, as named in the map (e.g. `main.o`)
+    #[serde(default)]
+    pub unit: Option<String>,
+    /// Load address for raw binaries. DOLs and RELs contain their own section layout.
+    #[serde(default)]
+    pub base_address: Option<u64>,
+}
+
+/// Architecture of the code in a [`FileRange`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RangeArch {
+    #[default]
+    Ppc,
+    /// Big-endian MIPS, such as N64 overlays
+    Mips,
+    /// N64 RSP microcode
+    Rsp,
+}
+
+/// A unit's code as a byte range within a larger file, such as an overlay or a microcode
+/// blob in a ROM.
+#[derive(Debug, Default, Clone, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct FileRange {
+    /// Offset of the range within the file
+    pub offset: u64,
+    /// Length of the range in bytes
+    pub size: u64,
+    /// Address the range is loaded at
+    #[serde(default)]
+    pub load_address: u64,
+    #[serde(default)]
+    pub arch: RangeArch,
+    /// Name of the symbol covering the whole range, if there's no symbol map.
+    /// Defaults to `range_{load_address}`.
+    #[serde(default)]
+    pub symbol: Option<String>,
+}
+
+/// A symbol read from a symbol map.
+#[derive(Debug, Clone)]
+pub struct MapSymbol {
+    pub name: String,
+    /// Section name, if known
+    pub section: Option<String>,
+    /// Virtual address, or offset within the section for relocatable modules (RELs)
+    pub address: u64,
+    pub size: Option<u64>,
+    /// Unit (object file) the symbol belongs to
+    pub unit: Option<String>,
+}
+
+/// Parses a CodeWarrior linker map or a simple symbol list.
+pub fn parse_map(text: &str) -> Vec<MapSymbol> {
+    if text.lines().any(|l| l.trim_end().ends_with(" section layout")) {
+        parse_cw_map(text)
+    } else {
+        parse_symbol_list(text)
+    }
+}
+
+fn parse_hex(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let s = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    u64::from_str_radix(s, 16).ok()
+}
+
+fn parse_cw_map(text: &str) -> Vec<MapSymbol> {
+    let mut out = Vec::new();
+    let mut section: Option<String> = None;
+    for line in text.lines() {
+        if let Some(name) = line.trim_end().strip_suffix(" section layout") {
+            section = Some(name.trim().to_string());
+            continue;
+        }
+        if !line.starts_with(' ') {
+            // "Memory map:", "Linker generated symbols:", etc.
+            if !line.trim().is_empty() {
+                section = None;
+            }
+            continue;
+        }
+        let Some(section) = &section else {
+            continue;
+        };
+        // "  00000000 000024 80003100  4 name \tunit.a object.o"
+        let (entry, unit) = match line.split_once('\t') {
+            Some((entry, unit)) => (entry, unit.split_whitespace().last()),
+            None => (line, None),
+        };
+        if entry.contains("(entry of ") {
+            continue;
+        }
+        let tokens = entry.split_whitespace().collect::<Vec<_>>();
+        if tokens.len() < 4 {
+            continue;
+        }
+        let (Some(start), Some(size), Some(vaddr)) =
+            (parse_hex(tokens[0]), parse_hex(tokens[1]), parse_hex(tokens[2]))
+        else {
+            // UNUSED symbols
+            continue;
+        };
+        // Newer maps include an alignment column
+        let name = if tokens.len() >= 5 && tokens[3].parse::<u32>().is_ok() {
+            tokens[4]
+        } else {
+            tokens[3]
+        };
+        // Skip section symbols
+        if size == 0 || name.starts_with('.') {
+            continue;
+        }
+        out.push(MapSymbol {
+            name: name.to_string(),
+            section: Some(section.clone()),
+            address: if vaddr != 0 { vaddr } else { start },
+            size: Some(size),
+            unit: unit.map(str::to_string),
+        });
+    }
+    out
+}
+
+fn parse_symbol_list(text: &str) -> Vec<MapSymbol> {
+    let mut out = Vec::new();
+    for line in text.lines() {
+        let (line, comment) = match line.split_once("//") {
+            Some((line, comment)) => (line.trim(), Some(comment)),
+            None => (line.trim(), None),
+        };
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let size = comment
+            .and_then(|c| c.split_whitespace().find_map(|t| t.strip_prefix("size:")))
+            .and_then(parse_hex);
+        if let Some((name, value)) = line.split_once('=') {
+            // name = .text:0x80003100; // type:function size:0x24
+            let value = value.trim().trim_end_matches(';');
+            let (section, address) = match value.rsplit_once(':') {
+                Some((section, address)) => (Some(section.trim().to_string()), address),
+                None => (None, value),
+            };
+            if let Some(address) = parse_hex(address) {
+                out.push(MapSymbol {
+                    name: name.trim().to_string(),
+                    section,
+                    address,
+                    size,
+                    unit: None,
+                });
+            }
+            continue;
+        }
+        // address [size] name
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let (address, size, name) = match tokens.as_slice() {
+            [address, name] => (parse_hex(address), size, *name),
+            [address, size, name] => (parse_hex(address), parse_hex(size), *name),
+            _ => continue,
+        };
+        if let Some(address) = address {
+            out.push(MapSymbol {
+                name: name.to_string(),
+                section: None,
+                address,
+                size,
+                unit: None,
+            });
+        }
+    }
+    out
+}
+
+/// A section of a linked binary, before symbols are assigned.
+pub(crate) struct BinarySection {
+    pub(crate) name: String,
+    pub(crate) kind: ObjSectionKind,
+    /// Virtual address, or 0 for relocatable modules
+    pub(crate) address: u64,
+    pub(crate) data: Vec<u8>,
+    pub(crate) orig_index: usize,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let Some(bytes) = data.get(offset..offset + 4) else {
+        bail!("Unexpected end of file reading {:#x}", offset);
+    };
+    Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    let Some(bytes) = data.get(offset..offset + 2) else {
+        bail!("Unexpected end of file reading {:#x}", offset);
+    };
+    Ok(u16::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+pub(crate) fn parse_dol(data: &[u8]) -> Result<Vec<BinarySection>> {
+    ensure!(data.len() >= 0x100, "DOL header too small");
+    let mut sections = Vec::new();
+    for i in 0..18 {
+        let offset = read_u32(data, i * 4)? as usize;
+        let address = read_u32(data, 0x48 + i * 4)? as u64;
+        let size = read_u32(data, 0x90 + i * 4)? as usize;
+        if size == 0 {
+            continue;
+        }
+        let Some(section_data) = data.get(offset..offset + size) else {
+            bail!("DOL section {} out of bounds", i);
+        };
+        let (name, kind) = if i < 7 {
+            (format!("text{i}"), ObjSectionKind::Code)
+        } else {
+            (format!("data{}", i - 7), ObjSectionKind::Data)
+        };
+        sections.push(BinarySection {
+            name,
+            kind,
+            address,
+            data: section_data.to_vec(),
+            orig_index: i,
+        });
+    }
+    let bss_address = read_u32(data, 0xD8)? as u64;
+    let bss_size = read_u32(data, 0xDC)? as usize;
+    if bss_size > 0 {
+        sections.push(BinarySection {
+            name: "bss".to_string(),
+            kind: ObjSectionKind::Bss,
+            address: bss_address,
+            data: vec![0; bss_size],
+            orig_index: 18,
+        });
+    }
+    Ok(sections)
+}
+
+// Section names used by the standard REL linker script
+const REL_SECTION_NAMES: [&str; 8] =
+    ["", ".init", ".text", ".ctors", ".dtors", ".rodata", ".data", ".bss"];
+
+struct RelInfo {
+    module_id: u32,
+    sections: Vec<BinarySection>,
+    /// (module ID, offset of relocation data)
+    imports: Vec<(u32, usize)>,
+}
+
+fn parse_rel(data: &[u8]) -> Result<RelInfo> {
+    ensure!(data.len() >= 0x40, "REL header too small");
+    let module_id = read_u32(data, 0x00)?;
+    let num_sections = read_u32(data, 0x0C)? as usize;
+    let section_info_offset = read_u32(data, 0x10)? as usize;
+    let imp_offset = read_u32(data, 0x28)? as usize;
+    let imp_size = read_u32(data, 0x2C)? as usize;
+
+    let mut sections = Vec::new();
+    for i in 0..num_sections {
+        let offset = read_u32(data, section_info_offset + i * 8)? as usize;
+        let size = read_u32(data, section_info_offset + i * 8 + 4)? as usize;
+        if size == 0 {
+            continue;
+        }
+        let name =
+            REL_SECTION_NAMES.get(i).map_or_else(|| format!(".section{i}"), |s| s.to_string());
+        let executable = offset & 1 != 0;
+        let offset = offset & !3;
+        if offset == 0 {
+            sections.push(BinarySection {
+                name,
+                kind: ObjSectionKind::Bss,
+                address: 0,
+                data: vec![0; size],
+                orig_index: i,
+            });
+            continue;
+        }
+        let Some(section_data) = data.get(offset..offset + size) else {
+            bail!("REL section {} out of bounds", i);
+        };
+        sections.push(BinarySection {
+            name,
+            kind: if executable { ObjSectionKind::Code } else { ObjSectionKind::Data },
+            address: 0,
+            data: section_data.to_vec(),
+            orig_index: i,
+        });
+    }
+
+    let mut imports = Vec::new();
+    for i in 0..imp_size / 8 {
+        let module = read_u32(data, imp_offset + i * 8)?;
+        let offset = read_u32(data, imp_offset + i * 8 + 4)? as usize;
+        imports.push((module, offset));
+    }
+    Ok(RelInfo { module_id, sections, imports })
+}
+
+const R_DOLPHIN_NOP: u8 = 201;
+const R_DOLPHIN_SECTION: u8 = 202;
+const R_DOLPHIN_END: u8 = 203;
+
+/// A REL relocation, before resolving the target symbol.
+struct RelReloc {
+    section: usize,
+    offset: u64,
+    r_type: u32,
+    module: u32,
+    target_section: usize,
+    addend: u64,
+}
+
+fn parse_rel_relocations(data: &[u8], imports: &[(u32, usize)]) -> Result<Vec<RelReloc>> {
+    let mut out = Vec::new();
+    for &(module, mut offset) in imports {
+        let mut section = 0usize;
+        let mut address = 0u64;
+        loop {
+            let delta = read_u16(data, offset)? as u64;
+            let Some(&r_type) = data.get(offset + 2) else {
+                bail!("Unexpected end of file reading relocations");
+            };
+            let Some(&target_section) = data.get(offset + 3) else {
+                bail!("Unexpected end of file reading relocations");
+            };
+            let addend = read_u32(data, offset + 4)? as u64;
+            offset += 8;
+            address += delta;
+            match r_type {
+                R_DOLPHIN_NOP => {}
+                R_DOLPHIN_SECTION => {
+                    section = target_section as usize;
+                    address = 0;
+                }
+                R_DOLPHIN_END => break,
+                r_type => out.push(RelReloc {
+                    section,
+                    offset: address,
+                    r_type: r_type as u32,
+                    module,
+                    target_section: target_section as usize,
+                    addend,
+                }),
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Relocation types supported by the PPC architecture
+fn is_supported_reloc(r_type: u32) -> bool {
+    matches!(
+        r_type,
+        elf::R_PPC_ADDR32
+            | elf::R_PPC_UADDR32
+            | elf::R_PPC_ADDR16_LO
+            | elf::R_PPC_ADDR16_HI
+            | elf::R_PPC_ADDR16_HA
+            | elf::R_PPC_REL24
+            | elf::R_PPC_REL14
+            | elf::R_PPC_EMB_SDA21
+    )
+}
+
+fn to_obj_symbol(
+    arch: &dyn ObjArch,
+    symbol: &MapSymbol,
+    address: u64,
+    virtual_address: Option<u64>,
+) -> ObjSymbol {
+    ObjSymbol {
+        name: symbol.name.clone(),
+        demangled_name: arch.demangle(&symbol.name),
+        address,
+        section_address: address,
+        size: symbol.size.unwrap_or(0),
+        size_known: symbol.size.is_some(),
+        flags: ObjSymbolFlagSet(ObjSymbolFlags::Global.into()),
+        addend: 0,
+        virtual_address,
+    }
+}
+
+fn unknown_symbol(name: String) -> ObjSymbol {
+    ObjSymbol {
+        name,
+        demangled_name: None,
+        address: 0,
+        section_address: 0,
+        size: 0,
+        size_known: false,
+        flags: Default::default(),
+        addend: 0,
+        virtual_address: None,
+    }
+}
+
+fn infer_sizes(section: &mut ObjSection) {
+    section.symbols.sort_by_key(|s| s.address);
+    let end = section.address + section.size;
+    let mut iter = section.symbols.iter_mut().peekable();
+    while let Some(symbol) = iter.next() {
+        if symbol.size == 0 {
+            let next = iter.peek().map_or(end, |s| s.address);
+            symbol.size = next.saturating_sub(symbol.address);
+        }
+    }
+}
+
+/// Synthesizes `R_PPC_REL24` relocations for branches to symbols in the map, so that
+/// calls can be compared against relocated calls in a locally built object.
+fn branch_relocations(
+    arch: &dyn ObjArch,
+    section: &ObjSection,
+    base: u64,
+    by_address: &BTreeMap<u64, &MapSymbol>,
+) -> Vec<ObjReloc> {
+    let mut out = Vec::new();
+    for symbol in &section.symbols {
+        let start = symbol.section_address as usize;
+        let end = (start + symbol.size as usize).min(section.data.len());
+        for offset in (start..end).step_by(4) {
+            let Some(bytes) = section.data.get(offset..offset + 4) else {
+                break;
+            };
+            let ins = u32::from_be_bytes(bytes.try_into().unwrap());
+            // b, bl (relative)
+            if ins >> 26 != 18 || ins & 2 != 0 {
+                continue;
+            }
+            let disp = ((ins & 0x3FFFFFC) << 6) as i32 >> 6;
+            let pc = base + offset as u64;
+            let dest = pc.wrapping_add_signed(disp as i64) & 0xFFFFFFFF;
+            // Branches within the function aren't relocated
+            if (base + start as u64..base + end as u64).contains(&dest) {
+                continue;
+            }
+            let Some(target) = by_address.get(&dest) else {
+                continue;
+            };
+            out.push(ObjReloc {
+                flags: RelocationFlags::Elf { r_type: elf::R_PPC_REL24 },
+                address: offset as u64,
+                target: to_obj_symbol(arch, target, 0, Some(dest)),
+                target_section: target.section.clone(),
+                via: None,
+            });
+        }
+    }
+    out
+}
+
+/// Container format of a linked binary.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BinaryKind {
+    Dol,
+    Rel,
+    Raw,
+}
+
+impl BinaryKind {
+    /// Guesses the format from the file extension, falling back to a raw binary.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()) {
+            Some(ext) if ext == "dol" => BinaryKind::Dol,
+            Some(ext) if ext == "rel" => BinaryKind::Rel,
+            _ => BinaryKind::Raw,
+        }
+    }
+}
+
+/// Reads a linked binary, creating symbols from the symbol map.
+pub fn read(path: &Path, map: &SymbolMapConfig) -> Result<ObjInfo> {
+    let (data, timestamp) = vfs::read(path)?;
+    let map_text = fs::read_to_string(&map.path)
+        .with_context(|| format!("Failed to read symbol map {}", map.path.display()))?;
+    let mut obj = parse(&data, BinaryKind::from_path(path), &map_text, map)?;
+    obj.path = path.to_owned();
+    obj.timestamp = timestamp;
+    Ok(obj)
+}
+
+/// Parses a linked binary from memory, creating symbols from the symbol map contents.
+/// The returned object has no path or timestamp set.
+pub fn parse(
+    data: &[u8],
+    kind: BinaryKind,
+    map_text: &str,
+    map: &SymbolMapConfig,
+) -> Result<ObjInfo> {
+    let all_symbols = parse_map(map_text);
+    let symbols = all_symbols
+        .iter()
+        .filter(|s| match (&map.unit, &s.unit) {
+            (Some(unit), Some(s_unit)) => s_unit == unit,
+            (Some(_), None) => false,
+            (None, _) => true,
+        })
+        .collect::<Vec<_>>();
+    ensure!(!symbols.is_empty(), "No symbols found in symbol map {}", map.path.display());
+    // Symbols by virtual address, including symbols outside of the unit
+    let by_address = all_symbols.iter().map(|s| (s.address, s)).collect::<BTreeMap<_, _>>();
+
+    let arch = new_binary_arch(Architecture::PowerPc)?;
+    let (binary_sections, rel) = match kind {
+        BinaryKind::Dol => (parse_dol(data)?, None),
+        BinaryKind::Rel => {
+            let mut rel = parse_rel(data)?;
+            (std::mem::take(&mut rel.sections), Some(rel))
+        }
+        BinaryKind::Raw => {
+            let section = BinarySection {
+                name: ".text".to_string(),
+                kind: ObjSectionKind::Code,
+                address: map.base_address.unwrap_or(0),
+                data: data.to_vec(),
+                orig_index: 0,
+            };
+            (vec![section], None)
+        }
+    };
+
+    let mut sections = Vec::new();
+    for binary_section in binary_sections {
+        let end = binary_section.address + binary_section.data.len() as u64;
+        let section_symbols = symbols
+            .iter()
+            .filter(|s| {
+                if rel.is_some() {
+                    s.section.as_deref() == Some(binary_section.name.as_str()) && s.address < end
+                } else {
+                    (binary_section.address..end).contains(&s.address)
+                }
+            })
+            .collect::<Vec<_>>();
+        if section_symbols.is_empty() {
+            continue;
+        }
+        // Use the map's section name for DOL sections
+        let name = match (&rel, section_symbols.first().and_then(|s| s.section.as_ref())) {
+            (None, Some(name)) => name.clone(),
+            _ => binary_section.name,
+        };
+        let virtual_address = rel.is_none().then_some(binary_section.address);
+        let mut section = ObjSection {
+            name,
+            kind: binary_section.kind,
+            address: 0,
+            size: binary_section.data.len() as u64,
+            align: None,
+            data: binary_section.data,
+            orig_index: binary_section.orig_index,
+            symbols: section_symbols
+                .iter()
+                .map(|s| {
+                    let offset = s.address - binary_section.address;
+                    to_obj_symbol(arch.as_ref(), s, offset, virtual_address.map(|_| s.address))
+                })
+                .collect(),
+            relocations: Vec::new(),
+            virtual_address,
+            line_info: Default::default(),
+            file_info: Default::default(),
+        };
+        infer_sizes(&mut section);
+        if section.kind == ObjSectionKind::Code && rel.is_none() {
+            section.relocations =
+                branch_relocations(arch.as_ref(), &section, binary_section.address, &by_address);
+        }
+        sections.push(section);
+    }
+
+    if let Some(rel) = &rel {
+        let relocations = parse_rel_relocations(data, &rel.imports)?;
+        for reloc in relocations {
+            let Some(section) = sections.iter_mut().find(|s| s.orig_index == reloc.section) else {
+                continue;
+            };
+            if !is_supported_reloc(reloc.r_type) {
+                log::warn!(
+                    "Unsupported REL relocation type {} at {:#x}",
+                    reloc.r_type,
+                    reloc.offset
+                );
+                continue;
+            }
+            let target_section_name = REL_SECTION_NAMES.get(reloc.target_section).copied();
+            let target = if reloc.module == rel.module_id {
+                all_symbols
+                    .iter()
+                    .find(|s| {
+                        s.section.as_deref() == target_section_name && s.address == reloc.addend
+                    })
+                    .map(|s| to_obj_symbol(arch.as_ref(), s, s.address, None))
+                    .unwrap_or_else(|| {
+                        let mut symbol =
+                            unknown_symbol(target_section_name.unwrap_or("<unknown>").to_string());
+                        symbol.addend = reloc.addend as i64;
+                        symbol
+                    })
+            } else if reloc.module == 0 {
+                // Absolute address in the DOL
+                by_address
+                    .get(&reloc.addend)
+                    .map(|s| to_obj_symbol(arch.as_ref(), s, 0, Some(reloc.addend)))
+                    .unwrap_or_else(|| {
+                        let prefix = if reloc.r_type == elf::R_PPC_REL24 { "fn" } else { "lbl" };
+                        unknown_symbol(format!("{prefix}_{:08X}", reloc.addend))
+                    })
+            } else {
+                unknown_symbol(format!(
+                    "lbl_{}_{}_{:X}",
+                    reloc.module, reloc.target_section, reloc.addend
+                ))
+            };
+            section.relocations.push(ObjReloc {
+                flags: RelocationFlags::Elf { r_type: reloc.r_type },
+                address: reloc.offset,
+                target,
+                target_section: target_section_name.map(str::to_string),
+                via: None,
+            });
+        }
+        for section in &mut sections {
+            section.relocations.sort_by_key(|r| r.address);
+        }
+    }
+
+    let split_meta =
+        rel.as_ref().map(|rel| SplitMeta { module_id: Some(rel.module_id), ..Default::default() });
+    sections.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(ObjInfo {
+        arch,
+        path: PathBuf::new(),
+        timestamp: FileTime::zero(),
+        sections,
+        split_meta,
+        source_files: Vec::new(),
+        accepted_ranges: Vec::new(),
+    })
+}
+
+/// Reads a unit's target: a byte range of a file, a linked binary with a symbol map,
+/// or an object file.
+pub fn read_target(
+    path: &Path,
+    map: Option<&SymbolMapConfig>,
+    range: Option<&FileRange>,
+) -> Result<ObjInfo> {
+    match (range, map) {
+        (Some(range), map) => read_range(path, range, map),
+        (None, Some(map)) => read(path, map),
+        (None, None) => super::read::read(path),
+    }
+}
+
+/// Reads a byte range of a file, creating symbols from the symbol map if given.
+pub fn read_range(
+    path: &Path,
+    range: &FileRange,
+    map: Option<&SymbolMapConfig>,
+) -> Result<ObjInfo> {
+    let (data, timestamp) = vfs::read(path)?;
+    let map_text = map
+        .map(|map| {
+            fs::read_to_string(&map.path)
+                .with_context(|| format!("Failed to read symbol map {}", map.path.display()))
+        })
+        .transpose()?;
+    let mut obj = parse_range(&data, range, map.zip(map_text.as_deref()))?;
+    obj.path = path.to_owned();
+    obj.timestamp = timestamp;
+    Ok(obj)
+}
+
+/// Parses a byte range of a file from memory, creating symbols from the symbol map
+/// contents if given. The returned object has no path or timestamp set.
+pub fn parse_range(
+    data: &[u8],
+    range: &FileRange,
+    map: Option<(&SymbolMapConfig, &str)>,
+) -> Result<ObjInfo> {
+    let start = range.offset as usize;
+    let Some(data) = data.get(start..start + range.size as usize) else {
+        bail!("Range {:#x}..{:#x} is out of bounds", range.offset, range.offset + range.size);
+    };
+    let arch = match range.arch {
+        RangeArch::Ppc => new_binary_arch(Architecture::PowerPc)?,
+        RangeArch::Mips => new_binary_arch(Architecture::Mips)?,
+        RangeArch::Rsp => new_rsp_arch()?,
+    };
+    let end = range.load_address + range.size;
+    let mut symbols = match map {
+        Some((map, text)) => parse_map(text)
+            .iter()
+            .filter(|s| (range.load_address..end).contains(&s.address))
+            .filter(|s| match (&map.unit, &s.unit) {
+                (Some(unit), Some(s_unit)) => s_unit == unit,
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .map(|s| {
+                let offset = s.address - range.load_address;
+                to_obj_symbol(arch.as_ref(), s, offset, Some(s.address))
+            })
+            .collect::<Vec<_>>(),
+        None => vec![],
+    };
+    if symbols.is_empty() {
+        let name =
+            range.symbol.clone().unwrap_or_else(|| format!("range_{:08X}", range.load_address));
+        let symbol = MapSymbol {
+            name,
+            section: None,
+            address: range.load_address,
+            size: Some(range.size),
+            unit: None,
+        };
+        symbols.push(to_obj_symbol(arch.as_ref(), &symbol, 0, Some(range.load_address)));
+    }
+    let mut section = ObjSection {
+        name: ".text".to_string(),
+        kind: ObjSectionKind::Code,
+        address: 0,
+        size: range.size,
+        align: None,
+        data: data.to_vec(),
+        orig_index: 0,
+        symbols,
+        relocations: Vec::new(),
+        virtual_address: Some(range.load_address),
+        line_info: Default::default(),
+        file_info: Default::default(),
+    };
+    infer_sizes(&mut section);
+    Ok(ObjInfo {
+        arch,
+        path: PathBuf::new(),
+        timestamp: FileTime::zero(),
+        sections: vec![section],
+        split_meta: None,
+        source_files: Vec::new(),
+        accepted_ranges: Vec::new(),
+    })
+}
@@ -0,0 +1,88 @@
+use crate::obj::{ObjInfo, ObjSymbol};
+
+/// Symbol name demangling scheme.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    Eq,
+    PartialEq,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::VariantArray,
+    strum::EnumMessage,
+)]
+pub enum Demangler {
+    /// Use the scheme preferred by the object's architecture
+    #[default]
+    #[strum(message = "Auto (default)")]
+    Auto,
+    #[strum(message = "Itanium (GCC, Clang)")]
+    Itanium,
+    #[strum(message = "MSVC")]
+    Msvc,
+    #[strum(message = "CodeWarrior")]
+    CodeWarrior,
+    #[strum(message = "None")]
+    None,
+}
+
+impl Demangler {
+    /// Demangles a symbol name. [`Demangler::Auto`] returns `None`, as it's
+    /// resolved by [`ObjArch::demangle`](crate::arch::ObjArch::demangle).
+    pub fn demangle(self, name: &str) -> Option<String> {
+        match self {
+            Demangler::Auto | Demangler::None => None,
+            Demangler::Itanium => demangle_itanium(name),
+            Demangler::Msvc => demangle_msvc(name),
+            Demangler::CodeWarrior => demangle_codewarrior(name),
+        }
+    }
+}
+
+#[cfg(feature = "cpp_demangle")]
+fn demangle_itanium(name: &str) -> Option<String> {
+    // Mach-O symbols carry an extra leading underscore
+    let name = name.strip_prefix('_').filter(|n| n.starts_with("_Z")).unwrap_or(name);
+    cpp_demangle::Symbol::new(name)
+        .ok()
+        .and_then(|s| s.demangle(&cpp_demangle::DemangleOptions::default()).ok())
+}
+
+#[cfg(not(feature = "cpp_demangle"))]
+fn demangle_itanium(_name: &str) -> Option<String> { None }
+
+#[cfg(feature = "msvc-demangler")]
+fn demangle_msvc(name: &str) -> Option<String> {
+    if !name.starts_with('?') {
+        return None;
+    }
+    msvc_demangler::demangle(name, msvc_demangler::DemangleFlags::llvm()).ok()
+}
+
+#[cfg(not(feature = "msvc-demangler"))]
+fn demangle_msvc(_name: &str) -> Option<String> { None }
+
+#[cfg(feature = "cwdemangle")]
+fn demangle_codewarrior(name: &str) -> Option<String> {
+    cwdemangle::demangle(name, &cwdemangle::DemangleOptions::default())
+}
+
+#[cfg(not(feature = "cwdemangle"))]
+fn demangle_codewarrior(_name: &str) -> Option<String> { None }
+
+/// Recomputes the demangled names of all symbols in the object using the given scheme.
+/// The raw name is always left as-is in [`ObjSymbol::name`].
+pub fn demangle_symbols(obj: &mut ObjInfo, demangler: Demangler) {
+    if demangler == Demangler::Auto {
+        return;
+    }
+    let demangle = |symbol: &mut ObjSymbol| {
+        symbol.demangled_name = demangler.demangle(&symbol.name);
+    };
+    for section in &mut obj.sections {
+        section.symbols.iter_mut().for_each(demangle);
+        section.relocations.iter_mut().map(|r| &mut r.target).for_each(demangle);
+    }
+}
@@ -1,14 +1,22 @@
+pub mod binary;
+pub mod demangle;
 pub mod read;
+pub mod source;
 pub mod split_meta;
+pub mod veneer;
 
-use std::{borrow::Cow, collections::BTreeMap, fmt, path::PathBuf};
+use std::{borrow::Cow, collections::BTreeMap, fmt, ops::Range, path::PathBuf};
 
 use filetime::FileTime;
 use flagset::{flags, FlagSet};
 use object::RelocationFlags;
 use split_meta::SplitMeta;
 
-use crate::{arch::ObjArch, util::ReallySigned};
+use crate::{
+    arch::ObjArch,
+    diff::{NumberFormat, NumberRadix},
+    util::ReallySigned,
+};
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum ObjSectionKind {
@@ -34,13 +42,17 @@ pub struct ObjSection {
     pub kind: ObjSectionKind,
     pub address: u64,
     pub size: u64,
+    /// Required alignment, if known
+    pub align: Option<u64>,
     pub data: Vec<u8>,
     pub orig_index: usize,
     pub symbols: Vec<ObjSymbol>,
     pub relocations: Vec<ObjReloc>,
     pub virtual_address: Option<u64>,
-    /// Line number info (.line or .debug_line section)
+    /// Line number info (.line, .debug_line or .stab section)
     pub line_info: BTreeMap<u64, u64>,
+    /// Source file info, as indices into [`ObjInfo::source_files`]
+    pub file_info: BTreeMap<u64, usize>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -63,12 +75,78 @@ impl ObjInsArgValue {
     }
 }
 
+impl ObjInsArgValue {
+    /// Display the value with the given number format.
+    pub fn display(&self, format: NumberFormat) -> ObjInsArgValueDisplay<'_> {
+        ObjInsArgValueDisplay { value: self, format }
+    }
+}
+
+/// Displays the value in hexadecimal, with signed values shown as negative.
 impl fmt::Display for ObjInsArgValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.display(NumberFormat::default()), f)
+    }
+}
+
+pub struct ObjInsArgValueDisplay<'a> {
+    value: &'a ObjInsArgValue,
+    format: NumberFormat,
+}
+
+impl fmt::Display for ObjInsArgValueDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let NumberFormat { radix, unsigned, min_digits } = self.format;
+        let digits = min_digits as usize;
+        let value = match *self.value {
+            // Two's complement at 32 bits, unless the value doesn't fit
+            ObjInsArgValue::Signed(v) if v < 0 && unsigned && v >= i32::MIN as i64 => {
+                v as u32 as u64
+            }
+            ObjInsArgValue::Signed(v) if v < 0 && unsigned => v as u64,
+            ObjInsArgValue::Signed(v) => {
+                // Width includes the sign and prefix
+                let sign = (v < 0) as usize;
+                return match radix {
+                    NumberRadix::Hex => {
+                        write!(f, "{:#0w$x}", ReallySigned(v), w = digits + sign + 2)
+                    }
+                    NumberRadix::Decimal => write!(f, "{:0w$}", v, w = digits + sign),
+                };
+            }
+            ObjInsArgValue::Unsigned(v) => v,
+            ObjInsArgValue::Opaque(ref v) => return write!(f, "{}", v),
+        };
+        match radix {
+            NumberRadix::Hex => write!(f, "{:#0w$x}", value, w = digits + 2),
+            NumberRadix::Decimal => write!(f, "{:0w$}", value, w = digits),
+        }
+    }
+}
+
+/// A floating-point constant loaded from a data section, such as a constant pool entry.
+#[derive(Debug, Copy, Clone)]
+pub enum ObjConstant {
+    Float(f32),
+    Double(f64),
+}
+
+/// Constants are compared by their bits, so that NaNs compare equal.
+impl PartialEq for ObjConstant {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ObjConstant::Float(a), ObjConstant::Float(b)) => a.to_bits() == b.to_bits(),
+            (ObjConstant::Double(a), ObjConstant::Double(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ObjConstant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ObjInsArgValue::Signed(v) => write!(f, "{:#x}", ReallySigned(*v)),
-            ObjInsArgValue::Unsigned(v) => write!(f, "{:#x}", v),
-            ObjInsArgValue::Opaque(v) => write!(f, "{}", v),
+            ObjConstant::Float(v) => write!(f, "{v:?}f"),
+            ObjConstant::Double(v) => write!(f, "{v:?}"),
         }
     }
 }
@@ -79,6 +157,8 @@ pub enum ObjInsArg {
     Arg(ObjInsArgValue),
     Reloc,
     BranchDest(u64),
+    /// Raw bytes that couldn't be decoded
+    Bytes(Vec<u8>),
 }
 
 impl ObjInsArg {
@@ -87,6 +167,7 @@ impl ObjInsArg {
             (ObjInsArg::Arg(a), ObjInsArg::Arg(b)) => a.loose_eq(b),
             (ObjInsArg::Reloc, ObjInsArg::Reloc) => true,
             (ObjInsArg::BranchDest(a), ObjInsArg::BranchDest(b)) => a == b,
+            (ObjInsArg::Bytes(a), ObjInsArg::Bytes(b)) => a == b,
             _ => false,
         }
     }
@@ -123,15 +204,72 @@ pub struct ObjSymbol {
     pub virtual_address: Option<u64>,
 }
 
+impl ObjSymbol {
+    /// Returns the demangled name if requested and available, otherwise the raw name.
+    pub fn display_name(&self, demangled: bool) -> &str {
+        match &self.demangled_name {
+            Some(name) if demangled => name,
+            _ => &self.name,
+        }
+    }
+}
+
+/// Mapping of target symbol names to base symbol names.
+pub type SymbolMappings = BTreeMap<String, String>;
+
+/// How the loader sizes symbols that have no size in the object.
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::VariantArray,
+    strum::EnumMessage,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeInference {
+    /// Extend to the next symbol or the end of the section
+    #[default]
+    #[strum(message = "Next symbol")]
+    NextSymbol,
+    /// Functions end at their last return instruction, excluding the padding after it.
+    /// Other symbols extend to the next symbol.
+    #[strum(message = "Function end")]
+    FunctionEnd,
+}
+
+/// An instruction or address range in a target function whose mismatches are accepted,
+/// such as compiler bugs that can never be matched.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AcceptedMismatch {
+    /// Target function symbol name
+    pub symbol: String,
+    /// Offset of the first accepted instruction from the start of the function
+    pub start: u64,
+    /// End offset of the range (exclusive). If unset, only the instruction at `start`.
+    #[serde(default)]
+    pub end: Option<u64>,
+    /// Description of the known issue
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
 pub struct ObjInfo {
     pub arch: Box<dyn ObjArch>,
     pub path: PathBuf,
     pub timestamp: FileTime,
     pub sections: Vec<ObjSection>,
-    /// Common BSS symbols
-    pub common: Vec<ObjSymbol>,
     /// Split object metadata (.note.split section)
     pub split_meta: Option<SplitMeta>,
+    /// Source files referenced by line info
+    pub source_files: Vec<String>,
+    /// Address ranges with accepted mismatches, by section index
+    pub accepted_ranges: Vec<(usize, Range<u64>)>,
 }
 
 #[derive(Debug, Clone)]
@@ -140,6 +278,8 @@ pub struct ObjReloc {
     pub address: u64,
     pub target: ObjSymbol,
     pub target_section: Option<String>,
+    /// Call stub the relocation originally targeted, when resolved to the stub's callee
+    pub via: Option<String>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
@@ -150,10 +290,6 @@ pub struct SymbolRef {
 
 impl ObjInfo {
     pub fn section_symbol(&self, symbol_ref: SymbolRef) -> (Option<&ObjSection>, &ObjSymbol) {
-        if symbol_ref.section_idx == self.sections.len() {
-            let symbol = &self.common[symbol_ref.symbol_idx];
-            return (None, symbol);
-        }
         let section = &self.sections[symbol_ref.section_idx];
         let symbol = &section.symbols[symbol_ref.symbol_idx];
         (Some(section), symbol)
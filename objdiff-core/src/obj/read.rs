@@ -1,20 +1,34 @@
-use std::{fs, io::Cursor, path::Path};
+use std::{
+    fs,
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+};
 
 use anyhow::{anyhow, bail, ensure, Context, Result};
 use byteorder::{BigEndian, ReadBytesExt};
 use filetime::FileTime;
 use flagset::Flags;
 use object::{
-    BinaryFormat, File, Object, ObjectSection, ObjectSymbol, RelocationTarget, SectionIndex,
-    SectionKind, Symbol, SymbolKind, SymbolScope, SymbolSection,
+    macho,
+    read::{
+        archive::ArchiveFile,
+        macho::{MachHeader, Section as _, Segment as _},
+    },
+    Architecture, BinaryFormat, Endian, Endianness, File, Object, ObjectSection, ObjectSymbol,
+    RelocationFlags, RelocationTarget, SectionIndex, SectionKind, Symbol, SymbolIndex, SymbolKind,
+    SymbolScope, SymbolSection,
 };
 
 use crate::{
     arch::{new_arch, ObjArch},
+    diff::DiffObjConfig,
     obj::{
         split_meta::{SplitMeta, SPLITMETA_SECTION},
-        ObjInfo, ObjReloc, ObjSection, ObjSectionKind, ObjSymbol, ObjSymbolFlagSet, ObjSymbolFlags,
+        veneer::resolve_veneers,
+        AcceptedMismatch, ObjInfo, ObjIns, ObjReloc, ObjSection, ObjSectionKind, ObjSymbol,
+        ObjSymbolFlagSet, ObjSymbolFlags, SizeInference, SymbolMappings, SymbolRef,
     },
+    vfs,
 };
 
 fn to_obj_section_kind(kind: SectionKind) -> Option<ObjSectionKind> {
@@ -61,6 +75,9 @@ fn to_obj_symbol(
     } else {
         symbol.address()
     };
+    // Mach-O sections are laid out sequentially, normalize to section-relative addresses
+    let address =
+        if obj_file.format() == BinaryFormat::MachO { section_address } else { symbol.address() };
     let demangled_name = arch.demangle(name);
     // Find the virtual address for the symbol if available
     let virtual_address = split_meta
@@ -69,7 +86,7 @@ fn to_obj_symbol(
     Ok(ObjSymbol {
         name: name.to_string(),
         demangled_name,
-        address: symbol.address(),
+        address,
         section_address,
         size: symbol.size(),
         size_known: symbol.size() != 0,
@@ -104,14 +121,16 @@ fn filter_sections(obj_file: &File<'_>, split_meta: Option<&SplitMeta>) -> Resul
         result.push(ObjSection {
             name: name.to_string(),
             kind,
-            address: section.address(),
+            address: if obj_file.format() == BinaryFormat::MachO { 0 } else { section.address() },
             size: section.size(),
+            align: Some(section.align()),
             data: data.to_vec(),
             orig_index: section.index().0,
             symbols: Vec::new(),
             relocations: Vec::new(),
             virtual_address,
             line_info: Default::default(),
+            file_info: Default::default(),
         });
     }
     result.sort_by(|a, b| a.name.cmp(&b.name));
@@ -134,7 +153,15 @@ fn symbols_by_section(
                 if symbol.is_local() && section.kind == ObjSectionKind::Code {
                     // TODO strip local syms in diff?
                     let name = symbol.name().context("Failed to process symbol name")?;
-                    if symbol.size() == 0 || name.starts_with("lbl_") {
+                    if name.starts_with("lbl_") {
+                        continue;
+                    }
+                    if obj_file.format() == BinaryFormat::MachO {
+                        // Mach-O symbols are unsized, only skip assembler-local labels
+                        if name.starts_with('L') {
+                            continue;
+                        }
+                    } else if symbol.size() == 0 {
                         continue;
                     }
                 }
@@ -156,27 +183,61 @@ fn symbols_by_section(
     Ok(result)
 }
 
-fn common_symbols(
+/// Allocate common symbols in a synthetic `.comm` BSS section, as the linker would, so that
+/// they pair with symbols that the other object defines in `.bss`.
+fn common_section(
     arch: &dyn ObjArch,
     obj_file: &File<'_>,
     split_meta: Option<&SplitMeta>,
-) -> Result<Vec<ObjSymbol>> {
-    obj_file
-        .symbols()
-        .filter(Symbol::is_common)
-        .map(|symbol| to_obj_symbol(arch, obj_file, &symbol, 0, split_meta))
-        .collect::<Result<Vec<ObjSymbol>>>()
+) -> Result<Option<ObjSection>> {
+    let mut symbols = Vec::new();
+    let mut size = 0;
+    let mut section_align = 1;
+    for symbol in obj_file.symbols().filter(Symbol::is_common) {
+        // ELF commons hold their alignment in place of an address, COFF commons their size
+        let (symbol_size, align) = if obj_file.format() == BinaryFormat::Elf {
+            (symbol.size(), symbol.address().max(1))
+        } else {
+            let symbol_size = if symbol.size() != 0 { symbol.size() } else { symbol.address() };
+            (symbol_size, symbol_size.next_power_of_two().clamp(1, 8))
+        };
+        let address = (size + align - 1) / align * align;
+        let mut obj_symbol = to_obj_symbol(arch, obj_file, &symbol, 0, split_meta)?;
+        obj_symbol.address = address;
+        obj_symbol.section_address = address;
+        obj_symbol.size = symbol_size;
+        obj_symbol.size_known = symbol_size != 0;
+        symbols.push(obj_symbol);
+        size = address + symbol_size;
+        section_align = section_align.max(align);
+    }
+    if symbols.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(ObjSection {
+        name: ".comm".to_string(),
+        kind: ObjSectionKind::Bss,
+        address: 0,
+        size,
+        align: Some(section_align),
+        data: Vec::new(),
+        // Not backed by a section of the object
+        orig_index: usize::MAX,
+        symbols,
+        relocations: Vec::new(),
+        virtual_address: None,
+        line_info: Default::default(),
+        file_info: Default::default(),
+    }))
 }
 
 fn find_section_symbol(
     arch: &dyn ObjArch,
     obj_file: &File<'_>,
-    target: &Symbol<'_, '_>,
+    section_index: SectionIndex,
     address: u64,
     split_meta: Option<&SplitMeta>,
 ) -> Result<ObjSymbol> {
-    let section_index =
-        target.section_index().ok_or_else(|| anyhow::Error::msg("Unknown section index"))?;
     let section = obj_file.section_by_index(section_index)?;
     let mut closest_symbol: Option<Symbol<'_, '_>> = None;
     for symbol in obj_file.symbols() {
@@ -203,7 +264,11 @@ fn find_section_symbol(
     Ok(ObjSymbol {
         name: name.to_string(),
         demangled_name: None,
-        address: offset,
+        address: if obj_file.format() == BinaryFormat::MachO {
+            offset - section.address()
+        } else {
+            offset
+        },
         section_address: address - section.address(),
         size: 0,
         size_known: false,
@@ -216,12 +281,20 @@ fn find_section_symbol(
 fn relocations_by_section(
     arch: &dyn ObjArch,
     obj_file: &File<'_>,
+    data: &[u8],
     section: &ObjSection,
     split_meta: Option<&SplitMeta>,
 ) -> Result<Vec<ObjReloc>> {
+    if obj_file.format() == BinaryFormat::MachO && obj_file.architecture() == Architecture::PowerPc
+    {
+        return macho_ppc_relocations(arch, obj_file, data, section, split_meta);
+    }
     let obj_section = obj_file.section_by_index(SectionIndex(section.orig_index))?;
     let mut relocations = Vec::<ObjReloc>::new();
     for (address, reloc) in obj_section.relocations() {
+        if arch.ignore_relocation(reloc.flags()) {
+            continue;
+        }
         let symbol = match reloc.target() {
             RelocationTarget::Symbol(idx) => {
                 if idx.0 == u32::MAX as usize {
@@ -260,16 +333,241 @@ fn relocations_by_section(
             }
             SymbolKind::Section => {
                 ensure!(addend >= 0, "Negative addend in reloc: {addend}");
-                find_section_symbol(arch, obj_file, &symbol, addend as u64, split_meta)
+                let section_index = symbol
+                    .section_index()
+                    .ok_or_else(|| anyhow::Error::msg("Unknown section index"))?;
+                find_section_symbol(arch, obj_file, section_index, addend as u64, split_meta)
             }
             kind => Err(anyhow!("Unhandled relocation symbol type {kind:?}")),
         }?;
-        relocations.push(ObjReloc { flags, address, target, target_section });
+        relocations.push(ObjReloc { flags, address, target, target_section, via: None });
+    }
+    Ok(relocations)
+}
+
+/// Mach-O relocation target, before resolving to a symbol.
+enum MachORelocTarget {
+    Symbol(SymbolIndex),
+    Section(SectionIndex),
+    /// Scattered relocations reference an address instead of a symbol
+    Address(u64),
+}
+
+fn macho_section_relocations<'data, Mach: MachHeader<Endian = Endianness>>(
+    data: &'data [u8],
+    section_index: SectionIndex,
+) -> Result<(&'data [macho::Relocation<Endianness>], Endianness)> {
+    let header = Mach::parse(data, 0)?;
+    let endian = header.endian()?;
+    let mut commands = header.load_commands(endian, data, 0)?;
+    // Section indices start at 1
+    let mut index = 1;
+    while let Some(command) = commands.next()? {
+        let Some((segment, segment_data)) = Mach::Segment::from_command(command)? else {
+            continue;
+        };
+        for section in segment.sections(endian, segment_data)? {
+            if index == section_index.0 {
+                return Ok((section.relocations(endian, data)?, endian));
+            }
+            index += 1;
+        }
+    }
+    bail!("Mach-O section {} not found", section_index.0)
+}
+
+/// Reads PPC Mach-O relocations from the raw relocation table. `object` skips scattered
+/// relocations and doesn't combine half relocations with their `PPC_RELOC_PAIR`, which
+/// holds the other half of the target address (or the subtrahend for `*_SECTDIFF`).
+fn macho_ppc_relocations(
+    arch: &dyn ObjArch,
+    obj_file: &File<'_>,
+    data: &[u8],
+    section: &ObjSection,
+    split_meta: Option<&SplitMeta>,
+) -> Result<Vec<ObjReloc>> {
+    let section_index = SectionIndex(section.orig_index);
+    let (raw_relocations, endian) = if obj_file.is_64() {
+        macho_section_relocations::<macho::MachHeader64<Endianness>>(data, section_index)?
+    } else {
+        macho_section_relocations::<macho::MachHeader32<Endianness>>(data, section_index)?
+    };
+    let cputype =
+        if obj_file.is_64() { macho::CPU_TYPE_POWERPC64 } else { macho::CPU_TYPE_POWERPC };
+    let section_base = obj_file.section_by_index(section_index)?.address();
+
+    let mut relocations = Vec::<ObjReloc>::new();
+    let mut iter = raw_relocations.iter();
+    while let Some(reloc) = iter.next() {
+        let (address, r_type, r_pcrel, r_length, target) = if reloc.r_scattered(endian, cputype) {
+            let info = reloc.scattered_info(endian);
+            let target = MachORelocTarget::Address(info.r_value as u64);
+            (info.r_address, info.r_type, info.r_pcrel, info.r_length, target)
+        } else {
+            let info = reloc.info(endian);
+            let target = if info.r_extern {
+                MachORelocTarget::Symbol(SymbolIndex(info.r_symbolnum as usize))
+            } else {
+                MachORelocTarget::Section(SectionIndex(info.r_symbolnum as usize))
+            };
+            (info.r_address, info.r_type, info.r_pcrel, info.r_length, target)
+        };
+        if r_type == macho::PPC_RELOC_PAIR {
+            log::warn!("Unexpected PPC_RELOC_PAIR at {:#x}", address);
+            continue;
+        }
+        // Read the paired relocation: (other half, subtrahend address)
+        let pair = if macho_ppc_has_pair(r_type) {
+            let Some(pair) = iter.next() else {
+                bail!("Missing PPC_RELOC_PAIR for relocation at {:#x}", address);
+            };
+            if pair.r_scattered(endian, cputype) {
+                let info = pair.scattered_info(endian);
+                (info.r_address, Some(info.r_value as u64))
+            } else {
+                (pair.info(endian).r_address, None)
+            }
+        } else {
+            (0, None)
+        };
+
+        let value = macho_ppc_reloc_value(section, section_base, address, r_type, pair.0)?;
+        // For section differences, the encoded value is relative to the subtrahend
+        let value = match pair.1 {
+            Some(subtrahend) if macho_ppc_is_sectdiff(r_type) => value.wrapping_add(subtrahend),
+            _ => value,
+        };
+        let (target, target_section) = match target {
+            MachORelocTarget::Symbol(idx) => {
+                let symbol = obj_file.symbol_by_index(idx)?;
+                let addend = value.wrapping_sub(symbol.address()) as i32 as i64;
+                let target_section = match symbol.section() {
+                    SymbolSection::Common => Some(".comm".to_string()),
+                    SymbolSection::Section(idx) => obj_file
+                        .section_by_index(idx)
+                        .and_then(|s| s.name().map(|s| s.to_string()))
+                        .ok(),
+                    _ => None,
+                };
+                (to_obj_symbol(arch, obj_file, &symbol, addend, split_meta)?, target_section)
+            }
+            MachORelocTarget::Section(idx) => {
+                let target_section = obj_file.section_by_index(idx)?;
+                let name = target_section.name()?.to_string();
+                (find_section_symbol(arch, obj_file, idx, value, split_meta)?, Some(name))
+            }
+            MachORelocTarget::Address(r_value) => {
+                let Some(target_section) = obj_file
+                    .sections()
+                    .find(|s| (s.address()..s.address() + s.size().max(1)).contains(&r_value))
+                else {
+                    log::warn!("Failed to locate scattered relocation {:#x} target", address);
+                    continue;
+                };
+                let name = target_section.name()?.to_string();
+                let symbol =
+                    find_section_symbol(arch, obj_file, target_section.index(), value, split_meta)?;
+                (symbol, Some(name))
+            }
+        };
+        relocations.push(ObjReloc {
+            flags: RelocationFlags::MachO { r_type, r_pcrel, r_length },
+            address: address as u64,
+            target,
+            target_section,
+            via: None,
+        });
     }
+    relocations.sort_by_key(|r| r.address);
     Ok(relocations)
 }
 
-fn line_info(obj_file: &File<'_>, sections: &mut [ObjSection]) -> Result<()> {
+fn macho_ppc_has_pair(r_type: u8) -> bool {
+    matches!(
+        r_type,
+        macho::PPC_RELOC_HI16
+            | macho::PPC_RELOC_LO16
+            | macho::PPC_RELOC_HA16
+            | macho::PPC_RELOC_LO14
+            | macho::PPC_RELOC_SECTDIFF
+            | macho::PPC_RELOC_HI16_SECTDIFF
+            | macho::PPC_RELOC_LO16_SECTDIFF
+            | macho::PPC_RELOC_HA16_SECTDIFF
+            | macho::PPC_RELOC_LO14_SECTDIFF
+            | macho::PPC_RELOC_LOCAL_SECTDIFF
+    )
+}
+
+fn macho_ppc_is_sectdiff(r_type: u8) -> bool {
+    matches!(
+        r_type,
+        macho::PPC_RELOC_SECTDIFF
+            | macho::PPC_RELOC_HI16_SECTDIFF
+            | macho::PPC_RELOC_LO16_SECTDIFF
+            | macho::PPC_RELOC_HA16_SECTDIFF
+            | macho::PPC_RELOC_LO14_SECTDIFF
+            | macho::PPC_RELOC_LOCAL_SECTDIFF
+    )
+}
+
+/// Reconstructs the address encoded at a PPC Mach-O relocation. Half relocations
+/// combine the instruction's immediate with the other half stored in the pair.
+fn macho_ppc_reloc_value(
+    section: &ObjSection,
+    section_base: u64,
+    address: u32,
+    r_type: u8,
+    other_half: u32,
+) -> Result<u64> {
+    let offset = address as usize;
+    let Some(bytes) = section.data.get(offset..offset + 4) else {
+        bail!("Relocation {:#x} out of bounds", address);
+    };
+    let word = u32::from_be_bytes(bytes.try_into().unwrap());
+    let pc = section_base + address as u64;
+    let other_half = other_half & 0xFFFF;
+    Ok(match r_type {
+        macho::PPC_RELOC_VANILLA | macho::PPC_RELOC_SECTDIFF | macho::PPC_RELOC_LOCAL_SECTDIFF => {
+            word as u64
+        }
+        macho::PPC_RELOC_BR24 => {
+            let disp = ((word & 0x3FFFFFC) << 6) as i32 >> 6;
+            pc.wrapping_add_signed(disp as i64) as u32 as u64
+        }
+        macho::PPC_RELOC_BR14 => {
+            let disp = (word & 0xFFFC) as u16 as i16;
+            pc.wrapping_add_signed(disp as i64) as u32 as u64
+        }
+        macho::PPC_RELOC_HI16 | macho::PPC_RELOC_HI16_SECTDIFF => {
+            (((word & 0xFFFF) << 16) | other_half) as u64
+        }
+        macho::PPC_RELOC_HA16 | macho::PPC_RELOC_HA16_SECTDIFF => {
+            ((word & 0xFFFF) << 16).wrapping_add(other_half as u16 as i16 as i32 as u32) as u64
+        }
+        macho::PPC_RELOC_LO16 | macho::PPC_RELOC_LO16_SECTDIFF => {
+            ((other_half << 16) | (word & 0xFFFF)) as u64
+        }
+        macho::PPC_RELOC_LO14 | macho::PPC_RELOC_LO14_SECTDIFF => {
+            ((other_half << 16) | (word & 0xFFFC)) as u64
+        }
+        _ => bail!("Unsupported PPC Mach-O relocation type {r_type} at {:#x}", address),
+    })
+}
+
+fn add_source_file(source_files: &mut Vec<String>, path: String) -> usize {
+    if let Some(idx) = source_files.iter().position(|p| *p == path) {
+        idx
+    } else {
+        source_files.push(path);
+        source_files.len() - 1
+    }
+}
+
+fn line_info(
+    obj_file: &File<'_>,
+    sections: &mut [ObjSection],
+    source_files: &mut Vec<String>,
+) -> Result<()> {
     // DWARF 1.1
     if let Some(section) = obj_file.section_by_name(".line") {
         let data = section.uncompressed_data()?;
@@ -333,26 +631,32 @@ fn line_info(obj_file: &File<'_>, sections: &mut [ObjSection]) -> Result<()> {
                     .ok_or_else(|| anyhow!("Next text section not found for line info"))?
                     .index()
                     .0;
-                let mut lines = sections
-                    .iter_mut()
-                    .find(|s| s.orig_index == section_index)
-                    .map(|s| &mut s.line_info);
+                let mut section_idx = sections.iter().position(|s| s.orig_index == section_index);
 
+                let mut files = std::collections::HashMap::new();
                 let mut rows = program.rows();
-                while let Some((_header, row)) = rows.next_row()? {
-                    if let (Some(line), Some(lines)) = (row.line(), &mut lines) {
-                        lines.insert(row.address(), line.get());
+                while let Some((header, row)) = rows.next_row()? {
+                    if let (Some(line), Some(section_idx)) = (row.line(), section_idx) {
+                        let section = &mut sections[section_idx];
+                        section.line_info.insert(row.address(), line.get());
+                        let file_idx = match files.get(&row.file_index()) {
+                            Some(&idx) => Some(idx),
+                            None => dwarf_file_name(&dwarf, &unit, header, row).map(|path| {
+                                let idx = add_source_file(source_files, path);
+                                files.insert(row.file_index(), idx);
+                                idx
+                            }),
+                        };
+                        if let Some(file_idx) = file_idx {
+                            section.file_info.insert(row.address(), file_idx);
+                        }
                     }
                     if row.end_sequence() {
                         // The next row is the start of a new sequence, which means we must
                         // advance to the next .text section.
                         let section_index = text_sections.next().map(|s| s.index().0);
-                        lines = section_index.map(|index| {
-                            &mut sections
-                                .iter_mut()
-                                .find(|s| s.orig_index == index)
-                                .unwrap()
-                                .line_info
+                        section_idx = section_index.map(|index| {
+                            sections.iter().position(|s| s.orig_index == index).unwrap()
                         });
                     }
                 }
@@ -363,16 +667,219 @@ fn line_info(obj_file: &File<'_>, sections: &mut [ObjSection]) -> Result<()> {
         }
     }
 
+    // STABS
+    stabs_line_info(obj_file, sections, source_files)?;
+
     Ok(())
 }
 
-pub fn read(obj_path: &Path) -> Result<ObjInfo> {
-    let (data, timestamp) = {
-        let file = fs::File::open(obj_path)?;
-        let timestamp = FileTime::from_last_modification_time(&file.metadata()?);
-        (unsafe { memmap2::Mmap::map(&file) }?, timestamp)
+#[cfg(feature = "dwarf")]
+fn dwarf_file_name<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    header: &gimli::LineProgramHeader<R>,
+    row: &gimli::LineRow,
+) -> Option<String> {
+    let file = row.file(header)?;
+    let name = dwarf.attr_string(unit, file.path_name()).ok()?;
+    let name = name.to_string_lossy().ok()?.into_owned();
+    if name.starts_with('/') || name.contains(':') {
+        return Some(name);
+    }
+    let dir = file
+        .directory(header)
+        .and_then(|dir| dwarf.attr_string(unit, dir).ok())
+        .and_then(|dir| dir.to_string_lossy().ok().map(|s| s.into_owned()));
+    match dir {
+        Some(dir) if !dir.is_empty() => Some(format!("{}/{}", dir.trim_end_matches('/'), name)),
+        _ => Some(name),
+    }
+}
+
+const N_FUN: u8 = 0x24;
+const N_SLINE: u8 = 0x44;
+const N_SO: u8 = 0x64;
+const N_SOL: u8 = 0x84;
+
+fn read_cstr(data: &[u8], offset: usize) -> &str {
+    data.get(offset..)
+        .and_then(|s| s.split(|&b| b == 0).next())
+        .and_then(|s| std::str::from_utf8(s).ok())
+        .unwrap_or_default()
+}
+
+/// Reads line info from STABS debug info (.stab and .stabstr sections).
+fn stabs_line_info(
+    obj_file: &File<'_>,
+    sections: &mut [ObjSection],
+    source_files: &mut Vec<String>,
+) -> Result<()> {
+    let (Some(stab), Some(stabstr)) =
+        (obj_file.section_by_name(".stab"), obj_file.section_by_name(".stabstr"))
+    else {
+        return Ok(());
     };
-    let obj_file = File::parse(&*data)?;
+    let data = stab.uncompressed_data()?;
+    let strings = stabstr.uncompressed_data()?;
+    let endian = obj_file.endianness();
+
+    let mut str_base = 0usize;
+    let mut next_str_base = 0usize;
+    let mut so_dir = String::new();
+    let mut current_file = None;
+    let mut function = None;
+    for entry in data.chunks_exact(12) {
+        let n_strx = endian.read_u32_bytes(entry[0..4].try_into().unwrap()) as usize;
+        let n_type = entry[4];
+        let n_desc = endian.read_u16_bytes(entry[6..8].try_into().unwrap());
+        let n_value = endian.read_u32_bytes(entry[8..12].try_into().unwrap());
+        match n_type {
+            // Unit header: the value is the size of the unit's string table
+            0 => {
+                str_base = next_str_base;
+                next_str_base += n_value as usize;
+            }
+            N_SO => {
+                let name = read_cstr(&strings, str_base + n_strx);
+                if name.is_empty() {
+                    current_file = None;
+                } else if name.ends_with('/') {
+                    so_dir = name.to_string();
+                } else {
+                    let path = if name.starts_with('/') || name.contains(':') {
+                        name.to_string()
+                    } else {
+                        format!("{so_dir}{name}")
+                    };
+                    current_file = Some(add_source_file(source_files, path));
+                }
+            }
+            N_SOL => {
+                let name = read_cstr(&strings, str_base + n_strx);
+                current_file = Some(add_source_file(source_files, name.to_string()));
+            }
+            N_FUN => {
+                // Function addresses are often unrelocated, so resolve them by name
+                let name = read_cstr(&strings, str_base + n_strx);
+                let name = name.split(':').next().unwrap_or_default();
+                function = if name.is_empty() {
+                    None
+                } else {
+                    sections.iter().enumerate().find_map(|(section_idx, section)| {
+                        if section.kind != ObjSectionKind::Code {
+                            return None;
+                        }
+                        let symbol = section.symbols.iter().find(|s| s.name == name)?;
+                        Some((section_idx, symbol.address))
+                    })
+                };
+            }
+            N_SLINE => {
+                if let Some((section_idx, address)) = function {
+                    let section = &mut sections[section_idx];
+                    let address = address + n_value as u64;
+                    section.line_info.insert(address, n_desc as u64);
+                    if let Some(file_idx) = current_file {
+                        section.file_info.insert(address, file_idx);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Splits a path of the form `libfoo.a(bar.o)` into the archive path and member name.
+pub fn split_archive_path(path: &Path) -> Option<(PathBuf, &str)> {
+    let path_str = path.to_str()?;
+    let inner = path_str.strip_suffix(')')?;
+    let (archive, member) = inner.rsplit_once('(')?;
+    if archive.is_empty() || member.is_empty() {
+        return None;
+    }
+    Some((PathBuf::from(archive), member))
+}
+
+/// Returns the path of the object or archive, stripping any archive member name. The
+/// file may be [within a container](crate::vfs).
+pub fn archive_file_path(path: &Path) -> PathBuf {
+    match split_archive_path(path) {
+        Some((archive, _)) => archive,
+        None => path.to_path_buf(),
+    }
+}
+
+/// Returns the path of the file on disk, stripping any archive member name and path
+/// within a container.
+pub fn file_path(path: &Path) -> PathBuf { vfs::host_path(&archive_file_path(path)) }
+
+/// Whether the file is an `ar` archive (GNU, BSD or MSVC).
+pub fn is_archive(path: &Path) -> bool {
+    let is_magic =
+        |magic: &[u8]| magic == object::archive::MAGIC || magic == object::archive::THIN_MAGIC;
+    if vfs::is_container_path(path) {
+        // Only extract files that look like archives from their container
+        let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+        return matches!(ext.as_deref(), Some("a" | "lib"))
+            && vfs::read(path).is_ok_and(|(data, _)| data.get(..8).is_some_and(is_magic));
+    }
+    let mut magic = [0u8; 8];
+    fs::File::open(path).and_then(|mut f| f.read_exact(&mut magic)).is_ok() && is_magic(&magic)
+}
+
+/// Lists the object members of an archive.
+pub fn archive_members(path: &Path) -> Result<Vec<String>> {
+    let (data, _) = map_file(path)?;
+    parse_archive_members(&data)
+        .with_context(|| format!("Failed to parse archive {}", path.display()))
+}
+
+/// Lists the object members of an archive in memory.
+pub fn parse_archive_members(data: &[u8]) -> Result<Vec<String>> {
+    let archive = ArchiveFile::parse(data)?;
+    let mut members = Vec::new();
+    for member in archive.members() {
+        let member = member?;
+        let name = String::from_utf8_lossy(member.name()).into_owned();
+        // Skip MSVC short import members and other non-object members
+        if member.data(data).is_ok_and(|d| File::parse(d).is_ok()) {
+            members.push(name);
+        }
+    }
+    Ok(members)
+}
+
+pub(crate) fn map_file(path: &Path) -> Result<(vfs::FileData, FileTime)> { vfs::read(path) }
+
+/// Returns the object data, extracting the archive member if the path refers to one.
+pub(crate) fn object_data<'a>(data: &'a [u8], obj_path: &Path) -> Result<&'a [u8]> {
+    let Some((archive_path, member_name)) = split_archive_path(obj_path) else {
+        return Ok(data);
+    };
+    let archive = ArchiveFile::parse(data)
+        .with_context(|| format!("Failed to parse archive {}", archive_path.display()))?;
+    for member in archive.members() {
+        let member = member?;
+        if member.name() == member_name.as_bytes() {
+            return Ok(member.data(data)?);
+        }
+    }
+    bail!("Member {} not found in archive {}", member_name, archive_path.display())
+}
+
+pub fn read(obj_path: &Path) -> Result<ObjInfo> {
+    let (data, timestamp) = map_file(&archive_file_path(obj_path))?;
+    let mut obj = parse(object_data(&data, obj_path)?)?;
+    obj.path = obj_path.to_owned();
+    obj.timestamp = timestamp;
+    Ok(obj)
+}
+
+/// Parses an object file from memory. The returned object has no path or timestamp set;
+/// archive members must be extracted by the caller.
+pub fn parse(data: &[u8]) -> Result<ObjInfo> {
+    let obj_file = File::parse(data)?;
     let arch = new_arch(&obj_file)?;
     let split_meta = split_meta(&obj_file)?;
     let mut sections = filter_sections(&obj_file, split_meta.as_ref())?;
@@ -380,19 +887,122 @@ pub fn read(obj_path: &Path) -> Result<ObjInfo> {
         section.symbols =
             symbols_by_section(arch.as_ref(), &obj_file, section, split_meta.as_ref())?;
         section.relocations =
-            relocations_by_section(arch.as_ref(), &obj_file, section, split_meta.as_ref())?;
+            relocations_by_section(arch.as_ref(), &obj_file, data, section, split_meta.as_ref())?;
     }
-    line_info(&obj_file, &mut sections)?;
-    let common = common_symbols(arch.as_ref(), &obj_file, split_meta.as_ref())?;
-    Ok(ObjInfo { arch, path: obj_path.to_owned(), timestamp, sections, common, split_meta })
+    let mut source_files = Vec::new();
+    line_info(&obj_file, &mut sections, &mut source_files)?;
+    sections.extend(common_section(arch.as_ref(), &obj_file, split_meta.as_ref())?);
+    let mut obj = ObjInfo {
+        arch,
+        path: PathBuf::new(),
+        timestamp: FileTime::zero(),
+        sections,
+        split_meta,
+        source_files,
+        accepted_ranges: Vec::new(),
+    };
+    resolve_veneers(&mut obj);
+    Ok(obj)
 }
 
-pub fn has_function(obj_path: &Path, symbol_name: &str) -> Result<bool> {
-    let data = {
-        let file = fs::File::open(obj_path)?;
-        unsafe { memmap2::Mmap::map(&file) }?
+/// Rename symbols using the provided mappings, so that the object can be paired with
+/// another object using different names. Relocation targets are renamed as well.
+pub fn apply_symbol_mappings(obj: &mut ObjInfo, mappings: &SymbolMappings) {
+    if mappings.is_empty() {
+        return;
+    }
+    let arch = obj.arch.as_ref();
+    let rename = |symbol: &mut ObjSymbol| {
+        if let Some(name) = mappings.get(&symbol.name) {
+            symbol.name.clone_from(name);
+            symbol.demangled_name = arch.demangle(name);
+        }
     };
-    Ok(File::parse(&*data)?
+    for section in &mut obj.sections {
+        section.symbols.iter_mut().for_each(rename);
+        section.relocations.iter_mut().map(|r| &mut r.target).for_each(rename);
+    }
+}
+
+/// Mark target instruction ranges whose mismatches are accepted. Entries refer to the
+/// object's own symbol names, so this must be applied before [`apply_symbol_mappings`].
+pub fn apply_accepted_mismatches(obj: &mut ObjInfo, accepted: &[AcceptedMismatch]) {
+    for entry in accepted {
+        let found = obj.sections.iter().enumerate().find_map(|(section_idx, section)| {
+            section.symbols.iter().find(|s| s.name == entry.symbol).map(|s| (section_idx, s))
+        });
+        let Some((section_idx, symbol)) = found else {
+            log::warn!("Accepted mismatch references unknown symbol {}", entry.symbol);
+            continue;
+        };
+        let start = symbol.address + entry.start;
+        let end = symbol.address + entry.end.unwrap_or(entry.start + 1);
+        obj.accepted_ranges.push((section_idx, start..end));
+    }
+}
+
+/// Resize symbols that have no size in the object. The loader extends them to the next
+/// symbol; with [`SizeInference::FunctionEnd`], functions are trimmed to end at their last
+/// return instruction instead, so that the padding and any unnamed code after it are
+/// excluded.
+pub fn infer_symbol_sizes(obj: &mut ObjInfo, inference: SizeInference) {
+    if inference == SizeInference::NextSymbol {
+        return;
+    }
+    let config = DiffObjConfig::default();
+    let mut sizes = Vec::new();
+    for (section_idx, section) in obj.sections.iter().enumerate() {
+        if section.kind != ObjSectionKind::Code {
+            continue;
+        }
+        for (symbol_idx, symbol) in section.symbols.iter().enumerate() {
+            if symbol.size_known || symbol.size == 0 {
+                continue;
+            }
+            let symbol_ref = SymbolRef { section_idx, symbol_idx };
+            let Ok(code) = obj.arch.process_code(obj, symbol_ref, &config) else {
+                continue;
+            };
+            if let Some(end) = function_end(obj.arch.as_ref(), &code.insts) {
+                sizes.push((symbol_ref, end.saturating_sub(symbol.address)));
+            }
+        }
+    }
+    for (symbol_ref, size) in sizes {
+        let symbol = &mut obj.sections[symbol_ref.section_idx].symbols[symbol_ref.symbol_idx];
+        if size != 0 && size < symbol.size {
+            symbol.size = size;
+        }
+    }
+}
+
+/// End address of a function whose last instruction before any padding is a return,
+/// including the return's delay slot.
+fn function_end(arch: &dyn ObjArch, insts: &[ObjIns]) -> Option<u64> {
+    let last = insts.iter().rposition(|ins| !arch.is_padding(ins))?;
+    let (ret, end) = if arch.has_delay_slot(&insts[last]) {
+        // A nop delay slot counts as padding
+        (last, last + 1)
+    } else if last > 0 && arch.has_delay_slot(&insts[last - 1]) {
+        (last - 1, last)
+    } else {
+        (last, last)
+    };
+    if !arch.is_return(&insts[ret]) {
+        return None;
+    }
+    let end = insts.get(end)?;
+    Some(end.address + end.size as u64)
+}
+
+pub fn has_function(obj_path: &Path, symbol_name: &str) -> Result<bool> {
+    let (data, _) = map_file(&archive_file_path(obj_path))?;
+    data_has_function(object_data(&data, obj_path)?, symbol_name)
+}
+
+/// Whether the object file in memory defines a function named `symbol_name`.
+pub fn data_has_function(data: &[u8], symbol_name: &str) -> Result<bool> {
+    Ok(File::parse(data)?
         .symbol_by_name(symbol_name)
         .filter(|o| o.kind() == SymbolKind::Text)
         .is_some())
@@ -0,0 +1,74 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::obj::{ObjInfo, ObjSection};
+
+/// Source location of an address, resolved from line info.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SourceLocation {
+    /// Index into [`ObjInfo::source_files`], if known
+    pub file: Option<usize>,
+    pub line: u64,
+}
+
+impl ObjInfo {
+    /// Resolves the source location for an address within a section.
+    pub fn source_location(&self, section: &ObjSection, address: u64) -> Option<SourceLocation> {
+        let line = section.line_info.range(..=address).last().map(|(_, &l)| l)?;
+        let file = section.file_info.range(..=address).last().map(|(_, &f)| f);
+        Some(SourceLocation { file, line })
+    }
+
+    /// Path of the source file for a location, as recorded in the object.
+    pub fn source_file(&self, location: SourceLocation) -> Option<&str> {
+        location.file.and_then(|idx| self.source_files.get(idx)).map(String::as_str)
+    }
+}
+
+/// Lazily loads and caches source files referenced by line info.
+#[derive(Default)]
+pub struct SourceCache {
+    /// Directories used to resolve relative source paths
+    pub search_dirs: Vec<PathBuf>,
+    files: HashMap<String, Option<Vec<String>>>,
+}
+
+impl SourceCache {
+    pub fn new(search_dirs: Vec<PathBuf>) -> Self { Self { search_dirs, files: HashMap::new() } }
+
+    /// Returns the text of a (1-based) source line, or `None` if the file can't be found.
+    pub fn line(&mut self, path: &str, line: u64) -> Option<&str> {
+        let lines = self.lines(path)?;
+        let idx = usize::try_from(line).ok()?.checked_sub(1)?;
+        lines.get(idx).map(String::as_str)
+    }
+
+    /// Returns all lines of a source file, or `None` if the file can't be found.
+    pub fn lines(&mut self, path: &str) -> Option<&[String]> {
+        if !self.files.contains_key(path) {
+            let contents = self
+                .resolve(path)
+                .and_then(|p| fs::read(p).ok())
+                .map(|data| String::from_utf8_lossy(&data).lines().map(str::to_string).collect());
+            self.files.insert(path.to_string(), contents);
+        }
+        self.files.get(path).and_then(|f| f.as_deref())
+    }
+
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        // Paths recorded by Windows toolchains use backslashes
+        let path = path.replace('\\', "/");
+        let path = Path::new(&path);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+        self.search_dirs
+            .iter()
+            .map(|dir| dir.join(path))
+            .chain(std::iter::once(path.to_path_buf()))
+            .find(|p| p.is_file())
+    }
+}
@@ -0,0 +1,123 @@
+//! Call stubs (veneers, long-branch trampolines and PLT-like entries) placed between a
+//! call and its callee. Calls through a stub are resolved to the ultimate callee, so that
+//! code calling through a stub matches code calling the function directly.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    diff::DiffObjConfig,
+    obj::{ObjInfo, ObjSectionKind, ObjSymbol, SymbolRef},
+};
+
+/// Stubs are a few instructions, plus a literal for the target address on some targets.
+const MAX_STUB_SIZE: u64 = 24;
+
+/// Stubs calling stubs are followed at most this many times.
+const MAX_CHAIN: usize = 4;
+
+/// Whether the name is one the common linkers give to the stubs they generate.
+fn is_stub_name(name: &str) -> bool {
+    name.ends_with("_veneer")
+        || name.ends_with("@plt")
+        || name.starts_with("__long_branch_")
+        || name.starts_with("__plt_")
+        || (name.starts_with("__")
+            && (name.ends_with("_from_thumb")
+                || name.ends_with("_from_arm")
+                || name.ends_with("_change")))
+}
+
+/// The ultimate callee of a stub and its section.
+#[derive(Debug, Clone)]
+struct StubTarget {
+    target: ObjSymbol,
+    target_section: Option<String>,
+}
+
+/// Detect whether a code symbol is a stub. A stub only refers to a single symbol, through
+/// one or more relocations (such as a high/low address pair), and jumps to it through a
+/// register rather than returning. Linker-generated stubs are also recognized by name.
+fn detect_stub(obj: &ObjInfo, symbol_ref: SymbolRef) -> Option<StubTarget> {
+    let (section, symbol) = obj.section_symbol(symbol_ref);
+    let section = section.filter(|s| s.kind == ObjSectionKind::Code)?;
+    if symbol.size == 0 || symbol.size > MAX_STUB_SIZE {
+        return None;
+    }
+    let range = symbol.section_address..symbol.section_address + symbol.size;
+    let mut relocs = section.relocations.iter().filter(|r| range.contains(&r.address));
+    let first = relocs.next()?;
+    if relocs.any(|r| {
+        r.target.name != first.target.name
+            || r.target.addend != first.target.addend
+            || r.target_section != first.target_section
+    }) {
+        return None;
+    }
+    if first.target.name == symbol.name {
+        return None;
+    }
+    if !is_stub_name(&symbol.name) {
+        let code = obj.arch.process_code(obj, symbol_ref, &DiffObjConfig::default()).ok()?;
+        if code.insts.iter().any(|ins| obj.arch.is_return(ins))
+            || !code.insts.iter().any(|ins| obj.arch.is_indirect_jump(ins))
+        {
+            return None;
+        }
+    }
+    Some(StubTarget { target: first.target.clone(), target_section: first.target_section.clone() })
+}
+
+/// Point relocations that target a stub at its ultimate callee instead, noting the stub in
+/// [`ObjReloc::via`](crate::obj::ObjReloc::via). Relocations within the stubs themselves
+/// are left as they are.
+pub fn resolve_veneers(obj: &mut ObjInfo) {
+    let mut stubs = BTreeMap::<(Option<String>, String), StubTarget>::new();
+    let mut ranges = Vec::new();
+    for (section_idx, section) in obj.sections.iter().enumerate() {
+        if section.kind != ObjSectionKind::Code {
+            continue;
+        }
+        for (symbol_idx, symbol) in section.symbols.iter().enumerate() {
+            if let Some(stub) = detect_stub(obj, SymbolRef { section_idx, symbol_idx }) {
+                stubs.insert((Some(section.name.clone()), symbol.name.clone()), stub);
+                ranges.push((
+                    section_idx,
+                    symbol.section_address..symbol.section_address + symbol.size,
+                ));
+            }
+        }
+    }
+    if stubs.is_empty() {
+        return;
+    }
+    for (section_idx, section) in obj.sections.iter_mut().enumerate() {
+        for reloc in &mut section.relocations {
+            if ranges
+                .iter()
+                .any(|(idx, range)| *idx == section_idx && range.contains(&reloc.address))
+            {
+                continue;
+            }
+            let mut via = None;
+            let mut resolved = None;
+            let mut key = (reloc.target_section.clone(), reloc.target.name.clone());
+            for _ in 0..MAX_CHAIN {
+                let Some(stub) = stubs.get(&key) else {
+                    break;
+                };
+                via.get_or_insert_with(|| key.1.clone());
+                key = (stub.target_section.clone(), stub.target.name.clone());
+                resolved = Some(stub);
+            }
+            let Some(stub) = resolved else {
+                continue;
+            };
+            // Calls are made to the start of the stub; keep any addend of the call itself
+            let addend = reloc.target.addend;
+            reloc.target = stub.target.clone();
+            reloc.target.addend += addend;
+            reloc.target_section = stub.target_section.clone();
+            reloc.via = via;
+        }
+    }
+}
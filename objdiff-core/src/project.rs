@@ -0,0 +1,264 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use anyhow::{bail, Context, Result};
+use filetime::FileTime;
+
+use crate::{
+    config::{ProjectObject, TargetVersion},
+    diff::{diff_objs, DiffObjConfig, DiffObjsResult},
+    obj::{
+        binary,
+        demangle::{demangle_symbols, Demangler},
+        read, ObjInfo, ObjSectionKind,
+    },
+};
+
+/// The result of diffing a single unit.
+pub struct UnitDiff {
+    pub name: String,
+    pub target: Option<ObjInfo>,
+    pub base: Option<ObjInfo>,
+    pub diff: DiffObjsResult,
+}
+
+impl UnitDiff {
+    /// Code match percentage of the unit, weighted by function size.
+    pub fn match_percent(&self) -> Option<f32> {
+        let (obj, obj_diff) = match (&self.target, &self.diff.left) {
+            (Some(obj), Some(diff)) => (obj, diff),
+            _ => (self.base.as_ref()?, self.diff.right.as_ref()?),
+        };
+        let mut total = 0u64;
+        let mut matched = 0f64;
+        for (section, section_diff) in obj.sections.iter().zip(&obj_diff.sections) {
+            if section.kind != ObjSectionKind::Code {
+                continue;
+            }
+            for (symbol, symbol_diff) in section.symbols.iter().zip(&section_diff.symbols) {
+                total += symbol.size;
+                matched +=
+                    symbol.size as f64 * symbol_diff.match_percent.unwrap_or(0.0) as f64 / 100.0;
+            }
+        }
+        if total == 0 {
+            return Some(100.0);
+        }
+        Some((matched / total as f64 * 100.0) as f32)
+    }
+}
+
+/// Progress of a [`ProjectDiffer::diff_units`] run.
+#[derive(Debug, Clone)]
+pub struct DiffProgress<'a> {
+    /// Name of the unit that just finished
+    pub unit: &'a str,
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Shared flag used to cancel a running [`ProjectDiffer::diff_units`].
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) { self.0.store(true, Ordering::Relaxed); }
+
+    pub fn is_cancelled(&self) -> bool { self.0.load(Ordering::Relaxed) }
+}
+
+/// Modification state of an input file, used to invalidate cached results.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct FileStamp {
+    path: PathBuf,
+    mtime: FileTime,
+    len: u64,
+}
+
+impl FileStamp {
+    fn new(path: &Path) -> Result<Self> {
+        let path = read::file_path(path);
+        let metadata = fs::metadata(&path)
+            .with_context(|| format!("Failed to get metadata for {}", path.display()))?;
+        Ok(Self {
+            mtime: FileTime::from_last_modification_time(&metadata),
+            len: metadata.len(),
+            path,
+        })
+    }
+}
+
+struct CacheEntry {
+    object: ProjectObject,
+    stamps: Vec<FileStamp>,
+    result: Arc<UnitDiff>,
+}
+
+#[derive(Default)]
+struct Cache {
+    config: Option<(DiffObjConfig, Demangler)>,
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// Diffs project units concurrently, caching results. A unit is only re-diffed when
+/// its configuration or the modification time of one of its inputs changes.
+#[derive(Default)]
+pub struct ProjectDiffer {
+    cache: Mutex<Cache>,
+}
+
+impl ProjectDiffer {
+    pub fn new() -> Self { Self::default() }
+
+    /// Drops all cached results.
+    pub fn clear(&self) { *self.cache.lock().unwrap() = Cache::default(); }
+
+    /// Diff every unit using up to `threads` worker threads (0 for the number of CPUs).
+    /// Unit paths must already be resolved. `progress` is called from the worker threads
+    /// as each unit finishes. Results are returned in the order of `objects`.
+    pub fn diff_units(
+        &self,
+        objects: &[ProjectObject],
+        config: &DiffObjConfig,
+        demangler: Demangler,
+        threads: usize,
+        progress: impl Fn(DiffProgress) + Sync,
+        cancel: &CancelToken,
+    ) -> Result<Vec<Result<Arc<UnitDiff>>>> {
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if !matches!(&cache.config, Some((c, d)) if c == config && *d == demangler) {
+                cache.entries.clear();
+                cache.config = Some((config.clone(), demangler));
+            }
+        }
+
+        let threads = match threads {
+            0 => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            n => n,
+        }
+        .min(objects.len().max(1));
+        let next = AtomicUsize::new(0);
+        let completed = AtomicUsize::new(0);
+        let results = objects.iter().map(|_| Mutex::new(None)).collect::<Vec<_>>();
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    if cancel.is_cancelled() {
+                        break;
+                    }
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(object) = objects.get(idx) else {
+                        break;
+                    };
+                    let result = self.diff_unit(object, config, demangler);
+                    *results[idx].lock().unwrap() = Some(result);
+                    progress(DiffProgress {
+                        unit: object.name(),
+                        completed: completed.fetch_add(1, Ordering::Relaxed) + 1,
+                        total: objects.len(),
+                    });
+                });
+            }
+        });
+        if cancel.is_cancelled() {
+            bail!("Cancelled");
+        }
+        Ok(results.into_iter().map(|r| r.into_inner().unwrap().unwrap()).collect())
+    }
+
+    fn diff_unit(
+        &self,
+        object: &ProjectObject,
+        config: &DiffObjConfig,
+        demangler: Demangler,
+    ) -> Result<Arc<UnitDiff>> {
+        let name = object.name();
+        let stamps = [&object.target_path, &object.base_path]
+            .into_iter()
+            .flatten()
+            .map(|p| FileStamp::new(p))
+            .chain(object.target_map.as_ref().map(|m| FileStamp::new(&m.path)))
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(entry) = self.cache.lock().unwrap().entries.get(name) {
+            if entry.object == *object && entry.stamps == stamps {
+                return Ok(entry.result.clone());
+            }
+        }
+
+        let (target, base) = load_unit(object, demangler)?;
+        let diff = diff_objs(config, target.as_ref(), base.as_ref(), None)?;
+        let result = Arc::new(UnitDiff { name: name.to_string(), target, base, diff });
+        self.cache.lock().unwrap().entries.insert(name.to_string(), CacheEntry {
+            object: object.clone(),
+            stamps,
+            result: result.clone(),
+        });
+        Ok(result)
+    }
+}
+
+/// Load the target and base objects of a unit, applying the unit's accepted
+/// mismatches and symbol mappings.
+pub fn load_unit(
+    object: &ProjectObject,
+    demangler: Demangler,
+) -> Result<(Option<ObjInfo>, Option<ObjInfo>)> {
+    let target =
+        object.target_path.as_ref().map(|p| load_target(object, p, demangler)).transpose()?;
+    let base = object
+        .base_path
+        .as_ref()
+        .map(|p| {
+            let mut obj =
+                read::read(p).with_context(|| format!("Failed to open {}", p.display()))?;
+            read::infer_symbol_sizes(&mut obj, object.size_inference.unwrap_or_default());
+            demangle_symbols(&mut obj, demangler);
+            Ok::<_, anyhow::Error>(obj)
+        })
+        .transpose()?;
+    Ok((target, base))
+}
+
+/// Loads the target object of each of the project's target versions. Versions without
+/// an object for the unit are skipped.
+pub fn load_target_versions(
+    object: &ProjectObject,
+    project_dir: &Path,
+    versions: &[TargetVersion],
+    demangler: Demangler,
+) -> Result<Vec<(String, ObjInfo)>> {
+    let mut out = Vec::new();
+    for (name, path) in object.version_target_paths(project_dir, versions) {
+        if !read::file_path(&path).exists() {
+            continue;
+        }
+        let obj = load_target(object, &path, demangler)
+            .with_context(|| format!("Loading target version {name}"))?;
+        out.push((name, obj));
+    }
+    Ok(out)
+}
+
+fn load_target(object: &ProjectObject, path: &Path, demangler: Demangler) -> Result<ObjInfo> {
+    let mut obj =
+        binary::read_target(path, object.target_map.as_ref(), object.target_range.as_ref())
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+    read::infer_symbol_sizes(&mut obj, object.size_inference.unwrap_or_default());
+    if let Some(accepted) = &object.accepted_mismatches {
+        read::apply_accepted_mismatches(&mut obj, accepted);
+    }
+    if let Some(mappings) = &object.symbol_mappings {
+        read::apply_symbol_mappings(&mut obj, mappings);
+    }
+    demangle_symbols(&mut obj, demangler);
+    Ok(obj)
+}
@@ -0,0 +1,641 @@
+use std::{
+    collections::HashSet,
+    io::{Read, Write},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{
+    diff::{layout::diff_layout, DiffPolicyPreset, ObjDiff},
+    obj::{ObjInfo, ObjSectionKind, ObjSymbolFlags},
+    workflow::{WorkflowState, WorkflowStatus},
+};
+
+/// Current report schema version. Increment when making incompatible changes.
+pub const REPORT_VERSION: u32 = 1;
+
+/// Magic bytes identifying a binary report.
+const REPORT_MAGIC: [u8; 4] = *b"ODRP";
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Report {
+    /// Schema version. Reports without a version are treated as version 0.
+    #[serde(default)]
+    pub version: u32,
+    pub fuzzy_match_percent: f32,
+    pub total_code: u64,
+    pub matched_code: u64,
+    pub matched_code_percent: f32,
+    pub total_data: u64,
+    pub matched_data: u64,
+    pub matched_data_percent: f32,
+    pub total_functions: u32,
+    pub matched_functions: u32,
+    pub matched_functions_percent: f32,
+    #[serde(default)]
+    pub total_instructions: u64,
+    #[serde(default)]
+    pub matched_instructions: u64,
+    /// Scoring policy used for the fuzzy match percentages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<DiffPolicyPreset>,
+    pub units: Vec<ReportUnit>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ReportUnit {
+    pub name: String,
+    pub fuzzy_match_percent: f32,
+    pub total_code: u64,
+    pub matched_code: u64,
+    pub total_data: u64,
+    pub matched_data: u64,
+    pub total_functions: u32,
+    pub matched_functions: u32,
+    #[serde(default)]
+    pub total_instructions: u64,
+    #[serde(default)]
+    pub matched_instructions: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub complete: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module_id: Option<u32>,
+    /// Progress categories the unit belongs to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    /// The compiler invocation that produced the base object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compile_command: Option<CompileCommand>,
+    /// Whether the section sizes and symbol layout match the target. Unset if either
+    /// object is missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout_ok: Option<bool>,
+    /// Layout differences from the target
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub layout_issues: Vec<String>,
+    pub sections: Vec<ReportItem>,
+    pub functions: Vec<ReportItem>,
+}
+
+/// A compiler invocation, recorded so that a base object can be reproduced from the report.
+#[derive(Debug, Clone, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CompileCommand {
+    /// Working directory of the compiler, relative to the project directory if inside it
+    pub directory: String,
+    /// Source file, relative to `directory`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Full command line
+    pub command: String,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ReportItem {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub demangled_name: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_hex",
+        deserialize_with = "deserialize_hex"
+    )]
+    pub address: Option<u64>,
+    pub size: u64,
+    pub fuzzy_match_percent: f32,
+    /// Workflow status from the project's workflow state file, if marked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow: Option<WorkflowStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+}
+
+impl Report {
+    /// Calculate the project totals from the units.
+    pub fn calculate_progress(&mut self) {
+        self.version = REPORT_VERSION;
+        self.fuzzy_match_percent = 0.0;
+        self.total_code = 0;
+        self.matched_code = 0;
+        self.total_data = 0;
+        self.matched_data = 0;
+        self.total_functions = 0;
+        self.matched_functions = 0;
+        self.total_instructions = 0;
+        self.matched_instructions = 0;
+        for unit in &self.units {
+            self.fuzzy_match_percent += unit.fuzzy_match_percent * unit.total_code as f32;
+            self.total_code += unit.total_code;
+            self.matched_code += unit.matched_code;
+            self.total_data += unit.total_data;
+            self.matched_data += unit.matched_data;
+            self.total_functions += unit.total_functions;
+            self.matched_functions += unit.matched_functions;
+            self.total_instructions += unit.total_instructions;
+            self.matched_instructions += unit.matched_instructions;
+        }
+        if self.total_code == 0 {
+            self.fuzzy_match_percent = 100.0;
+        } else {
+            self.fuzzy_match_percent /= self.total_code as f32;
+        }
+        self.matched_code_percent = percent(self.matched_code, self.total_code);
+        self.matched_data_percent = percent(self.matched_data, self.total_data);
+        self.matched_functions_percent =
+            percent(self.matched_functions as u64, self.total_functions as u64);
+    }
+
+    /// Annotate the functions with their workflow status and assignee.
+    pub fn apply_workflow(&mut self, state: &WorkflowState) {
+        for unit in &mut self.units {
+            let Some(symbols) = state.units.get(&unit.name) else {
+                continue;
+            };
+            for function in &mut unit.functions {
+                if let Some(workflow) = symbols.get(&function.name) {
+                    function.workflow = Some(workflow.status);
+                    function.assignee.clone_from(&workflow.assignee);
+                }
+            }
+        }
+    }
+
+    /// Parse a report in either JSON or binary format.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.starts_with(&REPORT_MAGIC) {
+            Self::read_binary(&mut &data[..])
+        } else {
+            serde_json::from_slice(data).context("Failed to parse JSON report")
+        }
+    }
+
+    pub fn write_json<W: Write>(&self, w: W) -> Result<()> {
+        serde_json::to_writer_pretty(w, self)?;
+        Ok(())
+    }
+
+    /// Write the report in the compact binary format. All integers are little-endian; a
+    /// string is a `u32` byte length followed by UTF-8, an option is a `u8` 0 (none) or 1
+    /// followed by the value, and a list is a `u32` count followed by the items.
+    ///
+    /// - Header: magic `ODRP`, `u32` version, `f32` fuzzy match percent, `u64` total and
+    ///   matched code, `u64` total and matched data, `u32` total and matched functions, `u64`
+    ///   total and matched instructions, `u8` policy tag
+    /// - Units: list of name, the same totals as the header from the fuzzy match percent to
+    ///   the matched instructions, `u8` complete (0 unknown, 1 false, 2 true), optional module
+    ///   name, optional `u32` module id, list of categories, optional compile command
+    ///   (directory, optional file, command), `u8` layout ok (as complete), list of layout
+    ///   issues, then the section and function items
+    /// - Items: list of name, optional demangled name, optional `u64` address, `u64` size,
+    ///   `f32` fuzzy match percent, `u8` workflow tag (0 for none), optional assignee
+    pub fn write_binary<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(&REPORT_MAGIC)?;
+        w.write_u32::<LittleEndian>(REPORT_VERSION)?;
+        w.write_f32::<LittleEndian>(self.fuzzy_match_percent)?;
+        w.write_u64::<LittleEndian>(self.total_code)?;
+        w.write_u64::<LittleEndian>(self.matched_code)?;
+        w.write_u64::<LittleEndian>(self.total_data)?;
+        w.write_u64::<LittleEndian>(self.matched_data)?;
+        w.write_u32::<LittleEndian>(self.total_functions)?;
+        w.write_u32::<LittleEndian>(self.matched_functions)?;
+        w.write_u64::<LittleEndian>(self.total_instructions)?;
+        w.write_u64::<LittleEndian>(self.matched_instructions)?;
+        w.write_u8(self.policy.map_or(0, policy_tag))?;
+        w.write_u32::<LittleEndian>(self.units.len() as u32)?;
+        for unit in &self.units {
+            write_string(w, &unit.name)?;
+            w.write_f32::<LittleEndian>(unit.fuzzy_match_percent)?;
+            w.write_u64::<LittleEndian>(unit.total_code)?;
+            w.write_u64::<LittleEndian>(unit.matched_code)?;
+            w.write_u64::<LittleEndian>(unit.total_data)?;
+            w.write_u64::<LittleEndian>(unit.matched_data)?;
+            w.write_u32::<LittleEndian>(unit.total_functions)?;
+            w.write_u32::<LittleEndian>(unit.matched_functions)?;
+            w.write_u64::<LittleEndian>(unit.total_instructions)?;
+            w.write_u64::<LittleEndian>(unit.matched_instructions)?;
+            w.write_u8(match unit.complete {
+                None => 0,
+                Some(false) => 1,
+                Some(true) => 2,
+            })?;
+            write_option(w, unit.module_name.as_deref(), write_string)?;
+            write_option(w, unit.module_id, |w, v| Ok(w.write_u32::<LittleEndian>(v)?))?;
+            w.write_u32::<LittleEndian>(unit.categories.len() as u32)?;
+            for category in &unit.categories {
+                write_string(w, category)?;
+            }
+            write_option(w, unit.compile_command.as_ref(), |w, c| {
+                write_string(w, &c.directory)?;
+                write_option(w, c.file.as_deref(), write_string)?;
+                write_string(w, &c.command)
+            })?;
+            w.write_u8(match unit.layout_ok {
+                None => 0,
+                Some(false) => 1,
+                Some(true) => 2,
+            })?;
+            w.write_u32::<LittleEndian>(unit.layout_issues.len() as u32)?;
+            for issue in &unit.layout_issues {
+                write_string(w, issue)?;
+            }
+            write_items(w, &unit.sections)?;
+            write_items(w, &unit.functions)?;
+        }
+        Ok(())
+    }
+
+    /// Read a report in the compact binary format.
+    pub fn read_binary<R: Read>(r: &mut R) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if magic != REPORT_MAGIC {
+            bail!("Invalid binary report magic");
+        }
+        let version = r.read_u32::<LittleEndian>()?;
+        if version != REPORT_VERSION {
+            bail!("Unsupported report version {version} (expected {REPORT_VERSION})");
+        }
+        let mut report = Report {
+            version,
+            fuzzy_match_percent: r.read_f32::<LittleEndian>()?,
+            total_code: r.read_u64::<LittleEndian>()?,
+            matched_code: r.read_u64::<LittleEndian>()?,
+            total_data: r.read_u64::<LittleEndian>()?,
+            matched_data: r.read_u64::<LittleEndian>()?,
+            total_functions: r.read_u32::<LittleEndian>()?,
+            matched_functions: r.read_u32::<LittleEndian>()?,
+            total_instructions: r.read_u64::<LittleEndian>()?,
+            matched_instructions: r.read_u64::<LittleEndian>()?,
+            ..Default::default()
+        };
+        report.matched_code_percent = percent(report.matched_code, report.total_code);
+        report.matched_data_percent = percent(report.matched_data, report.total_data);
+        report.matched_functions_percent =
+            percent(report.matched_functions as u64, report.total_functions as u64);
+        report.policy = policy_from_tag(r.read_u8()?)?;
+        let unit_count = r.read_u32::<LittleEndian>()?;
+        for _ in 0..unit_count {
+            report.units.push(ReportUnit {
+                name: read_string(r)?,
+                fuzzy_match_percent: r.read_f32::<LittleEndian>()?,
+                total_code: r.read_u64::<LittleEndian>()?,
+                matched_code: r.read_u64::<LittleEndian>()?,
+                total_data: r.read_u64::<LittleEndian>()?,
+                matched_data: r.read_u64::<LittleEndian>()?,
+                total_functions: r.read_u32::<LittleEndian>()?,
+                matched_functions: r.read_u32::<LittleEndian>()?,
+                total_instructions: r.read_u64::<LittleEndian>()?,
+                matched_instructions: r.read_u64::<LittleEndian>()?,
+                complete: match r.read_u8()? {
+                    0 => None,
+                    1 => Some(false),
+                    2 => Some(true),
+                    v => bail!("Invalid complete value {v}"),
+                },
+                module_name: read_option(r, read_string)?,
+                module_id: read_option(r, |r| Ok(r.read_u32::<LittleEndian>()?))?,
+                categories: {
+                    let count = r.read_u32::<LittleEndian>()?;
+                    (0..count).map(|_| read_string(r)).collect::<Result<_>>()?
+                },
+                compile_command: read_option(r, |r| {
+                    Ok(CompileCommand {
+                        directory: read_string(r)?,
+                        file: read_option(r, read_string)?,
+                        command: read_string(r)?,
+                    })
+                })?,
+                layout_ok: match r.read_u8()? {
+                    0 => None,
+                    1 => Some(false),
+                    2 => Some(true),
+                    v => bail!("Invalid layout value {v}"),
+                },
+                layout_issues: {
+                    let count = r.read_u32::<LittleEndian>()?;
+                    (0..count).map(|_| read_string(r)).collect::<Result<_>>()?
+                },
+                sections: read_items(r)?,
+                functions: read_items(r)?,
+            });
+        }
+        Ok(report)
+    }
+}
+
+/// Per-function diff results of a unit, before deduplication. Stored in the
+/// [`DiffCache`](crate::cache::DiffCache).
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct UnitSummary {
+    pub module_name: Option<String>,
+    pub module_id: Option<u32>,
+    /// Layout differences from the target, if both objects exist
+    pub layout: Option<Vec<String>>,
+    pub sections: Vec<SectionSummary>,
+    pub functions: Vec<FunctionSummary>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SectionSummary {
+    pub name: String,
+    pub code: bool,
+    pub size: u64,
+    pub address: Option<u64>,
+    pub match_percent: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct FunctionSummary {
+    pub name: String,
+    pub demangled_name: Option<String>,
+    pub address: Option<u64>,
+    pub size: u64,
+    pub match_percent: Option<f32>,
+    pub instructions: u64,
+    /// Global or weak, and therefore subject to deduplication
+    pub global: bool,
+}
+
+impl UnitSummary {
+    /// Summarize the diff result of a target and base object. The layout is compared
+    /// separately, see [`UnitSummary::with_layout`].
+    pub fn from_diff(obj: &ObjInfo, obj_diff: &ObjDiff) -> Self {
+        let mut summary = UnitSummary {
+            module_name: obj.split_meta.as_ref().and_then(|m| m.module_name.clone()),
+            module_id: obj.split_meta.as_ref().and_then(|m| m.module_id),
+            ..Default::default()
+        };
+        for (section, section_diff) in obj.sections.iter().zip(&obj_diff.sections) {
+            summary.sections.push(SectionSummary {
+                name: section.name.clone(),
+                code: section.kind == ObjSectionKind::Code,
+                size: section.size,
+                address: section.virtual_address,
+                match_percent: section_diff.match_percent,
+            });
+            if section.kind != ObjSectionKind::Code {
+                continue;
+            }
+            for (symbol, symbol_diff) in section.symbols.iter().zip(&section_diff.symbols) {
+                if symbol.size == 0 {
+                    continue;
+                }
+                summary.functions.push(FunctionSummary {
+                    name: symbol.name.clone(),
+                    demangled_name: symbol.demangled_name.clone(),
+                    address: symbol.virtual_address,
+                    size: symbol.size,
+                    match_percent: symbol_diff.match_percent,
+                    instructions: symbol_diff
+                        .instructions
+                        .iter()
+                        .filter(|i| i.ins.is_some())
+                        .count() as u64,
+                    global: symbol.flags.0.contains(ObjSymbolFlags::Global)
+                        || symbol.flags.0.contains(ObjSymbolFlags::Weak),
+                });
+            }
+        }
+        summary
+    }
+
+    /// Records the layout differences between the target and base objects.
+    pub fn with_layout(mut self, target: &ObjInfo, base: &ObjInfo) -> Self {
+        self.layout = Some(diff_layout(target, base).iter().map(|i| i.to_string()).collect());
+        self
+    }
+}
+
+impl ReportUnit {
+    /// Generate a report unit from the diff result of a target and base object.
+    ///
+    /// If `existing_functions` is provided, global and weak functions already present
+    /// in the set are skipped, allowing deduplication across units.
+    pub fn from_diff(
+        name: String,
+        complete: Option<bool>,
+        obj: &ObjInfo,
+        obj_diff: &ObjDiff,
+        existing_functions: Option<&mut HashSet<String>>,
+    ) -> Self {
+        Self::from_summary(
+            name,
+            complete,
+            &UnitSummary::from_diff(obj, obj_diff),
+            existing_functions,
+        )
+    }
+
+    /// Generate a report unit from a summarized diff result. See [`ReportUnit::from_diff`].
+    pub fn from_summary(
+        name: String,
+        complete: Option<bool>,
+        summary: &UnitSummary,
+        mut existing_functions: Option<&mut HashSet<String>>,
+    ) -> Self {
+        let mut unit = ReportUnit {
+            name,
+            complete,
+            module_name: summary.module_name.clone(),
+            module_id: summary.module_id,
+            layout_ok: summary.layout.as_ref().map(|l| l.is_empty()),
+            layout_issues: summary.layout.clone().unwrap_or_default(),
+            ..Default::default()
+        };
+        // Support cases where we don't have a target object,
+        // assume complete means 100% match
+        let default_percent = if complete == Some(true) { 100.0 } else { 0.0 };
+        for section in &summary.sections {
+            let section_match_percent = section.match_percent.unwrap_or(default_percent);
+            unit.sections.push(ReportItem {
+                name: section.name.clone(),
+                demangled_name: None,
+                fuzzy_match_percent: section_match_percent,
+                size: section.size,
+                address: section.address,
+                ..Default::default()
+            });
+            if !section.code {
+                unit.total_data += section.size;
+                if section_match_percent == 100.0 {
+                    unit.matched_data += section.size;
+                }
+            }
+        }
+        for function in &summary.functions {
+            if let Some(existing_functions) = &mut existing_functions {
+                if function.global && !existing_functions.insert(function.name.clone()) {
+                    continue;
+                }
+            }
+            let match_percent = function.match_percent.unwrap_or(default_percent);
+            unit.fuzzy_match_percent += match_percent * function.size as f32;
+            unit.total_code += function.size;
+            unit.total_instructions += function.instructions;
+            if match_percent == 100.0 {
+                unit.matched_code += function.size;
+                unit.matched_instructions += function.instructions;
+            }
+            unit.functions.push(ReportItem {
+                name: function.name.clone(),
+                demangled_name: function.demangled_name.clone(),
+                size: function.size,
+                fuzzy_match_percent: match_percent,
+                address: function.address,
+                ..Default::default()
+            });
+            if match_percent == 100.0 {
+                unit.matched_functions += 1;
+            }
+            unit.total_functions += 1;
+        }
+        if unit.total_code == 0 {
+            unit.fuzzy_match_percent = 100.0;
+        } else {
+            unit.fuzzy_match_percent /= unit.total_code as f32;
+        }
+        unit
+    }
+}
+
+#[inline]
+fn percent(matched: u64, total: u64) -> f32 {
+    if total == 0 {
+        100.0
+    } else {
+        matched as f32 / total as f32 * 100.0
+    }
+}
+
+/// Stable binary tag of a policy preset. 0 stands for no preset.
+fn policy_tag(policy: DiffPolicyPreset) -> u8 {
+    match policy {
+        DiffPolicyPreset::Default => 1,
+        DiffPolicyPreset::IgnoreRegalloc => 2,
+        DiffPolicyPreset::Strict => 3,
+    }
+}
+
+fn policy_from_tag(tag: u8) -> Result<Option<DiffPolicyPreset>> {
+    Ok(Some(match tag {
+        0 => return Ok(None),
+        1 => DiffPolicyPreset::Default,
+        2 => DiffPolicyPreset::IgnoreRegalloc,
+        3 => DiffPolicyPreset::Strict,
+        v => bail!("Invalid policy value {v}"),
+    }))
+}
+
+/// Stable binary tag of a workflow status. 0 stands for no status.
+fn workflow_tag(status: WorkflowStatus) -> u8 {
+    match status {
+        WorkflowStatus::NotStarted => 1,
+        WorkflowStatus::InProgress => 2,
+        WorkflowStatus::NeedsReview => 3,
+        WorkflowStatus::Matched => 4,
+    }
+}
+
+fn workflow_from_tag(tag: u8) -> Result<Option<WorkflowStatus>> {
+    Ok(Some(match tag {
+        0 => return Ok(None),
+        1 => WorkflowStatus::NotStarted,
+        2 => WorkflowStatus::InProgress,
+        3 => WorkflowStatus::NeedsReview,
+        4 => WorkflowStatus::Matched,
+        v => bail!("Invalid workflow value {v}"),
+    }))
+}
+
+fn write_string<W: Write>(w: &mut W, s: &str) -> Result<()> {
+    w.write_u32::<LittleEndian>(s.len() as u32)?;
+    w.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+fn read_string<R: Read>(r: &mut R) -> Result<String> {
+    // Grow the buffer as data is read, as the length may be corrupt
+    let len = r.read_u32::<LittleEndian>()?;
+    let mut buf = Vec::new();
+    r.by_ref().take(len as u64).read_to_end(&mut buf)?;
+    ensure!(buf.len() == len as usize, "Report is truncated");
+    String::from_utf8(buf).context("Invalid UTF-8 string in report")
+}
+
+fn write_option<W: Write, T>(
+    w: &mut W,
+    value: Option<T>,
+    f: impl FnOnce(&mut W, T) -> Result<()>,
+) -> Result<()> {
+    match value {
+        Some(v) => {
+            w.write_u8(1)?;
+            f(w, v)
+        }
+        None => Ok(w.write_u8(0)?),
+    }
+}
+
+fn read_option<R: Read, T>(r: &mut R, f: impl FnOnce(&mut R) -> Result<T>) -> Result<Option<T>> {
+    match r.read_u8()? {
+        0 => Ok(None),
+        1 => f(r).map(Some),
+        v => bail!("Invalid option tag {v}"),
+    }
+}
+
+fn write_items<W: Write>(w: &mut W, items: &[ReportItem]) -> Result<()> {
+    w.write_u32::<LittleEndian>(items.len() as u32)?;
+    for item in items {
+        write_string(w, &item.name)?;
+        write_option(w, item.demangled_name.as_deref(), write_string)?;
+        write_option(w, item.address, |w, v| Ok(w.write_u64::<LittleEndian>(v)?))?;
+        w.write_u64::<LittleEndian>(item.size)?;
+        w.write_f32::<LittleEndian>(item.fuzzy_match_percent)?;
+        w.write_u8(item.workflow.map_or(0, workflow_tag))?;
+        write_option(w, item.assignee.as_deref(), write_string)?;
+    }
+    Ok(())
+}
+
+fn read_items<R: Read>(r: &mut R) -> Result<Vec<ReportItem>> {
+    let count = r.read_u32::<LittleEndian>()?;
+    let mut items = Vec::new();
+    for _ in 0..count {
+        items.push(ReportItem {
+            name: read_string(r)?,
+            demangled_name: read_option(r, read_string)?,
+            address: read_option(r, |r| Ok(r.read_u64::<LittleEndian>()?))?,
+            size: r.read_u64::<LittleEndian>()?,
+            fuzzy_match_percent: r.read_f32::<LittleEndian>()?,
+            workflow: workflow_from_tag(r.read_u8()?)?,
+            assignee: read_option(r, read_string)?,
+        });
+    }
+    Ok(items)
+}
+
+fn serialize_hex<S>(x: &Option<u64>, s: S) -> Result<S::Ok, S::Error>
+where S: serde::Serializer {
+    if let Some(x) = x {
+        s.serialize_str(&format!("{:#x}", x))
+    } else {
+        s.serialize_none()
+    }
+}
+
+fn deserialize_hex<'de, D>(d: D) -> Result<Option<u64>, D::Error>
+where D: serde::Deserializer<'de> {
+    use serde::Deserialize;
+    let s = String::deserialize(d)?;
+    if s.is_empty() {
+        Ok(None)
+    } else if !s.starts_with("0x") {
+        Err(serde::de::Error::custom("expected hex string"))
+    } else {
+        u64::from_str_radix(&s[2..], 16).map(Some).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::config::ScratchConfig;
+
+pub const API_HOST: &str = "https://decomp.me";
+
+/// Parameters for creating a new decomp.me scratch.
+#[derive(Debug, Clone)]
+pub struct CreateScratchConfig {
+    pub compiler: String,
+    pub platform: String,
+    pub compiler_flags: String,
+    pub function_name: String,
+    /// Absolute path to the target object
+    pub target_obj: PathBuf,
+    /// Preprocessed context, if any
+    pub context: Option<String>,
+}
+
+impl CreateScratchConfig {
+    pub fn new(
+        scratch_config: &ScratchConfig,
+        target_obj: PathBuf,
+        function_name: String,
+        context: Option<String>,
+    ) -> Self {
+        Self {
+            compiler: scratch_config.compiler.clone().unwrap_or_default(),
+            platform: scratch_config.platform.clone().unwrap_or_default(),
+            compiler_flags: scratch_config.c_flags.clone().unwrap_or_default(),
+            function_name,
+            target_obj,
+            context,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct CreateScratchResult {
+    pub slug: String,
+    pub claim_token: String,
+    /// URL used to open and claim the scratch
+    pub scratch_url: String,
+}
+
+#[derive(Debug, Default, Clone, serde::Deserialize)]
+struct CreateScratchResponse {
+    pub slug: String,
+    pub claim_token: String,
+}
+
+/// Uploads the target object to decomp.me, creating a new scratch for the function.
+pub fn create_scratch(config: &CreateScratchConfig) -> Result<CreateScratchResult> {
+    let diff_flags = [format!("--disassemble={}", config.function_name)];
+    let diff_flags = serde_json::to_string(&diff_flags).unwrap();
+    let file = reqwest::blocking::multipart::Part::file(&config.target_obj)
+        .with_context(|| format!("Failed to open {}", config.target_obj.display()))?;
+    let form = reqwest::blocking::multipart::Form::new()
+        .text("compiler", config.compiler.clone())
+        .text("platform", config.platform.clone())
+        .text("compiler_flags", config.compiler_flags.clone())
+        .text("diff_label", config.function_name.clone())
+        .text("diff_flags", diff_flags)
+        .text("context", config.context.clone().unwrap_or_default())
+        .text("source_code", "// Move related code from Context tab to here")
+        .part("target_obj", file);
+    let client = reqwest::blocking::Client::new();
+    let response = client
+        .post(format!("{API_HOST}/api/scratch"))
+        .multipart(form)
+        .send()
+        .map_err(|e| anyhow!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Failed to create scratch: {}", response.text()?));
+    }
+    let body: CreateScratchResponse = response.json().context("Failed to parse response")?;
+    let scratch_url = format!("{API_HOST}/scratch/{}/claim?token={}", body.slug, body.claim_token);
+    Ok(CreateScratchResult { slug: body.slug, claim_token: body.claim_token, scratch_url })
+}
@@ -0,0 +1,331 @@
+//! Diff session bundles: the objects of a unit, the settings that affect its diff, the
+//! match results and the instruction notes, in a single compressed file. A contributor can
+//! send their exact mismatch state to someone who doesn't have the build environment.
+//!
+//! Opening a bundle extracts it into a directory laid out as a project with a single unit
+//! and no build commands, which any frontend can then open as usual. Bundles are untrusted:
+//! the project configuration is rebuilt from the fields a session can hold, and its unit
+//! may only refer to the bundled files.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use crate::{
+    config::{ProjectConfig, ProjectObject},
+    diff::{diff_objs, idiom::Idiom, DiffPolicyPreset, MipsInstrCategory, NumberFormat},
+    obj::{
+        binary::{FileRange, SymbolMapConfig},
+        demangle::Demangler,
+        read, AcceptedMismatch, ObjSectionKind, SizeInference, SymbolMappings,
+    },
+    project::load_unit,
+    vfs,
+    workflow::{workflow_path, SymbolWorkflow, WorkflowState, DEFAULT_WORKFLOW_PATH},
+};
+
+/// File extension of session bundles.
+pub const SESSION_EXTENSION: &str = "objdiff-session";
+
+const MAGIC: &[u8; 8] = b"OBJDIFFS";
+const SESSION_VERSION: u32 = 1;
+
+/// Name of the manifest in an extracted session.
+pub const MANIFEST_FILENAME: &str = "session.json";
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct SessionManifest {
+    /// Version of objdiff that exported the session
+    pub objdiff_version: String,
+    pub unit: String,
+    /// Function the session was exported for
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Project configuration of the extracted session, with the unit's paths pointing at
+    /// the bundled files
+    pub config: SessionConfig,
+    /// Match percentage of each function at export time, by target symbol name
+    #[serde(default)]
+    pub results: BTreeMap<String, f32>,
+    /// Workflow state of the unit's functions, including instruction notes
+    #[serde(default)]
+    pub workflow: BTreeMap<String, SymbolWorkflow>,
+    /// Bundled files, stored in this order after the manifest
+    pub files: Vec<SessionFile>,
+}
+
+/// The project settings a session carries. Anything else in a bundle's configuration, such
+/// as build commands or target directories, is dropped when it's read.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct SessionConfig {
+    #[serde(default)]
+    pub demangler: Option<Demangler>,
+    #[serde(default)]
+    pub mips_instr_category: Option<MipsInstrCategory>,
+    #[serde(default)]
+    pub number_format: Option<NumberFormat>,
+    #[serde(default)]
+    pub ignore_padding: Option<bool>,
+    #[serde(default)]
+    pub idioms: Option<BTreeSet<Idiom>>,
+    pub units: Vec<SessionUnit>,
+}
+
+/// A unit of a [`SessionConfig`], whose paths are names of bundled files.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct SessionUnit {
+    pub name: String,
+    #[serde(default)]
+    pub target_path: Option<PathBuf>,
+    #[serde(default)]
+    pub base_path: Option<PathBuf>,
+    #[serde(default)]
+    pub target_map: Option<SymbolMapConfig>,
+    #[serde(default)]
+    pub target_range: Option<FileRange>,
+    #[serde(default)]
+    pub symbol_mappings: Option<SymbolMappings>,
+    #[serde(default)]
+    pub accepted_mismatches: Option<Vec<AcceptedMismatch>>,
+    #[serde(default)]
+    pub size_inference: Option<SizeInference>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct SessionFile {
+    /// File name within the extracted session
+    pub name: String,
+    pub size: u64,
+}
+
+/// Match percentage of each target function of a unit, with the settings of a report using
+/// the default scoring policy, so that the recipient sees the same mismatches. Unit paths
+/// must already be resolved.
+pub fn unit_results(
+    project: &ProjectConfig,
+    object: &ProjectObject,
+) -> Result<BTreeMap<String, f32>> {
+    let (target, base) = load_unit(object, project.demangler.unwrap_or_default())?;
+    let config = project.report_diff_config(DiffPolicyPreset::default());
+    let result = diff_objs(&config, target.as_ref(), base.as_ref(), None)?;
+    let mut out = BTreeMap::new();
+    let (Some(target), Some(diff)) = (&target, &result.left) else {
+        return Ok(out);
+    };
+    for (section, section_diff) in target.sections.iter().zip(&diff.sections) {
+        if section.kind != ObjSectionKind::Code {
+            continue;
+        }
+        for (symbol, symbol_diff) in section.symbols.iter().zip(&section_diff.symbols) {
+            if let Some(percent) = symbol_diff.match_percent {
+                out.insert(symbol.name.clone(), percent);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Name of a bundled input file, and the path the unit refers to it by. Archive members
+/// keep their member name.
+fn bundled_name(prefix: &str, path: &Path) -> (String, PathBuf) {
+    let file = read::archive_file_path(path);
+    let name = match file.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{prefix}.{ext}"),
+        None => prefix.to_string(),
+    };
+    let unit_path = match read::split_archive_path(path) {
+        Some((_, member)) => PathBuf::from(format!("{name}({member})")),
+        None => PathBuf::from(&name),
+    };
+    (name, unit_path)
+}
+
+/// Export the diff session of a unit to `out`. Unit paths must already be resolved.
+pub fn export_session(
+    project_dir: &Path,
+    project: &ProjectConfig,
+    object: &ProjectObject,
+    symbol: Option<&str>,
+    out: &Path,
+) -> Result<SessionManifest> {
+    let unit = object.name().to_string();
+    let mut files = Vec::<(String, PathBuf)>::new();
+    let mut bundle = |prefix: &str, path: &Path| {
+        let (name, unit_path) = bundled_name(prefix, path);
+        files.push((name, read::archive_file_path(path)));
+        unit_path
+    };
+    let target_path = object.target_path.as_deref().map(|p| bundle("target", p));
+    let base_path = object.base_path.as_deref().map(|p| bundle("base", p));
+    let target_map = object.target_map.as_ref().map(|map| {
+        let mut map = map.clone();
+        map.path = bundle("target_map", &map.path);
+        map
+    });
+    ensure!(target_path.is_some() || base_path.is_some(), "Unit {unit} has no objects");
+
+    let config = SessionConfig {
+        demangler: project.demangler,
+        mips_instr_category: project.mips_instr_category,
+        number_format: project.number_format,
+        ignore_padding: project.ignore_padding,
+        idioms: project.idioms.clone(),
+        units: vec![SessionUnit {
+            name: unit.clone(),
+            target_path,
+            base_path,
+            target_map,
+            target_range: object.target_range.clone(),
+            symbol_mappings: object.symbol_mappings.clone(),
+            accepted_mismatches: object.accepted_mismatches.clone(),
+            size_inference: object.size_inference.or(project.size_inference),
+        }],
+    };
+    let workflow_state =
+        WorkflowState::load(&workflow_path(project_dir, project.workflow_path.as_deref()))?;
+    let mut manifest = SessionManifest {
+        objdiff_version: env!("CARGO_PKG_VERSION").to_string(),
+        unit: unit.clone(),
+        symbol: symbol.map(str::to_string),
+        config,
+        results: unit_results(project, object)?,
+        workflow: workflow_state.units.get(&unit).cloned().unwrap_or_default(),
+        files: Vec::with_capacity(files.len()),
+    };
+    let mut contents = Vec::with_capacity(files.len());
+    for (name, path) in files {
+        let (data, _) =
+            vfs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        manifest.files.push(SessionFile { name, size: data.len() as u64 });
+        contents.push(data.to_vec());
+    }
+
+    let file =
+        fs::File::create(out).with_context(|| format!("Failed to create {}", out.display()))?;
+    let mut w = GzEncoder::new(BufWriter::new(file), Compression::default());
+    let manifest_data = serde_json::to_vec(&manifest)?;
+    w.write_all(MAGIC)?;
+    w.write_all(&SESSION_VERSION.to_le_bytes())?;
+    w.write_all(&(manifest_data.len() as u64).to_le_bytes())?;
+    w.write_all(&manifest_data)?;
+    for data in &contents {
+        w.write_all(data)?;
+    }
+    w.finish()?.flush().with_context(|| format!("Failed to write {}", out.display()))?;
+    Ok(manifest)
+}
+
+fn read_u64(r: &mut impl Read) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Extract a session bundle into `dir` as a single unit project, returning its manifest.
+pub fn open_session(path: &Path, dir: &Path) -> Result<SessionManifest> {
+    let file =
+        fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut r = GzDecoder::new(BufReader::new(file));
+    let mut magic = [0u8; 8];
+    r.read_exact(&mut magic).context("Not a session bundle")?;
+    ensure!(&magic == MAGIC, "Not a session bundle");
+    let mut version = [0u8; 4];
+    r.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != SESSION_VERSION {
+        bail!("Unsupported session version {version}, expected {SESSION_VERSION}");
+    }
+    let manifest_len = read_u64(&mut r)?;
+    let mut manifest_data = Vec::new();
+    r.by_ref().take(manifest_len).read_to_end(&mut manifest_data)?;
+    let manifest: SessionManifest =
+        serde_json::from_slice(&manifest_data).context("Failed to parse session manifest")?;
+    for file in &manifest.files {
+        // Names are generated on export, never paths
+        ensure!(
+            !file.name.is_empty() && !file.name.contains(['/', '\\']) && file.name != "..",
+            "Invalid file name in session: {}",
+            file.name
+        );
+    }
+    check_unit_paths(&manifest)?;
+
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    for file in &manifest.files {
+        let mut data = Vec::new();
+        r.by_ref().take(file.size).read_to_end(&mut data)?;
+        ensure!(data.len() as u64 == file.size, "Session bundle is truncated");
+        let out = dir.join(&file.name);
+        fs::write(&out, data).with_context(|| format!("Failed to write {}", out.display()))?;
+    }
+    fs::write(dir.join("objdiff.json"), serde_json::to_vec_pretty(&project_config(&manifest)?)?)
+        .context("Failed to write the session project configuration")?;
+    let mut workflow = WorkflowState::default();
+    if !manifest.workflow.is_empty() {
+        workflow.units.insert(manifest.unit.clone(), manifest.workflow.clone());
+    }
+    workflow.save(&dir.join(DEFAULT_WORKFLOW_PATH))?;
+    fs::write(dir.join(MANIFEST_FILENAME), serde_json::to_vec_pretty(&manifest)?)
+        .context("Failed to write the session manifest")?;
+    Ok(manifest)
+}
+
+/// Ensure that every path of the session's units names a bundled file, or a member of one.
+fn check_unit_paths(manifest: &SessionManifest) -> Result<()> {
+    for unit in &manifest.config.units {
+        let paths = [&unit.target_path, &unit.base_path]
+            .into_iter()
+            .flatten()
+            .chain(unit.target_map.as_ref().map(|map| &map.path));
+        for path in paths {
+            let file = read::archive_file_path(path);
+            ensure!(
+                manifest.files.iter().any(|f| file.as_os_str() == f.name.as_str()),
+                "Session unit {} refers to {}, which isn't a bundled file",
+                unit.name,
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The project configuration of an extracted session. Sessions open read-only, so nothing
+/// is ever built, whatever the configuration defaults are.
+fn project_config(manifest: &SessionManifest) -> Result<serde_json::Value> {
+    let mut config = serde_json::to_value(&manifest.config)?;
+    let object = config.as_object_mut().context("Invalid session configuration")?;
+    object.insert("build_base".to_string(), false.into());
+    object.insert("build_target".to_string(), false.into());
+    Ok(config)
+}
+
+/// Functions whose match percentage differs from the one recorded in the session, as
+/// `(name, recorded, current)`. Differences usually come from different objdiff versions.
+pub fn compare_results(
+    manifest: &SessionManifest,
+    current: &BTreeMap<String, f32>,
+) -> Vec<(String, Option<f32>, Option<f32>)> {
+    let mut names = manifest.results.keys().chain(current.keys()).collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let recorded = manifest.results.get(name).copied();
+            let now = current.get(name).copied();
+            let same = match (recorded, now) {
+                (Some(a), Some(b)) => (a - b).abs() < 0.01,
+                (None, None) => true,
+                _ => false,
+            };
+            (!same).then(|| (name.clone(), recorded, now))
+        })
+        .collect()
+}
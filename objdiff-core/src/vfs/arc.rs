@@ -0,0 +1,156 @@
+//! RARC and U8 archives, and Yaz0 compression.
+
+use anyhow::{bail, ensure, Context, Result};
+
+use crate::vfs::{cstr, name_eq, slice, YAZ0_MAGIC};
+
+fn be16(data: &[u8], offset: usize) -> Result<u16> {
+    Ok(u16::from_be_bytes(slice(data, offset, 2)?.try_into().unwrap()))
+}
+
+fn be32(data: &[u8], offset: usize) -> Result<u32> {
+    Ok(u32::from_be_bytes(slice(data, offset, 4)?.try_into().unwrap()))
+}
+
+/// The offset of entry `index` of a table at `base`, failing on corrupt offsets rather than
+/// overflowing.
+fn table_offset(base: usize, index: usize, stride: usize) -> Result<usize> {
+    index
+        .checked_mul(stride)
+        .and_then(|offset| offset.checked_add(base))
+        .context("Archive offset out of range")
+}
+
+/// Decompress Yaz0 data.
+pub fn yaz0_decompress(data: &[u8]) -> Result<Vec<u8>> {
+    ensure!(data.len() >= 16 && data.starts_with(YAZ0_MAGIC), "Not Yaz0 data");
+    // The size is untrusted, so let the buffer grow as data is produced
+    let size = be32(data, 4)? as usize;
+    let mut out = Vec::new();
+    let mut src = 16;
+    let next = |src: &mut usize| -> Result<u8> {
+        let byte = *data.get(*src).context("Truncated Yaz0 data")?;
+        *src += 1;
+        Ok(byte)
+    };
+    while out.len() < size {
+        let header = next(&mut src)?;
+        for bit in (0..8).rev() {
+            if out.len() >= size {
+                break;
+            }
+            if header & (1 << bit) != 0 {
+                out.push(next(&mut src)?);
+                continue;
+            }
+            let b1 = next(&mut src)? as usize;
+            let b2 = next(&mut src)? as usize;
+            let dist = ((b1 & 0xF) << 8 | b2) + 1;
+            let len = match b1 >> 4 {
+                0 => next(&mut src)? as usize + 0x12,
+                n => n + 2,
+            };
+            ensure!(dist <= out.len(), "Invalid Yaz0 back-reference");
+            // Copies may overlap the bytes they produce
+            for _ in 0..len {
+                out.push(out[out.len() - dist]);
+            }
+        }
+    }
+    out.truncate(size);
+    Ok(out)
+}
+
+/// Decompress a file read from an archive if it's Yaz0-compressed.
+fn file_data(data: &[u8]) -> Result<Vec<u8>> {
+    if data.starts_with(YAZ0_MAGIC) {
+        yaz0_decompress(data)
+    } else {
+        Ok(data.to_vec())
+    }
+}
+
+/// Read a file from a RARC archive, by its path relative to the root directory.
+pub fn rarc_file(data: &[u8], path: &str) -> Result<Vec<u8>> {
+    const INFO: usize = 0x20;
+    let data_start = table_offset(INFO, be32(data, 0x0C)? as usize, 1)?;
+    let node_start = table_offset(INFO, be32(data, INFO + 0x04)? as usize, 1)?;
+    let entry_start = table_offset(INFO, be32(data, INFO + 0x0C)? as usize, 1)?;
+    let strings = table_offset(INFO, be32(data, INFO + 0x14)? as usize, 1)?;
+    let string_table = data.get(strings..).context("String table out of bounds")?;
+
+    let mut node = 0usize;
+    let mut components = path.split(['/', '\\']).filter(|c| !c.is_empty()).peekable();
+    while let Some(component) = components.next() {
+        let node_data = slice(data, table_offset(node_start, node, 0x10)?, 0x10)?;
+        let count = be16(node_data, 0x0A)? as usize;
+        let first = be32(node_data, 0x0C)? as usize;
+        let mut found = false;
+        for idx in first..first.saturating_add(count) {
+            let entry = slice(data, table_offset(entry_start, idx, 0x14)?, 0x14)?;
+            let type_name = be32(entry, 0x04)?;
+            let is_dir = (type_name >> 24) & 0x02 != 0;
+            let name = cstr(string_table, (type_name & 0xFFFFFF) as usize)?;
+            if !name_eq(name, component) || name == b"." || name == b".." {
+                continue;
+            }
+            let offset = be32(entry, 0x08)? as usize;
+            let size = be32(entry, 0x0C)? as usize;
+            let last = components.peek().is_none();
+            match (is_dir, last) {
+                (false, true) => {
+                    return file_data(slice(data, table_offset(data_start, offset, 1)?, size)?)
+                }
+                (true, false) => {
+                    node = offset;
+                    found = true;
+                    break;
+                }
+                (true, true) => bail!("{path} is a directory"),
+                (false, false) => bail!("{component} is not a directory"),
+            }
+        }
+        ensure!(found, "File {path} not found in archive");
+    }
+    bail!("File {path} not found in archive")
+}
+
+/// Read a file from a U8 archive, by its path relative to the root directory.
+pub fn u8_file(data: &[u8], path: &str) -> Result<Vec<u8>> {
+    let root = be32(data, 0x04)? as usize;
+    let count = be32(data, table_offset(root, 0x08, 1)?)? as usize;
+    // Node offsets are below the string table, so they can't overflow
+    let strings = table_offset(root, count, 12)?;
+    let string_table = data.get(strings..).context("String table out of bounds")?;
+
+    // Nodes are stored depth-first; a directory's size is the index after its last child
+    let mut idx = 1;
+    let mut end = count;
+    let mut components = path.split(['/', '\\']).filter(|c| !c.is_empty());
+    let Some(mut component) = components.next() else {
+        bail!("Empty path");
+    };
+    while idx < end {
+        let node = root + idx * 12;
+        let type_name = be32(data, node)?;
+        let is_dir = type_name >> 24 == 1;
+        let offset = be32(data, node + 0x04)? as usize;
+        let size = be32(data, node + 0x08)? as usize;
+        let name = cstr(string_table, (type_name & 0xFFFFFF) as usize)?;
+        if !name_eq(name, component) {
+            idx = if is_dir { size.max(idx + 1) } else { idx + 1 };
+            continue;
+        }
+        match (is_dir, components.next()) {
+            (false, None) => return file_data(slice(data, offset, size)?),
+            (true, Some(next)) => {
+                component = next;
+                idx += 1;
+                end = size.min(count);
+            }
+            (true, None) => bail!("{path} is a directory"),
+            (false, Some(_)) => bail!("{component} is not a directory"),
+        }
+    }
+    bail!("File {path} not found in archive")
+}
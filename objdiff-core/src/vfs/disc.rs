@@ -0,0 +1,33 @@
+//! GameCube and Wii disc images.
+
+use std::{io::Read, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+/// Read a file from the data partition of a disc image: `sys/main.dol` for the main
+/// executable, or `files/` followed by a path within the disc filesystem.
+pub fn read_file(host: &Path, path: &str) -> Result<Vec<u8>> {
+    let disc = nod::Disc::new(host)
+        .with_context(|| format!("Failed to open disc image {}", host.display()))?;
+    let mut partition = disc
+        .open_partition_kind(nod::PartitionKind::Data)
+        .context("Failed to open the data partition")?;
+    let meta = partition.meta().context("Failed to read the partition metadata")?;
+    let path = path.trim_start_matches(['/', '\\']);
+    if path.eq_ignore_ascii_case("sys/main.dol") {
+        return Ok(meta.raw_dol.to_vec());
+    }
+    let file_path = match path.get(..6) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("files/") => &path[6..],
+        _ => bail!("Disc paths must start with files/ or be sys/main.dol, found {path}"),
+    };
+    let fst = meta.fst().map_err(|e| anyhow!("Failed to read the disc filesystem: {e}"))?;
+    let Some((_, node)) = fst.find(file_path) else {
+        bail!("File {path} not found in disc image {}", host.display());
+    };
+    let context = || format!("Failed to read {path} from disc image {}", host.display());
+    let mut file = partition.open_file(node).with_context(context)?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).with_context(context)?;
+    Ok(data)
+}
@@ -0,0 +1,228 @@
+//! Paths into container files, so that units can refer to objects and binaries inside the
+//! original media without extracting them first.
+//!
+//! A container path is a file on disk followed by one or more paths within containers,
+//! separated by `:`, such as `orig/GALE01.rvz:files/RELS.arc:mod.rel`. Each path is looked
+//! up in the file before it. The container formats are behind optional features:
+//!
+//! - `disc`: GameCube and Wii disc images, in any format supported by `nod`. Paths start
+//!   with `files/` for the filesystem, or are `sys/main.dol` for the main executable.
+//! - `arc`: RARC (`.arc`, `.szs`) and U8 (Wii `.arc`) archives, optionally Yaz0-compressed.
+//! - `nitrofs`: Nintendo DS ROMs. Paths are within the ROM filesystem, or are `arm9.bin`
+//!   and `arm7.bin` for the main executables.
+
+#[cfg(feature = "arc")]
+mod arc;
+#[cfg(feature = "disc")]
+mod disc;
+#[cfg(feature = "nitrofs")]
+mod nitro;
+
+use std::{
+    fs,
+    io::Read,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use filetime::FileTime;
+
+/// Separator between the paths of a container path.
+pub const SEPARATOR: char = ':';
+
+/// Contents of a file read through the virtual filesystem.
+pub enum FileData {
+    /// A file on disk
+    Mapped(memmap2::Mmap),
+    /// A file extracted from a container
+    Owned(Vec<u8>),
+}
+
+impl Deref for FileData {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            FileData::Mapped(data) => data,
+            FileData::Owned(data) => data,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ContainerKind {
+    Disc,
+    Rarc,
+    U8,
+    NitroRom,
+}
+
+impl ContainerKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ContainerKind::Disc => "Disc image",
+            ContainerKind::Rarc => "RARC archive",
+            ContainerKind::U8 => "U8 archive",
+            ContainerKind::NitroRom => "Nintendo DS ROM",
+        }
+    }
+
+    /// Cargo feature that enables the format.
+    pub fn feature(self) -> &'static str {
+        match self {
+            ContainerKind::Disc => "disc",
+            ContainerKind::Rarc | ContainerKind::U8 => "arc",
+            ContainerKind::NitroRom => "nitrofs",
+        }
+    }
+}
+
+const YAZ0_MAGIC: &[u8; 4] = b"Yaz0";
+
+/// Detect the container format of a file from its header. Yaz0-compressed data must be
+/// decompressed first.
+pub fn detect(data: &[u8]) -> Option<ContainerKind> {
+    if data.starts_with(b"RARC") {
+        Some(ContainerKind::Rarc)
+    } else if data.starts_with(&[0x55, 0xAA, 0x38, 0x2D]) {
+        Some(ContainerKind::U8)
+    } else if is_disc_header(data) {
+        Some(ContainerKind::Disc)
+    } else if data.len() >= 0x200 && data[0x15C..0x15E] == [0x56, 0xCF] {
+        // Nintendo logo checksum
+        Some(ContainerKind::NitroRom)
+    } else {
+        None
+    }
+}
+
+fn is_disc_header(data: &[u8]) -> bool {
+    const COMPRESSED_MAGICS: [&[u8; 4]; 5] =
+        [b"RVZ\x01", b"WIA\x01", b"WBFS", b"CISO", &[0x01, 0xC0, 0x0B, 0xB1]];
+    COMPRESSED_MAGICS.iter().any(|magic| data.starts_with(*magic))
+        || data.get(0x1C..0x20) == Some(&[0xC2, 0x33, 0x9F, 0x3D])
+        || data.get(0x18..0x1C) == Some(&[0x5D, 0x1C, 0x9E, 0xA3])
+}
+
+/// Length of a drive prefix such as `C:` or `\\?\C:`, whose colon isn't a separator.
+fn drive_prefix_len(s: &str) -> usize {
+    let (prefix, rest) = match s.strip_prefix(r"\\?\") {
+        Some(rest) => (4, rest),
+        None => (0, s),
+    };
+    let bytes = rest.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        prefix + 2
+    } else {
+        0
+    }
+}
+
+/// Split a path into the file on disk and the paths within containers, outermost first.
+/// A separator only splits the path where the part before it is an existing file, so
+/// plain paths that happen to contain the separator are left alone.
+pub fn split_path(path: &Path) -> (PathBuf, Vec<&str>) {
+    let Some(s) = path.to_str() else {
+        return (path.to_path_buf(), Vec::new());
+    };
+    let start = drive_prefix_len(s);
+    for (idx, _) in s.match_indices(SEPARATOR).filter(|(idx, _)| *idx >= start) {
+        let host = Path::new(&s[..idx]);
+        if host.is_file() {
+            let inner = s[idx + 1..].split(SEPARATOR).filter(|p| !p.is_empty()).collect();
+            return (host.to_path_buf(), inner);
+        }
+    }
+    (path.to_path_buf(), Vec::new())
+}
+
+/// The file on disk that a path refers to or is contained in.
+pub fn host_path(path: &Path) -> PathBuf { split_path(path).0 }
+
+/// Whether the path refers to a file within a container.
+pub fn is_container_path(path: &Path) -> bool { !split_path(path).1.is_empty() }
+
+/// Read a file, extracting it from its containers if needed. The timestamp is that of the
+/// file on disk.
+pub fn read(path: &Path) -> Result<(FileData, FileTime)> {
+    let (host, inner) = split_path(path);
+    let file =
+        fs::File::open(&host).with_context(|| format!("Failed to open {}", host.display()))?;
+    let timestamp = FileTime::from_last_modification_time(&file.metadata()?);
+    let mut inner = inner.into_iter();
+    let Some(first) = inner.next() else {
+        return Ok((FileData::Mapped(unsafe { memmap2::Mmap::map(&file) }?), timestamp));
+    };
+    let mut header = Vec::with_capacity(0x200);
+    (&file).take(0x200).read_to_end(&mut header)?;
+    let mut data = if is_disc_header(&header) {
+        read_disc(&host, first)?
+    } else {
+        let mapped = unsafe { memmap2::Mmap::map(&file) }?;
+        read_in(&mapped, first)
+            .with_context(|| format!("Failed to read {first} from {}", host.display()))?
+    };
+    let mut parent = first;
+    for name in inner {
+        data =
+            read_in(&data, name).with_context(|| format!("Failed to read {name} from {parent}"))?;
+        parent = name;
+    }
+    Ok((FileData::Owned(data), timestamp))
+}
+
+#[cfg(feature = "disc")]
+fn read_disc(host: &Path, name: &str) -> Result<Vec<u8>> { disc::read_file(host, name) }
+
+#[cfg(not(feature = "disc"))]
+fn read_disc(host: &Path, _name: &str) -> Result<Vec<u8>> {
+    bail!("{} is a disc image, which requires the `disc` feature", host.display())
+}
+
+/// Read a file from a container in memory.
+fn read_in(data: &[u8], name: &str) -> Result<Vec<u8>> {
+    if data.starts_with(YAZ0_MAGIC) {
+        #[cfg(feature = "arc")]
+        return read_in(&arc::yaz0_decompress(data)?, name);
+        #[cfg(not(feature = "arc"))]
+        bail!("Yaz0-compressed containers require the `arc` feature");
+    }
+    let name = name.trim_start_matches(['/', '\\']);
+    match detect(data) {
+        #[cfg(feature = "arc")]
+        Some(ContainerKind::Rarc) => arc::rarc_file(data, name),
+        #[cfg(feature = "arc")]
+        Some(ContainerKind::U8) => arc::u8_file(data, name),
+        #[cfg(feature = "nitrofs")]
+        Some(ContainerKind::NitroRom) => nitro::rom_file(data, name),
+        Some(ContainerKind::Disc) => bail!("Disc images can't be nested in other containers"),
+        #[cfg(not(all(feature = "arc", feature = "nitrofs")))]
+        Some(kind) => {
+            bail!("{} support requires the `{}` feature", kind.name(), kind.feature())
+        }
+        None => bail!("Not a supported container format"),
+    }
+}
+
+/// Compare a path component case-insensitively, as the console filesystems do.
+#[cfg(any(feature = "arc", feature = "nitrofs"))]
+fn name_eq(a: &[u8], b: &str) -> bool { a.eq_ignore_ascii_case(b.as_bytes()) }
+
+/// Read a NUL-terminated name from a string table.
+#[cfg(feature = "arc")]
+fn cstr(data: &[u8], offset: usize) -> Result<&[u8]> {
+    let Some(rest) = data.get(offset..) else {
+        bail!("Name offset {offset:#x} out of bounds");
+    };
+    Ok(rest.iter().position(|&b| b == 0).map_or(rest, |end| &rest[..end]))
+}
+
+/// A byte range of a container, checked against its size.
+#[cfg(any(feature = "arc", feature = "nitrofs"))]
+fn slice(data: &[u8], offset: usize, size: usize) -> Result<&[u8]> {
+    match offset.checked_add(size).and_then(|end| data.get(offset..end)) {
+        Some(slice) => Ok(slice),
+        None => bail!("File data {offset:#x}+{size:#x} out of bounds"),
+    }
+}
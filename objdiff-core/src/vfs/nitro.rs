@@ -0,0 +1,75 @@
+//! Nintendo DS ROMs and their NitroFS filesystem.
+
+use anyhow::{bail, Context, Result};
+
+use crate::vfs::{name_eq, slice};
+
+fn le16(data: &[u8], offset: usize) -> Result<u16> {
+    Ok(u16::from_le_bytes(slice(data, offset, 2)?.try_into().unwrap()))
+}
+
+fn le32(data: &[u8], offset: usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(slice(data, offset, 4)?.try_into().unwrap()))
+}
+
+/// A range of the ROM given by the header fields at `offset_field` and `size_field`.
+fn header_range(data: &[u8], offset_field: usize, size_field: usize) -> Result<&[u8]> {
+    let offset = le32(data, offset_field)? as usize;
+    let size = le32(data, size_field)? as usize;
+    slice(data, offset, size)
+}
+
+/// Read a file from a DS ROM: `arm9.bin` and `arm7.bin` for the main executables, or a
+/// path within the ROM filesystem.
+pub fn rom_file(data: &[u8], path: &str) -> Result<Vec<u8>> {
+    if path.eq_ignore_ascii_case("arm9.bin") {
+        return Ok(header_range(data, 0x20, 0x2C)?.to_vec());
+    }
+    if path.eq_ignore_ascii_case("arm7.bin") {
+        return Ok(header_range(data, 0x30, 0x3C)?.to_vec());
+    }
+    let fnt = header_range(data, 0x40, 0x44).context("Invalid file name table")?;
+    let fat = header_range(data, 0x48, 0x4C).context("Invalid file allocation table")?;
+
+    // Directory IDs start at 0xF000 with the root directory
+    let mut dir = 0usize;
+    let mut components = path.split(['/', '\\']).filter(|c| !c.is_empty()).peekable();
+    'outer: while let Some(component) = components.next() {
+        let mut offset = le32(fnt, dir * 8)? as usize;
+        let mut file_id = le16(fnt, dir * 8 + 4)? as usize;
+        let last = components.peek().is_none();
+        loop {
+            let kind = *fnt.get(offset).context("Truncated file name table")?;
+            if kind == 0 {
+                break;
+            }
+            let len = (kind & 0x7F) as usize;
+            let name = slice(fnt, offset + 1, len)?;
+            offset += 1 + len;
+            let is_dir = kind & 0x80 != 0;
+            if is_dir {
+                let id = le16(fnt, offset)? as usize;
+                offset += 2;
+                if name_eq(name, component) {
+                    if last {
+                        bail!("{path} is a directory");
+                    }
+                    dir = id.checked_sub(0xF000).context("Invalid directory ID")?;
+                    continue 'outer;
+                }
+            } else if name_eq(name, component) {
+                if !last {
+                    bail!("{component} is not a directory");
+                }
+                let start = le32(fat, file_id * 8)? as usize;
+                let end = le32(fat, file_id * 8 + 4)? as usize;
+                let size = end.checked_sub(start).context("Invalid file allocation")?;
+                return Ok(slice(data, start, size)?.to_vec());
+            } else {
+                file_id += 1;
+            }
+        }
+        bail!("File {path} not found in ROM");
+    }
+    bail!("Empty path")
+}
@@ -0,0 +1,267 @@
+//! `wasm-bindgen` API for diffing objects client-side in a browser.
+//!
+//! Objects are passed in as byte arrays; nothing here touches the file system.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    diff::{
+        diff_objs,
+        display::{display_rows, DiffText, DisplayRow},
+        theme::TokenKind,
+        DiffObjConfig, NumberFormat, ObjDiff, ObjInsArgDiffKind, ObjInsDiffKind, RelocDiffKind,
+    },
+    obj::{read, ObjInfo, ObjSectionKind, SymbolRef},
+};
+
+/// Side of the diff.
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Two parsed objects and their diff.
+#[wasm_bindgen]
+pub struct DiffSession {
+    config: DiffObjConfig,
+    left: Option<(ObjInfo, ObjDiff)>,
+    right: Option<(ObjInfo, ObjDiff)>,
+}
+
+#[derive(Serialize)]
+struct SymbolInfo<'a> {
+    name: &'a str,
+    demangled_name: Option<&'a str>,
+    section: &'a str,
+    kind: &'static str,
+    address: u64,
+    size: u64,
+    match_percent: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct RowPage {
+    total_rows: usize,
+    rows: Vec<Row>,
+}
+
+#[derive(Serialize)]
+struct Row {
+    kind: &'static str,
+    /// Alignment padding after the end of the function's code
+    padding: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Segment {
+    Basic { text: String },
+    BasicColor { text: String, color: usize },
+    Line { line: usize },
+    Address { address: u64 },
+    Encoding { bytes: Vec<u8>, mismatched: Vec<usize> },
+    Opcode { mnemonic: String, op: u16 },
+    Argument { text: String, diff: Option<ArgDiff> },
+    BranchDest { address: u64, label: Option<usize> },
+    Label { label: usize, color: usize },
+    Moved { block: usize },
+    Accepted,
+    Bytes { bytes: Vec<u8>, mismatched: Vec<usize> },
+    Symbol { name: String, demangled_name: Option<String>, reloc_diff: Option<&'static str> },
+    Comment { text: String },
+    Spacing { count: usize },
+}
+
+#[derive(Serialize)]
+struct ArgDiff {
+    index: usize,
+    kind: &'static str,
+    reloc: Option<&'static str>,
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    Ok(serde_wasm_bindgen::to_value(value)?)
+}
+
+#[wasm_bindgen]
+impl DiffSession {
+    /// Parses and diffs the objects. `config` is a [`DiffObjConfig`] object, or `undefined`
+    /// for the defaults.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        left: Option<Box<[u8]>>,
+        right: Option<Box<[u8]>>,
+        config: JsValue,
+    ) -> Result<DiffSession, JsError> {
+        let config: DiffObjConfig = if config.is_undefined() || config.is_null() {
+            DiffObjConfig::default()
+        } else {
+            serde_wasm_bindgen::from_value(config)?
+        };
+        let left = left.map(|data| read::parse(&data)).transpose()?;
+        let right = right.map(|data| read::parse(&data)).transpose()?;
+        let result = diff_objs(&config, left.as_ref(), right.as_ref(), None)?;
+        Ok(Self { config, left: left.zip(result.left), right: right.zip(result.right) })
+    }
+
+    fn side(&self, side: Side) -> Result<&(ObjInfo, ObjDiff), JsError> {
+        match side {
+            Side::Left => self.left.as_ref(),
+            Side::Right => self.right.as_ref(),
+        }
+        .ok_or_else(|| JsError::new("No object loaded on this side"))
+    }
+
+    /// Lists the symbols of one side with their match percentages.
+    pub fn symbols(&self, side: Side) -> Result<JsValue, JsError> {
+        let (obj, diff) = self.side(side)?;
+        let mut out = Vec::new();
+        for (section_idx, section) in obj.sections.iter().enumerate() {
+            for (symbol_idx, symbol) in section.symbols.iter().enumerate() {
+                let symbol_diff = diff.symbol_diff(SymbolRef { section_idx, symbol_idx });
+                out.push(SymbolInfo {
+                    name: &symbol.name,
+                    demangled_name: symbol.demangled_name.as_deref(),
+                    section: &section.name,
+                    kind: match section.kind {
+                        ObjSectionKind::Code => "code",
+                        ObjSectionKind::Data => "data",
+                        ObjSectionKind::Bss => "bss",
+                    },
+                    address: symbol.address,
+                    size: symbol.size,
+                    match_percent: symbol_diff.match_percent,
+                });
+            }
+        }
+        to_js(&out)
+    }
+
+    /// Returns the instruction rows of a function, as segments in display order.
+    pub fn function_diff(&self, side: Side, symbol_name: &str) -> Result<JsValue, JsError> {
+        to_js(&self.rows(side, symbol_name, 0, usize::MAX)?.1)
+    }
+
+    /// Returns `count` instruction rows of a function starting at row `start`, along with
+    /// the total number of rows, so that very large functions can be rendered a screenful
+    /// at a time.
+    pub fn function_rows(
+        &self,
+        side: Side,
+        symbol_name: &str,
+        start: usize,
+        count: usize,
+    ) -> Result<JsValue, JsError> {
+        let (total_rows, rows) = self.rows(side, symbol_name, start, count)?;
+        to_js(&RowPage { total_rows, rows })
+    }
+
+    fn rows(
+        &self,
+        side: Side,
+        symbol_name: &str,
+        start: usize,
+        count: usize,
+    ) -> Result<(usize, Vec<Row>), JsError> {
+        let (obj, diff) = self.side(side)?;
+        let symbol_ref = obj
+            .sections
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.kind == ObjSectionKind::Code)
+            .find_map(|(section_idx, section)| {
+                let symbol_idx = section.symbols.iter().position(|s| s.name == symbol_name)?;
+                Some(SymbolRef { section_idx, symbol_idx })
+            })
+            .ok_or_else(|| JsError::new(&format!("Function {symbol_name} not found")))?;
+        let (_, symbol) = obj.section_symbol(symbol_ref);
+        let number_format = self.config.number_format;
+        let instructions = &diff.symbol_diff(symbol_ref).instructions;
+        let end = start.saturating_add(count).min(instructions.len());
+        let rows = display_rows(instructions, symbol.address, start.min(end)..end)
+            .map(|row| display_row(row, number_format))
+            .collect();
+        Ok((instructions.len(), rows))
+    }
+}
+
+fn display_row(row: DisplayRow, number_format: NumberFormat) -> Row {
+    let ins_diff = row.ins_diff;
+    let mut segments = Vec::new();
+    row.display(|text| {
+        segments.push(match text {
+            DiffText::Basic(text) => Segment::Basic { text: text.to_string() },
+            DiffText::Styled(text, TokenKind::Rotation(color)) => {
+                Segment::BasicColor { text: text.to_string(), color }
+            }
+            DiffText::Styled(text, _) => Segment::Basic { text: text.to_string() },
+            DiffText::Line(line) => Segment::Line { line },
+            DiffText::Address(address) => Segment::Address { address },
+            DiffText::Encoding(encoding) => Segment::Encoding {
+                bytes: encoding.bytes.clone(),
+                mismatched: (0..encoding.bytes.len())
+                    .filter(|&i| encoding.byte_mismatch(i))
+                    .collect(),
+            },
+            DiffText::Opcode(mnemonic, op) => {
+                Segment::Opcode { mnemonic: mnemonic.to_string(), op }
+            }
+            DiffText::Argument(arg, diff) => Segment::Argument {
+                text: arg.display(number_format).to_string(),
+                diff: diff.map(|d| ArgDiff {
+                    index: d.idx,
+                    kind: match d.kind {
+                        ObjInsArgDiffKind::Mismatch => "mismatch",
+                        ObjInsArgDiffKind::RegisterSwap => "register_swap",
+                    },
+                    reloc: d.reloc.map(reloc_diff_name),
+                }),
+            },
+            DiffText::BranchDest(address, label) => Segment::BranchDest { address, label },
+            DiffText::Label(label, color) => Segment::Label { label, color },
+            DiffText::Moved(block) => Segment::Moved { block },
+            DiffText::Accepted => Segment::Accepted,
+            DiffText::Bytes(bytes, diff) => Segment::Bytes {
+                bytes: bytes.to_vec(),
+                mismatched: (0..bytes.len())
+                    .filter(|&i| diff.is_some_and(|d| d.byte_mismatch(i)))
+                    .collect(),
+            },
+            DiffText::Symbol(symbol, reloc_diff, _) => Segment::Symbol {
+                name: symbol.name.clone(),
+                demangled_name: symbol.demangled_name.clone(),
+                reloc_diff: reloc_diff.map(reloc_diff_name),
+            },
+            DiffText::Comment(text) => Segment::Comment { text: text.to_string() },
+            DiffText::Spacing(count) => Segment::Spacing { count },
+            DiffText::Eol => return Ok(()),
+        });
+        Ok::<_, ()>(())
+    })
+    .unwrap();
+    Row { kind: ins_diff_kind_name(ins_diff.kind), padding: ins_diff.padding, segments }
+}
+
+fn ins_diff_kind_name(kind: ObjInsDiffKind) -> &'static str {
+    match kind {
+        ObjInsDiffKind::None => "none",
+        ObjInsDiffKind::OpMismatch => "op_mismatch",
+        ObjInsDiffKind::ArgMismatch => "arg_mismatch",
+        ObjInsDiffKind::Replace => "replace",
+        ObjInsDiffKind::Delete => "delete",
+        ObjInsDiffKind::Insert => "insert",
+    }
+}
+
+fn reloc_diff_name(kind: RelocDiffKind) -> &'static str {
+    match kind {
+        RelocDiffKind::SameSymbol => "same_symbol",
+        RelocDiffKind::SameAddress => "same_address",
+        RelocDiffKind::SameValue => "same_value",
+        RelocDiffKind::Mismatch => "mismatch",
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    diff::ObjDiff,
+    obj::{ObjInfo, ObjSectionKind},
+};
+
+/// Default workflow state file, relative to the project directory. Unlike the state under
+/// `.objdiff`, it's meant to be committed and shared between contributors.
+pub const DEFAULT_WORKFLOW_PATH: &str = "objdiff-workflow.json";
+
+/// Progress of a function, as marked by the people working on it.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::VariantArray,
+    strum::EnumMessage,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowStatus {
+    #[default]
+    #[strum(message = "Not started")]
+    NotStarted,
+    #[strum(message = "In progress")]
+    InProgress,
+    /// Believed to be done, waiting for someone to check it
+    #[strum(message = "Needs review")]
+    NeedsReview,
+    #[strum(message = "Matched")]
+    Matched,
+}
+
+impl WorkflowStatus {
+    /// Name used in the state file and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            WorkflowStatus::NotStarted => "not_started",
+            WorkflowStatus::InProgress => "in_progress",
+            WorkflowStatus::NeedsReview => "needs_review",
+            WorkflowStatus::Matched => "matched",
+        }
+    }
+}
+
+impl FromStr for WorkflowStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.replace('-', "_").as_str() {
+            "not_started" | "none" => Ok(WorkflowStatus::NotStarted),
+            "in_progress" | "wip" => Ok(WorkflowStatus::InProgress),
+            "needs_review" | "review" => Ok(WorkflowStatus::NeedsReview),
+            "matched" | "done" => Ok(WorkflowStatus::Matched),
+            _ => Err(format!("Invalid workflow status: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SymbolWorkflow {
+    pub status: WorkflowStatus,
+    /// Who is working on the function
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Notes on instructions, by offset from the start of the function
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<u64, String>,
+}
+
+/// Workflow state of a project's functions. Functions without an entry are not started.
+#[derive(Debug, Clone, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct WorkflowState {
+    /// Unit name -> symbol name -> state
+    #[serde(default)]
+    pub units: BTreeMap<String, BTreeMap<String, SymbolWorkflow>>,
+}
+
+impl WorkflowState {
+    /// Load the state file. A missing file is an empty state.
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        serde_json::from_slice(&data).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Write the state file. Entries are sorted, so that the file diffs cleanly under
+    /// version control.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut data = serde_json::to_vec_pretty(self)?;
+        data.push(b'\n');
+        // Replaced atomically so that readers never see a partial file
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, data)
+            .and_then(|_| fs::rename(&tmp_path, path))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn get(&self, unit: &str, symbol: &str) -> Option<&SymbolWorkflow> {
+        self.units.get(unit)?.get(symbol)
+    }
+
+    pub fn status(&self, unit: &str, symbol: &str) -> WorkflowStatus {
+        self.get(unit, symbol).map(|s| s.status).unwrap_or_default()
+    }
+
+    /// Set the status of a function, keeping its assignee and note. Setting a function
+    /// back to not started without an assignee or note removes its entry.
+    pub fn set_status(&mut self, unit: &str, symbol: &str, status: WorkflowStatus) {
+        let entry =
+            self.units.entry(unit.to_string()).or_default().entry(symbol.to_string()).or_default();
+        entry.status = status;
+        if *entry == SymbolWorkflow::default() {
+            self.clear(unit, symbol);
+        }
+    }
+
+    /// Replace the state of a function.
+    pub fn set(&mut self, unit: &str, symbol: &str, workflow: SymbolWorkflow) {
+        if workflow == SymbolWorkflow::default() {
+            self.clear(unit, symbol);
+        } else {
+            self.units.entry(unit.to_string()).or_default().insert(symbol.to_string(), workflow);
+        }
+    }
+
+    /// Attach a note to the instruction at `offset` within a function, or remove it if the
+    /// note is empty.
+    pub fn set_annotation(&mut self, unit: &str, symbol: &str, offset: u64, note: &str) {
+        let mut workflow = self.get(unit, symbol).cloned().unwrap_or_default();
+        if note.is_empty() {
+            workflow.annotations.remove(&offset);
+        } else {
+            workflow.annotations.insert(offset, note.to_string());
+        }
+        self.set(unit, symbol, workflow);
+    }
+
+    /// Set the instruction comments of a unit's diff from its annotations.
+    pub fn annotate(&self, unit: &str, obj: &ObjInfo, diff: &mut ObjDiff) {
+        apply_annotations(self.units.get(unit).unwrap_or(&BTreeMap::new()), obj, diff);
+    }
+
+    /// Remove the state of a function, resetting it to not started.
+    pub fn clear(&mut self, unit: &str, symbol: &str) {
+        if let Some(symbols) = self.units.get_mut(unit) {
+            symbols.remove(symbol);
+            if symbols.is_empty() {
+                self.units.remove(unit);
+            }
+        }
+    }
+}
+
+/// Set the instruction comments of a diff from the annotations of its functions, keyed by
+/// symbol name. Existing comments are replaced.
+pub fn apply_annotations(
+    symbols: &BTreeMap<String, SymbolWorkflow>,
+    obj: &ObjInfo,
+    diff: &mut ObjDiff,
+) {
+    for (section, section_diff) in obj.sections.iter().zip(&mut diff.sections) {
+        if section.kind != ObjSectionKind::Code {
+            continue;
+        }
+        for (symbol, symbol_diff) in section.symbols.iter().zip(&mut section_diff.symbols) {
+            let annotations = symbols.get(&symbol.name).map(|w| &w.annotations);
+            for ins_diff in &mut symbol_diff.instructions {
+                ins_diff.comment = ins_diff
+                    .ins
+                    .as_ref()
+                    .and_then(|ins| annotations?.get(&(ins.address - symbol.address)).cloned());
+            }
+        }
+    }
+}
+
+/// Resolve the workflow state file of a project.
+pub fn workflow_path(project_dir: &Path, configured: Option<&Path>) -> PathBuf {
+    project_dir.join(configured.unwrap_or(Path::new(DEFAULT_WORKFLOW_PATH)))
+}
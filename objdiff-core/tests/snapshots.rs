@@ -0,0 +1,121 @@
+//! Golden snapshots of the diff display events for the sample object pairs in
+//! `tests/snapshots`. Each case is a directory `tests/snapshots/<arch>/<case>` holding a
+//! `target.o` and a `base.o`, whose diff is compared against `<case>/diff.snap`.
+//!
+//! After an intended change to the diff output, regenerate the snapshots with
+//! `cargo test -p objdiff-core --features all,update-snapshots --test snapshots` (or by
+//! setting `OBJDIFF_UPDATE_SNAPSHOTS=1`) and review them with `git diff`. A case without a
+//! snapshot fails until it's generated this way.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use objdiff_core::{
+    diff::{diff_objs, snapshot::unit_snapshot, DiffObjConfig},
+    obj::read,
+};
+
+/// Architecture directories, and whether the architecture is enabled.
+const ARCHES: &[(&str, bool)] = &[
+    ("arm", cfg!(feature = "arm")),
+    ("arm64", cfg!(feature = "arm64")),
+    ("m68k", cfg!(feature = "m68k")),
+    ("mips", cfg!(feature = "mips")),
+    ("ppc", cfg!(feature = "ppc")),
+    ("riscv", cfg!(feature = "riscv")),
+    ("sh", cfg!(feature = "sh")),
+    ("x86_64", cfg!(feature = "x86")),
+];
+
+fn snapshot(case: &Path) -> Result<String> {
+    let target = read::read(&case.join("target.o")).context("Failed to read target.o")?;
+    let base = read::read(&case.join("base.o")).context("Failed to read base.o")?;
+    let result = diff_objs(&DiffObjConfig::default(), Some(&target), Some(&base), None)?;
+    Ok(unit_snapshot(
+        result.left.as_ref().map(|d| (&target, d)),
+        result.right.as_ref().map(|d| (&base, d)),
+    ))
+}
+
+/// The first line at which two snapshots differ.
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => {}
+            (None, None) => break,
+            (e, a) => {
+                return format!(
+                    "line {line}:\n  expected: {}\n  actual:   {}",
+                    e.unwrap_or("<end>"),
+                    a.unwrap_or("<end>")
+                );
+            }
+        }
+    }
+    "line endings".to_string()
+}
+
+fn cases(dir: &Path) -> Vec<PathBuf> {
+    let mut cases = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+    cases.sort();
+    cases
+}
+
+#[test]
+fn snapshots() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+    let update = cfg!(feature = "update-snapshots")
+        || std::env::var_os("OBJDIFF_UPDATE_SNAPSHOTS").is_some_and(|v| v != "0");
+    let mut failures = Vec::new();
+    for entry in fs::read_dir(&root).unwrap() {
+        let dir = entry.unwrap().path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let arch = dir.file_name().unwrap().to_string_lossy().into_owned();
+        match ARCHES.iter().find(|(name, _)| *name == arch) {
+            Some((_, true)) => {}
+            Some((_, false)) => continue,
+            None => {
+                failures.push(format!("{arch}: unknown architecture directory"));
+                continue;
+            }
+        }
+        for case in cases(&dir) {
+            let name = format!("{arch}/{}", case.file_name().unwrap().to_string_lossy());
+            let actual = match snapshot(&case) {
+                Ok(actual) => actual,
+                Err(e) => {
+                    failures.push(format!("{name}: {e:#}"));
+                    continue;
+                }
+            };
+            let path = case.join("diff.snap");
+            match fs::read_to_string(&path) {
+                Ok(expected) if expected == actual => {}
+                _ if update => fs::write(&path, actual).unwrap(),
+                Ok(expected) => failures.push(format!(
+                    "{name}: snapshot differs at {}",
+                    first_difference(&expected, &actual)
+                )),
+                Err(_) => failures.push(format!("{name}: missing snapshot")),
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} snapshot(s) failed; rerun with the update-snapshots feature if the changes are \
+         intended:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
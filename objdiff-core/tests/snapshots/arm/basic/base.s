@@ -0,0 +1,35 @@
+	.syntax	unified
+	.text
+	.arm
+	.globl	add_scaled
+	.type	add_scaled, %function
+add_scaled:
+	lsl	r0, r0, #2
+	add	r0, r1, r0
+	add	r0, r0, #8
+	bx	lr
+	.size	add_scaled, .-add_scaled
+
+	.globl	update
+	.type	update, %function
+update:
+	push	{r4, lr}
+	bl	foo
+	ldr	r1, .Lcounter
+	ldr	r2, [r1]
+	add	r2, r2, r0
+	str	r2, [r1]
+	pop	{r4, pc}
+.Lcounter:
+	.word	counter
+	.size	update, .-update
+
+	.thumb
+	.globl	thumb_sum
+	.type	thumb_sum, %function
+	.thumb_func
+thumb_sum:
+	subs	r0, r0, r1
+	lsls	r0, r0, #1
+	bx	lr
+	.size	thumb_sum, .-thumb_sum
@@ -0,0 +1,25 @@
+	.text
+	.globl	add_scaled
+	.type	add_scaled, %function
+add_scaled:
+	add	w0, w1, w0, lsl #2
+	add	w0, w0, #16
+	ret
+	.size	add_scaled, .-add_scaled
+
+	.globl	update
+	.type	update, %function
+update:
+	stp	x29, x30, [sp, #-16]!
+	mov	x29, sp
+	bl	bar
+	adrp	x8, counter
+	ldr	w9, [x8, :lo12:counter]
+	add	w9, w9, w0
+	str	w9, [x8, :lo12:counter]
+	cbnz	w9, .Lnonzero
+	mov	w0, wzr
+.Lnonzero:
+	ldp	x29, x30, [sp], #16
+	ret
+	.size	update, .-update
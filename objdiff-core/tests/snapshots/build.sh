@@ -0,0 +1,25 @@
+#!/bin/sh
+# Rebuilds the sample objects from their sources. The objects are checked in, so that the
+# snapshots don't depend on the toolchain installed; only run this when changing a sample,
+# then regenerate the snapshots.
+set -e
+cd "$(dirname "$0")"
+CFLAGS="-O2 -fno-pic -fno-asynchronous-unwind-tables -fcf-protection=none -fno-stack-protector"
+for src in x86_64/*/*.c; do
+    gcc $CFLAGS -c "$src" -o "${src%.c}.o"
+done
+assemble() {
+    arch=$1
+    shift
+    for src in "$arch"/*/*.s; do
+        llvm-mc -filetype=obj "$@" "$src" -o "${src%.s}.o"
+    done
+}
+assemble arm -triple=armv5te-none-eabi
+assemble arm64 -triple=aarch64-none-elf
+assemble m68k -triple=m68k-unknown-linux
+assemble mips -triple=mips-unknown-linux -mcpu=mips3 -mattr=+noabicalls
+assemble ppc -triple=powerpc-unknown-eabi -mcpu=750
+assemble riscv -triple=riscv32-unknown-elf -mattr=+m,+c
+# LLVM has no SuperH backend
+python3 sh/build.py sh/*/*.s
@@ -0,0 +1,19 @@
+	.text
+	.globl	add_scaled
+	.type	add_scaled, @function
+add_scaled:
+	move.l	(4,%sp), %d0
+	lsl.l	#3, %d0
+	add.l	(8,%sp), %d0
+	rts
+	.size	add_scaled, .-add_scaled
+
+	.globl	update
+	.type	update, @function
+update:
+	jsr	foo
+	move.l	counter, %d1
+	add.l	%d1, %d0
+	move.l	%d0, counter
+	rts
+	.size	update, .-update
@@ -0,0 +1,29 @@
+	.set	noreorder
+	.text
+	.globl	add_scaled
+	.type	add_scaled, @function
+add_scaled:
+	sll	$v0, $a0, 2
+	addu	$v0, $v0, $a1
+	jr	$ra
+	addiu	$v0, $v0, 16
+	.size	add_scaled, .-add_scaled
+
+	.globl	update
+	.type	update, @function
+update:
+	addiu	$sp, $sp, -24
+	sw	$ra, 20($sp)
+	jal	bar
+	nop
+	lui	$t0, %hi(counter)
+	lw	$t1, %lo(counter)($t0)
+	addu	$t1, $t1, $v0
+	bgez	$t1, $Lpositive
+	sw	$t1, %lo(counter)($t0)
+	move	$v0, $zero
+$Lpositive:
+	lw	$ra, 20($sp)
+	jr	$ra
+	addiu	$sp, $sp, 24
+	.size	update, .-update
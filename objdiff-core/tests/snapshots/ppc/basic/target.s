@@ -0,0 +1,39 @@
+	.text
+	.globl	add_scaled
+	.type	add_scaled, @function
+add_scaled:
+	slwi	3, 3, 2
+	add	3, 3, 4
+	addi	3, 3, 16
+	blr
+	.size	add_scaled, .-add_scaled
+
+	.globl	update
+	.type	update, @function
+update:
+	mflr	0
+	stw	0, 4(1)
+	stwu	1, -16(1)
+	bl	bar
+	lis	4, counter@ha
+	lwz	5, counter@l(4)
+	add	5, 5, 3
+	stw	5, counter@l(4)
+	cmpwi	5, 0
+	bge	.Lpositive
+	li	3, 0
+.Lpositive:
+	lwz	0, 20(1)
+	addi	1, 1, 16
+	mtlr	0
+	blr
+	.size	update, .-update
+
+	.globl	scale
+	.type	scale, @function
+scale:
+	lis	4, factor@ha
+	lfs	2, factor@l(4)
+	fmuls	1, 1, 2
+	blr
+	.size	scale, .-scale
@@ -0,0 +1,28 @@
+	.option	norelax
+	.text
+	.globl	add_scaled
+	.type	add_scaled, @function
+add_scaled:
+	slli	a0, a0, 2
+	add	a0, a0, a1
+	addi	a0, a0, 8
+	ret
+	.size	add_scaled, .-add_scaled
+
+	.globl	update
+	.type	update, @function
+update:
+	addi	sp, sp, -16
+	sw	ra, 12(sp)
+	call	foo
+	lui	a1, %hi(counter)
+	lw	a2, %lo(counter)(a1)
+	add	a2, a2, a0
+	sw	a2, %lo(counter)(a1)
+	bgez	a2, .Lpositive
+	li	a0, 0
+.Lpositive:
+	lw	ra, 12(sp)
+	addi	sp, sp, 16
+	ret
+	.size	update, .-update
@@ -0,0 +1,27 @@
+	.globl	add_scaled
+add_scaled:
+	shll2	r4
+	add	r5, r4
+	mov	r4, r0
+	rts
+	add	#8, r0
+	.align	2
+
+	.globl	update
+update:
+	sts.l	pr, @-r15
+	mov.l	.Lfunc, r1
+	jsr	@r1
+	nop
+	mov.l	.Lcounter, r2
+	mov.l	@r2, r3
+	add r3, r0
+	mov.l	r3, @r2
+	lds.l	@r15+, pr
+	rts
+	nop
+	.align	2
+.Lfunc:
+	.long	foo
+.Lcounter:
+	.long	counter
@@ -0,0 +1,146 @@
+#!/usr/bin/env python3
+# Assembles the SH-4 samples into little-endian ELF objects. LLVM has no SuperH backend, so
+# this encodes the few instructions the samples use and writes the object directly.
+import re
+import struct
+import sys
+
+R_SH_DIR32 = 1
+
+REG = r"r(\d+)"
+# (pattern, encoder) for each supported instruction form
+FORMS = [
+    (rf"nop$", lambda: 0x0009),
+    (rf"rts$", lambda: 0x000B),
+    (rf"shll2 {REG}$", lambda n: 0x4008 | n << 8),
+    (rf"add {REG}, {REG}$", lambda m, n: 0x300C | n << 8 | m << 4),
+    (rf"add #(-?\d+), {REG}$", lambda i, n: 0x7000 | n << 8 | (i & 0xFF)),
+    (rf"mov {REG}, {REG}$", lambda m, n: 0x6003 | n << 8 | m << 4),
+    (rf"mov #(-?\d+), {REG}$", lambda i, n: 0xE000 | n << 8 | (i & 0xFF)),
+    (rf"mov\.l @{REG}, {REG}$", lambda m, n: 0x6002 | n << 8 | m << 4),
+    (rf"mov\.l {REG}, @{REG}$", lambda m, n: 0x2002 | n << 8 | m << 4),
+    (rf"sts\.l pr, @-r15$", lambda: 0x4F22),
+    (rf"lds\.l @r15\+, pr$", lambda: 0x4F26),
+    (rf"jsr @{REG}$", lambda n: 0x400B | n << 8),
+]
+
+
+def assemble(lines):
+    """Two passes: lay out instructions and labels, then encode."""
+    items = []  # (offset, kind, operands)
+    labels = {}
+    symbols = []  # global symbol names
+    offset = 0
+    for line in lines:
+        line = line.split(";")[0].strip()
+        if not line:
+            continue
+        if line.endswith(":"):
+            labels[line[:-1]] = offset
+            continue
+        op, _, rest = re.sub(r"\s+", " ", line).partition(" ")
+        if op == ".globl":
+            symbols.append(rest)
+        elif op == ".align":
+            while offset % (1 << int(rest)):
+                items.append((offset, "ins", "nop"))
+                offset += 2
+        elif op == ".long":
+            items.append((offset, "long", rest))
+            offset += 4
+        else:
+            items.append((offset, "ins", f"{op} {rest}".strip()))
+            offset += 2
+
+    data = bytearray(offset)
+    relocs = []  # (offset, symbol)
+    for at, kind, text in items:
+        if kind == "long":
+            relocs.append((at, text))
+            continue
+        m = re.fullmatch(rf"mov\.l (\.\w+), {REG}", text)
+        if m:
+            disp = (labels[m[1]] - ((at & ~3) + 4)) // 4
+            assert 0 <= disp < 256 and labels[m[1]] % 4 == 0, text
+            struct.pack_into("<H", data, at, 0xD000 | int(m[2]) << 8 | disp)
+            continue
+        for pattern, encode in FORMS:
+            m = re.fullmatch(pattern, text)
+            if m:
+                struct.pack_into("<H", data, at, encode(*map(int, m.groups())))
+                break
+        else:
+            sys.exit(f"unsupported instruction: {text}")
+
+    # Function sizes run to the next global symbol or the end of the section
+    starts = sorted(labels[name] for name in symbols) + [offset]
+    funcs = [(name, labels[name], starts[starts.index(labels[name]) + 1] - labels[name])
+             for name in symbols]
+    return bytes(data), funcs, relocs
+
+
+def write_elf(path, text, funcs, relocs):
+    strtab = bytearray(b"\0")
+
+    def add_str(name):
+        index = len(strtab)
+        strtab.extend(name.encode() + b"\0")
+        return index
+
+    # Null symbol, section symbol for .text, then globals: functions and undefined externs
+    symtab = [struct.pack("<IIIBBH", 0, 0, 0, 0, 0, 0),
+              struct.pack("<IIIBBH", 0, 0, 0, 3, 0, 1)]
+    first_global = len(symtab)
+    sym_index = {}
+    for name, value, size in funcs:
+        sym_index[name] = len(symtab)
+        symtab.append(struct.pack("<IIIBBH", add_str(name), value, size, 0x12, 0, 1))
+    for _, name in relocs:
+        if name not in sym_index:
+            sym_index[name] = len(symtab)
+            symtab.append(struct.pack("<IIIBBH", add_str(name), 0, 0, 0x10, 0, 0))
+    rela = b"".join(struct.pack("<IIi", at, sym_index[name] << 8 | R_SH_DIR32, 0)
+                    for at, name in relocs)
+
+    shstrtab = bytearray(b"\0")
+    names = {}
+    for name in [".text", ".rela.text", ".symtab", ".strtab", ".shstrtab"]:
+        names[name] = len(shstrtab)
+        shstrtab.extend(name.encode() + b"\0")
+
+    contents = [text, rela, b"".join(symtab), bytes(strtab), bytes(shstrtab)]
+    offsets = []
+    body = bytearray()
+    for content in contents:
+        while (52 + len(body)) % 4:
+            body.append(0)
+        offsets.append(52 + len(body))
+        body.extend(content)
+    while (52 + len(body)) % 4:
+        body.append(0)
+    shoff = 52 + len(body)
+
+    # name, type, flags, offset, size, link, info, align, entsize
+    sections = [
+        (0, 0, 0, 0, 0, 0, 0, 0, 0),
+        (names[".text"], 1, 0x6, offsets[0], len(text), 0, 0, 4, 0),
+        (names[".rela.text"], 4, 0x40, offsets[1], len(rela), 3, 1, 4, 12),
+        (names[".symtab"], 2, 0, offsets[2], len(contents[2]), 4, first_global, 4, 16),
+        (names[".strtab"], 3, 0, offsets[3], len(strtab), 0, 0, 1, 0),
+        (names[".shstrtab"], 3, 0, offsets[4], len(shstrtab), 0, 0, 1, 0),
+    ]
+    ident = b"\x7fELF" + bytes([1, 1, 1, 0]) + bytes(8)
+    # EM_SH, EF_SH4
+    header = ident + struct.pack("<HHIIIIIHHHHHH", 1, 42, 1, 0, 0, shoff, 0x9, 52, 0, 0, 40,
+                                 len(sections), 5)
+    with open(path, "wb") as f:
+        f.write(header + body)
+        for name, typ, flags, off, size, link, info, align, entsize in sections:
+            f.write(struct.pack("<IIIIIIIIII", name, typ, flags, 0, off, size, link, info,
+                                align, entsize))
+
+
+for src in sys.argv[1:]:
+    with open(src) as f:
+        text, funcs, relocs = assemble(f.readlines())
+    write_elf(src[:-2] + ".o", text, funcs, relocs)
@@ -0,0 +1,5 @@
+int scale(int x, int y) { return x * 5 + y; }
+
+unsigned int mask_bits(unsigned int value, int shift) { return (value >> shift) & 0xff; }
+
+long mix(long a, long b, long c) { return (b ^ c) + a * 3; }
@@ -0,0 +1,11 @@
+extern int counter;
+extern int limit;
+int foo(int);
+int bar(int);
+
+int step(int x) {
+    counter += foo(x + 1);
+    return counter < limit;
+}
+
+int twice(int x) { return foo(foo(x)); }
@@ -0,0 +1,19 @@
+int sum(const int *values, int count) {
+    int total = 0;
+    for (int i = 0; i < count; i++) {
+        if (values[i] < 0) {
+            continue;
+        }
+        total += values[i];
+    }
+    return total;
+}
+
+int find(const int *values, int count, int needle) {
+    for (int i = 0; i < count; i++) {
+        if (values[i] == needle) {
+            return i;
+        }
+    }
+    return -1;
+}
@@ -0,0 +1,16 @@
+int sum(const int *values, int count) {
+    int total = 0;
+    for (int i = 0; i < count; i++) {
+        total += values[i];
+    }
+    return total;
+}
+
+int find(const int *values, int count, int needle) {
+    for (int i = 0; i < count; i++) {
+        if (values[i] == needle) {
+            return i;
+        }
+    }
+    return -1;
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     default::Default,
     fs,
     path::{Path, PathBuf},
@@ -13,11 +14,19 @@ use filetime::FileTime;
 use globset::{Glob, GlobSet};
 use notify::{RecursiveMode, Watcher};
 use objdiff_core::{
+    build::BuildCommand,
     config::{
         build_globset, ProjectConfigInfo, ProjectObject, ScratchConfig, DEFAULT_WATCH_PATTERNS,
     },
-    diff::DiffObjConfig,
+    diff::{idiom::Idiom, DiffObjConfig, DiffPolicy, DiffPolicyPreset, NumberRadix},
+    obj::{
+        binary::{FileRange, SymbolMapConfig},
+        demangle::Demangler,
+        read, AcceptedMismatch, SizeInference, SymbolMappings,
+    },
+    workflow::{WorkflowState, WorkflowStatus},
 };
+use strum::{EnumMessage, VariantArray};
 use time::UtcOffset;
 
 use crate::{
@@ -65,6 +74,18 @@ pub struct ObjectConfig {
     pub reverse_fn_order: Option<bool>,
     pub complete: Option<bool>,
     pub scratch: Option<ScratchConfig>,
+    #[serde(default)]
+    pub symbol_mappings: SymbolMappings,
+    #[serde(default)]
+    pub target_map: Option<SymbolMapConfig>,
+    #[serde(default)]
+    pub target_range: Option<FileRange>,
+    #[serde(default)]
+    pub accepted_mismatches: Vec<AcceptedMismatch>,
+    #[serde(default)]
+    pub size_inference: SizeInference,
+    #[serde(default)]
+    pub build: Option<BuildCommand>,
 }
 
 #[inline]
@@ -109,6 +130,11 @@ pub struct AppConfig {
     pub recent_projects: Vec<PathBuf>,
     #[serde(default)]
     pub diff_obj_config: DiffObjConfig,
+    /// Symbol mappings added from the UI, keyed by object name.
+    #[serde(default)]
+    pub custom_symbol_mappings: BTreeMap<String, SymbolMappings>,
+    #[serde(default)]
+    pub demangler: Demangler,
 
     #[serde(skip)]
     pub objects: Vec<ProjectObject>,
@@ -126,6 +152,11 @@ pub struct AppConfig {
     pub queue_reload: bool,
     #[serde(skip)]
     pub project_config_info: Option<ProjectConfigInfo>,
+    /// Shared workflow state file of the project, if a project config is loaded
+    #[serde(skip)]
+    pub workflow_path: Option<PathBuf>,
+    #[serde(skip)]
+    pub workflow: WorkflowState,
 }
 
 impl Default for AppConfig {
@@ -146,6 +177,8 @@ impl Default for AppConfig {
             watch_patterns: DEFAULT_WATCH_PATTERNS.iter().map(|s| Glob::new(s).unwrap()).collect(),
             recent_projects: vec![],
             diff_obj_config: Default::default(),
+            custom_symbol_mappings: Default::default(),
+            demangler: Default::default(),
             objects: vec![],
             object_nodes: vec![],
             watcher_change: false,
@@ -154,6 +187,8 @@ impl Default for AppConfig {
             queue_build: false,
             queue_reload: false,
             project_config_info: None,
+            workflow_path: None,
+            workflow: Default::default(),
         }
     }
 }
@@ -177,6 +212,8 @@ impl AppConfig {
         self.obj_change = true;
         self.queue_build = false;
         self.project_config_info = None;
+        self.workflow_path = None;
+        self.workflow = Default::default();
     }
 
     pub fn set_target_obj_dir(&mut self, path: PathBuf) {
@@ -198,6 +235,68 @@ impl AppConfig {
         self.obj_change = true;
         self.queue_build = false;
     }
+
+    /// Maps a target symbol name to a base symbol name for the selected object
+    /// and queues a rebuild.
+    pub fn add_symbol_mapping(&mut self, target: String, base: String) {
+        let Some(selected_obj) = &self.selected_obj else {
+            return;
+        };
+        self.custom_symbol_mappings
+            .entry(selected_obj.name.clone())
+            .or_default()
+            .insert(target, base);
+        self.queue_build = true;
+    }
+
+    /// Removes a symbol mapping added with [`AppConfig::add_symbol_mapping`].
+    pub fn remove_symbol_mapping(&mut self, target: &str) {
+        let Some(selected_obj) = &self.selected_obj else {
+            return;
+        };
+        if let Some(mappings) = self.custom_symbol_mappings.get_mut(&selected_obj.name) {
+            if mappings.remove(target).is_some() {
+                self.queue_build = true;
+            }
+        }
+    }
+
+    /// Returns the symbol mappings for the selected object, combining the
+    /// project configuration with mappings added from the UI.
+    pub fn selected_symbol_mappings(&self) -> SymbolMappings {
+        let Some(selected_obj) = &self.selected_obj else {
+            return SymbolMappings::default();
+        };
+        let mut mappings = selected_obj.symbol_mappings.clone();
+        if let Some(custom) = self.custom_symbol_mappings.get(&selected_obj.name) {
+            mappings.extend(custom.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        mappings
+    }
+
+    /// Sets the workflow status of a symbol in the selected object and saves the
+    /// project's workflow state file.
+    pub fn set_workflow_status(&mut self, symbol: &str, status: WorkflowStatus) {
+        let (Some(selected_obj), Some(path)) = (&self.selected_obj, &self.workflow_path) else {
+            return;
+        };
+        self.workflow.set_status(&selected_obj.name, symbol, status);
+        if let Err(e) = self.workflow.save(path) {
+            log::error!("Failed to save workflow state: {e:#}");
+        }
+    }
+
+    /// Attaches a note to an instruction of a symbol in the selected object, or removes it
+    /// if the note is empty, and saves the project's workflow state file.
+    pub fn set_annotation(&mut self, symbol: &str, offset: u64, note: &str) {
+        let (Some(selected_obj), Some(path)) = (&self.selected_obj, &self.workflow_path) else {
+            return;
+        };
+        self.workflow.set_annotation(&selected_obj.name, symbol, offset, note);
+        if let Err(e) = self.workflow.save(path) {
+            log::error!("Failed to save workflow state: {e:#}");
+        }
+    }
 }
 
 pub type AppConfigRef = Arc<RwLock<AppConfig>>;
@@ -340,8 +439,14 @@ impl App {
             if let Some(project_dir) = &config.project_dir {
                 match build_globset(&config.watch_patterns).map_err(anyhow::Error::new).and_then(
                     |globset| {
-                        create_watcher(ctx.clone(), self.modified.clone(), project_dir, globset)
-                            .map_err(anyhow::Error::new)
+                        create_watcher(
+                            ctx.clone(),
+                            self.modified.clone(),
+                            project_dir,
+                            globset,
+                            config.workflow_path.clone(),
+                        )
+                        .map_err(anyhow::Error::new)
                     },
                 ) {
                     Ok(watcher) => self.watcher = Some(watcher),
@@ -503,6 +608,54 @@ impl eframe::App for App {
                     {
                         config.queue_reload = true;
                     }
+                    if ui
+                        .checkbox(
+                            &mut config.diff_obj_config.detect_moved_blocks,
+                            "Detect moved blocks",
+                        )
+                        .on_hover_text(
+                            "Marks reordered instruction blocks instead of showing them as inserted and deleted.",
+                        )
+                        .changed()
+                    {
+                        config.queue_reload = true;
+                    }
+                    if ui
+                        .checkbox(
+                            &mut config.diff_obj_config.ignore_padding,
+                            "Ignore alignment padding",
+                        )
+                        .on_hover_text(
+                            "Excludes nops padding the end of functions from the match percentage. They're still shown, dimmed.",
+                        )
+                        .changed()
+                    {
+                        config.queue_reload = true;
+                    }
+                    if ui
+                        .checkbox(
+                            &mut config.diff_obj_config.normalize_reloc_addends,
+                            "Normalize relocation addends",
+                        )
+                        .on_hover_text(
+                            "Resolves relocations like sym+0x8 to the symbol containing the target address before comparing them.",
+                        )
+                        .changed()
+                    {
+                        config.queue_reload = true;
+                    }
+                    if ui
+                        .checkbox(
+                            &mut config.diff_obj_config.symbolic_addresses,
+                            "Compare addresses by section",
+                        )
+                        .on_hover_text(
+                            "Compares immediates inside a section's address range by section and offset. Useful for linked binaries.",
+                        )
+                        .changed()
+                    {
+                        config.queue_reload = true;
+                    }
                     if ui
                         .checkbox(
                             &mut config.diff_obj_config.space_between_args,
@@ -512,6 +665,78 @@ impl eframe::App for App {
                     {
                         config.queue_reload = true;
                     }
+                    if ui
+                        .checkbox(
+                            &mut config.diff_obj_config.show_encoding,
+                            "Show instruction encoding",
+                        )
+                        .on_hover_text("Shows the bytes of each instruction after its address.")
+                        .changed()
+                    {
+                        config.queue_reload = true;
+                    }
+                    ui.menu_button("Number format", |ui| {
+                        let number_format = &mut config.diff_obj_config.number_format;
+                        for &radix in NumberRadix::VARIANTS {
+                            if ui
+                                .radio(number_format.radix == radix, radix.get_message().unwrap())
+                                .clicked()
+                            {
+                                number_format.radix = radix;
+                            }
+                        }
+                        ui.separator();
+                        ui.checkbox(&mut number_format.unsigned, "Unsigned negative values")
+                            .on_hover_text("Shows -0x4 as 0xfffffffc.");
+                        ui.horizontal(|ui| {
+                            ui.label("Minimum digits:");
+                            ui.add(egui::DragValue::new(&mut number_format.min_digits).clamp_range(0..=16));
+                        });
+                    });
+                    ui.menu_button("Match scoring", |ui| {
+                        let current = config.diff_obj_config.policy.preset();
+                        for &preset in DiffPolicyPreset::VARIANTS {
+                            if ui
+                                .radio(current == Some(preset), preset.get_message().unwrap())
+                                .clicked()
+                            {
+                                config.diff_obj_config.policy = DiffPolicy::from_preset(preset);
+                                config.queue_reload = true;
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        if ui
+                            .checkbox(
+                                &mut config.diff_obj_config.policy.strict_bytes,
+                                "Byte-exact instructions",
+                            )
+                            .on_hover_text(
+                                "Counts instructions whose encodings differ as mismatched, even if they decode the same.",
+                            )
+                            .changed()
+                        {
+                            config.queue_reload = true;
+                        }
+                    });
+                    ui.menu_button("Equivalent idioms", |ui| {
+                        let idioms = &mut config.diff_obj_config.idioms;
+                        for &idiom in Idiom::VARIANTS {
+                            let mut enabled = idioms.contains(&idiom);
+                            if ui
+                                .checkbox(&mut enabled, idiom.get_message().unwrap())
+                                .on_hover_text(idiom.get_detailed_message().unwrap())
+                                .changed()
+                            {
+                                if enabled {
+                                    idioms.insert(idiom);
+                                } else {
+                                    idioms.remove(&idiom);
+                                }
+                                config.queue_reload = true;
+                            }
+                        }
+                    });
                 });
             });
         });
@@ -561,6 +786,7 @@ fn create_watcher(
     modified: Arc<AtomicBool>,
     project_dir: &Path,
     patterns: GlobSet,
+    ignore_path: Option<PathBuf>,
 ) -> notify::Result<notify::RecommendedWatcher> {
     let base_dir = project_dir.to_owned();
     let mut watcher =
@@ -573,6 +799,10 @@ fn create_watcher(
                         | notify::EventKind::Remove(..)
                 ) {
                     for path in &event.paths {
+                        // Marking symbols in the UI shouldn't trigger a rebuild
+                        if ignore_path.as_deref() == Some(path.as_path()) {
+                            continue;
+                        }
                         let Ok(path) = path.strip_prefix(&base_dir) else {
                             continue;
                         };
@@ -592,7 +822,7 @@ fn create_watcher(
 
 #[inline]
 fn file_modified(path: &Path, last_ts: FileTime) -> bool {
-    if let Ok(metadata) = fs::metadata(path) {
+    if let Ok(metadata) = fs::metadata(read::file_path(path)) {
         FileTime::from_last_modification_time(&metadata) != last_ts
     } else {
         false
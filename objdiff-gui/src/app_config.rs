@@ -61,6 +61,12 @@ impl ObjectConfigV0 {
             reverse_fn_order: self.reverse_fn_order,
             complete: None,
             scratch: None,
+            symbol_mappings: Default::default(),
+            target_map: None,
+            target_range: None,
+            accepted_mismatches: Default::default(),
+            size_inference: Default::default(),
+            build: None,
         }
     }
 }
@@ -2,7 +2,10 @@ use std::path::{Component, Path};
 
 use anyhow::Result;
 use globset::Glob;
-use objdiff_core::config::{try_project_config, ProjectObject, DEFAULT_WATCH_PATTERNS};
+use objdiff_core::{
+    config::{expand_archive_objects, try_project_config, ProjectObject, DEFAULT_WATCH_PATTERNS},
+    workflow::{workflow_path, WorkflowState},
+};
 
 use crate::app::AppConfig;
 
@@ -80,13 +83,39 @@ pub fn load_project_config(config: &mut AppConfig) -> Result<()> {
             DEFAULT_WATCH_PATTERNS.iter().map(|s| Glob::new(s).unwrap()).collect()
         });
         config.watcher_change = true;
-        config.objects = project_config.objects;
+        if let Some(demangler) = project_config.demangler {
+            config.demangler = demangler;
+        }
+        if let Some(category) = project_config.mips_instr_category {
+            config.diff_obj_config.mips_instr_category = category;
+        }
+        if let Some(number_format) = project_config.number_format {
+            config.diff_obj_config.number_format = number_format;
+        }
+        if let Some(ignore_padding) = project_config.ignore_padding {
+            config.diff_obj_config.ignore_padding = ignore_padding;
+        }
+        if let Some(idioms) = project_config.idioms {
+            config.diff_obj_config.idioms = idioms;
+        }
+        config.objects = expand_archive_objects(
+            project_config.objects,
+            project_dir,
+            config.target_obj_dir.as_deref(),
+            config.base_obj_dir.as_deref(),
+        );
         config.object_nodes = build_nodes(
             &config.objects,
             project_dir,
             config.target_obj_dir.as_deref(),
             config.base_obj_dir.as_deref(),
         );
+        let path = workflow_path(project_dir, project_config.workflow_path.as_deref());
+        config.workflow = WorkflowState::load(&path).unwrap_or_else(|e| {
+            log::error!("Failed to load workflow state: {e:#}");
+            Default::default()
+        });
+        config.workflow_path = Some(path);
         config.project_config_info = Some(info);
     }
     Ok(())
@@ -1,12 +1,17 @@
 use std::{fs, path::PathBuf, sync::mpsc::Receiver};
 
-use anyhow::{anyhow, bail, Context, Result};
-use const_format::formatcp;
+use anyhow::{anyhow, bail, Result};
+use objdiff_core::{
+    build::{BuildCommand, BuildConfig, BuildStatus},
+    config::ScratchConfig,
+    scratch,
+    scratch::{create_scratch, CreateScratchResult},
+};
 
 use crate::{
     app::AppConfig,
     jobs::{
-        objdiff::{run_make, BuildConfig, BuildStatus},
+        objdiff::{build_config, run_make},
         start_job, update_status, Job, JobContext, JobResult, JobState,
     },
 };
@@ -14,13 +19,8 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct CreateScratchConfig {
     pub build_config: BuildConfig,
-    pub context_path: Option<PathBuf>,
-    pub build_context: bool,
-
-    // Scratch fields
-    pub compiler: String,
-    pub platform: String,
-    pub compiler_flags: String,
+    pub build_command: Option<BuildCommand>,
+    pub scratch_config: ScratchConfig,
     pub function_name: String,
     pub target_obj: PathBuf,
 }
@@ -37,12 +37,9 @@ impl CreateScratchConfig {
             bail!("No scratch configuration for {}", selected_obj.name);
         };
         Ok(Self {
-            build_config: BuildConfig::from_config(config),
-            context_path: scratch_config.ctx_path.clone(),
-            build_context: scratch_config.build_ctx,
-            compiler: scratch_config.compiler.clone().unwrap_or_default(),
-            platform: scratch_config.platform.clone().unwrap_or_default(),
-            compiler_flags: scratch_config.c_flags.clone().unwrap_or_default(),
+            build_config: build_config(config),
+            build_command: selected_obj.build.clone(),
+            scratch_config: scratch_config.clone(),
             function_name,
             target_obj: target_path.to_path_buf(),
         })
@@ -56,19 +53,6 @@ impl CreateScratchConfig {
     }
 }
 
-#[derive(Default, Debug, Clone)]
-pub struct CreateScratchResult {
-    pub scratch_url: String,
-}
-
-#[derive(Debug, Default, Clone, serde::Deserialize)]
-struct CreateScratchResponse {
-    pub slug: String,
-    pub claim_token: String,
-}
-
-const API_HOST: &str = "https://decomp.me";
-
 fn run_create_scratch(
     status: &JobContext,
     cancel: Receiver<()>,
@@ -78,10 +62,10 @@ fn run_create_scratch(
         config.build_config.project_dir.as_ref().ok_or_else(|| anyhow!("Missing project dir"))?;
 
     let mut context = None;
-    if let Some(context_path) = &config.context_path {
-        if config.build_context {
+    if let Some(context_path) = &config.scratch_config.ctx_path {
+        if config.scratch_config.build_ctx {
             update_status(status, "Building context".to_string(), 0, 2, &cancel)?;
-            match run_make(&config.build_config, context_path) {
+            match run_make(&config.build_config, config.build_command.as_ref(), context_path) {
                 BuildStatus { success: true, .. } => {}
                 BuildStatus { success: false, stdout, stderr, .. } => {
                     bail!("Failed to build context:\n{stdout}\n{stderr}")
@@ -96,34 +80,15 @@ fn run_create_scratch(
     }
 
     update_status(status, "Creating scratch".to_string(), 1, 2, &cancel)?;
-    let diff_flags = [format!("--disassemble={}", config.function_name)];
-    let diff_flags = serde_json::to_string(&diff_flags).unwrap();
-    let obj_path = project_dir.join(&config.target_obj);
-    let file = reqwest::blocking::multipart::Part::file(&obj_path)
-        .with_context(|| format!("Failed to open {}", obj_path.display()))?;
-    let form = reqwest::blocking::multipart::Form::new()
-        .text("compiler", config.compiler.clone())
-        .text("platform", config.platform.clone())
-        .text("compiler_flags", config.compiler_flags.clone())
-        .text("diff_label", config.function_name.clone())
-        .text("diff_flags", diff_flags)
-        .text("context", context.unwrap_or_default())
-        .text("source_code", "// Move related code from Context tab to here")
-        .part("target_obj", file);
-    let client = reqwest::blocking::Client::new();
-    let response = client
-        .post(formatcp!("{API_HOST}/api/scratch"))
-        .multipart(form)
-        .send()
-        .map_err(|e| anyhow!("Failed to send request: {}", e))?;
-    if !response.status().is_success() {
-        return Err(anyhow!("Failed to create scratch: {}", response.text()?));
-    }
-    let body: CreateScratchResponse = response.json().context("Failed to parse response")?;
-    let scratch_url = format!("{API_HOST}/scratch/{}/claim?token={}", body.slug, body.claim_token);
+    let result = create_scratch(&scratch::CreateScratchConfig::new(
+        &config.scratch_config,
+        project_dir.join(&config.target_obj),
+        config.function_name.clone(),
+        context,
+    ))?;
 
     update_status(status, "Complete".to_string(), 2, 2, &cancel)?;
-    Ok(Box::from(CreateScratchResult { scratch_url }))
+    Ok(Box::from(result))
 }
 
 pub fn start_create_scratch(ctx: &egui::Context, config: CreateScratchConfig) -> JobState {
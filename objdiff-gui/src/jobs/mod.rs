@@ -8,15 +8,17 @@ use std::{
 };
 
 use anyhow::Result;
+use objdiff_core::scratch::CreateScratchResult;
 
 use crate::jobs::{
-    check_update::CheckUpdateResult, create_scratch::CreateScratchResult, objdiff::ObjDiffResult,
+    check_update::CheckUpdateResult, objdiff::ObjDiffResult, project_diff::ProjectDiffResult,
     update::UpdateResult,
 };
 
 pub mod check_update;
 pub mod create_scratch;
 pub mod objdiff;
+pub mod project_diff;
 pub mod update;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -25,6 +27,7 @@ pub enum Job {
     CheckUpdate,
     Update,
     CreateScratch,
+    ProjectDiff,
 }
 pub static JOB_ID: AtomicUsize = AtomicUsize::new(0);
 
@@ -125,6 +128,7 @@ pub enum JobResult {
     CheckUpdate(Option<Box<CheckUpdateResult>>),
     Update(Box<UpdateResult>),
     CreateScratch(Option<Box<CreateScratchResult>>),
+    ProjectDiff(Option<Box<ProjectDiffResult>>),
 }
 
 fn should_cancel(rx: &Receiver<()>) -> bool {
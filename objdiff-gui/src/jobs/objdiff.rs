@@ -1,14 +1,14 @@
-use std::{
-    path::{Path, PathBuf},
-    process::Command,
-    str::from_utf8,
-    sync::mpsc::Receiver,
-};
+use std::{path::Path, sync::mpsc::Receiver};
 
 use anyhow::{anyhow, Context, Error, Result};
 use objdiff_core::{
-    diff::{diff_objs, DiffObjConfig, ObjDiff},
-    obj::{read, ObjInfo},
+    build::{BuildCommand, BuildConfig, BuildRunner, BuildStatus, CommandRunner},
+    diff::{diff_objs_with_progress, DiffObjConfig, DiffProgress, ObjDiff},
+    obj::{
+        binary,
+        demangle::{demangle_symbols, Demangler},
+        read, ObjInfo,
+    },
 };
 use time::OffsetDateTime;
 
@@ -17,40 +17,15 @@ use crate::{
     jobs::{start_job, update_status, Job, JobContext, JobResult, JobState},
 };
 
-pub struct BuildStatus {
-    pub success: bool,
-    pub cmdline: String,
-    pub stdout: String,
-    pub stderr: String,
-}
+/// Functions with at least this many instructions report their diff progress.
+const LARGE_FUNCTION_INSTRUCTIONS: usize = 4096;
 
-impl Default for BuildStatus {
-    fn default() -> Self {
-        BuildStatus {
-            success: true,
-            cmdline: String::new(),
-            stdout: String::new(),
-            stderr: String::new(),
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct BuildConfig {
-    pub project_dir: Option<PathBuf>,
-    pub custom_make: Option<String>,
-    pub custom_args: Option<Vec<String>>,
-    pub selected_wsl_distro: Option<String>,
-}
-
-impl BuildConfig {
-    pub(crate) fn from_config(config: &AppConfig) -> Self {
-        Self {
-            project_dir: config.project_dir.clone(),
-            custom_make: config.custom_make.clone(),
-            custom_args: config.custom_args.clone(),
-            selected_wsl_distro: config.selected_wsl_distro.clone(),
-        }
+pub(crate) fn build_config(config: &AppConfig) -> BuildConfig {
+    BuildConfig {
+        project_dir: config.project_dir.clone(),
+        custom_make: config.custom_make.clone(),
+        custom_args: config.custom_args.clone(),
+        selected_wsl_distro: config.selected_wsl_distro.clone(),
     }
 }
 
@@ -60,16 +35,21 @@ pub struct ObjDiffConfig {
     pub build_target: bool,
     pub selected_obj: Option<ObjectConfig>,
     pub diff_obj_config: DiffObjConfig,
+    pub demangler: Demangler,
 }
 
 impl ObjDiffConfig {
     pub(crate) fn from_config(config: &AppConfig) -> Self {
         Self {
-            build_config: BuildConfig::from_config(config),
+            build_config: build_config(config),
             build_base: config.build_base,
             build_target: config.build_target,
-            selected_obj: config.selected_obj.clone(),
+            selected_obj: config.selected_obj.clone().map(|mut obj| {
+                obj.symbol_mappings = config.selected_symbol_mappings();
+                obj
+            }),
             diff_obj_config: config.diff_obj_config.clone(),
+            demangler: config.demangler,
         }
     }
 }
@@ -82,76 +62,12 @@ pub struct ObjDiffResult {
     pub time: OffsetDateTime,
 }
 
-pub(crate) fn run_make(config: &BuildConfig, arg: &Path) -> BuildStatus {
-    let Some(cwd) = &config.project_dir else {
-        return BuildStatus {
-            success: false,
-            stderr: "Missing project dir".to_string(),
-            ..Default::default()
-        };
-    };
-    match run_make_cmd(config, cwd, arg) {
-        Ok(status) => status,
-        Err(e) => BuildStatus { success: false, stderr: e.to_string(), ..Default::default() },
-    }
-}
-
-fn run_make_cmd(config: &BuildConfig, cwd: &Path, arg: &Path) -> Result<BuildStatus> {
-    let make = config.custom_make.as_deref().unwrap_or("make");
-    let make_args = config.custom_args.as_deref().unwrap_or(&[]);
-    #[cfg(not(windows))]
-    let mut command = {
-        let mut command = Command::new(make);
-        command.current_dir(cwd).args(make_args).arg(arg);
-        command
-    };
-    #[cfg(windows)]
-    let mut command = {
-        use std::os::windows::process::CommandExt;
-
-        use path_slash::PathExt;
-        let mut command = if config.selected_wsl_distro.is_some() {
-            Command::new("wsl")
-        } else {
-            Command::new(make)
-        };
-        if let Some(distro) = &config.selected_wsl_distro {
-            // Strip distro root prefix \\wsl.localhost\{distro}
-            let wsl_path_prefix = format!("\\\\wsl.localhost\\{}", distro);
-            let cwd = match cwd.strip_prefix(wsl_path_prefix) {
-                Ok(new_cwd) => format!("/{}", new_cwd.to_slash_lossy().as_ref()),
-                Err(_) => cwd.to_string_lossy().to_string(),
-            };
-
-            command
-                .arg("--cd")
-                .arg(cwd)
-                .arg("-d")
-                .arg(distro)
-                .arg("--")
-                .arg(make)
-                .args(make_args)
-                .arg(arg.to_slash_lossy().as_ref());
-        } else {
-            command.current_dir(cwd).args(make_args).arg(arg.to_slash_lossy().as_ref());
-        }
-        command.creation_flags(winapi::um::winbase::CREATE_NO_WINDOW);
-        command
-    };
-    let mut cmdline = shell_escape::escape(command.get_program().to_string_lossy()).into_owned();
-    for arg in command.get_args() {
-        cmdline.push(' ');
-        cmdline.push_str(shell_escape::escape(arg.to_string_lossy()).as_ref());
-    }
-    let output = command.output().context("Failed to execute build")?;
-    let stdout = from_utf8(&output.stdout).context("Failed to process stdout")?;
-    let stderr = from_utf8(&output.stderr).context("Failed to process stderr")?;
-    Ok(BuildStatus {
-        success: output.status.code().unwrap_or(-1) == 0,
-        cmdline,
-        stdout: stdout.to_string(),
-        stderr: stderr.to_string(),
-    })
+pub(crate) fn run_make(
+    config: &BuildConfig,
+    unit: Option<&BuildCommand>,
+    arg: &Path,
+) -> BuildStatus {
+    CommandRunner.build(config, unit, arg)
 }
 
 fn run_build(
@@ -204,7 +120,11 @@ fn run_build(
                 total,
                 &cancel,
             )?;
-            run_make(&config.build_config, target_path_rel)
+            run_make(
+                &config.build_config,
+                obj_config.build.as_ref(),
+                &read::file_path(target_path_rel),
+            )
         }
         _ => BuildStatus::default(),
     };
@@ -218,29 +138,40 @@ fn run_build(
                 total,
                 &cancel,
             )?;
-            run_make(&config.build_config, base_path_rel)
+            run_make(
+                &config.build_config,
+                obj_config.build.as_ref(),
+                &read::file_path(base_path_rel),
+            )
         }
         _ => BuildStatus::default(),
     };
 
     let time = OffsetDateTime::now_utc();
 
-    let first_obj =
-        match &obj_config.target_path {
-            Some(target_path) if first_status.success => {
-                update_status(
-                    context,
-                    format!("Loading target {}", target_path_rel.unwrap().display()),
-                    2,
-                    total,
-                    &cancel,
-                )?;
-                Some(read::read(target_path).with_context(|| {
-                    format!("Failed to read object '{}'", target_path.display())
-                })?)
-            }
-            _ => None,
-        };
+    let first_obj = match &obj_config.target_path {
+        Some(target_path) if first_status.success => {
+            update_status(
+                context,
+                format!("Loading target {}", target_path_rel.unwrap().display()),
+                2,
+                total,
+                &cancel,
+            )?;
+            let mut obj = binary::read_target(
+                target_path,
+                obj_config.target_map.as_ref(),
+                obj_config.target_range.as_ref(),
+            )
+            .with_context(|| format!("Failed to read object '{}'", target_path.display()))?;
+            read::infer_symbol_sizes(&mut obj, obj_config.size_inference);
+            read::apply_accepted_mismatches(&mut obj, &obj_config.accepted_mismatches);
+            read::apply_symbol_mappings(&mut obj, &obj_config.symbol_mappings);
+            demangle_symbols(&mut obj, config.demangler);
+            Some(obj)
+        }
+        _ => None,
+    };
 
     let second_obj = match &obj_config.base_path {
         Some(base_path) if second_status.success => {
@@ -251,16 +182,43 @@ fn run_build(
                 total,
                 &cancel,
             )?;
-            Some(
-                read::read(base_path)
-                    .with_context(|| format!("Failed to read object '{}'", base_path.display()))?,
-            )
+            let mut obj = read::read(base_path)
+                .with_context(|| format!("Failed to read object '{}'", base_path.display()))?;
+            read::infer_symbol_sizes(&mut obj, obj_config.size_inference);
+            demangle_symbols(&mut obj, config.demangler);
+            Some(obj)
         }
         _ => None,
     };
 
     update_status(context, "Performing diff".to_string(), 4, total, &cancel)?;
-    let result = diff_objs(&config.diff_obj_config, first_obj.as_ref(), second_obj.as_ref(), None)?;
+    let result = diff_objs_with_progress(
+        &config.diff_obj_config,
+        first_obj.as_ref(),
+        second_obj.as_ref(),
+        None,
+        |symbol_ref, progress| {
+            // Only report functions large enough to keep the diff busy for a while
+            let detail = match progress {
+                DiffProgress::Aligning { instructions }
+                    if instructions >= LARGE_FUNCTION_INSTRUCTIONS =>
+                {
+                    format!("aligning {instructions} instructions")
+                }
+                DiffProgress::Comparing { compared, total }
+                    if total >= LARGE_FUNCTION_INSTRUCTIONS =>
+                {
+                    format!("{compared}/{total} rows")
+                }
+                _ => return,
+            };
+            let (_, symbol) = first_obj.as_ref().unwrap().section_symbol(symbol_ref);
+            if let Ok(mut status) = context.status.write() {
+                status.status = format!("Performing diff: {} ({detail})", symbol.name);
+            }
+            context.egui.request_repaint();
+        },
+    )?;
 
     update_status(context, "Complete".to_string(), total, total, &cancel)?;
     Ok(Box::new(ObjDiffResult {
@@ -0,0 +1,112 @@
+use std::{
+    collections::HashMap,
+    sync::{mpsc::Receiver, Arc, Mutex},
+};
+
+use anyhow::{Error, Result};
+use objdiff_core::{
+    config::ProjectObject,
+    diff::DiffObjConfig,
+    obj::demangle::Demangler,
+    project::{CancelToken, ProjectDiffer},
+};
+
+use crate::{
+    app::AppConfig,
+    jobs::{start_job, update_status, Job, JobContext, JobResult, JobState},
+};
+
+pub struct ProjectDiffConfig {
+    pub objects: Vec<ProjectObject>,
+    pub diff_obj_config: DiffObjConfig,
+    pub demangler: Demangler,
+    pub differ: Arc<ProjectDiffer>,
+}
+
+impl ProjectDiffConfig {
+    pub(crate) fn from_config(config: &AppConfig, differ: Arc<ProjectDiffer>) -> Result<Self> {
+        let project_dir =
+            config.project_dir.as_deref().ok_or_else(|| Error::msg("Missing project dir"))?;
+        let objects = config
+            .objects
+            .iter()
+            .map(|object| {
+                let mut object = object.clone();
+                object.resolve_paths(
+                    project_dir,
+                    config.target_obj_dir.as_deref(),
+                    config.base_obj_dir.as_deref(),
+                );
+                object
+            })
+            .filter(|object| object.target_path.is_some() || object.base_path.is_some())
+            .collect();
+        Ok(Self {
+            objects,
+            diff_obj_config: config.diff_obj_config.clone(),
+            demangler: config.demangler,
+            differ,
+        })
+    }
+}
+
+pub struct ProjectDiffResult {
+    /// Code match percentage by unit name
+    pub match_percents: HashMap<String, f32>,
+    /// Units that failed to diff
+    pub errors: Vec<(String, String)>,
+}
+
+fn run_project_diff(
+    context: &JobContext,
+    cancel: Receiver<()>,
+    config: ProjectDiffConfig,
+) -> Result<Box<ProjectDiffResult>> {
+    let total = config.objects.len() as u32;
+    update_status(context, "Diffing units".to_string(), 0, total, &cancel)?;
+    let token = CancelToken::default();
+    let cancel = Mutex::new(cancel);
+    let results = config.differ.diff_units(
+        &config.objects,
+        &config.diff_obj_config,
+        config.demangler,
+        0,
+        |progress| {
+            let cancel = cancel.lock().unwrap();
+            if update_status(
+                context,
+                progress.unit.to_string(),
+                progress.completed as u32,
+                total,
+                &cancel,
+            )
+            .is_err()
+            {
+                token.cancel();
+            }
+        },
+        &token,
+    )?;
+
+    let mut match_percents = HashMap::new();
+    let mut errors = Vec::new();
+    for (object, result) in config.objects.iter().zip(results) {
+        match result {
+            Ok(unit) => {
+                if let Some(percent) = unit.match_percent() {
+                    match_percents.insert(unit.name.clone(), percent);
+                }
+            }
+            Err(e) => errors.push((object.name().to_string(), format!("{e:#}"))),
+        }
+    }
+    update_status(context, "Complete".to_string(), total, total, &cancel.lock().unwrap())?;
+    Ok(Box::new(ProjectDiffResult { match_percents, errors }))
+}
+
+pub fn start_project_diff(ctx: &egui::Context, config: ProjectDiffConfig) -> JobState {
+    start_job(ctx, "Project diff", Job::ProjectDiff, move |context, cancel| {
+        run_project_diff(&context, cancel, config)
+            .map(|result| JobResult::ProjectDiff(Some(result)))
+    })
+}
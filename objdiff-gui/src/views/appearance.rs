@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use egui::{text::LayoutJob, Color32, FontFamily, FontId, TextFormat, TextStyle, Widget};
+use objdiff_core::diff::theme::{Color, Theme};
 use time::UtcOffset;
 
 use crate::fonts::load_font_if_needed;
@@ -28,6 +29,11 @@ pub struct Appearance {
     pub insert_color: Color32, // GREEN
     #[serde(skip)]
     pub delete_color: Color32, // RED
+    #[serde(skip)]
+    pub swap_color: Color32, // YELLOW
+    /// Diff colors, built from the above
+    #[serde(skip)]
+    pub colors: Theme,
 
     // Global
     #[serde(skip)]
@@ -64,6 +70,8 @@ impl Default for Appearance {
             replace_color: Color32::LIGHT_BLUE,
             insert_color: Color32::GREEN,
             delete_color: Color32::from_rgb(200, 40, 41),
+            swap_color: Color32::YELLOW,
+            colors: Theme::default(),
             utc_offset: UtcOffset::UTC,
             fonts: FontState::default(),
             next_ui_font: None,
@@ -107,6 +115,8 @@ impl Appearance {
                 self.replace_color = Color32::LIGHT_BLUE;
                 self.insert_color = Color32::GREEN;
                 self.delete_color = Color32::from_rgb(200, 40, 41);
+                self.swap_color = Color32::YELLOW;
+                self.colors = Theme::dark();
             }
             eframe::Theme::Light => {
                 style.visuals = egui::Visuals::light();
@@ -117,11 +127,24 @@ impl Appearance {
                 self.replace_color = Color32::DARK_BLUE;
                 self.insert_color = Color32::DARK_GREEN;
                 self.delete_color = Color32::from_rgb(200, 40, 41);
+                self.swap_color = Color32::from_rgb(160, 120, 0);
+                self.colors = Theme::light();
             }
         }
+        self.colors.text = theme_color(self.text_color);
+        self.colors.emphasized = theme_color(self.emphasized_text_color);
+        self.colors.deemphasized = theme_color(self.deemphasized_text_color);
+        self.colors.replace = theme_color(self.replace_color);
+        self.colors.insert = theme_color(self.insert_color);
+        self.colors.delete = theme_color(self.delete_color);
+        self.colors.swap = theme_color(self.swap_color);
+        self.colors.rotation = self.diff_colors.iter().copied().map(theme_color).collect();
         ctx.set_style(style);
     }
 
+    /// The color of a diff theme color in the UI.
+    pub fn color32(&self, color: Color) -> Color32 { Color32::from_rgb(color.r, color.g, color.b) }
+
     pub fn post_update(&mut self, ctx: &egui::Context) {
         // Load fonts for next frame
         if let Some(next_ui_font) = self.next_ui_font.take() {
@@ -309,3 +332,5 @@ pub fn appearance_window(ctx: &egui::Context, show: &mut bool, appearance: &mut
         }
     });
 }
+
+fn theme_color(color: Color32) -> Color { Color::rgb(color.r(), color.g(), color.b()) }
@@ -2,8 +2,10 @@
 use std::string::FromUtf16Error;
 use std::{
     borrow::Cow,
+    collections::HashMap,
     mem::take,
     path::{PathBuf, MAIN_SEPARATOR},
+    sync::Arc,
 };
 
 #[cfg(all(windows, feature = "wsl"))]
@@ -16,7 +18,9 @@ use egui::{
 use globset::Glob;
 use objdiff_core::{
     config::{ProjectObject, DEFAULT_WATCH_PATTERNS},
-    diff::{MipsAbi, MipsInstrCategory, X86Formatter},
+    diff::{ArmArchVersion, MipsAbi, MipsInstrCategory, X86Formatter},
+    obj::demangle::Demangler,
+    project::ProjectDiffer,
 };
 use self_update::cargo_crate_version;
 use strum::{EnumMessage, VariantArray};
@@ -26,6 +30,7 @@ use crate::{
     config::ProjectObjectNode,
     jobs::{
         check_update::{start_check_update, CheckUpdateResult},
+        project_diff::{start_project_diff, ProjectDiffConfig},
         update::start_update,
         Job, JobQueue, JobResult,
     },
@@ -50,6 +55,11 @@ pub struct ConfigViewState {
     pub object_search: String,
     pub filter_diffable: bool,
     pub filter_incomplete: bool,
+    /// Code match percentage by unit name, from the last project diff
+    pub unit_match_percents: HashMap<String, f32>,
+    pub project_differ: Arc<ProjectDiffer>,
+    pub project_diff_running: bool,
+    pub queue_project_diff: bool,
     #[cfg(all(windows, feature = "wsl"))]
     pub available_wsl_distros: Option<Vec<String>>,
     pub file_dialog_state: FileDialogState,
@@ -57,17 +67,26 @@ pub struct ConfigViewState {
 
 impl ConfigViewState {
     pub fn pre_update(&mut self, jobs: &mut JobQueue, config: &AppConfigRef) {
-        jobs.results.retain_mut(|result| {
-            if let JobResult::CheckUpdate(result) = result {
+        jobs.results.retain_mut(|result| match result {
+            JobResult::CheckUpdate(result) => {
                 self.check_update = take(result);
                 false
-            } else {
-                true
             }
+            JobResult::ProjectDiff(result) => {
+                if let Some(result) = take(result) {
+                    for (name, error) in &result.errors {
+                        log::warn!("Failed to diff {name}: {error}");
+                    }
+                    self.unit_match_percents = result.match_percents;
+                }
+                false
+            }
+            _ => true,
         });
         self.build_running = jobs.is_running(Job::ObjDiff);
         self.check_update_running = jobs.is_running(Job::CheckUpdate);
         self.update_running = jobs.is_running(Job::Update);
+        self.project_diff_running = jobs.is_running(Job::ProjectDiff);
 
         // Check async file dialog results
         match self.file_dialog_state.poll() {
@@ -98,6 +117,12 @@ impl ConfigViewState {
                             reverse_fn_order: None,
                             complete: None,
                             scratch: None,
+                            symbol_mappings: Default::default(),
+                            target_map: None,
+                            target_range: None,
+                            accepted_mismatches: Default::default(),
+                            size_inference: Default::default(),
+                            build: None,
                         });
                     } else if let Ok(obj_path) = path.strip_prefix(target_dir) {
                         let base_path = base_dir.join(obj_path);
@@ -108,6 +133,12 @@ impl ConfigViewState {
                             reverse_fn_order: None,
                             complete: None,
                             scratch: None,
+                            symbol_mappings: Default::default(),
+                            target_map: None,
+                            target_range: None,
+                            accepted_mismatches: Default::default(),
+                            size_inference: Default::default(),
+                            build: None,
                         });
                     }
                 }
@@ -131,6 +162,20 @@ impl ConfigViewState {
         if let Some(bin_name) = self.queue_update.take() {
             jobs.push_once(Job::Update, || start_update(ctx, bin_name));
         }
+
+        if self.queue_project_diff {
+            self.queue_project_diff = false;
+            if let Ok(config) = config.read() {
+                match ProjectDiffConfig::from_config(&config, self.project_differ.clone()) {
+                    Ok(config) => {
+                        jobs.push_once(Job::ProjectDiff, || start_project_diff(ctx, config));
+                    }
+                    Err(err) => {
+                        log::error!("Failed to create project diff config: {err}");
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -297,6 +342,13 @@ pub fn config_ui(
             {
                 state.filter_incomplete = !state.filter_incomplete;
             }
+            if ui
+                .add_enabled(!state.project_diff_running, egui::Button::new("Diff all").small())
+                .on_hover_text_at_pointer("Diff every object to show its match percentage")
+                .clicked()
+            {
+                state.queue_project_diff = true;
+            }
         });
         if state.object_search.is_empty() {
             if had_search {
@@ -335,7 +387,14 @@ pub fn config_ui(
 
             ui.style_mut().wrap = Some(false);
             for node in nodes.iter() {
-                display_node(ui, &mut new_selected_obj, node, appearance, node_open);
+                display_node(
+                    ui,
+                    &mut new_selected_obj,
+                    node,
+                    appearance,
+                    node_open,
+                    &state.unit_match_percents,
+                );
             }
         });
     }
@@ -360,6 +419,7 @@ fn display_object(
     name: &str,
     object: &ProjectObject,
     appearance: &Appearance,
+    match_percent: Option<f32>,
 ) {
     let object_name = object.name();
     let selected = matches!(selected_obj, Some(obj) if obj.name == object_name);
@@ -374,9 +434,13 @@ fn display_object(
     } else {
         appearance.text_color
     };
+    let label = match match_percent {
+        Some(percent) => format!("{name} ({percent:.1}%)"),
+        None => name.to_string(),
+    };
     let clicked = SelectableLabel::new(
         selected,
-        RichText::new(name)
+        RichText::new(label)
             .font(FontId {
                 size: appearance.ui_font.size,
                 family: appearance.code_font.family.clone(),
@@ -395,6 +459,12 @@ fn display_object(
             reverse_fn_order: object.reverse_fn_order,
             complete: object.complete,
             scratch: object.scratch.clone(),
+            symbol_mappings: object.symbol_mappings.clone().unwrap_or_default(),
+            target_map: object.target_map.clone(),
+            target_range: object.target_range.clone(),
+            accepted_mismatches: object.accepted_mismatches.clone().unwrap_or_default(),
+            size_inference: object.size_inference.unwrap_or_default(),
+            build: object.build.clone(),
         });
     }
 }
@@ -414,10 +484,12 @@ fn display_node(
     node: &ProjectObjectNode,
     appearance: &Appearance,
     node_open: NodeOpen,
+    match_percents: &HashMap<String, f32>,
 ) {
     match node {
         ProjectObjectNode::File(name, object) => {
-            display_object(ui, selected_obj, name, object, appearance);
+            let match_percent = match_percents.get(object.name()).copied();
+            display_object(ui, selected_obj, name, object, appearance, match_percent);
         }
         ProjectObjectNode::Dir(name, children) => {
             let contains_obj = selected_obj.as_ref().map(|path| contains_node(node, path));
@@ -443,7 +515,7 @@ fn display_node(
             .open(open)
             .show(ui, |ui| {
                 for node in children {
-                    display_node(ui, selected_obj, node, appearance, node_open);
+                    display_node(ui, selected_obj, node, appearance, node_open, match_percents);
                 }
             });
         }
@@ -907,4 +979,40 @@ fn arch_config_ui(ui: &mut egui::Ui, config: &mut AppConfig, _appearance: &Appea
                 }
             }
         });
+    ui.separator();
+    ui.heading("ARM");
+    egui::ComboBox::new("arm_arch_version", "Architecture Version")
+        .selected_text(config.diff_obj_config.arm_arch_version.get_message().unwrap())
+        .show_ui(ui, |ui| {
+            for &version in ArmArchVersion::VARIANTS {
+                if ui
+                    .selectable_label(
+                        config.diff_obj_config.arm_arch_version == version,
+                        version.get_message().unwrap(),
+                    )
+                    .clicked()
+                {
+                    config.diff_obj_config.arm_arch_version = version;
+                    config.queue_reload = true;
+                }
+            }
+        });
+    ui.separator();
+    ui.heading("Symbols");
+    egui::ComboBox::new("demangler", "Demangler")
+        .selected_text(config.demangler.get_message().unwrap())
+        .show_ui(ui, |ui| {
+            for &demangler in Demangler::VARIANTS {
+                if ui
+                    .selectable_label(
+                        config.demangler == demangler,
+                        demangler.get_message().unwrap(),
+                    )
+                    .clicked()
+                {
+                    config.demangler = demangler;
+                    config.queue_reload = true;
+                }
+            }
+        });
 }
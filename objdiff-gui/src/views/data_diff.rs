@@ -1,10 +1,17 @@
-use std::{cmp::min, default::Default, mem::take};
+use std::default::Default;
 
 use egui::{text::LayoutJob, Align, Label, Layout, Sense, Vec2, Widget};
 use egui_extras::{Column, TableBuilder};
 use objdiff_core::{
-    diff::{ObjDataDiff, ObjDataDiffKind, ObjDiff},
-    obj::ObjInfo,
+    diff::{
+        display::{display_data_rows, DiffText, ObjDataByte, ObjDataRow},
+        jump_table::{
+            detect_jump_table, diff_jump_tables, display_jump_table_entry, JumpTable,
+            JumpTableEntry,
+        },
+        ObjDataDiffKind, ObjDiff,
+    },
+    obj::{ObjInfo, SymbolRef},
 };
 use time::format_description;
 
@@ -20,117 +27,129 @@ fn find_section(obj: &ObjInfo, selected_symbol: &SymbolRefByName) -> Option<usiz
     obj.sections.iter().position(|section| section.name == selected_symbol.section_name)
 }
 
-fn data_row_ui(ui: &mut egui::Ui, address: usize, diffs: &[ObjDataDiff], appearance: &Appearance) {
-    if diffs.iter().any(|d| d.kind != ObjDataDiffKind::None) {
+fn find_symbol(obj: &ObjInfo, selected_symbol: &SymbolRefByName) -> Option<SymbolRef> {
+    let section_idx = find_section(obj, selected_symbol)?;
+    let symbol_idx = obj.sections[section_idx]
+        .symbols
+        .iter()
+        .position(|symbol| symbol.name == selected_symbol.symbol_name)?;
+    Some(SymbolRef { section_idx, symbol_idx })
+}
+
+fn jump_table_row_ui(
+    ui: &mut egui::Ui,
+    table: &JumpTable,
+    entry: &JumpTableEntry,
+    kind: ObjDataDiffKind,
+    appearance: &Appearance,
+) {
+    if kind != ObjDataDiffKind::None {
         ui.painter().rect_filled(ui.available_rect_before_wrap(), 0.0, ui.visuals().faint_bg_color);
     }
+    let color = match kind {
+        ObjDataDiffKind::None => appearance.text_color,
+        ObjDataDiffKind::Replace => appearance.replace_color,
+        ObjDataDiffKind::Delete => appearance.delete_color,
+        ObjDataDiffKind::Insert => appearance.insert_color,
+    };
+    let mut job = LayoutJob::default();
+    display_jump_table_entry(table, entry, |text| {
+        let (text, color) = match text {
+            DiffText::Address(offset) => (format!("{offset:x}:"), appearance.text_color),
+            DiffText::Opcode(mnemonic, _) => (mnemonic.to_string(), color),
+            DiffText::BranchDest(_, Some(label)) => (format!(".L{label}"), color),
+            DiffText::Spacing(n) => (" ".repeat(n), color),
+            _ => return Ok::<_, ()>(()),
+        };
+        write_text(&text, color, &mut job, appearance.code_font.clone());
+        Ok(())
+    })
+    .unwrap();
+    Label::new(job)
+        .sense(Sense::click())
+        .ui(ui)
+        .on_hover_text(format!("{}+{:#x}", table.function_name, entry.target));
+}
+
+/// Show the selected symbol's jump tables entry by entry, in place of its bytes.
+fn jump_table_ui(
+    table: TableBuilder<'_>,
+    left_table: &Option<JumpTable>,
+    right_table: &Option<JumpTable>,
+    appearance: &Appearance,
+) {
+    let entries = |t: &Option<JumpTable>| t.as_ref().map_or(0, |t| t.entries.len());
+    let total_rows = entries(left_table).max(entries(right_table));
+    let kinds = match (left_table, right_table) {
+        (Some(l), Some(r)) => diff_jump_tables(l, r, false),
+        _ => vec![],
+    };
+    table.body(|body| {
+        body.rows(appearance.code_font.size, total_rows, |mut row| {
+            let row_index = row.index();
+            let kind = kinds.get(row_index).copied().unwrap_or(ObjDataDiffKind::None);
+            for table in [left_table, right_table] {
+                row.col(|ui| {
+                    if let Some(table) = table {
+                        if let Some(entry) = table.entries.get(row_index) {
+                            jump_table_row_ui(ui, table, entry, kind, appearance);
+                        }
+                    }
+                });
+            }
+        });
+    });
+}
+
+fn data_row_ui(ui: &mut egui::Ui, row: &ObjDataRow, appearance: &Appearance) {
+    if row.has_diff() {
+        ui.painter().rect_filled(ui.available_rect_before_wrap(), 0.0, ui.visuals().faint_bg_color);
+    }
+    let byte_color = |byte: &ObjDataByte| match byte.kind {
+        ObjDataDiffKind::None if byte.reloc => appearance.deemphasized_text_color,
+        ObjDataDiffKind::None => appearance.text_color,
+        ObjDataDiffKind::Replace => appearance.replace_color,
+        ObjDataDiffKind::Delete => appearance.delete_color,
+        ObjDataDiffKind::Insert => appearance.insert_color,
+    };
     let mut job = LayoutJob::default();
     write_text(
-        format!("{address:08x}: ").as_str(),
+        format!("{:08x}: ", row.address).as_str(),
         appearance.text_color,
         &mut job,
         appearance.code_font.clone(),
     );
-    let mut cur_addr = 0usize;
-    for diff in diffs {
-        let base_color = match diff.kind {
-            ObjDataDiffKind::None => appearance.text_color,
-            ObjDataDiffKind::Replace => appearance.replace_color,
-            ObjDataDiffKind::Delete => appearance.delete_color,
-            ObjDataDiffKind::Insert => appearance.insert_color,
+    for (i, byte) in row.bytes.iter().enumerate() {
+        let mut text = match byte.value {
+            Some(value) => format!("{value:02x} "),
+            None => "   ".to_string(),
         };
-        if diff.data.is_empty() {
-            let mut str = "   ".repeat(diff.len);
-            str.push_str(" ".repeat(diff.len / 8).as_str());
-            write_text(str.as_str(), base_color, &mut job, appearance.code_font.clone());
-            cur_addr += diff.len;
-        } else {
-            let mut text = String::new();
-            for byte in &diff.data {
-                text.push_str(format!("{byte:02x} ").as_str());
-                cur_addr += 1;
-                if cur_addr % 8 == 0 {
-                    text.push(' ');
-                }
-            }
-            write_text(text.as_str(), base_color, &mut job, appearance.code_font.clone());
+        if (i + 1) % 8 == 0 {
+            text.push(' ');
         }
+        write_text(text.as_str(), byte_color(byte), &mut job, appearance.code_font.clone());
     }
-    if cur_addr < BYTES_PER_ROW {
-        let n = BYTES_PER_ROW - cur_addr;
+    if row.bytes.len() < BYTES_PER_ROW {
+        let n = BYTES_PER_ROW - row.bytes.len();
         let mut str = " ".to_string();
         str.push_str("   ".repeat(n).as_str());
         str.push_str(" ".repeat(n / 8).as_str());
         write_text(str.as_str(), appearance.text_color, &mut job, appearance.code_font.clone());
     }
     write_text(" ", appearance.text_color, &mut job, appearance.code_font.clone());
-    for diff in diffs {
-        let base_color = match diff.kind {
-            ObjDataDiffKind::None => appearance.text_color,
-            ObjDataDiffKind::Replace => appearance.replace_color,
-            ObjDataDiffKind::Delete => appearance.delete_color,
-            ObjDataDiffKind::Insert => appearance.insert_color,
-        };
-        if diff.data.is_empty() {
-            write_text(
-                " ".repeat(diff.len).as_str(),
-                base_color,
-                &mut job,
-                appearance.code_font.clone(),
-            );
-        } else {
-            let mut text = String::new();
-            for byte in &diff.data {
-                let c = char::from(*byte);
-                if c.is_ascii() && !c.is_ascii_control() {
-                    text.push(c);
-                } else {
-                    text.push('.');
-                }
-            }
-            write_text(text.as_str(), base_color, &mut job, appearance.code_font.clone());
-        }
+    for byte in &row.bytes {
+        write_text(
+            byte.ascii().to_string().as_str(),
+            byte_color(byte),
+            &mut job,
+            appearance.code_font.clone(),
+        );
     }
     Label::new(job).sense(Sense::click()).ui(ui);
     //     .on_hover_ui_at_pointer(|ui| ins_hover_ui(ui, ins))
     //     .context_menu(|ui| ins_context_menu(ui, ins));
 }
 
-fn split_diffs(diffs: &[ObjDataDiff]) -> Vec<Vec<ObjDataDiff>> {
-    let mut split_diffs = Vec::<Vec<ObjDataDiff>>::new();
-    let mut row_diffs = Vec::<ObjDataDiff>::new();
-    let mut cur_addr = 0usize;
-    for diff in diffs {
-        let mut cur_len = 0usize;
-        while cur_len < diff.len {
-            let remaining_len = diff.len - cur_len;
-            let mut remaining_in_row = BYTES_PER_ROW - (cur_addr % BYTES_PER_ROW);
-            let len = min(remaining_len, remaining_in_row);
-            row_diffs.push(ObjDataDiff {
-                data: if diff.data.is_empty() {
-                    Vec::new()
-                } else {
-                    diff.data[cur_len..cur_len + len].to_vec()
-                },
-                kind: diff.kind,
-                len,
-                // TODO
-                symbol: String::new(),
-            });
-            remaining_in_row -= len;
-            cur_len += len;
-            cur_addr += len;
-            if remaining_in_row == 0 {
-                split_diffs.push(take(&mut row_diffs));
-            }
-        }
-    }
-    if !row_diffs.is_empty() {
-        split_diffs.push(take(&mut row_diffs));
-    }
-    split_diffs
-}
-
 fn data_table_ui(
     table: TableBuilder<'_>,
     left_obj: Option<&(ObjInfo, ObjDiff)>,
@@ -139,15 +158,15 @@ fn data_table_ui(
     config: &Appearance,
 ) -> Option<()> {
     let left_section = left_obj.and_then(|(obj, diff)| {
-        find_section(obj, selected_symbol).map(|i| (&obj.sections[i], &diff.sections[i]))
+        find_section(obj, selected_symbol).map(|i| (obj, &obj.sections[i], &diff.sections[i]))
     });
     let right_section = right_obj.and_then(|(obj, diff)| {
-        find_section(obj, selected_symbol).map(|i| (&obj.sections[i], &diff.sections[i]))
+        find_section(obj, selected_symbol).map(|i| (obj, &obj.sections[i], &diff.sections[i]))
     });
 
     let total_bytes = left_section
         .or(right_section)?
-        .1
+        .2
         .data_diff
         .iter()
         .fold(0usize, |accum, item| accum + item.len);
@@ -156,21 +175,24 @@ fn data_table_ui(
     }
     let total_rows = (total_bytes - 1) / BYTES_PER_ROW + 1;
 
-    let left_diffs = left_section.map(|(_, section)| split_diffs(&section.data_diff));
-    let right_diffs = right_section.map(|(_, section)| split_diffs(&section.data_diff));
+    let left_rows = left_section.map(|(obj, section, diff)| {
+        display_data_rows(obj, section, &diff.data_diff, BYTES_PER_ROW)
+    });
+    let right_rows = right_section.map(|(obj, section, diff)| {
+        display_data_rows(obj, section, &diff.data_diff, BYTES_PER_ROW)
+    });
 
     table.body(|body| {
         body.rows(config.code_font.size, total_rows, |mut row| {
             let row_index = row.index();
-            let address = row_index * BYTES_PER_ROW;
             row.col(|ui| {
-                if let Some(left_diffs) = &left_diffs {
-                    data_row_ui(ui, address, &left_diffs[row_index], config);
+                if let Some(data_row) = left_rows.as_ref().and_then(|r| r.get(row_index)) {
+                    data_row_ui(ui, data_row, config);
                 }
             });
             row.col(|ui| {
-                if let Some(right_diffs) = &right_diffs {
-                    data_row_ui(ui, address, &right_diffs[row_index], config);
+                if let Some(data_row) = right_rows.as_ref().and_then(|r| r.get(row_index)) {
+                    data_row_ui(ui, data_row, config);
                 }
             });
         });
@@ -267,6 +289,15 @@ pub fn data_diff_ui(ui: &mut egui::Ui, state: &mut DiffViewState, appearance: &A
         .resizable(false)
         .auto_shrink([false, false])
         .min_scrolled_height(available_height);
+    let detect = |obj: Option<&(ObjInfo, ObjDiff)>| {
+        obj.and_then(|(obj, _)| detect_jump_table(obj, find_symbol(obj, selected_symbol)?))
+    };
+    let left_table = detect(result.first_obj.as_ref());
+    let right_table = detect(result.second_obj.as_ref());
+    if left_table.is_some() || right_table.is_some() {
+        jump_table_ui(table, &left_table, &right_table, appearance);
+        return;
+    }
     data_table_ui(
         table,
         result.first_obj.as_ref(),
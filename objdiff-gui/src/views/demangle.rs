@@ -1,10 +1,23 @@
 use egui::TextStyle;
+use objdiff_core::obj::demangle::Demangler;
+use strum::{EnumMessage, VariantArray};
 
 use crate::views::appearance::Appearance;
 
 #[derive(Default)]
 pub struct DemangleViewState {
     pub text: String,
+    pub demangler: Demangler,
+}
+
+fn demangle(demangler: Demangler, text: &str) -> Option<String> {
+    match demangler {
+        // Without an object to infer the scheme from, try each one in turn.
+        Demangler::Auto => [Demangler::CodeWarrior, Demangler::Itanium, Demangler::Msvc]
+            .into_iter()
+            .find_map(|d| d.demangle(text)),
+        _ => demangler.demangle(text),
+    }
 }
 
 pub fn demangle_window(
@@ -14,9 +27,20 @@ pub fn demangle_window(
     appearance: &Appearance,
 ) {
     egui::Window::new("Demangle").open(show).show(ctx, |ui| {
+        egui::ComboBox::new("demangle_scheme", "Scheme")
+            .selected_text(state.demangler.get_message().unwrap())
+            .show_ui(ui, |ui| {
+                for &demangler in Demangler::VARIANTS {
+                    ui.selectable_value(
+                        &mut state.demangler,
+                        demangler,
+                        demangler.get_message().unwrap(),
+                    );
+                }
+            });
         ui.text_edit_singleline(&mut state.text);
         ui.add_space(10.0);
-        if let Some(demangled) = cwdemangle::demangle(&state.text, &Default::default()) {
+        if let Some(demangled) = demangle(state.demangler, &state.text) {
             ui.scope(|ui| {
                 ui.style_mut().override_text_style = Some(TextStyle::Monospace);
                 ui.colored_label(appearance.replace_color, &demangled);
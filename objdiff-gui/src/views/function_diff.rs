@@ -1,32 +1,52 @@
 use std::default::Default;
 
-use egui::{text::LayoutJob, Align, Label, Layout, Response, Sense, Vec2, Widget};
+use egui::{text::LayoutJob, Align, Id, Label, Layout, Response, Sense, Vec2, Widget};
 use egui_extras::{Column, TableBuilder, TableRow};
 use objdiff_core::{
-    arch::ObjArch,
     diff::{
-        display::{display_diff, DiffText, HighlightKind},
-        ObjDiff, ObjInsDiff, ObjInsDiffKind,
+        display::{arg_diff_legend, display_diff, DiffText, HighlightKind},
+        frame::{compare_frames, symbol_frame},
+        idiom::Idiom,
+        signature::{compare_signatures, symbol_signature},
+        theme::{byte_kind, encoding_byte_kind, token_kind},
+        NumberFormat, ObjDiff, ObjInsDiff, ObjInsDiffKind, ObjSymbolDiff, RelocDiffKind,
     },
     obj::{ObjInfo, ObjIns, ObjInsArg, ObjInsArgValue, ObjSection, ObjSymbol, SymbolRef},
 };
+use strum::EnumMessage;
 use time::format_description;
 
 use crate::views::{
     appearance::Appearance,
-    symbol_diff::{match_color_for_symbol, DiffViewState, SymbolRefByName, View},
+    symbol_diff::{match_color_for_symbol, DiffViewState, SymbolRefByName, SymbolViewState, View},
 };
 
 #[derive(Default)]
 pub struct FunctionViewState {
     pub highlight: HighlightKind,
+    pub number_format: NumberFormat,
+    /// Whether notes can be attached to target instructions
+    pub notes_available: bool,
 }
 
+/// Note being edited, kept in egui's memory since the context menu can't borrow the view
+/// state.
+#[derive(Clone)]
+struct NoteEdit {
+    symbol: String,
+    offset: u64,
+    text: String,
+}
+
+const NOTE_EDIT_ID: &str = "note_edit";
+
 fn ins_hover_ui(
     ui: &mut egui::Ui,
-    arch: &dyn ObjArch,
+    obj: &ObjInfo,
     section: &ObjSection,
     ins: &ObjIns,
+    reloc_diff: Option<RelocDiffKind>,
+    idiom: Option<Idiom>,
     symbol: &ObjSymbol,
     appearance: &Appearance,
 ) {
@@ -52,6 +72,18 @@ fn ins_hover_ui(
             ui.label(format!("Original: {}", orig));
         }
 
+        if let Some(idiom) = idiom {
+            ui.colored_label(
+                appearance.highlight_color,
+                format!("Equivalent: {}", idiom.get_message().unwrap()),
+            );
+        }
+
+        if let Some(location) = obj.source_location(section, ins.address) {
+            let file = obj.source_file(location).unwrap_or("<unknown>");
+            ui.label(format!("Source: {}:{}", file, location.line));
+        }
+
         for arg in &ins.args {
             if let ObjInsArg::Arg(arg) = arg {
                 match arg {
@@ -67,7 +99,7 @@ fn ins_hover_ui(
         }
 
         if let Some(reloc) = &ins.reloc {
-            ui.label(format!("Relocation type: {}", arch.display_reloc(reloc.flags)));
+            ui.label(format!("Relocation type: {}", obj.arch.display_reloc(reloc.flags)));
             ui.colored_label(appearance.highlight_color, format!("Name: {}", reloc.target.name));
             if let Some(section) = &reloc.target_section {
                 ui.colored_label(appearance.highlight_color, format!("Section: {section}"));
@@ -82,11 +114,36 @@ fn ins_hover_ui(
             } else {
                 ui.colored_label(appearance.highlight_color, "Extern".to_string());
             }
+            match reloc_diff {
+                Some(RelocDiffKind::SameAddress) => {
+                    ui.colored_label(
+                        appearance.replace_color,
+                        "Target: different symbol, same address",
+                    );
+                }
+                Some(RelocDiffKind::SameValue) => {
+                    ui.colored_label(
+                        appearance.highlight_color,
+                        "Target: different symbol, same value",
+                    );
+                }
+                Some(RelocDiffKind::Mismatch) => {
+                    ui.colored_label(appearance.delete_color, "Target: mismatch");
+                }
+                _ => {}
+            }
         }
     });
 }
 
-fn ins_context_menu(ui: &mut egui::Ui, section: &ObjSection, ins: &ObjIns, symbol: &ObjSymbol) {
+fn ins_context_menu(
+    ui: &mut egui::Ui,
+    section: &ObjSection,
+    ins: &ObjIns,
+    symbol: &ObjSymbol,
+    comment: Option<&str>,
+    annotatable: bool,
+) {
     ui.scope(|ui| {
         ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
         ui.style_mut().wrap = Some(false);
@@ -153,9 +210,59 @@ fn ins_context_menu(ui: &mut egui::Ui, section: &ObjSection, ins: &ObjIns, symbo
                 ui.close_menu();
             }
         }
+
+        if annotatable {
+            ui.separator();
+            if ui.button(if comment.is_some() { "Edit note…" } else { "Add note…" }).clicked() {
+                let edit = NoteEdit {
+                    symbol: symbol.name.clone(),
+                    offset: ins.address - symbol.address,
+                    text: comment.unwrap_or_default().to_string(),
+                };
+                ui.data_mut(|data| data.insert_temp(Id::new(NOTE_EDIT_ID), edit));
+                ui.close_menu();
+            }
+        }
     });
 }
 
+/// Window for editing the note of an instruction, queueing the result to be saved.
+fn note_edit_ui(ctx: &egui::Context, state: &mut SymbolViewState) {
+    let id = Id::new(NOTE_EDIT_ID);
+    let Some(mut edit) = ctx.data(|data| data.get_temp::<NoteEdit>(id)) else {
+        return;
+    };
+    let mut open = true;
+    let mut close = false;
+    egui::Window::new(format!("Note on {}+{:#x}", edit.symbol, edit.offset))
+        .id(id.with("window"))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.text_edit_singleline(&mut edit.text);
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    state.queue_note =
+                        Some((edit.symbol.clone(), edit.offset, edit.text.trim().to_string()));
+                    close = true;
+                }
+                if ui.button("Delete").clicked() {
+                    state.queue_note = Some((edit.symbol.clone(), edit.offset, String::new()));
+                    close = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    close = true;
+                }
+            });
+        });
+    if open && !close {
+        ctx.data_mut(|data| data.insert_temp(id, edit));
+    } else {
+        ctx.data_mut(|data| data.remove::<NoteEdit>(id));
+    }
+}
+
 fn find_symbol(obj: &ObjInfo, selected_symbol: &SymbolRefByName) -> Option<SymbolRef> {
     for (section_idx, section) in obj.sections.iter().enumerate() {
         for (symbol_idx, symbol) in section.symbols.iter().enumerate() {
@@ -177,26 +284,16 @@ fn diff_text_ui(
     response_cb: impl Fn(Response) -> Response,
 ) {
     let label_text;
-    let mut base_color = match ins_diff.kind {
-        ObjInsDiffKind::None | ObjInsDiffKind::OpMismatch | ObjInsDiffKind::ArgMismatch => {
-            appearance.text_color
-        }
-        ObjInsDiffKind::Replace => appearance.replace_color,
-        ObjInsDiffKind::Delete => appearance.delete_color,
-        ObjInsDiffKind::Insert => appearance.insert_color,
-    };
+    let base_color =
+        appearance.color32(appearance.colors.color(token_kind(&text, ins_diff), ins_diff));
     let mut pad_to = 0;
+    let mut hover_text = None;
     match text {
-        DiffText::Basic(text) => {
+        DiffText::Basic(text) | DiffText::Styled(text, _) => {
             label_text = text.to_string();
         }
-        DiffText::BasicColor(s, idx) => {
-            label_text = s.to_string();
-            base_color = appearance.diff_colors[idx % appearance.diff_colors.len()];
-        }
         DiffText::Line(num) => {
             label_text = num.to_string();
-            base_color = appearance.deemphasized_text_color;
             pad_to = 5;
         }
         DiffText::Address(addr) => {
@@ -205,24 +302,60 @@ fn diff_text_ui(
         }
         DiffText::Opcode(mnemonic, _op) => {
             label_text = mnemonic.to_string();
-            if ins_diff.kind == ObjInsDiffKind::OpMismatch {
-                base_color = appearance.replace_color;
-            }
             pad_to = 8;
         }
-        DiffText::Argument(arg, diff) => {
-            label_text = arg.to_string();
-            if let Some(diff) = diff {
-                base_color = appearance.diff_colors[diff.idx % appearance.diff_colors.len()]
+        DiffText::Argument(arg, _) => {
+            label_text = arg.display(ins_view_state.number_format).to_string();
+        }
+        DiffText::BranchDest(addr, label) => {
+            label_text = match label {
+                Some(label) => format!(".L{label}"),
+                None => format!("{addr:x}"),
+            };
+        }
+        DiffText::Label(label, _) => {
+            label_text = format!(".L{label}");
+            pad_to = 4;
+        }
+        DiffText::Bytes(bytes, diff) => {
+            // Color each byte by its own comparison
+            let mut job = LayoutJob::default();
+            for (i, b) in bytes.iter().enumerate() {
+                let color =
+                    appearance.color32(appearance.colors.color(byte_kind(diff, i), ins_diff));
+                let text = if i > 0 { format!(" {b:02x}") } else { format!("{b:02x}") };
+                job.append(&text, 0.0, appearance.code_text_format(color, false));
+            }
+            response_cb(Label::new(job).sense(Sense::click()).ui(ui));
+            return;
+        }
+        DiffText::Encoding(encoding) => {
+            let mut job = LayoutJob::default();
+            for (i, b) in encoding.bytes.iter().enumerate() {
+                let color = appearance
+                    .color32(appearance.colors.color(encoding_byte_kind(encoding, i), ins_diff));
+                job.append(&format!(" {b:02x}"), 0.0, appearance.code_text_format(color, false));
             }
+            response_cb(Label::new(job).sense(Sense::click()).ui(ui));
+            let len = encoding.bytes.len() * 3;
+            if len < 13 {
+                ui.add_space((13 - len) as f32 * space_width);
+            }
+            return;
         }
-        DiffText::BranchDest(addr) => {
-            label_text = format!("{addr:x}");
+        DiffText::Moved(_) => {
+            label_text = " <> ".to_string();
         }
-        DiffText::Symbol(sym) => {
+        DiffText::Accepted => {
+            label_text = " ok ".to_string();
+        }
+        DiffText::Symbol(sym, _, callee_match) => {
             let name = sym.demangled_name.as_ref().unwrap_or(&sym.name);
             label_text = name.clone();
-            base_color = appearance.emphasized_text_color;
+            hover_text = callee_match.map(|p| format!("{p:.2}% matched"));
+        }
+        DiffText::Comment(text) => {
+            label_text = format!(" # {text}");
         }
         DiffText::Spacing(n) => {
             ui.add_space(n as f32 * space_width);
@@ -241,6 +374,9 @@ fn diff_text_ui(
     ))
     .sense(Sense::click())
     .ui(ui);
+    if let Some(hover_text) = hover_text {
+        response = response.on_hover_text(hover_text);
+    }
     response = response_cb(response);
     if response.clicked() {
         if highlight {
@@ -263,7 +399,7 @@ fn asm_row_ui(
     response_cb: impl Fn(Response) -> Response,
 ) {
     ui.spacing_mut().item_spacing.x = 0.0;
-    if ins_diff.kind != ObjInsDiffKind::None {
+    if ins_diff.kind != ObjInsDiffKind::None && !ins_diff.accepted {
         ui.painter().rect_filled(ui.available_rect_before_wrap(), 0.0, ui.visuals().faint_bg_color);
     }
     let space_width = ui.fonts(|f| f.glyph_width(&appearance.code_font, ' '));
@@ -280,15 +416,28 @@ fn asm_col_ui(
     symbol_ref: SymbolRef,
     appearance: &Appearance,
     ins_view_state: &mut FunctionViewState,
+    annotatable: bool,
 ) {
     let (section, symbol) = obj.0.section_symbol(symbol_ref);
     let section = section.unwrap();
     let ins_diff = &obj.1.symbol_diff(symbol_ref).instructions[row.index()];
     let response_cb = |response: Response| {
         if let Some(ins) = &ins_diff.ins {
-            response.context_menu(|ui| ins_context_menu(ui, section, ins, symbol));
+            response.context_menu(|ui| {
+                let comment = ins_diff.comment.as_deref();
+                ins_context_menu(ui, section, ins, symbol, comment, annotatable)
+            });
             response.on_hover_ui_at_pointer(|ui| {
-                ins_hover_ui(ui, obj.0.arch.as_ref(), section, ins, symbol, appearance)
+                ins_hover_ui(
+                    ui,
+                    &obj.0,
+                    section,
+                    ins,
+                    ins_diff.reloc_diff,
+                    ins_diff.idiom,
+                    symbol,
+                    appearance,
+                )
             })
         } else {
             response
@@ -333,13 +482,29 @@ fn asm_table_ui(
     };
     table.body(|body| {
         body.rows(appearance.code_font.size, instructions_len, |mut row| {
+            // Notes are keyed by target offsets
+            let annotatable = ins_view_state.notes_available;
             if let (Some(left_obj), Some(left_symbol_ref)) = (left_obj, left_symbol) {
-                asm_col_ui(&mut row, left_obj, left_symbol_ref, appearance, ins_view_state);
+                asm_col_ui(
+                    &mut row,
+                    left_obj,
+                    left_symbol_ref,
+                    appearance,
+                    ins_view_state,
+                    annotatable,
+                );
             } else {
                 empty_col_ui(&mut row);
             }
             if let (Some(right_obj), Some(right_symbol_ref)) = (right_obj, right_symbol) {
-                asm_col_ui(&mut row, right_obj, right_symbol_ref, appearance, ins_view_state);
+                asm_col_ui(
+                    &mut row,
+                    right_obj,
+                    right_symbol_ref,
+                    appearance,
+                    ins_view_state,
+                    false,
+                );
             } else {
                 empty_col_ui(&mut row);
             }
@@ -348,6 +513,25 @@ fn asm_table_ui(
     Some(())
 }
 
+/// Lists the mismatched argument pairs of the function in their diff colors.
+fn legend_ui(
+    ui: &mut egui::Ui,
+    left: &ObjSymbolDiff,
+    right: &ObjSymbolDiff,
+    appearance: &Appearance,
+) {
+    ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+    let legend = arg_diff_legend(left, right);
+    if legend.is_empty() {
+        ui.label("No mismatched arguments");
+        return;
+    }
+    for entry in legend {
+        let color = appearance.diff_colors[entry.idx % appearance.diff_colors.len()];
+        ui.colored_label(color, format!("{} ↔ {}", entry.left, entry.right));
+    }
+}
+
 pub fn function_diff_ui(ui: &mut egui::Ui, state: &mut DiffViewState, appearance: &Appearance) {
     let (Some(result), Some(selected_symbol)) = (&state.build, &state.symbol_state.selected_symbol)
     else {
@@ -443,20 +627,58 @@ pub fn function_diff_ui(ui: &mut egui::Ui, state: &mut DiffViewState, appearance
 
                     ui.scope(|ui| {
                         ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
-                        if let Some(match_percent) = result
-                            .second_obj
-                            .as_ref()
-                            .and_then(|(obj, diff)| {
+                        let symbol_diff = |obj: &Option<(ObjInfo, ObjDiff)>| {
+                            obj.as_ref().and_then(|(obj, diff)| {
                                 find_symbol(obj, selected_symbol).map(|sref| {
-                                    &diff.sections[sref.section_idx].symbols[sref.symbol_idx]
+                                    (obj, &diff.sections[sref.section_idx].symbols[sref.symbol_idx])
                                 })
                             })
-                            .and_then(|symbol| symbol.match_percent)
-                        {
-                            ui.colored_label(
-                                match_color_for_symbol(match_percent, appearance),
-                                &format!("{match_percent:.0}%"),
-                            );
+                        };
+                        let left = symbol_diff(&result.first_obj);
+                        let right = symbol_diff(&result.second_obj);
+                        if let Some(match_percent) = right.and_then(|(_, d)| d.match_percent) {
+                            ui.horizontal(|ui| {
+                                let response = ui.colored_label(
+                                    match_color_for_symbol(match_percent, appearance),
+                                    &format!("{match_percent:.0}%"),
+                                );
+                                let Some(((left_obj, left_diff), (right_obj, right_diff))) =
+                                    left.zip(right)
+                                else {
+                                    return;
+                                };
+                                response.on_hover_ui(|ui| {
+                                    legend_ui(ui, left_diff, right_diff, appearance)
+                                });
+                                let differences = symbol_frame(left_obj, left_diff)
+                                    .zip(symbol_frame(right_obj, right_diff))
+                                    .map(|(l, r)| compare_frames(&l, &r))
+                                    .unwrap_or_default();
+                                if !differences.is_empty() {
+                                    ui.colored_label(appearance.delete_color, "⚠ Stack frame")
+                                        .on_hover_ui(|ui| {
+                                            ui.style_mut().override_text_style =
+                                                Some(egui::TextStyle::Monospace);
+                                            for difference in &differences {
+                                                ui.label(difference.to_string());
+                                            }
+                                        });
+                                }
+                                let differences = symbol_signature(left_obj, left_diff)
+                                    .zip(symbol_signature(right_obj, right_diff))
+                                    .map(|(l, r)| compare_signatures(&l, &r))
+                                    .unwrap_or_default();
+                                if !differences.is_empty() {
+                                    ui.colored_label(appearance.delete_color, "⚠ Signature")
+                                        .on_hover_ui(|ui| {
+                                            ui.style_mut().override_text_style =
+                                                Some(egui::TextStyle::Monospace);
+                                            for difference in &differences {
+                                                ui.label(difference.to_string());
+                                            }
+                                        });
+                                }
+                            });
                         } else {
                             ui.colored_label(appearance.replace_color, "Missing");
                         }
@@ -486,4 +708,5 @@ pub fn function_diff_ui(ui: &mut egui::Ui, state: &mut DiffViewState, appearance
         appearance,
         &mut state.function_state,
     );
+    note_edit_ui(ui.ctx(), &mut state.symbol_state);
 }
@@ -1,4 +1,4 @@
-use std::mem::take;
+use std::{collections::BTreeMap, mem::take};
 
 use egui::{
     text::LayoutJob, Align, CollapsingHeader, Color32, Id, Layout, OpenUrl, ScrollArea,
@@ -6,15 +6,19 @@ use egui::{
 };
 use egui_extras::{Size, StripBuilder};
 use objdiff_core::{
-    diff::{ObjDiff, ObjSymbolDiff},
+    build::{BuildErrorSeverity, BuildStatus},
+    diff::{filter::SymbolFilter, ObjDiff, ObjSymbolDiff},
     obj::{ObjInfo, ObjSection, ObjSectionKind, ObjSymbol, ObjSymbolFlags, SymbolRef},
+    scratch::CreateScratchResult,
+    workflow::{apply_annotations, SymbolWorkflow, WorkflowStatus},
 };
+use strum::{EnumMessage, VariantArray};
 
 use crate::{
     app::AppConfigRef,
     jobs::{
-        create_scratch::{start_create_scratch, CreateScratchConfig, CreateScratchResult},
-        objdiff::{BuildStatus, ObjDiffResult},
+        create_scratch::{start_create_scratch, CreateScratchConfig},
+        objdiff::ObjDiffResult,
         Job, JobQueue, JobResult,
     },
     views::{appearance::Appearance, function_diff::FunctionViewState, write_text},
@@ -57,13 +61,26 @@ pub struct SymbolViewState {
     pub reverse_fn_order: bool,
     pub disable_reverse_fn_order: bool,
     pub show_hidden_symbols: bool,
+    /// Target symbol waiting to be mapped to a base symbol.
+    pub pending_mapping: Option<String>,
+    pub queue_mapping: Option<(String, String)>,
+    /// Workflow state of the selected object's symbols, by symbol name
+    pub workflow: BTreeMap<String, SymbolWorkflow>,
+    /// Whether the project has a workflow state file to save to
+    pub workflow_available: bool,
+    pub workflow_filter: Option<WorkflowStatus>,
+    pub queue_workflow: Option<(String, WorkflowStatus)>,
+    /// Symbol name, instruction offset and text of a note to save
+    pub queue_note: Option<(String, u64, String)>,
 }
 
 impl DiffViewState {
     pub fn pre_update(&mut self, jobs: &mut JobQueue, config: &AppConfigRef) {
+        let mut new_build = false;
         jobs.results.retain_mut(|result| match result {
             JobResult::ObjDiff(result) => {
                 self.build = take(result);
+                new_build = true;
                 false
             }
             JobResult::CreateScratch(result) => {
@@ -82,8 +99,21 @@ impl DiffViewState {
                     self.symbol_state.reverse_fn_order = value;
                     self.symbol_state.disable_reverse_fn_order = true;
                 }
+                let workflow =
+                    config.workflow.units.get(&obj_config.name).cloned().unwrap_or_default();
+                if new_build || workflow != self.symbol_state.workflow {
+                    if let Some((obj, diff)) =
+                        self.build.as_mut().and_then(|b| b.first_obj.as_mut())
+                    {
+                        apply_annotations(&workflow, obj, diff);
+                    }
+                }
+                self.symbol_state.workflow = workflow;
             }
+            self.symbol_state.workflow_available = config.workflow_path.is_some();
+            self.function_state.notes_available = config.workflow_path.is_some();
             self.scratch_available = CreateScratchConfig::is_available(&config);
+            self.function_state.number_format = config.diff_obj_config.number_format;
         }
     }
 
@@ -99,6 +129,24 @@ impl DiffViewState {
             }
         }
 
+        if let Some((target, base)) = self.symbol_state.queue_mapping.take() {
+            if let Ok(mut config) = config.write() {
+                config.add_symbol_mapping(target, base);
+            }
+        }
+
+        if let Some((symbol, status)) = self.symbol_state.queue_workflow.take() {
+            if let Ok(mut config) = config.write() {
+                config.set_workflow_status(&symbol, status);
+            }
+        }
+
+        if let Some((symbol, offset, text)) = self.symbol_state.queue_note.take() {
+            if let Ok(mut config) = config.write() {
+                config.set_annotation(&symbol, offset, &text);
+            }
+        }
+
         if self.queue_scratch {
             self.queue_scratch = false;
             if let Some(function_name) =
@@ -131,7 +179,12 @@ pub fn match_color_for_symbol(match_percent: f32, appearance: &Appearance) -> Co
     }
 }
 
-fn symbol_context_menu_ui(ui: &mut Ui, symbol: &ObjSymbol) {
+fn symbol_context_menu_ui(
+    ui: &mut Ui,
+    symbol: &ObjSymbol,
+    state: &mut SymbolViewState,
+    left: bool,
+) {
     ui.scope(|ui| {
         ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
         ui.style_mut().wrap = Some(false);
@@ -152,10 +205,63 @@ fn symbol_context_menu_ui(ui: &mut Ui, symbol: &ObjSymbol) {
                 ui.close_menu();
             }
         }
+        if left {
+            if state.pending_mapping.as_deref() == Some(symbol.name.as_str()) {
+                if ui.button("Cancel mapping").clicked() {
+                    state.pending_mapping = None;
+                    ui.close_menu();
+                }
+            } else if ui.button("Map to base symbol…").clicked() {
+                state.pending_mapping = Some(symbol.name.clone());
+                ui.close_menu();
+            }
+        } else if let Some(target) = &state.pending_mapping {
+            if ui.button(format!("Map \"{target}\" to this symbol")).clicked() {
+                state.queue_mapping = Some((target.clone(), symbol.name.clone()));
+                state.pending_mapping = None;
+                ui.close_menu();
+            }
+        }
+        if state.workflow_available {
+            let current = workflow_status(state, symbol);
+            ui.menu_button("Mark as…", |ui| {
+                for &status in WorkflowStatus::VARIANTS {
+                    if ui
+                        .add_enabled(
+                            status != current,
+                            egui::Button::new(status.get_message().unwrap()),
+                        )
+                        .clicked()
+                    {
+                        state.queue_workflow = Some((symbol.name.clone(), status));
+                        ui.close_menu();
+                    }
+                }
+            });
+        }
     });
 }
 
-fn symbol_hover_ui(ui: &mut Ui, symbol: &ObjSymbol, appearance: &Appearance) {
+fn workflow_status(state: &SymbolViewState, symbol: &ObjSymbol) -> WorkflowStatus {
+    state.workflow.get(&symbol.name).map(|w| w.status).unwrap_or_default()
+}
+
+fn workflow_color(status: WorkflowStatus, appearance: &Appearance) -> Color32 {
+    match status {
+        WorkflowStatus::NotStarted => appearance.deemphasized_text_color,
+        WorkflowStatus::InProgress => appearance.replace_color,
+        WorkflowStatus::NeedsReview => appearance.highlight_color,
+        WorkflowStatus::Matched => appearance.insert_color,
+    }
+}
+
+fn symbol_hover_ui(
+    ui: &mut Ui,
+    symbol: &ObjSymbol,
+    symbol_diff: &ObjSymbolDiff,
+    workflow: Option<&SymbolWorkflow>,
+    appearance: &Appearance,
+) {
     ui.scope(|ui| {
         ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
         ui.style_mut().wrap = Some(false);
@@ -173,6 +279,21 @@ fn symbol_hover_ui(ui: &mut Ui, symbol: &ObjSymbol, appearance: &Appearance) {
         if let Some(address) = symbol.virtual_address {
             ui.colored_label(appearance.replace_color, format!("Virtual address: {:#x}", address));
         }
+        if symbol_diff.weak_mismatch {
+            ui.colored_label(appearance.delete_color, "Binding: weak on only one side");
+        }
+        if let Some(workflow) = workflow {
+            ui.colored_label(
+                workflow_color(workflow.status, appearance),
+                format!("Status: {}", workflow.status.get_message().unwrap()),
+            );
+            if let Some(assignee) = &workflow.assignee {
+                ui.colored_label(appearance.highlight_color, format!("Assignee: {assignee}"));
+            }
+            if let Some(note) = &workflow.note {
+                ui.colored_label(appearance.highlight_color, format!("Note: {note}"));
+            }
+        }
     });
 }
 
@@ -213,7 +334,21 @@ fn symbol_ui(
     if symbol.flags.0.contains(ObjSymbolFlags::Hidden) {
         write_text("h", appearance.deemphasized_text_color, &mut job, appearance.code_font.clone());
     }
+    if symbol_diff.weak_mismatch {
+        // Weak on only one side
+        write_text("!", appearance.delete_color, &mut job, appearance.code_font.clone());
+    }
     write_text("] ", appearance.text_color, &mut job, appearance.code_font.clone());
+    let workflow = state.workflow.get(&symbol.name);
+    if let Some(status) = workflow.map(|w| w.status).filter(|&s| s != WorkflowStatus::NotStarted) {
+        let tag = match status {
+            WorkflowStatus::InProgress => "WIP",
+            WorkflowStatus::NeedsReview => "REVIEW",
+            _ => "DONE",
+        };
+        write_text(tag, workflow_color(status, appearance), &mut job, appearance.code_font.clone());
+        write_text(" ", appearance.text_color, &mut job, appearance.code_font.clone());
+    }
     if let Some(match_percent) = symbol_diff.match_percent {
         write_text("(", appearance.text_color, &mut job, appearance.code_font.clone());
         write_text(
@@ -225,10 +360,10 @@ fn symbol_ui(
         write_text(") ", appearance.text_color, &mut job, appearance.code_font.clone());
     }
     write_text(name, appearance.highlight_color, &mut job, appearance.code_font.clone());
-    let response = SelectableLabel::new(selected, job)
-        .ui(ui)
-        .on_hover_ui_at_pointer(|ui| symbol_hover_ui(ui, symbol, appearance));
-    response.context_menu(|ui| symbol_context_menu_ui(ui, symbol));
+    let response = SelectableLabel::new(selected, job).ui(ui).on_hover_ui_at_pointer(|ui| {
+        symbol_hover_ui(ui, symbol, symbol_diff, workflow, appearance)
+    });
+    response.context_menu(|ui| symbol_context_menu_ui(ui, symbol, state, left));
     if response.clicked() {
         if let Some(section) = section {
             if section.kind == ObjSectionKind::Code {
@@ -261,22 +396,12 @@ fn symbol_ui(
     ret
 }
 
-fn symbol_matches_search(symbol: &ObjSymbol, search_str: &str) -> bool {
-    search_str.is_empty()
-        || symbol.name.contains(search_str)
-        || symbol
-            .demangled_name
-            .as_ref()
-            .map(|s| s.to_ascii_lowercase().contains(search_str))
-            .unwrap_or(false)
-}
-
 #[must_use]
 fn symbol_list_ui(
     ui: &mut Ui,
     obj: &(ObjInfo, ObjDiff),
     state: &mut SymbolViewState,
-    lower_search: &str,
+    filter: &SymbolFilter,
     appearance: &Appearance,
     left: bool,
 ) -> Option<View> {
@@ -286,22 +411,6 @@ fn symbol_list_ui(
             ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
             ui.style_mut().wrap = Some(false);
 
-            if !obj.0.common.is_empty() {
-                CollapsingHeader::new(".comm").default_open(true).show(ui, |ui| {
-                    for (symbol, symbol_diff) in obj.0.common.iter().zip(&obj.1.common) {
-                        ret = ret.or(symbol_ui(
-                            ui,
-                            symbol,
-                            symbol_diff,
-                            None,
-                            state,
-                            appearance,
-                            left,
-                        ));
-                    }
-                });
-            }
-
             for (section, section_diff) in obj.0.sections.iter().zip(&obj.1.sections) {
                 let mut header = LayoutJob::simple_singleline(
                     format!("{} ({:x})", section.name, section.size),
@@ -336,7 +445,11 @@ fn symbol_list_ui(
                             for (symbol, symbol_diff) in
                                 section.symbols.iter().zip(&section_diff.symbols).rev()
                             {
-                                if !symbol_matches_search(symbol, lower_search) {
+                                if !filter.matches(Some(section), symbol, symbol_diff)
+                                    || state
+                                        .workflow_filter
+                                        .is_some_and(|s| s != workflow_status(state, symbol))
+                                {
                                     continue;
                                 }
                                 ret = ret.or(symbol_ui(
@@ -353,7 +466,11 @@ fn symbol_list_ui(
                             for (symbol, symbol_diff) in
                                 section.symbols.iter().zip(&section_diff.symbols)
                             {
-                                if !symbol_matches_search(symbol, lower_search) {
+                                if !filter.matches(Some(section), symbol, symbol_diff)
+                                    || state
+                                        .workflow_filter
+                                        .is_some_and(|s| s != workflow_status(state, symbol))
+                                {
                                     continue;
                                 }
                                 ret = ret.or(symbol_ui(
@@ -391,6 +508,17 @@ fn build_log_ui(ui: &mut Ui, status: &BuildStatus, appearance: &Appearance) {
             ui.style_mut().wrap = Some(false);
 
             ui.label(&status.cmdline);
+            for error in &status.errors {
+                let color = match error.severity {
+                    BuildErrorSeverity::Error => appearance.delete_color,
+                    BuildErrorSeverity::Warning => appearance.replace_color,
+                    BuildErrorSeverity::Note => appearance.text_color,
+                };
+                ui.colored_label(color, error.to_string());
+            }
+            if !status.errors.is_empty() {
+                ui.separator();
+            }
             ui.colored_label(appearance.replace_color, &status.stdout);
             ui.colored_label(appearance.delete_color, &status.stderr);
         });
@@ -442,7 +570,33 @@ pub fn symbol_diff_ui(ui: &mut Ui, state: &mut DiffViewState, appearance: &Appea
                         }
                     });
 
-                    TextEdit::singleline(search).hint_text("Filter symbols").ui(ui);
+                    ui.horizontal(|ui| {
+                        TextEdit::singleline(search)
+                            .hint_text("Filter symbols (text, glob or /regex/)")
+                            .ui(ui);
+                        if symbol_state.workflow_available {
+                            egui::ComboBox::from_id_source("workflow_filter")
+                                .selected_text(
+                                    symbol_state
+                                        .workflow_filter
+                                        .map_or("All statuses", |s| s.get_message().unwrap()),
+                                )
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(
+                                        &mut symbol_state.workflow_filter,
+                                        None,
+                                        "All statuses",
+                                    );
+                                    for &status in WorkflowStatus::VARIANTS {
+                                        ui.selectable_value(
+                                            &mut symbol_state.workflow_filter,
+                                            Some(status),
+                                            status.get_message().unwrap(),
+                                        );
+                                    }
+                                });
+                        }
+                    });
                 },
             );
 
@@ -480,7 +634,7 @@ pub fn symbol_diff_ui(ui: &mut Ui, state: &mut DiffViewState, appearance: &Appea
 
     // Table
     let mut ret = None;
-    let lower_search = search.to_ascii_lowercase();
+    let filter = SymbolFilter::search(search);
     StripBuilder::new(ui).size(Size::remainder()).vertical(|mut strip| {
         strip.strip(|builder| {
             builder.sizes(Size::remainder(), 2).horizontal(|mut strip| {
@@ -492,7 +646,7 @@ pub fn symbol_diff_ui(ui: &mut Ui, state: &mut DiffViewState, appearance: &Appea
                                     ui,
                                     obj,
                                     symbol_state,
-                                    &lower_search,
+                                    &filter,
                                     appearance,
                                     true,
                                 ));
@@ -512,7 +666,7 @@ pub fn symbol_diff_ui(ui: &mut Ui, state: &mut DiffViewState, appearance: &Appea
                                     ui,
                                     obj,
                                     symbol_state,
-                                    &lower_search,
+                                    &filter,
                                     appearance,
                                     false,
                                 ));